@@ -1,8 +1,12 @@
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use worker::game::{GameEvent, NodeCoord};
-use worker::raft::storage::GameEventRequest;
+use worker::client_api::CommandHistory;
+use worker::game::finalkill::ReceiveBudget;
+use worker::game::{CommandEvent, GameEvent, NodeCoord, TelemetryEvent};
+use worker::app::App;
+use worker::raft::audit::CommandOrigin;
+use worker::supervisor::Supervisor;
 use worker::{bootstrap_cluster, generate_node_id, join_cluster, NodeRegistry, RaftNode};
 
 /// Local player context - tracks which player this client represents
@@ -12,6 +16,8 @@ pub struct PlayerContext {
     pub player_name: String,
     pub capital_coord: NodeCoord,
     pub game_id: String,
+    /// Start of the account token joined with, for the audit trail
+    pub token_id: Option<String>,
 }
 
 /// Client state shared across HTTP handlers
@@ -20,6 +26,10 @@ pub struct ClientState {
     pub raft_node: Arc<RwLock<Option<Arc<RaftNode>>>>,
     pub player_context: Arc<RwLock<Option<PlayerContext>>>,
     pub master_url: Arc<String>,
+    /// Orders sent from /my/*, for GET /my/history and cancelling
+    pub history: Arc<std::sync::Mutex<CommandHistory>>,
+    /// What /finalkill lets in (see worker::game::finalkill)
+    pub finalkill_budget: ReceiveBudget,
 }
 
 #[tokio::main]
@@ -35,11 +45,13 @@ async fn main() -> Result<()> {
         raft_node: Arc::new(RwLock::new(None)),
         player_context: Arc::new(RwLock::new(None)),
         master_url: Arc::new(master_url),
+        history: Arc::new(std::sync::Mutex::new(CommandHistory::new())),
+        finalkill_budget: ReceiveBudget::from_env(),
     };
 
     // Start HTTP API server
     println!("\nStarting HTTP API server...");
-    let api_addr = "0.0.0.0:8080";
+    let api_addr = format!("{}:8080", worker::metadata::bind_ip());
 
     println!("\n=== Client Ready ===");
     println!("  HTTP API Port: 8080");
@@ -47,10 +59,15 @@ async fn main() -> Result<()> {
     println!("  Call POST /join to join a game");
     println!("===================\n");
 
-    // Start server and block
-    start_api_server(client_state, api_addr.to_string()).await?;
-
-    Ok(())
+    // Serve until SIGTERM or Ctrl-C, then stop every supervised task before Raft
+    let raft_node = client_state.raft_node.clone();
+    let result = App::new().run(start_api_server(client_state, api_addr)).await;
+    if let Some(raft_node) = raft_node.read().await.as_ref() {
+        if let Err(e) = raft_node.raft.shutdown().await {
+            eprintln!("[Raft] Shutdown failed: {}", e);
+        }
+    }
+    result
 }
 
 /// Generate a unique player ID
@@ -71,6 +88,32 @@ fn current_timestamp() -> u64 {
         .as_micros() as u64
 }
 
+/// Submit `event` for `player_id`, recorded in the command history
+/// The submission runs as its own task, so POST /my/history/{id}/cancel can abort it
+/// while it's still waiting on the leader.
+async fn submit_tracked(
+    state: &ClientState,
+    raft_node: &Arc<RaftNode>,
+    player_id: u64,
+    event: GameEvent,
+    origin: CommandOrigin,
+) -> Result<u64, String> {
+    let id = state.history.lock().unwrap().start(player_id, &event, current_timestamp() / 1_000_000);
+    let raft_node = raft_node.clone();
+    let submission = Supervisor::global()
+        .spawn_scoped("command_submission", async move { raft_node.commands.submit_from(event, Some(origin)).await });
+    state.history.lock().unwrap().track(id, submission.abort_handle());
+
+    let result = match submission.await {
+        Ok(Some(result)) => result.map_err(|e| e.to_string()),
+        Ok(None) => Err("Cancelled".to_string()),
+        Err(e) if e.is_cancelled() => Err("Cancelled".to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    state.history.lock().unwrap().finish(id, result.clone());
+    result
+}
+
 /// Wait for Raft leader election to complete
 async fn wait_for_leader(raft_node: &Arc<RaftNode>, timeout: std::time::Duration) -> Result<u64> {
     use std::time::Instant;
@@ -111,13 +154,13 @@ async fn find_random_unoccupied_coord(raft_node: &Arc<RaftNode>) -> Result<NodeC
     use rand::Rng;
     let mut rng = rand::thread_rng();
 
-    // Try up to 100 times to find an unoccupied spot
+    // Try up to 100 times to find an unoccupied spot off any chasm
     for _ in 0..100 {
         let q = rng.gen_range(-10..=10);
         let r = rng.gen_range(-10..=10);
         let coord = NodeCoord::new(q, r);
 
-        if !sm.game_state.nodes.contains_key(&coord) {
+        if !sm.game_state.nodes.contains_key(&coord) && sm.game_state.terrain_at(coord).is_passable() {
             return Ok(coord);
         }
     }
@@ -129,45 +172,101 @@ async fn find_random_unoccupied_coord(raft_node: &Arc<RaftNode>) -> Result<NodeC
     Ok(NodeCoord::new(q, r))
 }
 
+/// Committed state with the mirrored metrics view in place (metrics aren't in the log)
+async fn game_state_with_metrics(raft_node: &RaftNode) -> worker::GameState {
+    let storage = raft_node.storage.read().await;
+    let sm_arc = storage.state_machine();
+    drop(storage);
+    let mut game_state = sm_arc.read().await.game_state.clone();
+    raft_node.metrics_view.overlay(&mut game_state).await;
+    game_state
+}
+
+/// Mirror the leader's metrics view for the displays (the client has nothing to report)
+async fn spawn_telemetry(raft_node: Arc<RaftNode>) {
+    let cadence = worker::raft::metrics_view::StreamCadence::new(raft_node.storage.read().await.state_machine());
+    worker::supervisor::Supervisor::global().spawn("telemetry", worker::supervisor::RestartPolicy::always(), move || {
+        worker::raft::metrics_view::stream_to_leader(
+            raft_node.raft.clone(),
+            raft_node.registry.clone(),
+            raft_node.metrics_view.clone(),
+            None,
+            cadence.clone(),
+        )
+    });
+}
+
+/// How often the client tells the game it's still here (the leader stands a
+/// player's nodes down after CLIENT_IDLE_SECS of silence), in game time
+const HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Commit a ClientHeartbeat every HEARTBEAT_INTERVAL until the player is out
+/// A sped-up game (committed TimeScaleSet) hears from the client that much sooner.
+fn spawn_heartbeat(raft_node: Arc<RaftNode>, player_id: u64) {
+    Supervisor::global().spawn_transient("heartbeat", async move {
+        loop {
+            let (playing, time_scale) = {
+                let storage = raft_node.storage.read().await;
+                let sm_arc = storage.state_machine();
+                drop(storage);
+                let sm = sm_arc.read().await;
+                let playing = !sm.game_state.game_over && sm.game_state.players.get(&player_id).is_none_or(|p| p.alive);
+                (playing, sm.game_state.time_scale.max(1))
+            };
+            if !playing {
+                println!("[Heartbeat] Player {} is out, stopping", player_id);
+                break;
+            }
+
+            let event = GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat {
+                player_id,
+                timestamp: current_timestamp() / 1_000_000,
+            });
+            if let Err(e) = raft_node.commands.submit(event).await {
+                eprintln!("[Heartbeat] Failed to submit heartbeat: {}", e);
+            }
+            tokio::time::sleep(HEARTBEAT_INTERVAL / time_scale).await;
+        }
+        Ok(())
+    });
+}
+
 /// Start the HTTP API server for player actions
 async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
     use axum::{
         extract::{
             ws::{Message, WebSocket},
-            State, WebSocketUpgrade,
+            ConnectInfo, Path, Query, RawQuery, State, WebSocketUpgrade,
         },
-        http::StatusCode,
+        http::{HeaderMap, StatusCode},
         response::Response,
         routing::{get, post},
         Json, Router,
     };
-    use serde::{Deserialize, Serialize};
-    use tower_http::cors::CorsLayer;
+    use serde::Deserialize;
+    use worker::cors::CorsPolicy;
+    use worker::game::finalkill::{expected_final_kill, resolve_sources, ReceivePermit, ReceiveRefusal, RECEIVE_MAX_FRAME_BYTES};
+    use worker::client_api::{
+        announcement_views, node_views, plan_bulk_attack, player_views, AttackWarning, BulkAttackRequest, BulkAttackResponse, CancelError,
+        ClientGameState, DefendRequest, JoinRequest, JoinStatus, OwnedNodeInfo, PathResponse, PlayerStatusResponse,
+        CommandRecord, SetAttackRequest, StateUpdate, CLIENT_GAME_STATE_FIELDS, MAX_BULK_ORDERS,
+    };
+    use worker::http_cache::{self, FieldsQuery};
+    use worker::request_id::{self, RequestIdExt};
+    use worker::client_ws::{WsSession, MIN_PUSH_INTERVAL_MS};
     use tower_http::services::ServeDir;
-
-    #[derive(Serialize)]
-    struct PlayerStatusResponse {
-        player_id: u64,
-        player_name: String,
-        capital_coord: NodeCoord,
-        alive: bool,
-        owned_nodes: usize,
-        is_leader: bool,
-    }
-
-    #[derive(Serialize)]
-    struct NodeInfo {
-        coord: NodeCoord,
-        node_type: String,
-        current_target: Option<String>,
-    }
+    use std::net::SocketAddr;
+    use worker::game::Awards;
+    use worker::raft::api::{EventsQuery, EventsResponse};
+    use worker::raft::audit;
+    use worker::reconnect::{new_token, reconnect_key, Session};
 
     #[derive(Deserialize)]
-    struct AttackRequest {
-        target_q: i32,
-        target_r: i32,
-        node_q: Option<i32>,
-        node_r: Option<i32>,
+    struct PathQuery {
+        /// "q,r" - defaults to the player's capital
+        from: Option<NodeCoord>,
+        /// "q,r"
+        to: NodeCoord,
     }
 
     // GET /my/status - Get local player status
@@ -198,15 +297,17 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
             player_name: ctx.player_name.clone(),
             capital_coord: ctx.capital_coord,
             alive: is_alive,
+            contested_until: sm.game_state.capital_contest(ctx.player_id).map(|(_, c)| c.expires_at),
             owned_nodes: owned_nodes.len(),
             is_leader,
+            phase: sm.game_state.phase,
         }))
     }
 
     // GET /my/nodes - Get all nodes owned by local player
     async fn get_player_nodes(
         State(state): State<ClientState>,
-    ) -> Result<Json<Vec<NodeInfo>>, String> {
+    ) -> Result<Json<Vec<OwnedNodeInfo>>, String> {
         // Check if joined
         let raft_node = state.raft_node.read().await;
         let raft_node = raft_node.as_ref()
@@ -216,28 +317,78 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         let ctx = player_ctx.as_ref()
             .ok_or("Player context not initialized".to_string())?;
 
-        let storage = raft_node.storage.read().await;
-        let sm_arc = storage.state_machine();
-        drop(storage);
-        let sm = sm_arc.read().await;
+        let game_state = game_state_with_metrics(raft_node).await;
 
-        let nodes = sm.game_state.get_player_nodes(ctx.player_id);
-        let node_infos: Vec<NodeInfo> = nodes
+        let nodes = game_state.get_player_nodes(ctx.player_id);
+        let node_infos: Vec<OwnedNodeInfo> = nodes
             .iter()
-            .map(|node| NodeInfo {
+            .map(|node| OwnedNodeInfo {
                 coord: node.coord,
                 node_type: format!("{:?}", node.node_type),
                 current_target: node.current_target.as_ref().map(|t| format!("{:?}", t)),
+                attack_status: match node.current_target {
+                    Some(worker::game::AttackTarget::Coordinate(target)) => {
+                        let queued = game_state.node_metrics.get(&target)
+                            .map(|m| m.queued_attackers.contains(&node.coord))
+                            .unwrap_or(false);
+                        Some(if queued { "queued" } else { "active" }.to_string())
+                    }
+                    Some(_) => Some("active".to_string()),
+                    None => None,
+                },
+                defense: game_state.defenses.get(&node.coord).copied(),
             })
             .collect();
 
         Ok(Json(node_infos))
     }
 
+    // GET /my/path?from=q,r&to=q,r - Cheapest attack corridor for the local player
+    async fn get_player_path(
+        State(state): State<ClientState>,
+        Query(query): Query<PathQuery>,
+    ) -> Result<Json<PathResponse>, String> {
+        // Check if joined
+        let raft_node = state.raft_node.read().await;
+        let raft_node = raft_node.as_ref()
+            .ok_or("Not joined to any game. Call POST /join first".to_string())?;
+
+        let player_ctx = state.player_context.read().await;
+        let ctx = player_ctx.as_ref()
+            .ok_or("Player context not initialized".to_string())?;
+
+        let from = query.from.unwrap_or(ctx.capital_coord);
+        let to = query.to;
+
+        // Loss discounts come from the metrics view
+        let game_state = game_state_with_metrics(raft_node).await;
+
+        let plan = worker::game::pathfinding::find_path(
+            &game_state,
+            ctx.player_id,
+            from,
+            to,
+            &worker::game::PathCosts::default(),
+        )
+        .ok_or("No path found within search limit".to_string())?;
+
+        Ok(Json(PathResponse {
+            next_target: plan.next_target(ctx.player_id),
+            plan,
+        }))
+    }
+
     // GET /game/state - Get full game state (for frontend visualization)
+    // ETag'd on the log index and metrics version, trimmed with ?fields=
     async fn get_game_state(
         State(state): State<ClientState>,
-    ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+        headers: HeaderMap,
+        Query(query): Query<FieldsQuery>,
+    ) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+        let fields = query
+            .parse(&CLIENT_GAME_STATE_FIELDS)
+            .map_err(|e| (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))))?;
+
         // Check if joined
         let raft_node = state.raft_node.read().await;
         let raft_node = raft_node.as_ref()
@@ -250,66 +401,70 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         let sm_arc = storage.state_machine();
         drop(storage);
         let sm = sm_arc.read().await;
+        let mut game_state = sm.game_state.clone();
+        raft_node.metrics_view.overlay(&mut game_state).await;
+        let metrics_version = if fields.has("nodes") { raft_node.metrics_view.version() } else { 0 };
+        let etag = http_cache::etag(&[game_state.last_applied_log_index, metrics_version]);
+
+        // Full game state for the frontend
+        Ok(http_cache::respond(&headers, etag, &fields, || ClientGameState {
+            players: player_views(&game_state),
+            nodes: node_views(&game_state),
+            total_events: sm.events.len(),
+            phase: game_state.phase,
+            features: game_state.features(),
+        }))
+    }
 
-        // Serialize full game state for frontend
-        let game_state_json = serde_json::json!({
-            "players": sm.game_state.players.iter().map(|(id, p)| {
-                // Count nodes owned by this player
-                let node_count = sm.game_state.nodes.values()
-                    .filter(|n| n.owner_id == *id)
-                    .count();
-
-                serde_json::json!({
-                    "player_id": id,  // Fixed: was "id", now "player_id"
-                    "name": &p.name,
-                    "capital_coord": {
-                        "q": p.capital_coord.q,
-                        "r": p.capital_coord.r
-                    },
-                    "alive": p.alive,
-                    "join_time": p.join_time,
-                    "node_count": node_count  // Added: node count for UI
-                })
-            }).collect::<Vec<_>>(),
-            "nodes": sm.game_state.nodes.iter().map(|(coord, node)| {
-                // Properly serialize current_target as JSON object
-                let current_target_json = node.current_target.as_ref().map(|t| match t {
-                    worker::game::AttackTarget::Coordinate(target_coord) => {
-                        serde_json::json!({
-                            "q": target_coord.q,
-                            "r": target_coord.r
-                        })
-                    },
-                    worker::game::AttackTarget::Player(player_id) => {
-                        serde_json::json!({
-                            "player_id": player_id
-                        })
-                    },
-                });
-
-                // Get metrics for this node if available
-                let metrics = sm.game_state.node_metrics.get(coord);
-
-                serde_json::json!({
-                    "coord": {
-                        "q": coord.q,
-                        "r": coord.r
-                    },
-                    "owner_id": node.owner_id,
-                    "current_target": current_target_json,
-                    "bandwidth_in": metrics.map(|m| m.bandwidth_in),
-                    "packet_loss": metrics.map(|m| m.packet_loss),
-                })
-            }).collect::<Vec<_>>(),
-            "total_events": sm.events.len()
-        });
+    // GET /events - A page of the committed log, same query as a worker's /events
+    async fn get_events(
+        State(state): State<ClientState>,
+        Query(query): Query<EventsQuery>,
+    ) -> Result<Json<EventsResponse>, (StatusCode, Json<serde_json::Value>)> {
+        // Check if joined
+        let raft_node = state.raft_node.read().await;
+        let raft_node = raft_node.as_ref()
+            .ok_or_else(|| (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Not joined to any game. Call POST /join first"}))
+            ))?;
+
+        let storage = raft_node.storage.read().await;
+        let sm_arc = storage.state_machine();
+        drop(storage);
+        let sm = sm_arc.read().await;
+
+        Ok(Json(query.page(&sm.events, &sm.event_times)))
+    }
 
-        Ok(Json(game_state_json))
+    // GET /awards - The game's awards, same as a worker's /awards
+    async fn get_awards(
+        State(state): State<ClientState>,
+    ) -> Result<Json<Awards>, (StatusCode, Json<serde_json::Value>)> {
+        // Check if joined
+        let raft_node = state.raft_node.read().await;
+        let raft_node = raft_node.as_ref()
+            .ok_or_else(|| (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Not joined to any game. Call POST /join first"}))
+            ))?;
+
+        let sm_arc = raft_node.storage.read().await.state_machine();
+        Ok(Json(worker::raft::api::game_awards(&sm_arc, &raft_node.metrics_view).await))
+    }
+
+    /// Who a command came from: the API caller, acting for the joined player
+    fn command_origin(peer: SocketAddr, ctx: Option<&PlayerContext>) -> CommandOrigin {
+        CommandOrigin {
+            token_id: ctx.and_then(|c| c.token_id.clone()),
+            ..CommandOrigin::new(peer.ip(), ctx.map(|c| c.player_id))
+        }
     }
 
     // POST /events - Submit a custom game event (for advanced frontend features)
     async fn submit_event(
         State(state): State<ClientState>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
         Json(event_json): Json<serde_json::Value>,
     ) -> Result<Json<String>, String> {
         // Check if joined
@@ -321,8 +476,8 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         let event: GameEvent = serde_json::from_value(event_json)
             .map_err(|e| format!("Failed to parse event: {}", e))?;
 
-        let request = GameEventRequest { event };
-        raft_node.raft.client_write(request).await
+        let origin = command_origin(peer, state.player_context.read().await.as_ref());
+        raft_node.commands.submit_from(event, Some(origin)).await
             .map_err(|e| format!("Failed to submit event: {}", e))?;
 
         Ok(Json("Event submitted successfully".to_string()))
@@ -331,7 +486,8 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
     // POST /my/attack - Set attack target for a node
     async fn set_attack_target(
         State(state): State<ClientState>,
-        Json(req): Json<AttackRequest>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        Json(req): Json<SetAttackRequest>,
     ) -> Result<Json<String>, String> {
         // Check if joined
         let raft_node = state.raft_node.read().await;
@@ -342,14 +498,10 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         let ctx = player_ctx.as_ref()
             .ok_or("Player context not initialized".to_string())?;
 
-        let target_coord = NodeCoord::new(req.target_q, req.target_r);
+        let target_coord = req.target;
 
         // If no node specified, use the capital
-        let node_coord = if let (Some(q), Some(r)) = (req.node_q, req.node_r) {
-            NodeCoord::new(q, r)
-        } else {
-            ctx.capital_coord
-        };
+        let node_coord = req.node.unwrap_or(ctx.capital_coord);
 
         // Verify player owns the node
         let storage = raft_node.storage.read().await;
@@ -357,36 +509,134 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         drop(storage);
         let sm = sm_arc.read().await;
 
-        if let Some(node) = sm.game_state.nodes.get(&node_coord) {
-            if node.owner_id != ctx.player_id {
-                return Err("You don't own this node".to_string());
-            }
-        } else {
-            return Err("Node not found".to_string());
-        }
-
-        // Check adjacency - target must be adjacent to attacking node
-        if !node_coord.is_adjacent(&target_coord) {
-            drop(sm);
-            return Err("Target must be adjacent to your node".to_string());
-        }
-
+        // Same check the frontend runs locally (camhack-rules)
+        let now = current_timestamp() / 1_000_000;
+        let valid = sm.game_state.validate_attack(Some(ctx.player_id), node_coord, target_coord, now);
         drop(sm);
+        valid.map_err(|e| e.to_string())?;
 
         // Submit SetNodeTarget event
-        let event = GameEvent::SetNodeTarget {
+        let event = GameEvent::Command(CommandEvent::SetNodeTarget {
             node_coord,
             target: Some(worker::game::AttackTarget::Coordinate(target_coord)),
             timestamp: current_timestamp(),
-        };
+        });
 
-        let request = GameEventRequest { event };
-        match raft_node.raft.client_write(request).await {
+        match submit_tracked(&state, raft_node, ctx.player_id, event, command_origin(peer, Some(ctx))).await {
             Ok(_) => Ok(Json("Attack target set successfully".to_string())),
             Err(e) => Err(format!("Failed to set attack target: {}", e)),
         }
     }
 
+    // POST /my/attack/bulk - Set attack targets for several nodes in one log entry
+    // Invalid orders are reported back, the valid ones still go through together
+    async fn set_attack_targets(
+        State(state): State<ClientState>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        Json(req): Json<BulkAttackRequest>,
+    ) -> Result<Json<BulkAttackResponse>, String> {
+        if req.orders.is_empty() {
+            return Err("No orders given".to_string());
+        }
+        if req.orders.len() > MAX_BULK_ORDERS {
+            return Err(format!("At most {} orders per request", MAX_BULK_ORDERS));
+        }
+
+        let raft_node = state.raft_node.read().await;
+        let raft_node = raft_node.as_ref()
+            .ok_or("Not joined to any game. Call POST /join first".to_string())?;
+
+        let player_ctx = state.player_context.read().await;
+        let ctx = player_ctx.as_ref()
+            .ok_or("Player context not initialized".to_string())?;
+
+        let storage = raft_node.storage.read().await;
+        let sm_arc = storage.state_machine();
+        drop(storage);
+        let sm = sm_arc.read().await;
+
+        let now = current_timestamp() / 1_000_000;
+        let (orders, rejected) = plan_bulk_attack(&sm.game_state, ctx.player_id, ctx.capital_coord, &req.orders, now);
+        drop(sm);
+
+        if orders.is_empty() {
+            return Ok(Json(BulkAttackResponse { accepted: 0, rejected, log_index: None }));
+        }
+
+        let accepted = orders.len();
+        let event = GameEvent::Command(CommandEvent::SetNodeTargets {
+            orders,
+            timestamp: current_timestamp(),
+        });
+
+        match submit_tracked(&state, raft_node, ctx.player_id, event, command_origin(peer, Some(ctx))).await {
+            Ok(log_index) => Ok(Json(BulkAttackResponse { accepted, rejected, log_index: Some(log_index) })),
+            Err(e) => Err(format!("Failed to set attack targets: {}", e)),
+        }
+    }
+
+    // POST /my/defend - Garrison a node's responder (optionally helping a neighbour's), or stand it down
+    async fn set_node_defense(
+        State(state): State<ClientState>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        Json(req): Json<DefendRequest>,
+    ) -> Result<Json<String>, String> {
+        let raft_node = state.raft_node.read().await;
+        let raft_node = raft_node.as_ref()
+            .ok_or("Not joined to any game. Call POST /join first".to_string())?;
+
+        let player_ctx = state.player_context.read().await;
+        let ctx = player_ctx.as_ref()
+            .ok_or("Player context not initialized".to_string())?;
+
+        let node_coord = req.node.unwrap_or(ctx.capital_coord);
+        let assist = if req.stand_down { None } else { req.assist };
+
+        let storage = raft_node.storage.read().await;
+        let sm_arc = storage.state_machine();
+        drop(storage);
+        let sm = sm_arc.read().await;
+        let valid = sm.game_state.validate_defense(Some(ctx.player_id), node_coord, assist);
+        drop(sm);
+        valid.map_err(|e| e.to_string())?;
+
+        let event = GameEvent::Command(CommandEvent::SetNodeDefense {
+            node_coord,
+            defense: (!req.stand_down).then_some(worker::game::DefenseOrder { assist }),
+            timestamp: current_timestamp(),
+        });
+
+        match submit_tracked(&state, raft_node, ctx.player_id, event, command_origin(peer, Some(ctx))).await {
+            Ok(_) if req.stand_down => Ok(Json("Defense stood down".to_string())),
+            Ok(_) => Ok(Json("Defense set successfully".to_string())),
+            Err(e) => Err(format!("Failed to set defense: {}", e)),
+        }
+    }
+
+    // GET /my/history - The player's recent orders and whether they committed, newest first
+    async fn get_command_history(State(state): State<ClientState>) -> Result<Json<Vec<CommandRecord>>, String> {
+        let player_ctx = state.player_context.read().await;
+        let ctx = player_ctx.as_ref()
+            .ok_or("Not joined to any game. Call POST /join first".to_string())?;
+        Ok(Json(state.history.lock().unwrap().records(ctx.player_id)))
+    }
+
+    // POST /my/history/{id}/cancel - Give up on an order still waiting to commit (best-effort)
+    async fn cancel_command(
+        State(state): State<ClientState>,
+        Path(id): Path<u64>,
+    ) -> Result<Json<String>, (StatusCode, String)> {
+        let player_ctx = state.player_context.read().await;
+        let ctx = player_ctx.as_ref()
+            .ok_or((StatusCode::BAD_REQUEST, "Not joined to any game. Call POST /join first".to_string()))?;
+        let cancelled = state.history.lock().unwrap().cancel(ctx.player_id, id);
+        match cancelled {
+            Ok(()) => Ok(Json(format!("Command {} cancelled", id))),
+            Err(e @ CancelError::NotFound) => Err((StatusCode::NOT_FOUND, e.to_string())),
+            Err(e) => Err((StatusCode::CONFLICT, e.to_string())),
+        }
+    }
+
     // WebSocket handler for real-time updates
     async fn websocket_handler(
         State(state): State<ClientState>,
@@ -396,16 +646,6 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
     }
 
     async fn handle_websocket(mut socket: WebSocket, state: ClientState) {
-        #[derive(Serialize)]
-        struct StateUpdate {
-            log_index: u64,
-            event_count: usize,
-            player_count: usize,
-            node_count: usize,
-            alive_players: usize,
-            latest_event: Option<String>,
-        }
-
         // Check if joined
         {
             let raft_node = state.raft_node.read().await;
@@ -418,10 +658,12 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
             }
         }
 
-        let mut last_log_index = 0u64;
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(500));
+        // Checks for changes at the fastest cadence; the session decides when a push is due
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(MIN_PUSH_INTERVAL_MS));
+        let mut session = WsSession::new();
 
         loop {
+            let mut replies = Vec::new();
             tokio::select! {
                 _ = interval.tick() => {
                     // Check if still joined
@@ -430,62 +672,83 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
                         break;
                     };
 
+                    // Warnings only go to the player owning the attacked nodes
+                    let player_id = state.player_context.read().await.as_ref().map(|ctx| ctx.player_id);
+
                     // Get current state
                     let storage = raft_node.storage.read().await;
                     let sm_arc = storage.state_machine();
                     drop(storage);
                     let sm = sm_arc.read().await;
 
-                    // Check if state has changed
                     let current_log_index = sm.last_applied_log_index;
-                    if current_log_index != last_log_index {
-                        last_log_index = current_log_index;
-
-                        let latest_event = sm.events.last().map(|e| format!("{:?}", e));
-                        let update = StateUpdate {
-                            log_index: current_log_index,
-                            event_count: sm.events.len(),
-                            player_count: sm.game_state.players.len(),
-                            node_count: sm.game_state.nodes.len(),
-                            alive_players: sm.game_state.players.values().filter(|p| p.alive).count(),
-                            latest_event,
-                        };
-                        drop(sm);
-
-                        // Send update to client
-                        let json = match serde_json::to_string(&update) {
-                            Ok(j) => j,
-                            Err(_) => break,
-                        };
-
-                        match socket.send(Message::Text(json)).await {
-                            Ok(_) => {},
-                            Err(_) => break, // Client disconnected
-                        }
+                    let change_version = sm.change_version;
+                    let metrics_version = raft_node.metrics_view.version();
+                    if !session.due(change_version, metrics_version, std::time::Instant::now()) {
+                        continue;
                     }
+
+                    // Loss and queued attackers come from the metrics view
+                    let mut game_state = sm.game_state.clone();
+                    raft_node.metrics_view.overlay(&mut game_state).await;
+
+                    let latest_event = sm.events.last().map(|e| format!("{:?}", e));
+                    let now = current_timestamp() / 1_000_000;
+                    let attack_warnings = game_state
+                        .under_attack(now)
+                        .iter()
+                        .filter(|(_, status)| Some(status.owner_id) == player_id)
+                        .map(|(coord, status)| AttackWarning::new(*coord, status))
+                        .collect();
+                    let update = StateUpdate {
+                        log_index: current_log_index,
+                        event_count: sm.events.len(),
+                        player_count: game_state.players.len(),
+                        node_count: game_state.nodes.len(),
+                        alive_players: game_state.players.values().filter(|p| p.alive).count(),
+                        phase: game_state.phase,
+                        latest_event,
+                        attack_warnings,
+                        announcements: announcement_views(&game_state, now),
+                    };
+                    replies = session.push(&game_state, update, change_version, metrics_version, std::time::Instant::now());
+                    drop(sm);
                 }
 
                 msg = socket.recv() => {
                     match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            replies.extend(session.handle(&text).iter().filter_map(|m| serde_json::to_string(m).ok()));
+                        }
                         Some(Ok(Message::Close(_))) => break,
-                        Some(Ok(_)) => {}, // Ignore other messages
+                        Some(Ok(_)) => {}, // Ignore pings and binary frames
                         Some(Err(_)) => break,
                         None => break,
                     }
                 }
             }
+
+            for json in replies {
+                if socket.send(Message::Text(json)).await.is_err() {
+                    return; // Client disconnected
+                }
+            }
         }
     }
 
     // GET /discover - Discover available games from master
+    // Query string (e.g. ?joinable=true) is forwarded to the master's /games
     async fn discover_games(
         State(state): State<ClientState>,
+        RawQuery(query): RawQuery,
     ) -> Result<Json<serde_json::Value>, String> {
         let client = reqwest::Client::new();
         let master_url = state.master_url.as_str();
+        let query = query.map(|q| format!("?{}", q)).unwrap_or_default();
 
         let response = client
-            .get(format!("{}/games", master_url))
+            .get(format!("{}/games{}", master_url, query))
+            .request_id()
             .send()
             .await
             .map_err(|e| format!("Failed to contact master: {}", e))?
@@ -499,36 +762,46 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
     // GET /status - Get client join status
     async fn get_status(
         State(state): State<ClientState>,
-    ) -> Json<serde_json::Value> {
+    ) -> Json<JoinStatus> {
         let player_ctx = state.player_context.read().await;
         let raft_node = state.raft_node.read().await;
 
         if let (Some(ctx), Some(_)) = (player_ctx.as_ref(), raft_node.as_ref()) {
-            serde_json::json!({
-                "joined": true,
-                "player_id": ctx.player_id,
-                "player_name": ctx.player_name,
-                "game_id": ctx.game_id,
-                "capital_coord": { "q": ctx.capital_coord.q, "r": ctx.capital_coord.r }
+            Json(JoinStatus {
+                joined: true,
+                player_id: Some(ctx.player_id),
+                player_name: Some(ctx.player_name.clone()),
+                game_id: Some(ctx.game_id.clone()),
+                capital_coord: Some(ctx.capital_coord),
             })
         } else {
-            serde_json::json!({
-                "joined": false
+            Json(JoinStatus {
+                joined: false,
+                player_id: None,
+                player_name: None,
+                game_id: None,
+                capital_coord: None,
             })
-        }.into()
+        }
     }
 
     // POST /join - Join a game
-    #[derive(Deserialize)]
-    struct JoinRequest {
-        game_id: String,
-        player_name: String,
-    }
-
+    // A failure names the request id, which the master's and workers' logs carry too
     async fn join_game(
         State(state): State<ClientState>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
         Json(req): Json<JoinRequest>,
     ) -> Result<Json<String>, String> {
+        try_join_game(state, peer, req).await.map_err(|e| {
+            eprintln!("{}Join failed: {}", request_id::prefix(), e);
+            match request_id::current() {
+                Some(id) => format!("{} (request {})", e, id),
+                None => e,
+            }
+        })
+    }
+
+    async fn try_join_game(state: ClientState, peer: SocketAddr, req: JoinRequest) -> Result<Json<String>, String> {
         // Check if already joined
         {
             let raft_node = state.raft_node.read().await;
@@ -538,6 +811,21 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         }
 
         println!("\n=== Joining Game: {} ===", req.game_id);
+        if let Some(id) = request_id::current() {
+            println!("Request id: {}", id);
+        }
+
+        // Returning players with an account keep their player id (and rating)
+        let account = match &req.token {
+            Some(token) => {
+                let account = worker::registry::verify_player(&state.master_url, &req.player_name, token)
+                    .await
+                    .map_err(|e| format!("Failed to verify player: {}", e))?;
+                println!("Playing as account {} (rating {:.0})", account.player_id, account.rating);
+                Some(account)
+            }
+            None => None,
+        };
 
         // Get client ID
         let client_id = std::env::var("CLIENT_ID")
@@ -550,22 +838,23 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
             .map_err(|e| format!("Failed to get task ARN: {}", e))?;
 
         // Register with master and get peer
-        let peer = worker::registry::register_and_get_peer(
+        let registration = worker::registry::register_and_get_peer(
             client_id,
             task_arn,
             my_ip.clone(),
             req.game_id.clone(),
         ).await
             .map_err(|e| format!("Failed to register with master: {}", e))?;
+        let my_host = registration.advertised_host(&my_ip);
 
         // Initialize Raft node
         let node_id = generate_node_id();
-        let registry = NodeRegistry::new();
+        let registry = NodeRegistry::from_env().await;
 
-        let raft_node = if let Some(peer_info) = peer {
-            join_cluster(node_id, my_ip.clone(), peer_info, registry).await
+        let raft_node = if let Some(peer_info) = registration.peer {
+            join_cluster(node_id, my_host.clone(), peer_info, registry, None).await
         } else {
-            bootstrap_cluster(node_id, my_ip.clone(), registry).await
+            bootstrap_cluster(node_id, my_host.clone(), registry, None).await
         }.map_err(|e| format!("Failed to initialize Raft: {}", e))?;
 
         // Wait for leader election to complete before proceeding
@@ -573,24 +862,48 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         wait_for_leader(&raft_node, std::time::Duration::from_secs(10)).await
             .map_err(|e| format!("Leader election failed: {}", e))?;
 
+        // A client restarted mid-game takes its player back instead of joining again
+        let session_path = Session::default_path();
+        if let Some(session) = Session::load(&session_path, &req.game_id, &req.player_name).await {
+            println!("Reconnecting as player {} (capital at {})", session.player_id, session.capital_coord);
+            let player_ctx = PlayerContext {
+                player_id: session.player_id,
+                player_name: session.player_name.clone(),
+                capital_coord: session.capital_coord,
+                game_id: session.game_id.clone(),
+                token_id: req.token.as_deref().map(audit::token_id),
+            };
+            let origin = command_origin(peer, Some(&player_ctx));
+            reconnect_player(&raft_node, session, &session_path, my_host, origin).await?;
+
+            spawn_heartbeat(raft_node.clone(), player_ctx.player_id);
+            spawn_telemetry(raft_node.clone()).await;
+            *state.player_context.write().await = Some(player_ctx);
+            *state.raft_node.write().await = Some(raft_node);
+
+            // The capital worker is still running (or the game already took it)
+            println!("✓ Reconnected to game: {}", req.game_id);
+            return Ok(Json(format!("Reconnected to game {} as {}", req.game_id, req.player_name)));
+        }
+
         // Initialize player
-        let player_id = generate_player_id();
+        let player_id = account.map(|a| a.player_id).unwrap_or_else(generate_player_id);
         let capital_coord = find_random_unoccupied_coord(&raft_node).await
             .map_err(|e| format!("Failed to find capital position: {}", e))?;
 
-        // Submit PlayerJoin event
-        let join_event = GameEvent::PlayerJoin {
+        // Submit PlayerJoin event - only the token's hash goes in the log
+        let token = new_token();
+        let join_event = GameEvent::Command(CommandEvent::PlayerJoin {
             player_id,
             name: req.player_name.clone(),
             capital_coord,
-            node_ip: my_ip,
-            is_client: true,  // This is a client (player's laptop)
-            timestamp: current_timestamp(),
-        };
-
-        let event_request = GameEventRequest { event: join_event };
-        raft_node.raft.client_write(event_request).await
-            .map_err(|e| format!("Failed to submit join event: {}", e))?;
+            capital_ip: None,  // Capital worker is spawned below, it reports its own IP
+            client_endpoint: Some(my_host),  // This laptop - the final-kill target
+            // Unix seconds: the rules time spawn protection from the join
+            timestamp: current_timestamp() / 1_000_000,
+            raft_node_id: raft_node.node_id,
+            reconnect_key: Some(reconnect_key(&token)),
+        });
 
         // Store state
         let player_ctx = PlayerContext {
@@ -598,23 +911,41 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
             player_name: req.player_name.clone(),
             capital_coord,
             game_id: req.game_id.clone(),
+            token_id: req.token.as_deref().map(audit::token_id),
         };
 
+        raft_node.commands.submit_from(join_event, Some(command_origin(peer, Some(&player_ctx)))).await
+            .map_err(|e| format!("Failed to submit join event: {}", e))?;
+
+        let session = Session {
+            game_id: req.game_id.clone(),
+            player_id,
+            player_name: req.player_name.clone(),
+            capital_coord,
+            token,
+            next_token: None,
+        };
+        if let Err(e) = session.save(&session_path).await {
+            eprintln!("⚠ Failed to save session to {} (a restart will join as a new player): {}", session_path.display(), e);
+        }
+
+        spawn_heartbeat(raft_node.clone(), player_id);
+        spawn_telemetry(raft_node.clone()).await;
         *state.player_context.write().await = Some(player_ctx);
         *state.raft_node.write().await = Some(raft_node);
 
         println!("✓ Successfully joined game: {}", req.game_id);
 
         // Spawn capital worker for this player
-        println!("Spawning capital worker at ({}, {})...", capital_coord.q, capital_coord.r);
+        println!("Spawning capital worker at ({})...", capital_coord);
         let client = reqwest::Client::new();
         let spawn_result = client
             .post(format!("{}/spawn_single_node", state.master_url.as_str()))
+            .request_id()
             .json(&serde_json::json!({
                 "game_id": req.game_id,
                 "is_capital": true,
-                "q": capital_coord.q,
-                "r": capital_coord.r
+                "coord": capital_coord
             }))
             .send()
             .await;
@@ -635,23 +966,107 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
         Ok(Json(format!("Successfully joined game {} as {}", req.game_id, req.player_name)))
     }
 
+    /// Take `session`'s player back with PlayerReconnected, rotating its token
+    /// The token spent is whichever one matches the committed key - a reconnect that
+    /// committed before the client saved its new token leaves it in `next_token`.
+    async fn reconnect_player(
+        raft_node: &Arc<RaftNode>,
+        session: Session,
+        path: &std::path::Path,
+        my_host: String,
+        origin: CommandOrigin,
+    ) -> Result<(), String> {
+        let committed = {
+            let sm_arc = raft_node.storage.read().await.state_machine();
+            let sm = sm_arc.read().await;
+            session
+                .candidates()
+                .into_iter()
+                .find(|token| sm.game_state.validate_reconnect(session.player_id, &reconnect_key(token)).is_ok())
+        };
+        let token = committed.unwrap_or_else(|| session.token.clone());
+
+        // Save the next token before submitting, so a crash mid-commit can't lose it
+        let next = new_token();
+        let pending = Session { token: token.clone(), next_token: Some(next.clone()), ..session };
+        pending.save(path).await.map_err(|e| format!("Failed to save session: {}", e))?;
+
+        let event = GameEvent::Command(CommandEvent::PlayerReconnected {
+            player_id: pending.player_id,
+            token,
+            next_key: reconnect_key(&next),
+            client_endpoint: Some(my_host),
+            timestamp: current_timestamp() / 1_000_000,
+            raft_node_id: raft_node.node_id,
+        });
+        raft_node.commands.submit_from(event, Some(origin)).await
+            .map_err(|e| format!("Failed to reconnect: {}", e))?;
+
+        let session = Session { token: next, next_token: None, ..pending };
+        if let Err(e) = session.save(path).await {
+            eprintln!("⚠ Failed to save session to {}: {}", path.display(), e);
+        }
+        Ok(())
+    }
+
     // WebSocket handler for final kill attacks (10-second client kill)
+    // Only while committed state has one under way against this player, only from the
+    // captor's nodes and the leader, and within the connection and byte budget
     async fn finalkill_handler(
-        State(_state): State<ClientState>,
+        State(state): State<ClientState>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
         ws: WebSocketUpgrade,
     ) -> Response {
-        ws.on_upgrade(handle_finalkill_websocket)
+        let refuse = |status: StatusCode, reason: &str| {
+            eprintln!("[FinalKill] Refused {}: {}", peer.ip(), reason);
+            Response::builder().status(status).body(reason.to_string().into()).unwrap()
+        };
+        let Some(raft_node) = state.raft_node.read().await.clone() else {
+            return refuse(StatusCode::FORBIDDEN, "not in a game");
+        };
+        let Some(player_id) = state.player_context.read().await.as_ref().map(|ctx| ctx.player_id) else {
+            return refuse(StatusCode::FORBIDDEN, "not in a game");
+        };
+        let leader = raft_node.raft.metrics().borrow().current_leader;
+        let leader_host = match leader {
+            Some(leader) => raft_node.registry.get_address(leader).await.and_then(|addr| addr.rsplit_once(':').map(|(host, _)| host.to_string())),
+            None => None,
+        };
+        let expected = {
+            let sm_arc = raft_node.storage.read().await.state_machine();
+            let sm = sm_arc.read().await;
+            expected_final_kill(&sm.game_state, player_id, leader_host.as_deref())
+        };
+        let Some(expected) = expected else {
+            return refuse(StatusCode::FORBIDDEN, "no final kill under way");
+        };
+        if !resolve_sources(&expected.sources).await.contains(&peer.ip()) {
+            return refuse(StatusCode::FORBIDDEN, "not one of the captor's nodes");
+        }
+        let permit = match state.finalkill_budget.admit(&expected) {
+            Ok(permit) => permit,
+            Err(ReceiveRefusal::TooManyConnections) => return refuse(StatusCode::TOO_MANY_REQUESTS, "connection budget spent"),
+            Err(ReceiveRefusal::BudgetSpent) => return refuse(StatusCode::TOO_MANY_REQUESTS, "byte budget spent"),
+        };
+        ws.max_message_size(RECEIVE_MAX_FRAME_BYTES)
+            .max_frame_size(RECEIVE_MAX_FRAME_BYTES)
+            .on_upgrade(move |socket| handle_finalkill_websocket(socket, permit))
     }
 
-    async fn handle_finalkill_websocket(mut socket: WebSocket) {
+    async fn handle_finalkill_websocket(mut socket: WebSocket, permit: ReceivePermit) {
         println!("[FinalKill] Attacker connected, receiving flood data...");
         let mut bytes_received = 0u64;
 
-        // Receive data until connection closes
+        // Receive data until connection closes, or the final kill's byte budget is spent
         while let Some(msg) = socket.recv().await {
             match msg {
                 Ok(Message::Binary(data)) => {
                     bytes_received += data.len() as u64;
+                    if !permit.received(data.len()) {
+                        println!("[FinalKill] Byte budget spent, closing early after {} bytes", bytes_received);
+                        let _ = socket.send(Message::Close(None)).await;
+                        break;
+                    }
                 }
                 Ok(Message::Close(_)) => {
                     println!("[FinalKill] Connection closed, total bytes: {}", bytes_received);
@@ -671,25 +1086,33 @@ async fn start_api_server(state: ClientState, addr: String) -> Result<()> {
     }
 
     // Build router
+    let cors = Arc::new(CorsPolicy::from_env());
     let app = Router::new()
         .route("/discover", get(discover_games))
         .route("/status", get(get_status))
         .route("/join", post(join_game))
         .route("/my/status", get(get_player_status))
         .route("/my/nodes", get(get_player_nodes))
+        .route("/my/path", get(get_player_path))
         .route("/my/attack", post(set_attack_target))
+        .route("/my/attack/bulk", post(set_attack_targets))
+        .route("/my/defend", post(set_node_defense))
+        .route("/my/history", get(get_command_history))
+        .route("/my/history/:id/cancel", post(cancel_command))
         .route("/game/state", get(get_game_state))
-        .route("/events", post(submit_event))
+        .route("/events", get(get_events).post(submit_event))
+        .route("/awards", get(get_awards))
+        .route("/grid/neighbors", get(worker::raft::api::grid_neighbors))
+        .route("/grid/path", get(worker::raft::api::grid_path))
         .route("/ws", get(websocket_handler))
         .route("/finalkill", get(finalkill_handler))
         .nest_service("/", ServeDir::new("static").append_index_html_on_directories(true))
-        .layer(CorsLayer::permissive())  // Enable CORS for frontend
+        .layer(axum::middleware::from_fn_with_state(cors.clone(), worker::cors::check_websocket_origin))
+        .layer(cors.layer())
+        .layer(http_cache::compression())
+        .layer(axum::middleware::from_fn(request_id::propagate))
         .with_state(state);
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    println!("✓ HTTP API listening on {}", addr);
-    axum::serve(listener, app).await?;
-
-    Ok(())
+    // Start server (HTTPS/WSS if TLS_CERT_PATH and TLS_KEY_PATH are set)
+    worker::tls::serve(&addr, app).await
 }
@@ -0,0 +1,354 @@
+// camhack-local - a whole game on one machine, for offline play and demos
+//
+// Starts a master with the local spawn backend (SPAWN_BACKEND=local), has it start N
+// workers for one game, then starts M player clients. Every node keeps the standard
+// ports (5000 Raft, 8080 HTTP, 8081 UDP, 8082 internal HTTP), so each process gets its
+// own loopback address instead: clients 127.0.1.x, workers 127.0.10.x (handed out by
+// the master).
+// MASTER_URL, NODE_IP and BIND_IP are wired up here and by the master; anything else
+// (GAME_RULES, NETSIM_*, ...) is inherited from this process's environment.
+// Ctrl-C stops the master's workers through /kill_workers, then the clients and master.
+
+use anyhow::{bail, Context, Result};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+use tokio::time::Instant;
+
+/// First address the master hands to a worker (its LOCAL_FIRST_IP)
+const WORKER_FIRST_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 10, 1);
+
+/// Client n gets 127.0.1.n
+fn client_ip(n: u8) -> Ipv4Addr {
+    Ipv4Addr::new(127, 0, 1, n)
+}
+
+/// Port of every client's and worker's HTTP API
+const API_PORT: u16 = 8080;
+
+/// Ports every node binds on its own address - the master (on all interfaces) can't use them
+const NODE_PORTS: [u16; 4] = [5000, 8080, 8081, 8082];
+
+/// How long a process gets to answer HTTP, and the game's workers to register
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+const USAGE: &str = "\
+Usage: camhack-local [options]
+
+  --workers N       Workers the master starts for the game (default: 3)
+  --clients M       Player clients, at most 254 (default: 2)
+  --game-id ID      Game to start (default: local-game)
+  --master-port P   Master HTTP port (default: 9080)
+  --join            Join client n to the game as player-n
+  --netsim PROFILE  NETSIM_PROFILE for every node, e.g. congested (loopback never loses packets)
+  --dry-run         Simulate attack traffic instead of sending it (NET_TRANSPORT=dry-run)
+  --time-scale N    Run the game N times faster than real time, up to 60 (default: 1)
+  --logs DIR        One log file per process (default: camhack-local-logs)
+  --release         Run release builds
+  --build           cargo build the master, worker and client first
+";
+
+struct Options {
+    workers: u32,
+    clients: u8,
+    game_id: String,
+    master_port: u16,
+    join: bool,
+    netsim: Option<String>,
+    dry_run: bool,
+    time_scale: u32,
+    logs: PathBuf,
+    release: bool,
+    build: bool,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Options {
+            workers: 3,
+            clients: 2,
+            game_id: "local-game".to_string(),
+            master_port: 9080,
+            join: false,
+            netsim: None,
+            dry_run: false,
+            time_scale: 1,
+            logs: PathBuf::from("camhack-local-logs"),
+            release: false,
+            build: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("{} needs a value\n\n{}", arg, USAGE));
+            match arg.as_str() {
+                "--workers" => options.workers = value()?.parse().context("--workers")?,
+                "--clients" => options.clients = value()?.parse().context("--clients")?,
+                "--game-id" => options.game_id = value()?,
+                "--master-port" => options.master_port = value()?.parse().context("--master-port")?,
+                "--join" => options.join = true,
+                "--netsim" => options.netsim = Some(value()?),
+                "--dry-run" => options.dry_run = true,
+                "--time-scale" => options.time_scale = value()?.parse().context("--time-scale")?,
+                "--logs" => options.logs = value()?.into(),
+                "--release" => options.release = true,
+                "--build" => options.build = true,
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                other => bail!("Unknown option {}\n\n{}", other, USAGE),
+            }
+        }
+        if options.clients == u8::MAX {
+            bail!("--clients: at most 254");
+        }
+        if !(1..=60).contains(&options.time_scale) {
+            bail!("--time-scale: 1 to 60");
+        }
+        if NODE_PORTS.contains(&options.master_port) {
+            bail!("--master-port: {} is used by every node", options.master_port);
+        }
+        Ok(options)
+    }
+}
+
+/// The processes started so far, stopped together however the run ends
+struct Cluster {
+    http: reqwest::Client,
+    master_url: String,
+    master: Option<Child>,
+    clients: Vec<Child>,
+}
+
+impl Cluster {
+    async fn stop(&mut self) {
+        if let Some(mut master) = self.master.take() {
+            // The master's workers are its children, and only it can stop them
+            if matches!(master.try_wait(), Ok(None)) {
+                let url = format!("{}/kill_workers", self.master_url);
+                if let Err(e) = self.http.post(&url).send().await {
+                    eprintln!("Couldn't stop the workers ({}), check for stray worker processes", e);
+                }
+            }
+            let _ = master.kill().await;
+        }
+        for mut client in self.clients.drain(..) {
+            let _ = client.kill().await;
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let options = Options::parse(std::env::args().skip(1))?;
+
+    let mut cluster = Cluster {
+        http: reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?,
+        master_url: format!("http://127.0.0.1:{}", options.master_port),
+        master: None,
+        clients: Vec::new(),
+    };
+    let result = run(&options, &mut cluster).await;
+    cluster.stop().await;
+    result
+}
+
+async fn run(options: &Options, cluster: &mut Cluster) -> Result<()> {
+    // The master, worker and client crates sit next to this one
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().context("no repository root")?;
+    if options.build {
+        for name in ["master", "worker", "client"] {
+            build(root, name, options.release).await?;
+        }
+    }
+    let master_bin = binary(root, "master", options.release)?;
+    let worker_bin = binary(root, "worker", options.release)?;
+    let client_bin = binary(root, "client", options.release)?;
+
+    check_loopback()?;
+    std::fs::create_dir_all(&options.logs).with_context(|| format!("can't create {}", options.logs.display()))?;
+    let logs = options.logs.canonicalize()?;
+
+    let mut common = Vec::new();
+    if let Some(profile) = &options.netsim {
+        common.push(("NETSIM_PROFILE", profile.clone()));
+    }
+    if options.dry_run {
+        common.push(("NET_TRANSPORT", "dry-run".to_string()));
+    }
+    if options.time_scale > 1 {
+        common.push(("TIME_SCALE", options.time_scale.to_string()));
+    }
+
+    // Master, spawning workers as its own child processes
+    let mut env = common.clone();
+    env.extend([
+        ("PORT", options.master_port.to_string()),
+        ("MASTER_URL", cluster.master_url.clone()),
+        ("SPAWN_BACKEND", "local".to_string()),
+        ("LOCAL_WORKER_BIN", worker_bin.display().to_string()),
+        ("LOCAL_FIRST_IP", WORKER_FIRST_IP.to_string()),
+        ("LOCAL_LOG_DIR", logs.display().to_string()),
+    ]);
+    let master = cluster.master.insert(start("master", &master_bin, &logs, env)?);
+    wait_until_up(&cluster.http, &format!("{}/", cluster.master_url), master, "master").await?;
+
+    let url = format!("{}/spawn_workers?count={}&game_id={}", cluster.master_url, options.workers, options.game_id);
+    let spawned: serde_json::Value = cluster.http.post(&url).send().await?.error_for_status()?.json().await?;
+    let referee_token = spawned["referee_token"].as_str().unwrap_or("(game already existed)").to_string();
+    wait_for_workers(cluster, options.workers as usize).await?;
+
+    // Clients, each on its own loopback address
+    let mut client_urls = Vec::new();
+    for n in 1..=options.clients {
+        let ip = client_ip(n).to_string();
+        let name = format!("client-{}", n);
+        // Each client its own session file, so one restarted by hand reclaims its
+        // player; a session from an earlier run belongs to a game that's gone
+        let session = logs.join(format!("{}.session.json", name));
+        let _ = std::fs::remove_file(&session);
+        let mut env = common.clone();
+        env.extend([
+            ("MASTER_URL", cluster.master_url.clone()),
+            ("NODE_IP", ip.clone()),
+            ("BIND_IP", ip.clone()),
+            ("CLIENT_ID", name.clone()),
+            ("TASK_ARN", format!("local/{}", name)),
+            ("RECONNECT_FILE", session.display().to_string()),
+        ]);
+        let client = start(&name, &client_bin, &logs, env)?;
+        cluster.clients.push(client);
+        client_urls.push(format!("http://{}:{}", ip, API_PORT));
+    }
+    for (n, url) in (1..).zip(&client_urls) {
+        let client = &mut cluster.clients[n - 1];
+        wait_until_up(&cluster.http, &format!("{}/status", url), client, &format!("client-{}", n)).await?;
+        if options.join {
+            let join = serde_json::json!({ "game_id": options.game_id, "player_name": format!("player-{}", n) });
+            let reply = cluster.http.post(format!("{}/join", url)).json(&join).send().await?.text().await?;
+            println!("client-{} joined: {}", n, reply);
+        }
+    }
+
+    println!("\n=== Local game {} ===", options.game_id);
+    println!("  Master:        {}", cluster.master_url);
+    println!("  Workers:       {} from {} ({}/games)", options.workers, WORKER_FIRST_IP, cluster.master_url);
+    for (n, url) in (1..).zip(&client_urls) {
+        println!("  client-{}:      {}", n, url);
+    }
+    if options.time_scale > 1 {
+        println!("  Time scale:    {}x", options.time_scale);
+    }
+    println!("  Referee token: {}", referee_token);
+    println!("  Logs:          {}", logs.display());
+    println!("Ctrl-C to stop\n");
+
+    let master = cluster.master.as_mut().context("master not started")?;
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => println!("\nStopping..."),
+        status = master.wait() => println!("Master exited ({}), stopping...", status?),
+    }
+    Ok(())
+}
+
+/// `cargo build` one of the repository's crates
+async fn build(root: &Path, name: &str, release: bool) -> Result<()> {
+    println!("Building {}...", name);
+    let mut command = Command::new("cargo");
+    command.arg("build").arg("--manifest-path").arg(root.join(name).join("Cargo.toml"));
+    if release {
+        command.arg("--release");
+    }
+    let status = command.status().await.context("can't run cargo")?;
+    if !status.success() {
+        bail!("cargo build of {} failed ({})", name, status);
+    }
+    Ok(())
+}
+
+/// A crate's binary in its own target directory
+fn binary(root: &Path, name: &str, release: bool) -> Result<PathBuf> {
+    let profile = if release { "release" } else { "debug" };
+    let path = root.join(name).join("target").join(profile).join(name);
+    if !path.exists() {
+        bail!(
+            "{} not found - build it with `cargo build{} --manifest-path {}/Cargo.toml` or pass --build",
+            path.display(),
+            if release { " --release" } else { "" },
+            root.join(name).display()
+        );
+    }
+    Ok(path)
+}
+
+/// Fail early where only 127.0.0.1 is loopback (macOS needs an alias per address)
+fn check_loopback() -> Result<()> {
+    for ip in [client_ip(1), WORKER_FIRST_IP] {
+        if let Err(e) = std::net::UdpSocket::bind((ip, 0)) {
+            bail!(
+                "can't bind {} ({}). Every node needs its own loopback address; on macOS add them \
+                 with `sudo ifconfig lo0 alias {}` (and the following addresses)",
+                ip, e, ip
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Start `bin` with `env` on top of ours, logging to `<logs>/<name>.log`
+fn start(name: &str, bin: &Path, logs: &Path, env: Vec<(&'static str, String)>) -> Result<Child> {
+    let log = std::fs::File::create(logs.join(format!("{}.log", name)))?;
+    let child = Command::new(bin)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("can't start {}", bin.display()))?;
+    println!("Started {} (pid {:?})", name, child.id());
+    Ok(child)
+}
+
+/// Poll `url` until it answers, failing if `child` exits first
+async fn wait_until_up(http: &reqwest::Client, url: &str, child: &mut Child, name: &str) -> Result<()> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            bail!("{} exited during startup ({}), see {}.log", name, status, name);
+        }
+        if http.get(url).send().await.is_ok_and(|r| r.status().is_success()) {
+            return Ok(());
+        }
+        if Instant::now() > deadline {
+            bail!("{} didn't answer on {} within {:?}", name, url, STARTUP_TIMEOUT);
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Wait until `count` workers have registered with the master, so clients find peers
+async fn wait_for_workers(cluster: &mut Cluster, count: usize) -> Result<()> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    let url = format!("{}/status", cluster.master_url);
+    loop {
+        let status: Option<serde_json::Value> = match cluster.http.get(&url).send().await {
+            Ok(response) => response.json().await.ok(),
+            Err(_) => None,
+        };
+        let registered = status.and_then(|s| s["active_workers"].as_u64()).unwrap_or(0) as usize;
+        if registered >= count {
+            return Ok(());
+        }
+        if let Some(master) = &mut cluster.master {
+            if let Some(status) = master.try_wait()? {
+                bail!("master exited while workers started ({}), see master.log", status);
+            }
+        }
+        if Instant::now() > deadline {
+            bail!("{} of {} workers registered within {:?}, see the worker logs", registered, count, STARTUP_TIMEOUT);
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
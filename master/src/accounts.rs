@@ -0,0 +1,279 @@
+// Player accounts - stable identities and a ladder rating across games
+//
+// POST /players claims a name and hands back a player id and a secret token. A
+// client joining with that token (POST /join on the client) is checked against
+// POST /players/verify and plays under its stable player id instead of a fresh one,
+// so the master can recognise it in the leader's summaries. When a game finishes,
+// every account that played is rated ELO-style against the winner.
+//
+// Accounts live in memory, and in ACCOUNTS_FILE (JSON, see persist) if set so a
+// master restart keeps the ladder.
+
+use crate::persist;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Rating every account starts on (and guests are counted at)
+pub const INITIAL_RATING: f64 = 1200.0;
+
+/// Most rating a player can gain or lose in one game
+const K_FACTOR: f64 = 32.0;
+
+/// One finished game in a player's history
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RatedGame {
+    pub game_id: String,
+    pub won: bool,
+    pub players: usize,
+    pub rating_before: f64,
+    pub rating_after: f64,
+    pub finished_at: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub player_id: u64,
+    pub name: String,
+    token: String,
+    pub rating: f64,
+    /// Oldest first
+    pub games: Vec<RatedGame>,
+}
+
+/// A player in a finished game, as reported by its leader
+pub struct GameResult {
+    pub player_id: u64,
+    pub name: String,
+    pub won: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Accounts {
+    accounts: HashMap<u64, Account>, // player_id -> Account
+    next_id: u64,
+    #[serde(skip)]
+    path: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum AccountError {
+    EmptyName,
+    NameTaken,
+    BadToken,
+}
+
+impl std::fmt::Display for AccountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountError::EmptyName => write!(f, "Name can't be empty"),
+            AccountError::NameTaken => write!(f, "Name is already taken"),
+            AccountError::BadToken => write!(f, "Unknown name or wrong token"),
+        }
+    }
+}
+
+impl Accounts {
+    /// Load ACCOUNTS_FILE if set (a missing file starts an empty ladder)
+    pub fn from_env() -> Result<Self, String> {
+        let Ok(path) = std::env::var("ACCOUNTS_FILE") else {
+            return Ok(Self::default());
+        };
+        let accounts = Self::load(path)?;
+        eprintln!("✓ Accounts: {} ({} players)", accounts.path.as_deref().unwrap_or(""), accounts.accounts.len());
+        Ok(accounts)
+    }
+
+    /// The accounts saved at `path` (none if there's no file), saving back to it
+    pub fn load(path: String) -> Result<Self, String> {
+        let mut accounts: Self = persist::load(&path)?.unwrap_or_default();
+        accounts.path = Some(path);
+        Ok(accounts)
+    }
+
+    /// Claim `name`, returning the new account and its token
+    pub fn register(&mut self, name: &str) -> Result<(Account, String), AccountError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AccountError::EmptyName);
+        }
+        if self.accounts.values().any(|a| a.name == name) {
+            return Err(AccountError::NameTaken);
+        }
+
+        // Account ids start at 1 (0 is the neutral owner)
+        self.next_id += 1;
+        let account = Account {
+            player_id: self.next_id,
+            name: name.to_string(),
+            token: new_token(),
+            rating: INITIAL_RATING,
+            games: Vec::new(),
+        };
+        self.accounts.insert(account.player_id, account.clone());
+        self.save();
+        let token = account.token.clone();
+        Ok((account, token))
+    }
+
+    /// The account `name` belongs to, if `token` is its token
+    pub fn verify(&self, name: &str, token: &str) -> Result<&Account, AccountError> {
+        self.accounts
+            .values()
+            .find(|a| a.name == name && a.token == token)
+            .ok_or(AccountError::BadToken)
+    }
+
+    pub fn get(&self, player_id: u64) -> Option<&Account> {
+        self.accounts.get(&player_id)
+    }
+
+    /// Rate a finished game: each loser against the winner, with the K-factor shared
+    /// across the losers so a big game is worth as much as a duel. Players without an
+    /// account (or whose id and name don't match one) count at INITIAL_RATING and
+    /// aren't recorded. A game without exactly one winner (a draw, a game stopped
+    /// early) isn't rated. Returns how many accounts were rated.
+    pub fn record_game(&mut self, game_id: &str, results: &[GameResult], finished_at: u64) -> usize {
+        let mut winners = results.iter().filter(|r| r.won);
+        let (Some(winner), None) = (winners.next(), winners.next()) else {
+            return 0;
+        };
+        if results.len() < 2 {
+            return 0;
+        }
+
+        let rating_of = |r: &GameResult| {
+            self.accounts
+                .get(&r.player_id)
+                .filter(|a| a.name == r.name)
+                .map(|a| a.rating)
+        };
+        let winner_rating = rating_of(winner).unwrap_or(INITIAL_RATING);
+        let k = K_FACTOR / (results.len() - 1) as f64;
+
+        let mut deltas = Vec::new();
+        let mut winner_delta = 0.0;
+        for loser in results.iter().filter(|r| !r.won) {
+            let loser_rating = rating_of(loser).unwrap_or(INITIAL_RATING);
+            winner_delta += k * (1.0 - expected_score(winner_rating, loser_rating));
+            if let Some(rating) = rating_of(loser) {
+                deltas.push((loser.player_id, rating, -k * expected_score(loser_rating, winner_rating)));
+            }
+        }
+        if let Some(rating) = rating_of(winner) {
+            deltas.push((winner.player_id, rating, winner_delta));
+        }
+
+        for (player_id, rating_before, delta) in &deltas {
+            let account = self.accounts.get_mut(player_id).expect("rated players have accounts");
+            account.rating = rating_before + delta;
+            account.games.push(RatedGame {
+                game_id: game_id.to_string(),
+                won: *player_id == winner.player_id,
+                players: results.len(),
+                rating_before: *rating_before,
+                rating_after: account.rating,
+                finished_at,
+            });
+        }
+        if !deltas.is_empty() {
+            self.save();
+        }
+        deltas.len()
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(e) = persist::save(path, self) {
+            eprintln!("Failed to save accounts to {}: {}", path, e);
+        }
+    }
+}
+
+/// Chance that a player rated `rating` beats one rated `opponent`
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// 128 random bits as hex (also used for referee tokens)
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    if let Err(e) = std::fs::File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut bytes)) {
+        // Not secret without urandom, but still unique per account
+        eprintln!("WARNING: /dev/urandom unavailable ({}), token falls back to hashing", e);
+        use std::hash::{BuildHasher, Hasher};
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        bytes[..8].copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(account: &Account, won: bool) -> GameResult {
+        GameResult { player_id: account.player_id, name: account.name.clone(), won }
+    }
+
+    #[test]
+    fn test_expected_score() {
+        assert_eq!(expected_score(1200.0, 1200.0), 0.5);
+        assert!((expected_score(1600.0, 1200.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!((expected_score(1350.0, 1100.0) + expected_score(1100.0, 1350.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_accounts_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format!("accounts-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("accounts.json").to_string_lossy().into_owned();
+
+        let mut accounts = Accounts::load(path.clone()).unwrap();
+        let (alice, token) = accounts.register("alice").unwrap();
+        let (bob, _) = accounts.register("bob").unwrap();
+        accounts.record_game("g1", &[result(&alice, true), result(&bob, false)], 100);
+
+        let mut reloaded = Accounts::load(path).unwrap();
+        assert_eq!(reloaded.verify("alice", &token).unwrap().rating, accounts.get(alice.player_id).unwrap().rating);
+        assert_eq!(reloaded.get(bob.player_id).unwrap().games.len(), 1);
+        // Ids keep counting from where they were
+        assert_eq!(reloaded.register("carol").unwrap().0.player_id, bob.player_id + 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_record_game_rates_a_single_winner() {
+        let mut accounts = Accounts::default();
+        let (alice, _) = accounts.register("alice").unwrap();
+        let (bob, _) = accounts.register("bob").unwrap();
+        let guest = GameResult { player_id: 99, name: "guest".to_string(), won: false };
+
+        // Even ratings: the winner takes K/2 from each of the two losers
+        let rated = accounts.record_game("g1", &[result(&alice, true), result(&bob, false), guest], 100);
+        assert_eq!(rated, 2);
+        assert_eq!(accounts.get(alice.player_id).unwrap().rating, INITIAL_RATING + 16.0);
+        assert_eq!(accounts.get(bob.player_id).unwrap().rating, INITIAL_RATING - 8.0);
+        let history = &accounts.get(bob.player_id).unwrap().games;
+        assert_eq!((history.len(), history[0].won, history[0].players), (1, false, 3));
+
+        // No winner, two winners, or nobody to beat: nothing changes
+        for results in [
+            vec![result(&alice, false), result(&bob, false)],
+            vec![result(&alice, true), result(&bob, true)],
+            vec![result(&alice, true)],
+        ] {
+            assert_eq!(accounts.record_game("g2", &results, 200), 0);
+        }
+        assert_eq!(accounts.get(alice.player_id).unwrap().games.len(), 1);
+
+        // A result naming an account's id under another name isn't that account
+        let impostor = GameResult { player_id: alice.player_id, name: "mallory".to_string(), won: false };
+        assert_eq!(accounts.record_game("g3", &[result(&bob, true), impostor], 300), 1);
+        assert_eq!(accounts.get(alice.player_id).unwrap().rating, INITIAL_RATING + 16.0);
+    }
+}
@@ -0,0 +1,114 @@
+// DNS-based service discovery via a Route53 private hosted zone
+//
+// With DISCOVERY_MODE=dns every worker gets a stable name under its game's
+// subdomain, e.g. `worker-123.game-001.camhack.internal`. The master upserts the
+// A record on registration (so a task that moves keeps its name) and hands out
+// names instead of raw IPs from /get_peer. Workers resolve names when they
+// connect, never at registration time.
+
+use aws_sdk_route53::types::{Change, ChangeAction, ChangeBatch, ResourceRecord, ResourceRecordSet, RrType};
+use std::net::Ipv4Addr;
+
+#[derive(Clone)]
+pub struct DnsDiscovery {
+    client: aws_sdk_route53::Client,
+    hosted_zone_id: String,
+    domain: String,
+    ttl: i64,
+}
+
+impl DnsDiscovery {
+    /// Load settings from the environment - None unless DISCOVERY_MODE=dns
+    /// DISCOVERY_ZONE_ID: Route53 private hosted zone ID (required in dns mode)
+    /// DISCOVERY_DOMAIN: zone's domain, e.g. camhack.internal (required in dns mode)
+    /// DISCOVERY_TTL: record TTL in seconds (default: 10)
+    pub fn from_env(config: &aws_config::SdkConfig) -> Option<Self> {
+        let mode = std::env::var("DISCOVERY_MODE").unwrap_or_else(|_| "ip".to_string());
+        if mode != "dns" {
+            return None;
+        }
+
+        let (Ok(hosted_zone_id), Ok(domain)) = (
+            std::env::var("DISCOVERY_ZONE_ID"),
+            std::env::var("DISCOVERY_DOMAIN"),
+        ) else {
+            eprintln!("WARNING: DISCOVERY_MODE=dns needs DISCOVERY_ZONE_ID and DISCOVERY_DOMAIN, falling back to IPs");
+            return None;
+        };
+
+        if config.credentials_provider().is_none() {
+            eprintln!("WARNING: No AWS credentials for Route53, falling back to IPs");
+            return None;
+        }
+
+        let ttl = std::env::var("DISCOVERY_TTL")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10);
+
+        Some(Self {
+            client: aws_sdk_route53::Client::new(config),
+            hosted_zone_id: hosted_zone_id.trim_start_matches("/hostedzone/").to_string(),
+            domain: domain.trim_end_matches('.').to_string(),
+            ttl,
+        })
+    }
+
+    /// Stable DNS name for a worker: `<worker>.<game>.<domain>`
+    pub fn name_for(&self, worker_id: &str, game_id: &str) -> String {
+        format!("{}.{}.{}", dns_label(worker_id), dns_label(game_id), self.domain)
+    }
+
+    /// Point `name` at `ip` (creates or replaces the record)
+    pub async fn upsert(&self, name: &str, ip: Ipv4Addr) -> Result<(), String> {
+        self.change(ChangeAction::Upsert, name, ip).await
+    }
+
+    /// Remove the record for `name` (must match its current IP)
+    pub async fn delete(&self, name: &str, ip: Ipv4Addr) -> Result<(), String> {
+        self.change(ChangeAction::Delete, name, ip).await
+    }
+
+    async fn change(&self, action: ChangeAction, name: &str, ip: Ipv4Addr) -> Result<(), String> {
+        let record = ResourceRecord::builder()
+            .value(ip.to_string())
+            .build()
+            .map_err(|e| format!("Failed to build DNS record: {}", e))?;
+        let record_set = ResourceRecordSet::builder()
+            .name(name)
+            .r#type(RrType::A)
+            .ttl(self.ttl)
+            .resource_records(record)
+            .build()
+            .map_err(|e| format!("Failed to build DNS record set: {}", e))?;
+        let change = Change::builder()
+            .action(action)
+            .resource_record_set(record_set)
+            .build()
+            .map_err(|e| format!("Failed to build DNS change: {}", e))?;
+        let batch = ChangeBatch::builder()
+            .changes(change)
+            .build()
+            .map_err(|e| format!("Failed to build DNS change batch: {}", e))?;
+
+        self.client
+            .change_resource_record_sets()
+            .hosted_zone_id(&self.hosted_zone_id)
+            .change_batch(batch)
+            .send()
+            .await
+            .map_err(|e| format!("Route53 request failed: {}", aws_sdk_route53::error::DisplayErrorContext(e)))?;
+        Ok(())
+    }
+}
+
+/// Turn an arbitrary ID into a valid DNS label (lowercase alphanumerics and '-', max 63 chars)
+fn dns_label(id: &str) -> String {
+    let label: String = id
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let label = label.trim_matches('-');
+    label[..label.len().min(63)].trim_end_matches('-').to_string()
+}
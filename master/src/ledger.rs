@@ -0,0 +1,139 @@
+// Spawn ledger - every task this master ever started, and why it stopped
+//
+// The game map only knows workers that registered and whose game is still around,
+// so a task that never registered, or outlived a master restart, was invisible and
+// kept billing. The ledger records each task at run_task time (ARN, game, coordinate)
+// and stamps it when the master stops it. POST /cleanup_orphans uses it to tell a
+// worker that is still starting from a leaked one, and GET /ledger hands the whole
+// record to cleanup tooling.
+//
+// Like accounts, the ledger lives in memory, and in SPAWN_LEDGER_FILE (JSON, see
+// persist) if set so it survives the master itself.
+
+use crate::persist;
+use crate::Coord;
+use serde::{Deserialize, Serialize};
+
+/// One task started by the master
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub task_arn: String,
+    pub game_id: String,
+    /// Coordinate it was started for (None for /spawn_workers and warm pool workers)
+    pub coord: Option<Coord>,
+    pub started_at: u64,
+    pub stopped_at: Option<u64>,
+    /// e.g. "kill_workers" or "orphan"
+    pub stop_reason: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct SpawnLedger {
+    /// Oldest first
+    entries: Vec<LedgerEntry>,
+    #[serde(skip)]
+    path: Option<String>,
+}
+
+impl SpawnLedger {
+    /// Load SPAWN_LEDGER_FILE if set (a missing file starts an empty ledger)
+    pub fn from_env() -> Result<Self, String> {
+        let Ok(path) = std::env::var("SPAWN_LEDGER_FILE") else {
+            return Ok(Self::default());
+        };
+        let ledger = Self::load(path)?;
+        let running = ledger.entries.iter().filter(|e| e.stopped_at.is_none()).count();
+        eprintln!("✓ Spawn ledger: {} ({} tasks, {} not stopped)", ledger.path.as_deref().unwrap_or(""), ledger.entries.len(), running);
+        Ok(ledger)
+    }
+
+    /// The ledger saved at `path` (empty if there's none), saving back to it
+    pub fn load(path: String) -> Result<Self, String> {
+        let mut ledger: Self = persist::load(&path)?.unwrap_or_default();
+        ledger.path = Some(path);
+        Ok(ledger)
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// The entry for `task_arn`, if the master started it
+    pub fn get(&self, task_arn: &str) -> Option<&LedgerEntry> {
+        self.entries.iter().rev().find(|e| e.task_arn == task_arn)
+    }
+
+    /// Record a task the master just started
+    pub fn record_start(&mut self, task_arn: &str, game_id: &str, coord: Option<Coord>) {
+        self.entries.push(LedgerEntry {
+            task_arn: task_arn.to_string(),
+            game_id: game_id.to_string(),
+            coord,
+            started_at: now(),
+            stopped_at: None,
+            stop_reason: None,
+        });
+        self.save();
+    }
+
+    /// Stamp the tasks in `task_arns` as stopped for `reason` (ones already stopped keep
+    /// their first reason; ones the master didn't start are left out)
+    pub fn record_stops<'a>(&mut self, task_arns: impl IntoIterator<Item = &'a str>, reason: &str) {
+        let stopped_at = now();
+        let mut changed = false;
+        for task_arn in task_arns {
+            let entry = self.entries.iter_mut().rev().find(|e| e.task_arn == task_arn);
+            if let Some(entry) = entry.filter(|e| e.stopped_at.is_none()) {
+                entry.stopped_at = Some(stopped_at);
+                entry.stop_reason = Some(reason.to_string());
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(e) = persist::save(path, self) {
+            eprintln!("Failed to save spawn ledger to {}: {}", path, e);
+        }
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_survives_a_restart() {
+        let dir = std::env::temp_dir().join(format!("ledger-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ledger.json").to_string_lossy().into_owned();
+
+        let mut ledger = SpawnLedger::load(path.clone()).unwrap();
+        ledger.record_start("arn:1", "g1", Some(Coord { q: 1, r: -1 }));
+        ledger.record_start("arn:2", "g1", None);
+        ledger.record_stops(["arn:1"], "kill_workers");
+
+        let reloaded = SpawnLedger::load(path).unwrap();
+        let summary = |l: &SpawnLedger| {
+            l.entries().iter().map(|e| (e.task_arn.clone(), e.coord.as_ref().map(|c| (c.q, c.r)), e.stop_reason.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(summary(&reloaded), summary(&ledger));
+        assert_eq!(reloaded.get("arn:1").unwrap().stop_reason.as_deref(), Some("kill_workers"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,135 @@
+// Local spawn backend - workers as child processes instead of ECS tasks
+//
+// With SPAWN_BACKEND=local the master runs LOCAL_WORKER_BIN for every worker it would
+// otherwise start on Fargate, so a whole game fits on one machine (see camhack-local).
+// Workers always use the same ports (5000/8080/8081), so each one gets its own
+// loopback address instead: 127.0.0.0/8 is all loopback on Linux. The address is
+// handed over as NODE_IP (what the worker registers) and BIND_IP (what it listens on),
+// with a synthetic TASK_ARN that /kill_workers stops the process by.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+/// First loopback address handed to a spawned worker (LOCAL_FIRST_IP)
+/// camhack-local keeps 127.0.1.x for its clients, so the ranges don't meet.
+const DEFAULT_FIRST_IP: Ipv4Addr = Ipv4Addr::new(127, 0, 10, 1);
+
+/// Workers started by this master, by their synthetic task ARN
+#[derive(Clone)]
+pub struct LocalBackend {
+    worker_bin: PathBuf,
+    log_dir: Option<PathBuf>,
+    next_ip: Arc<Mutex<u32>>,
+    children: Arc<Mutex<HashMap<String, Child>>>,
+}
+
+impl LocalBackend {
+    /// The local backend if SPAWN_BACKEND=local
+    pub fn from_env() -> Option<Self> {
+        if std::env::var("SPAWN_BACKEND").ok()? != "local" {
+            return None;
+        }
+        let worker_bin = std::env::var("LOCAL_WORKER_BIN").unwrap_or_else(|_| "worker".to_string());
+        let first_ip = std::env::var("LOCAL_FIRST_IP")
+            .ok()
+            .and_then(|s| s.parse::<Ipv4Addr>().ok())
+            .unwrap_or(DEFAULT_FIRST_IP);
+        Some(Self {
+            worker_bin: worker_bin.into(),
+            log_dir: std::env::var("LOCAL_LOG_DIR").ok().map(PathBuf::from),
+            next_ip: Arc::new(Mutex::new(u32::from(first_ip))),
+            children: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn worker_bin(&self) -> &std::path::Path {
+        &self.worker_bin
+    }
+
+    /// Start a worker process with `env` and its own loopback address
+    /// Returns its synthetic task ARN.
+    pub async fn spawn(&self, env: Vec<(&'static str, String)>) -> Result<String, String> {
+        let ip = self.next_address().await?;
+        let worker_id = env
+            .iter()
+            .find(|(name, _)| *name == "WORKER_ID")
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| ip.to_string());
+        let task_arn = format!("local/{}", worker_id);
+
+        let mut command = Command::new(&self.worker_bin);
+        command
+            .envs(env)
+            .env("NODE_IP", ip.to_string())
+            .env("BIND_IP", ip.to_string())
+            .env("TASK_ARN", &task_arn)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        if let Some(dir) = &self.log_dir {
+            let log = std::fs::File::create(dir.join(format!("{}.log", worker_id)))
+                .map_err(|e| format!("can't create log for {}: {}", worker_id, e))?;
+            let err_log = log.try_clone().map_err(|e| e.to_string())?;
+            command.stdout(log).stderr(err_log);
+        }
+
+        let child = command
+            .spawn()
+            .map_err(|e| format!("can't start {}: {}", self.worker_bin.display(), e))?;
+        println!("Started local worker {} on {} (pid {:?})", worker_id, ip, child.id());
+        self.children.lock().await.insert(task_arn.clone(), child);
+        Ok(task_arn)
+    }
+
+    /// Stop the worker started as `task_arn` (false if it isn't ours)
+    pub async fn stop(&self, task_arn: &str) -> bool {
+        let Some(mut child) = self.children.lock().await.remove(task_arn) else {
+            return false;
+        };
+        if let Err(e) = child.kill().await {
+            eprintln!("Failed to stop local worker {}: {}", task_arn, e);
+        }
+        true
+    }
+
+    /// Stop every worker still running, e.g. before the master exits
+    /// Returns the task ARNs stopped.
+    pub async fn stop_all(&self) -> Vec<String> {
+        let children: Vec<_> = self.children.lock().await.drain().collect();
+        let mut stopped = Vec::new();
+        for (task_arn, mut child) in children {
+            if let Err(e) = child.kill().await {
+                eprintln!("Failed to stop local worker {}: {}", task_arn, e);
+            }
+            stopped.push(task_arn);
+        }
+        stopped
+    }
+
+    /// Task ARNs of the workers whose processes are still running
+    pub async fn running(&self) -> Vec<String> {
+        let mut children = self.children.lock().await;
+        children
+            .iter_mut()
+            .filter_map(|(task_arn, child)| matches!(child.try_wait(), Ok(None)).then(|| task_arn.clone()))
+            .collect()
+    }
+
+    async fn next_address(&self) -> Result<Ipv4Addr, String> {
+        let mut next = self.next_ip.lock().await;
+        // Skip .0 and .255, which some tools treat as network/broadcast addresses
+        while matches!(*next & 0xff, 0 | 255) {
+            *next += 1;
+        }
+        let ip = Ipv4Addr::from(*next);
+        if !ip.is_loopback() {
+            return Err("out of loopback addresses".to_string());
+        }
+        *next += 1;
+        Ok(ip)
+    }
+}
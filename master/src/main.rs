@@ -1,23 +1,52 @@
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
+use accounts::{AccountError, Accounts, GameResult};
 use aws_sdk_ecs::Client as EcsClient;
+use camhack_rules::{GameSettings, SettingError, SpawnTier, RULES_PRESETS};
+use discovery::DnsDiscovery;
+use ledger::SpawnLedger;
+use local::LocalBackend;
+use metadata::SelfIdentity;
+use pool::WarmPool;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::io::Write;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+mod accounts;
+mod discovery;
+mod ledger;
+mod local;
+mod metadata;
+mod persist;
+mod pool;
+mod request_id;
+
 #[derive(Clone, Serialize, Deserialize)]
 struct WorkerInfo {
     task_arn: String,
     ip: String,
     port: u16,
     game_id: String,
+    #[serde(default)]
+    dns_name: Option<String>, // Set in DNS discovery mode
+}
+
+impl WorkerInfo {
+    /// Address handed to peers - the DNS name if registered, otherwise the IP
+    fn host(&self) -> &str {
+        self.dns_name.as_deref().unwrap_or(&self.ip)
+    }
 }
 
 #[derive(Clone, Serialize)]
@@ -26,6 +55,160 @@ struct GameCluster {
     workers: HashMap<String, WorkerInfo>, // worker_id -> WorkerInfo
     #[serde(skip_serializing)]
     created_at: std::time::SystemTime,
+    #[serde(skip_serializing)]
+    summary: Option<GameSummary>,
+    #[serde(skip_serializing)]
+    event_log: VecDeque<EventSummary>, // Most recent committed events, oldest first
+    #[serde(skip_serializing)]
+    pool: WarmPool, // Idle workers ready to adopt a coordinate
+    #[serde(skip_serializing)]
+    rematch_of: Option<String>, // Finished game this one re-runs
+    #[serde(skip_serializing)]
+    rematch_game_id: Option<String>, // Rematch staged from this game
+    #[serde(skip_serializing)]
+    invited_players: Vec<String>, // Names invited back for a rematch
+}
+
+impl GameCluster {
+    fn new(game_id: String) -> Self {
+        GameCluster {
+            game_id,
+            workers: HashMap::new(),
+            created_at: std::time::SystemTime::now(),
+            summary: None,
+            event_log: VecDeque::new(),
+            pool: WarmPool::default(),
+            rematch_of: None,
+            rematch_game_id: None,
+            invited_players: Vec::new(),
+        }
+    }
+}
+
+/// A finished game's setup, kept after its cluster is gone so it can be rematched
+#[derive(Clone)]
+struct ArchivedGame {
+    rules_preset: String,
+    setup: GameSetup,
+    rematches: u32, // Rematches staged so far (numbers the new game ids)
+    awards: Option<serde_json::Value>, // From the final summary, for the closing ceremony
+}
+
+/// Committed events kept per game for GET /games/:game_id/summary
+const EVENT_LOG_CAPACITY: usize = 200;
+
+// Mirrors worker::game::summary - shipped by each game's Raft leader
+#[derive(Clone, Serialize, Deserialize)]
+struct PlayerSummary {
+    player_id: u64,
+    name: String,
+    alive: bool,
+    node_count: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct EventSummary {
+    seq: u64,
+    family: String,
+    kind: String,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct MapSetup {
+    seed: u64,
+    radius: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CapitalPlacement {
+    name: String,
+    coord: Coord,
+}
+
+/// A hex coordinate, like the worker's NodeCoord: written as {q, r}, read from {q, r} or "q,r"
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "CoordRepr")]
+struct Coord {
+    q: i32,
+    r: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CoordRepr {
+    Fields { q: i32, r: i32 },
+    Text(String),
+}
+
+impl TryFrom<CoordRepr> for Coord {
+    type Error = String;
+
+    fn try_from(repr: CoordRepr) -> Result<Self, Self::Error> {
+        match repr {
+            CoordRepr::Fields { q, r } => Ok(Coord { q, r }),
+            CoordRepr::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl FromStr for Coord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once(',')
+            .and_then(|(q, r)| Some(Coord { q: q.trim().parse().ok()?, r: r.trim().parse().ok()? }))
+            .ok_or_else(|| format!("Invalid coordinate '{}', expected q,r", s))
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.q, self.r)
+    }
+}
+
+/// A request's coordinate: nested `coord`, or flat `q`/`r` from older senders
+fn coord_fields<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Coord, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CoordFields {
+        Nested { coord: Coord },
+        Flat { q: i32, r: i32 },
+    }
+
+    match CoordFields::deserialize(deserializer)? {
+        CoordFields::Nested { coord } => Ok(coord),
+        CoordFields::Flat { q, r } => Ok(Coord { q, r }),
+    }
+}
+
+// Read by the leader from the committed PlayerJoin and MapGenerated events
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct GameSetup {
+    map: Option<MapSetup>,
+    capitals: Vec<CapitalPlacement>, // In join order
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct GameSummary {
+    game_id: String,
+    leader_node_id: u64,
+    #[serde(default)]
+    leader_api_addr: String, // ip:port of the leader's HTTP API
+    #[serde(default)]
+    rules_preset: String,
+    phase: String, // lobby | live | sudden_death | finished
+    players: Vec<PlayerSummary>,
+    node_count: usize,
+    #[serde(default)]
+    map_radius: u32,
+    total_events: u64,
+    recent_events: Vec<EventSummary>,
+    #[serde(default)]
+    setup: GameSetup,
+    #[serde(default)]
+    awards: Option<serde_json::Value>, // End-of-game awards, kept as sent (final summary only)
+    timestamp: u64,
 }
 
 #[derive(Clone)]
@@ -34,10 +217,22 @@ struct AppState {
     cluster_name: String,
     task_definition: String,
     capital_task_definition: String,  // 2x CPU/memory for capitals
+    small_task_definition: String,  // Frontier nodes (SMALL_TASK_DEFINITION, else the worker's)
     subnet_id: String,
     security_group_id: String,
     games: Arc<RwLock<HashMap<String, GameCluster>>>, // game_id -> GameCluster
+    archive: Arc<RwLock<HashMap<String, ArchivedGame>>>, // Finished games, kept past /kill_workers
+    accounts: Arc<RwLock<Accounts>>, // Player identities and ratings (ACCOUNTS_FILE)
+    ledger: Arc<RwLock<SpawnLedger>>, // Every task started, for orphan cleanup (SPAWN_LEDGER_FILE)
+    referee_tokens: Arc<RwLock<HashMap<String, String>>>, // game_id -> REFEREE_TOKEN
+    game_settings: Arc<RwLock<HashMap<String, GameSettings>>>, // game_id -> its config, overriding the master's own
     self_task_arn: Option<String>,
+    master_url: Option<String>, // Injected into spawned tasks as MASTER_URL
+    next_worker_seq: Arc<AtomicU64>, // Numbers WORKER_IDs handed to spawned tasks
+    warm_pool_size: usize, // Idle workers kept per live game (WARM_POOL_SIZE)
+    time_scale: Option<String>, // TIME_SCALE passed on to every spawned worker
+    dns: Option<DnsDiscovery>, // DISCOVERY_MODE=dns
+    local: Option<LocalBackend>, // SPAWN_BACKEND=local: workers are child processes
 }
 
 #[derive(Deserialize)]
@@ -45,6 +240,26 @@ struct SpawnQuery {
     count: Option<u32>,
     game_id: Option<String>,
     is_capital: Option<bool>,  // Spawn capital nodes with 2x resources
+    features: Option<String>,  // FEATURES for this game's workers, e.g. "fog_of_war,-sudden_death"
+}
+
+#[derive(Deserialize)]
+struct CreateGameRequest {
+    game_id: String,
+    #[serde(default = "default_worker_count")]
+    workers: u32,
+    #[serde(default)]
+    settings: GameSettings,
+}
+
+fn default_worker_count() -> u32 {
+    1
+}
+
+#[derive(Serialize)]
+struct InvalidSettingsResponse {
+    error: String,
+    errors: Vec<SettingError>,
 }
 
 #[derive(Serialize)]
@@ -52,6 +267,8 @@ struct SpawnResponse {
     message: String,
     spawned_count: usize,
     task_arns: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    referee_token: Option<String>, // Only in the response that created the game
 }
 
 #[derive(Serialize)]
@@ -79,6 +296,7 @@ struct RegisterWorkerRequest {
 #[derive(Serialize)]
 struct RegisterWorkerResponse {
     message: String,
+    dns_name: Option<String>, // Name to advertise instead of the IP (DNS discovery mode)
 }
 
 #[derive(Deserialize)]
@@ -89,15 +307,75 @@ struct GetPeerQuery {
 
 #[derive(Serialize)]
 struct GetPeerResponse {
-    peer_ip: Option<String>,
+    peer_ip: Option<String>, // DNS name in DNS discovery mode
     peer_port: Option<u16>,
 }
 
+#[derive(Deserialize)]
+struct GetGamesQuery {
+    joinable: Option<bool>, // Only games that can (or can't) be joined
+}
+
 #[derive(Serialize)]
 struct GameInfo {
     game_id: String,
     worker_count: usize,
     created_at_secs: u64,
+    status: String, // starting (no summary yet) | lobby | live | sudden_death | finished
+    player_names: Vec<String>,
+    alive_players: usize,
+    node_count: usize,
+    map_radius: u32,
+    rules_preset: Option<String>,
+    joinable: bool,
+    spectator_url: Option<String>, // Leader's /game/state
+    rematch_of: Option<String>,
+    rematch_game_id: Option<String>,
+    invited_players: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RegisterPlayerRequest {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct RegisterPlayerResponse {
+    player_id: u64,
+    name: String,
+    token: String, // Secret - send it with /join to play under this account
+    rating: f64,
+}
+
+#[derive(Deserialize)]
+struct VerifyPlayerRequest {
+    name: String,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct VerifyPlayerResponse {
+    player_id: u64,
+    name: String,
+    rating: f64,
+}
+
+#[derive(Serialize)]
+struct PlayerProfileResponse {
+    player_id: u64,
+    name: String,
+    rating: f64,
+    games: Vec<accounts::RatedGame>, // Oldest first
+}
+
+#[derive(Serialize)]
+struct RematchResponse {
+    game_id: String,
+    rematch_of: String,
+    invited_players: Vec<String>,
+    rules_preset: String,
+    task_arn: Option<String>, // Founding worker of the new game
+    referee_token: String, // The requester referees the rematch
 }
 
 #[derive(Serialize)]
@@ -109,15 +387,30 @@ struct GetGamesResponse {
 struct SpawnSingleNodeRequest {
     game_id: String,
     is_capital: bool,
-    q: i32,  // Node coordinate q
-    r: i32,  // Node coordinate r
+    #[serde(flatten, deserialize_with = "coord_fields")]
+    coord: Coord,
+    /// Terrain hint from the worker (1.0 = plain ground, above 1.0 = high ground)
+    #[serde(default = "default_capacity_multiplier")]
+    capacity_multiplier: f32,
+    /// Task size the game rules picked for the hex; workers from before it send none
+    #[serde(default)]
+    tier: Option<SpawnTier>,
+}
+
+fn default_capacity_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct PoolReadyRequest {
+    worker_id: String,
 }
 
 #[derive(Serialize)]
 struct SpawnSingleNodeResponse {
     message: String,
     task_arn: Option<String>,
-    coord: String,
+    coord: Coord,
 }
 
 #[tokio::main]
@@ -129,7 +422,7 @@ async fn main() {
 
     // Load AWS configuration
     eprintln!("Loading AWS configuration...");
-    let config = aws_config::load_from_env().await;
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
     let ecs_client = EcsClient::new(&config);
     eprintln!("✓ AWS configuration loaded");
 
@@ -140,6 +433,11 @@ async fn main() {
         .unwrap_or_else(|_| "worker".to_string());
     let capital_task_definition = std::env::var("CAPITAL_TASK_DEFINITION")
         .unwrap_or_else(|_| "worker-capital".to_string());
+    let small_task_definition = std::env::var("SMALL_TASK_DEFINITION")
+        .unwrap_or_else(|_| task_definition.clone());
+
+    // Workers as local processes instead of ECS tasks (no VPC settings needed)
+    let local = LocalBackend::from_env();
 
     // Validate required environment variables with helpful error messages
     let subnet_id = match std::env::var("SUBNET_ID") {
@@ -152,6 +450,7 @@ async fn main() {
             eprintln!("✓ SUBNET_ID: {}", val);
             val
         }
+        Err(_) if local.is_some() => String::new(),
         Err(_) => {
             eprintln!("ERROR: SUBNET_ID environment variable is required but not set");
             eprintln!("This variable should contain the AWS subnet ID where workers will be launched.");
@@ -170,6 +469,7 @@ async fn main() {
             eprintln!("✓ SECURITY_GROUP_ID: {}", val);
             val
         }
+        Err(_) if local.is_some() => String::new(),
         Err(_) => {
             eprintln!("ERROR: SECURITY_GROUP_ID environment variable is required but not set");
             eprintln!("This variable should contain the AWS security group ID for workers.");
@@ -178,24 +478,79 @@ async fn main() {
         }
     };
 
-    // Try to get our own task ARN (for self-kill)
-    let self_task_arn = std::env::var("SELF_TASK_ARN").ok();
+    let port = match std::env::var("PORT")
+        .unwrap_or_else(|_| "8080".to_string())
+        .parse::<u16>()
+    {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("ERROR: Invalid PORT value: {}", e);
+            eprintln!("PORT must be a valid number between 1 and 65535");
+            std::process::exit(1);
+        }
+    };
+
+    // Our own task ARN (for self-kill) and the address workers should call us on
+    let identity = SelfIdentity::resolve(port).await;
+    if identity.master_url.is_none() {
+        eprintln!("WARNING: Could not determine MASTER_URL, spawned tasks rely on their task definition's");
+    }
+
+    let warm_pool_size = pool::pool_size_from_env();
+    let time_scale = std::env::var("TIME_SCALE").ok().filter(|s| s.parse::<u32>().is_ok_and(|n| n > 1));
+
+    // Optional Route53 names for workers instead of raw IPs
+    let dns = DnsDiscovery::from_env(&config);
 
     eprintln!("Configuration:");
+    match &local {
+        Some(local) => eprintln!("  Spawn backend: local ({})", local.worker_bin().display()),
+        None => eprintln!("  Spawn backend: ecs"),
+    }
     eprintln!("  Cluster: {}", cluster_name);
     eprintln!("  Worker task def: {}", task_definition);
     eprintln!("  Capital task def: {}", capital_task_definition);
-    eprintln!("  Self task ARN: {:?}", self_task_arn);
+    eprintln!("  Small task def: {}", small_task_definition);
+    eprintln!("  Self task ARN: {:?}", identity.task_arn);
+    eprintln!("  Master URL for workers: {:?}", identity.master_url);
+    eprintln!("  Discovery: {}", if dns.is_some() { "dns" } else { "ip" });
+    eprintln!("  Warm pool: {} per game", warm_pool_size);
+    if let Some(scale) = &time_scale {
+        eprintln!("  Time scale: {}x", scale);
+    }
+
+    // An unreadable file would start an empty ladder and lose every token
+    let accounts = Accounts::from_env().unwrap_or_else(|e| {
+        eprintln!("ERROR: Accounts: {}", e);
+        std::process::exit(1);
+    });
+    // An unreadable ledger would hide every task it recorded
+    let ledger = SpawnLedger::from_env().unwrap_or_else(|e| {
+        eprintln!("ERROR: Spawn ledger: {}", e);
+        std::process::exit(1);
+    });
 
     let state = AppState {
         ecs_client,
         cluster_name,
         task_definition,
         capital_task_definition,
+        small_task_definition,
         subnet_id,
         security_group_id,
         games: Arc::new(RwLock::new(HashMap::new())),
-        self_task_arn,
+        archive: Arc::new(RwLock::new(HashMap::new())),
+        accounts: Arc::new(RwLock::new(accounts)),
+        ledger: Arc::new(RwLock::new(ledger)),
+        referee_tokens: Arc::new(RwLock::new(HashMap::new())),
+        game_settings: Arc::new(RwLock::new(HashMap::new())),
+        self_task_arn: identity.task_arn,
+        master_url: identity.master_url,
+        next_worker_seq: Arc::new(AtomicU64::new(0)),
+        warm_pool_size,
+        time_scale,
+        dns,
+        local,
     };
 
     // Build HTTP router
@@ -205,25 +560,24 @@ async fn main() {
         .route("/spawn_single_node", post(spawn_single_node))
         .route("/kill_workers", post(kill_workers))
         .route("/kill", post(kill_self))
+        .route("/cleanup_orphans", post(cleanup_orphans))
+        .route("/ledger", get(get_ledger))
         .route("/status", get(status))
         .route("/register_worker", post(register_worker))
         .route("/get_peer", get(get_peer))
-        .route("/games", get(get_games))
+        .route("/games", get(get_games).post(create_game))
+        .route("/games/:game_id/summary", post(report_summary).get(get_game_summary))
+        .route("/games/:game_id/pool", post(pool_worker_ready))
+        .route("/games/:game_id/rematch", post(rematch_game))
+        .route("/games/:game_id/awards", get(get_game_awards))
+        .route("/players", post(register_player))
+        .route("/players/verify", post(verify_player))
+        .route("/players/:player_id", get(get_player))
+        .layer(camhack_web::cors::CorsPolicy::from_env().layer())
+        .layer(axum::middleware::from_fn(request_id::propagate))
         .with_state(state);
 
     // Start HTTP server
-    let port = match std::env::var("PORT")
-        .unwrap_or_else(|_| "8080".to_string())
-        .parse::<u16>()
-    {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!("ERROR: Invalid PORT value: {}", e);
-            eprintln!("PORT must be a valid number between 1 and 65535");
-            std::process::exit(1);
-        }
-    };
-
     let addr = format!("0.0.0.0:{}", port);
     eprintln!("\n=== STARTING HTTP SERVER ===");
     println!("Master node listening on {}", addr);
@@ -231,11 +585,18 @@ async fn main() {
     println!("  GET  /                - Health check");
     println!("  GET  /status          - Show active workers");
     println!("  GET  /games           - List all available games");
+    println!("  POST /games           - Create a game from validated settings and spawn its workers");
     println!("  POST /spawn_workers?count=N&game_id=X - Spawn N workers for game X");
     println!("  POST /kill_workers    - Kill all workers");
     println!("  POST /kill            - Kill master (self)");
+    println!("  POST /cleanup_orphans?stop=true - List (and stop) running tasks no game owns");
+    println!("  GET  /ledger          - Every task this master started");
     println!("  POST /register_worker - Register worker with master");
     println!("  GET  /get_peer?game_id=X - Get a peer for joining cluster");
+    println!("  POST /games/:id/pool  - Warm pool worker ready to adopt a coordinate");
+    println!("  POST /games/:id/rematch - New game with a finished game's rules, map and capitals");
+    println!("  POST /players         - Claim a name, get a player id and token");
+    println!("  GET  /players/:id     - Rating and game history");
 
     let listener = match tokio::net::TcpListener::bind(&addr).await {
         Ok(l) => {
@@ -288,20 +649,65 @@ async fn status(State(state): State<AppState>) -> impl IntoResponse {
 async fn spawn_workers(
     Query(params): Query<SpawnQuery>,
     State(state): State<AppState>,
-) -> impl IntoResponse {
+) -> axum::response::Response {
     let count = params.count.unwrap_or(1);
     let game_id = params.game_id.unwrap_or_else(|| "default-game".to_string());
     let is_capital = params.is_capital.unwrap_or(false);
 
+    // Features a demo switches for this game only
+    if let Some(features) = params.features {
+        let settings = GameSettings { features: Some(features.clone()), ..GameSettings::default() };
+        if let Err(errors) = settings.validate() {
+            return invalid_settings(&game_id, errors).into_response();
+        }
+        println!("Game {} runs with FEATURES={}", game_id, features);
+        state.game_settings.write().await.entry(game_id.clone()).or_default().features = Some(features);
+    }
+
+    spawn_game_workers(&state, game_id, count, is_capital).await.into_response()
+}
+
+/// Create a game from its settings and spawn its first workers
+/// The settings are checked against the rules' schema first: 400 names every bad field,
+/// so nothing the workers would refuse reaches the game. 409 if the game exists.
+async fn create_game(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateGameRequest>,
+) -> axum::response::Response {
+    if let Err(errors) = payload.settings.validate() {
+        return invalid_settings(&payload.game_id, errors).into_response();
+    }
+    let exists = state.games.read().await.contains_key(&payload.game_id)
+        || state.referee_tokens.read().await.contains_key(&payload.game_id);
+    if exists {
+        return (StatusCode::CONFLICT, format!("Game {} already exists", payload.game_id)).into_response();
+    }
+
+    println!("{}Creating game {} with {:?}", request_id::prefix(), payload.game_id, payload.settings);
+    state.game_settings.write().await.insert(payload.game_id.clone(), payload.settings);
+    spawn_game_workers(&state, payload.game_id, payload.workers, false).await.into_response()
+}
+
+/// 400 listing each setting that can't be used
+fn invalid_settings(game_id: &str, errors: Vec<SettingError>) -> (StatusCode, Json<InvalidSettingsResponse>) {
+    let listed: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    eprintln!("{}Refused settings for game {}: {}", request_id::prefix(), game_id, listed.join("; "));
+    (
+        StatusCode::BAD_REQUEST,
+        Json(InvalidSettingsResponse { error: format!("Invalid settings for game {}", game_id), errors }),
+    )
+}
+
+/// Spawn `count` workers for a game, creating it (and its referee token) on the first call
+async fn spawn_game_workers(state: &AppState, game_id: String, count: u32, is_capital: bool) -> (StatusCode, Json<SpawnResponse>) {
     println!(
-        "Spawning {} {} workers for game {}...",
+        "{}Spawning {} {} workers for game {}...",
+        request_id::prefix(),
         count,
         if is_capital { "capital" } else { "regular" },
         game_id
     );
 
-    let mut spawned_arns = Vec::new();
-
     // Select task definition based on whether it's a capital
     let task_def = if is_capital {
         &state.capital_task_definition
@@ -309,129 +715,285 @@ async fn spawn_workers(
         &state.task_definition
     };
 
-    // Build task overrides to set GAME_ID environment variable
-    let mut task_override = aws_sdk_ecs::types::TaskOverride::builder();
+    // The first spawn for a game creates it, and its caller gets the referee token
+    let (referee_token, created) = grant_referee_token(state, &game_id).await;
+    let referee_token = created.then_some(referee_token);
 
-    let container_override = aws_sdk_ecs::types::ContainerOverride::builder()
-        .name(if is_capital { "udp-node-capital" } else { "udp-node" })
-        .environment(
-            aws_sdk_ecs::types::KeyValuePair::builder()
-                .name("GAME_ID")
-                .value(&game_id)
-                .build()
-        )
-        .build();
+    // One run_task per worker, so each gets its own WORKER_ID
+    let mut spawned_arns = Vec::new();
+    let mut failure = None;
+    for _ in 0..count {
+        let mut env = worker_environment(state, &game_id).await;
+        if let Some(rules) = state.game_settings.read().await.get(&game_id).and_then(|s| s.rules.clone()) {
+            env.push(("GAME_RULES", rules));
+        }
+        match run_worker_task(state, task_def, is_capital, env).await {
+            Ok(Some(task_arn)) => {
+                println!("{}Spawned worker: {}", request_id::prefix(), task_arn);
+                spawned_arns.push(task_arn);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("{}Failed to spawn workers: {}", request_id::prefix(), e);
+                failure = Some(e);
+                break;
+            }
+        }
+    }
 
-    task_override = task_override.container_overrides(container_override);
+    // Note: Workers will register themselves with /register_worker after they start
+    // We just track that we spawned them via ECS
+
+    match failure {
+        Some(e) if spawned_arns.is_empty() => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(SpawnResponse {
+                message: format!("Failed to spawn workers: {}", e),
+                spawned_count: 0,
+                task_arns: vec![],
+                referee_token,
+            }),
+        ),
+        failure => (
+            StatusCode::OK,
+            Json(SpawnResponse {
+                message: match failure {
+                    Some(e) => format!("Spawned {} of {} workers for game {}: {}", spawned_arns.len(), count, game_id, e),
+                    None => format!("Successfully spawned {} workers for game {}", spawned_arns.len(), game_id),
+                },
+                spawned_count: spawned_arns.len(),
+                task_arns: spawned_arns,
+                referee_token,
+            }),
+        ),
+    }
+}
 
-    // Spawn workers using ECS run_task
-    match state
-        .ecs_client
-        .run_task()
-        .cluster(&state.cluster_name)
-        .task_definition(task_def)
-        .count(count as i32)
-        .launch_type(aws_sdk_ecs::types::LaunchType::Fargate)
-        .network_configuration(
-            aws_sdk_ecs::types::NetworkConfiguration::builder()
-                .awsvpc_configuration(
-                    aws_sdk_ecs::types::AwsVpcConfiguration::builder()
-                        .subnets(&state.subnet_id)
-                        .security_groups(&state.security_group_id)
-                        .assign_public_ip(aws_sdk_ecs::types::AssignPublicIp::Enabled)
-                        .build()
-                        .expect("Failed to build AWS VPC configuration - this should not happen with valid subnet and security group IDs"),
-                )
-                .build(),
-        )
-        .overrides(task_override.build())
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if let Some(tasks) = response.tasks {
-                for task in tasks {
-                    if let Some(task_arn) = task.task_arn {
-                        println!("Spawned worker: {}", task_arn);
-                        spawned_arns.push(task_arn.clone());
-                    }
-                }
-            }
+/// Rules preset the game was created with, or else the one its leader reported
+/// (workers default to standard)
+async fn game_rules_preset(state: &AppState, game_id: &str) -> Result<String, String> {
+    if let Some(rules) = state.game_settings.read().await.get(game_id).and_then(|s| s.rules.clone()) {
+        return Ok(rules);
+    }
+    let reported = state.games.read().await
+        .get(game_id)
+        .and_then(|g| g.summary.as_ref())
+        .map(|s| s.rules_preset.clone())
+        .filter(|r| !r.is_empty());
+    spawnable_preset(game_id, reported)
+}
 
-            // Note: Workers will register themselves with /register_worker after they start
-            // We just track that we spawned them via ECS
+/// The preset to hand new workers as GAME_RULES, refusing a leader's `custom` rules:
+/// GAME_RULES only names a preset, and a worker handed one it doesn't know plays standard
+fn spawnable_preset(game_id: &str, reported: Option<String>) -> Result<String, String> {
+    match reported {
+        None => Ok("standard".to_string()),
+        Some(rules) if RULES_PRESETS.contains(&rules.as_str()) => Ok(rules),
+        Some(rules) => Err(format!(
+            "Game {} runs {} rules, which new workers can't be given (only the {} presets)",
+            game_id,
+            rules,
+            RULES_PRESETS.join(", ")
+        )),
+    }
+}
 
-            (
-                StatusCode::OK,
-                Json(SpawnResponse {
-                    message: format!("Successfully spawned {} workers for game {}", spawned_arns.len(), game_id),
-                    spawned_count: spawned_arns.len(),
-                    task_arns: spawned_arns,
-                }),
-            )
+/// Hand `coord` to an idle pool worker of the game, returning its task ARN
+/// Workers that don't answer are dropped from the pool (they're still killed with the game).
+async fn adopt_pool_worker(state: &AppState, game_id: &str, coord: Coord) -> Option<String> {
+    loop {
+        let worker = {
+            let mut games = state.games.write().await;
+            let game_cluster = games.get_mut(game_id)?;
+            let worker_id = game_cluster.pool.take()?;
+            game_cluster.workers.get(&worker_id).cloned().map(|w| (worker_id, w))
+        };
+        let Some((worker_id, worker_info)) = worker else {
+            continue;
+        };
+
+        match pool::adopt(worker_info.host(), game_id, coord).await {
+            Ok(()) => {
+                println!("Warm worker {} adopted ({}) in game {}", worker_id, coord, game_id);
+                return Some(worker_info.task_arn);
+            }
+            Err(e) => eprintln!("Warm worker {} failed to adopt ({}): {}", worker_id, coord, e),
         }
+    }
+}
+
+/// Spawn pool workers until the game has WARM_POOL_SIZE idle or starting
+async fn refill_pool(state: AppState, game_id: String) {
+    let deficit = {
+        let mut games = state.games.write().await;
+        let Some(game_cluster) = games.get_mut(&game_id) else {
+            return;
+        };
+        let deficit = game_cluster.pool.deficit(state.warm_pool_size);
+        game_cluster.pool.start_warming(deficit);
+        deficit
+    };
+    if deficit == 0 {
+        return;
+    }
+
+    let rules_preset = match game_rules_preset(&state, &game_id).await {
+        Ok(rules) => rules,
         Err(e) => {
-            eprintln!("Failed to spawn workers: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(SpawnResponse {
-                    message: format!("Failed to spawn workers: {}", e),
-                    spawned_count: 0,
-                    task_arns: vec![],
-                }),
-            )
+            eprintln!("Not refilling warm pool: {}", e);
+            if let Some(game_cluster) = state.games.write().await.get_mut(&game_id) {
+                game_cluster.pool.warming_failed(deficit);
+            }
+            return;
+        }
+    };
+    println!("Refilling warm pool for game {} with {} worker(s)", game_id, deficit);
+    let mut failed = 0;
+    for _ in 0..deficit {
+        let mut env = worker_environment(&state, &game_id).await;
+        env.push(("WARM_POOL", "1".to_string()));
+        env.push(("GAME_RULES", rules_preset.clone()));
+
+        match run_worker_task(&state, &state.task_definition, false, env).await {
+            Ok(Some(task_arn)) => println!("Spawned warm worker: {}", task_arn),
+            Ok(None) => failed += 1,
+            Err(e) => {
+                eprintln!("Failed to spawn warm worker for game {}: {}", game_id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        if let Some(game_cluster) = state.games.write().await.get_mut(&game_id) {
+            game_cluster.pool.warming_failed(failed);
         }
     }
 }
 
-async fn spawn_single_node(
-    State(state): State<AppState>,
-    Json(payload): Json<SpawnSingleNodeRequest>,
-) -> impl IntoResponse {
-    let coord_str = format!("({}, {})", payload.q, payload.r);
-    println!(
-        "Spawning single {} node at {} for game {}...",
-        if payload.is_capital { "capital" } else { "regular" },
-        coord_str,
-        payload.game_id
-    );
+/// The game's referee token, created on first use (true if this call created it)
+async fn grant_referee_token(state: &AppState, game_id: &str) -> (String, bool) {
+    let mut tokens = state.referee_tokens.write().await;
+    if let Some(token) = tokens.get(game_id) {
+        return (token.clone(), false);
+    }
+    let token = accounts::new_token();
+    tokens.insert(game_id.to_string(), token.clone());
+    println!("Granted a referee token for game {}", game_id);
+    (token, true)
+}
 
-    // Select task definition based on whether it's a capital
-    let task_def = if payload.is_capital {
-        &state.capital_task_definition
-    } else {
-        &state.task_definition
+/// The cluster's token for workers' internal APIs (INTERNAL_API_TOKEN), if any
+fn internal_api_token() -> Option<String> {
+    std::env::var("INTERNAL_API_TOKEN").ok().filter(|t| !t.is_empty())
+}
+
+/// Environment every spawned worker gets: GAME_ID, a fresh WORKER_ID, the game's
+/// REFEREE_TOKEN, and MASTER_URL, INTERNAL_API_TOKEN and ALLOWED_ORIGINS when known
+/// The game's settings (but its rules - see game_rules_preset) come before the
+/// master's own defaults for the same variables.
+async fn worker_environment(state: &AppState, game_id: &str) -> Vec<(&'static str, String)> {
+    let seq = state.next_worker_seq.fetch_add(1, Ordering::Relaxed);
+    let (referee_token, _) = grant_referee_token(state, game_id).await;
+    let mut env = vec![
+        ("GAME_ID", game_id.to_string()),
+        ("WORKER_ID", format!("{}-worker-{}", game_id, seq)),
+        ("REFEREE_TOKEN", referee_token),
+    ];
+    if let Some(settings) = state.game_settings.read().await.get(game_id) {
+        env.extend(settings.env().into_iter().filter(|(name, _)| *name != "GAME_RULES"));
+    }
+    let unset = |env: &Vec<(&'static str, String)>, name: &str| env.iter().all(|(n, _)| *n != name);
+    if let Some(master_url) = &state.master_url {
+        env.push(("MASTER_URL", master_url.clone()));
+    }
+    // The worker registers under the id of the request that started it
+    if let Some(id) = request_id::current() {
+        env.push(("REQUEST_ID", id));
+    }
+    if let Some(time_scale) = state.time_scale.clone().filter(|_| unset(&env, "TIME_SCALE")) {
+        env.push(("TIME_SCALE", time_scale));
+    }
+    if let Some(token) = internal_api_token() {
+        env.push(("INTERNAL_API_TOKEN", token));
+    }
+    if let Some(origins) = std::env::var("ALLOWED_ORIGINS").ok().filter(|o| !o.is_empty()) {
+        env.push(("ALLOWED_ORIGINS", origins));
+    }
+    // Every worker may end up leading, so every worker gets the audience webhook
+    for name in ["OBSERVER_WEBHOOK_URL", "OBSERVER_WEBHOOK_FORMAT"] {
+        if let Some(value) = std::env::var(name).ok().filter(|v| !v.is_empty()) {
+            env.push((name, value));
+        }
+    }
+    // A dry-run game stays dry on every worker, and whichever becomes leader
+    // commits the same elimination policy, applies the same supply lines and calls
+    // sudden death at the same time limit, with the same features
+    for name in [
+        "NET_TRANSPORT",
+        "DRY_RUN_LINK_MBPS",
+        "DRY_RUN_FLOODER_MBPS",
+        "ELIMINATION_POLICY",
+        "ELIMINATION_DECAY_SECS",
+        "SUPPLY_FALLOFF",
+        "SUPPLY_FREE_HOPS",
+        "SUPPLY_MIN_EFFECTIVENESS",
+        "TIME_LIMIT_SECS",
+        "FEATURES",
+    ] {
+        if let Some(value) = std::env::var(name).ok().filter(|v| !v.is_empty() && unset(&env, name)) {
+            env.push((name, value));
+        }
+    }
+    env
+}
+
+/// Start one worker task with `env` overriding its container's environment, and
+/// record it in the spawn ledger
+/// Returns the new task's ARN (None if ECS accepted the call but started nothing)
+async fn run_worker_task(
+    state: &AppState,
+    task_def: &str,
+    is_capital: bool,
+    env: Vec<(&'static str, String)>,
+) -> Result<Option<String>, String> {
+    let var = |name: &str| env.iter().find(|(n, _)| *n == name).map(|(_, value)| value.clone());
+    let game_id = var("GAME_ID").unwrap_or_default();
+    let coord = var("NODE_COORD_Q")
+        .zip(var("NODE_COORD_R"))
+        .and_then(|(q, r)| Some(Coord { q: q.parse().ok()?, r: r.parse().ok()? }));
+
+    // Locally every worker is the same binary, whatever its size
+    let task_arn = match &state.local {
+        Some(local) => Some(local.spawn(env).await?),
+        None => run_ecs_task(state, task_def, is_capital, env).await?,
     };
+    if let Some(task_arn) = &task_arn {
+        state.ledger.write().await.record_start(task_arn, &game_id, coord);
+    }
+    Ok(task_arn)
+}
 
-    // Build task overrides to set GAME_ID and NODE_COORD environment variables
+async fn run_ecs_task(
+    state: &AppState,
+    task_def: &str,
+    is_capital: bool,
+    env: Vec<(&'static str, String)>,
+) -> Result<Option<String>, String> {
     let container_override = aws_sdk_ecs::types::ContainerOverride::builder()
-        .name(if payload.is_capital { "udp-node-capital" } else { "udp-node" })
-        .environment(
-            aws_sdk_ecs::types::KeyValuePair::builder()
-                .name("GAME_ID")
-                .value(&payload.game_id)
-                .build()
-        )
-        .environment(
-            aws_sdk_ecs::types::KeyValuePair::builder()
-                .name("NODE_COORD_Q")
-                .value(payload.q.to_string())
-                .build()
-        )
-        .environment(
-            aws_sdk_ecs::types::KeyValuePair::builder()
-                .name("NODE_COORD_R")
-                .value(payload.r.to_string())
-                .build()
-        )
+        .name(if is_capital { "udp-node-capital" } else { "udp-node" })
+        .set_environment(Some(
+            env.into_iter()
+                .map(|(name, value)| aws_sdk_ecs::types::KeyValuePair::builder().name(name).value(value).build())
+                .collect(),
+        ))
         .build();
 
     let task_override = aws_sdk_ecs::types::TaskOverride::builder()
         .container_overrides(container_override)
         .build();
 
-    // Spawn single task
-    match state
+    let response = state
         .ecs_client
         .run_task()
         .cluster(&state.cluster_name)
@@ -453,38 +1015,100 @@ async fn spawn_single_node(
         .overrides(task_override)
         .send()
         .await
-    {
-        Ok(response) => {
-            let task_arn = response
-                .tasks
-                .and_then(|tasks| tasks.first().and_then(|task| task.task_arn.clone()));
+        .map_err(|e| e.to_string())?;
 
+    Ok(response
+        .tasks
+        .and_then(|tasks| tasks.first().and_then(|task| task.task_arn.clone())))
+}
+
+async fn spawn_single_node(
+    State(state): State<AppState>,
+    Json(payload): Json<SpawnSingleNodeRequest>,
+) -> impl IntoResponse {
+    let coord = payload.coord;
+    println!(
+        "{}Spawning single {} node at ({}) for game {}...",
+        request_id::prefix(),
+        if payload.is_capital { "capital" } else { "regular" },
+        coord,
+        payload.game_id
+    );
+
+    // Capitals get the bigger task definition, other hexes whatever their tier calls for
+    let tier = if payload.is_capital {
+        SpawnTier::Large
+    } else {
+        payload.tier.unwrap_or(if payload.capacity_multiplier > 1.0 { SpawnTier::Large } else { SpawnTier::Regular })
+    };
+    if !payload.is_capital && tier != SpawnTier::Regular {
+        println!("{:?} tier at ({}) (x{} terrain)", tier, coord, payload.capacity_multiplier);
+    }
+    let large = tier == SpawnTier::Large;
+    let task_def = match tier {
+        SpawnTier::Large => &state.capital_task_definition,
+        SpawnTier::Regular => &state.task_definition,
+        SpawnTier::Small => &state.small_task_definition,
+    };
+
+    // An idle pool worker takes the hex right away; otherwise start a task
+    if let Some(task_arn) = adopt_pool_worker(&state, &payload.game_id, coord).await {
+        tokio::spawn(refill_pool(state.clone(), payload.game_id.clone()));
+        return (
+            StatusCode::OK,
+            Json(SpawnSingleNodeResponse {
+                message: format!("Adopted warm worker at ({}) for game {}", coord, payload.game_id),
+                task_arn: Some(task_arn),
+                coord,
+            }),
+        );
+    }
+    if state.warm_pool_size > 0 {
+        tokio::spawn(refill_pool(state.clone(), payload.game_id.clone()));
+    }
+
+    let rules_preset = match game_rules_preset(&state, &payload.game_id).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("{}Not spawning single node: {}", request_id::prefix(), e);
+            return (StatusCode::CONFLICT, Json(SpawnSingleNodeResponse { message: e, task_arn: None, coord }));
+        }
+    };
+
+    // Coordinates and rules on top of the usual worker environment
+    let mut env = worker_environment(&state, &payload.game_id).await;
+    env.push(("NODE_COORD_Q", coord.q.to_string()));
+    env.push(("NODE_COORD_R", coord.r.to_string()));
+    env.push(("GAME_RULES", rules_preset));
+
+    match run_worker_task(&state, task_def, large, env).await {
+        Ok(task_arn) => {
             if let Some(ref arn) = task_arn {
-                println!("Spawned single node: {}", arn);
+                println!("{}Spawned single node: {}", request_id::prefix(), arn);
             }
 
             (
                 StatusCode::OK,
                 Json(SpawnSingleNodeResponse {
                     message: format!(
-                        "Successfully spawned {} node at {} for game {}",
+                        "Successfully spawned {} node at ({}) for game {}",
                         if payload.is_capital { "capital" } else { "regular" },
-                        coord_str,
+                        coord,
                         payload.game_id
                     ),
                     task_arn,
-                    coord: coord_str,
+                    coord,
                 }),
             )
         }
         Err(e) => {
-            eprintln!("Failed to spawn single node: {}", e);
+            eprintln!("{}Failed to spawn single node: {}", request_id::prefix(), e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(SpawnSingleNodeResponse {
                     message: format!("Failed to spawn node: {}", e),
                     task_arn: None,
-                    coord: coord_str,
+                    coord,
                 }),
             )
         }
@@ -501,9 +1125,16 @@ async fn kill_workers(State(state): State<AppState>) -> impl IntoResponse {
 
     println!("Killing {} workers across {} games...", all_workers.len(), games.len());
 
-    let mut killed_count = 0;
+    let mut killed_arns = Vec::new();
 
     for (worker_id, worker_info) in &all_workers {
+        if let Some(local) = &state.local {
+            if local.stop(&worker_info.task_arn).await {
+                println!("Killed worker {}: {}", worker_id, worker_info.task_arn);
+                killed_arns.push(worker_info.task_arn.clone());
+            }
+            continue;
+        }
         match state
             .ecs_client
             .stop_task()
@@ -514,7 +1145,7 @@ async fn kill_workers(State(state): State<AppState>) -> impl IntoResponse {
         {
             Ok(_) => {
                 println!("Killed worker {}: {}", worker_id, worker_info.task_arn);
-                killed_count += 1;
+                killed_arns.push(worker_info.task_arn.clone());
             }
             Err(e) => {
                 eprintln!("Failed to kill worker {} ({}): {}", worker_id, worker_info.task_arn, e);
@@ -522,6 +1153,25 @@ async fn kill_workers(State(state): State<AppState>) -> impl IntoResponse {
         }
     }
 
+    // Local workers that haven't registered yet aren't in any game
+    if let Some(local) = &state.local {
+        killed_arns.extend(local.stop_all().await);
+    }
+    let killed_count = killed_arns.len();
+    state.ledger.write().await.record_stops(killed_arns.iter().map(String::as_str), "kill_workers");
+
+    // Drop DNS records so names don't outlive their tasks
+    if let Some(dns) = &state.dns {
+        for (_, worker_info) in &all_workers {
+            // Only workers with a valid IPv4 address got a name
+            if let (Some(name), Ok(ip)) = (&worker_info.dns_name, worker_info.ip.parse()) {
+                if let Err(e) = dns.delete(name, ip).await {
+                    eprintln!("Failed to delete DNS name {}: {}", name, e);
+                }
+            }
+        }
+    }
+
     // Clear all games
     state.games.write().await.clear();
 
@@ -560,27 +1210,224 @@ async fn kill_self(State(state): State<AppState>) -> impl IntoResponse {
             }
         }
     } else {
-        eprintln!("SELF_TASK_ARN not set, cannot self-terminate via ECS");
+        eprintln!("Own task ARN unknown (no SELF_TASK_ARN or ECS metadata), cannot self-terminate via ECS");
+
+        // Local workers would outlive the process (kill_on_drop doesn't run on exit)
+        if let Some(local) = &state.local {
+            let stopped = local.stop_all().await;
+            state.ledger.write().await.record_stops(stopped.iter().map(String::as_str), "master_exit");
+        }
 
         // Fallback: exit the process (container will stop)
         std::process::exit(0);
     }
 }
 
+/// Tasks started this recently are still booting (not yet registered), not orphans
+const ORPHAN_GRACE_SECS: u64 = 300;
+
+#[derive(Deserialize)]
+struct CleanupQuery {
+    stop: Option<bool>, // Stop the orphans found (default: only list them)
+}
+
+#[derive(Serialize)]
+struct OrphanTask {
+    task_arn: String,
+    // From the spawn ledger, if this master started the task
+    game_id: Option<String>,
+    coord: Option<Coord>,
+    started_at: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct CleanupResponse {
+    message: String,
+    orphans: Vec<OrphanTask>,
+    stopped_count: usize,
+}
+
+/// Worker tasks running in the cluster (or as local processes) that no live game
+/// owns: not registered with any game, not the master itself, and not started in the
+/// last ORPHAN_GRACE_SECS. Listed by default; `?stop=true` stops them and records
+/// them in the ledger as stopped for "orphan".
+async fn cleanup_orphans(
+    Query(params): Query<CleanupQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let running = match running_worker_tasks(&state).await {
+        Ok(running) => running,
+        Err(e) => {
+            eprintln!("Failed to list running tasks: {}", e);
+            let response = CleanupResponse {
+                message: format!("Failed to list running tasks: {}", e),
+                orphans: vec![],
+                stopped_count: 0,
+            };
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(response));
+        }
+    };
+
+    let owned: std::collections::HashSet<String> = state.games.read().await
+        .values()
+        .flat_map(|g| g.workers.values().map(|w| w.task_arn.clone()))
+        .chain(state.self_task_arn.clone())
+        .collect();
+    let now = ledger::now();
+    let orphans: Vec<OrphanTask> = {
+        let ledger = state.ledger.read().await;
+        running
+            .into_iter()
+            .filter(|task_arn| !owned.contains(task_arn))
+            .map(|task_arn| {
+                let entry = ledger.get(&task_arn);
+                OrphanTask {
+                    game_id: entry.map(|e| e.game_id.clone()),
+                    coord: entry.and_then(|e| e.coord),
+                    started_at: entry.map(|e| e.started_at),
+                    task_arn,
+                }
+            })
+            .filter(|orphan| orphan.started_at.map_or(true, |t| now.saturating_sub(t) >= ORPHAN_GRACE_SECS))
+            .collect()
+    };
+
+    if !params.stop.unwrap_or(false) {
+        println!("Found {} orphaned worker tasks (not stopping)", orphans.len());
+        let response = CleanupResponse {
+            message: format!("Found {} orphaned tasks, POST ?stop=true to stop them", orphans.len()),
+            orphans,
+            stopped_count: 0,
+        };
+        return (StatusCode::OK, Json(response));
+    }
+
+    let mut stopped = Vec::new();
+    for orphan in &orphans {
+        let result = match &state.local {
+            Some(local) => Ok(local.stop(&orphan.task_arn).await),
+            None => state
+                .ecs_client
+                .stop_task()
+                .cluster(&state.cluster_name)
+                .task(&orphan.task_arn)
+                .reason("Orphaned worker (no live game)")
+                .send()
+                .await
+                .map(|_| true)
+                .map_err(|e| e.to_string()),
+        };
+        match result {
+            Ok(true) => {
+                println!("Stopped orphaned task {}", orphan.task_arn);
+                stopped.push(orphan.task_arn.as_str());
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to stop orphaned task {}: {}", orphan.task_arn, e),
+        }
+    }
+    state.ledger.write().await.record_stops(stopped.iter().copied(), "orphan");
+
+    let stopped_count = stopped.len();
+    (
+        StatusCode::OK,
+        Json(CleanupResponse {
+            message: format!("Stopped {} of {} orphaned tasks", stopped_count, orphans.len()),
+            orphans,
+            stopped_count,
+        }),
+    )
+}
+
+/// ARNs of running tasks of the worker task definitions (or local worker processes)
+async fn running_worker_tasks(state: &AppState) -> Result<Vec<String>, String> {
+    if let Some(local) = &state.local {
+        return Ok(local.running().await);
+    }
+
+    // By family, so the master's own task and anything else in the cluster are left alone
+    let mut families = vec![
+        task_family(&state.task_definition),
+        task_family(&state.capital_task_definition),
+        task_family(&state.small_task_definition),
+    ];
+    families.sort_unstable();
+    families.dedup();
+    let mut task_arns = Vec::new();
+    for family in families {
+        let mut next_token = None;
+        loop {
+            let response = state
+                .ecs_client
+                .list_tasks()
+                .cluster(&state.cluster_name)
+                .family(family)
+                .desired_status(aws_sdk_ecs::types::DesiredStatus::Running)
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            task_arns.extend(response.task_arns.unwrap_or_default());
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+    }
+    Ok(task_arns)
+}
+
+/// Family of a task definition given as `family`, `family:revision` or a full ARN
+fn task_family(task_definition: &str) -> &str {
+    let name = task_definition.rsplit('/').next().unwrap_or(task_definition);
+    name.split(':').next().unwrap_or(name)
+}
+
+/// Every task the master started, oldest first
+async fn get_ledger(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.ledger.read().await.entries().to_vec())
+}
+
 async fn register_worker(
     State(state): State<AppState>,
     Json(payload): Json<RegisterWorkerRequest>,
 ) -> impl IntoResponse {
     println!(
-        "Registering worker: {} at {}:{} for game: {}",
+        "{}Registering worker: {} at {}:{} for game: {}",
+        request_id::prefix(),
         payload.worker_id, payload.ip, payload.port, payload.game_id
     );
 
+    // In DNS mode, point the worker's stable name at its current IP
+    let dns_name = match (&state.dns, payload.ip.parse::<Ipv4Addr>()) {
+        (Some(_), Err(_)) => {
+            // Not an A record value - peers get the address as given
+            eprintln!("Not registering a DNS name for {}: '{}' isn't an IPv4 address", payload.worker_id, payload.ip);
+            None
+        }
+        (Some(dns), Ok(ip)) => {
+            let name = dns.name_for(&payload.worker_id, &payload.game_id);
+            match dns.upsert(&name, ip).await {
+                Ok(()) => {
+                    println!("Registered DNS name {} -> {}", name, payload.ip);
+                    Some(name)
+                }
+                Err(e) => {
+                    // Peers can still reach this worker by IP
+                    eprintln!("Failed to register DNS name {}: {}", name, e);
+                    None
+                }
+            }
+        }
+        (None, _) => None,
+    };
+
     let worker_info = WorkerInfo {
         task_arn: payload.task_arn,
         ip: payload.ip,
         port: payload.port,
         game_id: payload.game_id.clone(),
+        dns_name: dns_name.clone(),
     };
 
     let mut games = state.games.write().await;
@@ -588,11 +1435,7 @@ async fn register_worker(
     // Get or create the game cluster
     let game_cluster = games.entry(payload.game_id.clone()).or_insert_with(|| {
         println!("Creating new game cluster: {}", payload.game_id);
-        GameCluster {
-            game_id: payload.game_id.clone(),
-            workers: HashMap::new(),
-            created_at: std::time::SystemTime::now(),
-        }
+        GameCluster::new(payload.game_id.clone())
     });
 
     // Add worker to the game cluster
@@ -609,6 +1452,7 @@ async fn register_worker(
         StatusCode::OK,
         Json(RegisterWorkerResponse {
             message: format!("Worker {} registered successfully to game {}", payload.worker_id, payload.game_id),
+            dns_name,
         }),
     )
 }
@@ -642,10 +1486,10 @@ async fn get_peer(
         if let Some((_worker_id, worker_info)) = available_peers.first() {
             println!(
                 "Returning peer for game {}: {}:{} (excluding requester {})",
-                params.game_id, worker_info.ip, worker_info.port, params.requesting_ip
+                params.game_id, worker_info.host(), worker_info.port, params.requesting_ip
             );
             return Json(GetPeerResponse {
-                peer_ip: Some(worker_info.ip.clone()),
+                peer_ip: Some(worker_info.host().to_string()),
                 peer_port: Some(worker_info.port),
             });
         }
@@ -659,24 +1503,18 @@ async fn get_peer(
     })
 }
 
-async fn get_games(State(state): State<AppState>) -> impl IntoResponse {
+async fn get_games(
+    Query(params): Query<GetGamesQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
     let games = state.games.read().await;
 
-    let game_infos: Vec<GameInfo> = games.values()
-        .map(|game_cluster| {
-            // Convert SystemTime to seconds since UNIX_EPOCH
-            let created_at_secs = game_cluster.created_at
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-
-            GameInfo {
-                game_id: game_cluster.game_id.clone(),
-                worker_count: game_cluster.workers.len(),
-                created_at_secs,
-            }
-        })
+    let mut game_infos: Vec<GameInfo> = games.values()
+        .map(game_info)
+        .filter(|info| params.joinable.map_or(true, |j| info.joinable == j))
         .collect();
+    // Newest first for the game browser
+    game_infos.sort_by(|a, b| b.created_at_secs.cmp(&a.created_at_secs).then_with(|| a.game_id.cmp(&b.game_id)));
 
     println!("Returning {} active games", game_infos.len());
 
@@ -684,3 +1522,311 @@ async fn get_games(State(state): State<AppState>) -> impl IntoResponse {
         games: game_infos,
     })
 }
+
+/// Lobby view of a game, from its cluster and the leader's latest summary
+fn game_info(game_cluster: &GameCluster) -> GameInfo {
+    // Convert SystemTime to seconds since UNIX_EPOCH
+    let created_at_secs = game_cluster.created_at
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let summary = game_cluster.summary.as_ref();
+    let status = summary.map(|s| s.phase.clone()).unwrap_or_else(|| "starting".to_string());
+
+    GameInfo {
+        game_id: game_cluster.game_id.clone(),
+        worker_count: game_cluster.workers.len(),
+        created_at_secs,
+        player_names: summary
+            .map(|s| s.players.iter().map(|p| p.name.clone()).collect())
+            .unwrap_or_default(),
+        alive_players: summary.map(|s| s.players.iter().filter(|p| p.alive).count()).unwrap_or(0),
+        node_count: summary.map(|s| s.node_count).unwrap_or(0),
+        map_radius: summary.map(|s| s.map_radius).unwrap_or(0),
+        rules_preset: summary.map(|s| s.rules_preset.clone()).filter(|r| !r.is_empty()),
+        // Joining needs a running peer to contact and a game that isn't over
+        joinable: !game_cluster.workers.is_empty() && status != "finished",
+        spectator_url: summary
+            .filter(|s| !s.leader_api_addr.is_empty())
+            .map(|s| format!("http://{}/game/state", s.leader_api_addr)),
+        status,
+        rematch_of: game_cluster.rematch_of.clone(),
+        rematch_game_id: game_cluster.rematch_game_id.clone(),
+        invited_players: game_cluster.invited_players.clone(),
+    }
+}
+
+async fn report_summary(
+    Path(game_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut summary): Json<GameSummary>,
+) -> impl IntoResponse {
+    // The first finished summary rates the game, so only the game's workers may send one
+    if !camhack_web::auth::authorized(internal_api_token().as_deref(), &headers) {
+        eprintln!("Refused summary for game {} without the internal API token", game_id);
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let mut games = state.games.write().await;
+
+    let Some(game_cluster) = games.get_mut(&game_id) else {
+        eprintln!("Summary for unknown game {}", game_id);
+        return StatusCode::NOT_FOUND;
+    };
+
+    // Append new events (a re-sent batch after a failed report may overlap)
+    let last_seq = game_cluster.event_log.back().map(|e| e.seq).unwrap_or(0);
+    for event in summary.recent_events.drain(..) {
+        if event.seq > last_seq {
+            game_cluster.event_log.push_back(event);
+        }
+    }
+    while game_cluster.event_log.len() > EVENT_LOG_CAPACITY {
+        game_cluster.event_log.pop_front();
+    }
+
+    println!(
+        "Summary for game {}: phase={} players={} nodes={} events={}",
+        game_id, summary.phase, summary.players.len(), summary.node_count, summary.total_events
+    );
+    // Keep the pool topped up while the game is live
+    let live = summary.phase != "finished";
+    let archived = (!live).then(|| (summary.rules_preset.clone(), summary.setup.clone(), summary.awards.clone()));
+    let results: Vec<GameResult> = summary
+        .players
+        .iter()
+        .map(|p| GameResult { player_id: p.player_id, name: p.name.clone(), won: p.alive })
+        .collect();
+    let finished_at = summary.timestamp;
+    game_cluster.summary = Some(summary);
+    drop(games);
+
+    // Remember how a finished game was set up, for rematches
+    if let Some((rules_preset, setup, awards)) = archived {
+        let mut archive = state.archive.write().await;
+        let first_report = !archive.contains_key(&game_id);
+        let entry = archive.entry(game_id.clone()).or_insert_with(|| ArchivedGame {
+            rules_preset: String::new(),
+            setup: GameSetup::default(),
+            rematches: 0,
+            awards: None,
+        });
+        entry.rules_preset = rules_preset;
+        entry.setup = setup;
+        // Only the final summary carries awards; don't lose them to a later report
+        if awards.is_some() {
+            entry.awards = awards;
+        }
+        drop(archive);
+
+        // Rate the game once, on the first finished summary
+        if first_report {
+            let rated = state.accounts.write().await.record_game(&game_id, &results, finished_at);
+            if rated > 0 {
+                println!("Rated {} account(s) for game {}", rated, game_id);
+            }
+        }
+    }
+
+    if live && state.warm_pool_size > 0 {
+        tokio::spawn(refill_pool(state.clone(), game_id));
+    }
+
+    StatusCode::OK
+}
+
+async fn pool_worker_ready(
+    Path(game_id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<PoolReadyRequest>,
+) -> impl IntoResponse {
+    let mut games = state.games.write().await;
+
+    let Some(game_cluster) = games.get_mut(&game_id) else {
+        return (StatusCode::NOT_FOUND, "Unknown game");
+    };
+    if !game_cluster.workers.contains_key(&payload.worker_id) {
+        return (StatusCode::NOT_FOUND, "Worker not registered");
+    }
+
+    if game_cluster.pool.ready(payload.worker_id.clone()) {
+        println!(
+            "Warm worker {} ready in game {} ({} idle)",
+            payload.worker_id, game_id, game_cluster.pool.idle()
+        );
+    }
+    (StatusCode::OK, "Ready")
+}
+
+async fn get_game_summary(
+    Path(game_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let games = state.games.read().await;
+
+    let Some(game_cluster) = games.get(&game_id) else {
+        return (StatusCode::NOT_FOUND, Json(None));
+    };
+    let Some(mut summary) = game_cluster.summary.clone() else {
+        // Game exists but its leader hasn't reported yet
+        return (StatusCode::NOT_FOUND, Json(None));
+    };
+
+    // Serve the accumulated log rather than just the last batch
+    summary.recent_events = game_cluster.event_log.iter().cloned().collect();
+
+    (StatusCode::OK, Json(Some(summary)))
+}
+
+/// A finished game's awards, as its leader computed them at shutdown
+/// 404 for an unknown game (or one whose final summary hasn't arrived), 409 while it's still running.
+async fn get_game_awards(
+    Path(game_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let awards = state.archive.read().await.get(&game_id).and_then(|archived| archived.awards.clone());
+    if let Some(awards) = awards {
+        return Ok(Json(awards));
+    }
+    let running = state
+        .games
+        .read()
+        .await
+        .get(&game_id)
+        .is_some_and(|game| game.summary.as_ref().map_or(true, |s| s.phase != "finished"));
+    Err(if running {
+        (StatusCode::CONFLICT, format!("Game {} hasn't finished", game_id))
+    } else {
+        (StatusCode::NOT_FOUND, format!("No awards for game {}", game_id))
+    })
+}
+
+/// Stage a rematch of a finished game: same rules, map and capitals, same players invited
+/// The new game gets one founding worker; players join it as usual and are put back on
+/// their old capitals (CAPITAL_PLACEMENTS) while those hexes are free.
+async fn rematch_game(
+    Path(game_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<RematchResponse>, (StatusCode, String)> {
+    let (archived, new_game_id, rules_preset) = {
+        let mut archive = state.archive.write().await;
+        let Some(archived) = archive.get_mut(&game_id) else {
+            let known = state.games.read().await.contains_key(&game_id);
+            return Err(if known {
+                (StatusCode::CONFLICT, format!("Game {} hasn't finished", game_id))
+            } else {
+                (StatusCode::NOT_FOUND, format!("Unknown game {}", game_id))
+            });
+        };
+        let rules_preset = spawnable_preset(&game_id, Some(archived.rules_preset.clone()).filter(|r| !r.is_empty()))
+            .map_err(|e| (StatusCode::CONFLICT, e))?;
+        archived.rematches += 1;
+        (archived.clone(), format!("{}-rematch-{}", game_id, archived.rematches), rules_preset)
+    };
+    let invited_players: Vec<String> = archived.setup.capitals.iter().map(|c| c.name.clone()).collect();
+    println!(
+        "Rematch of {} as {} ({} rules, invited: {:?})",
+        game_id, new_game_id, rules_preset, invited_players
+    );
+
+    {
+        let mut games = state.games.write().await;
+        if let Some(original) = games.get_mut(&game_id) {
+            original.rematch_game_id = Some(new_game_id.clone());
+        }
+        let mut rematch = GameCluster::new(new_game_id.clone());
+        rematch.rematch_of = Some(game_id.clone());
+        rematch.invited_players = invited_players.clone();
+        games.insert(new_game_id.clone(), rematch);
+    }
+
+    let (referee_token, _) = grant_referee_token(&state, &new_game_id).await;
+    let mut env = worker_environment(&state, &new_game_id).await;
+    env.push(("GAME_RULES", rules_preset.clone()));
+    if let Some(map) = archived.setup.map {
+        env.push(("MAP_SEED", map.seed.to_string()));
+        env.push(("MAP_RADIUS", map.radius.to_string()));
+    }
+    let placements: Vec<String> = archived
+        .setup
+        .capitals
+        .iter()
+        .map(|c| format!("{}:{}", c.name, c.coord))
+        .collect();
+    env.push(("CAPITAL_PLACEMENTS", placements.join(";")));
+
+    let task_arn = match run_worker_task(&state, &state.task_definition, false, env).await {
+        Ok(task_arn) => task_arn,
+        Err(e) => {
+            eprintln!("Failed to start rematch {}: {}", new_game_id, e);
+            state.games.write().await.remove(&new_game_id);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start rematch: {}", e)));
+        }
+    };
+
+    Ok(Json(RematchResponse {
+        game_id: new_game_id,
+        rematch_of: game_id,
+        invited_players,
+        rules_preset,
+        task_arn,
+        referee_token,
+    }))
+}
+
+async fn register_player(
+    State(state): State<AppState>,
+    Json(payload): Json<RegisterPlayerRequest>,
+) -> Result<(StatusCode, Json<RegisterPlayerResponse>), (StatusCode, String)> {
+    let (account, token) = state.accounts.write().await.register(&payload.name).map_err(|e| {
+        let status = match e {
+            AccountError::NameTaken => StatusCode::CONFLICT,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (status, e.to_string())
+    })?;
+    println!("Registered player {} as {}", account.name, account.player_id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RegisterPlayerResponse {
+            player_id: account.player_id,
+            name: account.name,
+            token,
+            rating: account.rating,
+        }),
+    ))
+}
+
+async fn verify_player(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyPlayerRequest>,
+) -> Result<Json<VerifyPlayerResponse>, (StatusCode, String)> {
+    let accounts = state.accounts.read().await;
+    let account = accounts
+        .verify(&payload.name, &payload.token)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e.to_string()))?;
+
+    Ok(Json(VerifyPlayerResponse {
+        player_id: account.player_id,
+        name: account.name.clone(),
+        rating: account.rating,
+    }))
+}
+
+async fn get_player(
+    Path(player_id): Path<u64>,
+    State(state): State<AppState>,
+) -> Result<Json<PlayerProfileResponse>, StatusCode> {
+    let accounts = state.accounts.read().await;
+    let account = accounts.get(player_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(PlayerProfileResponse {
+        player_id: account.player_id,
+        name: account.name.clone(),
+        rating: account.rating,
+        games: account.games.clone(),
+    }))
+}
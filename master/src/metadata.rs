@@ -0,0 +1,96 @@
+// The master's own identity, from ECS task metadata
+//
+// Spawned workers need an address to reach the master (MASTER_URL) and /kill needs
+// the master's own task ARN. Both are resolved once at startup from the task
+// metadata endpoint, so neither has to be baked into a task definition. Env vars
+// still win, for local runs and unusual networking.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Task metadata v2 endpoint, used when the v4 URI isn't injected
+const ECS_TASK_URI_V2: &str = "http://169.254.170.2/v2/task";
+
+#[derive(Debug, Deserialize)]
+struct EcsTask {
+    #[serde(rename = "TaskARN")]
+    task_arn: String,
+    #[serde(rename = "Containers", default)]
+    containers: Vec<EcsContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EcsContainer {
+    #[serde(rename = "Networks", default)]
+    networks: Vec<EcsNetwork>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EcsNetwork {
+    #[serde(rename = "IPv4Addresses", default)]
+    ipv4_addresses: Vec<String>,
+}
+
+/// Who the master is, as far as spawned tasks and /kill care
+#[derive(Debug, Clone, Default)]
+pub struct SelfIdentity {
+    /// Own task ARN, for self-termination
+    pub task_arn: Option<String>,
+    /// URL injected into workers as MASTER_URL
+    pub master_url: Option<String>,
+}
+
+impl SelfIdentity {
+    /// Resolve the master's identity
+    /// SELF_TASK_ARN and MASTER_URL override what ECS metadata reports; outside ECS
+    /// whatever isn't set stays None.
+    pub async fn resolve(port: u16) -> Self {
+        let env_task_arn = std::env::var("SELF_TASK_ARN").ok().filter(|s| !s.is_empty());
+        let env_master_url = std::env::var("MASTER_URL").ok().filter(|s| !s.is_empty());
+
+        let task = if env_task_arn.is_some() && env_master_url.is_some() {
+            None
+        } else {
+            match fetch_task().await {
+                Ok(task) => Some(task),
+                Err(e) => {
+                    eprintln!("WARNING: ECS task metadata unavailable ({}), not running in ECS?", e);
+                    None
+                }
+            }
+        };
+
+        let task_arn = env_task_arn.or_else(|| task.as_ref().map(|t| t.task_arn.clone()));
+        let master_url = env_master_url.or_else(|| {
+            let ip = task
+                .as_ref()?
+                .containers
+                .iter()
+                .flat_map(|c| &c.networks)
+                .flat_map(|n| &n.ipv4_addresses)
+                .next()?;
+            Some(format!("http://{}:{}", ip, port))
+        });
+
+        Self { task_arn, master_url }
+    }
+}
+
+async fn fetch_task() -> Result<EcsTask, String> {
+    // The v4 endpoint is injected on Fargate platform 1.4+; older tasks only have v2
+    let uri = std::env::var("ECS_CONTAINER_METADATA_URI_V4")
+        .map(|base| format!("{}/task", base.trim_end_matches('/')))
+        .unwrap_or_else(|_| ECS_TASK_URI_V2.to_string());
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let response = client.get(&uri).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", uri, response.status()));
+    }
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| format!("bad task metadata: {}", e))
+}
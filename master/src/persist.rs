@@ -0,0 +1,64 @@
+// JSON state files the master keeps across restarts (SPAWN_LEDGER_FILE, ACCOUNTS_FILE)
+//
+// A file is replaced through a temp file and a rename, like the worker's identity and
+// snapshots, so a crash mid-write leaves the previous version instead of half of the
+// new one. A file that doesn't parse is moved aside to `<path>.corrupt-<unix secs>`
+// rather than read as empty and overwritten on the next save.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Read `path` (Ok(None) if there's no file yet, or it was unreadable and moved aside)
+/// Err when it can't be read at all - the caller shouldn't start over an empty state.
+pub fn load<T: DeserializeOwned>(path: &str) -> Result<Option<T>, String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            let aside = format!("{}.corrupt-{}", path, crate::ledger::now());
+            std::fs::rename(path, &aside).map_err(|err| format!("{} doesn't parse ({}) and can't be moved aside: {}", path, e, err))?;
+            eprintln!("WARNING: {} doesn't parse ({}), moved it to {} and starting empty", path, e, aside);
+            Ok(None)
+        }
+    }
+}
+
+/// Replace `path` with `value` as JSON, atomically
+pub fn save<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    let tmp = format!("{}.tmp", path);
+    let bytes = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, bytes).map_err(|e| format!("Failed to write {}: {}", tmp, e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to move {} into place: {}", tmp, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_round_trip_and_corrupt_files() {
+        let dir = std::env::temp_dir().join(format!("persist-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json").to_string_lossy().into_owned();
+
+        assert_eq!(load::<BTreeMap<String, u64>>(&path), Ok(None));
+        let value = BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        save(&path, &value).unwrap();
+        assert_eq!(load(&path), Ok(Some(value)));
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+
+        // Half a file is kept for inspection, not read as empty and overwritten
+        std::fs::write(&path, b"{\"a\": 1, \"b").unwrap();
+        assert_eq!(load::<BTreeMap<String, u64>>(&path), Ok(None));
+        assert!(!std::path::Path::new(&path).exists());
+        let aside: Vec<_> = std::fs::read_dir(&dir).unwrap().filter_map(|e| e.ok()).collect();
+        assert_eq!(aside.len(), 1);
+        assert!(aside[0].file_name().to_string_lossy().starts_with("state.json.corrupt-"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
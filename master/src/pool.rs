@@ -0,0 +1,101 @@
+// Warm worker pool - idle workers handed out instantly by spawn_single_node
+//
+// A cold Fargate start takes about a minute, during which a lazily-initialized node
+// can't be attacked. With WARM_POOL_SIZE=N the master keeps N idle workers per live
+// game: spawned with WARM_POOL=1, they join the game's Raft cluster without a
+// coordinate and announce themselves on POST /games/:game_id/pool once their API is
+// up. spawn_single_node then adopts one (POST /adopt on the worker) instead of
+// starting a task, and the pool is topped up in the background.
+
+use crate::Coord;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Port of the worker's internal API, where /adopt is served (its INTERNAL_API_PORT)
+const WORKER_INTERNAL_PORT: u16 = 8082;
+
+/// Idle workers per game (WARM_POOL_SIZE, default: 0 = no pool)
+pub fn pool_size_from_env() -> usize {
+    std::env::var("WARM_POOL_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0)
+}
+
+/// One game's pool
+#[derive(Clone, Default)]
+pub struct WarmPool {
+    /// Announced workers, oldest first
+    idle: VecDeque<String>,
+    /// Pool tasks spawned that haven't announced themselves yet
+    warming: usize,
+}
+
+impl WarmPool {
+    /// Workers to spawn to get back to `target`, counting ones still starting
+    pub fn deficit(&self, target: usize) -> usize {
+        target.saturating_sub(self.idle.len() + self.warming)
+    }
+
+    pub fn start_warming(&mut self, count: usize) {
+        self.warming += count;
+    }
+
+    /// Pool tasks that never started
+    pub fn warming_failed(&mut self, count: usize) {
+        self.warming = self.warming.saturating_sub(count);
+    }
+
+    /// A pool worker is up and can be adopted (false if it already was)
+    pub fn ready(&mut self, worker_id: String) -> bool {
+        if self.idle.contains(&worker_id) {
+            return false;
+        }
+        self.warming = self.warming.saturating_sub(1);
+        self.idle.push_back(worker_id);
+        true
+    }
+
+    /// Take the longest-idle worker
+    pub fn take(&mut self) -> Option<String> {
+        self.idle.pop_front()
+    }
+
+    pub fn idle(&self) -> usize {
+        self.idle.len()
+    }
+}
+
+#[derive(Serialize)]
+struct AdoptRequest<'a> {
+    game_id: &'a str,
+    coord: Coord,
+}
+
+/// Tell the idle worker at `host` to take over `coord` of `game_id`
+pub async fn adopt(host: &str, game_id: &str, coord: Coord) -> Result<(), String> {
+    let body = serde_json::to_string(&AdoptRequest { game_id, coord }).map_err(|e| e.to_string())?;
+
+    let mut request = reqwest::Client::new().post(format!("http://{}:{}/adopt", host, WORKER_INTERNAL_PORT));
+    if let Some(token) = crate::internal_api_token() {
+        request = request.bearer_auth(token);
+    }
+    if let Some(id) = crate::request_id::current() {
+        request = request.header(crate::request_id::HEADER.as_str(), id);
+    }
+    let response = request
+        .header("content-type", "application/json")
+        .body(body)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{}: {}", status, text));
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+// Request ids for the master's HTTP API
+//
+// Same X-Request-Id scheme as the workers and clients (worker/src/request_id.rs),
+// which the master doesn't link against: each request runs under the caller's id (or
+// a fresh one), the id is echoed on the response and failed responses are logged with
+// it. Workers the master starts while handling a request get the id as REQUEST_ID,
+// so their registration calls come back under it.
+
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::hash::{BuildHasher, Hasher};
+
+/// Header the id travels in
+pub const HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest id accepted from a caller; longer ones are replaced
+const MAX_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// A fresh id: 16 hex digits (the master has no rand, RandomState is seeded randomly)
+fn new_id() -> String {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+    format!("{:016x}", hasher.finish())
+}
+
+/// The id of the request this task is handling, if any
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// "[Request <id>] " inside a request, for log lines worth following across services
+pub fn prefix() -> String {
+    current().map(|id| format!("[Request {}] ", id)).unwrap_or_default()
+}
+
+/// A caller's id, if it's one we can log and echo safely
+fn accept(value: &HeaderValue) -> Option<String> {
+    let id = value.to_str().ok()?.trim();
+    let printable = id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c));
+    (!id.is_empty() && id.len() <= MAX_LEN && printable).then(|| id.to_string())
+}
+
+/// Middleware: adopt or assign the request's id, echo it, and log failures with it
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let id = request.headers().get(HEADER).and_then(accept).unwrap_or_else(new_id);
+    let (method, path) = (request.method().clone(), request.uri().path().to_string());
+
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        eprintln!("[Request {}] {} {} -> {}", id, method, path, status);
+    }
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HEADER, value);
+    }
+    response
+}
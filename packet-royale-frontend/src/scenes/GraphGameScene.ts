@@ -854,6 +854,11 @@ export class GraphGameScene extends Phaser.Scene {
     wsService.onUpdate((update: GameStateUpdate) => {
       debugLog('[WebSocket] Update received, log_index:', update.log_index);
 
+      // Banners for every player (the UI scene shows them)
+      if (update.announcements?.length) {
+        this.events.emit('announcements', update.announcements);
+      }
+
       // Only fetch if log index changed
       if (update.log_index > this.lastLogIndex) {
         this.lastLogIndex = update.log_index;
@@ -5,6 +5,7 @@
 import Phaser from "phaser";
 import type { GameState } from "../types/gameTypes";
 import type { NetworkGameState, NetworkNode } from "../types/graphTypes";
+import type { Announcement } from "../services/websocketService";
 import { COLORS } from "../config/visualConstants";
 import {
   initiateCapture,
@@ -35,6 +36,11 @@ export class UIScene extends Phaser.Scene {
   private scanline!: Phaser.GameObjects.Graphics;
   private scanlineY = 0;
 
+  // Announcement banner (newest one showing), hidden when it expires
+  private announcementText!: Phaser.GameObjects.Text;
+  private announcementTimer: Phaser.Time.TimerEvent | null = null;
+  private shownAnnouncementId: number | null = null;
+
   constructor() {
     super({ key: "UIScene" });
   }
@@ -52,15 +58,50 @@ export class UIScene extends Phaser.Scene {
     // Create scanline effect
     this.createScanlineEffect();
 
+    // Announcement banner below the top bar
+    this.createAnnouncementBanner();
+
     // Listen for game state updates
     const gameScene = this.scene.get("GraphGameScene");
     gameScene.events.on("gameStateUpdated", this.updateUI, this);
     gameScene.events.on("nodeSelected", this.onNodeSelected, this);
     gameScene.events.on("captureAttempted", this.onCaptureAttempted, this);
+    gameScene.events.on("announcements", this.onAnnouncements, this);
 
     console.log("UIScene initialized");
   }
 
+  private createAnnouncementBanner() {
+    const { width } = this.cameras.main;
+    this.announcementText = this.add.text(width / 2, 80, "", {
+      fontSize: "18px",
+      fontFamily: "monospace",
+      fontStyle: "bold",
+      backgroundColor: "#000000cc",
+      padding: { x: 16, y: 8 },
+    });
+    this.announcementText.setOrigin(0.5, 0);
+    this.announcementText.setScrollFactor(0);
+    this.announcementText.setVisible(false);
+  }
+
+  private onAnnouncements(announcements: Announcement[]) {
+    // The newest one wins; it stays up for the time it had left when pushed
+    const latest = announcements[announcements.length - 1];
+    if (!latest || latest.id === this.shownAnnouncementId) return;
+    this.shownAnnouncementId = latest.id;
+
+    const colors = { info: "#00ffff", warning: "#ffaa00", critical: "#ff0044" };
+    this.announcementText.setText(latest.text.toUpperCase());
+    this.announcementText.setColor(colors[latest.severity] ?? colors.info);
+    this.announcementText.setVisible(true);
+
+    this.announcementTimer?.remove();
+    this.announcementTimer = this.time.delayedCall(latest.remaining_secs * 1000, () => {
+      this.announcementText.setVisible(false);
+    });
+  }
+
   private onNodeSelected(node: NetworkNode) {
     this.selectedNode = node;
     this.updateButtonStates();
@@ -15,6 +15,7 @@ export interface BackendPlayerInfo {
   capital_coord: BackendNodeCoord;
   alive: boolean;
   node_count: number;
+  idle?: boolean; // Client stopped heartbeating; their nodes stand idle
 }
 
 // Backend sends AttackTarget as an enum: { Coordinate: {...} } | { Player: number }
@@ -7,6 +7,24 @@
 
 import { getBackendUrl } from '../config/backend';
 
+export type HexDirection = 'east' | 'north_east' | 'north_west' | 'west' | 'south_west' | 'south_east';
+
+export interface AttackWarning {
+  coord: { q: number; r: number };
+  attackers: { coord: { q: number; r: number }; owner_id: number; direction: HexDirection | null }[];
+  loss_percent: number | null;
+}
+
+export type AnnouncementSeverity = 'info' | 'warning' | 'critical';
+
+export interface Announcement {
+  id: number;
+  text: string;
+  severity: AnnouncementSeverity;
+  expires_at: number;
+  remaining_secs: number;
+}
+
 export interface GameStateUpdate {
   log_index: number;
   event_count: number;
@@ -14,6 +32,8 @@ export interface GameStateUpdate {
   node_count: number;
   alive_players: number;
   latest_event?: string;
+  attack_warnings?: AttackWarning[];
+  announcements?: Announcement[];
 }
 
 export type UpdateCallback = (update: GameStateUpdate) => void;
@@ -0,0 +1,55 @@
+"""Greedy bot: join a game, then keep attacking the nearest hex we don't own.
+
+    maturin develop -m python/Cargo.toml
+    python python/examples/greedy_bot.py --client localhost:8080 --name Bot
+"""
+
+import argparse
+
+import camhack_py as camhack
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument("--client", default="localhost:8080")
+    parser.add_argument("--name", default="Bot")
+    parser.add_argument("--game", help="game id (default: first joinable game)")
+    args = parser.parse_args()
+
+    client = camhack.Client(args.client)
+
+    if not client.status()["joined"]:
+        game_id = args.game
+        if game_id is None:
+            games = client.discover(joinable_only=True)
+            if not games:
+                raise SystemExit("No joinable games")
+            game_id = games[0]["game_id"]
+        print(client.join(game_id, args.name))
+
+    # Re-plan whenever the applied log advances
+    for update in client.subscribe():
+        me = client.my_status()
+        if not me["alive"]:
+            print("Eliminated")
+            break
+
+        owned = {(n["coord"]["q"], n["coord"]["r"]) for n in client.my_nodes()}
+        capital = (me["capital_coord"]["q"], me["capital_coord"]["r"])
+
+        # Closest unowned hex adjacent to our territory
+        frontier = {c for node in owned for c in camhack.neighbors(node)} - owned
+        if not frontier:
+            continue
+        target = min(frontier, key=lambda c: camhack.distance(capital, c))
+        attacker = next(n for n in owned if camhack.is_adjacent(n, target))
+
+        try:
+            client.attack(target, node=attacker)
+            print(f"[log {update['log_index']}] {attacker} -> {target}")
+        except camhack.CamHackError as e:
+            print(f"Attack failed: {e}")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,154 @@
+// Blocking Python wrapper around camhack_client_sdk::ClientApi
+//
+// Every call runs on a small Tokio runtime owned by the Client, with the GIL
+// released, so bots can use plain synchronous Python (and threads).
+
+use crate::CamHackError;
+use camhack_client_sdk::types::{NodeCoord, StateUpdate};
+use camhack_client_sdk::ClientApi;
+use futures_util::{Stream, StreamExt};
+use pyo3::prelude::*;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Runtime;
+
+type UpdateStream = Pin<Box<dyn Stream<Item = anyhow::Result<StateUpdate>> + Send>>;
+
+/// Connection to a player's client binary (default http://localhost:8080)
+#[pyclass(module = "camhack_py")]
+pub struct Client {
+    api: ClientApi,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Run an SDK call to completion without holding the GIL
+    fn call<F, T>(&self, py: Python<'_>, future: F) -> PyResult<T>
+    where
+        F: Future<Output = anyhow::Result<T>> + Send,
+        T: Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(future))
+            .map_err(|e| CamHackError::new_err(format!("{:#}", e)))
+    }
+}
+
+#[pymethods]
+impl Client {
+    #[new]
+    #[pyo3(signature = (base_url = "localhost:8080"))]
+    fn new(base_url: &str) -> PyResult<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| CamHackError::new_err(format!("Failed to start runtime: {}", e)))?;
+        Ok(Self {
+            api: ClientApi::new(base_url),
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Games known to the master, as a list of dicts
+    #[pyo3(signature = (joinable_only = false))]
+    fn discover(&self, py: Python<'_>, joinable_only: bool) -> PyResult<PyObject> {
+        let games = self.call(py, self.api.discover(joinable_only))?;
+        to_python(py, &games.games)
+    }
+
+    /// Whether this client has joined a game
+    fn status(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let status = self.call(py, self.api.status())?;
+        to_python(py, &status)
+    }
+
+    /// Join `game_id` as `player_name`
+    fn join(&self, py: Python<'_>, game_id: &str, player_name: &str) -> PyResult<String> {
+        self.call(py, self.api.join(game_id, player_name))
+    }
+
+    /// This player's id, capital, alive flag and node count
+    fn my_status(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let status = self.call(py, self.api.my_status())?;
+        to_python(py, &status)
+    }
+
+    /// Nodes this player owns and what each is attacking
+    fn my_nodes(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let nodes = self.call(py, self.api.my_nodes())?;
+        to_python(py, &nodes)
+    }
+
+    /// Cheapest route to `to`, from `from_` (default: the capital)
+    #[pyo3(signature = (to, from_ = None))]
+    fn my_path(&self, py: Python<'_>, to: (i32, i32), from_: Option<(i32, i32)>) -> PyResult<PyObject> {
+        let path = self.call(py, self.api.my_path(from_.map(coord), coord(to)))?;
+        to_python(py, &path)
+    }
+
+    /// Attack `target` from `node` (default: the capital)
+    #[pyo3(signature = (target, node = None))]
+    fn attack(&self, py: Python<'_>, target: (i32, i32), node: Option<(i32, i32)>) -> PyResult<String> {
+        self.call(py, self.api.attack(coord(target), node.map(coord)))
+    }
+
+    /// Full game state: players, nodes and event count
+    fn state(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let state = self.call(py, self.api.game_state())?;
+        to_python(py, &state)
+    }
+
+    /// Iterator of state updates from /ws, one dict per applied-log advance
+    fn subscribe(&self, py: Python<'_>) -> PyResult<StateStream> {
+        let api = self.api.clone();
+        let stream = self.call(py, async move {
+            let stream = api.subscribe().await?;
+            Ok(Box::pin(stream) as UpdateStream)
+        })?;
+        Ok(StateStream {
+            stream: Mutex::new(stream),
+            runtime: self.runtime.clone(),
+        })
+    }
+}
+
+/// Blocking iterator over `Client.subscribe()` updates
+/// Iteration stops when the client closes the socket
+#[pyclass(module = "camhack_py")]
+pub struct StateStream {
+    stream: Mutex<UpdateStream>,
+    runtime: Arc<Runtime>,
+}
+
+#[pymethods]
+impl StateStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let next = py.allow_threads(|| {
+            let mut stream = self.stream.lock().unwrap();
+            self.runtime.block_on(stream.next())
+        });
+        match next {
+            Some(Ok(update)) => to_python(py, &update).map(Some),
+            Some(Err(e)) => Err(CamHackError::new_err(format!("{:#}", e))),
+            None => Ok(None),
+        }
+    }
+}
+
+fn coord((q, r): (i32, i32)) -> NodeCoord {
+    NodeCoord::new(q, r)
+}
+
+/// Convert any response into plain Python dicts/lists via JSON
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| CamHackError::new_err(format!("Failed to encode response: {}", e)))?;
+    let loaded = py.import("json")?.call_method1("loads", (json,))?;
+    Ok(loaded.unbind())
+}
@@ -0,0 +1,76 @@
+// Hex-grid math - thin wrappers over worker::game::NodeCoord
+// Coordinates are (q, r) tuples in axial form, same as the API's {q, r}
+
+use pyo3::prelude::*;
+use worker::game::NodeCoord;
+
+type Coord = (i32, i32);
+
+fn coord((q, r): Coord) -> NodeCoord {
+    NodeCoord::new(q, r)
+}
+
+fn tuples(coords: impl IntoIterator<Item = NodeCoord>) -> Vec<Coord> {
+    coords.into_iter().map(|c| (c.q, c.r)).collect()
+}
+
+/// The 6 adjacent hexes
+#[pyfunction]
+pub fn neighbors(c: Coord) -> Vec<Coord> {
+    tuples(coord(c).neighbors())
+}
+
+/// Hex distance between two coordinates
+#[pyfunction]
+pub fn distance(a: Coord, b: Coord) -> u32 {
+    coord(a).distance(&coord(b))
+}
+
+/// Whether two coordinates are neighbours (attacks must target one)
+#[pyfunction]
+pub fn is_adjacent(a: Coord, b: Coord) -> bool {
+    coord(a).is_adjacent(&coord(b))
+}
+
+/// Hexes exactly `radius` steps away
+#[pyfunction]
+pub fn ring(c: Coord, radius: u32) -> Vec<Coord> {
+    tuples(coord(c).ring(radius))
+}
+
+/// Hexes within `radius`, centre-out
+#[pyfunction]
+pub fn spiral(c: Coord, radius: u32) -> Vec<Coord> {
+    tuples(coord(c).spiral(radius))
+}
+
+/// Straight hex line from `a` to `b`, inclusive
+#[pyfunction]
+pub fn line(a: Coord, b: Coord) -> Vec<Coord> {
+    tuples(coord(a).line_to(&coord(b)))
+}
+
+/// Pixel centre of a hex (pointy-top, same as the frontend)
+#[pyfunction]
+pub fn to_pixel(c: Coord, size: f64) -> (f64, f64) {
+    coord(c).to_pixel(size)
+}
+
+/// Hex containing a pixel
+#[pyfunction]
+pub fn from_pixel(x: f64, y: f64, size: f64) -> Coord {
+    let c = NodeCoord::from_pixel(x, y, size);
+    (c.q, c.r)
+}
+
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(neighbors, module)?)?;
+    module.add_function(wrap_pyfunction!(distance, module)?)?;
+    module.add_function(wrap_pyfunction!(is_adjacent, module)?)?;
+    module.add_function(wrap_pyfunction!(ring, module)?)?;
+    module.add_function(wrap_pyfunction!(spiral, module)?)?;
+    module.add_function(wrap_pyfunction!(line, module)?)?;
+    module.add_function(wrap_pyfunction!(to_pixel, module)?)?;
+    module.add_function(wrap_pyfunction!(from_pixel, module)?)?;
+    Ok(())
+}
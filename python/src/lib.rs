@@ -0,0 +1,22 @@
+// camhack_py - Python bindings for camhack-client-sdk
+//
+// Exposes a blocking `Client` (join/attack/state/subscribe) and the hex-grid
+// math so bots can be written in Python. Build with `maturin develop`.
+
+mod client;
+mod grid;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+create_exception!(camhack_py, CamHackError, PyException, "A CamHack API call failed");
+
+#[pymodule]
+fn camhack_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add("CamHackError", module.py().get_type::<CamHackError>())?;
+    module.add_class::<client::Client>()?;
+    module.add_class::<client::StateStream>()?;
+    grid::register(module)?;
+    Ok(())
+}
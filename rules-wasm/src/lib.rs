@@ -0,0 +1,262 @@
+// camhack-rules-wasm - browser bindings for camhack-rules
+//
+// Build: wasm-pack build rules-wasm --target web --out-dir ../packet-royale-frontend/src/wasm/rules
+//
+// The frontend feeds committed events (GET /events on any worker) into a
+// `Predictor`, which replays them through the server's GameState::process_event
+// and runs the leader's capture tracker, so predicted captures, attack validation
+// and capture progress match the server exactly. Player ids cross as f64 (JS numbers).
+
+use camhack_rules::{CaptureRules, CaptureTracker, FactEvent, Feature, GameEvent, GameState, NodeCoord};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Events as GET /events returns them, or a bare array
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum EventBatch {
+    Response { events: Vec<GameEvent> },
+    List(Vec<GameEvent>),
+}
+
+/// A capture the leader is expected to commit
+#[derive(Serialize)]
+struct PredictedCapture {
+    coord: NodeCoord,
+    new_owner_id: u64,
+}
+
+/// Local replica of the game state plus the leader's capture tracker
+#[wasm_bindgen]
+pub struct Predictor {
+    state: GameState,
+    tracker: CaptureTracker,
+    rules: CaptureRules,
+}
+
+#[wasm_bindgen]
+impl Predictor {
+    /// Predictor for a named rules preset ("standard", "blitz", "siege")
+    #[wasm_bindgen(constructor)]
+    pub fn new(preset: &str) -> Result<Predictor, JsError> {
+        let rules = CaptureRules::preset(preset).ok_or_else(|| JsError::new(&format!("Unknown rules preset '{}'", preset)))?;
+        Ok(Self { state: GameState::new(), tracker: CaptureTracker::new(), rules })
+    }
+
+    /// Predictor with explicit capture thresholds (a regular node's - capitals and
+    /// neutral nodes scale them as in the presets)
+    /// `max_capture_multiplier` caps the combined-arms speed-up (default: standard's)
+    #[wasm_bindgen(js_name = withRules)]
+    pub fn with_rules(overload_threshold: f32, overload_duration_secs: u32, max_capture_multiplier: Option<f32>) -> Predictor {
+        Self {
+            state: GameState::new(),
+            tracker: CaptureTracker::new(),
+            rules: CaptureRules {
+                overload_duration_secs: overload_duration_secs as u64,
+                overload_threshold,
+                max_capture_multiplier: max_capture_multiplier.unwrap_or(CaptureRules::STANDARD.max_capture_multiplier),
+                tiers: CaptureRules::STANDARD.tiers,
+            },
+        }
+    }
+
+    /// Apply one committed event (JSON, either envelope or legacy layout)
+    #[wasm_bindgen(js_name = applyEvent)]
+    pub fn apply_event(&mut self, event_json: &str) -> Result<(), JsError> {
+        let event: GameEvent = serde_json::from_str(event_json).map_err(|e| JsError::new(&e.to_string()))?;
+        self.apply(event);
+        Ok(())
+    }
+
+    /// Apply a batch of committed events, in log order
+    /// Accepts the body of GET /events or a bare JSON array
+    #[wasm_bindgen(js_name = applyEvents)]
+    pub fn apply_events(&mut self, events_json: &str) -> Result<usize, JsError> {
+        let events = match serde_json::from_str(events_json).map_err(|e| JsError::new(&e.to_string()))? {
+            EventBatch::Response { events } | EventBatch::List(events) => events,
+        };
+        let count = events.len();
+        for event in events {
+            self.apply(event);
+        }
+        Ok(count)
+    }
+
+    /// Run the capture rule at `now` (unix seconds)
+    /// Returns JSON `[{coord: {q, r}, new_owner_id}]` - captures the leader should commit now.
+    /// Nothing is applied locally; the committed NodeCaptured event does that.
+    pub fn tick(&mut self, now: f64) -> String {
+        let captures: Vec<PredictedCapture> = self
+            .tracker
+            .tick(&self.rules, &self.state, now as u64)
+            .into_iter()
+            .filter_map(|capture| match capture {
+                FactEvent::NodeCaptured { node_coord, new_owner_id, .. } => Some(PredictedCapture {
+                    coord: node_coord,
+                    new_owner_id,
+                }),
+                _ => None,
+            })
+            .collect();
+        serde_json::to_string(&captures).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Capture progress of a hex at `now` (0.0 to 1.0), undefined if it isn't overloaded
+    #[wasm_bindgen(js_name = captureProgress)]
+    pub fn capture_progress(&self, q: i32, r: i32, now: f64) -> Option<f32> {
+        self.tracker.progress(&self.rules, &self.state, &NodeCoord::new(q, r), now as u64)
+    }
+
+    /// Combined-arms multiplier on a hex's capture timer (1.0 = no boost)
+    #[wasm_bindgen(js_name = captureMultiplier)]
+    pub fn capture_multiplier(&self, q: i32, r: i32) -> f32 {
+        self.tracker.multiplier(&NodeCoord::new(q, r))
+    }
+
+    /// Player on course to capture a hex, undefined if none
+    #[wasm_bindgen(js_name = capturingPlayer)]
+    pub fn capturing_player(&self, q: i32, r: i32) -> Option<f64> {
+        self.tracker.capturing_player(&NodeCoord::new(q, r)).map(|id| id as f64)
+    }
+
+    /// Why `player_id` can't attack (to_q, to_r) from (from_q, from_r) at `now`, undefined if it can
+    #[wasm_bindgen(js_name = validateAttack)]
+    pub fn validate_attack(&self, player_id: f64, from_q: i32, from_r: i32, to_q: i32, to_r: i32, now: f64) -> Option<String> {
+        self.state
+            .validate_attack(Some(player_id as u64), NodeCoord::new(from_q, from_r), NodeCoord::new(to_q, to_r), now as u64)
+            .err()
+            .map(|e| e.to_string())
+    }
+
+    /// Seconds of spawn protection left on the capital at (q, r), undefined if none
+    #[wasm_bindgen(js_name = spawnProtection)]
+    pub fn spawn_protection(&self, q: i32, r: i32, now: f64) -> Option<f64> {
+        self.state.spawn_protection(NodeCoord::new(q, r), now as u64).map(|secs| secs as f64)
+    }
+
+    /// Capacity multiplier of the terrain at (q, r): 1 plain, 1.5 highland, 0.75 lowland, 0 chasm
+    #[wasm_bindgen(js_name = terrainMultiplier)]
+    pub fn terrain_multiplier(&self, q: i32, r: i32) -> f32 {
+        self.state.terrain_at(NodeCoord::new(q, r)).capacity_multiplier()
+    }
+
+    /// Owner of a hex, undefined if there's no node
+    pub fn owner(&self, q: i32, r: i32) -> Option<f64> {
+        self.state.nodes.get(&NodeCoord::new(q, r)).map(|n| n.owner_id as f64)
+    }
+
+    /// Log index of the last applied event
+    #[wasm_bindgen(js_name = lastAppliedIndex)]
+    pub fn last_applied_index(&self) -> f64 {
+        self.state.last_applied_log_index as f64
+    }
+
+    #[wasm_bindgen(js_name = gameOver)]
+    pub fn game_over(&self) -> bool {
+        self.state.game_over
+    }
+
+    /// "lobby", "live", "sudden_death" or "finished" - sudden death halves capture times
+    pub fn phase(&self) -> String {
+        self.state.phase.name().to_string()
+    }
+
+    /// Whether the game runs the feature called `name` (e.g. "fog_of_war")
+    #[wasm_bindgen(js_name = hasFeature)]
+    pub fn has_feature(&self, name: &str) -> bool {
+        Feature::from_name(name).is_some_and(|f| self.state.features().contains(f))
+    }
+}
+
+impl Predictor {
+    fn apply(&mut self, event: GameEvent) {
+        let index = self.state.last_applied_log_index + 1;
+        self.state.process_event(event, index);
+    }
+}
+
+// ============= Grid Math =============
+// Coordinates cross as flat [q0, r0, q1, r1, ...] arrays
+
+fn flatten(coords: impl IntoIterator<Item = NodeCoord>) -> Vec<i32> {
+    coords.into_iter().flat_map(|c| [c.q, c.r]).collect()
+}
+
+#[wasm_bindgen(js_name = hexDistance)]
+pub fn hex_distance(q1: i32, r1: i32, q2: i32, r2: i32) -> u32 {
+    NodeCoord::new(q1, r1).distance(&NodeCoord::new(q2, r2))
+}
+
+#[wasm_bindgen(js_name = isAdjacent)]
+pub fn is_adjacent(q1: i32, r1: i32, q2: i32, r2: i32) -> bool {
+    NodeCoord::new(q1, r1).is_adjacent(&NodeCoord::new(q2, r2))
+}
+
+#[wasm_bindgen(js_name = hexNeighbors)]
+pub fn hex_neighbors(q: i32, r: i32) -> Vec<i32> {
+    flatten(NodeCoord::new(q, r).neighbors())
+}
+
+#[wasm_bindgen(js_name = hexRing)]
+pub fn hex_ring(q: i32, r: i32, radius: u32) -> Vec<i32> {
+    flatten(NodeCoord::new(q, r).ring(radius))
+}
+
+#[wasm_bindgen(js_name = hexLine)]
+pub fn hex_line(q1: i32, r1: i32, q2: i32, r2: i32) -> Vec<i32> {
+    flatten(NodeCoord::new(q1, r1).line_to(&NodeCoord::new(q2, r2)))
+}
+
+/// Pixel centre `[x, y]` (pointy-top, same as hexUtils.hexToPixel)
+#[wasm_bindgen(js_name = hexToPixel)]
+pub fn hex_to_pixel(q: i32, r: i32, size: f64) -> Vec<f64> {
+    let (x, y) = NodeCoord::new(q, r).to_pixel(size);
+    vec![x, y]
+}
+
+/// Hex `[q, r]` containing a pixel
+#[wasm_bindgen(js_name = pixelToHex)]
+pub fn pixel_to_hex(x: f64, y: f64, size: f64) -> Vec<i32> {
+    let c = NodeCoord::from_pixel(x, y, size);
+    vec![c.q, c.r]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_and_predict_capture() {
+        let events = serde_json::json!({
+            "events": [
+                { "PlayerJoin": { "player_id": 1, "name": "Alice", "capital_coord": { "q": 0, "r": 0 },
+                                  "node_ip": "10.0.0.1", "is_client": false, "timestamp": 0 } },
+                { "PlayerJoin": { "player_id": 2, "name": "Bob", "capital_coord": { "q": 1, "r": 0 },
+                                  "node_ip": "10.0.0.2", "is_client": false, "timestamp": 0 } },
+                { "SetNodeTarget": { "node_coord": { "q": 1, "r": 0 },
+                                     "target": { "Coordinate": { "q": 0, "r": 0 } }, "timestamp": 0 } },
+                { "NodeMetricsReport": { "node_coord": { "q": 0, "r": 0 }, "bandwidth_in": 1000,
+                                         "packet_loss": 0.9, "timestamp": 0 } }
+            ],
+            "count": 4
+        });
+
+        let mut predictor = Predictor::new("blitz").unwrap();
+        assert_eq!(predictor.apply_events(&events.to_string()).unwrap(), 4);
+        assert_eq!(predictor.last_applied_index(), 4.0);
+        assert_eq!(predictor.owner(0, 0), Some(1.0));
+
+        assert_eq!(predictor.validate_attack(2.0, 1, 0, 0, 0, 100.0), None);
+        assert!(predictor.validate_attack(1.0, 1, 0, 0, 0, 100.0).is_some());
+        // Fresh capitals are spawn-protected
+        assert!(predictor.validate_attack(2.0, 1, 0, 0, 0, 10.0).is_some());
+        assert_eq!(predictor.spawn_protection(0, 0, 10.0), Some(50.0));
+
+        // Blitz captures after 2s of overload - 4s for a capital
+        assert_eq!(predictor.tick(100.0), "[]");
+        assert_eq!(predictor.capture_progress(0, 0, 102.0), Some(0.5));
+        assert_eq!(predictor.capturing_player(0, 0), Some(2.0));
+        assert_eq!(predictor.tick(103.0), "[]");
+        assert_eq!(predictor.tick(104.0), r#"[{"coord":{"q":0,"r":0},"new_owner_id":2}]"#);
+    }
+}
@@ -0,0 +1,724 @@
+use crate::events::{AttackTarget, FactEvent, GamePhase, NodeCoord, NodeType};
+use crate::state::{scale_secs, CaptureBoost, GameState, Node};
+use crate::terrain::Terrain;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// Named rule sets, shown in the game browser
+pub const RULES_PRESETS: [&str; 3] = ["standard", "blitz", "siege"];
+
+/// Capture thresholds - the part of the game config that decides captures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureRules {
+    /// How long a node must be overloaded before it's captured (seconds)
+    pub overload_duration_secs: u64,
+    /// Packet loss threshold to consider a node overloaded (0.0 to 1.0)
+    pub overload_threshold: f32,
+    /// Cap on the combined-arms speed-up (1.0 = attackers don't stack)
+    pub max_capture_multiplier: f32,
+    /// How the thresholds above (a regular node's) scale for other capacity tiers
+    pub tiers: TierScaling,
+}
+
+/// Capacity tier of a capture target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapacityTier {
+    /// Neutral nodes, on the smallest tasks
+    Npc,
+    Regular,
+    /// Capitals, on the larger task size
+    Capital,
+}
+
+impl CapacityTier {
+    /// A neutral node is an NPC whatever its type
+    pub fn of(node: &Node) -> Self {
+        if node.owner_id == 0 {
+            return CapacityTier::Npc;
+        }
+        match node.node_type {
+            NodeType::Capital => CapacityTier::Capital,
+            NodeType::Regular => CapacityTier::Regular,
+        }
+    }
+}
+
+/// Multipliers on a regular node's overload threshold and duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TierScale {
+    pub threshold: f32,
+    pub duration: f32,
+}
+
+impl TierScale {
+    pub const NONE: Self = Self { threshold: 1.0, duration: 1.0 };
+}
+
+/// Per-tier scaling of the capture thresholds (regular nodes are the baseline)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TierScaling {
+    pub npc: TierScale,
+    pub capital: TierScale,
+}
+
+impl TierScaling {
+    /// NPCs fold at lower loss and sooner, capitals need more loss for longer
+    pub const DEFAULT: Self = Self {
+        npc: TierScale { threshold: 0.75, duration: 0.5 },
+        capital: TierScale { threshold: 1.5, duration: 2.0 },
+    };
+    /// Every tier falls the same way
+    pub const FLAT: Self = Self { npc: TierScale::NONE, capital: TierScale::NONE };
+
+    pub fn scale(&self, tier: CapacityTier) -> TierScale {
+        match tier {
+            CapacityTier::Npc => self.npc,
+            CapacityTier::Regular => TierScale::NONE,
+            CapacityTier::Capital => self.capital,
+        }
+    }
+}
+
+impl Default for TierScaling {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Default for CaptureRules {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl CaptureRules {
+    pub const STANDARD: Self = Self {
+        overload_duration_secs: 5,
+        overload_threshold: 0.2, // 20% packet loss
+        max_capture_multiplier: 3.0,
+        tiers: TierScaling::DEFAULT,
+    };
+    /// Fast captures
+    pub const BLITZ: Self = Self {
+        overload_duration_secs: 2,
+        overload_threshold: 0.15,
+        max_capture_multiplier: 2.0,
+        tiers: TierScaling::DEFAULT,
+    };
+    /// Nodes hold out longer, coordinated sieges pay off most
+    pub const SIEGE: Self = Self {
+        overload_duration_secs: 10,
+        overload_threshold: 0.3,
+        max_capture_multiplier: 4.0,
+        tiers: TierScaling::DEFAULT,
+    };
+
+    /// Capture rules of a named preset (case-insensitive, empty = standard)
+    pub fn preset(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("standard") {
+            Some(Self::STANDARD)
+        } else if name.eq_ignore_ascii_case("blitz") {
+            Some(Self::BLITZ)
+        } else if name.eq_ignore_ascii_case("siege") {
+            Some(Self::SIEGE)
+        } else {
+            None
+        }
+    }
+
+    /// These rules for a target standing on `terrain`
+    /// High ground stretches the overload duration, low ground shortens it.
+    pub fn on_terrain(&self, terrain: Terrain) -> Self {
+        let multiplier = terrain.capacity_multiplier();
+        if multiplier <= 0.0 || self.overload_duration_secs == 0 {
+            return *self;
+        }
+        let secs = libm::ceilf(self.overload_duration_secs as f32 * multiplier) as u64;
+        Self { overload_duration_secs: secs.max(1), ..*self }
+    }
+
+    /// These rules for a target of capacity `tier`
+    /// The threshold never goes past 100% loss; a non-zero duration stays at least 1s.
+    pub fn for_tier(&self, tier: CapacityTier) -> Self {
+        let scale = self.tiers.scale(tier);
+        let secs = if self.overload_duration_secs == 0 {
+            0
+        } else {
+            (libm::ceilf(self.overload_duration_secs as f32 * scale.duration.max(0.0)) as u64).max(1)
+        };
+        Self {
+            overload_duration_secs: secs,
+            overload_threshold: (self.overload_threshold * scale.threshold.max(0.0)).min(1.0),
+            ..*self
+        }
+    }
+
+    /// These rules at `time_scale`x game speed: the overload duration shrinks, the threshold doesn't
+    pub fn at_time_scale(&self, time_scale: u32) -> Self {
+        Self { overload_duration_secs: scale_secs(self.overload_duration_secs, time_scale), ..*self }
+    }
+
+    /// These rules in game `phase`: sudden death halves the overload duration (at least 1s)
+    pub fn in_phase(&self, phase: GamePhase) -> Self {
+        if phase != GamePhase::SuddenDeath || self.overload_duration_secs == 0 {
+            return *self;
+        }
+        Self { overload_duration_secs: self.overload_duration_secs.div_ceil(2), ..*self }
+    }
+
+    /// These rules for `node`: scaled by its capacity tier, then by its terrain, the
+    /// game's phase and its speed
+    pub fn for_target(&self, state: &GameState, node: &Node) -> Self {
+        self.for_tier(CapacityTier::of(node))
+            .on_terrain(state.terrain_at(node.coord))
+            .in_phase(state.phase)
+            .at_time_scale(state.time_scale)
+    }
+
+    /// Whether this packet loss counts as overloaded
+    pub fn is_overloaded(&self, packet_loss: f32) -> bool {
+        packet_loss >= self.overload_threshold
+    }
+
+    /// Combined-arms multiplier for attackers with these capacities
+    /// Total capacity relative to the strongest single attacker, so one node alone is 1.0
+    pub fn capture_multiplier(&self, capacities: &[f32]) -> f32 {
+        let strongest = capacities.iter().copied().fold(0.0, f32::max);
+        if strongest <= 0.0 {
+            return 1.0;
+        }
+        let total: f32 = capacities.iter().sum();
+        (total / strongest).clamp(1.0, self.max_capture_multiplier.max(1.0))
+    }
+
+    /// Overload time needed to capture at `multiplier` (at least 1s unless captures are instant)
+    pub fn required_duration_secs(&self, multiplier: f32) -> u64 {
+        if self.overload_duration_secs == 0 {
+            return 0;
+        }
+        let secs = libm::ceilf(self.overload_duration_secs as f32 / multiplier.max(1.0)) as u64;
+        secs.max(1)
+    }
+
+    /// Fraction of the (boosted) overload duration served, 0.0 to 1.0
+    pub fn progress(&self, overloaded_for_secs: u64, multiplier: f32) -> f32 {
+        let required = self.required_duration_secs(multiplier);
+        if required == 0 {
+            return 1.0;
+        }
+        (overloaded_for_secs as f32 / required as f32).min(1.0)
+    }
+}
+
+/// Attack capacity a node brings to combined arms - capitals run on larger tasks
+pub fn attack_capacity(node_type: NodeType) -> f32 {
+    match node_type {
+        NodeType::Capital => 2.0,
+        NodeType::Regular => 1.0,
+    }
+}
+
+/// Tracks how long each attacked node has been overloaded
+///
+/// The leader runs this to decide captures; the browser runs the same code on
+/// replicated state to predict them and draw capture progress.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureTracker {
+    /// target_coord -> (attacker_id, overload_start_time)
+    overload_start_times: BTreeMap<NodeCoord, (u64, u64)>,
+    /// target_coord -> combined-arms boost last reported (only while > 1.0)
+    boosts: BTreeMap<NodeCoord, CaptureBoost>,
+}
+
+impl CaptureTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A tracker for a newly elected leader: no overload timed yet, but it knows the
+    /// boosts the previous leader committed, so it ends them like its own
+    pub fn resume(state: &GameState) -> Self {
+        Self { overload_start_times: BTreeMap::new(), boosts: state.capture_boosts.clone() }
+    }
+
+    /// Update overload tracking at `now` (unix seconds) and return the facts that are due:
+    /// captures, plus CaptureBoostChanged whenever a target's combined-arms boost changes
+    pub fn tick(&mut self, rules: &CaptureRules, state: &GameState, now: u64) -> Vec<FactEvent> {
+        let mut facts = Vec::new();
+
+        // Check all nodes that are being attacked
+        for node in state.nodes.values() {
+            let target_coord = node.coord;
+            let rules = &rules.for_target(state, node);
+
+            // A spawn-protected capital shrugs off any overload
+            if state.spawn_protection(target_coord, now).is_some() {
+                self.forget(rules, target_coord, now, &mut facts);
+                continue;
+            }
+
+            // Find who's attacking this node (only grid coordinate attacks)
+            // Multiple attackers: only first attacker's owner captures; that owner's
+            // other adjacent attackers speed the capture up (combined arms)
+            let attacker = state
+                .nodes
+                .values()
+                .find(|n| n.current_target == Some(AttackTarget::Coordinate(target_coord)));
+
+            let Some(attacker) = attacker else {
+                // No one attacking, clear any tracking
+                self.forget(rules, target_coord, now, &mut facts);
+                continue;
+            };
+
+            // Check if node is overloaded
+            if let Some(metrics) = state.node_metrics.get(&target_coord) {
+                if rules.is_overloaded(metrics.packet_loss) {
+                    // Track when overload started
+                    let (tracked_attacker, overload_start) = *self
+                        .overload_start_times
+                        .entry(target_coord)
+                        .or_insert((attacker.owner_id, now));
+
+                    let boost = combined_arms(rules, state, target_coord, tracked_attacker);
+                    self.report_boost(target_coord, boost, now, &mut facts);
+
+                    // Check if sustained long enough
+                    if now.saturating_sub(overload_start) >= boost.required_secs {
+                        facts.push(FactEvent::NodeCaptured {
+                            node_coord: target_coord,
+                            new_owner_id: tracked_attacker,
+                            timestamp: now,
+                        });
+
+                        // Clear tracking after capture (NodeCaptured clears the boost in state)
+                        self.overload_start_times.remove(&target_coord);
+                        self.boosts.remove(&target_coord);
+                    }
+                } else {
+                    // Not overloaded anymore, reset tracking
+                    self.forget(rules, target_coord, now, &mut facts);
+                }
+            }
+        }
+
+        facts
+    }
+
+    /// Emit CaptureBoostChanged if `boost` differs from what was last reported
+    fn report_boost(&mut self, coord: NodeCoord, boost: CaptureBoost, now: u64, facts: &mut Vec<FactEvent>) {
+        let previous = self.boosts.get(&coord).copied();
+        if boost.multiplier > 1.0 {
+            if previous == Some(boost) {
+                return;
+            }
+            self.boosts.insert(coord, boost);
+        } else if previous.is_some() {
+            self.boosts.remove(&coord);
+        } else {
+            return;
+        }
+
+        facts.push(FactEvent::CaptureBoostChanged {
+            node_coord: coord,
+            attacker_id: boost.attacker_id,
+            attackers: boost.attackers,
+            multiplier: boost.multiplier,
+            required_secs: boost.required_secs,
+            timestamp: now,
+        });
+    }
+
+    /// Stop tracking `coord`, reporting the end of any boost
+    fn forget(&mut self, rules: &CaptureRules, coord: NodeCoord, now: u64, facts: &mut Vec<FactEvent>) {
+        self.overload_start_times.remove(&coord);
+        if let Some(previous) = self.boosts.get(&coord).copied() {
+            let reset = CaptureBoost {
+                attacker_id: previous.attacker_id,
+                attackers: 0,
+                multiplier: 1.0,
+                required_secs: rules.required_duration_secs(1.0),
+            };
+            self.report_boost(coord, reset, now, facts);
+        }
+    }
+
+    /// Capture progress of `coord` at `now` (0.0 to 1.0), None if it isn't overloaded
+    pub fn progress(&self, rules: &CaptureRules, state: &GameState, coord: &NodeCoord, now: u64) -> Option<f32> {
+        let rules = match state.nodes.get(coord) {
+            Some(node) => rules.for_target(state, node),
+            None => rules.on_terrain(state.terrain_at(*coord)).at_time_scale(state.time_scale),
+        };
+        self.overload_start_times
+            .get(coord)
+            .map(|(_, start)| rules.progress(now.saturating_sub(*start), self.multiplier(coord)))
+    }
+
+    /// Player currently on course to capture `coord`
+    pub fn capturing_player(&self, coord: &NodeCoord) -> Option<u64> {
+        self.overload_start_times.get(coord).map(|(attacker, _)| *attacker)
+    }
+
+    /// Combined-arms multiplier applied to `coord`'s capture timer (1.0 if none)
+    pub fn multiplier(&self, coord: &NodeCoord) -> f32 {
+        self.boosts.get(coord).map_or(1.0, |boost| boost.multiplier)
+    }
+}
+
+/// Combined-arms boost `player_id` gets on `target` from its adjacent attackers
+fn combined_arms(rules: &CaptureRules, state: &GameState, target: NodeCoord, player_id: u64) -> CaptureBoost {
+    let capacities: Vec<f32> = state
+        .nodes
+        .values()
+        .filter(|n| {
+            n.owner_id == player_id
+                && n.current_target == Some(AttackTarget::Coordinate(target))
+                && n.coord.is_adjacent(&target)
+        })
+        .map(|n| attack_capacity(n.node_type) * state.terrain_at(n.coord).capacity_multiplier())
+        .collect();
+
+    let multiplier = rules.capture_multiplier(&capacities);
+    CaptureBoost {
+        attacker_id: player_id,
+        attackers: capacities.len() as u32,
+        multiplier,
+        required_secs: rules.required_duration_secs(multiplier),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{CommandEvent, GameEvent, TelemetryEvent};
+    use crate::terrain::TerrainTile;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn join(state: &mut GameState, player_id: u64, q: i32, index: u64) {
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id,
+                name: "Player".to_string(),
+                capital_coord: NodeCoord::new(q, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 0,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            index,
+        );
+    }
+
+    #[test]
+    fn test_capture_after_sustained_overload() {
+        let rules = CaptureRules { overload_duration_secs: 5, overload_threshold: 0.5, max_capture_multiplier: 3.0, tiers: TierScaling::FLAT };
+        let mut state = GameState::new();
+        join(&mut state, 1, 0, 1);
+        join(&mut state, 2, 1, 2);
+
+        // Bob attacks Alice's capital, which reports heavy loss
+        state.process_event(
+            GameEvent::Command(CommandEvent::SetNodeTarget {
+                node_coord: NodeCoord::new(1, 0),
+                target: Some(AttackTarget::Coordinate(NodeCoord::new(0, 0))),
+                timestamp: 0,
+            }),
+            3,
+        );
+        state.process_event(
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: NodeCoord::new(0, 0),
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: vec![],
+            }),
+            4,
+        );
+
+        // Alice's capital is still spawn-protected at 30: the overload doesn't count
+        let mut tracker = CaptureTracker::new();
+        assert!(tracker.tick(&rules, &state, 30).is_empty());
+        assert_eq!(tracker.capturing_player(&NodeCoord::new(0, 0)), None);
+
+        assert!(tracker.tick(&rules, &state, 100).is_empty());
+        assert_eq!(tracker.progress(&rules, &state, &NodeCoord::new(0, 0), 102), Some(0.4));
+        assert_eq!(tracker.capturing_player(&NodeCoord::new(0, 0)), Some(2));
+
+        let captures = tracker.tick(&rules, &state, 105);
+        assert!(matches!(
+            captures.as_slice(),
+            [FactEvent::NodeCaptured { new_owner_id: 2, timestamp: 105, .. }]
+        ));
+        assert_eq!(tracker.progress(&rules, &state, &NodeCoord::new(0, 0), 105), None);
+    }
+
+    #[test]
+    fn test_time_scale_shortens_captures() {
+        let rules = CaptureRules { overload_duration_secs: 5, overload_threshold: 0.5, max_capture_multiplier: 3.0, tiers: TierScaling::FLAT };
+        let mut state = GameState::new();
+        state.process_event(GameEvent::Fact(FactEvent::TimeScaleSet { scale: 10, timestamp: 0 }), 1);
+        join(&mut state, 1, 0, 2);
+        join(&mut state, 2, 1, 3);
+        state.process_event(
+            GameEvent::Command(CommandEvent::SetNodeTarget {
+                node_coord: NodeCoord::new(1, 0),
+                target: Some(AttackTarget::Coordinate(NodeCoord::new(0, 0))),
+                timestamp: 0,
+            }),
+            4,
+        );
+        state.process_event(
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: NodeCoord::new(0, 0),
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: vec![],
+            }),
+            5,
+        );
+
+        // Spawn protection is down to 6s and the 5s overload to 1s
+        let mut tracker = CaptureTracker::new();
+        assert!(tracker.tick(&rules, &state, 10).is_empty());
+        assert_eq!(tracker.progress(&rules, &state, &NodeCoord::new(0, 0), 10), Some(0.0));
+        let captures = tracker.tick(&rules, &state, 11);
+        assert!(matches!(captures.as_slice(), [FactEvent::NodeCaptured { new_owner_id: 2, .. }]));
+    }
+
+    fn target(state: &mut GameState, from: NodeCoord, to: Option<NodeCoord>, index: u64) {
+        state.process_event(
+            GameEvent::Command(CommandEvent::SetNodeTarget {
+                node_coord: from,
+                target: to.map(AttackTarget::Coordinate),
+                timestamp: 0,
+            }),
+            index,
+        );
+    }
+
+    #[test]
+    fn test_combined_arms_speeds_up_capture() {
+        let rules = CaptureRules { overload_duration_secs: 5, overload_threshold: 0.5, max_capture_multiplier: 3.0, tiers: TierScaling::FLAT };
+        let mut state = GameState::new();
+        join(&mut state, 1, 0, 1);
+        join(&mut state, 2, 1, 2);
+
+        // Bob's capital plus two regular nodes, all adjacent to Alice's capital
+        let alice = NodeCoord::new(0, 0);
+        let allies = [NodeCoord::new(0, 1), NodeCoord::new(1, -1)];
+        for (i, coord) in allies.iter().enumerate() {
+            state.process_event(
+                GameEvent::Fact(FactEvent::NodeInitializationStarted { node_coord: *coord, owner_id: 2, timestamp: 0 }),
+                3 + i as u64,
+            );
+        }
+        target(&mut state, NodeCoord::new(1, 0), Some(alice), 5);
+        target(&mut state, allies[0], Some(alice), 6);
+        target(&mut state, allies[1], Some(alice), 7);
+        state.process_event(
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: alice,
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: vec![],
+            }),
+            8,
+        );
+
+        // Capacity 2 + 1 + 1 against a strongest attacker of 2: twice as fast, 3s instead of 5s
+        let mut tracker = CaptureTracker::new();
+        let facts = tracker.tick(&rules, &state, 100);
+        assert!(matches!(
+            facts.as_slice(),
+            [FactEvent::CaptureBoostChanged { attacker_id: 2, attackers: 3, multiplier, required_secs: 3, .. }] if *multiplier == 2.0
+        ));
+        assert_eq!(tracker.multiplier(&alice), 2.0);
+        assert!(tracker.tick(&rules, &state, 101).is_empty(), "unchanged boost isn't re-reported");
+
+        // The allies pull out: the boost is reported gone and the normal timer applies
+        target(&mut state, allies[0], None, 9);
+        target(&mut state, allies[1], None, 10);
+        let facts = tracker.tick(&rules, &state, 103);
+        assert!(matches!(facts.as_slice(), [FactEvent::CaptureBoostChanged { multiplier, .. }] if *multiplier == 1.0));
+        assert_eq!(tracker.progress(&rules, &state, &alice, 103), Some(0.6));
+
+        let facts = tracker.tick(&rules, &state, 105);
+        assert!(matches!(facts.as_slice(), [FactEvent::NodeCaptured { new_owner_id: 2, .. }]));
+    }
+
+    #[test]
+    fn test_new_leader_ends_committed_boosts() {
+        let rules = CaptureRules { overload_duration_secs: 5, overload_threshold: 0.5, max_capture_multiplier: 3.0, tiers: TierScaling::FLAT };
+        let mut state = GameState::new();
+        join(&mut state, 1, 0, 1);
+        join(&mut state, 2, 1, 2);
+        let alice = NodeCoord::new(0, 0);
+        let ally = NodeCoord::new(0, 1);
+        state.process_event(GameEvent::Fact(FactEvent::NodeInitializationStarted { node_coord: ally, owner_id: 2, timestamp: 0 }), 3);
+        target(&mut state, NodeCoord::new(1, 0), Some(alice), 4);
+        target(&mut state, ally, Some(alice), 5);
+        state.process_event(
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: alice,
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: vec![],
+            }),
+            6,
+        );
+
+        // The old leader commits the boost, then leadership moves and the ally pulls out
+        let facts = CaptureTracker::new().tick(&rules, &state, 100);
+        assert_eq!(facts.len(), 1);
+        state.process_event(GameEvent::Fact(facts[0].clone()), 7);
+        assert!(state.capture_boosts.contains_key(&alice));
+        target(&mut state, ally, None, 8);
+
+        // A blank tracker never ends it; one resumed from the log does
+        assert!(CaptureTracker::new().tick(&rules, &state, 101).is_empty());
+        let facts = CaptureTracker::resume(&state).tick(&rules, &state, 101);
+        assert!(matches!(facts.as_slice(), [FactEvent::CaptureBoostChanged { multiplier, .. }] if *multiplier == 1.0));
+        state.process_event(GameEvent::Fact(facts[0].clone()), 9);
+        assert!(state.capture_boosts.is_empty());
+    }
+
+    #[test]
+    fn test_terrain_scales_capture() {
+        let rules = CaptureRules { overload_duration_secs: 4, overload_threshold: 0.5, max_capture_multiplier: 3.0, tiers: TierScaling::FLAT };
+        assert_eq!(rules.on_terrain(Terrain::Highland).overload_duration_secs, 6);
+        assert_eq!(rules.on_terrain(Terrain::Lowland).overload_duration_secs, 3);
+        assert_eq!(rules.on_terrain(Terrain::Plain), rules);
+
+        let mut state = GameState::new();
+        join(&mut state, 1, 0, 1);
+        join(&mut state, 2, 1, 2);
+        let alice = NodeCoord::new(0, 0);
+        let tiles = vec![TerrainTile { coord: alice, terrain: Terrain::Highland }];
+        state.process_event(GameEvent::Fact(FactEvent::MapGenerated { seed: 7, radius: 3, tiles, timestamp: 0 }), 3);
+        target(&mut state, NodeCoord::new(1, 0), Some(alice), 4);
+        state.process_event(
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: alice,
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: vec![],
+            }),
+            5,
+        );
+
+        // Alice's capital sits on high ground: 6s instead of 4s
+        let mut tracker = CaptureTracker::new();
+        assert!(tracker.tick(&rules, &state, 100).is_empty());
+        assert_eq!(tracker.progress(&rules, &state, &alice, 103), Some(0.5));
+        assert!(tracker.tick(&rules, &state, 105).is_empty());
+        assert!(matches!(tracker.tick(&rules, &state, 106).as_slice(), [FactEvent::NodeCaptured { .. }]));
+    }
+
+    #[test]
+    fn test_sudden_death_halves_captures() {
+        let rules = CaptureRules { overload_duration_secs: 5, overload_threshold: 0.5, max_capture_multiplier: 3.0, tiers: TierScaling::FLAT };
+        assert_eq!(rules.in_phase(GamePhase::SuddenDeath).overload_duration_secs, 3);
+        assert_eq!(rules.in_phase(GamePhase::Live), rules);
+        let instant = CaptureRules { overload_duration_secs: 0, ..rules };
+        assert_eq!(instant.in_phase(GamePhase::SuddenDeath), instant);
+
+        let mut state = GameState::new();
+        join(&mut state, 1, 0, 1);
+        join(&mut state, 2, 1, 2);
+        state.process_event(GameEvent::Fact(FactEvent::PhaseChanged { phase: GamePhase::SuddenDeath, timestamp: 0 }), 3);
+        let alice = NodeCoord::new(0, 0);
+        target(&mut state, NodeCoord::new(1, 0), Some(alice), 4);
+        state.process_event(
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: alice,
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: vec![],
+            }),
+            5,
+        );
+
+        // 3s instead of 5s
+        let mut tracker = CaptureTracker::new();
+        assert!(tracker.tick(&rules, &state, 100).is_empty());
+        assert!(tracker.tick(&rules, &state, 102).is_empty());
+        assert!(matches!(tracker.tick(&rules, &state, 103).as_slice(), [FactEvent::NodeCaptured { .. }]));
+    }
+
+    #[test]
+    fn test_capacity_tiers_scale_capture() {
+        let rules = CaptureRules::STANDARD;
+        assert_eq!(rules.for_tier(CapacityTier::Regular), rules);
+        let npc = rules.for_tier(CapacityTier::Npc);
+        assert!((npc.overload_threshold - 0.15).abs() < 1e-6);
+        assert_eq!(npc.overload_duration_secs, 3);
+        let capital = rules.for_tier(CapacityTier::Capital);
+        assert!((capital.overload_threshold - 0.3).abs() < 1e-6);
+        assert_eq!(capital.overload_duration_secs, 10);
+        let flat = CaptureRules { tiers: TierScaling::FLAT, ..rules };
+        assert_eq!(flat.for_tier(CapacityTier::Capital), flat);
+
+        // Alice's capital shrugs off 25% loss that would topple a regular node
+        let mut state = GameState::new();
+        join(&mut state, 1, 0, 1);
+        join(&mut state, 2, 1, 2);
+        let alice = NodeCoord::new(0, 0);
+        target(&mut state, NodeCoord::new(1, 0), Some(alice), 3);
+        let report = |loss| {
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord: alice,
+                bandwidth_in: 1000,
+                packet_loss: loss,
+                timestamp: 0,
+                queued_attackers: vec![],
+            })
+        };
+        state.process_event(report(0.25), 4);
+        let mut tracker = CaptureTracker::new();
+        assert!(tracker.tick(&rules, &state, 100).is_empty());
+        assert_eq!(tracker.progress(&rules, &state, &alice, 105), None);
+
+        // At 40% it falls, after 10s instead of 5s
+        state.process_event(report(0.4), 5);
+        assert!(tracker.tick(&rules, &state, 200).is_empty());
+        assert_eq!(tracker.progress(&rules, &state, &alice, 205), Some(0.5));
+        assert!(tracker.tick(&rules, &state, 209).is_empty());
+        assert!(matches!(tracker.tick(&rules, &state, 210).as_slice(), [FactEvent::NodeCaptured { .. }]));
+    }
+
+    #[test]
+    fn test_capture_multiplier_caps() {
+        let rules = CaptureRules::BLITZ;
+        assert_eq!(rules.capture_multiplier(&[]), 1.0);
+        assert_eq!(rules.capture_multiplier(&[2.0]), 1.0);
+        assert_eq!(rules.capture_multiplier(&[1.0, 1.0, 1.0, 1.0]), 2.0);
+        assert_eq!(rules.required_duration_secs(2.0), 1);
+        assert_eq!(CaptureRules::SIEGE.required_duration_secs(3.0), 4);
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(CaptureRules::preset(" Blitz "), Some(CaptureRules::BLITZ));
+        assert_eq!(CaptureRules::preset(""), Some(CaptureRules::STANDARD));
+        assert_eq!(CaptureRules::preset("capture-the-flag"), None);
+        assert!(RULES_PRESETS.iter().all(|name| CaptureRules::preset(name).is_some()));
+    }
+
+    #[test]
+    fn test_progress_caps_at_one() {
+        let rules = CaptureRules::default();
+        assert_eq!(rules.progress(0, 1.0), 0.0);
+        assert_eq!(rules.progress(50, 1.0), 1.0);
+        assert!(rules.is_overloaded(0.2));
+        assert!(!rules.is_overloaded(0.19));
+    }
+}
@@ -0,0 +1,295 @@
+// NodeCoord text forms - "q,r" strings and the shapes requests may send
+//
+// A coordinate always serializes as `{"q": .., "r": ..}`. Reading JSON (or a query
+// string) also accepts "q,r" and `[q, r]`, and numbers given as strings, so the same
+// type works in bodies and query parameters. Binary formats (bincode in the Raft log
+// and snapshots) keep the plain struct layout.
+//
+// Requests that predate nested coordinates send separate halves (`q`/`r`,
+// `target_q`/`target_r`). `deserialize_fields` reads either form from a flattened
+// field, and `serialize_fields` writes the nested one.
+
+use crate::events::NodeCoord;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serializer};
+
+impl fmt::Display for NodeCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.q, self.r)
+    }
+}
+
+/// Why a "q,r" string isn't a coordinate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCoordError {
+    input: String,
+}
+
+impl fmt::Display for ParseCoordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid coordinate '{}', expected q,r", self.input)
+    }
+}
+
+impl FromStr for NodeCoord {
+    type Err = ParseCoordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCoordError { input: String::from(s) };
+        let (q, r) = s.split_once(',').ok_or_else(invalid)?;
+        let q = q.trim().parse().map_err(|_| invalid())?;
+        let r = r.trim().parse().map_err(|_| invalid())?;
+        Ok(NodeCoord::new(q, r))
+    }
+}
+
+const FIELDS: &[&str] = &["q", "r"];
+
+impl<'de> Deserialize<'de> for NodeCoord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(CoordVisitor)
+        } else {
+            deserializer.deserialize_struct("NodeCoord", FIELDS, CoordVisitor)
+        }
+    }
+}
+
+struct CoordVisitor;
+
+impl<'de> Visitor<'de> for CoordVisitor {
+    type Value = NodeCoord;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a coordinate as {\"q\", \"r\"}, \"q,r\" or [q, r]")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<NodeCoord, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NodeCoord, A::Error> {
+        let q = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let r = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(NodeCoord::new(q, r))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<NodeCoord, A::Error> {
+        let (mut q, mut r) = (None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "q" => q = Some(map.next_value::<Half>()?.0),
+                "r" => r = Some(map.next_value::<Half>()?.0),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let q = q.ok_or_else(|| de::Error::missing_field("q"))?;
+        let r = r.ok_or_else(|| de::Error::missing_field("r"))?;
+        Ok(NodeCoord::new(q, r))
+    }
+}
+
+/// One half of a coordinate: a number, or a number in a string (query parameters
+/// reach flattened fields as strings)
+struct Half(i32);
+
+impl<'de> Deserialize<'de> for Half {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HalfVisitor;
+
+        impl<'de> Visitor<'de> for HalfVisitor {
+            type Value = Half;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an integer")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Half, E> {
+                i32::try_from(v).map(Half).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Half, E> {
+                i32::try_from(v).map(Half).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Half, E> {
+                v.trim().parse().map(Half).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(HalfVisitor)
+    }
+}
+
+/// Read the coordinate called `name` from a flattened field: `name` in any form
+/// NodeCoord accepts, or its halves (`q`/`r` when `name` is "coord", otherwise
+/// `{name}_q`/`{name}_r`). None if neither is present
+pub fn deserialize_fields<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    name: &'static str,
+) -> Result<Option<NodeCoord>, D::Error> {
+    deserializer.deserialize_map(FieldsVisitor { name })
+}
+
+/// Write `coord` as the nested `name` field of the surrounding struct (nothing if None)
+pub fn serialize_fields<S: Serializer>(
+    coord: Option<&NodeCoord>,
+    name: &'static str,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut fields = serializer.serialize_struct("NodeCoord", coord.is_some() as usize)?;
+    if let Some(coord) = coord {
+        fields.serialize_field(name, coord)?;
+    }
+    fields.end()
+}
+
+struct FieldsVisitor {
+    name: &'static str,
+}
+
+impl FieldsVisitor {
+    /// Which half `key` names, if any
+    fn half(&self, key: &str) -> Option<char> {
+        let half = if self.name == "coord" {
+            key
+        } else {
+            key.strip_prefix(self.name)?.strip_prefix('_')?
+        };
+        match half {
+            "q" => Some('q'),
+            "r" => Some('r'),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for FieldsVisitor {
+    type Value = Option<NodeCoord>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a `{}` coordinate or its q/r halves", self.name)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Option<NodeCoord>, A::Error> {
+        let (mut whole, mut q, mut r) = (None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.name {
+                whole = Some(map.next_value::<NodeCoord>()?);
+                continue;
+            }
+            match self.half(&key) {
+                Some('q') => q = Some(map.next_value::<Half>()?.0),
+                Some(_) => r = Some(map.next_value::<Half>()?.0),
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        match (whole, q, r) {
+            (Some(coord), _, _) => Ok(Some(coord)),
+            (None, Some(q), Some(r)) => Ok(Some(NodeCoord::new(q, r))),
+            (None, None, None) => Ok(None),
+            (None, _, _) => Err(de::Error::custom(format!("`{}` needs both q and r", self.name))),
+        }
+    }
+}
+
+/// `#[serde(flatten, with = "camhack_rules::coord::fields")]` on a required `coord: NodeCoord`
+pub mod fields {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(coord: &NodeCoord, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_fields(Some(coord), "coord", serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NodeCoord, D::Error> {
+        deserialize_fields(deserializer, "coord")?.ok_or_else(|| de::Error::missing_field("coord"))
+    }
+}
+
+/// `#[serde(flatten, with = "camhack_rules::coord::optional_fields")]` on a `coord: Option<NodeCoord>`
+pub mod optional_fields {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(coord: &Option<NodeCoord>, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_fields(coord.as_ref(), "coord", serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NodeCoord>, D::Error> {
+        deserialize_fields(deserializer, "coord")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Request {
+        #[serde(default)]
+        game_id: Option<String>,
+        #[serde(flatten, with = "fields")]
+        coord: NodeCoord,
+    }
+
+    #[test]
+    fn test_display_and_parse() {
+        let coord = NodeCoord::new(3, -2);
+        assert_eq!(coord.to_string(), "3,-2");
+        assert_eq!("3,-2".parse::<NodeCoord>(), Ok(coord));
+        assert_eq!(" 3 , -2 ".parse::<NodeCoord>(), Ok(coord));
+        assert!("3".parse::<NodeCoord>().is_err());
+        assert!("3,x".parse::<NodeCoord>().is_err());
+    }
+
+    #[test]
+    fn test_json_accepts_every_shape() {
+        let coord = NodeCoord::new(1, -1);
+        for json in [r#"{"q":1,"r":-1}"#, r#""1,-1""#, "[1,-1]", r#"{"q":"1","r":"-1"}"#] {
+            assert_eq!(serde_json::from_str::<NodeCoord>(json).unwrap(), coord, "{}", json);
+        }
+        assert_eq!(serde_json::to_string(&coord).unwrap(), r#"{"q":1,"r":-1}"#);
+    }
+
+    #[test]
+    fn test_bincode_layout_unchanged() {
+        let coord = NodeCoord::new(-4, 7);
+        let bytes = bincode::serialize(&coord).unwrap();
+        assert_eq!(bytes, bincode::serialize(&(-4i32, 7i32)).unwrap());
+        assert_eq!(bincode::deserialize::<NodeCoord>(&bytes).unwrap(), coord);
+    }
+
+    #[test]
+    fn test_request_fields_flat_or_nested() {
+        for json in [r#"{"game_id":"g","q":2,"r":0}"#, r#"{"game_id":"g","coord":"2,0"}"#, r#"{"coord":{"q":2,"r":0}}"#] {
+            let request: Request = serde_json::from_str(json).unwrap();
+            assert_eq!(request.coord, NodeCoord::new(2, 0), "{}", json);
+        }
+
+        let request = Request { game_id: None, coord: NodeCoord::new(2, 0) };
+        assert_eq!(serde_json::to_string(&request).unwrap(), r#"{"game_id":null,"coord":{"q":2,"r":0}}"#);
+
+        assert!(serde_json::from_str::<Request>(r#"{"q":2}"#).is_err());
+        assert!(serde_json::from_str::<Request>(r#"{"game_id":"g"}"#).is_err());
+    }
+
+    #[test]
+    fn test_prefixed_halves() {
+        let json = serde_json::json!({ "target_q": 1, "target_r": 2, "node": "0,0" });
+        let target = deserialize_fields(&json, "target").unwrap();
+        let node = deserialize_fields(&json, "node").unwrap();
+        assert_eq!(target, Some(NodeCoord::new(1, 2)));
+        assert_eq!(node, Some(NodeCoord::new(0, 0)));
+        assert_eq!(deserialize_fields(&json, "from").unwrap(), None);
+    }
+}
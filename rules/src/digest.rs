@@ -0,0 +1,278 @@
+// Stable digest of a GameState, for spotting nodes whose state has diverged
+//
+// Two nodes that applied the same log prefix must hold the same state, so hashing it
+// at the same log index on each and comparing the results catches a divergence long
+// before anything visibly desyncs. The hash is FNV-1a over every committed field in
+// map order, with integers written little-endian, so it's the same on every
+// platform, build and process. Telemetry (`node_metrics`) is left out: workers no
+// longer commit it.
+
+use crate::events::{AttackTarget, Effect, EliminationPolicy, NodeCoord, NodeType};
+use crate::state::{GameState, NodeInitState};
+use crate::terrain::Terrain;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a over the values written to it
+struct StateHasher(u64);
+
+impl StateHasher {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.bytes(&[value as u8]);
+    }
+
+    /// Length first, so adjacent strings can't run together
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+
+    fn coord(&mut self, coord: NodeCoord) {
+        self.bytes(&coord.q.to_le_bytes());
+        self.bytes(&coord.r.to_le_bytes());
+    }
+
+    fn opt_u64(&mut self, value: Option<u64>) {
+        self.bool(value.is_some());
+        self.u64(value.unwrap_or(0));
+    }
+
+    fn target(&mut self, target: Option<AttackTarget>) {
+        match target {
+            None => self.bytes(&[0]),
+            Some(AttackTarget::Coordinate(coord)) => {
+                self.bytes(&[1]);
+                self.coord(coord);
+            }
+            Some(AttackTarget::Player(player_id)) => {
+                self.bytes(&[2]);
+                self.u64(player_id);
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Hash of the committed state; equal on every node at the same log index
+    pub fn digest(&self) -> u64 {
+        let mut h = StateHasher(FNV_OFFSET);
+
+        h.u64(self.players.len() as u64);
+        for player in self.players.values() {
+            h.u64(player.player_id);
+            h.str(&player.name);
+            h.coord(player.capital_coord);
+            h.bool(player.client_endpoint.is_some());
+            h.str(player.client_endpoint.as_deref().unwrap_or(""));
+            h.bool(player.alive);
+            h.u64(player.join_time);
+            h.u64(player.protected_until);
+            h.u64(player.last_heartbeat);
+            h.opt_u64(player.idle_since);
+            h.bool(player.idle_neutralized);
+            // Only when set, so states from before reconnect keys hash as they did
+            if let Some(key) = &player.reconnect_key {
+                h.str(key);
+            }
+        }
+
+        h.u64(self.nodes.len() as u64);
+        for node in self.nodes.values() {
+            h.coord(node.coord);
+            h.u64(node.owner_id);
+            h.bytes(&[match node.node_type {
+                NodeType::Capital => 0,
+                NodeType::Regular => 1,
+            }]);
+            h.target(node.current_target);
+            h.bytes(&[match node.init_state {
+                NodeInitState::Initializing => 0,
+                NodeInitState::Ready => 1,
+            }]);
+        }
+
+        h.u64(self.node_ips.len() as u64);
+        for (coord, ip) in &self.node_ips {
+            h.coord(*coord);
+            h.str(ip);
+        }
+
+        h.u64(self.capture_boosts.len() as u64);
+        for (coord, boost) in &self.capture_boosts {
+            h.coord(*coord);
+            h.u64(boost.attacker_id);
+            h.u32(boost.attackers);
+            h.u32(boost.multiplier.to_bits());
+            h.u64(boost.required_secs);
+        }
+
+        h.u64(self.effects.len() as u64);
+        for (id, record) in &self.effects {
+            h.u64(*id);
+            h.str(record.effect.kind());
+            match &record.effect {
+                Effect::SpawnNode { node_coord } => h.coord(*node_coord),
+                Effect::FinalKill { player_id, client_endpoint, attacker_nodes } => {
+                    h.u64(*player_id);
+                    h.str(client_endpoint);
+                    h.u64(attacker_nodes.len() as u64);
+                    for coord in attacker_nodes {
+                        h.coord(*coord);
+                    }
+                }
+                Effect::ShutdownGame => {}
+            }
+            h.u64(record.requested_at);
+            h.bool(record.completion.is_some());
+            if let Some(completion) = &record.completion {
+                h.str(completion.error.as_deref().unwrap_or(""));
+                h.u64(completion.timestamp);
+            }
+        }
+
+        h.u64(self.contested_capitals.len() as u64);
+        for (coord, contest) in &self.contested_capitals {
+            h.coord(*coord);
+            h.u64(contest.player_id);
+            h.u64(contest.expires_at);
+        }
+
+        h.u64(self.defenses.len() as u64);
+        for (coord, defense) in &self.defenses {
+            h.coord(*coord);
+            h.bool(defense.assist.is_some());
+            h.coord(defense.assist.unwrap_or(NodeCoord::new(0, 0)));
+        }
+
+        h.u64(self.terrain.len() as u64);
+        for (coord, terrain) in &self.terrain {
+            h.coord(*coord);
+            h.bytes(&[match terrain {
+                Terrain::Plain => 0,
+                Terrain::Highland => 1,
+                Terrain::Lowland => 2,
+                Terrain::Chasm => 3,
+            }]);
+        }
+
+        h.u64(self.retargeted_at.len() as u64);
+        for (coord, at) in &self.retargeted_at {
+            h.coord(*coord);
+            h.u64(*at);
+        }
+
+        h.opt_u64(self.map_seed);
+        h.u64(self.clock);
+        h.u32(self.time_scale);
+        h.opt_u64(self.retarget_cooldown_secs);
+        h.u64(self.last_applied_log_index);
+        h.bool(self.game_over);
+        // Only once committed, so states of games without a policy hash as they did
+        if let Some(policy) = self.elimination_policy {
+            h.str(policy.kind());
+            if let EliminationPolicy::Decay { interval_secs } = policy {
+                h.u64(interval_secs);
+            }
+            h.u64(self.decaying.len() as u64);
+            for (player_id, decay) in &self.decaying {
+                h.u64(*player_id);
+                h.u64(decay.since);
+                h.u32(decay.decayed);
+            }
+        }
+        // Lobby, live and finished follow from what's hashed above; sudden death doesn't
+        if let Some(since) = self.sudden_death_since {
+            h.u64(since);
+        }
+        if let Some(features) = self.features {
+            h.u64(features.iter().count() as u64);
+            for feature in features.iter() {
+                h.str(feature.name());
+            }
+        }
+        // Only while something is queued, like the policy above; the command is a
+        // committed event, so its id stands for it
+        if !self.scheduled.is_empty() {
+            h.u64(self.scheduled.len() as u64);
+            for (id, scheduled) in &self.scheduled {
+                h.u64(*id);
+                h.u64(scheduled.execute_at);
+                h.u64(scheduled.scheduled_at);
+            }
+        }
+        if !self.announcements.is_empty() {
+            h.u64(self.announcements.len() as u64);
+            for (id, announcement) in &self.announcements {
+                h.u64(*id);
+                h.str(&announcement.text);
+                h.str(announcement.severity.as_str());
+                h.u64(announcement.posted_at);
+                h.u64(announcement.expires_at);
+            }
+        }
+        // Only once committed, like the elimination policy
+        if let Some(interval_ms) = self.metrics_interval_ms {
+            h.u64(interval_ms);
+        }
+        if let Some(version) = self.cluster_version {
+            h.u64(version as u64);
+        }
+        h.bool(self.game_end.is_some());
+        if let Some(end) = &self.game_end {
+            h.opt_u64(end.winner_id);
+            h.u64(end.ended_at);
+            h.u64(end.members.len() as u64);
+            for member in &end.members {
+                h.u64(*member);
+            }
+            h.u64(end.acknowledged.len() as u64);
+            for member in &end.acknowledged {
+                h.u64(*member);
+            }
+        }
+        h.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::CommandEvent;
+
+    #[test]
+    fn test_digest() {
+        let replay = || {
+            let mut state = GameState::new();
+            state.process_committed(CommandEvent::test_join(1, 0).into(), 1, 1000);
+            state.process_committed(CommandEvent::test_join(2, 1).into(), 2, 1001);
+            state
+        };
+        let (a, mut b) = (replay(), replay());
+        assert_eq!(a.digest(), b.digest(), "same log, same digest");
+        assert_ne!(a.digest(), GameState::new().digest());
+
+        // Any drift shows, even one the events would never produce
+        b.nodes.get_mut(&NodeCoord::new(1, 0)).unwrap().current_target =
+            Some(AttackTarget::Coordinate(NodeCoord::new(0, 0)));
+        assert_ne!(a.digest(), b.digest());
+
+        // Pinned: nodes on different builds compare digests, so a change must be deliberate
+        assert_eq!(GameState::new().digest(), 3154170021600782922);
+    }
+}
@@ -0,0 +1,1115 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::features::FeatureFlags;
+use crate::terrain::TerrainTile;
+use crate::upcast::{CommandEventV1, Upcast, VersionedBody, VersionedJson};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Axial coordinates for triangular grid
+/// Each node has 6 neighbors at: (q±1, r), (q, r±1), (q±1, r∓1)
+/// Serializes as `{q, r}`; also reads "q,r" and `[q, r]` from JSON (see coord.rs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct NodeCoord {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// Type of node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeType {
+    /// Player's capital - worker node on grid with larger EC2 instance
+    Capital,
+    /// Regular captured node - worker node on grid with standard EC2 instance
+    Regular,
+}
+
+/// Attack target - can attack either a grid coordinate or a player's client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttackTarget {
+    /// Attack a node at a specific coordinate on the grid
+    Coordinate(NodeCoord),
+    /// Attack a player's client (their laptop)
+    Player(u64),  // player_id
+}
+
+/// Wire format version written by this build
+/// Bump when an event's layout changes; decoders reject versions newer than this
+/// v2: PlayerJoin split `node_ip` + `is_client` into `capital_ip` and `client_endpoint`
+/// v3: PlayerJoin gained `reconnect_key`
+pub const EVENT_FORMAT_VERSION: u16 = 3;
+
+/// Newest cluster version this build applies: every event variant it knows needs at
+/// most this one (`GameEvent::min_cluster_version`)
+/// Bump when adding a variant older builds couldn't decode, and give the variant the
+/// new number - it is only proposed once the cluster has committed that version.
+/// 1: every variant from before the version gate (builds without Hello)
+/// 2: ClusterVersionSet
+pub const CLUSTER_VERSION: u32 = 2;
+
+/// Event families - each gets its own validation, retention and replication policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventFamily {
+    /// Player intents - validated against the submitter's permissions
+    Command,
+    /// Outcomes decided by the leader - authoritative, never rejected on replay
+    Fact,
+    /// Measurements - high volume, only the latest per node matters
+    Telemetry,
+}
+
+/// Player intents submitted by clients and workers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CommandEvent {
+    /// Player joins the game: a capital worker on the grid, plus a client (laptop)
+    /// endpoint for human players. The client never occupies a hex.
+    PlayerJoin {
+        player_id: u64,
+        name: String,
+        capital_coord: NodeCoord,
+        /// Capital worker's address if it's already running, None while the master
+        /// spawns it (its NodeInitializationComplete fills it in)
+        capital_ip: Option<String>,
+        /// Player's client address - the final-kill target, None for worker-only players
+        client_endpoint: Option<String>,
+        timestamp: u64,
+        /// Raft node ID of the joining process (0 = unknown), feeds the NodeRegistry
+        #[serde(default)]
+        raft_node_id: u64,
+        /// SHA-256 (hex) of the client's secret reconnect token, None if the player
+        /// can't reconnect. Never the token itself: anyone can read the log.
+        #[serde(default)]
+        reconnect_key: Option<String>,
+    },
+    /// Node switches its attack target (or None to stop attacking)
+    SetNodeTarget {
+        node_coord: NodeCoord,
+        target: Option<AttackTarget>,  // None = stop attacking
+        timestamp: u64,
+    },
+    /// Several nodes switch targets in one commit, applied in order
+    SetNodeTargets {
+        orders: Vec<AttackOrder>,
+        timestamp: u64,
+    },
+    /// Node stops attacking and garrisons its responder (or None to stand down)
+    SetNodeDefense {
+        node_coord: NodeCoord,
+        defense: Option<DefenseOrder>,
+        timestamp: u64,
+    },
+    /// A restarted client takes its player back (see GameState::validate_reconnect)
+    /// `token` has to hash to the player's reconnect_key, which the leader checks; once
+    /// committed the token is spent and `next_key` replaces the key.
+    PlayerReconnected {
+        player_id: u64,
+        token: String,
+        next_key: String,
+        /// The client's new address - the final-kill target
+        client_endpoint: Option<String>,
+        timestamp: u64,
+        /// Raft node ID of the reconnecting process (0 = unknown), feeds the NodeRegistry
+        #[serde(default)]
+        raft_node_id: u64,
+    },
+    /// Queue `command` to run at `execute_at` (unix seconds): timed ceasefires, delayed
+    /// reinforcements, scripted scenario steps. Committing it only queues the command
+    /// (GameState::scheduled, keyed by this event's log index); when the time comes the
+    /// leader commits a ScheduledEventFired, which applies it.
+    ScheduleEvent {
+        execute_at: u64,
+        command: Box<CommandEvent>,
+        timestamp: u64,
+    },
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl CommandEvent {
+    /// Test fixture: player "p{player_id}" joins at time 0 with a capital at (q, 0)
+    /// running on 10.0.0.{player_id}
+    pub fn test_join(player_id: u64, q: i32) -> Self {
+        CommandEvent::PlayerJoin {
+            player_id,
+            name: format!("p{}", player_id),
+            capital_coord: NodeCoord::new(q, 0),
+            capital_ip: Some(format!("10.0.0.{}", player_id)),
+            client_endpoint: None,
+            timestamp: 0,
+            raft_node_id: 0,
+            reconnect_key: None,
+        }
+    }
+}
+
+/// One node's new target in a SetNodeTargets batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttackOrder {
+    pub node_coord: NodeCoord,
+    pub target: Option<AttackTarget>,
+}
+
+/// A node's defend order (see GameState::responder_multiplier)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefenseOrder {
+    /// Adjacent friendly node given half of the boost, None to keep it all
+    pub assist: Option<NodeCoord>,
+}
+
+/// Side effect outside the state machine, run by the leader (see EffectRequested)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect {
+    /// Ask the master to start a worker for a lazily initialized hex
+    SpawnNode { node_coord: NodeCoord },
+    /// Flood an eliminated player's client from the capturing player's nodes
+    FinalKill {
+        player_id: u64,
+        client_endpoint: String,
+        attacker_nodes: Vec<NodeCoord>,
+    },
+    /// Game over: report the final summary, then tear down the workers and master
+    ShutdownGame,
+}
+
+impl Effect {
+    /// Variant name, for logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Effect::SpawnNode { .. } => "SpawnNode",
+            Effect::FinalKill { .. } => "FinalKill",
+            Effect::ShutdownGame => "ShutdownGame",
+        }
+    }
+}
+
+/// Correction a game's referee can force through (see FactEvent::RefereeAction)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefereeOverride {
+    /// Hand a node back to `owner_id`; giving a player their own capital back
+    /// undoes its fall, reviving them if it eliminated them
+    RevertCapture { node_coord: NodeCoord, owner_id: u64 },
+    /// Make a node neutral and stop its attack; a neutralized capital is lost
+    NeutralizeNode { node_coord: NodeCoord },
+    /// Give a player more time on whichever clocks are running for them:
+    /// their capital's grace window and their spawn protection
+    ExtendTime { player_id: u64, secs: u64 },
+}
+
+impl RefereeOverride {
+    /// Variant name, for logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RefereeOverride::RevertCapture { .. } => "RevertCapture",
+            RefereeOverride::NeutralizeNode { .. } => "NeutralizeNode",
+            RefereeOverride::ExtendTime { .. } => "ExtendTime",
+        }
+    }
+}
+
+/// What happens to an eliminated player's nodes (see FactEvent::EliminationPolicySet)
+/// Their capital is already someone else's (or neutral); this covers the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EliminationPolicy {
+    /// Whoever holds the fallen capital takes them (neutral if nobody alive does)
+    Captor,
+    /// They turn neutral at once
+    Neutral,
+    /// They stand down and turn neutral one at a time, one every `interval_secs`,
+    /// the furthest from the fallen capital first - free to capture meanwhile
+    Decay { interval_secs: u64 },
+}
+
+impl EliminationPolicy {
+    /// Variant name, for logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EliminationPolicy::Captor => "captor",
+            EliminationPolicy::Neutral => "neutral",
+            EliminationPolicy::Decay { .. } => "decay",
+        }
+    }
+}
+
+/// How loudly an announcement shows (see FactEvent::Announcement)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AnnouncementSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnouncementSeverity::Info => "info",
+            AnnouncementSeverity::Warning => "warning",
+            AnnouncementSeverity::Critical => "critical",
+        }
+    }
+}
+
+/// Where a game is in its lifecycle (see FactEvent::PhaseChanged)
+/// Ordered: a game only moves forward, except that a referee revive can take a
+/// finished game back to where it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    /// Waiting for a second player
+    #[default]
+    Lobby,
+    /// Two or more players are fighting
+    #[serde(alias = "in_progress")]
+    Live,
+    /// The time limit ran out: captures take half as long, to force a finish
+    SuddenDeath,
+    /// One player left (or none)
+    Finished,
+}
+
+impl GamePhase {
+    /// Phase name, as the state endpoints report it
+    pub fn name(&self) -> &'static str {
+        match self {
+            GamePhase::Lobby => "lobby",
+            GamePhase::Live => "live",
+            GamePhase::SuddenDeath => "sudden_death",
+            GamePhase::Finished => "finished",
+        }
+    }
+}
+
+/// Outcomes established by the leader or by node lifecycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FactEvent {
+    /// Node is captured after sustained overload
+    NodeCaptured {
+        node_coord: NodeCoord,
+        new_owner_id: u64,
+        timestamp: u64,
+    },
+    /// Node initialization started (lazy init triggered)
+    NodeInitializationStarted {
+        node_coord: NodeCoord,
+        owner_id: u64,  // 0 = neutral/unowned
+        timestamp: u64,
+    },
+    /// Node initialization complete (EC2 task is ready)
+    NodeInitializationComplete {
+        node_coord: NodeCoord,
+        node_ip: String,
+        timestamp: u64,
+        /// Raft node ID of the spawned worker (0 = unknown), feeds the NodeRegistry
+        #[serde(default)]
+        raft_node_id: u64,
+    },
+    /// Master never accepted the spawn - the placeholder node is dropped
+    NodeInitializationFailed {
+        node_coord: NodeCoord,
+        attempts: u32,
+        reason: String,
+        timestamp: u64,
+    },
+    /// Combined arms: several of the capturing player's nodes are attacking one
+    /// overloaded target, so its capture timer runs faster (multiplier 1.0 = back to normal)
+    CaptureBoostChanged {
+        node_coord: NodeCoord,
+        attacker_id: u64,
+        /// Capturing player's adjacent nodes attacking the target
+        attackers: u32,
+        multiplier: f32,
+        /// Overload time now needed to capture
+        required_secs: u64,
+        timestamp: u64,
+    },
+    /// The leader owes a side effect; its id is the log index of this event
+    EffectRequested {
+        effect: Effect,
+        timestamp: u64,
+    },
+    /// A requested effect has run (`error` if it failed) and must never run again
+    EffectCompleted {
+        effect_id: u64,
+        error: Option<String>,
+        timestamp: u64,
+    },
+    /// Last stand: the capital at `node_coord` is about to fall, but its owner stays
+    /// alive until `expires_at` and survives by recapturing it. Committed right
+    /// before the capital's NodeCaptured; without one, losing a capital is fatal.
+    CapitalContested {
+        node_coord: NodeCoord,
+        player_id: u64,
+        expires_at: u64,
+        timestamp: u64,
+    },
+    /// A contested capital's grace window ran out without a recapture
+    PlayerEliminated {
+        player_id: u64,
+        timestamp: u64,
+    },
+    /// The game's terrain, generated once at game start (plain hexes left out)
+    MapGenerated {
+        seed: u64,
+        radius: u32,
+        tiles: Vec<TerrainTile>,
+        timestamp: u64,
+    },
+    /// A referee overrode the rules (submitted through the token-gated /admin/override,
+    /// never through /events), kept in the log so every override can be audited
+    RefereeAction {
+        action: RefereeOverride,
+        reason: String,
+        timestamp: u64,
+    },
+    /// A player's client stopped heartbeating: their nodes stop attacking. With
+    /// `neutralize` (a second, later event) their regular nodes turn neutral too -
+    /// the capital stays theirs. A heartbeat after it brings the player back.
+    OwnerIdle {
+        player_id: u64,
+        neutralize: bool,
+        timestamp: u64,
+    },
+    /// Game time runs `scale` times faster than wall time (playtesting), committed once
+    /// before the game starts - every duration in the rules shrinks by this factor
+    TimeScaleSet {
+        scale: u32,
+        timestamp: u64,
+    },
+    /// Least time between one node's target changes, when the game's config differs
+    /// from the rules default - committed once, like the time scale
+    RetargetCooldownSet {
+        secs: u64,
+        timestamp: u64,
+    },
+    /// The leader starts shutting a finished game down. Nothing is torn down until
+    /// every Raft member in `members` acknowledges it, or the leader stops waiting.
+    GameEnded {
+        winner_id: Option<u64>,
+        members: Vec<u64>,
+        timestamp: u64,
+    },
+    /// A Raft member has applied GameEnded, so it no longer needs the cluster
+    GameOverAcknowledged {
+        raft_node_id: u64,
+        timestamp: u64,
+    },
+    /// The leader's `GameState::digest` after applying `log_index`, for every other
+    /// node to compare with its own (changes nothing in the state)
+    StateDigest {
+        log_index: u64,
+        digest: u64,
+        timestamp: u64,
+    },
+    /// How a final kill on `player_id`'s client went, measured by the flooding leader
+    /// (changes nothing in the state)
+    FinalKillReport {
+        player_id: u64,
+        /// Bytes the client's connections accepted
+        bytes_delivered: u64,
+        /// From the first connection attempt to the last connection closing
+        duration_ms: u64,
+        /// Connections attempted, and how many of them got through
+        connections: u32,
+        connected: u32,
+        timestamp: u64,
+    },
+    /// What eliminations do to the loser's nodes, when the game's config sets it -
+    /// committed once, like the time scale (without one, their nodes stay theirs)
+    EliminationPolicySet {
+        policy: EliminationPolicy,
+        timestamp: u64,
+    },
+    /// Under the decay policy, one of an eliminated player's nodes turns neutral
+    NodeDecayed {
+        node_coord: NodeCoord,
+        player_id: u64,
+        timestamp: u64,
+    },
+    /// The leader moves the game to another phase - today only into sudden death,
+    /// when the game's time limit runs out (lobby -> live and -> finished follow
+    /// from joins and eliminations without an event)
+    PhaseChanged {
+        phase: GamePhase,
+        timestamp: u64,
+    },
+    /// Experimental subsystems the game runs, when its config differs from the
+    /// defaults - committed once, like the elimination policy
+    FeaturesSet {
+        features: FeatureFlags,
+        timestamp: u64,
+    },
+    /// A scheduled command's time came: the leader applies it, unless it would refuse
+    /// the same command submitted now (`refused` says why) - either way it leaves the queue
+    ScheduledEventFired {
+        /// Log index of the ScheduleEvent
+        schedule_id: u64,
+        refused: Option<String>,
+        timestamp: u64,
+    },
+    /// A banner for every player (the leader's own, or a referee's) - shown for
+    /// `ttl_secs` of wall time, changes nothing else
+    Announcement {
+        text: String,
+        severity: AnnouncementSeverity,
+        ttl_secs: u64,
+        timestamp: u64,
+    },
+    /// How often workers stream metric samples to the leader (milliseconds), when
+    /// the game's config differs from the default - committed once, like the time scale
+    MetricsIntervalSet {
+        interval_ms: u64,
+        timestamp: u64,
+    },
+    /// Every member runs a build that applies cluster `version`, so events needing it
+    /// may now be proposed - committed by the leader during rolling upgrades, never lowered
+    ClusterVersionSet {
+        version: u32,
+        timestamp: u64,
+    },
+}
+
+/// Periodic measurements reported by nodes and clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TelemetryEvent {
+    /// Node reports its metrics (bandwidth, packet loss)
+    /// Workers no longer commit these - metrics travel outside the log - but older
+    /// logs and replays still carry them.
+    NodeMetricsReport {
+        node_coord: NodeCoord,
+        bandwidth_in: u64,  // bytes/sec
+        packet_loss: f32,   // 0.0 to 1.0
+        timestamp: u64,
+        /// Attacking nodes this node has no flooder budget for right now
+        #[serde(default)]
+        queued_attackers: Vec<NodeCoord>,
+    },
+    /// A player's client is still running (unix seconds)
+    ClientHeartbeat {
+        player_id: u64,
+        timestamp: u64,
+    },
+}
+
+/// Game events - all go through Raft consensus for CamHack
+///
+/// Serialized as a versioned, family-tagged envelope:
+/// JSON: `{"v":1,"family":"command","event":{"SetNodeTarget":{...}}}`
+/// bincode: the `Versioned` variant of `WireEvent`
+/// The flat pre-envelope layout (`{"SetNodeTarget":{...}}`) is still accepted on decode.
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    Command(CommandEvent),
+    Fact(FactEvent),
+    Telemetry(TelemetryEvent),
+}
+
+impl GameEvent {
+    /// Which family this event belongs to
+    pub fn family(&self) -> EventFamily {
+        match self {
+            GameEvent::Command(_) => EventFamily::Command,
+            GameEvent::Fact(_) => EventFamily::Fact,
+            GameEvent::Telemetry(_) => EventFamily::Telemetry,
+        }
+    }
+
+    /// Variant name, for logs and summaries
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameEvent::Command(CommandEvent::PlayerJoin { .. }) => "PlayerJoin",
+            GameEvent::Command(CommandEvent::PlayerReconnected { .. }) => "PlayerReconnected",
+            GameEvent::Command(CommandEvent::SetNodeTarget { .. }) => "SetNodeTarget",
+            GameEvent::Command(CommandEvent::SetNodeTargets { .. }) => "SetNodeTargets",
+            GameEvent::Command(CommandEvent::SetNodeDefense { .. }) => "SetNodeDefense",
+            GameEvent::Command(CommandEvent::ScheduleEvent { .. }) => "ScheduleEvent",
+            GameEvent::Fact(FactEvent::NodeCaptured { .. }) => "NodeCaptured",
+            GameEvent::Fact(FactEvent::NodeInitializationStarted { .. }) => "NodeInitializationStarted",
+            GameEvent::Fact(FactEvent::NodeInitializationComplete { .. }) => "NodeInitializationComplete",
+            GameEvent::Fact(FactEvent::NodeInitializationFailed { .. }) => "NodeInitializationFailed",
+            GameEvent::Fact(FactEvent::CaptureBoostChanged { .. }) => "CaptureBoostChanged",
+            GameEvent::Fact(FactEvent::EffectRequested { .. }) => "EffectRequested",
+            GameEvent::Fact(FactEvent::EffectCompleted { .. }) => "EffectCompleted",
+            GameEvent::Fact(FactEvent::CapitalContested { .. }) => "CapitalContested",
+            GameEvent::Fact(FactEvent::PlayerEliminated { .. }) => "PlayerEliminated",
+            GameEvent::Fact(FactEvent::MapGenerated { .. }) => "MapGenerated",
+            GameEvent::Fact(FactEvent::RefereeAction { .. }) => "RefereeAction",
+            GameEvent::Fact(FactEvent::OwnerIdle { .. }) => "OwnerIdle",
+            GameEvent::Fact(FactEvent::TimeScaleSet { .. }) => "TimeScaleSet",
+            GameEvent::Fact(FactEvent::RetargetCooldownSet { .. }) => "RetargetCooldownSet",
+            GameEvent::Fact(FactEvent::GameEnded { .. }) => "GameEnded",
+            GameEvent::Fact(FactEvent::GameOverAcknowledged { .. }) => "GameOverAcknowledged",
+            GameEvent::Fact(FactEvent::StateDigest { .. }) => "StateDigest",
+            GameEvent::Fact(FactEvent::FinalKillReport { .. }) => "FinalKillReport",
+            GameEvent::Fact(FactEvent::EliminationPolicySet { .. }) => "EliminationPolicySet",
+            GameEvent::Fact(FactEvent::NodeDecayed { .. }) => "NodeDecayed",
+            GameEvent::Fact(FactEvent::PhaseChanged { .. }) => "PhaseChanged",
+            GameEvent::Fact(FactEvent::FeaturesSet { .. }) => "FeaturesSet",
+            GameEvent::Fact(FactEvent::ScheduledEventFired { .. }) => "ScheduledEventFired",
+            GameEvent::Fact(FactEvent::Announcement { .. }) => "Announcement",
+            GameEvent::Fact(FactEvent::MetricsIntervalSet { .. }) => "MetricsIntervalSet",
+            GameEvent::Fact(FactEvent::ClusterVersionSet { .. }) => "ClusterVersionSet",
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport { .. }) => "NodeMetricsReport",
+            GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { .. }) => "ClientHeartbeat",
+        }
+    }
+
+    /// Hexes the event is about, for filtering logs (a map's tiles don't count)
+    pub fn coords(&self) -> Vec<NodeCoord> {
+        match self {
+            GameEvent::Command(CommandEvent::PlayerJoin { capital_coord, .. }) => alloc::vec![*capital_coord],
+            GameEvent::Command(CommandEvent::SetNodeTarget { .. }) | GameEvent::Command(CommandEvent::SetNodeTargets { .. }) => {
+                self.attack_orders()
+                    .into_iter()
+                    .flat_map(|order| match order.target {
+                        Some(AttackTarget::Coordinate(target)) => alloc::vec![order.node_coord, target],
+                        _ => alloc::vec![order.node_coord],
+                    })
+                    .collect()
+            }
+            GameEvent::Command(CommandEvent::SetNodeDefense { node_coord, defense, .. }) => {
+                core::iter::once(*node_coord).chain(defense.and_then(|d| d.assist)).collect()
+            }
+            GameEvent::Command(CommandEvent::ScheduleEvent { command, .. }) => {
+                GameEvent::Command((**command).clone()).coords()
+            }
+            GameEvent::Fact(FactEvent::NodeCaptured { node_coord, .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationStarted { node_coord, .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationComplete { node_coord, .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationFailed { node_coord, .. })
+            | GameEvent::Fact(FactEvent::CaptureBoostChanged { node_coord, .. })
+            | GameEvent::Fact(FactEvent::CapitalContested { node_coord, .. })
+            | GameEvent::Fact(FactEvent::NodeDecayed { node_coord, .. }) => alloc::vec![*node_coord],
+            GameEvent::Fact(FactEvent::EffectRequested { effect, .. }) => match effect {
+                Effect::SpawnNode { node_coord } => alloc::vec![*node_coord],
+                Effect::FinalKill { attacker_nodes, .. } => attacker_nodes.clone(),
+                Effect::ShutdownGame => Vec::new(),
+            },
+            GameEvent::Fact(FactEvent::RefereeAction { action, .. }) => match action {
+                RefereeOverride::RevertCapture { node_coord, .. } | RefereeOverride::NeutralizeNode { node_coord } => {
+                    alloc::vec![*node_coord]
+                }
+                RefereeOverride::ExtendTime { .. } => Vec::new(),
+            },
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport { node_coord, queued_attackers, .. }) => {
+                core::iter::once(*node_coord).chain(queued_attackers.iter().copied()).collect()
+            }
+            GameEvent::Command(CommandEvent::PlayerReconnected { .. })
+            | GameEvent::Fact(FactEvent::EffectCompleted { .. })
+            | GameEvent::Fact(FactEvent::PlayerEliminated { .. })
+            | GameEvent::Fact(FactEvent::MapGenerated { .. })
+            | GameEvent::Fact(FactEvent::OwnerIdle { .. })
+            | GameEvent::Fact(FactEvent::TimeScaleSet { .. })
+            | GameEvent::Fact(FactEvent::RetargetCooldownSet { .. })
+            | GameEvent::Fact(FactEvent::GameEnded { .. })
+            | GameEvent::Fact(FactEvent::GameOverAcknowledged { .. })
+            | GameEvent::Fact(FactEvent::StateDigest { .. })
+            | GameEvent::Fact(FactEvent::FinalKillReport { .. })
+            | GameEvent::Fact(FactEvent::EliminationPolicySet { .. })
+            | GameEvent::Fact(FactEvent::PhaseChanged { .. })
+            | GameEvent::Fact(FactEvent::FeaturesSet { .. })
+            | GameEvent::Fact(FactEvent::ScheduledEventFired { .. })
+            | GameEvent::Fact(FactEvent::Announcement { .. })
+            | GameEvent::Fact(FactEvent::MetricsIntervalSet { .. })
+            | GameEvent::Fact(FactEvent::ClusterVersionSet { .. })
+            | GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { .. }) => Vec::new(),
+        }
+    }
+
+    /// Players the event names, for filtering logs (owner 0 - neutral - is left out)
+    /// Only ids written in the event: a SetNodeTarget doesn't say whose node it is.
+    pub fn player_ids(&self) -> Vec<u64> {
+        let ids = match self {
+            GameEvent::Command(CommandEvent::PlayerJoin { player_id, .. })
+            | GameEvent::Command(CommandEvent::PlayerReconnected { player_id, .. })
+            | GameEvent::Fact(FactEvent::CapitalContested { player_id, .. })
+            | GameEvent::Fact(FactEvent::PlayerEliminated { player_id, .. })
+            | GameEvent::Fact(FactEvent::OwnerIdle { player_id, .. })
+            | GameEvent::Fact(FactEvent::FinalKillReport { player_id, .. })
+            | GameEvent::Fact(FactEvent::NodeDecayed { player_id, .. })
+            | GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id, .. }) => alloc::vec![*player_id],
+            GameEvent::Command(CommandEvent::SetNodeTarget { .. }) | GameEvent::Command(CommandEvent::SetNodeTargets { .. }) => {
+                self.attack_orders()
+                    .into_iter()
+                    .filter_map(|order| match order.target {
+                        Some(AttackTarget::Player(player_id)) => Some(player_id),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            GameEvent::Fact(FactEvent::NodeCaptured { new_owner_id: player_id, .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationStarted { owner_id: player_id, .. })
+            | GameEvent::Fact(FactEvent::CaptureBoostChanged { attacker_id: player_id, .. }) => alloc::vec![*player_id],
+            GameEvent::Fact(FactEvent::EffectRequested { effect: Effect::FinalKill { player_id, .. }, .. }) => {
+                alloc::vec![*player_id]
+            }
+            GameEvent::Fact(FactEvent::GameEnded { winner_id, .. }) => winner_id.iter().copied().collect(),
+            GameEvent::Command(CommandEvent::ScheduleEvent { command, .. }) => {
+                GameEvent::Command((**command).clone()).player_ids()
+            }
+            GameEvent::Fact(FactEvent::RefereeAction { action, .. }) => match action {
+                RefereeOverride::RevertCapture { owner_id: player_id, .. } | RefereeOverride::ExtendTime { player_id, .. } => {
+                    alloc::vec![*player_id]
+                }
+                RefereeOverride::NeutralizeNode { .. } => Vec::new(),
+            },
+            GameEvent::Command(CommandEvent::SetNodeDefense { .. })
+            | GameEvent::Fact(FactEvent::EffectRequested { .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationComplete { .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationFailed { .. })
+            | GameEvent::Fact(FactEvent::EffectCompleted { .. })
+            | GameEvent::Fact(FactEvent::MapGenerated { .. })
+            | GameEvent::Fact(FactEvent::TimeScaleSet { .. })
+            | GameEvent::Fact(FactEvent::RetargetCooldownSet { .. })
+            | GameEvent::Fact(FactEvent::GameOverAcknowledged { .. })
+            | GameEvent::Fact(FactEvent::StateDigest { .. })
+            | GameEvent::Fact(FactEvent::EliminationPolicySet { .. })
+            | GameEvent::Fact(FactEvent::PhaseChanged { .. })
+            | GameEvent::Fact(FactEvent::FeaturesSet { .. })
+            | GameEvent::Fact(FactEvent::ScheduledEventFired { .. })
+            | GameEvent::Fact(FactEvent::Announcement { .. })
+            | GameEvent::Fact(FactEvent::MetricsIntervalSet { .. })
+            | GameEvent::Fact(FactEvent::ClusterVersionSet { .. })
+            | GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport { .. }) => Vec::new(),
+        };
+        ids.into_iter().filter(|id| *id != 0).collect()
+    }
+
+    /// Cluster version every member must apply before the event may be proposed
+    /// A follower running an older build can't decode a variant it doesn't know, and
+    /// would stop applying the log at it.
+    pub fn min_cluster_version(&self) -> u32 {
+        match self {
+            GameEvent::Fact(FactEvent::ClusterVersionSet { .. }) => 2,
+            GameEvent::Command(CommandEvent::ScheduleEvent { command, .. }) => {
+                GameEvent::Command((**command).clone()).min_cluster_version()
+            }
+            _ => 1,
+        }
+    }
+
+    /// Target changes the event orders: one for SetNodeTarget, each of a SetNodeTargets
+    pub fn attack_orders(&self) -> Vec<AttackOrder> {
+        match self {
+            GameEvent::Command(CommandEvent::SetNodeTarget { node_coord, target, .. }) => {
+                alloc::vec![AttackOrder { node_coord: *node_coord, target: *target }]
+            }
+            GameEvent::Command(CommandEvent::SetNodeTargets { orders, .. }) => orders.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl From<CommandEvent> for GameEvent {
+    fn from(event: CommandEvent) -> Self {
+        GameEvent::Command(event)
+    }
+}
+
+impl From<FactEvent> for GameEvent {
+    fn from(event: FactEvent) -> Self {
+        GameEvent::Fact(event)
+    }
+}
+
+impl From<TelemetryEvent> for GameEvent {
+    fn from(event: TelemetryEvent) -> Self {
+        GameEvent::Telemetry(event)
+    }
+}
+
+// ============= Wire Format =============
+
+/// Binary body of a versioned event (bincode can't encode tagged enums)
+/// Decoding goes through `upcast::VersionedBody`, which picks the layout by version.
+#[derive(Serialize)]
+#[serde(remote = "GameEvent")]
+enum GameEventDef {
+    Command(CommandEvent),
+    Fact(FactEvent),
+    Telemetry(TelemetryEvent),
+}
+
+/// Binary wire layout
+/// The first six variants are the pre-envelope GameEvent as the last build before the
+/// envelope wrote it, and `VersionedV1` is the v1 envelope. New builds only write
+/// `Versioned`; either envelope's body is upcast by its `v`.
+/// Bincode reads every field a layout lists, so the `#[serde(default)]`s below only
+/// help JSON: binary entries written before `raft_node_id` and `queued_attackers`
+/// were added don't decode (see test_bincode_needs_every_field).
+#[derive(Deserialize)]
+enum WireEvent {
+    PlayerJoin {
+        player_id: u64,
+        name: String,
+        capital_coord: NodeCoord,
+        node_ip: String,
+        is_client: bool,
+        timestamp: u64,
+        #[serde(default)]
+        raft_node_id: u64,
+    },
+    SetNodeTarget {
+        node_coord: NodeCoord,
+        target: Option<AttackTarget>,
+        timestamp: u64,
+    },
+    NodeCaptured {
+        node_coord: NodeCoord,
+        new_owner_id: u64,
+        timestamp: u64,
+    },
+    NodeMetricsReport {
+        node_coord: NodeCoord,
+        bandwidth_in: u64,
+        packet_loss: f32,
+        timestamp: u64,
+        #[serde(default)]
+        queued_attackers: Vec<NodeCoord>,
+    },
+    NodeInitializationStarted {
+        node_coord: NodeCoord,
+        owner_id: u64,
+        timestamp: u64,
+    },
+    NodeInitializationComplete {
+        node_coord: NodeCoord,
+        node_ip: String,
+        timestamp: u64,
+        #[serde(default)]
+        raft_node_id: u64,
+    },
+    /// Same bytes as a `{ v, event }` struct variant
+    VersionedV1(VersionedBody),
+    Versioned(VersionedBody),
+}
+
+/// Index of `WireEvent::Versioned`
+const WIRE_VERSIONED_INDEX: u32 = 7;
+
+impl WireEvent {
+    fn into_event<E: serde::de::Error>(self) -> Result<GameEvent, E> {
+        Ok(match self {
+            WireEvent::PlayerJoin { player_id, name, capital_coord, node_ip, is_client, timestamp, raft_node_id } => {
+                CommandEventV1::PlayerJoin { player_id, name, capital_coord, node_ip, is_client, timestamp, raft_node_id }
+                    .upcast()
+                    .into()
+            }
+            WireEvent::SetNodeTarget { node_coord, target, timestamp } => {
+                CommandEvent::SetNodeTarget { node_coord, target, timestamp }.into()
+            }
+            WireEvent::NodeCaptured { node_coord, new_owner_id, timestamp } => {
+                FactEvent::NodeCaptured { node_coord, new_owner_id, timestamp }.into()
+            }
+            WireEvent::NodeMetricsReport { node_coord, bandwidth_in, packet_loss, timestamp, queued_attackers } => {
+                TelemetryEvent::NodeMetricsReport { node_coord, bandwidth_in, packet_loss, timestamp, queued_attackers }.into()
+            }
+            WireEvent::NodeInitializationStarted { node_coord, owner_id, timestamp } => {
+                FactEvent::NodeInitializationStarted { node_coord, owner_id, timestamp }.into()
+            }
+            WireEvent::NodeInitializationComplete { node_coord, node_ip, timestamp, raft_node_id } => {
+                FactEvent::NodeInitializationComplete { node_coord, node_ip, timestamp, raft_node_id }.into()
+            }
+            WireEvent::VersionedV1(VersionedBody { v, event }) | WireEvent::Versioned(VersionedBody { v, event }) => {
+                check_version(v)?;
+                event
+            }
+        })
+    }
+}
+
+/// JSON envelope, tagged by family; each body is upcast by its `v`
+#[derive(Deserialize)]
+#[serde(tag = "family", rename_all = "snake_case")]
+enum JsonEnvelope {
+    Command(VersionedJson<CommandEvent>),
+    Fact(VersionedJson<FactEvent>),
+    Telemetry(VersionedJson<TelemetryEvent>),
+}
+
+/// Anything a human-readable decoder may see: the envelope or a pre-envelope event
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonWire {
+    Envelope(JsonEnvelope),
+    Legacy(WireEvent),
+}
+
+fn check_version<E: serde::de::Error>(v: u16) -> Result<(), E> {
+    if v > EVENT_FORMAT_VERSION {
+        return Err(E::custom(format!(
+            "unsupported event format version {} (max {})",
+            v, EVENT_FORMAT_VERSION
+        )));
+    }
+    Ok(())
+}
+
+impl Serialize for GameEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeStruct, SerializeStructVariant};
+
+        if serializer.is_human_readable() {
+            let mut envelope = serializer.serialize_struct("GameEvent", 3)?;
+            envelope.serialize_field("v", &EVENT_FORMAT_VERSION)?;
+            envelope.serialize_field("family", &self.family())?;
+            match self {
+                GameEvent::Command(event) => envelope.serialize_field("event", event)?,
+                GameEvent::Fact(event) => envelope.serialize_field("event", event)?,
+                GameEvent::Telemetry(event) => envelope.serialize_field("event", event)?,
+            }
+            envelope.end()
+        } else {
+            /// Borrowing adapter so the body serializes without a clone
+            struct Body<'a>(&'a GameEvent);
+            impl Serialize for Body<'_> {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    GameEventDef::serialize(self.0, serializer)
+                }
+            }
+
+            let mut wire =
+                serializer.serialize_struct_variant("WireEvent", WIRE_VERSIONED_INDEX, "Versioned", 2)?;
+            wire.serialize_field("v", &EVENT_FORMAT_VERSION)?;
+            wire.serialize_field("event", &Body(self))?;
+            wire.end()
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GameEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return WireEvent::deserialize(deserializer)?.into_event();
+        }
+
+        match JsonWire::deserialize(deserializer)? {
+            JsonWire::Envelope(JsonEnvelope::Command(VersionedJson { v, event })) => check_version(v).map(|_| event.into()),
+            JsonWire::Envelope(JsonEnvelope::Fact(VersionedJson { v, event })) => check_version(v).map(|_| event.into()),
+            JsonWire::Envelope(JsonEnvelope::Telemetry(VersionedJson { v, event })) => {
+                check_version(v).map(|_| event.into())
+            }
+            JsonWire::Legacy(wire) => wire.into_event(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn set_target() -> GameEvent {
+        CommandEvent::SetNodeTarget {
+            node_coord: NodeCoord::new(1, 2),
+            target: Some(AttackTarget::Coordinate(NodeCoord::new(2, 2))),
+            timestamp: 42,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_json_envelope_roundtrip() {
+        let json = serde_json::to_value(set_target()).unwrap();
+        assert_eq!(json["v"], EVENT_FORMAT_VERSION);
+        assert_eq!(json["family"], "command");
+        assert!(json["event"]["SetNodeTarget"].is_object());
+
+        let decoded: GameEvent = serde_json::from_value(json).unwrap();
+        assert!(matches!(decoded, GameEvent::Command(CommandEvent::SetNodeTarget { timestamp: 42, .. })));
+    }
+
+    #[test]
+    fn test_bincode_roundtrip() {
+        let metrics: GameEvent = TelemetryEvent::NodeMetricsReport {
+            node_coord: NodeCoord::new(0, 0),
+            bandwidth_in: 1000,
+            packet_loss: 0.5,
+            timestamp: 7,
+            queued_attackers: vec![NodeCoord::new(1, 0)],
+        }
+        .into();
+
+        let bytes = bincode::serialize(&metrics).unwrap();
+        let decoded: GameEvent = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.family(), EventFamily::Telemetry);
+    }
+
+    #[test]
+    fn test_legacy_json_still_decodes() {
+        // Shape the frontend and pre-envelope builds send
+        let legacy = serde_json::json!({
+            "NodeCaptured": { "node_coord": { "q": 3, "r": -1 }, "new_owner_id": 9, "timestamp": 5 }
+        });
+
+        let decoded: GameEvent = serde_json::from_value(legacy).unwrap();
+        assert!(matches!(decoded, GameEvent::Fact(FactEvent::NodeCaptured { new_owner_id: 9, .. })));
+    }
+
+    #[test]
+    fn test_legacy_bincode_still_decodes() {
+        /// Pre-envelope layout, as older builds wrote it
+        #[derive(Serialize)]
+        #[allow(dead_code)]
+        enum OldGameEvent {
+            PlayerJoin,
+            SetNodeTarget { node_coord: NodeCoord, target: Option<AttackTarget>, timestamp: u64 },
+        }
+
+        let bytes = bincode::serialize(&OldGameEvent::SetNodeTarget {
+            node_coord: NodeCoord::new(1, 2),
+            target: None,
+            timestamp: 42,
+        })
+        .unwrap();
+
+        let decoded: GameEvent = bincode::deserialize(&bytes).unwrap();
+        assert!(matches!(decoded, GameEvent::Command(CommandEvent::SetNodeTarget { target: None, .. })));
+    }
+
+    #[test]
+    fn test_bincode_needs_every_field() {
+        /// PlayerJoin as the first builds wrote it, before `raft_node_id`
+        #[derive(Serialize)]
+        enum FirstGameEvent {
+            PlayerJoin { player_id: u64, name: String, capital_coord: NodeCoord, node_ip: String, is_client: bool, timestamp: u64 },
+        }
+        let join = FirstGameEvent::PlayerJoin {
+            player_id: 1,
+            name: "Alice".into(),
+            capital_coord: NodeCoord::new(0, 0),
+            node_ip: "10.0.0.1".into(),
+            is_client: false,
+            timestamp: 5,
+        };
+
+        // JSON fills the missing field in; bincode runs out of bytes looking for it
+        let decoded: GameEvent = serde_json::from_value(serde_json::to_value(&join).unwrap()).unwrap();
+        assert!(matches!(decoded, GameEvent::Command(CommandEvent::PlayerJoin { raft_node_id: 0, .. })));
+        assert!(bincode::deserialize::<GameEvent>(&bincode::serialize(&join).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_v1_player_join_migrates() {
+        // v1 JSON envelope, client player: the single IP was the laptop's
+        let v1 = serde_json::json!({
+            "v": 1,
+            "family": "command",
+            "event": { "PlayerJoin": { "player_id": 3, "name": "Carol", "capital_coord": { "q": 2, "r": 0 },
+                                       "node_ip": "192.168.1.9", "is_client": true, "timestamp": 5 } }
+        });
+        let decoded: GameEvent = serde_json::from_value(v1).unwrap();
+        match decoded {
+            GameEvent::Command(CommandEvent::PlayerJoin { capital_ip, client_endpoint, .. }) => {
+                assert_eq!(capital_ip, None);
+                assert_eq!(client_endpoint.as_deref(), Some("192.168.1.9"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        /// v1 bincode layout: six legacy variants, then the v1 envelope
+        #[derive(Serialize)]
+        #[allow(dead_code)]
+        enum OldWire {
+            A, B, C, D, E, F,
+            Versioned { v: u16, event: OldGameEvent },
+        }
+        #[derive(Serialize)]
+        enum OldGameEvent {
+            Command(OldCommand),
+        }
+        #[derive(Serialize)]
+        enum OldCommand {
+            PlayerJoin {
+                player_id: u64,
+                name: String,
+                capital_coord: NodeCoord,
+                node_ip: String,
+                is_client: bool,
+                timestamp: u64,
+                raft_node_id: u64,
+            },
+        }
+
+        let bytes = bincode::serialize(&OldWire::Versioned {
+            v: 1,
+            event: OldGameEvent::Command(OldCommand::PlayerJoin {
+                player_id: 4,
+                name: "Dan".into(),
+                capital_coord: NodeCoord::new(0, 1),
+                node_ip: "10.0.0.4".into(),
+                is_client: false,
+                timestamp: 6,
+                raft_node_id: 11,
+            }),
+        })
+        .unwrap();
+        match bincode::deserialize::<GameEvent>(&bytes).unwrap() {
+            GameEvent::Command(CommandEvent::PlayerJoin { capital_ip, client_endpoint, raft_node_id, .. }) => {
+                assert_eq!(capital_ip.as_deref(), Some("10.0.0.4"));
+                assert_eq!(client_endpoint, None);
+                assert_eq!(raft_node_id, 11);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let future = serde_json::json!({
+            "v": EVENT_FORMAT_VERSION + 1,
+            "family": "command",
+            "event": { "SetNodeTarget": { "node_coord": { "q": 0, "r": 0 }, "target": null, "timestamp": 0 } }
+        });
+        assert!(serde_json::from_value::<GameEvent>(future).is_err());
+    }
+
+    #[test]
+    fn test_coords_and_player_ids() {
+        assert_eq!(set_target().coords(), vec![NodeCoord::new(1, 2), NodeCoord::new(2, 2)]);
+        assert!(set_target().player_ids().is_empty());
+
+        let captured: GameEvent =
+            FactEvent::NodeCaptured { node_coord: NodeCoord::new(3, 0), new_owner_id: 7, timestamp: 1 }.into();
+        assert_eq!(captured.coords(), vec![NodeCoord::new(3, 0)]);
+        assert_eq!(captured.player_ids(), vec![7]);
+
+        // Neutral owner isn't a player
+        let neutral: GameEvent =
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(3, 0), owner_id: 0, timestamp: 1 }.into();
+        assert!(neutral.player_ids().is_empty());
+
+        let defend: GameEvent = CommandEvent::SetNodeDefense {
+            node_coord: NodeCoord::new(0, 0),
+            defense: Some(DefenseOrder { assist: Some(NodeCoord::new(1, 0)) }),
+            timestamp: 1,
+        }
+        .into();
+        assert_eq!(defend.kind(), "SetNodeDefense");
+        assert_eq!(defend.coords(), vec![NodeCoord::new(0, 0), NodeCoord::new(1, 0)]);
+        assert!(defend.attack_orders().is_empty());
+    }
+
+    #[test]
+    fn test_set_node_targets_orders() {
+        let batch: GameEvent = CommandEvent::SetNodeTargets {
+            orders: vec![
+                AttackOrder { node_coord: NodeCoord::new(0, 0), target: Some(AttackTarget::Coordinate(NodeCoord::new(1, 0))) },
+                AttackOrder { node_coord: NodeCoord::new(0, 1), target: Some(AttackTarget::Player(4)) },
+            ],
+            timestamp: 9,
+        }
+        .into();
+        assert_eq!(batch.kind(), "SetNodeTargets");
+        assert_eq!(batch.coords(), vec![NodeCoord::new(0, 0), NodeCoord::new(1, 0), NodeCoord::new(0, 1)]);
+        assert_eq!(batch.player_ids(), vec![4]);
+        assert_eq!(set_target().attack_orders().len(), 1);
+
+        let bytes = bincode::serialize(&batch).unwrap();
+        let decoded: GameEvent = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.attack_orders(), batch.attack_orders());
+        let decoded: GameEvent = serde_json::from_value(serde_json::to_value(&batch).unwrap()).unwrap();
+        assert_eq!(decoded.attack_orders(), batch.attack_orders());
+    }
+}
@@ -0,0 +1,136 @@
+// Feature flags - experimental subsystems switched per game, not per build
+//
+// Every binary ships every subsystem; which ones a game runs is part of its config.
+// The leader commits its set once at game start (FactEvent::FeaturesSet) when it
+// differs from the defaults, so a leader elected later - or started with another
+// environment - plays the game the first one started. Games that never committed a
+// set (and older logs) run the defaults: the stable features on, experiments off.
+//
+// Workers read the set from FEATURES: a comma-separated list of feature names to turn
+// on, and of `-name`s to turn off, applied to the defaults (`fog_of_war,-sudden_death`).
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// A subsystem a game can switch on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Players only see the hexes next to their own (experimental)
+    FogOfWar,
+    /// Nodes earn and spend resources (experimental)
+    Economy,
+    /// Attack traffic over QUIC instead of raw UDP (experimental)
+    QuicAttacks,
+    /// The leader calls sudden death when the time limit runs out
+    SuddenDeath,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] = [Feature::FogOfWar, Feature::Economy, Feature::QuicAttacks, Feature::SuddenDeath];
+
+    /// Feature name, as FEATURES and the state endpoints spell it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::FogOfWar => "fog_of_war",
+            Feature::Economy => "economy",
+            Feature::QuicAttacks => "quic_attacks",
+            Feature::SuddenDeath => "sudden_death",
+        }
+    }
+
+    /// The feature called `name` (case and `-`/`_` insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase().replace('-', "_");
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// A set of features, listed by name on the wire (`["fog_of_war", "sudden_death"]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<Feature>", into = "Vec<Feature>")]
+pub struct FeatureFlags(u32);
+
+impl FeatureFlags {
+    /// No features at all
+    pub const NONE: FeatureFlags = FeatureFlags(0);
+
+    pub fn contains(&self, feature: Feature) -> bool {
+        self.0 & feature.bit() != 0
+    }
+
+    pub fn with(self, feature: Feature) -> Self {
+        FeatureFlags(self.0 | feature.bit())
+    }
+
+    pub fn without(self, feature: Feature) -> Self {
+        FeatureFlags(self.0 & !feature.bit())
+    }
+
+    /// Features in the set, in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = Feature> + '_ {
+        Feature::ALL.into_iter().filter(|f| self.contains(*f))
+    }
+
+    /// The defaults with FEATURES applied: `name` turns a feature on, `-name` off
+    /// An error names the first unknown feature.
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut flags = Self::default();
+        for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (off, name) = match entry.strip_prefix('-') {
+                Some(name) => (true, name),
+                None => (false, entry.strip_prefix('+').unwrap_or(entry)),
+            };
+            let feature = Feature::from_name(name).ok_or_else(|| name.to_string())?;
+            flags = if off { flags.without(feature) } else { flags.with(feature) };
+        }
+        Ok(flags)
+    }
+}
+
+impl Default for FeatureFlags {
+    /// What a game runs without a committed set: stable features only
+    fn default() -> Self {
+        FeatureFlags::NONE.with(Feature::SuddenDeath)
+    }
+}
+
+impl From<Vec<Feature>> for FeatureFlags {
+    fn from(features: Vec<Feature>) -> Self {
+        features.into_iter().fold(FeatureFlags::NONE, FeatureFlags::with)
+    }
+}
+
+impl From<FeatureFlags> for Vec<Feature> {
+    fn from(flags: FeatureFlags) -> Self {
+        flags.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_feature_flags() {
+        assert_eq!(FeatureFlags::parse(""), Ok(FeatureFlags::default()));
+        assert!(FeatureFlags::default().contains(Feature::SuddenDeath));
+        assert!(!FeatureFlags::default().contains(Feature::FogOfWar));
+
+        let flags = FeatureFlags::parse(" Fog-Of-War, +economy ,-sudden_death").unwrap();
+        assert_eq!(flags.iter().collect::<Vec<_>>(), [Feature::FogOfWar, Feature::Economy]);
+        assert_eq!(FeatureFlags::parse("fog_of_war,teleports"), Err("teleports".to_string()));
+
+        // Listed by name on the wire, in both formats
+        let json = serde_json::to_string(&flags).unwrap();
+        assert_eq!(json, r#"["fog_of_war","economy"]"#);
+        assert_eq!(serde_json::from_str::<FeatureFlags>(&json).unwrap(), flags);
+        let bytes = bincode::serialize(&flags).unwrap();
+        assert_eq!(bincode::deserialize::<FeatureFlags>(&bytes).unwrap(), flags);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::events::NodeCoord;
+use alloc::vec::Vec;
+use libm::{fabs, round, sqrt};
+use serde::{Deserialize, Serialize};
+
+/// Axial direction vectors, in ring-walk order (matches redblobgames' hex guide)
+pub const DIRECTIONS: [(i32, i32); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// Compass side of a hex, for the pointy-top layout of `to_pixel` (north is up)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HexDirection {
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+
+impl HexDirection {
+    /// Same order as DIRECTIONS
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::NorthEast,
+        HexDirection::NorthWest,
+        HexDirection::West,
+        HexDirection::SouthWest,
+        HexDirection::SouthEast,
+    ];
+
+    /// Axial offset of the neighbour on this side
+    pub fn offset(self) -> (i32, i32) {
+        DIRECTIONS[self as usize]
+    }
+}
+
+/// Round fractional cube coordinates to the nearest hex
+fn cube_round(q: f64, r: f64, s: f64) -> NodeCoord {
+    let mut rq = round(q);
+    let mut rr = round(r);
+    let rs = round(s);
+
+    let q_diff = fabs(rq - q);
+    let r_diff = fabs(rr - r);
+    let s_diff = fabs(rs - s);
+
+    // Fix whichever component drifted most so q + r + s == 0 holds
+    if q_diff > r_diff && q_diff > s_diff {
+        rq = -rr - rs;
+    } else if r_diff > s_diff {
+        rr = -rq - rs;
+    }
+
+    NodeCoord::new(rq as i32, rr as i32)
+}
+
+impl NodeCoord {
+    /// Create a new coordinate
+    pub fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    /// Get all 6 neighbors for a triangular grid coordinate
+    /// In axial coordinates, neighbors are: (q±1, r), (q, r±1), (q+1, r-1), (q-1, r+1)
+    pub fn neighbors(&self) -> [NodeCoord; 6] {
+        [
+            NodeCoord::new(self.q + 1, self.r),
+            NodeCoord::new(self.q - 1, self.r),
+            NodeCoord::new(self.q, self.r + 1),
+            NodeCoord::new(self.q, self.r - 1),
+            NodeCoord::new(self.q + 1, self.r - 1),
+            NodeCoord::new(self.q - 1, self.r + 1),
+        ]
+    }
+
+    /// Check if two coordinates are adjacent (distance 1)
+    pub fn is_adjacent(&self, other: &NodeCoord) -> bool {
+        self.distance(other) == 1
+    }
+
+    /// Calculate distance between two coordinates
+    /// In i64, so hexes at opposite ends of the coordinate space don't overflow
+    pub fn distance(&self, other: &NodeCoord) -> u32 {
+        let (q1, r1, q2, r2) = (self.q as i64, self.r as i64, other.q as i64, other.r as i64);
+        let dq = (q1 - q2).abs();
+        let dr = (r1 - r2).abs();
+        let ds = (q1 + r1 - q2 - r2).abs();
+        u32::try_from((dq + dr + ds) / 2).unwrap_or(u32::MAX)
+    }
+
+    /// Pixel centre of this hex for a pointy-top layout with the given hex size
+    /// Same formula as the frontend's hexToPixel
+    pub fn to_pixel(&self, size: f64) -> (f64, f64) {
+        let x = size * (sqrt(3.0) * self.q as f64 + sqrt(3.0) / 2.0 * self.r as f64);
+        let y = size * (1.5 * self.r as f64);
+        (x, y)
+    }
+
+    /// Hex containing the given pixel (inverse of `to_pixel`)
+    pub fn from_pixel(x: f64, y: f64, size: f64) -> NodeCoord {
+        let q = (sqrt(3.0) / 3.0 * x - y / 3.0) / size;
+        let r = (2.0 / 3.0 * y) / size;
+        cube_round(q, r, -q - r)
+    }
+
+    /// All coordinates exactly `radius` steps away, walking around the ring
+    /// Radius 0 is just this coordinate. A ring reaching past the i32 coordinate space
+    /// is empty; callers taking a radius from outside should cap it well below that.
+    pub fn ring(&self, radius: u32) -> Vec<NodeCoord> {
+        if radius == 0 {
+            return alloc::vec![*self];
+        }
+
+        let in_range = |radius: i32, c: i32| c.checked_sub(radius).and(c.checked_add(radius)).is_some();
+        let radius = match i32::try_from(radius) {
+            Ok(radius) if in_range(radius, self.q) && in_range(radius, self.r) => radius,
+            _ => return Vec::new(),
+        };
+        let (dq, dr) = DIRECTIONS[4];
+        let mut current = NodeCoord::new(self.q + dq * radius, self.r + dr * radius);
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+
+        for (dq, dr) in DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(current);
+                current = NodeCoord::new(current.q + dq, current.r + dr);
+            }
+        }
+
+        ring
+    }
+
+    /// All coordinates within `radius`, ordered centre-out ring by ring
+    pub fn spiral(&self, radius: u32) -> Vec<NodeCoord> {
+        (0..=radius).flat_map(|r| self.ring(r)).collect()
+    }
+
+    /// Hexes on the straight line from this coordinate to `other`, inclusive
+    pub fn line_to(&self, other: &NodeCoord) -> Vec<NodeCoord> {
+        let n = self.distance(other);
+        if n == 0 {
+            return alloc::vec![*self];
+        }
+
+        // Nudge off exact hex edges so ties round consistently
+        let (aq, ar) = (self.q as f64 + 1e-6, self.r as f64 + 1e-6);
+        let (bq, br) = (other.q as f64 + 1e-6, other.r as f64 + 1e-6);
+
+        (0..=n)
+            .map(|i| {
+                let t = i as f64 / n as f64;
+                let q = aq + (bq - aq) * t;
+                let r = ar + (br - ar) * t;
+                cube_round(q, r, -q - r)
+            })
+            .collect()
+    }
+
+    /// Side of this hex that `other` lies towards (the first step of `line_to`)
+    /// None for the hex itself
+    pub fn direction_to(&self, other: &NodeCoord) -> Option<HexDirection> {
+        let step = *self.line_to(other).get(1)?;
+        let offset = (step.q - self.q, step.r - self.r);
+        HexDirection::ALL.into_iter().find(|d| d.offset() == offset)
+    }
+
+    /// Whether the straight line to `other` avoids every hex `blocked` rejects
+    /// Endpoints are not checked
+    pub fn has_line_of_sight(&self, other: &NodeCoord, blocked: impl Fn(&NodeCoord) -> bool) -> bool {
+        let line = self.line_to(other);
+        line.iter()
+            .skip(1)
+            .take(line.len().saturating_sub(2))
+            .all(|c| !blocked(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_neighbors() {
+        let coord = NodeCoord::new(0, 0);
+        let neighbors = coord.neighbors();
+        assert_eq!(neighbors.len(), 6);
+
+        // All neighbors should be distance 1
+        for neighbor in &neighbors {
+            assert_eq!(coord.distance(neighbor), 1);
+            assert!(coord.is_adjacent(neighbor));
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        let c1 = NodeCoord::new(0, 0);
+        let c2 = NodeCoord::new(2, 1);
+        assert_eq!(c1.distance(&c2), 3);
+
+        let c3 = NodeCoord::new(1, 0);
+        assert_eq!(c1.distance(&c3), 1);
+    }
+
+    #[test]
+    fn test_pixel_roundtrip() {
+        for coord in NodeCoord::new(0, 0).spiral(4) {
+            let (x, y) = coord.to_pixel(30.0);
+            assert_eq!(NodeCoord::from_pixel(x, y, 30.0), coord);
+        }
+    }
+
+    #[test]
+    fn test_ring_and_spiral() {
+        let center = NodeCoord::new(2, -1);
+        assert_eq!(center.ring(0), vec![center]);
+
+        let ring = center.ring(3);
+        assert_eq!(ring.len(), 18);
+        assert!(ring.iter().all(|c| center.distance(c) == 3));
+
+        // 1 + 6 + 12 hexes within radius 2
+        assert_eq!(center.spiral(2).len(), 19);
+    }
+
+    #[test]
+    fn test_far_coordinates_dont_overflow() {
+        let (low, high) = (NodeCoord::new(i32::MIN, 0), NodeCoord::new(i32::MAX, 0));
+        assert_eq!(low.distance(&high), u32::MAX);
+        assert_eq!(NodeCoord::new(i32::MIN, i32::MIN).distance(&NodeCoord::new(i32::MAX, i32::MAX)), u32::MAX);
+
+        assert!(high.ring(1).is_empty());
+        assert!(NodeCoord::new(0, 0).ring(u32::MAX).is_empty());
+        assert_eq!(NodeCoord::new(i32::MAX - 1, 0).ring(1).len(), 6);
+    }
+
+    #[test]
+    fn test_line_to() {
+        let a = NodeCoord::new(0, 0);
+        let b = NodeCoord::new(3, -1);
+        let line = a.line_to(&b);
+
+        assert_eq!(line.len(), 4);
+        assert_eq!(line.first(), Some(&a));
+        assert_eq!(line.last(), Some(&b));
+        assert!(line.windows(2).all(|w| w[0].is_adjacent(&w[1])));
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let a = NodeCoord::new(0, 0);
+        let b = NodeCoord::new(3, 0);
+
+        assert!(a.has_line_of_sight(&b, |_| false));
+        assert!(!a.has_line_of_sight(&b, |c| *c == NodeCoord::new(1, 0)));
+        // Endpoints never block
+        assert!(a.has_line_of_sight(&b, |c| *c == a || *c == b));
+    }
+
+    #[test]
+    fn test_direction_to() {
+        let centre = NodeCoord::new(1, 1);
+        for direction in HexDirection::ALL {
+            let (dq, dr) = direction.offset();
+            assert_eq!(centre.direction_to(&NodeCoord::new(1 + dq, 1 + dr)), Some(direction));
+        }
+        assert_eq!(centre.direction_to(&centre), None);
+        // Further away, the side the line leaves by
+        assert_eq!(centre.direction_to(&NodeCoord::new(4, 1)), Some(HexDirection::East));
+        assert_eq!(centre.direction_to(&NodeCoord::new(1, -2)), Some(HexDirection::NorthWest));
+    }
+}
@@ -0,0 +1,113 @@
+// Consistency checks over GameState
+//
+// Rules that every state reachable through process_event should satisfy. A violation
+// means an event was applied in a way the rules didn't plan for, or one node's state
+// has drifted from the rest - both far cheaper to catch at the entry that caused them
+// than when something visibly desyncs. The worker runs the checks after every applied
+// entry in debug builds and serves them as GET /debug/invariants.
+
+use crate::events::{AttackTarget, NodeCoord};
+use crate::state::GameState;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// One broken invariant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    /// A player's capital hex has no node
+    CapitalMissing { player_id: u64, coord: NodeCoord },
+    /// A node is owned by a player who never joined
+    UnknownOwner { coord: NodeCoord, owner_id: u64 },
+    /// An address is recorded for a hex without a node
+    OrphanIp { coord: NodeCoord },
+    /// A node is ordered to attack itself
+    SelfTarget { coord: NodeCoord },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::CapitalMissing { player_id, coord } => {
+                write!(f, "player {}'s capital {} has no node", player_id, coord)
+            }
+            Violation::UnknownOwner { coord, owner_id } => write!(f, "node {} is owned by unknown player {}", coord, owner_id),
+            Violation::OrphanIp { coord } => write!(f, "IP recorded for {}, which has no node", coord),
+            Violation::SelfTarget { coord } => write!(f, "node {} targets itself", coord),
+        }
+    }
+}
+
+impl GameState {
+    /// Every invariant this state breaks, players first, then nodes in coordinate order
+    pub fn check_invariants(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for player in self.players.values() {
+            if !self.nodes.contains_key(&player.capital_coord) {
+                violations.push(Violation::CapitalMissing { player_id: player.player_id, coord: player.capital_coord });
+            }
+        }
+        for node in self.nodes.values() {
+            if node.owner_id != 0 && !self.players.contains_key(&node.owner_id) {
+                violations.push(Violation::UnknownOwner { coord: node.coord, owner_id: node.owner_id });
+            }
+            if node.current_target == Some(AttackTarget::Coordinate(node.coord)) {
+                violations.push(Violation::SelfTarget { coord: node.coord });
+            }
+        }
+        for coord in self.node_ips.keys() {
+            if !self.nodes.contains_key(coord) {
+                violations.push(Violation::OrphanIp { coord: *coord });
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{CommandEvent, FactEvent, GameEvent};
+
+    #[test]
+    fn test_invariants() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".into(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".into()),
+                client_endpoint: None,
+                timestamp: 0,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        state.process_event(
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(1, 0), owner_id: 0, timestamp: 0 }.into(),
+            2,
+        );
+        assert!(state.check_invariants().is_empty());
+
+        // Corrupt the state directly, the way a divergent apply would
+        let capital = NodeCoord::new(0, 0);
+        state.nodes.get_mut(&capital).unwrap().current_target = Some(AttackTarget::Coordinate(capital));
+        state.nodes.get_mut(&NodeCoord::new(1, 0)).unwrap().owner_id = 9;
+        state.node_ips.insert(NodeCoord::new(5, 5), "10.0.0.5".into());
+        assert_eq!(
+            state.check_invariants(),
+            alloc::vec![
+                Violation::SelfTarget { coord: capital },
+                Violation::UnknownOwner { coord: NodeCoord::new(1, 0), owner_id: 9 },
+                Violation::OrphanIp { coord: NodeCoord::new(5, 5) },
+            ]
+        );
+
+        state.nodes.remove(&capital);
+        assert_eq!(state.check_invariants()[0], Violation::CapitalMissing { player_id: 1, coord: capital });
+        assert_eq!(state.check_invariants()[0].to_string(), "player 1's capital 0,0 has no node");
+    }
+}
@@ -0,0 +1,41 @@
+// camhack-rules - the game rules shared by the servers and the browser
+//
+// Events, hex-grid math, GameState::process_event (and the StateChanges it reports),
+// the capture rule, terrain and the game settings schema, with no dependency on std, tokio or Raft. The worker
+// re-exports these as `worker::game::{events, grid, state}`; `rules-wasm` compiles
+// them for the frontend so it predicts captures with exactly the server's code.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod capture;
+pub mod coord;
+pub mod digest;
+pub mod events;
+pub mod features;
+pub mod grid;
+pub mod invariants;
+pub mod settings;
+pub mod state;
+pub mod terrain;
+pub mod upcast;
+
+pub use capture::{attack_capacity, CapacityTier, CaptureRules, CaptureTracker, TierScale, TierScaling, RULES_PRESETS};
+pub use coord::ParseCoordError;
+pub use events::{
+    AnnouncementSeverity, AttackOrder, AttackTarget, CommandEvent, DefenseOrder, Effect, EliminationPolicy, EventFamily, FactEvent, GameEvent, GamePhase, NodeCoord, NodeType,
+    RefereeOverride, TelemetryEvent, CLUSTER_VERSION,
+};
+pub use features::{Feature, FeatureFlags};
+pub use grid::HexDirection;
+pub use invariants::Violation;
+pub use settings::{GameSettings, SettingError};
+pub use state::{
+    scale_secs, Announcement, AttackError, Attacker, CapitalContest, CaptureBoost, EffectCompletion, EffectRecord, GameEnd, GameState, JoinError, Node,
+    NodeDecay, NodeInitState, Player, ScheduledCommand, StateChange, UnderAttack, ATTACK_METRICS_MAX_AGE_SECS, DEFAULT_RETARGET_COOLDOWN_SECS, DEFEND_RESPONDER_BONUS, MAX_ANNOUNCEMENTS, MAX_ANNOUNCEMENT_LEN,
+    MAX_ANNOUNCEMENT_TTL_SECS, MAX_METRICS_INTERVAL_MS, MAX_SCHEDULED_EVENTS, MAX_TIME_SCALE, MIN_METRICS_INTERVAL_MS,
+    SPAWN_PROTECTION_SECS, CORE_RING, FRONTIER_DISTANCE,
+};
+pub use terrain::{SpawnTier, Terrain, TerrainTile};
+pub use upcast::{variant_version, SchemaChange, Upcast, SCHEMA_CHANGES};
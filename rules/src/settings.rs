@@ -0,0 +1,235 @@
+// Game settings - the typed schema of a game's configuration
+//
+// Workers read their game's config from the environment (GAME_RULES, MAP_RADIUS,
+// TIME_LIMIT_SECS, ...) the master sets when it spawns them; a value they can't use
+// is logged and replaced by its default, which no one creating the game sees.
+// GameSettings is that config as the master takes it (POST /games): `validate` checks
+// every field against the bounds the worker enforces, naming each bad one, and `env`
+// turns a valid one into the variables the worker reads.
+
+use crate::capture::RULES_PRESETS;
+use crate::events::EliminationPolicy;
+use crate::features::FeatureFlags;
+use crate::state::{MAX_METRICS_INTERVAL_MS, MAX_TIME_SCALE, MIN_METRICS_INTERVAL_MS};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Largest terrain radius (hexes from the origin) - generation is quadratic in it
+pub const MAX_MAP_RADIUS: u32 = 64;
+
+/// Shortest time limit a game can have (seconds; 0 = none)
+pub const MIN_TIME_LIMIT_SECS: u64 = 60;
+
+/// Longest time limit a game can have (seconds)
+pub const MAX_TIME_LIMIT_SECS: u64 = 24 * 3600;
+
+/// Longest retarget cooldown a game can commit (seconds)
+pub const MAX_RETARGET_COOLDOWN_SECS: u64 = 300;
+
+/// Bounds on the leader's game logic tick (milliseconds)
+pub const MIN_GAME_TICK_MS: u64 = 100;
+pub const MAX_GAME_TICK_MS: u64 = 10_000;
+
+/// ELIMINATION_POLICY: `keep` (or empty), `captor`, `neutral` or `decay` (one node
+/// every `interval_secs`). An error gives back the unknown name.
+pub fn parse_elimination_policy(name: &str, interval_secs: u64) -> Result<Option<EliminationPolicy>, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "" | "keep" => Ok(None),
+        "captor" => Ok(Some(EliminationPolicy::Captor)),
+        "neutral" => Ok(Some(EliminationPolicy::Neutral)),
+        "decay" => Ok(Some(EliminationPolicy::Decay { interval_secs: interval_secs.max(1) })),
+        _ => Err(name.to_string()),
+    }
+}
+
+/// A game's configuration; anything left out is the worker's default
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameSettings {
+    /// Rules preset (RULES_PRESETS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<String>,
+    /// Terrain radius, 0 = a uniform plane
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map_radius: Option<u32>,
+    /// Terrain seed, random if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map_seed: Option<u64>,
+    /// Game time runs this many times faster than wall time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_scale: Option<u32>,
+    /// Game time until sudden death (seconds, 0 = no limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit_secs: Option<u64>,
+    /// What an eliminated player's nodes do: keep, captor, neutral or decay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elimination_policy: Option<String>,
+    /// Time between two of an eliminated player's nodes decaying (seconds), required by `decay`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elimination_decay_secs: Option<u64>,
+    /// Least time between one node's target changes (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retarget_cooldown_secs: Option<u64>,
+    /// Leader's game logic tick (milliseconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_tick_ms: Option<u64>,
+    /// Interval of the metric samples workers stream to the leader (milliseconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_interval_ms: Option<u64>,
+    /// FEATURES list (`fog_of_war,-sudden_death`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<String>,
+}
+
+/// One field of a GameSettings that can't be used, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingError {
+    pub field: String,
+    pub message: String,
+}
+
+impl SettingError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self { field: field.to_string(), message: message.into() }
+    }
+}
+
+impl fmt::Display for SettingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl GameSettings {
+    /// Every field a worker would refuse or silently replace, in field order
+    pub fn validate(&self) -> Result<(), Vec<SettingError>> {
+        let mut errors = Vec::new();
+        if let Some(rules) = &self.rules {
+            if !RULES_PRESETS.contains(&rules.trim().to_ascii_lowercase().as_str()) {
+                errors.push(SettingError::new("rules", format!("unknown preset '{}' (one of {})", rules, RULES_PRESETS.join(", "))));
+            }
+        }
+        if let Some(radius) = self.map_radius {
+            if radius > MAX_MAP_RADIUS {
+                errors.push(SettingError::new("map_radius", format!("at most {}", MAX_MAP_RADIUS)));
+            }
+        }
+        if self.map_seed.is_some() && self.map_radius.unwrap_or(0) == 0 {
+            errors.push(SettingError::new("map_seed", "needs a map_radius"));
+        }
+        if let Some(scale) = self.time_scale {
+            if !(1..=MAX_TIME_SCALE).contains(&scale) {
+                errors.push(SettingError::new("time_scale", format!("between 1 and {}", MAX_TIME_SCALE)));
+            }
+        }
+        if let Some(secs) = self.time_limit_secs {
+            if secs != 0 && !(MIN_TIME_LIMIT_SECS..=MAX_TIME_LIMIT_SECS).contains(&secs) {
+                errors.push(SettingError::new(
+                    "time_limit_secs",
+                    format!("0 (no limit) or between {} and {}", MIN_TIME_LIMIT_SECS, MAX_TIME_LIMIT_SECS),
+                ));
+            }
+        }
+        if let Some(name) = &self.elimination_policy {
+            match parse_elimination_policy(name, self.elimination_decay_secs.unwrap_or(0)) {
+                Err(_) => errors.push(SettingError::new("elimination_policy", format!("unknown policy '{}' (keep, captor, neutral or decay)", name))),
+                Ok(Some(EliminationPolicy::Decay { .. })) if self.elimination_decay_secs.unwrap_or(0) == 0 => {
+                    errors.push(SettingError::new("elimination_decay_secs", "decay needs a non-zero interval"));
+                }
+                Ok(_) => {}
+            }
+        }
+        if self.elimination_decay_secs.is_some() && !self.elimination_policy.as_deref().is_some_and(|p| p.trim().eq_ignore_ascii_case("decay")) {
+            errors.push(SettingError::new("elimination_decay_secs", "only used by the decay policy"));
+        }
+        if let Some(secs) = self.retarget_cooldown_secs {
+            if secs > MAX_RETARGET_COOLDOWN_SECS {
+                errors.push(SettingError::new("retarget_cooldown_secs", format!("at most {}", MAX_RETARGET_COOLDOWN_SECS)));
+            }
+        }
+        if let Some(ms) = self.game_tick_ms {
+            if !(MIN_GAME_TICK_MS..=MAX_GAME_TICK_MS).contains(&ms) {
+                errors.push(SettingError::new("game_tick_ms", format!("between {} and {}", MIN_GAME_TICK_MS, MAX_GAME_TICK_MS)));
+            }
+        }
+        if let Some(ms) = self.metrics_interval_ms {
+            if !(MIN_METRICS_INTERVAL_MS..=MAX_METRICS_INTERVAL_MS).contains(&ms) {
+                errors.push(SettingError::new(
+                    "metrics_interval_ms",
+                    format!("between {} and {}", MIN_METRICS_INTERVAL_MS, MAX_METRICS_INTERVAL_MS),
+                ));
+            }
+        }
+        if let Some(list) = &self.features {
+            if let Err(name) = FeatureFlags::parse(list) {
+                errors.push(SettingError::new("features", format!("unknown feature '{}'", name)));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The environment variables a worker reads these settings from
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = Vec::new();
+        let mut push = |name, value: Option<String>| {
+            if let Some(value) = value {
+                env.push((name, value));
+            }
+        };
+        push("GAME_RULES", self.rules.clone());
+        push("MAP_RADIUS", self.map_radius.map(|v| v.to_string()));
+        push("MAP_SEED", self.map_seed.map(|v| v.to_string()));
+        push("TIME_SCALE", self.time_scale.map(|v| v.to_string()));
+        push("TIME_LIMIT_SECS", self.time_limit_secs.map(|v| v.to_string()));
+        push("ELIMINATION_POLICY", self.elimination_policy.clone());
+        push("ELIMINATION_DECAY_SECS", self.elimination_decay_secs.map(|v| v.to_string()));
+        push("RETARGET_COOLDOWN_SECS", self.retarget_cooldown_secs.map(|v| v.to_string()));
+        push("GAME_TICK_MS", self.game_tick_ms.map(|v| v.to_string()));
+        push("LIVE_METRICS_MS", self.metrics_interval_ms.map(|v| v.to_string()));
+        push("FEATURES", self.features.clone());
+        env
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_settings() {
+        assert_eq!(GameSettings::default().validate(), Ok(()));
+        let settings: GameSettings = serde_json::from_str(
+            r#"{"rules": "Blitz", "map_radius": 8, "map_seed": 7, "time_limit_secs": 900,
+                "elimination_policy": "decay", "elimination_decay_secs": 5, "features": "fog_of_war"}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.validate(), Ok(()));
+        assert!(settings.env().contains(&("ELIMINATION_DECAY_SECS", "5".to_string())));
+
+        let bad = GameSettings {
+            rules: Some("chess".to_string()),
+            map_radius: Some(500),
+            time_scale: Some(0),
+            time_limit_secs: Some(5),
+            elimination_policy: Some("decay".to_string()),
+            game_tick_ms: Some(1),
+            features: Some("teleports".to_string()),
+            ..GameSettings::default()
+        };
+        let fields: Vec<String> = bad.validate().unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(
+            fields,
+            ["rules", "map_radius", "time_scale", "time_limit_secs", "elimination_decay_secs", "game_tick_ms", "features"]
+        );
+
+        // Typos don't pass as defaults
+        assert!(serde_json::from_str::<GameSettings>(r#"{"map_raduis": 8}"#).is_err());
+    }
+}
@@ -0,0 +1,2589 @@
+use crate::events::{
+    AnnouncementSeverity, AttackTarget, CommandEvent, DefenseOrder, Effect, EliminationPolicy, FactEvent, GameEvent, GamePhase, NodeCoord, NodeType,
+    RefereeOverride, TelemetryEvent,
+};
+use crate::features::{Feature, FeatureFlags};
+use crate::grid::HexDirection;
+use crate::terrain::{SpawnTier, Terrain};
+use alloc::collections::btree_map::Entry;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// How long a new player's capital can't be targeted or captured (seconds)
+pub const SPAWN_PROTECTION_SECS: u64 = 60;
+
+/// Generous, so a brief gap in the metrics stream doesn't blank the warning
+pub const ATTACK_METRICS_MAX_AGE_SECS: u64 = 15;
+
+/// Fastest a game's clock can run (see FactEvent::TimeScaleSet)
+pub const MAX_TIME_SCALE: u32 = 60;
+
+/// Least time between one node's target changes (seconds), unless the game commits
+/// its own (FactEvent::RetargetCooldownSet) - keeps flooders from being torn down
+/// and set up again many times a second, and the log from filling with retargets
+pub const DEFAULT_RETARGET_COOLDOWN_SECS: u64 = 2;
+
+/// Extra responder capacity a defend order buys, as a share of the node's own
+/// (1.0 doubles it); an assisting node splits it with the node it assists
+pub const DEFEND_RESPONDER_BONUS: f32 = 1.0;
+
+/// Most commands a game can have scheduled at once (see CommandEvent::ScheduleEvent)
+pub const MAX_SCHEDULED_EVENTS: usize = 256;
+
+/// Longest an announcement may show (seconds)
+pub const MAX_ANNOUNCEMENT_TTL_SECS: u64 = 3600;
+
+/// Longest announcement text (characters)
+pub const MAX_ANNOUNCEMENT_LEN: usize = 280;
+
+/// Most announcements a game keeps at once; a new one pushes out the oldest
+pub const MAX_ANNOUNCEMENTS: usize = 16;
+
+/// Hexes this close to a capital spawn Large: the ring a capital is defended from
+pub const CORE_RING: u32 = 1;
+
+/// Hexes at least this far from every capital are the frontier and spawn Small
+pub const FRONTIER_DISTANCE: u32 = 4;
+
+/// Bounds on a committed metrics interval (see FactEvent::MetricsIntervalSet) - any
+/// slower and the leader's samples would go stale between arrivals
+pub const MIN_METRICS_INTERVAL_MS: u64 = 50;
+pub const MAX_METRICS_INTERVAL_MS: u64 = 1000;
+
+/// `secs` of wall time at `time_scale`x game speed
+/// Rounded up, so a non-zero duration stays at least 1s and 0 stays 0.
+pub fn scale_secs(secs: u64, time_scale: u32) -> u64 {
+    secs.div_ceil(u64::from(time_scale.max(1)))
+}
+
+/// Player state
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub player_id: u64,
+    pub name: String,
+    pub capital_coord: NodeCoord,
+    /// Player's client (laptop), off the grid - attacked once the player is dead
+    pub client_endpoint: Option<String>,
+    pub alive: bool,
+    pub join_time: u64,
+    /// Capital is spawn-protected until this time (unix seconds)
+    pub protected_until: u64,
+    /// Latest ClientHeartbeat from the player's client (the join time until one arrives)
+    pub last_heartbeat: u64,
+    /// When the client was declared gone (see OwnerIdle), None while it heartbeats
+    pub idle_since: Option<u64>,
+    /// The idle player's regular nodes have been made neutral
+    pub idle_neutralized: bool,
+    /// SHA-256 (hex) of the token the player's client reconnects with, None if it can't
+    pub reconnect_key: Option<String>,
+}
+
+impl Player {
+    /// Seconds of spawn protection left at `now`, None once it's over
+    pub fn spawn_protection_remaining(&self, now: u64) -> Option<u64> {
+        Some(self.protected_until.saturating_sub(now)).filter(|secs| *secs > 0)
+    }
+}
+
+/// Node initialization state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeInitState {
+    /// EC2 task is spawning, no IP available yet
+    Initializing,
+    /// EC2 instance is running and has IP address
+    Ready,
+}
+
+/// Node on the grid
+/// Capacity is determined by actual EC2 instance type, not hardcoded
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub coord: NodeCoord,
+    pub owner_id: u64,
+    pub node_type: NodeType,
+    pub current_target: Option<AttackTarget>,  // What this node is attacking
+    pub init_state: NodeInitState,  // Whether EC2 is ready
+}
+
+/// Metrics for a node at a point in time
+#[derive(Debug, Clone)]
+pub struct NodeMetrics {
+    pub bandwidth_in: u64,
+    pub packet_loss: f32,
+    pub timestamp: u64,
+    /// Attackers waiting for a flooder slot on this node (see NetworkBudget)
+    pub queued_attackers: Vec<NodeCoord>,
+    /// The node's own CPU use (0.0 to 1.0), None unless its worker reports it
+    pub cpu_utilization: Option<f32>,
+    /// Share of the node's memory in use (0.0 to 1.0), None unless its worker reports it
+    pub memory_pressure: Option<f32>,
+}
+
+/// Derived attack information
+#[derive(Debug, Clone)]
+pub struct Attack {
+    pub attacker_node: NodeCoord,
+    pub attacker_owner: u64,
+    pub target_node: NodeCoord,
+    pub target_owner: u64,
+}
+
+/// Combined-arms speed-up currently applied to a target's capture timer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureBoost {
+    /// Player the capture is going to
+    pub attacker_id: u64,
+    /// Capturing player's adjacent nodes attacking the target
+    pub attackers: u32,
+    /// How much faster the capture timer runs (> 1.0)
+    pub multiplier: f32,
+    /// Overload time needed to capture with this boost
+    pub required_secs: u64,
+}
+
+/// A node being attacked by another player, as its owner is warned about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnderAttack {
+    pub owner_id: u64,
+    pub attackers: Vec<Attacker>,
+    /// Packet loss on the node (0.0 to 1.0), None without recent inbound metrics
+    pub packet_loss: Option<f32>,
+}
+
+/// One node attacking an UnderAttack node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attacker {
+    pub coord: NodeCoord,
+    pub owner_id: u64,
+    /// Side of the target the attack comes from
+    pub direction: Option<HexDirection>,
+}
+
+/// A lost capital its former owner can still take back (see CapitalContested)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapitalContest {
+    pub player_id: u64,
+    /// When the owner is eliminated unless they hold the capital again
+    pub expires_at: u64,
+}
+
+/// An eliminated player's nodes decaying to neutral (EliminationPolicy::Decay)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeDecay {
+    /// When the player was eliminated
+    pub since: u64,
+    /// Nodes that have decayed so far
+    pub decayed: u32,
+}
+
+/// A side effect the leader was asked to run, and whether it has
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectRecord {
+    pub effect: Effect,
+    pub requested_at: u64,
+    /// None until the leader commits EffectCompleted
+    pub completion: Option<EffectCompletion>,
+}
+
+/// How a requested effect finished
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectCompletion {
+    pub error: Option<String>,
+    pub timestamp: u64,
+}
+
+/// A command waiting for its time (CommandEvent::ScheduleEvent)
+#[derive(Debug, Clone)]
+pub struct ScheduledCommand {
+    /// When the leader fires it (unix seconds)
+    pub execute_at: u64,
+    pub command: CommandEvent,
+    pub scheduled_at: u64,
+}
+
+/// A banner every player sees until it expires (FactEvent::Announcement)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    pub text: String,
+    pub severity: AnnouncementSeverity,
+    pub posted_at: u64,
+    /// Wall time, not game time: it's for people to read
+    pub expires_at: u64,
+}
+
+/// A finished game being shut down (see GameEnded), and who has acknowledged it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameEnd {
+    pub winner_id: Option<u64>,
+    pub ended_at: u64,
+    /// Raft members the leader waits for
+    pub members: BTreeSet<u64>,
+    /// Members that committed GameOverAcknowledged
+    pub acknowledged: BTreeSet<u64>,
+}
+
+impl GameEnd {
+    /// Members yet to acknowledge, in id order
+    pub fn pending(&self) -> Vec<u64> {
+        self.members.difference(&self.acknowledged).copied().collect()
+    }
+}
+
+/// Why an attack order is invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackError {
+    /// No node at the attacking coordinate
+    UnknownNode(NodeCoord),
+    /// The attacking node belongs to someone else
+    NotOwner,
+    /// Attacks only reach neighbouring hexes
+    NotAdjacent,
+    /// The target hex is a chasm - no node can ever stand there
+    Impassable,
+    /// The target is a new player's capital, still spawn-protected
+    SpawnProtected { remaining_secs: u64 },
+    /// The node changed target too recently (see retarget_cooldown)
+    Cooldown { remaining_secs: u64 },
+    /// A defend order can only assist a neighbouring node of the same owner
+    InvalidAssist,
+}
+
+impl core::fmt::Display for AttackError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AttackError::UnknownNode(coord) => write!(f, "Node ({}, {}) does not exist", coord.q, coord.r),
+            AttackError::NotOwner => write!(f, "You don't own this node"),
+            AttackError::NotAdjacent => write!(f, "Target must be adjacent to the attacking node"),
+            AttackError::Impassable => write!(f, "Target hex is impassable terrain"),
+            AttackError::SpawnProtected { remaining_secs } => {
+                write!(f, "Target capital is spawn-protected for another {}s", remaining_secs)
+            }
+            AttackError::Cooldown { remaining_secs } => {
+                write!(f, "Node changed target too recently, wait another {}s", remaining_secs)
+            }
+            AttackError::InvalidAssist => write!(f, "Can only assist an adjacent node you own"),
+        }
+    }
+}
+
+/// Why a join or reconnect is refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinError {
+    /// A player still in the game already goes by this name
+    NameTaken,
+    /// The player is already in the game - its client has to reconnect instead
+    AlreadyJoined,
+    /// No such player to reconnect as
+    UnknownPlayer(u64),
+    /// The player joined without a reconnect key
+    NotReconnectable,
+    /// The token doesn't match the player's reconnect key
+    WrongToken,
+    /// The game is in sudden death or finished - too late to join
+    Closed(GamePhase),
+}
+
+impl core::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            JoinError::NameTaken => write!(f, "Another player in this game already has that name"),
+            JoinError::AlreadyJoined => write!(f, "Already in this game - reconnect instead"),
+            JoinError::UnknownPlayer(player_id) => write!(f, "Player {} is not in this game", player_id),
+            JoinError::NotReconnectable => write!(f, "This player can't reconnect"),
+            JoinError::WrongToken => write!(f, "Wrong reconnect token"),
+            JoinError::Closed(phase) => write!(f, "The game is no longer open to new players ({})", phase.name()),
+        }
+    }
+}
+
+/// What applying one event changed, as `GameState::process_event` reports it
+/// Only real changes are reported: re-sending a node its current target, or a
+/// heartbeat from a client that never went idle, reports nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChange {
+    PlayerAdded { player_id: u64 },
+    PlayerEliminated { player_id: u64 },
+    /// A referee handed an eliminated player their capital back
+    PlayerRevived { player_id: u64 },
+    /// The player's client went idle (OwnerIdle) or came back (ClientHeartbeat)
+    PlayerIdleChanged { player_id: u64, idle: bool },
+    /// A restarted client took the player back (PlayerReconnected)
+    PlayerReconnected { player_id: u64 },
+    /// A capital, or a placeholder for a lazily-initialized node
+    NodeAdded { coord: NodeCoord, owner_id: u64 },
+    /// The node's worker came up at `ip`
+    NodeReady { coord: NodeCoord, ip: String },
+    /// A placeholder whose worker never came up was dropped
+    NodeRemoved { coord: NodeCoord },
+    NodeOwnerChanged { coord: NodeCoord, old_owner_id: u64, new_owner_id: u64 },
+    NodeTargetChanged { coord: NodeCoord, target: Option<AttackTarget> },
+    NodeDefenseChanged { coord: NodeCoord, defense: Option<DefenseOrder> },
+    /// A capture boost on the node started, changed or ended
+    CaptureBoostChanged { coord: NodeCoord },
+    CapitalContested { coord: NodeCoord, player_id: u64 },
+    MapGenerated,
+    /// At most one player is left alive
+    GameOver,
+    /// The game moved to another phase (a revive can move it back)
+    PhaseChanged { phase: GamePhase },
+    /// The leader started shutting the game down (GameEnded)
+    GameEnded { winner_id: Option<u64> },
+    GameOverAcknowledged { raft_node_id: u64 },
+    /// A command was queued (ScheduleEvent)
+    EventScheduled { schedule_id: u64, execute_at: u64 },
+    /// A queued command left the queue, applied unless `refused`
+    ScheduledEventFired { schedule_id: u64, refused: bool },
+    /// A banner went up (id = log index of the Announcement)
+    AnnouncementPosted { announcement_id: u64 },
+}
+
+/// Complete game state derived from events
+/// Ordered maps, so iteration (and any rule that picks "the first" node) is the
+/// same on every server and in the browser
+#[derive(Debug, Clone)]
+pub struct GameState {
+    /// All players
+    pub players: BTreeMap<u64, Player>,
+    /// All nodes on the grid (coord -> node)
+    pub nodes: BTreeMap<NodeCoord, Node>,
+    /// Latest metrics for each node
+    pub node_metrics: BTreeMap<NodeCoord, NodeMetrics>,
+    /// IP addresses of nodes (coord -> IP)
+    pub node_ips: BTreeMap<NodeCoord, String>,
+    /// Targets whose capture is sped up by combined arms (for the UI)
+    pub capture_boosts: BTreeMap<NodeCoord, CaptureBoost>,
+    /// Side effects requested by the leader (id = log index of the request)
+    pub effects: BTreeMap<u64, EffectRecord>,
+    /// Capitals in their grace window (coord -> former owner and deadline)
+    pub contested_capitals: BTreeMap<NodeCoord, CapitalContest>,
+    /// Nodes holding a defend order instead of attacking
+    pub defenses: BTreeMap<NodeCoord, DefenseOrder>,
+    /// Generated terrain, non-plain hexes only (empty = uniform plane)
+    pub terrain: BTreeMap<NodeCoord, Terrain>,
+    /// Seed of the committed map, None until MapGenerated
+    pub map_seed: Option<u64>,
+    /// Leader clock at the newest stamped entry (unix seconds, 0 until one is applied)
+    pub clock: u64,
+    /// How many times faster than wall time the game runs (1 until TimeScaleSet)
+    pub time_scale: u32,
+    /// Committed retarget cooldown (seconds), None = DEFAULT_RETARGET_COOLDOWN_SECS
+    pub retarget_cooldown_secs: Option<u64>,
+    /// When each node last took a new target
+    pub retargeted_at: BTreeMap<NodeCoord, u64>,
+    /// Committed elimination policy, None = an eliminated player keeps their nodes
+    pub elimination_policy: Option<EliminationPolicy>,
+    /// Eliminated players whose nodes are decaying (player -> progress)
+    pub decaying: BTreeMap<u64, NodeDecay>,
+    /// Where the game is in its lifecycle, kept current after every event
+    pub phase: GamePhase,
+    /// When the second player joined, None while in the lobby
+    pub live_since: Option<u64>,
+    /// When sudden death was committed (PhaseChanged), None until then
+    pub sudden_death_since: Option<u64>,
+    /// Committed feature set, None = FeatureFlags::default() (see `features()`)
+    pub features: Option<FeatureFlags>,
+    /// Commands waiting for their time (id = log index of the ScheduleEvent)
+    pub scheduled: BTreeMap<u64, ScheduledCommand>,
+    /// Banners posted and not yet pushed out (id = log index), expired ones included
+    /// until the next announcement - see `active_announcements`
+    pub announcements: BTreeMap<u64, Announcement>,
+    /// Committed metrics streaming interval (ms), None = each worker's LIVE_METRICS_MS
+    pub metrics_interval_ms: Option<u64>,
+    /// Committed cluster version (ClusterVersionSet), None = 1 - see `cluster_version()`
+    pub cluster_version: Option<u32>,
+    /// Last applied log index
+    pub last_applied_log_index: u64,
+    /// Game is over (only one player remaining)
+    pub game_over: bool,
+    /// Shutdown in progress, None until the leader commits GameEnded
+    pub game_end: Option<GameEnd>,
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        Self {
+            players: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            node_metrics: BTreeMap::new(),
+            node_ips: BTreeMap::new(),
+            capture_boosts: BTreeMap::new(),
+            effects: BTreeMap::new(),
+            contested_capitals: BTreeMap::new(),
+            defenses: BTreeMap::new(),
+            terrain: BTreeMap::new(),
+            map_seed: None,
+            clock: 0,
+            time_scale: 1,
+            retarget_cooldown_secs: None,
+            retargeted_at: BTreeMap::new(),
+            elimination_policy: None,
+            decaying: BTreeMap::new(),
+            phase: GamePhase::Lobby,
+            live_since: None,
+            sudden_death_since: None,
+            features: None,
+            scheduled: BTreeMap::new(),
+            announcements: BTreeMap::new(),
+            metrics_interval_ms: None,
+            cluster_version: None,
+            last_applied_log_index: 0,
+            game_over: false,
+            game_end: None,
+        }
+    }
+
+    /// Process a game event and update state, trusting the event's own timestamps
+    /// (offline replays and tests; the Raft log goes through `process_committed`)
+    /// Returns what the event changed, in the order it changed it.
+    pub fn process_event(&mut self, event: GameEvent, log_index: u64) -> Vec<StateChange> {
+        self.apply(event, log_index, None)
+    }
+
+    /// Process an event the leader stamped with `committed_at` (unix seconds)
+    ///
+    /// The stamp stands in for the event's own timestamp wherever the rules keep
+    /// time - joins and spawn protection, heartbeats, metrics, effects, overrides -
+    /// so a client or node with a skewed clock can't bend a timer. The clock never
+    /// runs backwards: a new leader whose clock lags is held at the last stamp.
+    pub fn process_committed(&mut self, event: GameEvent, log_index: u64, committed_at: u64) -> Vec<StateChange> {
+        self.clock = self.clock.max(committed_at);
+        let stamp = Some(self.clock).filter(|t| *t > 0);
+        self.apply(event, log_index, stamp)
+    }
+
+    /// Why `event` can't apply to this state, if it can't: it names a player, node,
+    /// effect or queued command the state doesn't have, so processing it would
+    /// change nothing. The rules still take such an event (as a no-op) - this lets
+    /// the caller record it instead of skipping it silently. Events that are
+    /// refused by a rule (a retarget inside its cooldown) or simply change nothing
+    /// (a repeated target) aren't dead letters.
+    pub fn skip_reason(&self, event: &GameEvent) -> Option<&'static str> {
+        let unknown_node = |coord: &NodeCoord| !self.nodes.contains_key(coord);
+        let unknown_player = |player_id: &u64| !self.players.contains_key(player_id);
+        match event {
+            GameEvent::Command(CommandEvent::PlayerReconnected { player_id, .. })
+            | GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id, .. })
+            | GameEvent::Fact(FactEvent::OwnerIdle { player_id, .. })
+            | GameEvent::Fact(FactEvent::RefereeAction { action: RefereeOverride::ExtendTime { player_id, .. }, .. }) => {
+                unknown_player(player_id).then_some("unknown player")
+            }
+            GameEvent::Command(CommandEvent::SetNodeTarget { node_coord, .. })
+            | GameEvent::Command(CommandEvent::SetNodeDefense { node_coord, .. })
+            | GameEvent::Fact(FactEvent::NodeCaptured { node_coord, .. })
+            | GameEvent::Fact(FactEvent::NodeInitializationComplete { node_coord, .. })
+            | GameEvent::Fact(FactEvent::RefereeAction { action: RefereeOverride::RevertCapture { node_coord, .. }, .. })
+            | GameEvent::Fact(FactEvent::RefereeAction { action: RefereeOverride::NeutralizeNode { node_coord }, .. }) => {
+                unknown_node(node_coord).then_some("unknown node")
+            }
+            GameEvent::Command(CommandEvent::SetNodeTargets { orders, .. }) => {
+                let all_unknown = !orders.is_empty() && orders.iter().all(|order| unknown_node(&order.node_coord));
+                all_unknown.then_some("unknown nodes")
+            }
+            GameEvent::Fact(FactEvent::NodeDecayed { player_id, .. }) => {
+                (!self.decaying.contains_key(player_id)).then_some("player's nodes aren't decaying")
+            }
+            GameEvent::Fact(FactEvent::EffectCompleted { effect_id, .. }) => {
+                (!self.effects.contains_key(effect_id)).then_some("unknown effect")
+            }
+            GameEvent::Fact(FactEvent::ScheduledEventFired { schedule_id, .. }) => {
+                (!self.scheduled.contains_key(schedule_id)).then_some("unknown scheduled event")
+            }
+            GameEvent::Fact(FactEvent::GameOverAcknowledged { .. }) => {
+                self.game_end.is_none().then_some("no shutdown in progress")
+            }
+            _ => None,
+        }
+    }
+
+    fn apply(&mut self, event: GameEvent, log_index: u64, stamp: Option<u64>) -> Vec<StateChange> {
+        self.last_applied_log_index = log_index;
+        let was_over = self.game_over;
+        let mut changes = Vec::new();
+        self.apply_event(event, log_index, stamp, &mut changes);
+
+        if self.game_over && !was_over {
+            changes.push(StateChange::GameOver);
+        }
+        let phase = self.derive_phase();
+        if phase != self.phase {
+            self.phase = phase;
+            changes.push(StateChange::PhaseChanged { phase });
+        }
+        changes
+    }
+
+    /// One event's own rules (`apply` does the bookkeeping every event shares)
+    fn apply_event(&mut self, event: GameEvent, log_index: u64, stamp: Option<u64>, changes: &mut Vec<StateChange>) {
+        let at = |claimed: u64| stamp.unwrap_or(claimed);
+        match event {
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id,
+                name,
+                capital_coord,
+                capital_ip,
+                client_endpoint,
+                timestamp,
+                reconnect_key,
+                ..
+            }) => {
+                // Create player
+                let player = Player {
+                    player_id,
+                    name,
+                    capital_coord,
+                    client_endpoint,
+                    alive: true,
+                    join_time: at(timestamp),
+                    protected_until: at(timestamp).saturating_add(self.scaled_secs(SPAWN_PROTECTION_SECS)),
+                    last_heartbeat: at(timestamp),
+                    idle_since: None,
+                    idle_neutralized: false,
+                    reconnect_key,
+                };
+                self.players.insert(player_id, player);
+                changes.push(StateChange::PlayerAdded { player_id });
+                if self.live_since.is_none() && self.players.len() >= 2 {
+                    self.live_since = Some(at(timestamp));
+                }
+
+                // Create capital node (capacity determined by EC2 instance type)
+                // Always a grid worker - the client never occupies a hex. Until the
+                // capital worker reports in it's Initializing, like a lazy node.
+                let capital = Node {
+                    coord: capital_coord,
+                    owner_id: player_id,
+                    node_type: NodeType::Capital,
+                    current_target: None,
+                    init_state: if capital_ip.is_some() { NodeInitState::Ready } else { NodeInitState::Initializing },
+                };
+                self.nodes.insert(capital_coord, capital);
+                changes.push(StateChange::NodeAdded { coord: capital_coord, owner_id: player_id });
+
+                // Store IP address
+                if let Some(ip) = capital_ip {
+                    self.node_ips.insert(capital_coord, ip.clone());
+                    changes.push(StateChange::NodeReady { coord: capital_coord, ip });
+                }
+            }
+
+            GameEvent::Command(CommandEvent::PlayerReconnected { player_id, next_key, client_endpoint, timestamp, .. }) => {
+                // The leader checked the token; the player is back, and next time it takes next_key's
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    player.reconnect_key = Some(next_key);
+                    player.client_endpoint = client_endpoint;
+                    player.last_heartbeat = player.last_heartbeat.max(at(timestamp));
+                    if player.idle_since.take().is_some() {
+                        changes.push(StateChange::PlayerIdleChanged { player_id, idle: false });
+                    }
+                    player.idle_neutralized = false;
+                    changes.push(StateChange::PlayerReconnected { player_id });
+                }
+            }
+
+            GameEvent::Command(CommandEvent::SetNodeTarget {
+                node_coord,
+                target,
+                timestamp,
+            }) => {
+                self.retarget(node_coord, target, at(timestamp), changes);
+            }
+
+            GameEvent::Command(CommandEvent::SetNodeTargets { orders, timestamp }) => {
+                for order in orders {
+                    self.retarget(order.node_coord, order.target, at(timestamp), changes);
+                }
+            }
+
+            GameEvent::Command(CommandEvent::SetNodeDefense { node_coord, defense, .. }) => {
+                if self.nodes.contains_key(&node_coord) {
+                    // Its capacity goes to the responder, none is left for flooding
+                    if defense.is_some() {
+                        self.set_target(node_coord, None, changes);
+                    }
+                    self.set_defense(node_coord, defense, changes);
+                }
+            }
+
+            GameEvent::Fact(FactEvent::NodeCaptured {
+                node_coord,
+                new_owner_id,
+                timestamp,
+            }) => {
+                let mut eliminated = None;
+                if self.nodes.contains_key(&node_coord) {
+                    self.set_target(node_coord, None, changes);  // Stop attacking when captured
+                    self.set_defense(node_coord, None, changes);
+                    self.clear_boost(node_coord, changes);
+                }
+                if let Some(node) = self.nodes.get_mut(&node_coord) {
+                    let old_owner_id = node.owner_id;
+                    node.owner_id = new_owner_id;
+                    if old_owner_id != new_owner_id {
+                        changes.push(StateChange::NodeOwnerChanged { coord: node_coord, old_owner_id, new_owner_id });
+                    }
+                    self.retargeted_at.remove(&node_coord);
+
+                    let contest = self.contested_capitals.get(&node_coord).copied();
+                    if node.node_type == NodeType::Capital {
+                        // Convert capital to regular node for new owner
+                        // (EC2 instance stays the same size - still has capital-level resources)
+                        node.node_type = NodeType::Regular;
+
+                        // If this was a capital, the old owner loses - unless it's
+                        // contested, when they get until the deadline to win it back
+                        if contest.map_or(true, |c| c.player_id != old_owner_id) {
+                            eliminated = Some(old_owner_id);
+                        }
+                    } else if contest.is_some_and(|c| c.player_id == new_owner_id) {
+                        // Last stand held: the capital is theirs again
+                        node.node_type = NodeType::Capital;
+                        self.contested_capitals.remove(&node_coord);
+                    }
+                }
+                if let Some(player_id) = eliminated {
+                    self.eliminate(player_id, at(timestamp), changes);
+                }
+            }
+
+            GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                node_coord,
+                bandwidth_in,
+                packet_loss,
+                timestamp,
+                queued_attackers,
+            }) => {
+                let metrics = NodeMetrics {
+                    bandwidth_in,
+                    packet_loss,
+                    timestamp: at(timestamp),
+                    queued_attackers,
+                    cpu_utilization: None,
+                    memory_pressure: None,
+                };
+                self.node_metrics.insert(node_coord, metrics);
+            }
+
+            GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id, timestamp }) => {
+                // The client is back (or never left) - nodes already neutralized stay lost
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    player.last_heartbeat = player.last_heartbeat.max(at(timestamp));
+                    if player.idle_since.take().is_some() {
+                        changes.push(StateChange::PlayerIdleChanged { player_id, idle: false });
+                    }
+                    player.idle_neutralized = false;
+                }
+            }
+
+            GameEvent::Fact(FactEvent::NodeInitializationStarted {
+                node_coord,
+                owner_id,
+                ..
+            }) => {
+                // Only insert if node doesn't already exist (deduplication)
+                // Create placeholder node in Initializing state
+                if let Entry::Vacant(entry) = self.nodes.entry(node_coord) {
+                    entry.insert(Node {
+                        coord: node_coord,
+                        owner_id,
+                        node_type: NodeType::Regular,  // Lazily initialized nodes are regular
+                        current_target: None,
+                        init_state: NodeInitState::Initializing,
+                    });
+                    changes.push(StateChange::NodeAdded { coord: node_coord, owner_id });
+                }
+            }
+
+            GameEvent::Fact(FactEvent::NodeInitializationComplete {
+                node_coord,
+                node_ip,
+                ..
+            }) => {
+                // Update node to Ready state and store IP
+                let mut changed = self.node_ips.get(&node_coord) != Some(&node_ip);
+                if let Some(node) = self.nodes.get_mut(&node_coord) {
+                    changed |= node.init_state != NodeInitState::Ready;
+                    node.init_state = NodeInitState::Ready;
+                }
+                self.node_ips.insert(node_coord, node_ip.clone());
+                if changed {
+                    changes.push(StateChange::NodeReady { coord: node_coord, ip: node_ip });
+                }
+            }
+
+            GameEvent::Fact(FactEvent::NodeInitializationFailed { node_coord, .. }) => {
+                // Drop the placeholder so the hex is free again, and stop anyone
+                // waiting to attack a node that will never come up
+                let still_initializing = self
+                    .nodes
+                    .get(&node_coord)
+                    .is_some_and(|n| n.init_state == NodeInitState::Initializing);
+
+                if still_initializing {
+                    self.clear_boost(node_coord, changes);
+                    self.nodes.remove(&node_coord);
+                    changes.push(StateChange::NodeRemoved { coord: node_coord });
+                    let waiting: Vec<NodeCoord> = self
+                        .nodes
+                        .values()
+                        .filter(|n| n.current_target == Some(AttackTarget::Coordinate(node_coord)))
+                        .map(|n| n.coord)
+                        .collect();
+                    for coord in waiting {
+                        self.set_target(coord, None, changes);
+                    }
+                }
+            }
+
+            GameEvent::Fact(FactEvent::CaptureBoostChanged {
+                node_coord,
+                attacker_id,
+                attackers,
+                multiplier,
+                required_secs,
+                ..
+            }) => {
+                if multiplier > 1.0 {
+                    let boost = CaptureBoost { attacker_id, attackers, multiplier, required_secs };
+                    if self.capture_boosts.insert(node_coord, boost) != Some(boost) {
+                        changes.push(StateChange::CaptureBoostChanged { coord: node_coord });
+                    }
+                } else {
+                    self.clear_boost(node_coord, changes);
+                }
+            }
+
+            GameEvent::Fact(FactEvent::EffectRequested { effect, timestamp }) => {
+                let record = EffectRecord { effect, requested_at: at(timestamp), completion: None };
+                self.effects.insert(log_index, record);
+            }
+
+            GameEvent::Fact(FactEvent::EffectCompleted { effect_id, error, timestamp }) => {
+                // First acknowledgment wins - a late duplicate from a deposed leader is ignored
+                if let Some(record) = self.effects.get_mut(&effect_id) {
+                    record.completion.get_or_insert(EffectCompletion { error, timestamp: at(timestamp) });
+                }
+            }
+
+            GameEvent::Fact(FactEvent::CapitalContested { node_coord, player_id, expires_at, .. }) => {
+                // A newer contest for the same hex replaces the old one
+                self.contested_capitals.insert(node_coord, CapitalContest { player_id, expires_at });
+                changes.push(StateChange::CapitalContested { coord: node_coord, player_id });
+            }
+
+            GameEvent::Fact(FactEvent::PlayerEliminated { player_id, timestamp }) => {
+                self.eliminate(player_id, at(timestamp), changes);
+            }
+
+            GameEvent::Fact(FactEvent::MapGenerated { seed, tiles, .. }) => {
+                self.terrain = tiles
+                    .into_iter()
+                    .filter(|tile| tile.terrain != Terrain::Plain)
+                    .map(|tile| (tile.coord, tile.terrain))
+                    .collect();
+                self.map_seed = Some(seed);
+                changes.push(StateChange::MapGenerated);
+            }
+
+            GameEvent::Fact(FactEvent::RefereeAction { action, timestamp, .. }) => {
+                self.apply_override(action, at(timestamp), changes);
+            }
+
+            GameEvent::Fact(FactEvent::OwnerIdle { player_id, neutralize, timestamp }) => {
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    if player.idle_since.is_none() {
+                        player.idle_since = Some(at(timestamp));
+                        changes.push(StateChange::PlayerIdleChanged { player_id, idle: true });
+                    }
+                    player.idle_neutralized |= neutralize;
+                }
+
+                // Nobody is left to steer these nodes - stop them attacking on old orders
+                let owned: Vec<(NodeCoord, NodeType)> = self
+                    .nodes
+                    .values()
+                    .filter(|n| n.owner_id == player_id)
+                    .map(|n| (n.coord, n.node_type))
+                    .collect();
+                for (coord, node_type) in owned {
+                    self.set_target(coord, None, changes);
+                    self.set_defense(coord, None, changes);
+                    if neutralize && node_type == NodeType::Regular {
+                        self.set_owner(coord, 0, changes);
+                        self.clear_boost(coord, changes);
+                    }
+                }
+            }
+
+            GameEvent::Fact(FactEvent::TimeScaleSet { scale, .. }) => {
+                // Set once - a later leader configured differently can't change speed mid-game
+                if self.time_scale == 1 {
+                    self.time_scale = scale.clamp(1, MAX_TIME_SCALE);
+                }
+            }
+
+            GameEvent::Fact(FactEvent::RetargetCooldownSet { secs, .. }) => {
+                self.retarget_cooldown_secs.get_or_insert(secs);
+            }
+
+            GameEvent::Fact(FactEvent::MetricsIntervalSet { interval_ms, .. }) => {
+                self.metrics_interval_ms.get_or_insert(interval_ms.clamp(MIN_METRICS_INTERVAL_MS, MAX_METRICS_INTERVAL_MS));
+            }
+
+            GameEvent::Fact(FactEvent::ClusterVersionSet { version, .. }) => {
+                // Only ever raised - members that upgraded don't go back
+                if version > self.cluster_version() {
+                    self.cluster_version = Some(version);
+                }
+            }
+
+            GameEvent::Fact(FactEvent::EliminationPolicySet { policy, .. }) => {
+                self.elimination_policy.get_or_insert(policy);
+            }
+
+            GameEvent::Fact(FactEvent::NodeDecayed { node_coord, player_id, .. }) => {
+                // Only while it's still theirs - a node captured meanwhile doesn't count
+                let theirs = self
+                    .nodes
+                    .get(&node_coord)
+                    .is_some_and(|n| n.owner_id == player_id && n.node_type != NodeType::Capital);
+                if let Some(decay) = self.decaying.get_mut(&player_id).filter(|_| theirs) {
+                    decay.decayed += 1;
+                    self.hand_over(node_coord, 0, changes);
+                }
+                if !self.nodes.values().any(|n| n.owner_id == player_id) {
+                    self.decaying.remove(&player_id);
+                }
+            }
+
+            GameEvent::Fact(FactEvent::PhaseChanged { phase, timestamp }) => {
+                // Lobby, live and finished follow from the players; only a live game
+                // can be put into sudden death, only once, and only if it plays with it
+                if phase == GamePhase::SuddenDeath
+                    && self.phase == GamePhase::Live
+                    && self.sudden_death_since.is_none()
+                    && self.features().contains(Feature::SuddenDeath)
+                {
+                    self.sudden_death_since = Some(at(timestamp));
+                }
+            }
+
+            GameEvent::Fact(FactEvent::FeaturesSet { features, .. }) => {
+                self.features.get_or_insert(features);
+            }
+
+            GameEvent::Command(CommandEvent::ScheduleEvent { execute_at, command, timestamp }) => {
+                // One level deep, and only while there's room - the leader refuses the rest
+                let nested = matches!(*command, CommandEvent::ScheduleEvent { .. });
+                if !nested && self.scheduled.len() < MAX_SCHEDULED_EVENTS {
+                    let scheduled = ScheduledCommand { execute_at, command: *command, scheduled_at: at(timestamp) };
+                    self.scheduled.insert(log_index, scheduled);
+                    changes.push(StateChange::EventScheduled { schedule_id: log_index, execute_at });
+                }
+            }
+
+            GameEvent::Fact(FactEvent::ScheduledEventFired { schedule_id, refused, timestamp }) => {
+                // Fired once: a deposed leader's duplicate finds the queue entry gone
+                if let Some(scheduled) = self.scheduled.remove(&schedule_id) {
+                    changes.push(StateChange::ScheduledEventFired { schedule_id, refused: refused.is_some() });
+                    if refused.is_none() {
+                        // Timed by the firing, not by when it was queued
+                        let fired_at = Some(at(timestamp));
+                        self.apply_event(GameEvent::Command(scheduled.command), log_index, fired_at, changes);
+                    }
+                }
+            }
+
+            GameEvent::Fact(FactEvent::Announcement { text, severity, ttl_secs, timestamp }) => {
+                let posted_at = at(timestamp);
+                self.announcements.retain(|_, a| a.expires_at > posted_at);
+                while self.announcements.len() >= MAX_ANNOUNCEMENTS {
+                    self.announcements.pop_first();
+                }
+                let expires_at = posted_at.saturating_add(ttl_secs.min(MAX_ANNOUNCEMENT_TTL_SECS));
+                self.announcements.insert(log_index, Announcement { text, severity, posted_at, expires_at });
+                changes.push(StateChange::AnnouncementPosted { announcement_id: log_index });
+            }
+
+            GameEvent::Fact(FactEvent::GameEnded { winner_id, members, timestamp }) => {
+                // Only a finished game ends, and only once - a deposed leader's duplicate is ignored
+                if self.game_over && self.game_end.is_none() {
+                    self.game_end = Some(GameEnd {
+                        winner_id,
+                        ended_at: at(timestamp),
+                        members: members.into_iter().collect(),
+                        acknowledged: BTreeSet::new(),
+                    });
+                    changes.push(StateChange::GameEnded { winner_id });
+                }
+            }
+
+            // Checked by each node against its own digest, outside the state
+            GameEvent::Fact(FactEvent::StateDigest { .. }) => {}
+
+            // A record for the log and the audience, nothing to apply
+            GameEvent::Fact(FactEvent::FinalKillReport { .. }) => {}
+
+            GameEvent::Fact(FactEvent::GameOverAcknowledged { raft_node_id, .. }) => {
+                if let Some(end) = self.game_end.as_mut() {
+                    if end.acknowledged.insert(raft_node_id) {
+                        changes.push(StateChange::GameOverAcknowledged { raft_node_id });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Scheduled commands whose time has come at `now`, oldest first
+    pub fn due_scheduled(&self, now: u64) -> impl Iterator<Item = (u64, &ScheduledCommand)> + '_ {
+        self.scheduled.iter().filter(move |(_, s)| s.execute_at <= now).map(|(id, s)| (*id, s))
+    }
+
+    /// The phase the game is in, from what's been committed
+    fn derive_phase(&self) -> GamePhase {
+        if self.game_over {
+            GamePhase::Finished
+        } else if self.sudden_death_since.is_some() {
+            GamePhase::SuddenDeath
+        } else if self.live_since.is_some() {
+            GamePhase::Live
+        } else {
+            GamePhase::Lobby
+        }
+    }
+
+    /// When a live game with a `limit_secs` time limit (game time) goes to sudden
+    /// death - None if it isn't live, has no limit or plays without sudden death
+    pub fn sudden_death_at(&self, limit_secs: u64) -> Option<u64> {
+        if self.phase != GamePhase::Live || limit_secs == 0 || !self.features().contains(Feature::SuddenDeath) {
+            return None;
+        }
+        Some(self.live_since?.saturating_add(self.scaled_secs(limit_secs)))
+    }
+
+    /// Features the game runs: the committed set, or the defaults until one is
+    pub fn features(&self) -> FeatureFlags {
+        self.features.unwrap_or_default()
+    }
+
+    /// Committed cluster version, 1 until a ClusterVersionSet
+    pub fn cluster_version(&self) -> u32 {
+        self.cluster_version.unwrap_or(1)
+    }
+
+    /// Whether every member applies `event`, so it may be proposed
+    pub fn permits(&self, event: &GameEvent) -> bool {
+        event.min_cluster_version() <= self.cluster_version()
+    }
+
+    /// A referee's correction - applied as-is, the referee is trusted
+    fn apply_override(&mut self, action: RefereeOverride, timestamp: u64, changes: &mut Vec<StateChange>) {
+        match action {
+            RefereeOverride::RevertCapture { node_coord, owner_id } => {
+                if !self.nodes.contains_key(&node_coord) {
+                    return;
+                }
+                self.set_owner(node_coord, owner_id, changes);
+                self.set_target(node_coord, None, changes);
+                self.clear_boost(node_coord, changes);
+                self.set_defense(node_coord, None, changes);
+                self.retargeted_at.remove(&node_coord);
+
+                // Their own capital back: as if it had never fallen
+                let home = self.players.get(&owner_id).is_some_and(|p| p.capital_coord == node_coord);
+                if home {
+                    if let Some(node) = self.nodes.get_mut(&node_coord) {
+                        node.node_type = NodeType::Capital;
+                    }
+                    self.contested_capitals.remove(&node_coord);
+                    if let Some(player) = self.players.get_mut(&owner_id) {
+                        if !player.alive {
+                            changes.push(StateChange::PlayerRevived { player_id: owner_id });
+                        }
+                        player.alive = true;
+                    }
+                    // Whatever hasn't decayed yet is theirs to keep (handed-over nodes aren't)
+                    self.decaying.remove(&owner_id);
+                    self.game_over = self.players.values().filter(|p| p.alive).count() <= 1;
+                }
+            }
+
+            RefereeOverride::NeutralizeNode { node_coord } => {
+                let Some(node) = self.nodes.get_mut(&node_coord) else {
+                    return;
+                };
+                let old_owner_id = node.owner_id;
+                let was_capital = node.node_type == NodeType::Capital;
+                node.node_type = NodeType::Regular;
+                self.set_owner(node_coord, 0, changes);
+                self.set_target(node_coord, None, changes);
+                self.clear_boost(node_coord, changes);
+                self.set_defense(node_coord, None, changes);
+                self.retargeted_at.remove(&node_coord);
+                if was_capital {
+                    self.eliminate(old_owner_id, timestamp, changes);
+                }
+            }
+
+            RefereeOverride::ExtendTime { player_id, secs } => {
+                for contest in self.contested_capitals.values_mut().filter(|c| c.player_id == player_id) {
+                    contest.expires_at = contest.expires_at.saturating_add(secs);
+                }
+                if let Some(player) = self.players.get_mut(&player_id) {
+                    if player.protected_until > timestamp {
+                        player.protected_until = player.protected_until.saturating_add(secs);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Announcements still showing at `now`, oldest first
+    pub fn active_announcements(&self, now: u64) -> impl Iterator<Item = (u64, &Announcement)> {
+        self.announcements.iter().filter(move |(_, a)| a.expires_at > now).map(|(id, a)| (*id, a))
+    }
+
+    /// `secs` of game time as wall time, at this game's committed time scale
+    pub fn scaled_secs(&self, secs: u64) -> u64 {
+        scale_secs(secs, self.time_scale)
+    }
+
+    /// Ground under `coord` (plain unless the map says otherwise)
+    pub fn terrain_at(&self, coord: NodeCoord) -> Terrain {
+        self.terrain.get(&coord).copied().unwrap_or_default()
+    }
+
+    /// Task size for a node spawned at `coord`
+    /// Large within CORE_RING of any capital or on high ground, Small from
+    /// FRONTIER_DISTANCE out (or before anyone has joined), Regular in between.
+    pub fn spawn_tier(&self, coord: NodeCoord) -> SpawnTier {
+        let nearest = self.players.values().map(|p| coord.distance(&p.capital_coord)).min();
+        match nearest {
+            _ if self.terrain_at(coord).capacity_multiplier() > 1.0 => SpawnTier::Large,
+            Some(distance) if distance <= CORE_RING => SpawnTier::Large,
+            Some(distance) if distance < FRONTIER_DISTANCE => SpawnTier::Regular,
+            _ => SpawnTier::Small,
+        }
+    }
+
+    /// Point a node at `target` - skipped while the node is in its retarget cooldown,
+    /// except to stop (which doesn't restart the cooldown)
+    fn retarget(&mut self, node_coord: NodeCoord, target: Option<AttackTarget>, now: u64, changes: &mut Vec<StateChange>) {
+        if target.is_some() && self.retarget_cooldown(node_coord, now).is_some() {
+            return;
+        }
+        if !self.nodes.contains_key(&node_coord) {
+            return;
+        }
+        self.set_target(node_coord, target, changes);
+        // Attacking again ends a defend order
+        if target.is_some() {
+            self.set_defense(node_coord, None, changes);
+            self.retargeted_at.insert(node_coord, now);
+        }
+    }
+
+    /// Point an existing node at `target`, reporting it if that's new
+    fn set_target(&mut self, coord: NodeCoord, target: Option<AttackTarget>, changes: &mut Vec<StateChange>) {
+        if let Some(node) = self.nodes.get_mut(&coord) {
+            if node.current_target != target {
+                node.current_target = target;
+                changes.push(StateChange::NodeTargetChanged { coord, target });
+            }
+        }
+    }
+
+    /// Give an existing node `owner_id`, reporting it if that's new
+    fn set_owner(&mut self, coord: NodeCoord, owner_id: u64, changes: &mut Vec<StateChange>) {
+        if let Some(node) = self.nodes.get_mut(&coord) {
+            let old_owner_id = node.owner_id;
+            if old_owner_id != owner_id {
+                node.owner_id = owner_id;
+                changes.push(StateChange::NodeOwnerChanged { coord, old_owner_id, new_owner_id: owner_id });
+            }
+        }
+    }
+
+    /// Set or clear the defend order at `coord`, reporting it if that's new
+    fn set_defense(&mut self, coord: NodeCoord, defense: Option<DefenseOrder>, changes: &mut Vec<StateChange>) {
+        let old = match defense {
+            Some(order) => self.defenses.insert(coord, order),
+            None => self.defenses.remove(&coord),
+        };
+        if old != defense {
+            changes.push(StateChange::NodeDefenseChanged { coord, defense });
+        }
+    }
+
+    /// End any capture boost on `coord`
+    fn clear_boost(&mut self, coord: NodeCoord, changes: &mut Vec<StateChange>) {
+        if self.capture_boosts.remove(&coord).is_some() {
+            changes.push(StateChange::CaptureBoostChanged { coord });
+        }
+    }
+
+    /// Seconds before the node at `coord` may take a new target, None if it may now
+    pub fn retarget_cooldown(&self, coord: NodeCoord, now: u64) -> Option<u64> {
+        let cooldown = self.scaled_secs(self.retarget_cooldown_secs.unwrap_or(DEFAULT_RETARGET_COOLDOWN_SECS));
+        let ready_at = self.retargeted_at.get(&coord)?.saturating_add(cooldown);
+        Some(ready_at.saturating_sub(now)).filter(|secs| *secs > 0)
+    }
+
+    /// Player is out; the game ends when at most one player is left alive
+    fn eliminate(&mut self, player_id: u64, now: u64, changes: &mut Vec<StateChange>) {
+        let mut was_alive = false;
+        if let Some(player) = self.players.get_mut(&player_id) {
+            if player.alive {
+                changes.push(StateChange::PlayerEliminated { player_id });
+            }
+            was_alive = player.alive;
+            player.alive = false;
+        }
+        self.contested_capitals.retain(|_, contest| contest.player_id != player_id);
+        if was_alive {
+            self.release_nodes(player_id, now, changes);
+        }
+
+        // Check if only one player remains (game over)
+        let alive_count = self.players.values().filter(|p| p.alive).count();
+        if alive_count <= 1 {
+            self.game_over = true;
+        }
+    }
+
+    /// What happens to a just-eliminated player's nodes, per the committed policy
+    /// Their capital is already lost; a contested capital of someone else's they held
+    /// goes back to its owner if that's who takes it (as a recapture would).
+    fn release_nodes(&mut self, player_id: u64, now: u64, changes: &mut Vec<StateChange>) {
+        let Some(policy) = self.elimination_policy else {
+            return;
+        };
+        let owned: Vec<NodeCoord> = self
+            .nodes
+            .values()
+            .filter(|n| n.owner_id == player_id && n.node_type != NodeType::Capital)
+            .map(|n| n.coord)
+            .collect();
+
+        match policy {
+            EliminationPolicy::Captor | EliminationPolicy::Neutral => {
+                let new_owner_id = match policy {
+                    EliminationPolicy::Captor => self.captor(player_id).unwrap_or(0),
+                    _ => 0,
+                };
+                for coord in owned {
+                    self.hand_over(coord, new_owner_id, changes);
+                }
+            }
+            EliminationPolicy::Decay { .. } => {
+                // Nobody steers them anymore; they go one by one (NodeDecayed)
+                for &coord in &owned {
+                    self.set_target(coord, None, changes);
+                    self.set_defense(coord, None, changes);
+                }
+                if !owned.is_empty() {
+                    self.decaying.insert(player_id, NodeDecay { since: now, decayed: 0 });
+                }
+            }
+        }
+    }
+
+    /// Give a node to `owner_id` outside a capture: it stops what it was doing, and a
+    /// contested capital reaching its former owner is their capital again
+    fn hand_over(&mut self, coord: NodeCoord, owner_id: u64, changes: &mut Vec<StateChange>) {
+        self.set_target(coord, None, changes);
+        self.set_defense(coord, None, changes);
+        self.clear_boost(coord, changes);
+        self.set_owner(coord, owner_id, changes);
+        self.retargeted_at.remove(&coord);
+        if self.contested_capitals.get(&coord).is_some_and(|c| c.player_id == owner_id) {
+            if let Some(node) = self.nodes.get_mut(&coord) {
+                node.node_type = NodeType::Capital;
+            }
+            self.contested_capitals.remove(&coord);
+        }
+    }
+
+    /// The live player holding `player_id`'s capital, if it isn't them
+    pub fn captor(&self, player_id: u64) -> Option<u64> {
+        let capital = self.players.get(&player_id)?.capital_coord;
+        let holder = self.nodes.get(&capital)?.owner_id;
+        Some(holder).filter(|id| *id != player_id && self.players.get(id).is_some_and(|p| p.alive))
+    }
+
+    /// The decaying player's node to turn neutral next, and when (None if none is left)
+    /// Furthest from their fallen capital first; one every `interval_secs` of game time.
+    pub fn next_decay(&self, player_id: u64) -> Option<(NodeCoord, u64)> {
+        let Some(EliminationPolicy::Decay { interval_secs }) = self.elimination_policy else {
+            return None;
+        };
+        let decay = self.decaying.get(&player_id)?;
+        let capital = self.players.get(&player_id)?.capital_coord;
+        let node = self
+            .nodes
+            .values()
+            .filter(|n| n.owner_id == player_id && n.node_type != NodeType::Capital)
+            .max_by_key(|n| n.coord.distance(&capital))?;
+        let due = decay.since.saturating_add(self.scaled_secs(interval_secs) * (u64::from(decay.decayed) + 1));
+        Some((node.coord, due))
+    }
+
+    /// The last player standing, once the game is over (None if nobody is)
+    pub fn winner(&self) -> Option<u64> {
+        if !self.game_over {
+            return None;
+        }
+        self.players.values().find(|p| p.alive).map(|p| p.player_id)
+    }
+
+    /// Capital `player_id` lost and can still win back before the deadline
+    /// A contest whose capture never committed (capital still theirs) doesn't count.
+    pub fn capital_contest(&self, player_id: u64) -> Option<(NodeCoord, CapitalContest)> {
+        self.contested_capitals
+            .iter()
+            .find(|(coord, contest)| {
+                contest.player_id == player_id && self.nodes.get(coord).is_some_and(|n| n.owner_id != player_id)
+            })
+            .map(|(coord, contest)| (*coord, *contest))
+    }
+
+    /// Requested effects with no committed completion, oldest first
+    pub fn pending_effects(&self) -> impl Iterator<Item = (u64, &Effect)> {
+        self.effects
+            .iter()
+            .filter(|(_, record)| record.completion.is_none())
+            .map(|(id, record)| (*id, &record.effect))
+    }
+
+    /// Whether an effect matching `matches` was ever requested
+    pub fn effect_requested(&self, matches: impl Fn(&Effect) -> bool) -> bool {
+        self.effects.values().any(|record| matches(&record.effect))
+    }
+
+    /// Whether an effect matching `matches` has completed
+    pub fn effect_completed(&self, matches: impl Fn(&Effect) -> bool) -> bool {
+        self.effects.values().any(|record| record.completion.is_some() && matches(&record.effect))
+    }
+
+    /// Get all active attacks
+    pub fn get_active_attacks(&self) -> Vec<Attack> {
+        let mut attacks = Vec::new();
+
+        for node in self.nodes.values() {
+            if let Some(AttackTarget::Coordinate(target_coord)) = node.current_target {
+                if let Some(target_node) = self.nodes.get(&target_coord) {
+                    attacks.push(Attack {
+                        attacker_node: node.coord,
+                        attacker_owner: node.owner_id,
+                        target_node: target_coord,
+                        target_owner: target_node.owner_id,
+                    });
+                }
+            }
+        }
+
+        attacks
+    }
+
+    /// Nodes attacked by another player at `now` (unix seconds), with the latest loss on each
+    /// Active attack orders decide who is under attack; inbound metrics no older than
+    /// ATTACK_METRICS_MAX_AGE_SECS say how badly. Neutral nodes are left out - nobody to warn.
+    pub fn under_attack(&self, now: u64) -> BTreeMap<NodeCoord, UnderAttack> {
+        let mut status: BTreeMap<NodeCoord, UnderAttack> = BTreeMap::new();
+        for attack in self.get_active_attacks() {
+            if attack.target_owner == 0 || attack.attacker_owner == attack.target_owner {
+                continue;
+            }
+            let target = status.entry(attack.target_node).or_insert_with(|| UnderAttack {
+                owner_id: attack.target_owner,
+                attackers: Vec::new(),
+                packet_loss: self
+                    .node_metrics
+                    .get(&attack.target_node)
+                    .filter(|m| now.saturating_sub(m.timestamp) <= self.scaled_secs(ATTACK_METRICS_MAX_AGE_SECS))
+                    .map(|m| m.packet_loss),
+            });
+            target.attackers.push(Attacker {
+                coord: attack.attacker_node,
+                owner_id: attack.attacker_owner,
+                direction: attack.target_node.direction_to(&attack.attacker_node),
+            });
+        }
+        status
+    }
+
+    /// Check an attack order at `now` (unix seconds) before submitting it
+    /// `player_id` is checked against the node's owner when given
+    pub fn validate_attack(&self, player_id: Option<u64>, node: NodeCoord, target: NodeCoord, now: u64) -> Result<(), AttackError> {
+        let attacker = self.nodes.get(&node).ok_or(AttackError::UnknownNode(node))?;
+        if player_id.is_some_and(|id| attacker.owner_id != id) {
+            return Err(AttackError::NotOwner);
+        }
+        if !node.is_adjacent(&target) {
+            return Err(AttackError::NotAdjacent);
+        }
+        if !self.terrain_at(target).is_passable() {
+            return Err(AttackError::Impassable);
+        }
+        if let Some(remaining_secs) = self.spawn_protection(target, now) {
+            return Err(AttackError::SpawnProtected { remaining_secs });
+        }
+        if let Some(remaining_secs) = self.retarget_cooldown(node, now) {
+            return Err(AttackError::Cooldown { remaining_secs });
+        }
+        Ok(())
+    }
+
+    /// Check a PlayerJoin before submitting it
+    /// Names are compared ignoring case and surrounding whitespace; an eliminated
+    /// player's name is free again.
+    pub fn validate_join(&self, player_id: u64, name: &str) -> Result<(), JoinError> {
+        if self.players.get(&player_id).is_some_and(|p| p.alive) {
+            return Err(JoinError::AlreadyJoined);
+        }
+        if self.phase >= GamePhase::SuddenDeath {
+            return Err(JoinError::Closed(self.phase));
+        }
+        let name = name.trim();
+        if self.players.values().any(|p| p.alive && p.name.trim().eq_ignore_ascii_case(name)) {
+            return Err(JoinError::NameTaken);
+        }
+        Ok(())
+    }
+
+    /// Check a PlayerReconnected before submitting it
+    /// `token_key` is the SHA-256 (hex) of the presented token - the rules can't hash it.
+    pub fn validate_reconnect(&self, player_id: u64, token_key: &str) -> Result<(), JoinError> {
+        let player = self.players.get(&player_id).ok_or(JoinError::UnknownPlayer(player_id))?;
+        match &player.reconnect_key {
+            None => Err(JoinError::NotReconnectable),
+            Some(key) if !key.eq_ignore_ascii_case(token_key) => Err(JoinError::WrongToken),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Same checks for a defend order: `node` must be `player_id`'s (if given), and
+    /// `assist` an adjacent node with the same owner
+    pub fn validate_defense(&self, player_id: Option<u64>, node: NodeCoord, assist: Option<NodeCoord>) -> Result<(), AttackError> {
+        let defender = self.nodes.get(&node).ok_or(AttackError::UnknownNode(node))?;
+        if player_id.is_some_and(|id| defender.owner_id != id) {
+            return Err(AttackError::NotOwner);
+        }
+        if let Some(assist) = assist {
+            let friendly = self.nodes.get(&assist).is_some_and(|n| n.owner_id == defender.owner_id);
+            if !node.is_adjacent(&assist) || !friendly {
+                return Err(AttackError::InvalidAssist);
+            }
+        }
+        Ok(())
+    }
+
+    /// How much the responder at `coord` takes relative to an undefended node's
+    /// A defend order adds DEFEND_RESPONDER_BONUS, or half of it when the other half
+    /// assists a neighbour; each same-owner neighbour assisting `coord` adds its half.
+    pub fn responder_multiplier(&self, coord: NodeCoord) -> f32 {
+        let Some(node) = self.nodes.get(&coord) else {
+            return 1.0;
+        };
+        let own = match self.defenses.get(&coord) {
+            Some(DefenseOrder { assist: Some(_) }) => DEFEND_RESPONDER_BONUS / 2.0,
+            Some(DefenseOrder { assist: None }) => DEFEND_RESPONDER_BONUS,
+            None => 0.0,
+        };
+        let assists = coord
+            .neighbors()
+            .iter()
+            .filter(|n| self.defenses.get(n).is_some_and(|d| d.assist == Some(coord)))
+            .filter(|n| self.nodes.get(n).is_some_and(|n| n.owner_id == node.owner_id))
+            .count();
+        1.0 + own + assists as f32 * DEFEND_RESPONDER_BONUS / 2.0
+    }
+
+    /// Seconds left before the capital at `coord` can be attacked, None if it isn't
+    /// a spawn-protected capital (or no longer belongs to the player it protects)
+    pub fn spawn_protection(&self, coord: NodeCoord, now: u64) -> Option<u64> {
+        let node = self.nodes.get(&coord).filter(|n| n.node_type == NodeType::Capital)?;
+        self.players
+            .get(&node.owner_id)
+            .filter(|p| p.capital_coord == coord)?
+            .spawn_protection_remaining(now)
+    }
+
+    /// Get all nodes owned by a player
+    pub fn get_player_nodes(&self, player_id: u64) -> Vec<&Node> {
+        self.nodes
+            .values()
+            .filter(|node| node.owner_id == player_id)
+            .collect()
+    }
+
+    // Note: Bandwidth is now measured via actual UDP/ACK metrics,
+    // not estimated from capacity
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::AttackOrder;
+    use alloc::boxed::Box;
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_player_join() {
+        let mut state = GameState::new();
+        let event = GameEvent::Command(CommandEvent::PlayerJoin {
+            player_id: 1,
+            name: "Alice".to_string(),
+            capital_coord: NodeCoord::new(0, 0),
+            capital_ip: Some("10.0.0.1".to_string()),
+            client_endpoint: None,
+            timestamp: 1000,
+            raft_node_id: 0,
+            reconnect_key: None,
+        });
+
+        state.process_event(event, 1);
+
+        assert_eq!(state.players.len(), 1);
+        assert_eq!(state.nodes.len(), 1);
+        assert!(state.players.get(&1).unwrap().alive);
+    }
+
+    #[test]
+    fn test_join_and_reconnect() {
+        let mut state = GameState::new();
+        let join = |player_id: u64, name: &str, q: i32| {
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id,
+                name: name.to_string(),
+                capital_coord: NodeCoord::new(q, 0),
+                capital_ip: None,
+                client_endpoint: Some("192.168.1.2".to_string()),
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: Some("key-1".to_string()),
+            })
+        };
+        assert_eq!(state.validate_join(1, "Alice"), Ok(()));
+        state.process_event(join(1, "Alice", 0), 1);
+
+        // One Alice at a time; the same player has to reconnect instead
+        assert_eq!(state.validate_join(2, " alice "), Err(JoinError::NameTaken));
+        assert_eq!(state.validate_join(1, "Alicia"), Err(JoinError::AlreadyJoined));
+        assert_eq!(state.validate_join(2, "Bob"), Ok(()));
+
+        assert_eq!(state.validate_reconnect(1, "key-2"), Err(JoinError::WrongToken));
+        assert_eq!(state.validate_reconnect(9, "key-1"), Err(JoinError::UnknownPlayer(9)));
+        assert_eq!(state.validate_reconnect(1, "KEY-1"), Ok(()));
+
+        state.process_event(GameEvent::Fact(FactEvent::OwnerIdle { player_id: 1, neutralize: false, timestamp: 1030 }), 2);
+        let reconnect = GameEvent::Command(CommandEvent::PlayerReconnected {
+            player_id: 1,
+            token: "token-1".to_string(),
+            next_key: "key-2".to_string(),
+            client_endpoint: Some("192.168.1.7".to_string()),
+            timestamp: 1040,
+            raft_node_id: 0,
+        });
+        let changes = state.process_event(reconnect, 3);
+        assert_eq!(
+            changes,
+            vec![StateChange::PlayerIdleChanged { player_id: 1, idle: false }, StateChange::PlayerReconnected { player_id: 1 }]
+        );
+        let alice = &state.players[&1];
+        assert_eq!((alice.client_endpoint.as_deref(), alice.last_heartbeat), (Some("192.168.1.7"), 1040));
+        // The old token is spent
+        assert_eq!(state.validate_reconnect(1, "key-1"), Err(JoinError::WrongToken));
+        assert_eq!(state.validate_reconnect(1, "key-2"), Ok(()));
+
+        // Keyless players can't reconnect; a dead player's name is free again
+        state.process_event(join(2, "Bob", 3), 4);
+        state.players.get_mut(&2).unwrap().reconnect_key = None;
+        assert_eq!(state.validate_reconnect(2, "key-1"), Err(JoinError::NotReconnectable));
+        state.players.get_mut(&2).unwrap().alive = false;
+        assert_eq!(state.validate_join(3, "Bob"), Ok(()));
+    }
+
+    #[test]
+    fn test_node_capture() {
+        let mut state = GameState::new();
+
+        // Add two players
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 2,
+                name: "Bob".to_string(),
+                capital_coord: NodeCoord::new(1, 0),
+                capital_ip: Some("10.0.0.2".to_string()),
+                client_endpoint: None,
+                timestamp: 1001,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            2,
+        );
+
+        // Bob captures Alice's capital
+        state.process_event(
+            GameEvent::Fact(FactEvent::NodeCaptured {
+                node_coord: NodeCoord::new(0, 0),
+                new_owner_id: 2,
+                timestamp: 2000,
+            }),
+            3,
+        );
+
+        // Alice should be dead
+        assert!(!state.players.get(&1).unwrap().alive);
+        // Bob should own the node
+        assert_eq!(state.nodes.get(&NodeCoord::new(0, 0)).unwrap().owner_id, 2);
+    }
+
+    #[test]
+    fn test_state_changes() {
+        let mut state = GameState::new();
+        let join = |player_id: u64, q: i32| {
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id,
+                name: format!("p{}", player_id),
+                capital_coord: NodeCoord::new(q, 0),
+                capital_ip: Some(format!("10.0.0.{}", player_id)),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            })
+        };
+        let alice = NodeCoord::new(0, 0);
+        let bob = NodeCoord::new(1, 0);
+        assert_eq!(
+            state.process_event(join(1, 0), 1),
+            vec![
+                StateChange::PlayerAdded { player_id: 1 },
+                StateChange::NodeAdded { coord: alice, owner_id: 1 },
+                StateChange::NodeReady { coord: alice, ip: "10.0.0.1".to_string() },
+            ]
+        );
+        state.process_event(join(2, 1), 2);
+
+        // A new target is reported once; sending it again changes nothing
+        let target = Some(AttackTarget::Coordinate(alice));
+        let attack = || GameEvent::from(CommandEvent::SetNodeTarget { node_coord: bob, target, timestamp: 2000 });
+        assert_eq!(state.process_event(attack(), 3), vec![StateChange::NodeTargetChanged { coord: bob, target }]);
+        assert!(state.process_event(attack(), 4).is_empty());
+        let heartbeat = GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id: 1, timestamp: 2001 });
+        assert!(state.process_event(heartbeat, 5).is_empty());
+        let end = || GameEvent::from(FactEvent::GameEnded { winner_id: Some(2), members: vec![10, 11], timestamp: 2003 });
+        assert!(state.process_event(end(), 5).is_empty(), "a running game can't end");
+
+        // Taking the last other capital ends the game
+        let capture = FactEvent::NodeCaptured { node_coord: alice, new_owner_id: 2, timestamp: 2002 };
+        assert_eq!(
+            state.process_event(capture.into(), 6),
+            vec![
+                StateChange::NodeOwnerChanged { coord: alice, old_owner_id: 1, new_owner_id: 2 },
+                StateChange::PlayerEliminated { player_id: 1 },
+                StateChange::GameOver,
+                StateChange::PhaseChanged { phase: GamePhase::Finished },
+            ]
+        );
+        assert_eq!(state.winner(), Some(2));
+
+        // Shutdown waits on each member's acknowledgment, counted once
+        assert_eq!(state.process_event(end(), 7), vec![StateChange::GameEnded { winner_id: Some(2) }]);
+        assert!(state.process_event(end(), 8).is_empty());
+        let ack = || GameEvent::from(FactEvent::GameOverAcknowledged { raft_node_id: 11, timestamp: 2004 });
+        assert_eq!(state.process_event(ack(), 9), vec![StateChange::GameOverAcknowledged { raft_node_id: 11 }]);
+        assert!(state.process_event(ack(), 10).is_empty());
+        let game_end = state.game_end.as_ref().unwrap();
+        assert_eq!((game_end.ended_at, game_end.pending()), (2003, vec![10]));
+    }
+
+    #[test]
+    fn test_client_player_capital_capture_kills_player() {
+        let mut state = GameState::new();
+
+        // Client player: the laptop is the endpoint, the capital worker isn't up yet
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: None,
+                client_endpoint: Some("192.168.1.9".to_string()),
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 2,
+                name: "Bob".to_string(),
+                capital_coord: NodeCoord::new(1, 0),
+                capital_ip: Some("10.0.0.2".to_string()),
+                client_endpoint: None,
+                timestamp: 1001,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            2,
+        );
+
+        let capital = state.nodes.get(&NodeCoord::new(0, 0)).unwrap();
+        assert_eq!(capital.node_type, NodeType::Capital);
+        assert_eq!(capital.init_state, NodeInitState::Initializing);
+        assert!(!state.node_ips.contains_key(&NodeCoord::new(0, 0)));
+
+        // Capital worker reports in
+        state.process_event(
+            GameEvent::Fact(FactEvent::NodeInitializationComplete {
+                node_coord: NodeCoord::new(0, 0),
+                node_ip: "10.0.0.1".to_string(),
+                raft_node_id: 7,
+                timestamp: 1002,
+            }),
+            3,
+        );
+        assert_eq!(state.nodes.get(&NodeCoord::new(0, 0)).unwrap().init_state, NodeInitState::Ready);
+        assert_eq!(state.node_ips.get(&NodeCoord::new(0, 0)).map(String::as_str), Some("10.0.0.1"));
+
+        // Losing the capital kills the player; the laptop stays the final-kill target
+        state.process_event(
+            GameEvent::Fact(FactEvent::NodeCaptured {
+                node_coord: NodeCoord::new(0, 0),
+                new_owner_id: 2,
+                timestamp: 2000,
+            }),
+            4,
+        );
+        let alice = state.players.get(&1).unwrap();
+        assert!(!alice.alive);
+        assert_eq!(alice.client_endpoint.as_deref(), Some("192.168.1.9"));
+        assert!(state.game_over);
+    }
+
+    #[test]
+    fn test_contested_capital_last_stand() {
+        let mut state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1), (3, 2)] {
+            state.process_event(
+                GameEvent::Command(CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 1000,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }),
+                id,
+            );
+        }
+        let alice_capital = NodeCoord::new(0, 0);
+        let contest = |player_id, expires_at| {
+            GameEvent::Fact(FactEvent::CapitalContested { node_coord: alice_capital, player_id, expires_at, timestamp: 2000 })
+        };
+        let capture = |new_owner_id| {
+            GameEvent::Fact(FactEvent::NodeCaptured { node_coord: alice_capital, new_owner_id, timestamp: 2000 })
+        };
+
+        // Contested capture: Alice hangs on, Bob holds a regular node
+        state.process_event(contest(1, 2030), 4);
+        state.process_event(capture(2), 5);
+        assert!(state.players[&1].alive);
+        assert_eq!(state.nodes[&alice_capital].node_type, NodeType::Regular);
+        assert_eq!(state.capital_contest(1), Some((alice_capital, CapitalContest { player_id: 1, expires_at: 2030 })));
+
+        // Changing hands between attackers keeps the contest going
+        state.process_event(capture(3), 6);
+        assert!(state.capital_contest(1).is_some());
+
+        // Winning it back restores the capital
+        state.process_event(capture(1), 7);
+        assert_eq!(state.nodes[&alice_capital].node_type, NodeType::Capital);
+        assert!(state.contested_capitals.is_empty());
+        assert!(state.players[&1].alive);
+
+        // Second fall: the window runs out
+        state.process_event(contest(1, 2100), 8);
+        state.process_event(capture(2), 9);
+        state.process_event(GameEvent::Fact(FactEvent::PlayerEliminated { player_id: 1, timestamp: 2100 }), 10);
+        assert!(!state.players[&1].alive);
+        assert!(state.contested_capitals.is_empty());
+        assert!(!state.game_over);
+    }
+
+    /// Alice (1), Bob (2) and Carol (3) with capitals at q = 0, 1, 2 and a regular
+    /// node or two each north of them, playing under `policy`
+    fn elimination_game(policy: Option<EliminationPolicy>) -> GameState {
+        let mut state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1), (3, 2)] {
+            state.process_event(
+                GameEvent::Command(CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 1000,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }),
+                id,
+            );
+        }
+        for (owner_id, q, r) in [(1, 0, 1), (1, 0, 2), (2, 1, 1), (3, 2, 1)] {
+            let node_coord = NodeCoord::new(q, r);
+            state.process_event(FactEvent::NodeInitializationStarted { node_coord, owner_id, timestamp: 1001 }.into(), 4);
+        }
+        if let Some(policy) = policy {
+            state.process_event(FactEvent::EliminationPolicySet { policy, timestamp: 1002 }.into(), 5);
+        }
+        state
+    }
+
+    fn capture(node_coord: NodeCoord, new_owner_id: u64, timestamp: u64) -> GameEvent {
+        FactEvent::NodeCaptured { node_coord, new_owner_id, timestamp }.into()
+    }
+
+    fn owners(state: &GameState) -> Vec<(NodeCoord, u64)> {
+        state.nodes.values().map(|n| (n.coord, n.owner_id)).collect()
+    }
+
+    #[test]
+    fn test_game_phases() {
+        let join = |player_id: u64, q: i32, timestamp: u64| -> GameEvent {
+            CommandEvent::PlayerJoin {
+                player_id,
+                name: format!("P{}", player_id),
+                capital_coord: NodeCoord::new(q, 0),
+                capital_ip: Some(format!("10.0.0.{}", player_id)),
+                client_endpoint: None,
+                timestamp,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }
+            .into()
+        };
+        let sudden_death = |timestamp| FactEvent::PhaseChanged { phase: GamePhase::SuddenDeath, timestamp }.into();
+
+        let mut state = GameState::new();
+        state.process_event(join(1, 0, 1000), 1);
+        assert_eq!(state.phase, GamePhase::Lobby);
+        // Sudden death needs a live game
+        state.process_event(sudden_death(1001), 2);
+        assert_eq!(state.phase, GamePhase::Lobby);
+        assert_eq!(state.sudden_death_at(600), None);
+
+        let changes = state.process_event(join(2, 1, 1010), 3);
+        assert!(changes.contains(&StateChange::PhaseChanged { phase: GamePhase::Live }));
+        assert_eq!(state.live_since, Some(1010));
+        assert_eq!(state.sudden_death_at(600), Some(1610));
+        assert_eq!(state.sudden_death_at(0), None);
+        state.process_event(join(3, 2, 1020), 4);
+
+        let changes = state.process_event(sudden_death(1610), 5);
+        assert_eq!(changes, vec![StateChange::PhaseChanged { phase: GamePhase::SuddenDeath }]);
+        assert_eq!(state.sudden_death_since, Some(1610));
+        assert_eq!(state.sudden_death_at(600), None);
+        assert_eq!(state.validate_join(4, "Late"), Err(JoinError::Closed(GamePhase::SuddenDeath)));
+
+        state.process_event(FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 1700 }.into(), 6);
+        assert_eq!(state.phase, GamePhase::SuddenDeath);
+        let changes = state.process_event(FactEvent::NodeCaptured { node_coord: NodeCoord::new(2, 0), new_owner_id: 2, timestamp: 1710 }.into(), 7);
+        assert!(changes.contains(&StateChange::PhaseChanged { phase: GamePhase::Finished }));
+        // Nothing moves a finished game back but a referee revive
+        state.process_event(sudden_death(1720), 8);
+        assert_eq!(state.phase, GamePhase::Finished);
+        let revert = RefereeOverride::RevertCapture { node_coord: NodeCoord::new(0, 0), owner_id: 1 };
+        state.process_event(FactEvent::RefereeAction { action: revert, reason: "test".to_string(), timestamp: 1730 }.into(), 9);
+        assert_eq!(state.phase, GamePhase::SuddenDeath);
+
+        // A game committed to play without sudden death never gets it
+        let mut state = GameState::new();
+        let features = FeatureFlags::default().without(Feature::SuddenDeath).with(Feature::FogOfWar);
+        state.process_event(FactEvent::FeaturesSet { features, timestamp: 1000 }.into(), 1);
+        // The first set committed stays
+        state.process_event(FactEvent::FeaturesSet { features: FeatureFlags::default(), timestamp: 1000 }.into(), 2);
+        assert_eq!(state.features(), features);
+        state.process_event(join(1, 0, 1000), 3);
+        state.process_event(join(2, 1, 1010), 4);
+        assert_eq!(state.sudden_death_at(600), None);
+        state.process_event(sudden_death(1610), 5);
+        assert_eq!(state.phase, GamePhase::Live);
+    }
+
+    #[test]
+    fn test_elimination_policy_captor_cascades() {
+        let (alice, bob, carol) = (NodeCoord::new(0, 0), NodeCoord::new(1, 0), NodeCoord::new(2, 0));
+
+        // No committed policy (older logs): the dead keep their nodes
+        let mut state = elimination_game(None);
+        state.process_event(capture(alice, 2, 2000), 6);
+        assert_eq!(state.nodes[&NodeCoord::new(0, 1)].owner_id, 1);
+
+        let mut state = elimination_game(Some(EliminationPolicy::Captor));
+        // The first policy committed stays
+        state.process_event(FactEvent::EliminationPolicySet { policy: EliminationPolicy::Neutral, timestamp: 1003 }.into(), 6);
+        assert_eq!(state.elimination_policy, Some(EliminationPolicy::Captor));
+
+        // Bob takes Alice's capital and, with it, everything she had
+        let target = Some(AttackTarget::Coordinate(NodeCoord::new(-1, 2)));
+        state.process_event(CommandEvent::SetNodeTarget { node_coord: NodeCoord::new(0, 2), target, timestamp: 1500 }.into(), 7);
+        let changes = state.process_event(capture(alice, 2, 2000), 8);
+        assert!(changes.contains(&StateChange::NodeOwnerChanged { coord: NodeCoord::new(0, 2), old_owner_id: 1, new_owner_id: 2 }));
+        assert!(changes.contains(&StateChange::NodeTargetChanged { coord: NodeCoord::new(0, 2), target: None }));
+        assert_eq!(state.captor(1), Some(2));
+        assert!(state.nodes.values().all(|n| n.owner_id != 1));
+
+        // Carol takes Bob's: she gets his nodes, Alice's old ones included, and wins
+        state.process_event(capture(bob, 3, 2100), 9);
+        assert_eq!(
+            owners(&state),
+            vec![
+                (alice, 3),
+                (NodeCoord::new(0, 1), 3),
+                (NodeCoord::new(0, 2), 3),
+                (bob, 3),
+                (NodeCoord::new(1, 1), 3),
+                (carol, 3),
+                (NodeCoord::new(2, 1), 3),
+            ]
+        );
+        assert!(state.game_over);
+        assert_eq!(state.winner(), Some(3));
+        assert!(state.check_invariants().is_empty());
+    }
+
+    #[test]
+    fn test_elimination_hands_back_contested_capital() {
+        let (alice, bob) = (NodeCoord::new(0, 0), NodeCoord::new(1, 0));
+        let mut state = elimination_game(Some(EliminationPolicy::Captor));
+
+        // Bob takes Alice's capital, but she has a last stand
+        let contest = FactEvent::CapitalContested { node_coord: alice, player_id: 1, expires_at: 2030, timestamp: 2000 };
+        state.process_event(contest.into(), 6);
+        state.process_event(capture(alice, 2, 2000), 7);
+        assert!(state.players[&1].alive);
+
+        // She eliminates Bob instead: her capital comes back with his nodes
+        state.process_event(capture(bob, 1, 2010), 8);
+        assert!(!state.players[&2].alive);
+        assert_eq!(state.nodes[&alice].owner_id, 1);
+        assert_eq!(state.nodes[&alice].node_type, NodeType::Capital);
+        assert!(state.contested_capitals.is_empty());
+        assert_eq!(state.nodes[&NodeCoord::new(1, 1)].owner_id, 1);
+
+        // Neutral: a contested capital goes neutral like the rest, its owner can still retake it
+        let mut state = elimination_game(Some(EliminationPolicy::Neutral));
+        state.process_event(capture(alice, 2, 2000), 6);
+        assert!(!state.players[&1].alive);
+        assert_eq!(state.nodes[&NodeCoord::new(0, 1)].owner_id, 0);
+        assert_eq!(state.nodes[&NodeCoord::new(0, 2)].owner_id, 0);
+        let contest = FactEvent::CapitalContested { node_coord: bob, player_id: 2, expires_at: 2130, timestamp: 2100 };
+        state.process_event(contest.into(), 7);
+        state.process_event(capture(bob, 3, 2100), 8);
+        // Carol eliminates Alice's killer without a captor: Bob's nodes go neutral
+        state.process_event(FactEvent::PlayerEliminated { player_id: 2, timestamp: 2130 }.into(), 9);
+        assert_eq!(state.nodes[&alice].owner_id, 0);
+        assert_eq!(state.nodes[&NodeCoord::new(1, 1)].owner_id, 0);
+        assert_eq!(state.nodes[&bob].owner_id, 3);
+        assert!(state.game_over);
+    }
+
+    #[test]
+    fn test_elimination_decay() {
+        let alice = NodeCoord::new(0, 0);
+        let (near, far) = (NodeCoord::new(0, 1), NodeCoord::new(0, 2));
+        let mut state = elimination_game(Some(EliminationPolicy::Decay { interval_secs: 10 }));
+        let target = Some(AttackTarget::Coordinate(NodeCoord::new(-1, 2)));
+        state.process_event(CommandEvent::SetNodeTarget { node_coord: far, target, timestamp: 1500 }.into(), 6);
+
+        // Alice falls: her nodes stand down but are hers until they decay
+        state.process_event(capture(alice, 2, 2000), 7);
+        assert_eq!(state.nodes[&far].owner_id, 1);
+        assert_eq!(state.nodes[&far].current_target, None);
+        assert_eq!(state.decaying[&1], NodeDecay { since: 2000, decayed: 0 });
+
+        // The furthest one goes first, the next one interval later
+        assert_eq!(state.next_decay(1), Some((far, 2010)));
+        state.process_event(FactEvent::NodeDecayed { node_coord: far, player_id: 1, timestamp: 2010 }.into(), 8);
+        assert_eq!(state.nodes[&far].owner_id, 0);
+        assert_eq!(state.next_decay(1), Some((near, 2020)));
+
+        // Captured before it decays: nothing left to decay, a late NodeDecayed is ignored
+        state.process_event(capture(near, 3, 2015), 9);
+        assert_eq!(state.next_decay(1), None);
+        state.process_event(FactEvent::NodeDecayed { node_coord: near, player_id: 1, timestamp: 2020 }.into(), 10);
+        assert_eq!(state.nodes[&near].owner_id, 3);
+        assert!(state.decaying.is_empty());
+
+        // Cascade: Bob's nodes, Alice's fallen capital among them, decay too - at game speed
+        let mut state = elimination_game(Some(EliminationPolicy::Decay { interval_secs: 10 }));
+        state.process_event(FactEvent::TimeScaleSet { scale: 2, timestamp: 1003 }.into(), 6);
+        state.process_event(capture(alice, 2, 2000), 7);
+        state.process_event(capture(NodeCoord::new(1, 0), 3, 2004), 8);
+        assert_eq!(state.decaying.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(state.next_decay(2), Some((NodeCoord::new(1, 1), 2009)));
+        assert_eq!(state.next_decay(1), Some((far, 2005)));
+
+        // A referee reviving Alice stops her decay
+        let revert = RefereeOverride::RevertCapture { node_coord: alice, owner_id: 1 };
+        state.process_event(FactEvent::RefereeAction { action: revert, reason: "test".to_string(), timestamp: 2006 }.into(), 9);
+        assert!(state.players[&1].alive);
+        assert!(!state.decaying.contains_key(&1));
+    }
+
+    #[test]
+    fn test_referee_overrides() {
+        let mut state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1), (3, 2)] {
+            state.process_event(
+                GameEvent::Command(CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 1000,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }),
+                id,
+            );
+        }
+        let referee = |action| FactEvent::RefereeAction { action, reason: "test".to_string(), timestamp: 1010 }.into();
+
+        // Still spawn-protected at 1010: protection is pushed back
+        state.process_event(referee(RefereeOverride::ExtendTime { player_id: 1, secs: 30 }), 4);
+        assert_eq!(state.players[&1].protected_until, 1000 + SPAWN_PROTECTION_SECS + 30);
+
+        // Alice's capital falls for good, then the referee reverts it
+        let alice_capital = NodeCoord::new(0, 0);
+        state.process_event(FactEvent::NodeCaptured { node_coord: alice_capital, new_owner_id: 2, timestamp: 2000 }.into(), 5);
+        assert!(!state.players[&1].alive);
+        state.process_event(referee(RefereeOverride::RevertCapture { node_coord: alice_capital, owner_id: 1 }), 6);
+        assert!(state.players[&1].alive);
+        assert_eq!(state.nodes[&alice_capital].owner_id, 1);
+        assert_eq!(state.nodes[&alice_capital].node_type, NodeType::Capital);
+
+        // Neutralizing two capitals ends the game
+        state.process_event(referee(RefereeOverride::NeutralizeNode { node_coord: NodeCoord::new(1, 0) }), 7);
+        assert_eq!(state.nodes[&NodeCoord::new(1, 0)].owner_id, 0);
+        assert!(!state.players[&2].alive);
+        assert!(!state.game_over);
+        state.process_event(referee(RefereeOverride::NeutralizeNode { node_coord: NodeCoord::new(2, 0) }), 8);
+        assert!(state.game_over);
+    }
+
+    #[test]
+    fn test_committed_timestamps_win() {
+        let mut state = GameState::new();
+        let join = |player_id, q| {
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id,
+                name: format!("P{}", player_id),
+                capital_coord: NodeCoord::new(q, 0),
+                capital_ip: None,
+                client_endpoint: None,
+                // A laptop clock an hour fast
+                timestamp: 4600,
+                raft_node_id: 0,
+                reconnect_key: None,
+            })
+        };
+
+        // Spawn protection runs from the leader's stamp, not the client's claim
+        state.process_committed(join(1, 0), 1, 1000);
+        assert_eq!(state.players[&1].join_time, 1000);
+        assert_eq!(state.players[&1].protected_until, 1000 + SPAWN_PROTECTION_SECS);
+
+        // A new leader with a slow clock doesn't rewind time
+        state.process_committed(join(2, 1), 2, 990);
+        assert_eq!(state.clock, 1000);
+        assert_eq!(state.players[&2].join_time, 1000);
+
+        // Unstamped processing keeps the event's own time
+        state.process_event(join(3, 2), 3);
+        assert_eq!(state.players[&3].join_time, 4600);
+    }
+
+    #[test]
+    fn test_idle_owner_hand_off() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: Some("10.0.1.1".to_string()),
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        state.process_event(
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(1, 0), owner_id: 1, timestamp: 1001 }.into(),
+            2,
+        );
+        for (index, coord) in [(3, NodeCoord::new(0, 0)), (4, NodeCoord::new(1, 0))] {
+            let target = Some(AttackTarget::Coordinate(NodeCoord::new(0, 1)));
+            state.process_event(CommandEvent::SetNodeTarget { node_coord: coord, target, timestamp: 1002 }.into(), index);
+        }
+        state.process_event(TelemetryEvent::ClientHeartbeat { player_id: 1, timestamp: 1005 }.into(), 5);
+        assert_eq!(state.players[&1].last_heartbeat, 1005);
+
+        // Client gone: nodes stand down but stay Alice's
+        state.process_event(FactEvent::OwnerIdle { player_id: 1, neutralize: false, timestamp: 1040 }.into(), 6);
+        assert_eq!(state.players[&1].idle_since, Some(1040));
+        assert!(state.nodes.values().all(|n| n.current_target.is_none() && n.owner_id == 1));
+
+        // Still gone: the regular node goes neutral, the capital doesn't
+        state.process_event(FactEvent::OwnerIdle { player_id: 1, neutralize: true, timestamp: 1100 }.into(), 7);
+        assert_eq!(state.players[&1].idle_since, Some(1040));
+        assert_eq!(state.nodes[&NodeCoord::new(1, 0)].owner_id, 0);
+        assert_eq!(state.nodes[&NodeCoord::new(0, 0)].owner_id, 1);
+        assert!(state.players[&1].alive);
+
+        // Client back
+        state.process_event(TelemetryEvent::ClientHeartbeat { player_id: 1, timestamp: 1200 }.into(), 8);
+        assert_eq!(state.players[&1].idle_since, None);
+        assert!(!state.players[&1].idle_neutralized);
+    }
+
+    #[test]
+    fn test_set_node_targets_applies_each_order() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        state.process_event(
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(1, 0), owner_id: 1, timestamp: 1001 }.into(),
+            2,
+        );
+
+        let target = Some(AttackTarget::Coordinate(NodeCoord::new(1, -1)));
+        let orders = vec![
+            AttackOrder { node_coord: NodeCoord::new(0, 0), target },
+            AttackOrder { node_coord: NodeCoord::new(1, 0), target },
+            // No node there: skipped like a lone SetNodeTarget would be
+            AttackOrder { node_coord: NodeCoord::new(5, 5), target },
+        ];
+        state.process_event(CommandEvent::SetNodeTargets { orders, timestamp: 1002 }.into(), 3);
+
+        assert!(state.nodes.values().all(|n| n.current_target == target));
+        assert!(!state.nodes.contains_key(&NodeCoord::new(5, 5)));
+    }
+
+    #[test]
+    fn test_scheduled_events() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        let target = Some(AttackTarget::Coordinate(NodeCoord::new(1, 0)));
+        let attack = CommandEvent::SetNodeTarget { node_coord: NodeCoord::new(0, 0), target, timestamp: 1001 };
+        let schedule = |command: CommandEvent| CommandEvent::ScheduleEvent { execute_at: 1100, command: Box::new(command), timestamp: 1001 };
+
+        // Queued, not applied; a schedule inside a schedule is dropped
+        let changes = state.process_event(schedule(attack.clone()).into(), 2);
+        assert_eq!(changes, [StateChange::EventScheduled { schedule_id: 2, execute_at: 1100 }]);
+        assert!(state.process_event(schedule(schedule(attack.clone())).into(), 3).is_empty());
+        assert_eq!(state.nodes[&NodeCoord::new(0, 0)].current_target, None);
+        assert_eq!(state.due_scheduled(1099).count(), 0);
+        assert_eq!(state.due_scheduled(1100).map(|(id, _)| id).collect::<Vec<_>>(), [2]);
+
+        // Firing applies it at the firing's time, once
+        let fired = FactEvent::ScheduledEventFired { schedule_id: 2, refused: None, timestamp: 1100 };
+        let changes = state.process_event(fired.clone().into(), 4);
+        assert_eq!(changes[0], StateChange::ScheduledEventFired { schedule_id: 2, refused: false });
+        assert_eq!(state.nodes[&NodeCoord::new(0, 0)].current_target, target);
+        assert_eq!(state.retargeted_at[&NodeCoord::new(0, 0)], 1100);
+        assert!(state.scheduled.is_empty());
+        assert!(state.process_event(fired.into(), 5).is_empty());
+
+        // A refused one only leaves the queue
+        let stop = CommandEvent::SetNodeTarget { node_coord: NodeCoord::new(0, 0), target: None, timestamp: 1101 };
+        state.process_event(schedule(stop).into(), 6);
+        let refused = FactEvent::ScheduledEventFired { schedule_id: 6, refused: Some("no".to_string()), timestamp: 1200 };
+        let changes = state.process_event(refused.into(), 7);
+        assert_eq!(changes, [StateChange::ScheduledEventFired { schedule_id: 6, refused: true }]);
+        assert_eq!(state.nodes[&NodeCoord::new(0, 0)].current_target, target);
+    }
+
+    #[test]
+    fn test_announcements() {
+        let mut state = GameState::new();
+        let announce = |text: &str, ttl_secs| {
+            GameEvent::from(FactEvent::Announcement {
+                text: text.to_string(),
+                severity: AnnouncementSeverity::Warning,
+                ttl_secs,
+                timestamp: 0,
+            })
+        };
+        let changes = state.process_committed(announce("5 minutes remaining", 30), 1, 1000);
+        assert_eq!(changes, [StateChange::AnnouncementPosted { announcement_id: 1 }]);
+        state.process_committed(announce("Demo restarting, sorry", 999_999), 2, 1010);
+        assert_eq!(state.announcements[&2].expires_at, 1010 + MAX_ANNOUNCEMENT_TTL_SECS);
+
+        let active = |state: &GameState, now| state.active_announcements(now).map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(active(&state, 1029), [1, 2]);
+        assert_eq!(active(&state, 1030), [2]);
+
+        // Expired ones go with the next announcement, the oldest once there are too many
+        state.process_committed(announce("Sudden death", 30), 3, 1040);
+        assert_eq!(state.announcements.keys().copied().collect::<Vec<_>>(), [2, 3]);
+        for index in 4..4 + MAX_ANNOUNCEMENTS as u64 {
+            state.process_committed(announce("Spam", 30), index, 1050);
+        }
+        assert_eq!(state.announcements.len(), MAX_ANNOUNCEMENTS);
+        assert!(!state.announcements.contains_key(&2));
+    }
+
+    #[test]
+    fn test_skip_reason() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        let aim = |node_coord| GameEvent::from(CommandEvent::SetNodeTarget { node_coord, target: None, timestamp: 1001 });
+        let heartbeat = |player_id| GameEvent::from(TelemetryEvent::ClientHeartbeat { player_id, timestamp: 1001 });
+
+        assert_eq!(state.skip_reason(&aim(NodeCoord::new(0, 0))), None);
+        assert_eq!(state.skip_reason(&aim(NodeCoord::new(4, 4))), Some("unknown node"));
+        assert_eq!(state.skip_reason(&heartbeat(1)), None);
+        assert_eq!(state.skip_reason(&heartbeat(9)), Some("unknown player"));
+        let completed = FactEvent::EffectCompleted { effect_id: 7, error: None, timestamp: 1001 };
+        assert_eq!(state.skip_reason(&completed.into()), Some("unknown effect"));
+        let ack = FactEvent::GameOverAcknowledged { raft_node_id: 1, timestamp: 1001 };
+        assert_eq!(state.skip_reason(&ack.into()), Some("no shutdown in progress"));
+
+        // A batch counts as long as one of its nodes exists
+        let order = |q| AttackOrder { node_coord: NodeCoord::new(q, 0), target: None };
+        let batch = |orders| GameEvent::from(CommandEvent::SetNodeTargets { orders, timestamp: 1001 });
+        assert_eq!(state.skip_reason(&batch(vec![order(0), order(5)])), None);
+        assert_eq!(state.skip_reason(&batch(vec![order(5)])), Some("unknown nodes"));
+
+        // Applying one anyway changes nothing
+        assert!(state.process_event(aim(NodeCoord::new(4, 4)), 2).is_empty());
+        assert!(!state.nodes.contains_key(&NodeCoord::new(4, 4)));
+    }
+
+    #[test]
+    fn test_retarget_cooldown() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        let capital = NodeCoord::new(0, 0);
+        let (east, west) = (NodeCoord::new(1, 0), NodeCoord::new(-1, 0));
+        let aim = |target: Option<NodeCoord>, timestamp| CommandEvent::SetNodeTarget {
+            node_coord: capital,
+            target: target.map(AttackTarget::Coordinate),
+            timestamp,
+        };
+
+        state.process_event(aim(Some(east), 1100).into(), 2);
+        assert_eq!(state.validate_attack(Some(1), capital, west, 1101), Err(AttackError::Cooldown { remaining_secs: 1 }));
+
+        // A retarget inside the cooldown is dropped; stopping isn't
+        state.process_event(aim(Some(west), 1101).into(), 3);
+        assert_eq!(state.nodes[&capital].current_target, Some(AttackTarget::Coordinate(east)));
+        state.process_event(aim(None, 1101).into(), 4);
+        assert_eq!(state.nodes[&capital].current_target, None);
+
+        state.process_event(aim(Some(west), 1100 + DEFAULT_RETARGET_COOLDOWN_SECS).into(), 5);
+        assert_eq!(state.nodes[&capital].current_target, Some(AttackTarget::Coordinate(west)));
+
+        // The committed cooldown wins, and only the first one counts
+        state.process_event(FactEvent::RetargetCooldownSet { secs: 10, timestamp: 0 }.into(), 6);
+        state.process_event(FactEvent::RetargetCooldownSet { secs: 0, timestamp: 0 }.into(), 7);
+        assert_eq!(state.retarget_cooldown(capital, 1105), Some(7));
+    }
+
+    #[test]
+    fn test_metrics_interval_committed_once() {
+        let mut state = GameState::new();
+        state.process_event(FactEvent::MetricsIntervalSet { interval_ms: 5, timestamp: 0 }.into(), 1);
+        state.process_event(FactEvent::MetricsIntervalSet { interval_ms: 1000, timestamp: 0 }.into(), 2);
+        assert_eq!(state.metrics_interval_ms, Some(MIN_METRICS_INTERVAL_MS));
+    }
+
+    #[test]
+    fn test_cluster_version_only_rises() {
+        let mut state = GameState::new();
+        let gated: GameEvent = FactEvent::ClusterVersionSet { version: 2, timestamp: 0 }.into();
+        assert_eq!(state.cluster_version(), 1);
+        assert!(!state.permits(&gated));
+
+        state.process_event(gated.clone(), 1);
+        state.process_event(FactEvent::ClusterVersionSet { version: 1, timestamp: 0 }.into(), 2);
+        assert_eq!(state.cluster_version(), 2);
+        assert!(state.permits(&gated));
+    }
+
+    #[test]
+    fn test_defense_boosts_responders() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        for (index, (q, owner_id)) in [(1, 1), (2, 2)].into_iter().enumerate() {
+            state.process_event(
+                FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(q, 0), owner_id, timestamp: 1001 }.into(),
+                2 + index as u64,
+            );
+        }
+        let capital = NodeCoord::new(0, 0);
+        let outpost = NodeCoord::new(1, 0);
+
+        assert_eq!(state.validate_defense(Some(1), outpost, Some(capital)), Ok(()));
+        assert_eq!(state.validate_defense(Some(1), outpost, Some(NodeCoord::new(2, 0))), Err(AttackError::InvalidAssist));
+        assert_eq!(state.validate_defense(Some(1), outpost, Some(NodeCoord::new(3, 0))), Err(AttackError::InvalidAssist));
+        assert_eq!(state.validate_defense(Some(2), outpost, None), Err(AttackError::NotOwner));
+
+        // Defending drops the node's attack
+        let target = Some(AttackTarget::Coordinate(NodeCoord::new(1, 1)));
+        state.process_event(CommandEvent::SetNodeTarget { node_coord: outpost, target, timestamp: 1002 }.into(), 4);
+        let defend = |assist| CommandEvent::SetNodeDefense {
+            node_coord: outpost,
+            defense: Some(DefenseOrder { assist }),
+            timestamp: 1003,
+        };
+        state.process_event(defend(None).into(), 5);
+        assert_eq!(state.nodes[&outpost].current_target, None);
+        assert_eq!(state.responder_multiplier(outpost), 1.0 + DEFEND_RESPONDER_BONUS);
+        assert_eq!(state.responder_multiplier(capital), 1.0);
+
+        // Assisting splits the bonus
+        state.process_event(defend(Some(capital)).into(), 6);
+        assert_eq!(state.responder_multiplier(outpost), 1.0 + DEFEND_RESPONDER_BONUS / 2.0);
+        assert_eq!(state.responder_multiplier(capital), 1.0 + DEFEND_RESPONDER_BONUS / 2.0);
+
+        // Attacking again ends the order
+        state.process_event(CommandEvent::SetNodeTarget { node_coord: outpost, target, timestamp: 1004 }.into(), 7);
+        assert!(state.defenses.is_empty());
+        assert_eq!(state.responder_multiplier(capital), 1.0);
+    }
+
+    #[test]
+    fn test_initialization_failed_frees_hex() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        state.process_event(
+            GameEvent::Command(CommandEvent::SetNodeTarget {
+                node_coord: NodeCoord::new(0, 0),
+                target: Some(AttackTarget::Coordinate(NodeCoord::new(1, 0))),
+                timestamp: 1001,
+            }),
+            2,
+        );
+        state.process_event(
+            GameEvent::Fact(FactEvent::NodeInitializationStarted {
+                node_coord: NodeCoord::new(1, 0),
+                owner_id: 0,
+                timestamp: 1002,
+            }),
+            3,
+        );
+
+        state.process_event(
+            GameEvent::Fact(FactEvent::NodeInitializationFailed {
+                node_coord: NodeCoord::new(1, 0),
+                attempts: 5,
+                reason: "master unavailable".to_string(),
+                timestamp: 1100,
+            }),
+            4,
+        );
+
+        assert!(!state.nodes.contains_key(&NodeCoord::new(1, 0)));
+        assert_eq!(state.nodes.get(&NodeCoord::new(0, 0)).unwrap().current_target, None);
+    }
+
+    #[test]
+    fn test_under_attack() {
+        let mut state = GameState::new();
+        for (coord, owner_id, target) in [
+            (NodeCoord::new(0, 0), 1, None),
+            (NodeCoord::new(1, 0), 2, Some(NodeCoord::new(0, 0))),
+            (NodeCoord::new(0, 1), 2, Some(NodeCoord::new(0, 0))),
+            (NodeCoord::new(-1, 0), 1, Some(NodeCoord::new(0, 0))), // Own node
+            (NodeCoord::new(2, 0), 1, Some(NodeCoord::new(3, 0))),  // Neutral target
+            (NodeCoord::new(3, 0), 0, None),
+        ] {
+            let node = Node {
+                coord,
+                owner_id,
+                node_type: NodeType::Regular,
+                current_target: target.map(AttackTarget::Coordinate),
+                init_state: NodeInitState::Ready,
+            };
+            state.nodes.insert(coord, node);
+        }
+        let metrics = NodeMetrics {
+            bandwidth_in: 9000,
+            packet_loss: 0.4,
+            timestamp: 1000,
+            queued_attackers: Vec::new(),
+            cpu_utilization: None,
+            memory_pressure: None,
+        };
+        state.node_metrics.insert(NodeCoord::new(0, 0), metrics);
+
+        let status = state.under_attack(1010);
+        assert_eq!(status.len(), 1);
+        let warning = &status[&NodeCoord::new(0, 0)];
+        assert_eq!(warning.owner_id, 1);
+        assert_eq!(warning.packet_loss, Some(0.4));
+        let directions: Vec<_> = warning.attackers.iter().map(|a| (a.owner_id, a.direction)).collect();
+        assert_eq!(directions, vec![(2, Some(HexDirection::SouthEast)), (2, Some(HexDirection::East))]);
+
+        // Stale metrics still warn, just without a loss figure
+        let status = state.under_attack(1000 + ATTACK_METRICS_MAX_AGE_SECS + 1);
+        assert_eq!(status[&NodeCoord::new(0, 0)].packet_loss, None);
+    }
+
+    #[test]
+    fn test_validate_attack() {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+
+        let capital = NodeCoord::new(0, 0);
+        assert_eq!(state.validate_attack(Some(1), capital, NodeCoord::new(1, 0), 1000), Ok(()));
+        assert_eq!(state.validate_attack(None, capital, NodeCoord::new(1, -1), 1000), Ok(()));
+        assert_eq!(state.validate_attack(Some(2), capital, NodeCoord::new(1, 0), 1000), Err(AttackError::NotOwner));
+        assert_eq!(state.validate_attack(Some(1), capital, NodeCoord::new(2, 0), 1000), Err(AttackError::NotAdjacent));
+        assert_eq!(
+            state.validate_attack(Some(1), NodeCoord::new(5, 5), capital, 1000),
+            Err(AttackError::UnknownNode(NodeCoord::new(5, 5)))
+        );
+
+        // Chasms can't be targeted
+        let chasm = crate::terrain::TerrainTile { coord: NodeCoord::new(1, 0), terrain: Terrain::Chasm };
+        state.process_event(FactEvent::MapGenerated { seed: 1, radius: 2, tiles: vec![chasm], timestamp: 1000 }.into(), 2);
+        assert_eq!(state.validate_attack(Some(1), capital, NodeCoord::new(1, 0), 1000), Err(AttackError::Impassable));
+        assert_eq!(state.map_seed, Some(1));
+    }
+
+    #[test]
+    fn test_spawn_tier_by_distance_from_capitals() {
+        let mut state = GameState::new();
+        assert_eq!(state.spawn_tier(NodeCoord::new(0, 0)), SpawnTier::Small);
+        for (id, q) in [(1, 0), (2, 10)] {
+            state.process_event(
+                GameEvent::Command(CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: None,
+                    client_endpoint: None,
+                    timestamp: 1000,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }),
+                id,
+            );
+        }
+
+        assert_eq!(state.spawn_tier(NodeCoord::new(1, -1)), SpawnTier::Large);
+        assert_eq!(state.spawn_tier(NodeCoord::new(9, 0)), SpawnTier::Large);
+        assert_eq!(state.spawn_tier(NodeCoord::new(3, 0)), SpawnTier::Regular);
+        assert_eq!(state.spawn_tier(NodeCoord::new(5, 0)), SpawnTier::Small);
+
+        // High ground out on the frontier still gets the bigger task
+        let highland = crate::terrain::TerrainTile { coord: NodeCoord::new(5, 0), terrain: Terrain::Highland };
+        state.process_event(FactEvent::MapGenerated { seed: 1, radius: 12, tiles: vec![highland], timestamp: 1000 }.into(), 3);
+        assert_eq!(state.spawn_tier(NodeCoord::new(5, 0)), SpawnTier::Large);
+    }
+
+    #[test]
+    fn test_spawn_protection() {
+        let mut state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            state.process_event(
+                GameEvent::Command(CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 1000 * id,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }),
+                id,
+            );
+        }
+        let (alice, bob) = (NodeCoord::new(0, 0), NodeCoord::new(1, 0));
+
+        // Alice (joined at 1000) is protected until 1060
+        assert_eq!(
+            state.validate_attack(Some(2), bob, alice, 1045),
+            Err(AttackError::SpawnProtected { remaining_secs: 15 })
+        );
+        assert_eq!(state.validate_attack(Some(2), bob, alice, 1060), Ok(()));
+        assert_eq!(state.spawn_protection(bob, 2030), Some(30));
+
+        // Protection belongs to the player, not the hex
+        state.process_event(
+            GameEvent::Fact(FactEvent::NodeCaptured { node_coord: bob, new_owner_id: 1, timestamp: 2030 }),
+            3,
+        );
+        assert_eq!(state.spawn_protection(bob, 2030), None);
+    }
+
+    #[test]
+    fn test_time_scale() {
+        assert_eq!(scale_secs(60, 10), 6);
+        assert_eq!(scale_secs(5, 10), 1);
+        assert_eq!(scale_secs(0, 10), 0);
+        assert_eq!(scale_secs(30, 0), 30);
+
+        let mut state = GameState::new();
+        state.process_event(FactEvent::TimeScaleSet { scale: 1000, timestamp: 0 }.into(), 1);
+        assert_eq!(state.time_scale, MAX_TIME_SCALE);
+
+        // Only the first one counts
+        state.process_event(FactEvent::TimeScaleSet { scale: 2, timestamp: 0 }.into(), 2);
+        assert_eq!(state.time_scale, MAX_TIME_SCALE);
+
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "P1".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: None,
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            3,
+        );
+        assert_eq!(state.players[&1].protected_until, 1000 + SPAWN_PROTECTION_SECS / 60);
+    }
+
+    #[test]
+    fn test_effect_ledger() {
+        let mut state = GameState::new();
+        let spawn = Effect::SpawnNode { node_coord: NodeCoord::new(1, 0) };
+        state.process_event(FactEvent::EffectRequested { effect: spawn.clone(), timestamp: 10 }.into(), 7);
+        state.process_event(FactEvent::EffectRequested { effect: Effect::ShutdownGame, timestamp: 11 }.into(), 8);
+
+        // Ids are the request's log index
+        assert_eq!(state.pending_effects().collect::<Vec<_>>(), vec![(7, &spawn), (8, &Effect::ShutdownGame)]);
+
+        state.process_event(FactEvent::EffectCompleted { effect_id: 7, error: None, timestamp: 12 }.into(), 9);
+        // A second ack (old leader finishing late) doesn't overwrite the first
+        let late = FactEvent::EffectCompleted { effect_id: 7, error: Some("timeout".to_string()), timestamp: 13 };
+        state.process_event(late.into(), 10);
+
+        assert_eq!(state.pending_effects().map(|(id, _)| id).collect::<Vec<_>>(), vec![8]);
+        assert_eq!(state.effects[&7].completion, Some(EffectCompletion { error: None, timestamp: 12 }));
+        assert!(state.effect_completed(|e| matches!(e, Effect::SpawnNode { .. })));
+        assert!(state.effect_requested(|e| *e == Effect::ShutdownGame));
+        assert!(!state.effect_completed(|e| *e == Effect::ShutdownGame));
+    }
+}
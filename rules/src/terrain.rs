@@ -0,0 +1,118 @@
+// Terrain - optional generated geography
+//
+// With a map enabled, the leader commits one MapGenerated event at game start listing
+// every non-plain hex within the map radius. Highland nodes hold out longer and hit
+// harder, lowland nodes the opposite, and chasms can't hold a node at all. The tiles
+// are committed rather than re-derived from the seed on every node, so a change to
+// the generator never changes the outcome of a replay.
+
+use crate::events::NodeCoord;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Kind of ground under a hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Terrain {
+    #[default]
+    Plain,
+    /// High ground - more capacity, slower to capture
+    Highland,
+    /// Low ground - less capacity, quicker to capture
+    Lowland,
+    /// Impassable - no node is ever spawned here
+    Chasm,
+}
+
+impl Terrain {
+    /// Capacity of a node on this ground relative to plain (0.0 = impassable)
+    /// Scales both how long the node takes to capture and what it adds to combined arms.
+    pub fn capacity_multiplier(self) -> f32 {
+        match self {
+            Terrain::Plain => 1.0,
+            Terrain::Highland => 1.5,
+            Terrain::Lowland => 0.75,
+            Terrain::Chasm => 0.0,
+        }
+    }
+
+    pub fn is_passable(self) -> bool {
+        self != Terrain::Chasm
+    }
+}
+
+/// Task size a new node's worker gets, by where its hex sits (see GameState::spawn_tier)
+/// Serialized lowercase, as the master's POST /spawn_single_node takes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpawnTier {
+    /// Far from every capital - fought over least, the smallest task
+    Small,
+    #[default]
+    Regular,
+    /// Next to a capital, or on high ground - the capital task size
+    Large,
+}
+
+/// One non-plain hex of a generated map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerrainTile {
+    pub coord: NodeCoord,
+    pub terrain: Terrain,
+}
+
+/// Out of 100 hexes, roughly how many get each non-plain terrain
+const HIGHLAND_PERCENT: u64 = 12;
+const LOWLAND_PERCENT: u64 = 12;
+const CHASM_PERCENT: u64 = 6;
+
+/// Terrain for every hex within `radius` of the origin, plain hexes left out
+/// Deterministic in `seed`, so the same seed always draws the same map.
+pub fn generate(seed: u64, radius: u32) -> Vec<TerrainTile> {
+    NodeCoord::new(0, 0)
+        .spiral(radius)
+        .into_iter()
+        .filter_map(|coord| {
+            let roll = hex_hash(seed, coord) % 100;
+            let terrain = if roll < HIGHLAND_PERCENT {
+                Terrain::Highland
+            } else if roll < HIGHLAND_PERCENT + LOWLAND_PERCENT {
+                Terrain::Lowland
+            } else if roll < HIGHLAND_PERCENT + LOWLAND_PERCENT + CHASM_PERCENT {
+                Terrain::Chasm
+            } else {
+                return None;
+            };
+            Some(TerrainTile { coord, terrain })
+        })
+        .collect()
+}
+
+/// SplitMix64 of the seed and coordinate
+fn hex_hash(seed: u64, coord: NodeCoord) -> u64 {
+    let mut x = seed ^ ((coord.q as u32 as u64) << 32 | coord.r as u32 as u64);
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let map = generate(42, 8);
+        assert_eq!(map, generate(42, 8));
+        assert_ne!(map, generate(43, 8));
+
+        // Only non-plain hexes, all within the radius, and a mix of each kind
+        let origin = NodeCoord::new(0, 0);
+        assert!(map.iter().all(|t| t.terrain != Terrain::Plain && t.coord.distance(&origin) <= 8));
+        for terrain in [Terrain::Highland, Terrain::Lowland, Terrain::Chasm] {
+            assert!(map.iter().any(|t| t.terrain == terrain), "no {:?}", terrain);
+        }
+        // 217 hexes at radius 8, about 30% of them non-plain
+        assert!((30..100).contains(&map.len()), "{} tiles", map.len());
+    }
+}
@@ -0,0 +1,467 @@
+// Upcasters - bring events written by older builds up to the current layout
+//
+// Every stored event carries its variant (the enum tag) and the format version it
+// was written at (`v` in the envelope). Decoding picks the layout that variant had
+// at that version and upcasts it, so the Raft log, snapshots and replay files all
+// come out in the current shape.
+//
+// When a variant's layout changes:
+// 1. bump EVENT_FORMAT_VERSION
+// 2. keep the old layout here as a `...V{n}` shape with an `Upcast` impl
+// 3. route versions before the bump to it in the family's `Versioned::decode`,
+//    and add it to the family's `AnyShape`
+// 4. register the change in SCHEMA_CHANGES and pin a fixture under rules/fixtures/
+
+use crate::events::{
+    AttackOrder, AttackTarget, CommandEvent, DefenseOrder, FactEvent, GameEvent, NodeCoord, TelemetryEvent, EVENT_FORMAT_VERSION,
+};
+use alloc::string::String;
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, EnumAccess, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+/// An old event layout that converts into the next one
+pub trait Upcast {
+    type Output;
+    fn upcast(self) -> Self::Output;
+}
+
+/// A registered layout change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// Format version that introduced the new layout
+    pub format_version: u16,
+    /// Variant whose layout changed
+    pub kind: &'static str,
+    pub description: &'static str,
+}
+
+/// Every layout change so far, oldest first
+pub const SCHEMA_CHANGES: &[SchemaChange] = &[
+    SchemaChange {
+        format_version: 2,
+        kind: "PlayerJoin",
+        description: "node_ip + is_client split into capital_ip and client_endpoint",
+    },
+    SchemaChange { format_version: 3, kind: "PlayerJoin", description: "reconnect_key added" },
+];
+
+/// Layout version of variant `kind` as written at `format_version` (starts at 1)
+pub fn variant_version(kind: &str, format_version: u16) -> u16 {
+    let changes = SCHEMA_CHANGES
+        .iter()
+        .filter(|change| change.kind == kind && change.format_version <= format_version)
+        .count();
+    1 + changes as u16
+}
+
+impl GameEvent {
+    /// Layout version of this event's variant in the current format
+    pub fn schema_version(&self) -> u16 {
+        variant_version(self.kind(), EVENT_FORMAT_VERSION)
+    }
+}
+
+// ============= Old Layouts =============
+
+/// CommandEvent as format v1 wrote it: PlayerJoin carried one IP, the client's if `is_client`
+#[derive(Deserialize)]
+pub(crate) enum CommandEventV1 {
+    PlayerJoin {
+        player_id: u64,
+        name: String,
+        capital_coord: NodeCoord,
+        node_ip: String,
+        is_client: bool,
+        timestamp: u64,
+        #[serde(default)]
+        raft_node_id: u64,
+    },
+    SetNodeTarget {
+        node_coord: NodeCoord,
+        target: Option<AttackTarget>,
+        timestamp: u64,
+    },
+}
+
+impl Upcast for CommandEventV1 {
+    type Output = CommandEvent;
+
+    fn upcast(self) -> CommandEvent {
+        match self {
+            CommandEventV1::PlayerJoin { player_id, name, capital_coord, node_ip, is_client, timestamp, raft_node_id } => {
+                let (capital_ip, client_endpoint) = if is_client { (None, Some(node_ip)) } else { (Some(node_ip), None) };
+                CommandEvent::PlayerJoin {
+                    player_id,
+                    name,
+                    capital_coord,
+                    capital_ip,
+                    client_endpoint,
+                    timestamp,
+                    raft_node_id,
+                    reconnect_key: None,
+                }
+            }
+            CommandEventV1::SetNodeTarget { node_coord, target, timestamp } => {
+                CommandEvent::SetNodeTarget { node_coord, target, timestamp }
+            }
+        }
+    }
+}
+
+/// CommandEvent as format v2 wrote it: PlayerJoin without a reconnect key
+/// Not in AnyCommandEvent: when the version isn't known up front the body is JSON,
+/// and the current layout's defaulted `reconnect_key` reads v2 bodies as they are.
+#[derive(Deserialize)]
+pub(crate) enum CommandEventV2 {
+    PlayerJoin {
+        player_id: u64,
+        name: String,
+        capital_coord: NodeCoord,
+        capital_ip: Option<String>,
+        client_endpoint: Option<String>,
+        timestamp: u64,
+        #[serde(default)]
+        raft_node_id: u64,
+    },
+    SetNodeTarget {
+        node_coord: NodeCoord,
+        target: Option<AttackTarget>,
+        timestamp: u64,
+    },
+    SetNodeTargets {
+        orders: alloc::vec::Vec<AttackOrder>,
+        timestamp: u64,
+    },
+    SetNodeDefense {
+        node_coord: NodeCoord,
+        defense: Option<DefenseOrder>,
+        timestamp: u64,
+    },
+}
+
+impl Upcast for CommandEventV2 {
+    type Output = CommandEvent;
+
+    fn upcast(self) -> CommandEvent {
+        match self {
+            CommandEventV2::PlayerJoin { player_id, name, capital_coord, capital_ip, client_endpoint, timestamp, raft_node_id } => {
+                CommandEvent::PlayerJoin {
+                    player_id,
+                    name,
+                    capital_coord,
+                    capital_ip,
+                    client_endpoint,
+                    timestamp,
+                    raft_node_id,
+                    reconnect_key: None,
+                }
+            }
+            CommandEventV2::SetNodeTarget { node_coord, target, timestamp } => {
+                CommandEvent::SetNodeTarget { node_coord, target, timestamp }
+            }
+            CommandEventV2::SetNodeTargets { orders, timestamp } => CommandEvent::SetNodeTargets { orders, timestamp },
+            CommandEventV2::SetNodeDefense { node_coord, defense, timestamp } => {
+                CommandEvent::SetNodeDefense { node_coord, defense, timestamp }
+            }
+        }
+    }
+}
+
+/// A command in any layout - older ones first, as they need fields later ones dropped
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum AnyCommandEvent {
+    V1(CommandEventV1),
+    Current(CommandEvent),
+}
+
+impl Upcast for AnyCommandEvent {
+    type Output = CommandEvent;
+
+    fn upcast(self) -> CommandEvent {
+        match self {
+            AnyCommandEvent::V1(event) => event.upcast(),
+            AnyCommandEvent::Current(event) => event,
+        }
+    }
+}
+
+// Families whose layout never changed are their own only shape
+impl Upcast for FactEvent {
+    type Output = Self;
+
+    fn upcast(self) -> Self {
+        self
+    }
+}
+
+impl Upcast for TelemetryEvent {
+    type Output = Self;
+
+    fn upcast(self) -> Self {
+        self
+    }
+}
+
+// ============= Version Dispatch =============
+
+/// An event family whose body layout depends on the format version
+pub(crate) trait Versioned: Sized {
+    /// Every layout the family has had, for when the version isn't known up front
+    type AnyShape: DeserializeOwned + Upcast<Output = Self>;
+
+    /// Decode a body written at format version `v`, upcast to the current layout
+    fn decode<'de, D: Deserializer<'de>>(v: u16, deserializer: D) -> Result<Self, D::Error>;
+}
+
+impl Versioned for CommandEvent {
+    type AnyShape = AnyCommandEvent;
+
+    fn decode<'de, D: Deserializer<'de>>(v: u16, deserializer: D) -> Result<Self, D::Error> {
+        match v {
+            0..=1 => CommandEventV1::deserialize(deserializer).map(Upcast::upcast),
+            2 => CommandEventV2::deserialize(deserializer).map(Upcast::upcast),
+            _ => CommandEvent::deserialize(deserializer),
+        }
+    }
+}
+
+impl Versioned for FactEvent {
+    type AnyShape = FactEvent;
+
+    fn decode<'de, D: Deserializer<'de>>(_v: u16, deserializer: D) -> Result<Self, D::Error> {
+        FactEvent::deserialize(deserializer)
+    }
+}
+
+impl Versioned for TelemetryEvent {
+    type AnyShape = TelemetryEvent;
+
+    fn decode<'de, D: Deserializer<'de>>(_v: u16, deserializer: D) -> Result<Self, D::Error> {
+        TelemetryEvent::deserialize(deserializer)
+    }
+}
+
+/// Decodes one family's body at a known format version
+struct FamilySeed<F> {
+    v: u16,
+    family: PhantomData<F>,
+}
+
+impl<F> FamilySeed<F> {
+    fn new(v: u16) -> Self {
+        Self { v, family: PhantomData }
+    }
+}
+
+impl<'de, F: Versioned> DeserializeSeed<'de> for FamilySeed<F> {
+    type Value = F;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<F, D::Error> {
+        F::decode(self.v, deserializer)
+    }
+}
+
+/// Family tag of a binary body (`Command(..)`, `Fact(..)`, `Telemetry(..)`)
+#[derive(Deserialize)]
+enum FamilyTag {
+    Command,
+    Fact,
+    Telemetry,
+}
+
+/// Decodes a binary event body written at format version `v`
+struct BodySeed {
+    v: u16,
+}
+
+impl<'de> DeserializeSeed<'de> for BodySeed {
+    type Value = GameEvent;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<GameEvent, D::Error> {
+        deserializer.deserialize_enum("GameEvent", &["Command", "Fact", "Telemetry"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for BodySeed {
+    type Value = GameEvent;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a game event body")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<GameEvent, A::Error> {
+        let (family, body) = data.variant::<FamilyTag>()?;
+        Ok(match family {
+            FamilyTag::Command => GameEvent::Command(body.newtype_variant_seed(FamilySeed::new(self.v))?),
+            FamilyTag::Fact => GameEvent::Fact(body.newtype_variant_seed(FamilySeed::new(self.v))?),
+            FamilyTag::Telemetry => GameEvent::Telemetry(body.newtype_variant_seed(FamilySeed::new(self.v))?),
+        })
+    }
+}
+
+/// Binary `{ v, event }` envelope; the body is decoded at the version read first
+pub(crate) struct VersionedBody {
+    pub v: u16,
+    pub event: GameEvent,
+}
+
+impl<'de> Deserialize<'de> for VersionedBody {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BodyVisitor;
+
+        impl<'de> Visitor<'de> for BodyVisitor {
+            type Value = VersionedBody;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a versioned game event")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<VersionedBody, A::Error> {
+                let v = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let event = seq
+                    .next_element_seed(BodySeed { v })?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(VersionedBody { v, event })
+            }
+        }
+
+        deserializer.deserialize_tuple(2, BodyVisitor)
+    }
+}
+
+/// JSON `{ v, event }` of one family (the `family` tag is already consumed)
+/// Our encoder writes `v` first, so the body is decoded at that version; if
+/// `event` comes first every known layout is tried instead.
+pub(crate) struct VersionedJson<F> {
+    pub v: u16,
+    pub event: F,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum EnvelopeField {
+    V,
+    Event,
+    #[serde(other)]
+    Other,
+}
+
+impl<'de, F: Versioned> Deserialize<'de> for VersionedJson<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct JsonVisitor<F>(PhantomData<F>);
+
+        impl<'de, F: Versioned> Visitor<'de> for JsonVisitor<F> {
+            type Value = VersionedJson<F>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an event envelope")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<VersionedJson<F>, A::Error> {
+                let mut v = None;
+                let mut event = None;
+                while let Some(field) = map.next_key()? {
+                    match field {
+                        EnvelopeField::V => v = Some(map.next_value()?),
+                        EnvelopeField::Event => {
+                            event = Some(match v {
+                                Some(v) => map.next_value_seed(FamilySeed::new(v))?,
+                                None => map.next_value::<F::AnyShape>()?.upcast(),
+                            });
+                        }
+                        EnvelopeField::Other => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(VersionedJson {
+                    v: v.ok_or_else(|| de::Error::missing_field("v"))?,
+                    event: event.ok_or_else(|| de::Error::missing_field("event"))?,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(JsonVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fixtures were written by the builds that used each format; they must keep
+    // decoding to the same current events for as long as logs may contain them.
+
+    const KEY: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn assert_player_join(event: GameEvent, capital: Option<&str>, client: Option<&str>) {
+        assert_reconnectable_join(event, capital, client, None);
+    }
+
+    fn assert_reconnectable_join(event: GameEvent, capital: Option<&str>, client: Option<&str>, key: Option<&str>) {
+        match event {
+            GameEvent::Command(CommandEvent::PlayerJoin { player_id, capital_ip, client_endpoint, raft_node_id, reconnect_key, .. }) => {
+                assert_eq!(player_id, 4);
+                assert_eq!(capital_ip.as_deref(), capital);
+                assert_eq!(client_endpoint.as_deref(), client);
+                assert_eq!(raft_node_id, 11);
+                assert_eq!(reconnect_key.as_deref(), key);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_binary_fixtures() {
+        // Pre-envelope layout
+        let v0: GameEvent = bincode::deserialize(include_bytes!("../fixtures/v0-set-target.bin")).unwrap();
+        assert!(matches!(v0, GameEvent::Command(CommandEvent::SetNodeTarget { timestamp: 42, .. })));
+
+        // v1 envelope, client player
+        let v1: GameEvent = bincode::deserialize(include_bytes!("../fixtures/v1-player-join.bin")).unwrap();
+        assert_player_join(v1, None, Some("192.168.1.9"));
+
+        // v2 envelope
+        let v2: GameEvent = bincode::deserialize(include_bytes!("../fixtures/v2-player-join.bin")).unwrap();
+        assert_player_join(v2, Some("10.0.0.4"), Some("192.168.1.9"));
+
+        // v3 envelope, with a reconnect key
+        let v3: GameEvent = bincode::deserialize(include_bytes!("../fixtures/v3-player-join.bin")).unwrap();
+        assert_reconnectable_join(v3, Some("10.0.0.4"), Some("192.168.1.9"), Some(KEY));
+    }
+
+    #[test]
+    fn test_json_fixtures() {
+        let v0: GameEvent = serde_json::from_str(include_str!("../fixtures/v0-node-captured.json")).unwrap();
+        assert!(matches!(v0, GameEvent::Fact(FactEvent::NodeCaptured { new_owner_id: 9, .. })));
+
+        let v1: GameEvent = serde_json::from_str(include_str!("../fixtures/v1-player-join.json")).unwrap();
+        assert_player_join(v1, Some("10.0.0.4"), None);
+
+        let v2: GameEvent = serde_json::from_str(include_str!("../fixtures/v2-player-join.json")).unwrap();
+        assert_player_join(v2, Some("10.0.0.4"), Some("192.168.1.9"));
+
+        let v3: GameEvent = serde_json::from_str(include_str!("../fixtures/v3-player-join.json")).unwrap();
+        assert_reconnectable_join(v3, Some("10.0.0.4"), Some("192.168.1.9"), Some(KEY));
+    }
+
+    #[test]
+    fn test_json_body_before_version() {
+        // Hand-written envelopes may put `event` first - every layout is tried
+        let json = r#"{"event":{"PlayerJoin":{"player_id":4,"name":"Dan","capital_coord":{"q":0,"r":1},
+            "node_ip":"192.168.1.9","is_client":true,"timestamp":6,"raft_node_id":11}},"family":"command","v":1}"#;
+        assert_player_join(serde_json::from_str(json).unwrap(), None, Some("192.168.1.9"));
+    }
+
+    #[test]
+    fn test_variant_versions() {
+        assert_eq!(variant_version("PlayerJoin", 1), 1);
+        assert_eq!(variant_version("PlayerJoin", 2), 2);
+        assert_eq!(variant_version("PlayerJoin", EVENT_FORMAT_VERSION), 3);
+        assert_eq!(variant_version("NodeCaptured", EVENT_FORMAT_VERSION), 1);
+        assert!(SCHEMA_CHANGES.iter().all(|change| change.format_version <= EVENT_FORMAT_VERSION));
+    }
+}
@@ -0,0 +1,28 @@
+// Play a scenario script against a live game
+//
+//   cargo run --example scenario -- scenarios/tutorial.yaml <worker url> [referee token]
+//
+// The worker should be the game's leader (writes elsewhere are refused). The referee
+// token, also read from REFEREE_TOKEN, is only needed for `capture` steps. `event` steps
+// go to the worker's internal listener and need INTERNAL_API_TOKEN if the cluster has one.
+
+use camhack_client_sdk::{Scenario, ScenarioRunner, WorkerApi};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), Some(worker_url)) = (args.next(), args.next()) else {
+        anyhow::bail!("Usage: scenario <scenario.yaml> <worker url> [referee token]");
+    };
+    let token = args.next().or_else(|| std::env::var("REFEREE_TOKEN").ok());
+
+    let scenario = Scenario::from_yaml(&std::fs::read_to_string(&path)?)?;
+    if let Some(description) = &scenario.description {
+        println!("{}", description);
+    }
+    let mut runner = ScenarioRunner::new(WorkerApi::new(&worker_url));
+    if let Some(token) = &token {
+        runner = runner.with_referee_token(token);
+    }
+    runner.run(&scenario).await
+}
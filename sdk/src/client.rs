@@ -0,0 +1,222 @@
+// Player client API (the client binary, default port 8080)
+
+use crate::http::{events_path, ws_url, HttpClient, RetryPolicy};
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use tokio_tungstenite::tungstenite::Message;
+use worker::client_api::{
+    BulkAttackRequest, BulkAttackResponse, ClientGameState, ClientMessage, CommandRecord, DefendRequest, JoinRequest, JoinStatus,
+    OwnedNodeInfo, PathResponse, PlayerStatusResponse, ServerMessage, SetAttackRequest, StateUpdate, Topic,
+};
+use worker::game::{Awards, GameEvent, NodeCoord};
+use worker::raft::api::{EventsQuery, EventsResponse, GridNeighborsResponse, GridPathResponse};
+use worker::registry::GetGamesResponse;
+
+/// Typed wrapper around a client binary's HTTP API
+#[derive(Debug, Clone)]
+pub struct ClientApi {
+    http: HttpClient,
+}
+
+impl ClientApi {
+    /// `base_url` is "host:port" or a full URL, e.g. http://localhost:8080
+    pub fn new(base_url: &str) -> Self {
+        Self::with_retry(base_url, RetryPolicy::default())
+    }
+
+    pub fn with_retry(base_url: &str, retry: RetryPolicy) -> Self {
+        Self { http: HttpClient::new(base_url, retry) }
+    }
+
+    /// GET /discover - games known to the master (optionally only joinable ones)
+    pub async fn discover(&self, joinable_only: bool) -> Result<GetGamesResponse> {
+        let path = if joinable_only { "/discover?joinable=true" } else { "/discover" };
+        self.http.get(path).await
+    }
+
+    /// GET /status - whether this client has joined a game
+    pub async fn status(&self) -> Result<JoinStatus> {
+        self.http.get("/status").await
+    }
+
+    /// POST /join - join `game_id` as `player_name`
+    pub async fn join(&self, game_id: &str, player_name: &str) -> Result<String> {
+        let request = JoinRequest {
+            game_id: game_id.to_string(),
+            player_name: player_name.to_string(),
+            token: None,
+        };
+        self.http.post("/join", &request).await
+    }
+
+    /// POST /join - join `game_id` under a master account (see `MasterApi::register_player`)
+    pub async fn join_with_token(&self, game_id: &str, player_name: &str, token: &str) -> Result<String> {
+        let request = JoinRequest {
+            game_id: game_id.to_string(),
+            player_name: player_name.to_string(),
+            token: Some(token.to_string()),
+        };
+        self.http.post("/join", &request).await
+    }
+
+    /// GET /my/status
+    pub async fn my_status(&self) -> Result<PlayerStatusResponse> {
+        self.http.get("/my/status").await
+    }
+
+    /// GET /my/nodes
+    pub async fn my_nodes(&self) -> Result<Vec<OwnedNodeInfo>> {
+        self.http.get("/my/nodes").await
+    }
+
+    /// GET /my/path - route to `to`, starting at `from` (default: the capital)
+    pub async fn my_path(&self, from: Option<NodeCoord>, to: NodeCoord) -> Result<PathResponse> {
+        let mut path = format!("/my/path?to={}", to);
+        if let Some(from) = from {
+            path.push_str(&format!("&from={}", from));
+        }
+        self.http.get(&path).await
+    }
+
+    /// POST /my/attack - attack `target` from `node` (default: the capital)
+    pub async fn attack(&self, target: NodeCoord, node: Option<NodeCoord>) -> Result<String> {
+        let request = SetAttackRequest { target, node };
+        self.http.post("/my/attack", &request).await
+    }
+
+    /// POST /my/defend - garrison `node` (default: the capital), giving half the boost to `assist` if set
+    pub async fn defend(&self, node: Option<NodeCoord>, assist: Option<NodeCoord>) -> Result<String> {
+        let request = DefendRequest { node, assist, stand_down: false };
+        self.http.post("/my/defend", &request).await
+    }
+
+    /// POST /my/defend with `stand_down` - `node` (default: the capital) may attack again
+    pub async fn stand_down(&self, node: Option<NodeCoord>) -> Result<String> {
+        let request = DefendRequest { node, assist: None, stand_down: true };
+        self.http.post("/my/defend", &request).await
+    }
+
+    /// POST /my/attack/bulk - several orders committed as one batch; invalid ones come back in `rejected`
+    pub async fn attack_bulk(&self, orders: &[SetAttackRequest]) -> Result<BulkAttackResponse> {
+        let request = BulkAttackRequest { orders: orders.to_vec() };
+        self.http.post("/my/attack/bulk", &request).await
+    }
+
+    /// GET /my/history - recent orders and their commit status, newest first
+    pub async fn my_history(&self) -> Result<Vec<CommandRecord>> {
+        self.http.get("/my/history").await
+    }
+
+    /// POST /my/history/{id}/cancel - abandon a still-pending order (it may commit anyway)
+    pub async fn cancel_command(&self, id: u64) -> Result<String> {
+        self.http.post(&format!("/my/history/{}/cancel", id), &()).await
+    }
+
+    /// GET /game/state
+    pub async fn game_state(&self) -> Result<ClientGameState> {
+        self.http.get("/game/state").await
+    }
+
+    /// GET /events - one page of the committed log (follow `next_after_index` for more)
+    pub async fn events(&self, query: &EventsQuery) -> Result<EventsResponse> {
+        self.http.get(&events_path(query)).await
+    }
+
+    /// POST /events - submit a raw game event
+    pub async fn submit_event(&self, event: &GameEvent) -> Result<String> {
+        self.http.post("/events", event).await
+    }
+
+    /// GET /awards - the game's awards (standings so far until it's over)
+    pub async fn awards(&self) -> Result<Awards> {
+        self.http.get("/awards").await
+    }
+
+    /// GET /grid/neighbors - hexes within `radius` of `center`
+    pub async fn grid_neighbors(&self, center: NodeCoord, radius: u32) -> Result<GridNeighborsResponse> {
+        let path = format!("/grid/neighbors?coord={}&radius={}", center, radius);
+        self.http.get(&path).await
+    }
+
+    /// GET /grid/path - straight hex line between two coordinates
+    pub async fn grid_path(&self, from: NodeCoord, to: NodeCoord) -> Result<GridPathResponse> {
+        let path = format!("/grid/path?from={}&to={}", from, to);
+        self.http.get(&path).await
+    }
+
+    /// Subscribe to /ws - yields a `StateUpdate` whenever the applied log advances
+    ///
+    /// The stream ends when the client closes the socket (e.g. on leaving the game).
+    /// An error message from the client ("not joined") is yielded as an `Err`.
+    pub async fn subscribe(&self) -> Result<impl Stream<Item = Result<StateUpdate>>> {
+        let url = ws_url(self.http.base_url(), "/ws");
+        let (socket, _) = tokio_tungstenite::connect_async(&url)
+            .await
+            .with_context(|| format!("Failed to connect to {}", url))?;
+
+        Ok(socket.filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(parse_ws::<StateUpdate>(&text)),
+                Ok(_) => None,
+                Err(e) => Some(Err(anyhow::anyhow!("WebSocket error: {}", e))),
+            }
+        }))
+    }
+
+    /// Subscribe to /ws `topics` - yields a versioned `ServerMessage` for each one as it
+    /// changes, starting with the `subscribed` acknowledgement
+    ///
+    /// Unknown topics come back as a `ServerBody::Error` message, not an `Err`.
+    pub async fn subscribe_topics(&self, topics: &[Topic]) -> Result<impl Stream<Item = Result<ServerMessage>>> {
+        self.open_topics(topics, None).await
+    }
+
+    /// Like `subscribe_topics`, with at most one push per `interval_ms` and `nodes`/`players`
+    /// as `NodesDelta`/`PlayersDelta` between full keyframes
+    ///
+    /// The `configured` reply follows `subscribed`.
+    pub async fn subscribe_deltas(
+        &self,
+        topics: &[Topic],
+        interval_ms: Option<u64>,
+    ) -> Result<impl Stream<Item = Result<ServerMessage>>> {
+        self.open_topics(topics, Some(ClientMessage::Configure { interval_ms, delta: Some(true) })).await
+    }
+
+    async fn open_topics(
+        &self,
+        topics: &[Topic],
+        configure: Option<ClientMessage>,
+    ) -> Result<impl Stream<Item = Result<ServerMessage>>> {
+        let url = ws_url(self.http.base_url(), "/ws");
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url)
+            .await
+            .with_context(|| format!("Failed to connect to {}", url))?;
+
+        let subscribe = ClientMessage::Subscribe { topics: topics.iter().map(Topic::to_string).collect() };
+        for message in std::iter::once(subscribe).chain(configure) {
+            socket
+                .send(Message::Text(serde_json::to_string(&message)?))
+                .await
+                .context("Failed to send subscription")?;
+        }
+
+        Ok(socket.filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(parse_ws::<ServerMessage>(&text)),
+                Ok(_) => None,
+                Err(e) => Some(Err(anyhow::anyhow!("WebSocket error: {}", e))),
+            }
+        }))
+    }
+}
+
+/// A /ws message as `T`, or the client's "not joined" error (sent before anything else)
+fn parse_ws<T: DeserializeOwned>(text: &str) -> Result<T> {
+    let value: serde_json::Value = serde_json::from_str(text).context("Invalid WebSocket message")?;
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        anyhow::bail!("{}", error);
+    }
+    serde_json::from_value(value).context("Unexpected WebSocket message")
+}
@@ -0,0 +1,352 @@
+// Shared HTTP plumbing: base URL handling, JSON bodies and retries
+
+use anyhow::{Context, Result};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+use worker::raft::api::EventsQuery;
+use worker::raft::audit::AuditQuery;
+use worker::request_id;
+
+/// How failed requests are retried
+///
+/// GETs are retried on connection errors, timeouts and 502/503/504.
+/// POSTs are only retried when the request never reached the server
+/// (connection refused etc.), so an event is never submitted twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Single attempt, no retries
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Delay before retry number `attempt` (1-based), doubling up to max_backoff
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Gateway errors worth retrying - the server behind the proxy may be back shortly
+fn retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// JSON-over-HTTP client bound to one base URL
+#[derive(Debug, Clone)]
+pub(crate) struct HttpClient {
+    base_url: String,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+    /// Bearer token sent with every request that doesn't bring its own
+    bearer: Option<String>,
+}
+
+impl HttpClient {
+    pub(crate) fn new(base_url: &str, retry: RetryPolicy) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            base_url: normalize_base_url(base_url),
+            http,
+            retry,
+            bearer: None,
+        }
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request (None: nothing)
+    pub(crate) fn with_bearer(mut self, token: Option<&str>) -> Self {
+        self.bearer = token.map(str::to_string);
+        self
+    }
+
+    pub(crate) fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub(crate) fn retry(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    pub(crate) fn bearer(&self) -> Option<&str> {
+        self.bearer.as_deref()
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.request::<(), T>(Method::GET, path, None, None).await
+    }
+
+    /// GET with `Authorization: Bearer <token>`
+    pub(crate) async fn get_authorized<T: DeserializeOwned>(&self, path: &str, token: &str) -> Result<T> {
+        self.request::<(), T>(Method::GET, path, None, Some(token)).await
+    }
+
+    pub(crate) async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.request(Method::POST, path, Some(body), None).await
+    }
+
+    /// POST with `Authorization: Bearer <token>`
+    pub(crate) async fn post_authorized<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        token: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.request(Method::POST, path, Some(body), Some(token)).await
+    }
+
+    async fn request<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+        bearer: Option<&str>,
+    ) -> Result<T> {
+        let bearer = bearer.or(self.bearer.as_deref());
+        let url = format!("{}{}", self.base_url, path);
+        let idempotent = method == Method::GET;
+        let mut attempt = 1;
+        // One X-Request-Id for every attempt, so the server logs show the retries together
+        let id = request_id::current().unwrap_or_else(request_id::new_id);
+
+        loop {
+            let mut request = self.http.request(method.clone(), &url).header(request_id::HEADER.as_str(), &id);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            if let Some(token) = bearer {
+                request = request.bearer_auth(token);
+            }
+
+            let retry = match request.send().await {
+                Ok(response) if idempotent && retryable_status(response.status()) && attempt < self.retry.max_attempts => {
+                    format!("{} returned {}", url, response.status())
+                }
+                Ok(response) => return decode(&method, &url, &id, response).await,
+                Err(e) if (e.is_connect() || (idempotent && e.is_timeout())) && attempt < self.retry.max_attempts => {
+                    format!("{} failed: {}", url, e)
+                }
+                Err(e) => return Err(e).with_context(|| format!("{} {} failed (request {})", method, url, id)),
+            };
+
+            let delay = self.retry.backoff(attempt);
+            eprintln!("[SDK] {}, retrying in {:?} (attempt {}/{})", retry, delay, attempt + 1, self.retry.max_attempts);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Turn a response into `T`, surfacing the body text on failure
+/// (the client API reports errors as plain-text bodies) and the request id to grep for
+async fn decode<T: DeserializeOwned>(method: &Method, url: &str, id: &str, response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read response from {}", url))?;
+
+    if !status.is_success() {
+        anyhow::bail!("{} {} returned {} (request {}): {}", method, url, status, id, text);
+    }
+
+    serde_json::from_str(&text).map_err(|e| anyhow::anyhow!("{} {}: {} (body: {})", method, url, e, text))
+}
+
+/// Accept "host:port" or a full URL, without a trailing slash
+fn normalize_base_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    if base_url.starts_with("http://") || base_url.starts_with("https://") {
+        base_url.to_string()
+    } else {
+        format!("http://{}", base_url)
+    }
+}
+
+/// Base URL of the worker internal listener on the same host as `base_url`
+/// The port is INTERNAL_API_PORT (default 8082) and the scheme follows PEER_TLS, as
+/// the workers' own calls to each other do.
+pub(crate) fn internal_base_url(base_url: &str) -> String {
+    let base_url = normalize_base_url(base_url);
+    let host = reqwest::Url::parse(&base_url)
+        .ok()
+        .and_then(|url| url.host().map(|host| host.to_string()))
+        .unwrap_or_else(|| "localhost".to_string());
+    worker::raft::internal::peer_internal_url(&host, "")
+}
+
+/// GET /events path for `query` (worker and client take the same parameters)
+pub(crate) fn events_path(query: &EventsQuery) -> String {
+    let mut params = Vec::new();
+    if let Some(after_index) = query.after_index {
+        params.push(format!("after_index={}", after_index));
+    }
+    if let Some(limit) = query.limit {
+        params.push(format!("limit={}", limit));
+    }
+    if let Some(event_type) = &query.event_type {
+        params.push(format!("event_type={}", event_type));
+    }
+    if let Some(coord) = query.coord {
+        params.push(format!("coord={}", coord));
+    }
+    if let Some(player) = query.player {
+        params.push(format!("player={}", player));
+    }
+    if params.is_empty() {
+        "/events".to_string()
+    } else {
+        format!("/events?{}", params.join("&"))
+    }
+}
+
+/// GET /admin/audit path for `query`
+pub(crate) fn audit_path(query: &AuditQuery) -> String {
+    let mut params = Vec::new();
+    if let Some(player_id) = query.player_id {
+        params.push(format!("player_id={}", player_id));
+    }
+    if let Some(after_index) = query.after_index {
+        params.push(format!("after_index={}", after_index));
+    }
+    if let Some(limit) = query.limit {
+        params.push(format!("limit={}", limit));
+    }
+    if params.is_empty() {
+        "/admin/audit".to_string()
+    } else {
+        format!("/admin/audit?{}", params.join("&"))
+    }
+}
+
+/// WebSocket URL for `path` on an HTTP base URL
+pub(crate) fn ws_url(base_url: &str, path: &str) -> String {
+    let base = if let Some(rest) = base_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        format!("ws://{}", base_url)
+    };
+    format!("{}{}", base, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::routing::get;
+    use axum::Router;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use worker::game::NodeCoord;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(normalize_base_url("10.0.0.5:8080/"), "http://10.0.0.5:8080");
+        assert_eq!(normalize_base_url("https://game.example"), "https://game.example");
+        assert_eq!(ws_url("http://localhost:8080", "/ws"), "ws://localhost:8080/ws");
+        assert_eq!(ws_url("https://game.example", "/ws"), "wss://game.example/ws");
+        assert_eq!(internal_base_url("10.0.0.5:8080"), "http://10.0.0.5:8082");
+        assert_eq!(internal_base_url("https://game.example/"), "http://game.example:8082");
+        assert_eq!(internal_base_url("http://[::1]:8080"), "http://[::1]:8082");
+        assert!(retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    #[test]
+    fn test_events_path() {
+        assert_eq!(events_path(&EventsQuery::default()), "/events");
+        let query = EventsQuery {
+            after_index: Some(41),
+            limit: Some(100),
+            event_type: Some("NodeCaptured,PlayerEliminated".to_string()),
+            coord: Some(NodeCoord::new(1, -2)),
+            player: None,
+        };
+        assert_eq!(
+            events_path(&query),
+            "/events?after_index=41&limit=100&event_type=NodeCaptured,PlayerEliminated&coord=1,-2"
+        );
+    }
+
+    #[test]
+    fn test_audit_path() {
+        assert_eq!(audit_path(&AuditQuery::default()), "/admin/audit");
+        let query = AuditQuery { player_id: Some(7), after_index: Some(12), limit: None };
+        assert_eq!(audit_path(&query), "/admin/audit?player_id=7&after_index=12");
+    }
+
+    /// 503 for the first two requests, then 200
+    async fn flaky_status(State(hits): State<Arc<AtomicU32>>) -> Result<axum::Json<serde_json::Value>, axum::http::StatusCode> {
+        if hits.fetch_add(1, Ordering::SeqCst) < 2 {
+            Err(axum::http::StatusCode::SERVICE_UNAVAILABLE)
+        } else {
+            Ok(axum::Json(serde_json::json!({ "ok": true })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_gateway_errors() {
+        let hits = Arc::new(AtomicU32::new(0));
+        let app = Router::new()
+            .route("/status", get(flaky_status))
+            .with_state(hits.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        };
+        let client = HttpClient::new(&addr.to_string(), policy);
+        let body: serde_json::Value = client.get("/status").await.unwrap();
+        assert_eq!(body["ok"], true);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // Out of attempts: the 503 is surfaced
+        hits.store(0, Ordering::SeqCst);
+        let client = HttpClient::new(&addr.to_string(), RetryPolicy::none());
+        assert!(client.get::<serde_json::Value>("/status").await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
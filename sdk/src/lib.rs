@@ -0,0 +1,32 @@
+// camhack-client-sdk - typed Rust client for the CamHack HTTP APIs
+//
+// Wraps the player client, worker and master endpoints. Request/response
+// types come from the worker crate, the same structs the servers serialize,
+// so the SDK can't drift from the wire format.
+
+mod client;
+mod http;
+mod master;
+pub mod scenario;
+mod worker_api;
+
+pub use client::ClientApi;
+pub use http::RetryPolicy;
+pub use master::MasterApi;
+pub use scenario::{Scenario, ScenarioRunner};
+pub use worker_api::WorkerApi;
+
+/// Wire types, re-exported so callers don't need a direct worker dependency
+pub mod types {
+    pub use worker::client_api::*;
+    pub use worker::game::{AttackTarget, GameEvent, GameSummary, MetricsSample, NodeCoord, PathPlan, RefereeOverride};
+    pub use worker::raft::api::{
+        CommandResponse, EventsQuery, EventsResponse, GameStateResponse, GridCell, GridNeighborsResponse,
+        GridPathResponse, JoinGameResponse, MetricsSeriesResponse, RefereeOverrideRequest, RegistryResponse,
+        StatusResponse, SubmitEventResponse,
+    };
+    pub use worker::raft::audit::{AuditQuery, AuditRecord, AuditResponse, CommandOrigin};
+    pub use worker::registry::{
+        GameInfo, GetGamesResponse, PlayerAccount, PlayerProfile, RatedGame, RegisterPlayerResponse, RematchResponse,
+    };
+}
@@ -0,0 +1,62 @@
+// Master API (game discovery and leader summaries)
+
+use crate::http::{HttpClient, RetryPolicy};
+use anyhow::Result;
+use worker::game::settings::GameSettings;
+use worker::game::{Awards, GameSummary};
+use worker::registry::{GetGamesResponse, PlayerProfile, RegisterPlayerResponse, RematchResponse, SpawnResponse};
+
+/// Typed wrapper around the master's read-only HTTP API
+#[derive(Debug, Clone)]
+pub struct MasterApi {
+    http: HttpClient,
+}
+
+impl MasterApi {
+    /// `base_url` is "host:port" or a full URL, e.g. http://master.camhack.internal:8000
+    pub fn new(base_url: &str) -> Self {
+        Self::with_retry(base_url, RetryPolicy::default())
+    }
+
+    pub fn with_retry(base_url: &str, retry: RetryPolicy) -> Self {
+        Self { http: HttpClient::new(base_url, retry) }
+    }
+
+    /// GET /games - newest first, optionally only joinable ones
+    pub async fn games(&self, joinable_only: bool) -> Result<GetGamesResponse> {
+        let path = if joinable_only { "/games?joinable=true" } else { "/games" };
+        self.http.get(path).await
+    }
+
+    /// POST /games - create a game with `workers` founding workers; invalid settings are
+    /// refused (400) with every bad field listed
+    pub async fn create_game(&self, game_id: &str, workers: u32, settings: &GameSettings) -> Result<SpawnResponse> {
+        let body = serde_json::json!({ "game_id": game_id, "workers": workers, "settings": settings });
+        self.http.post("/games", &body).await
+    }
+
+    /// GET /games/{id}/summary - latest summary reported by the game's leader
+    pub async fn game_summary(&self, game_id: &str) -> Result<GameSummary> {
+        self.http.get(&format!("/games/{}/summary", game_id)).await
+    }
+
+    /// GET /games/:game_id/awards - a finished game's awards
+    pub async fn game_awards(&self, game_id: &str) -> Result<Awards> {
+        self.http.get(&format!("/games/{}/awards", game_id)).await
+    }
+
+    /// POST /players - claim `name`; keep the returned token to join rated games
+    pub async fn register_player(&self, name: &str) -> Result<RegisterPlayerResponse> {
+        self.http.post("/players", &serde_json::json!({ "name": name })).await
+    }
+
+    /// GET /players/{id} - rating and finished games
+    pub async fn player(&self, player_id: u64) -> Result<PlayerProfile> {
+        self.http.get(&format!("/players/{}", player_id)).await
+    }
+
+    /// POST /games/{id}/rematch - new game with a finished game's rules, map and capitals
+    pub async fn rematch(&self, game_id: &str) -> Result<RematchResponse> {
+        self.http.post(&format!("/games/{}/rematch", game_id), &()).await
+    }
+}
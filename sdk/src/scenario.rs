@@ -0,0 +1,370 @@
+// Scenario scripts - scripted games for demos and tutorials
+//
+// A scenario is a YAML list of steps, each at an offset from the start of the run:
+//
+//   name: first-capture
+//   steps:
+//     - at: T+0s
+//       join: { name: bot-a }
+//     - at: T+0s
+//       join: { name: bot-b }
+//     - at: T+30s
+//       say: bot-a opens fire on the hex next to it
+//       attack: { from: bot-a, target: "1,0" }
+//     - at: T+60s
+//       capture: { node: "1,0", owner: bot-a }
+//
+// `ScenarioRunner` plays the steps against a live game through the same worker
+// endpoints a player or referee uses (join, attack, stop, referee overrides, raw
+// events), so a run is reproducible and nothing in the servers knows it's scripted.
+// Nodes are named by a "q,r" coordinate or by a player's name (their capital);
+// players joined earlier in the run are remembered, others are looked up in the game.
+
+use crate::worker_api::WorkerApi;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::Duration;
+use worker::game::{GameEvent, NodeCoord, RefereeOverride};
+
+/// Owner name that makes `capture` neutralize the node instead
+pub const NEUTRAL: &str = "neutral";
+
+/// Capital address bots join with unless a step gives one
+const DEFAULT_NODE_IP: &str = "127.0.0.1";
+
+/// A scripted sequence of steps
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// In the order they run (by `at`; steps at the same offset keep their order)
+    pub steps: Vec<Step>,
+}
+
+impl Scenario {
+    /// Parse a scenario file
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let mut scenario: Scenario = serde_yaml::from_str(yaml).context("Invalid scenario")?;
+        scenario.steps.sort_by_key(|step| step.at);
+        Ok(scenario)
+    }
+
+    /// When the last step runs, relative to the start
+    pub fn duration(&self) -> Duration {
+        self.steps.last().map(|step| step.at).unwrap_or_default()
+    }
+}
+
+/// One step: what to do, and when
+#[derive(Debug, Clone, Deserialize)]
+pub struct Step {
+    /// Offset from the start of the run: `T+30s`, `90s`, `1m30s`, `500ms` or plain seconds
+    #[serde(deserialize_with = "deserialize_offset")]
+    pub at: Duration,
+    /// Narration printed when the step runs (tutorials)
+    #[serde(default)]
+    pub say: Option<String>,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// What a step does
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// A bot player joins (POST /game/join); its capital is remembered by name
+    Join {
+        name: String,
+        #[serde(default = "default_node_ip")]
+        node_ip: String,
+    },
+    /// A node starts attacking a neighbour (POST /game/attack)
+    Attack { from: NodeRef, target: NodeRef },
+    /// A node stops attacking (POST /game/stop-attack)
+    Stop { node: NodeRef },
+    /// Hand a node to a player, or make it neutral (referee override, needs the referee token)
+    Capture { node: NodeRef, owner: String },
+    /// Submit any event as-is (POST /events), e.g. a ScheduleEvent
+    Event(GameEvent),
+}
+
+impl Action {
+    /// Step name, for logs and errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::Join { .. } => "join",
+            Action::Attack { .. } => "attack",
+            Action::Stop { .. } => "stop",
+            Action::Capture { .. } => "capture",
+            Action::Event(_) => "event",
+        }
+    }
+}
+
+fn default_node_ip() -> String {
+    DEFAULT_NODE_IP.to_string()
+}
+
+/// A node in a step: a "q,r" coordinate, or a player's name for their capital
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeRef {
+    Coord(NodeCoord),
+    Capital(String),
+}
+
+impl<'de> Deserialize<'de> for NodeRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(match value.parse() {
+            Ok(coord) => NodeRef::Coord(coord),
+            Err(_) => NodeRef::Capital(value),
+        })
+    }
+}
+
+/// `T+1m30s` -> 90s; the `T+` is optional, and a bare number is seconds
+pub fn parse_offset(input: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid offset '{}', expected e.g. T+30s", input);
+    let text = input.trim();
+    let text = text.strip_prefix("T+").or_else(|| text.strip_prefix("t+")).unwrap_or(text).trim();
+    if let Ok(secs) = text.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        total += match &rest[..unit_len] {
+            "ms" => Duration::from_millis(value),
+            "s" => Duration::from_secs(value),
+            "m" => Duration::from_secs(value * 60),
+            "h" => Duration::from_secs(value * 3600),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit_len..];
+    }
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+fn deserialize_offset<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Offset {
+        Secs(u64),
+        Text(String),
+    }
+    match Offset::deserialize(deserializer)? {
+        Offset::Secs(secs) => Ok(Duration::from_secs(secs)),
+        Offset::Text(text) => parse_offset(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Plays scenarios against one game through a worker's API (the leader's, for writes)
+pub struct ScenarioRunner {
+    worker: WorkerApi,
+    referee_token: Option<String>,
+    /// Players joined by this runner: name -> (player id, capital)
+    players: HashMap<String, (u64, NodeCoord)>,
+}
+
+impl ScenarioRunner {
+    pub fn new(worker: WorkerApi) -> Self {
+        Self { worker, referee_token: None, players: HashMap::new() }
+    }
+
+    /// The game's referee token, for `capture` steps
+    pub fn with_referee_token(mut self, token: &str) -> Self {
+        self.referee_token = Some(token.to_string());
+        self
+    }
+
+    /// Run every step at its offset from now; the first failing step ends the run
+    pub async fn run(&mut self, scenario: &Scenario) -> Result<()> {
+        let start = tokio::time::Instant::now();
+        println!("[Scenario] {}: {} steps over {}s", scenario.name, scenario.steps.len(), scenario.duration().as_secs());
+        for (number, step) in scenario.steps.iter().enumerate().map(|(i, step)| (i + 1, step)) {
+            tokio::time::sleep_until(start + step.at).await;
+            if let Some(say) = &step.say {
+                println!("[Scenario] {}", say);
+            }
+            let done = self
+                .run_step(&step.action)
+                .await
+                .with_context(|| format!("Step {} ({}) at T+{}s failed", number, step.action.kind(), step.at.as_secs()))?;
+            println!("[Scenario] T+{}s {}: {}", step.at.as_secs(), step.action.kind(), done);
+        }
+        Ok(())
+    }
+
+    /// Run one step, returning what it did
+    pub async fn run_step(&mut self, action: &Action) -> Result<String> {
+        match action {
+            Action::Join { name, node_ip } => {
+                let response = self.worker.join(name, node_ip).await?;
+                let (Some(player_id), Some(capital)) = (response.player_id, response.capital_coord) else {
+                    bail!("{}", response.message);
+                };
+                self.players.insert(name.clone(), (player_id, capital));
+                Ok(format!("{} joined as player {} at {}", name, player_id, capital))
+            }
+            Action::Attack { from, target } => {
+                let (from, target) = (self.resolve(from).await?, self.resolve(target).await?);
+                let response = self.worker.attack(from, target).await?;
+                if !response.success {
+                    bail!("{}", response.message);
+                }
+                Ok(format!("{} attacks {}", from, target))
+            }
+            Action::Stop { node } => {
+                let node = self.resolve(node).await?;
+                let response = self.worker.stop_attack(node).await?;
+                if !response.success {
+                    bail!("{}", response.message);
+                }
+                Ok(format!("{} stops attacking", node))
+            }
+            Action::Capture { node, owner } => {
+                let token = self.referee_token.clone().ok_or_else(|| anyhow!("Capture steps need the referee token"))?;
+                let node_coord = self.resolve(node).await?;
+                let action = if owner == NEUTRAL {
+                    RefereeOverride::NeutralizeNode { node_coord }
+                } else {
+                    RefereeOverride::RevertCapture { node_coord, owner_id: self.player(owner).await?.0 }
+                };
+                let response = self.worker.referee_override(&token, action, "scenario").await?;
+                if !response.success {
+                    bail!("{}", response.message);
+                }
+                Ok(format!("{} goes to {}", node_coord, owner))
+            }
+            Action::Event(event) => {
+                let response = self.worker.submit_event(event.clone()).await?;
+                if !response.success {
+                    bail!("{}", response.message);
+                }
+                Ok(format!("{} committed", event.kind()))
+            }
+        }
+    }
+
+    /// A node reference's coordinate
+    async fn resolve(&mut self, node: &NodeRef) -> Result<NodeCoord> {
+        match node {
+            NodeRef::Coord(coord) => Ok(*coord),
+            NodeRef::Capital(name) => Ok(self.player(name).await?.1),
+        }
+    }
+
+    /// A player's id and capital: joined by this run, or already in the game
+    async fn player(&mut self, name: &str) -> Result<(u64, NodeCoord)> {
+        if let Some(player) = self.players.get(name) {
+            return Ok(*player);
+        }
+        let state = self.worker.game_state().await?;
+        let player = state
+            .players
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| (p.player_id, p.capital_coord))
+            .ok_or_else(|| anyhow!("No player named '{}' in the game", name))?;
+        self.players.insert(name.to_string(), player);
+        Ok(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    const SCENARIO: &str = r#"
+name: first-capture
+steps:
+  - at: T+1m
+    capture: { node: "3,0", owner: neutral }
+  - at: T+0s
+    join: { name: bot-a }
+  - at: 500ms
+    say: bot-a opens fire
+    attack: { from: bot-a, target: "1,0" }
+  - at: 0
+    join: { name: bot-b, node_ip: 10.0.0.2 }
+"#;
+
+    #[test]
+    fn test_parse_scenario() {
+        assert_eq!(parse_offset("T+1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_offset("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_offset("45"), Ok(Duration::from_secs(45)));
+        assert!(parse_offset("T+").is_err());
+        assert!(parse_offset("30 parsecs").is_err());
+
+        // Sorted by offset, steps at the same offset in file order
+        let scenario = Scenario::from_yaml(SCENARIO).unwrap();
+        let kinds: Vec<_> = scenario.steps.iter().map(|s| s.action.kind()).collect();
+        assert_eq!(kinds, ["join", "join", "attack", "capture"]);
+        assert_eq!(scenario.duration(), Duration::from_secs(60));
+        assert!(matches!(&scenario.steps[1].action, Action::Join { node_ip, .. } if node_ip == "10.0.0.2"));
+        match &scenario.steps[2].action {
+            Action::Attack { from, target } => {
+                assert_eq!(from, &NodeRef::Capital("bot-a".to_string()));
+                assert_eq!(target, &NodeRef::Coord(NodeCoord::new(1, 0)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(scenario.steps[2].say.as_deref(), Some("bot-a opens fire"));
+        assert!(Scenario::from_yaml(include_str!("../scenarios/tutorial.yaml")).is_ok());
+        assert!(Scenario::from_yaml("name: x\nsteps:\n  - at: 1s\n    teleport: {}\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_resolves_joined_players() {
+        let attacks = Arc::new(Mutex::new(Vec::new()));
+        let recorded = attacks.clone();
+        let app = Router::new()
+            .route(
+                "/game/join",
+                post(|| async {
+                    Json(serde_json::json!({
+                        "success": true, "message": "joined", "player_id": 7, "capital_coord": { "q": 0, "r": 0 }
+                    }))
+                }),
+            )
+            .route(
+                "/game/attack",
+                post(move |Json(body): Json<serde_json::Value>| async move {
+                    recorded.lock().unwrap().push(body);
+                    Json(serde_json::json!({ "success": true, "message": "ok" }))
+                }),
+            )
+            .route("/game/state", get(|| async { Json(serde_json::json!({ "players": [], "nodes": [], "total_events": 0 })) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut runner = ScenarioRunner::new(WorkerApi::new(&addr.to_string()));
+        let scenario = Scenario::from_yaml(&SCENARIO.replace("T+1m", "1s")).unwrap();
+        let error = runner.run(&scenario).await.unwrap_err();
+
+        // The attack went from the joined bot's capital; the capture had no token
+        let first_attack = attacks.lock().unwrap()[0].clone();
+        assert_eq!(first_attack["node_coord"], serde_json::json!({ "q": 0, "r": 0 }));
+        assert!(format!("{:#}", error).contains("Step 4 (capture)"));
+        assert!(format!("{:#}", error).contains("referee token"));
+
+        // Players neither joined nor in the game are an error
+        let missing = runner.run_step(&Action::Stop { node: NodeRef::Capital("nobody".to_string()) }).await;
+        assert!(missing.unwrap_err().to_string().contains("nobody"));
+    }
+}
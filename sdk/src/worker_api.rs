@@ -0,0 +1,167 @@
+// Worker API (raft::api routers: public on 8080, internal on 8082 of each worker)
+
+use crate::http::{audit_path, events_path, internal_base_url, HttpClient, RetryPolicy};
+use anyhow::Result;
+use worker::game::timetravel::{StateDiff, StateView};
+use worker::game::events::AnnouncementSeverity;
+use worker::game::{Awards, GameEvent, GameState, NodeCoord, RefereeOverride};
+use worker::raft::api::{
+    AnnounceRequest, AttackRequest, CommandResponse, EventsQuery, EventsResponse, GameStateResponse, HandicapRequest, HandicapResponse,
+    JoinGameRequest, InvariantsResponse, JoinGameResponse, MetricsSeriesResponse, RefereeOverrideRequest, RegistryResponse, StatusResponse,
+    StopAttackRequest, SubmitEventRequest, SubmitEventResponse,
+};
+use worker::raft::audit::{AuditQuery, AuditResponse};
+use worker::raft::spectate;
+
+/// Typed wrapper around a worker's HTTP API
+/// Player and referee routes go to the public listener; status, registry, events and
+/// debug routes go to the internal one, with the cluster's INTERNAL_API_TOKEN.
+#[derive(Debug, Clone)]
+pub struct WorkerApi {
+    http: HttpClient,
+    internal: HttpClient,
+}
+
+impl WorkerApi {
+    /// `base_url` is the public API, "host:port" or a full URL, e.g. http://10.0.1.5:8080
+    /// The internal API is taken to be on the same host (see `with_internal`).
+    pub fn new(base_url: &str) -> Self {
+        Self::with_retry(base_url, RetryPolicy::default())
+    }
+
+    pub fn with_retry(base_url: &str, retry: RetryPolicy) -> Self {
+        let token = worker::raft::internal::internal_token();
+        Self {
+            http: HttpClient::new(base_url, retry),
+            internal: HttpClient::new(&internal_base_url(base_url), retry).with_bearer(token.as_deref()),
+        }
+    }
+
+    /// Reach the internal API at `base_url` with `token` instead
+    pub fn with_internal(mut self, base_url: &str, token: Option<&str>) -> Self {
+        self.internal = HttpClient::new(base_url, self.internal.retry()).with_bearer(token);
+        self
+    }
+
+    /// GET /status - Raft role, term and event count
+    pub async fn status(&self) -> Result<StatusResponse> {
+        self.internal.get("/status").await
+    }
+
+    /// GET /registry - the node id → address map this worker uses
+    pub async fn registry(&self) -> Result<RegistryResponse> {
+        self.internal.get("/registry").await
+    }
+
+    /// GET /debug/invariants - the broken GameState invariants on this worker (none if healthy)
+    pub async fn invariants(&self) -> Result<InvariantsResponse> {
+        self.internal.get("/debug/invariants").await
+    }
+
+    /// GET /debug/state-at - the game state right after the event at position `index`
+    pub async fn state_at(&self, index: usize) -> Result<StateView> {
+        self.internal.get(&format!("/debug/state-at?index={}", index)).await
+    }
+
+    /// GET /debug/state-diff - what changed between the states after events `from` and `to`
+    pub async fn state_diff(&self, from: usize, to: usize) -> Result<StateDiff> {
+        self.internal.get(&format!("/debug/state-diff?from={}&to={}", from, to)).await
+    }
+
+    /// GET /debug/handicap - the demo impairment on this worker's responder, if any
+    pub async fn handicap(&self) -> Result<HandicapResponse> {
+        self.internal.get("/debug/handicap").await
+    }
+
+    /// POST /debug/handicap - degrade this worker's responder for a while (a zero duration lifts it)
+    pub async fn set_handicap(&self, request: &HandicapRequest) -> Result<HandicapResponse> {
+        self.internal.post("/debug/handicap", request).await
+    }
+
+    /// GET /events - one page of committed events (follow `next_after_index` for more)
+    pub async fn events(&self, query: &EventsQuery) -> Result<EventsResponse> {
+        self.internal.get(&events_path(query)).await
+    }
+
+    /// POST /events - submit an event (must reach the leader)
+    pub async fn submit_event(&self, event: GameEvent) -> Result<SubmitEventResponse> {
+        self.internal.post("/events", &SubmitEventRequest { event }).await
+    }
+
+    /// POST /admin/override - commit a referee correction (must reach the leader)
+    /// `token` is the game's referee token, handed out by the master when the game was created
+    pub async fn referee_override(
+        &self,
+        token: &str,
+        action: RefereeOverride,
+        reason: &str,
+    ) -> Result<SubmitEventResponse> {
+        let request = RefereeOverrideRequest { action, reason: reason.to_string() };
+        self.http.post_authorized("/admin/override", token, &request).await
+    }
+
+    /// POST /admin/announce - show every player a banner for `ttl_secs` (must reach the leader)
+    /// `token` is the game's referee token
+    pub async fn announce(
+        &self,
+        token: &str,
+        text: &str,
+        severity: AnnouncementSeverity,
+        ttl_secs: u64,
+    ) -> Result<SubmitEventResponse> {
+        let request = AnnounceRequest { text: text.to_string(), severity, ttl_secs };
+        self.http.post_authorized("/admin/announce", token, &request).await
+    }
+
+    /// GET /admin/audit - who sent each committed command (follow `next_after_index` for more)
+    /// `token` is the game's referee token
+    pub async fn audit(&self, token: &str, query: &AuditQuery) -> Result<AuditResponse> {
+        self.http.get_authorized(&audit_path(query), token).await
+    }
+
+    /// POST /game/join (as a player outside the Raft cluster, like the frontend)
+    pub async fn join(&self, player_name: &str, node_ip: &str) -> Result<JoinGameResponse> {
+        let request = JoinGameRequest {
+            player_name: player_name.to_string(),
+            node_ip: node_ip.to_string(),
+            raft_node_id: 0,
+        };
+        self.http.post("/game/join", &request).await
+    }
+
+    /// POST /game/attack
+    pub async fn attack(&self, node_coord: NodeCoord, target_coord: NodeCoord) -> Result<CommandResponse> {
+        self.http.post("/game/attack", &AttackRequest { node_coord, target_coord }).await
+    }
+
+    /// POST /game/stop-attack
+    pub async fn stop_attack(&self, node_coord: NodeCoord) -> Result<CommandResponse> {
+        self.http.post("/game/stop-attack", &StopAttackRequest { node_coord }).await
+    }
+
+    /// GET /game/state
+    pub async fn game_state(&self) -> Result<GameStateResponse> {
+        self.http.get("/game/state").await
+    }
+
+    /// GET /spectate/snapshot - the worker's shipped snapshot, rebuilt into the game state
+    /// Returns the last log index it includes with the state, so a watcher can render
+    /// at once and follow /game/state from there.
+    pub async fn spectate(&self) -> Result<(u64, GameState)> {
+        let (meta, data) = spectate::fetch(self.internal.base_url(), self.internal.bearer()).await?;
+        let index = meta.last_log_id.map_or(0, |log_id| log_id.index);
+        Ok((index, spectate::state_from_snapshot(&meta, data).await?))
+    }
+
+    /// GET /game/metrics - recent metrics samples for `coord` over `window_secs`
+    pub async fn metrics(&self, coord: NodeCoord, window_secs: u64) -> Result<MetricsSeriesResponse> {
+        self.http
+            .get(&format!("/game/metrics?coord={}&window={}", coord, window_secs))
+            .await
+    }
+
+    /// GET /awards - the game's awards (standings so far until it's over)
+    pub async fn awards(&self) -> Result<Awards> {
+        self.http.get("/awards").await
+    }
+}
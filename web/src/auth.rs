@@ -0,0 +1,51 @@
+// Bearer token checks for the cluster's shared secrets (INTERNAL_API_TOKEN, REFEREE_TOKEN)
+
+use http::{header, HeaderMap};
+
+/// Whether `headers` carry `expected` (always true when no token is configured)
+pub fn authorized(expected: Option<&str>, headers: &HeaderMap) -> bool {
+    match expected {
+        Some(expected) => bearer_matches(expected, headers),
+        None => true,
+    }
+}
+
+/// Whether `headers` carry `Authorization: Bearer <expected>`
+/// The token is compared in constant time, so response timing doesn't give it away
+/// a byte at a time.
+pub fn bearer_matches(expected: &str, headers: &HeaderMap) -> bool {
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    presented.is_some_and(|presented| constant_time_eq(presented.as_bytes(), expected.as_bytes()))
+}
+
+/// Byte equality that looks at every byte whatever the first difference (only the
+/// length can short-circuit)
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_check() {
+        let mut headers = HeaderMap::new();
+        assert!(authorized(None, &headers));
+        assert!(!authorized(Some("s3cret"), &headers));
+
+        headers.insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert!(!authorized(Some("s3cret"), &headers));
+        headers.insert(header::AUTHORIZATION, "s3cret".parse().unwrap());
+        assert!(!authorized(Some("s3cret"), &headers));
+        headers.insert(header::AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        assert!(authorized(Some("s3cret"), &headers));
+        headers.insert(header::AUTHORIZATION, "Bearer s3cre".parse().unwrap());
+        assert!(!authorized(Some("s3cret"), &headers));
+        headers.insert(header::AUTHORIZATION, "Bearer s3cretx".parse().unwrap());
+        assert!(!authorized(Some("s3cret"), &headers));
+    }
+}
@@ -0,0 +1,133 @@
+// Cross-origin policy for the browser-facing APIs (worker, client and master)
+//
+// ALLOWED_ORIGINS lists the origins the frontend is served from, comma-separated
+// ("https://play.example.com,https://admin.example.com"). Those origins get CORS with
+// credentials (cookies, Authorization headers). "*" allows any origin but without
+// credentials - the old permissive policy. Unset, only local development origins are
+// allowed: http(s)://localhost, 127.0.0.1 or [::1], on any port.
+//
+// The worker adds the WebSocket origin check on top (worker/src/cors.rs); the
+// master serves no WebSockets and uses the layer alone.
+
+use http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+
+/// The servers' request id header (request_id::HEADER), readable by the frontend
+const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Which origins may call the API from a browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowedOrigins {
+    /// Local development origins only (ALLOWED_ORIGINS unset)
+    Local,
+    /// Any origin, without credentials ("*")
+    Any,
+    /// Exactly these origins, with credentials
+    List(Vec<String>),
+}
+
+/// CORS and WebSocket origin policy, shared by every router of a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    pub origins: AllowedOrigins,
+}
+
+impl CorsPolicy {
+    /// Load from ALLOWED_ORIGINS (default: local development origins)
+    pub fn from_env() -> Self {
+        let policy = Self::parse(std::env::var("ALLOWED_ORIGINS").ok().as_deref().unwrap_or(""));
+        println!("[CORS] Allowed origins: {}", policy.describe());
+        policy
+    }
+
+    /// Policy for an ALLOWED_ORIGINS value ("" = local origins only)
+    pub fn parse(value: &str) -> Self {
+        let origins: Vec<String> = value
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        let origins = if origins.is_empty() {
+            AllowedOrigins::Local
+        } else if origins.iter().any(|origin| origin == "*") {
+            AllowedOrigins::Any
+        } else {
+            AllowedOrigins::List(origins)
+        };
+        Self { origins }
+    }
+
+    fn describe(&self) -> String {
+        match &self.origins {
+            AllowedOrigins::Local => "localhost only (set ALLOWED_ORIGINS for production)".to_string(),
+            AllowedOrigins::Any => "any (no credentials)".to_string(),
+            AllowedOrigins::List(origins) => origins.join(", "),
+        }
+    }
+
+    /// Whether a browser at `origin` may use the API
+    pub fn allows(&self, origin: &str) -> bool {
+        match &self.origins {
+            AllowedOrigins::Local => is_local_origin(origin),
+            AllowedOrigins::Any => true,
+            AllowedOrigins::List(origins) => origins.iter().any(|allowed| allowed.eq_ignore_ascii_case(origin)),
+        }
+    }
+
+    /// The CORS layer for a router
+    pub fn layer(&self) -> CorsLayer {
+        let methods = [Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS];
+        match &self.origins {
+            AllowedOrigins::Any => CorsLayer::new().allow_origin(Any).allow_methods(methods).allow_headers(Any).expose_headers([REQUEST_ID]),
+            AllowedOrigins::Local | AllowedOrigins::List(_) => {
+                let policy = self.clone();
+                CorsLayer::new()
+                    .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                        origin.to_str().is_ok_and(|origin| policy.allows(origin))
+                    }))
+                    .allow_methods(methods)
+                    // Credentials rule out the `*` wildcard, so echo what the browser asks for
+                    .allow_headers(AllowHeaders::mirror_request())
+                    .allow_credentials(true)
+                    .expose_headers([REQUEST_ID])
+            }
+        }
+    }
+}
+
+/// http(s)://localhost, 127.0.0.1 or [::1], any port
+fn is_local_origin(origin: &str) -> bool {
+    let Some(authority) = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://")) else {
+        return false;
+    };
+    let host = match authority.strip_prefix("[::1]") {
+        Some(rest) => return rest.is_empty() || rest.starts_with(':'),
+        None => authority.split(':').next().unwrap_or(""),
+    };
+    host.eq_ignore_ascii_case("localhost") || host == "127.0.0.1"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_origins() {
+        let local = CorsPolicy::parse("");
+        assert_eq!(local.origins, AllowedOrigins::Local);
+        assert!(local.allows("http://localhost:5173"));
+        assert!(local.allows("https://127.0.0.1"));
+        assert!(local.allows("http://[::1]:8080"));
+        assert!(!local.allows("http://localhost.evil.com"));
+        assert!(!local.allows("https://play.example.com"));
+
+        let listed = CorsPolicy::parse(" https://play.example.com/, https://admin.example.com ");
+        assert!(listed.allows("https://play.example.com"));
+        assert!(listed.allows("https://admin.example.com"));
+        assert!(!listed.allows("http://localhost:5173"));
+
+        let any = CorsPolicy::parse("https://play.example.com,*");
+        assert_eq!(any.origins, AllowedOrigins::Any);
+        assert!(any.allows("https://anything.example"));
+    }
+}
@@ -0,0 +1,7 @@
+// HTTP helpers shared by the master and the workers (and through them the clients)
+//
+// The master doesn't link against the worker crate, so anything both servers must
+// agree on at the HTTP level lives here instead of being copied into each.
+
+pub mod auth;
+pub mod cors;
@@ -0,0 +1,42 @@
+//! State-machine apply throughput over a long game log.
+//!
+//! Applies 20 joins and 10,000 events of the running game between them
+//! (worker::bench::event_stream) to a fresh MemStorage, max_payload_entries
+//! (300) at a time as replication hands them over.
+//!
+//! Run with: cargo bench --bench apply
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use openraft::RaftStorage;
+use worker::bench::{entries, event_stream};
+use worker::MemStorage;
+
+/// Matches max_payload_entries in RaftNode::new
+const APPLY_BATCH: usize = 300;
+
+fn bench_apply(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let log = entries(&event_stream(20, 10_000));
+
+    let mut group = c.benchmark_group("apply");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(log.len() as u64));
+    group.bench_function("event_stream_10k", |b| {
+        b.iter_batched(
+            || (MemStorage::new(), log.clone()),
+            |(mut storage, log)| {
+                runtime.block_on(async {
+                    for chunk in log.chunks(APPLY_BATCH) {
+                        storage.apply_to_state_machine(chunk).await.unwrap();
+                    }
+                })
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_apply);
+criterion_main!(benches);
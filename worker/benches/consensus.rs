@@ -0,0 +1,35 @@
+//! client_write throughput and latency across a local 3-node cluster.
+//!
+//! Starts three RaftNodes on loopback gRPC (worker::bench::LocalCluster) and
+//! commits batches of 1, 10 and 100 concurrent game events through the leader;
+//! each iteration is one batch committed and applied on the leader.
+//!
+//! Run with: cargo bench --bench consensus
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use worker::bench::LocalCluster;
+
+fn bench_client_write(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let cluster = runtime.block_on(LocalCluster::start(3)).unwrap();
+
+    let mut group = c.benchmark_group("client_write_3_nodes");
+    group.sample_size(20);
+
+    let mut seq = 0;
+    for batch in [1, 10, 100] {
+        group.throughput(Throughput::Elements(batch as u64));
+        group.bench_with_input(BenchmarkId::new("concurrent", batch), &batch, |b, batch| {
+            b.iter(|| {
+                runtime.block_on(cluster.write_batch(*batch, seq)).unwrap();
+                seq += *batch as u64;
+            })
+        });
+    }
+
+    group.finish();
+    runtime.block_on(cluster.shutdown());
+}
+
+criterion_group!(benches, bench_client_write);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+//! Replication throughput benchmarks for AppendEntries compression.
+//!
+//! Builds a full `max_payload_entries` batch (300 entries) of realistic game
+//! events and measures encode + compress + decompress per codec.
+//!
+//! Run with: cargo bench --bench replication
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use openraft::{Entry, EntryPayload, LogId};
+use prost::Message;
+use worker::game::{AttackTarget, CommandEvent, GameEvent, NodeCoord, TelemetryEvent};
+use worker::raft::compression::{self, CompressionConfig};
+use worker::raft::conversions::proto::{
+    AppendEntriesRequest as ProtoAppendEntriesRequest, Compression, LogEntry as ProtoLogEntry,
+};
+use worker::raft::storage::GameRaftTypeConfig;
+use worker::GameEventRequest;
+
+/// Matches max_payload_entries in RaftNode::new
+const BATCH_SIZE: u64 = 300;
+
+/// Mix of metrics reports and attack orders - the bulk of a real game's log
+fn sample_batch() -> ProtoAppendEntriesRequest {
+    let entries: Vec<ProtoLogEntry> = (1..=BATCH_SIZE)
+        .map(|i| {
+            let coord = NodeCoord::new((i % 17) as i32, (i % 11) as i32 - 5);
+            let event = if i % 3 == 0 {
+                GameEvent::Command(CommandEvent::SetNodeTarget {
+                    node_coord: coord,
+                    target: Some(AttackTarget::Coordinate(NodeCoord::new(coord.q + 1, coord.r))),
+                    timestamp: 1_700_000_000 + i,
+                })
+            } else {
+                GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport {
+                    node_coord: coord,
+                    bandwidth_in: 12_500_000 + i * 37,
+                    packet_loss: (i % 100) as f32 / 100.0,
+                    timestamp: 1_700_000_000 + i,
+                    queued_attackers: Vec::new(),
+                })
+            };
+
+            Entry::<GameRaftTypeConfig> {
+                log_id: LogId::new(openraft::LeaderId::new(4, 1), i),
+                payload: EntryPayload::Normal(GameEventRequest { event, committed_at: 0, origin: None }),
+            }
+            .into()
+        })
+        .collect();
+
+    ProtoAppendEntriesRequest {
+        term: 4,
+        leader_id: 1,
+        entries,
+        ..Default::default()
+    }
+}
+
+fn bench_append_entries(c: &mut Criterion) {
+    let batch = sample_batch();
+    let raw_size = batch.encoded_len();
+
+    let mut group = c.benchmark_group("append_entries_300");
+    group.throughput(Throughput::Elements(BATCH_SIZE));
+
+    for codec in [Compression::None, Compression::Gzip, Compression::Zstd] {
+        let config = CompressionConfig { codec, min_bytes: 0 };
+
+        // Report wire size once so ratios show up next to timings
+        let mut sized = batch.clone();
+        compression::compress_request(&mut sized, &config, &compression::supported_codecs()).unwrap();
+        println!(
+            "{:?}: {} bytes on the wire ({} raw, {:.1}%)",
+            codec,
+            sized.encoded_len(),
+            raw_size,
+            sized.encoded_len() as f64 / raw_size as f64 * 100.0
+        );
+
+        group.bench_with_input(BenchmarkId::new("roundtrip", format!("{:?}", codec)), &config, |b, config| {
+            b.iter(|| {
+                let mut req = batch.clone();
+                compression::compress_request(&mut req, config, &compression::supported_codecs()).unwrap();
+                let wire = req.encode_to_vec();
+
+                let mut received = ProtoAppendEntriesRequest::decode(wire.as_slice()).unwrap();
+                black_box(compression::take_entries(&mut received).unwrap())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_append_entries);
+criterion_main!(benches);
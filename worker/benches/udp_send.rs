@@ -0,0 +1,72 @@
+//! Flooder send throughput: one syscall per packet vs sendmmsg batches.
+//!
+//! Sends SEND_BATCH 1KB attack packets to a loopback socket drained by a
+//! background thread, so the numbers are the sender's packets per second.
+//! `flooder_loop` runs the whole unpaced flooder (udp_attacker) the same way,
+//! encoding included.
+//!
+//! Run with: cargo bench --bench udp_send
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+use worker::bench::flood;
+use worker::game::udp::UdpAttackPacket;
+use worker::game::udp_batch::{bind_socket, send_batch, BatchMode, SEND_BATCH};
+
+/// A loopback receiver that discards everything, so the sender never backs up on it
+fn sink() -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 2048];
+        while socket.recv_from(&mut buf).is_ok() {}
+    });
+    addr
+}
+
+fn bench_udp_send(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let target = sink();
+    let socket = runtime.block_on(async { bind_socket(target).unwrap() });
+
+    let batch: Vec<Vec<u8>> = (0..SEND_BATCH as u64)
+        .map(|seq| UdpAttackPacket { seq, timestamp: 0, payload: vec![0u8; 1024] }.encode())
+        .collect();
+
+    let mut group = c.benchmark_group("flooder_send");
+    group.throughput(Throughput::Elements(SEND_BATCH as u64));
+
+    let mut modes = vec![BatchMode::PerPacket];
+    if cfg!(target_os = "linux") {
+        modes.push(BatchMode::Batched);
+    }
+    for mode in modes {
+        group.bench_with_input(BenchmarkId::new("batch_32", format!("{:?}", mode)), &mode, |b, mode| {
+            b.iter(|| runtime.block_on(send_batch(&socket, target, &batch, *mode)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_flooder_loop(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let target = sink();
+
+    let mut group = c.benchmark_group("flooder_loop");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("packets", |b| {
+        b.iter_custom(|packets| {
+            let (sent, elapsed) = runtime.block_on(flood(target, packets, Duration::from_secs(60))).unwrap();
+            // The flooder stops on a batch boundary, so scale to the packets asked for
+            elapsed.mul_f64(packets as f64 / sent.max(1) as f64)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_udp_send, bench_flooder_loop);
+criterion_main!(benches);
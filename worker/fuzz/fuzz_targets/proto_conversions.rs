@@ -0,0 +1,31 @@
+// Any gRPC body a peer can send must convert or fail with an error, never panic
+// The first byte picks the message, the rest is its protobuf encoding.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+use worker::raft::conversions::proto;
+use worker::raft::storage::GameRaftTypeConfig;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&kind, body)) = data.split_first() else {
+        return;
+    };
+    match kind % 3 {
+        0 => {
+            if let Ok(req) = proto::AppendEntriesRequest::decode(body) {
+                let _ = openraft::raft::AppendEntriesRequest::<GameRaftTypeConfig>::try_from(req);
+            }
+        }
+        1 => {
+            if let Ok(req) = proto::InstallSnapshotRequest::decode(body) {
+                let _ = openraft::raft::InstallSnapshotRequest::<GameRaftTypeConfig>::try_from(req);
+            }
+        }
+        _ => {
+            if let Ok(entry) = proto::LogEntry::decode(body) {
+                let _ = openraft::Entry::<GameRaftTypeConfig>::try_from(entry);
+            }
+        }
+    }
+});
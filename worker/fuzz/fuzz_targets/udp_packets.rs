@@ -0,0 +1,15 @@
+// Any datagram the responder or ack receiver is handed must decode or be dropped,
+// and anything that decodes must encode back to the same packet
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use worker::game::udp::{UdpAckPacket, UdpAttackPacket};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(packet) = UdpAttackPacket::decode(data) {
+        assert_eq!(UdpAttackPacket::decode(&packet.encode()), Some(packet));
+    }
+    if let Some(ack) = UdpAckPacket::decode(data) {
+        assert_eq!(UdpAckPacket::decode(&ack.encode()), Some(ack));
+    }
+});
@@ -55,7 +55,13 @@ sed "s/YOUR_ACCOUNT_ID/$AWS_ACCOUNT_ID/g" task-definition-capital.json | \
   sed "s/us-east-1/$AWS_REGION/g" | \
   sed "s|REPLACE_WITH_MASTER_IP|$MASTER_IP|g" > /tmp/task-definition-capital-updated.json
 
-# Register both task definitions
+# Process frontier worker task definition (container keeps the udp-node name)
+echo "  - Frontier worker (udp-node-small)"
+sed "s/YOUR_ACCOUNT_ID/$AWS_ACCOUNT_ID/g" task-definition-small.json | \
+  sed "s/us-east-1/$AWS_REGION/g" | \
+  sed "s|REPLACE_WITH_MASTER_IP|$MASTER_IP|g" > /tmp/task-definition-small-updated.json
+
+# Register the task definitions
 echo "Registering regular worker task definition..."
 TASK_DEF_ARN=$(aws ecs register-task-definition \
   --cli-input-json file:///tmp/task-definition-updated.json \
@@ -72,6 +78,14 @@ CAPITAL_TASK_DEF_ARN=$(aws ecs register-task-definition \
   --output text)
 echo "✓ Capital worker task definition registered: $CAPITAL_TASK_DEF_ARN"
 
+echo "Registering frontier worker task definition..."
+SMALL_TASK_DEF_ARN=$(aws ecs register-task-definition \
+  --cli-input-json file:///tmp/task-definition-small-updated.json \
+  --region $AWS_REGION \
+  --query 'taskDefinition.taskDefinitionArn' \
+  --output text)
+echo "✓ Frontier worker task definition registered: $SMALL_TASK_DEF_ARN"
+
 # Get default VPC and subnet if not provided
 if [ -z "$SUBNET_ID" ]; then
   echo "Getting default subnet..."
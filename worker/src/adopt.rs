@@ -0,0 +1,112 @@
+// Pre-join adoption - a worker process waiting to be told which node it is
+//
+// A warm pool worker started without GAME_ID has no game, no Raft group and no
+// coordinate. Until it gets them it serves only POST /adopt on the internal API
+// port (token-checked like the regular internal listener); the first request
+// naming a game is accepted (202) and the server shuts down so the regular API can
+// take the port. Startup then carries on as if GAME_ID and NODE_COORD_Q/R had been
+// set, committing NodeInitializationComplete once joined.
+
+use crate::game::NodeCoord;
+use crate::raft::api::{AdoptRequest, CommandResponse};
+use crate::raft::internal::{self, InternalApiConfig};
+use anyhow::Result;
+use axum::{extract::State, http::StatusCode, middleware, routing::post, Json, Router};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// The game and hex a worker was adopted into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub game_id: String,
+    pub coord: NodeCoord,
+    /// Id of the master request that adopted us, for the rest of startup (see request_id)
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone)]
+struct AdoptState {
+    /// Taken by the first valid request; later ones are refused
+    assign: Arc<Mutex<Option<mpsc::Sender<Assignment>>>>,
+}
+
+/// Serve POST /adopt on the internal listener until a request assigns this worker a game and coordinate
+pub async fn wait_for_assignment(internal: &InternalApiConfig) -> Result<Assignment> {
+    let (tx, mut rx) = mpsc::channel(1);
+    let state = AdoptState { assign: Arc::new(Mutex::new(Some(tx))) };
+    let app = Router::new()
+        .route("/adopt", post(handle_adopt))
+        .layer(middleware::from_fn_with_state(internal.token.clone(), internal::require_token))
+        .layer(middleware::from_fn(crate::request_id::propagate))
+        .with_state(state);
+
+    let addr = internal.addr();
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    println!("[Adopt] Waiting for an assignment on {}", addr);
+
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                let _ = done_rx.await;
+            })
+            .await
+    });
+
+    let assignment = rx
+        .recv()
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Adopt server stopped before an assignment"))?;
+    let _ = done_tx.send(());
+    server.await??;
+
+    println!("[Adopt] Assigned {:?} in game {}", assignment.coord, assignment.game_id);
+    Ok(assignment)
+}
+
+async fn handle_adopt(
+    State(state): State<AdoptState>,
+    Json(req): Json<AdoptRequest>,
+) -> (StatusCode, Json<CommandResponse>) {
+    let Some(game_id) = req.game_id.filter(|g| !g.is_empty()) else {
+        let message = "game_id is required until this worker has joined a game".to_string();
+        return (StatusCode::BAD_REQUEST, Json(CommandResponse { success: false, message, error: None }));
+    };
+
+    let Some(assign) = state.assign.lock().await.take() else {
+        let message = "Already adopted, joining its game".to_string();
+        return (StatusCode::CONFLICT, Json(CommandResponse { success: false, message, error: None }));
+    };
+
+    let coord = req.coord;
+    let request_id = crate::request_id::current();
+    let _ = assign.send(Assignment { game_id: game_id.clone(), coord, request_id }).await;
+    let message = format!("Joining game {} as {:?}", game_id, coord);
+    (StatusCode::ACCEPTED, Json(CommandResponse { success: true, message, error: None }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_first_request_with_game_wins() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let state = AdoptState { assign: Arc::new(Mutex::new(Some(tx))) };
+        let request = |game_id: Option<&str>, q| AdoptRequest { game_id: game_id.map(str::to_string), coord: NodeCoord::new(q, 0) };
+
+        // Without a game there's nothing to join
+        let (status, _) = handle_adopt(State(state.clone()), Json(request(None, 1))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = handle_adopt(State(state.clone()), Json(request(Some("game-7"), 2))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let (status, _) = handle_adopt(State(state), Json(request(Some("game-8"), 3))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        assert_eq!(
+            rx.recv().await,
+            Some(Assignment { game_id: "game-7".to_string(), coord: NodeCoord::new(2, 0), request_id: None })
+        );
+    }
+}
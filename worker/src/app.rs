@@ -0,0 +1,90 @@
+// App - the process around a worker's or client's tasks, and how it stops
+//
+// Everything long-lived runs under a Supervisor, which tracks each task it spawns.
+// App owns that supervisor for the binary: `run` drives the binary's main future
+// (the worker's tick scheduler, the client's API server) until it ends or the
+// process is told to stop (SIGTERM from ECS, Ctrl-C locally), then cancels every
+// tracked task and waits for them before main returns and Raft is shut down.
+
+use crate::supervisor::Supervisor;
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+
+/// How long tasks get to finish after they're cancelled
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// A binary's tasks, stopped together
+pub struct App {
+    supervisor: Supervisor,
+}
+
+impl App {
+    /// The process-wide supervisor's tasks (what /healthz reports)
+    pub fn new() -> Self {
+        Self::with_supervisor(Supervisor::global().clone())
+    }
+
+    pub fn with_supervisor(supervisor: Supervisor) -> Self {
+        Self { supervisor }
+    }
+
+    pub fn supervisor(&self) -> &Supervisor {
+        &self.supervisor
+    }
+
+    /// Run `main` until it returns or the process is told to stop, then shut down
+    /// An error from `main` is returned once the tasks have stopped.
+    pub async fn run(self, main: impl Future<Output = Result<()>>) -> Result<()> {
+        let result = tokio::select! {
+            result = main => result,
+            signal = stop_signal() => {
+                println!("[App] {}, shutting down", signal);
+                Ok(())
+            }
+        };
+        self.shutdown().await;
+        result
+    }
+
+    /// Cancel every tracked task and wait up to SHUTDOWN_GRACE for them to finish
+    pub async fn shutdown(&self) {
+        println!("[App] Stopping {} tasks", self.supervisor.tasks());
+        let stuck = self.supervisor.shutdown(SHUTDOWN_GRACE).await;
+        if stuck.is_empty() {
+            println!("[App] All tasks stopped");
+        } else {
+            eprintln!("[App] Still running after {:?}: {}", SHUTDOWN_GRACE, stuck.join(", "));
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wait for SIGTERM or Ctrl-C, naming which arrived
+async fn stop_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        let mut term = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(term) => term,
+            Err(e) => {
+                eprintln!("[App] Can't listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return "Ctrl-C";
+            }
+        };
+        tokio::select! {
+            _ = term.recv() => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "Ctrl-C",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
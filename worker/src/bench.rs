@@ -0,0 +1,247 @@
+// Benchmark harness - a local cluster and the workloads measured against it
+//
+// The consensus and flooding redesigns need baselines from before they land, taken
+// the same way after. The criterion benches (benches/) and the worker-bench binary
+// share what they measure from here:
+//
+// - LocalCluster: RaftNodes on loopback gRPC, each with its own registry and storage,
+//   exactly as workers run them (Hello, compression, the real state machine)
+// - event_stream: a log shaped like a real game's, for state-machine apply
+// - flood: the real flooder loop (udp_attacker) against a loopback sink
+//
+// Nothing here runs in a worker.
+
+use crate::game::udp::udp_attacker;
+use crate::game::udp_batch::SocketPool;
+use crate::game::{AttackTarget, CommandEvent, GameEvent, NetSimConfig, NodeCoord, TelemetryEvent};
+use crate::raft::grpc_server;
+use crate::raft::node_registry::NodeRegistry;
+use crate::raft::storage::{GameEventRequest, GameRaftTypeConfig, MemStorage, NodeId};
+use crate::raft::RaftNode;
+use anyhow::{Context, Result};
+use futures_util::future::try_join_all;
+use openraft::{Entry, EntryPayload, LogId};
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// How long a new cluster may take to elect its first leader
+const LEADER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Players a LocalCluster's game starts with
+pub const CLUSTER_PLAYERS: u64 = 8;
+
+/// Raft nodes on loopback, started and initialized as one cluster
+pub struct LocalCluster {
+    pub nodes: Vec<RaftNode>,
+    servers: Vec<JoinHandle<Result<()>>>,
+}
+
+impl LocalCluster {
+    /// Start `size` nodes (ids 1..=size), wait until the leader has every follower in
+    /// step, and join CLUSTER_PLAYERS players for the writes to play between
+    pub async fn start(size: u64) -> Result<Self> {
+        let mut listeners = Vec::new();
+        for _ in 0..size {
+            listeners.push(tokio::net::TcpListener::bind("127.0.0.1:0").await?);
+        }
+        let addrs = listeners.iter().map(|l| l.local_addr().map(|a| a.to_string())).collect::<std::io::Result<Vec<_>>>()?;
+
+        let mut nodes = Vec::new();
+        let mut servers = Vec::new();
+        for (id, listener) in (1..=size).zip(listeners) {
+            let registry = NodeRegistry::new();
+            for (peer, addr) in (1..=size).zip(&addrs) {
+                registry.register(peer, addr.clone()).await;
+            }
+            let storage = MemStorage::with_registry(registry.clone());
+            let node = RaftNode::with_storage(id, registry, storage).await?;
+            let storage = node.storage.read().await.clone_storage();
+            servers.push(tokio::spawn(grpc_server::serve_grpc_on(
+                node.raft.clone(),
+                storage,
+                node.registry.clone(),
+                node.metrics_view.clone(),
+                listener,
+            )));
+            nodes.push(node);
+        }
+
+        let members: BTreeMap<NodeId, ()> = (1..=size).map(|id| (id, ())).collect();
+        nodes[0].raft.initialize(members).await.context("Failed to initialize cluster")?;
+        // Followers never learn the membership (it replicates as a blank entry), so
+        // it's the leader that says when every one of them has caught up
+        nodes[0]
+            .raft
+            .wait(Some(LEADER_TIMEOUT))
+            .metrics(
+                |m| {
+                    m.current_leader.is_some()
+                        && m.replication.as_ref().is_some_and(|r| r.len() as u64 >= size - 1 && r.values().all(Option::is_some))
+                },
+                "leader elected and followers matched",
+            )
+            .await
+            .context("Cluster didn't come up")?;
+
+        let cluster = Self { nodes, servers };
+        for player_id in 1..=CLUSTER_PLAYERS {
+            cluster.leader().raft.client_write(GameEventRequest::new(join(player_id))).await?;
+        }
+        Ok(cluster)
+    }
+
+    /// The node currently leading
+    pub fn leader(&self) -> &RaftNode {
+        let leader = self.nodes[0].raft.metrics().borrow().current_leader;
+        self.nodes.iter().find(|node| Some(node.node_id) == leader).unwrap_or(&self.nodes[0])
+    }
+
+    /// Commit `count` game events (from the `seq`th on) through the leader at once,
+    /// each its own client_write
+    /// Returns how long each took to commit and apply, in submission order.
+    pub async fn write_batch(&self, count: usize, seq: u64) -> Result<Vec<Duration>> {
+        let leader = self.leader();
+        try_join_all((0..count as u64).map(|i| async move {
+            let started = Instant::now();
+            leader.raft.client_write(GameEventRequest::new(game_event(CLUSTER_PLAYERS, seq + i))).await?;
+            Ok::<_, anyhow::Error>(started.elapsed())
+        }))
+        .await
+    }
+
+    /// Stop every node and its gRPC server
+    pub async fn shutdown(self) {
+        for node in &self.nodes {
+            let _ = node.raft.shutdown().await;
+        }
+        for server in self.servers {
+            server.abort();
+        }
+    }
+}
+
+/// `players` joins, then `len` events of a running game between them
+pub fn event_stream(players: u64, len: usize) -> Vec<GameEvent> {
+    (1..=players).map(join).chain((0..len as u64).map(|seq| game_event(players, seq))).collect()
+}
+
+/// Player `player_id` joining with a capital of its own
+pub fn join(player_id: u64) -> GameEvent {
+    GameEvent::Command(CommandEvent::PlayerJoin {
+        player_id,
+        name: format!("player{}", player_id),
+        capital_coord: capital(player_id),
+        capital_ip: None,
+        client_endpoint: None,
+        timestamp: 1_700_000_000,
+        raft_node_id: 0,
+        reconnect_key: None,
+    })
+}
+
+/// The `seq`th event of a game between `players` who have joined: their capitals
+/// retargeting each other (the bulk of a real log), standing down, and client heartbeats
+pub fn game_event(players: u64, seq: u64) -> GameEvent {
+    let player_id = seq % players + 1;
+    let timestamp = 1_700_000_000 + seq;
+    match seq % 4 {
+        3 => GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id, timestamp }),
+        2 => GameEvent::Command(CommandEvent::SetNodeTarget { node_coord: capital(player_id), target: None, timestamp }),
+        _ => GameEvent::Command(CommandEvent::SetNodeTarget {
+            node_coord: capital(player_id),
+            target: Some(AttackTarget::Coordinate(capital(player_id % players + 1))),
+            timestamp,
+        }),
+    }
+}
+
+fn capital(player_id: u64) -> NodeCoord {
+    NodeCoord::new(player_id as i32 * 4, 0)
+}
+
+/// Log entries for `events`, at indexes 1.. in one term
+pub fn entries(events: &[GameEvent]) -> Vec<Entry<GameRaftTypeConfig>> {
+    (1..)
+        .zip(events)
+        .map(|(index, event)| Entry {
+            log_id: LogId::new(openraft::LeaderId::new(1, 1), index),
+            payload: EntryPayload::Normal(GameEventRequest::new(event.clone())),
+        })
+        .collect()
+}
+
+/// A loopback UDP receiver that drops everything, counting the packets it took
+pub fn udp_sink() -> Result<(SocketAddr, Arc<AtomicU64>)> {
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    let addr = socket.local_addr()?;
+    let received = Arc::new(AtomicU64::new(0));
+    let counter = received.clone();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 2048];
+        while socket.recv_from(&mut buf).is_ok() {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    Ok((addr, received))
+}
+
+/// Run one unpaced flooder at `target` until it has sent `packets` or `limit` is up
+/// Returns the packets it sent and how long that took. The flooder runs in the
+/// calling task; an unpaced one may never yield, so a thread of its own stops it.
+pub async fn flood(target: SocketAddr, packets: u64, limit: Duration) -> Result<(u64, Duration)> {
+    let shared = SocketPool::new().get(target, NetSimConfig::default())?;
+    let sent = shared.tracker.sent.clone();
+    let (stop, stop_signal) = broadcast::channel(1);
+
+    let counted = sent.clone();
+    let started = Instant::now();
+    let stopper = std::thread::spawn(move || {
+        while counted.load(Ordering::Relaxed) < packets && started.elapsed() < limit {
+            std::thread::sleep(Duration::from_micros(200));
+        }
+        let _ = stop.send(());
+        started.elapsed()
+    });
+    udp_attacker(shared, stop_signal, Arc::new(AtomicU64::new(0))).await?;
+    let elapsed = stopper.join().map_err(|_| anyhow::anyhow!("Flood stopper panicked"))?;
+    Ok((sent.load(Ordering::Relaxed), elapsed))
+}
+
+/// The `pct`th percentile (0-100) of `samples`, which it sorts
+pub fn percentile(samples: &mut [Duration], pct: f64) -> Duration {
+    if samples.is_empty() {
+        return Duration::ZERO;
+    }
+    samples.sort_unstable();
+    let rank = ((samples.len() - 1) as f64 * pct / 100.0).round() as usize;
+    samples[rank]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_cluster_replicates_writes() {
+        let cluster = LocalCluster::start(3).await.unwrap();
+        assert_eq!(cluster.write_batch(10, 0).await.unwrap().len(), 10);
+
+        // Followers apply as the next heartbeat tells them the commit index
+        let committed = cluster.leader().raft.metrics().borrow().last_applied.map(|id| id.index);
+        for node in &cluster.nodes {
+            node.raft
+                .wait(Some(Duration::from_secs(5)))
+                .metrics(|m| m.last_applied.map(|id| id.index) >= committed, "caught up")
+                .await
+                .unwrap();
+            let sm = node.storage.read().await.state_machine();
+            assert_eq!(sm.read().await.game_state.players.len(), CLUSTER_PLAYERS as usize);
+        }
+        cluster.shutdown().await;
+    }
+}
@@ -0,0 +1,135 @@
+// worker-bench - baseline numbers for consensus, apply and flooding on this machine
+//
+// The criterion benches (cargo bench) compare runs on a developer's machine; this
+// prints one line per measurement instead, so the same numbers can be taken wherever
+// the worker binary runs (a Fargate task, a CI runner) and pasted next to each other
+// before and after a redesign. Each measurement runs for --secs:
+//
+//   client_write  batches of 1/10/100 concurrent writes through a local 3-node cluster:
+//                 writes/sec and per-write commit latency (p50/p99)
+//   apply         a long game log applied to a fresh state machine: events/sec
+//   flood         one unpaced flooder into a loopback sink: packets/sec sent and taken
+
+use anyhow::{bail, Context, Result};
+use openraft::RaftStorage;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use worker::bench::{entries, event_stream, flood, percentile, udp_sink, LocalCluster};
+use worker::MemStorage;
+
+const USAGE: &str = "\
+Usage: worker-bench [options] [client_write|apply|flood ...]
+
+  --secs N          Seconds per measurement (default: 5)
+  --nodes N         Nodes in the client_write cluster (default: 3)
+
+Runs every measurement when none is named.
+";
+
+/// Concurrent writes per client_write batch
+const WRITE_BATCHES: [usize; 3] = [1, 10, 100];
+
+/// Matches max_payload_entries in RaftNode::new
+const APPLY_BATCH: usize = 300;
+
+struct Options {
+    secs: u64,
+    nodes: u64,
+    runs: Vec<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Options { secs: 5, nodes: 3, runs: Vec::new() };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("{} needs a value\n\n{}", arg, USAGE));
+            match arg.as_str() {
+                "--secs" => options.secs = value()?.parse().context("--secs")?,
+                "--nodes" => options.nodes = value()?.parse().context("--nodes")?,
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                "client_write" | "apply" | "flood" => options.runs.push(arg),
+                other => bail!("Unknown option {}\n\n{}", other, USAGE),
+            }
+        }
+        if options.secs == 0 {
+            bail!("--secs: at least 1");
+        }
+        if options.nodes == 0 {
+            bail!("--nodes: at least 1");
+        }
+        if options.runs.is_empty() {
+            options.runs = ["client_write", "apply", "flood"].map(String::from).to_vec();
+        }
+        Ok(options)
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let options = Options::parse(std::env::args().skip(1))?;
+    let window = Duration::from_secs(options.secs);
+    for run in &options.runs {
+        match run.as_str() {
+            "client_write" => client_write(options.nodes, window).await?,
+            "apply" => apply(window).await?,
+            _ => flooder(window).await?,
+        }
+    }
+    Ok(())
+}
+
+async fn client_write(nodes: u64, window: Duration) -> Result<()> {
+    let cluster = LocalCluster::start(nodes).await?;
+    let mut seq = 0;
+    for batch in WRITE_BATCHES {
+        let mut latencies = Vec::new();
+        let started = Instant::now();
+        while started.elapsed() < window {
+            latencies.extend(cluster.write_batch(batch, seq).await?);
+            seq += batch as u64;
+        }
+        let elapsed = started.elapsed();
+        println!(
+            "client_write nodes={} batch={}: {:.0} writes/s, p50 {:.2?}, p99 {:.2?}",
+            nodes,
+            batch,
+            latencies.len() as f64 / elapsed.as_secs_f64(),
+            percentile(&mut latencies, 50.0),
+            percentile(&mut latencies, 99.0),
+        );
+    }
+    cluster.shutdown().await;
+    Ok(())
+}
+
+async fn apply(window: Duration) -> Result<()> {
+    let log = entries(&event_stream(20, 10_000));
+    let mut applied = 0;
+    let mut busy = Duration::ZERO;
+    while busy < window {
+        let mut storage = MemStorage::new();
+        let started = Instant::now();
+        for chunk in log.chunks(APPLY_BATCH) {
+            storage.apply_to_state_machine(chunk).await?;
+        }
+        busy += started.elapsed();
+        applied += log.len();
+    }
+    println!("apply: {:.0} events/s ({} events)", applied as f64 / busy.as_secs_f64(), applied);
+    Ok(())
+}
+
+async fn flooder(window: Duration) -> Result<()> {
+    let (sink, received) = udp_sink()?;
+    let (sent, elapsed) = flood(sink, u64::MAX, window).await?;
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "flood: {:.0} pps sent, {:.0} pps received",
+        sent as f64 / secs,
+        received.load(Ordering::Relaxed) as f64 / secs,
+    );
+    Ok(())
+}
@@ -0,0 +1,759 @@
+// Request/response types for the player client's HTTP API (client binary)
+// Shared with camhack-client-sdk so both sides agree on the wire format
+
+use crate::game::events::AnnouncementSeverity;
+use crate::game::grid::HexDirection;
+use crate::game::state::UnderAttack;
+use crate::game::{
+    AttackOrder, AttackTarget, CommandEvent, DefenseOrder, FeatureFlags, GameEvent, GamePhase, GameState, MetricsSample, NodeCoord, PathPlan,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use tokio::task::AbortHandle;
+
+/// Request for POST /join
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinRequest {
+    pub game_id: String,
+    pub player_name: String,
+    /// Account token from the master's POST /players - play rated, under a stable player id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// Response for GET /status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinStatus {
+    pub joined: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capital_coord: Option<NodeCoord>,
+}
+
+/// Response for GET /my/status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerStatusResponse {
+    pub player_id: u64,
+    pub player_name: String,
+    pub capital_coord: NodeCoord,
+    pub alive: bool,
+    /// Capital lost: eliminated at this time unless it's recaptured first
+    #[serde(default)]
+    pub contested_until: Option<u64>,
+    pub owned_nodes: usize,
+    pub is_leader: bool,
+    #[serde(default)]
+    pub phase: GamePhase,
+}
+
+/// One entry of GET /my/nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedNodeInfo {
+    pub coord: NodeCoord,
+    pub node_type: String,
+    pub current_target: Option<String>,
+    /// "active" if the target is flooding this node, "queued" if the target is
+    /// out of flooder budget and this node is waiting its turn, None if not attacking
+    pub attack_status: Option<String>,
+    /// Defend order the node holds instead of attacking
+    #[serde(default)]
+    pub defense: Option<DefenseOrder>,
+}
+
+/// Response for GET /my/path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathResponse {
+    #[serde(flatten)]
+    pub plan: PathPlan,
+    /// First hex on the path this player doesn't own yet
+    pub next_target: Option<NodeCoord>,
+}
+
+/// Request for POST /my/attack
+/// Coordinates may also come as the older `target_q`/`target_r` and `node_q`/`node_r`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetAttackRequest {
+    #[serde(flatten, with = "target_fields")]
+    pub target: NodeCoord,
+    /// Attacking node - defaults to the player's capital
+    #[serde(flatten, with = "node_fields")]
+    pub node: Option<NodeCoord>,
+}
+
+mod target_fields {
+    use crate::game::{coord, NodeCoord};
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(target: &NodeCoord, serializer: S) -> Result<S::Ok, S::Error> {
+        coord::serialize_fields(Some(target), "target", serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NodeCoord, D::Error> {
+        coord::deserialize_fields(deserializer, "target")?.ok_or_else(|| de::Error::missing_field("target"))
+    }
+}
+
+mod node_fields {
+    use crate::game::{coord, NodeCoord};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(node: &Option<NodeCoord>, serializer: S) -> Result<S::Ok, S::Error> {
+        coord::serialize_fields(node.as_ref(), "node", serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NodeCoord>, D::Error> {
+        coord::deserialize_fields(deserializer, "node")
+    }
+}
+
+/// Request for POST /my/defend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefendRequest {
+    /// Defending node - defaults to the player's capital
+    #[serde(flatten, with = "node_fields")]
+    pub node: Option<NodeCoord>,
+    /// Adjacent node of yours to give half of the boost to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assist: Option<NodeCoord>,
+    /// Drop the defend order instead, so the node can attack again
+    #[serde(default)]
+    pub stand_down: bool,
+}
+
+/// Most orders one POST /my/attack/bulk takes
+pub const MAX_BULK_ORDERS: usize = 256;
+
+/// Request for POST /my/attack/bulk: attack orders committed together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkAttackRequest {
+    pub orders: Vec<SetAttackRequest>,
+}
+
+/// An order POST /my/attack/bulk left out of the batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedOrder {
+    /// Position in the request's `orders`
+    pub index: usize,
+    pub reason: String,
+}
+
+/// Response for POST /my/attack/bulk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkAttackResponse {
+    /// Orders committed, all in one SetNodeTargets
+    pub accepted: usize,
+    pub rejected: Vec<RejectedOrder>,
+    /// Where the batch was committed, None when no order was valid
+    pub log_index: Option<u64>,
+}
+
+/// Validate each order the way POST /my/attack does (ownership, adjacency, terrain,
+/// spawn protection), splitting them into the batch to commit and the rejects
+/// An order without a node attacks from `capital`; a node ordered twice keeps its first order.
+pub fn plan_bulk_attack(
+    state: &GameState,
+    player_id: u64,
+    capital: NodeCoord,
+    orders: &[SetAttackRequest],
+    now: u64,
+) -> (Vec<AttackOrder>, Vec<RejectedOrder>) {
+    let mut batch: Vec<AttackOrder> = Vec::new();
+    let mut rejected = Vec::new();
+    for (index, order) in orders.iter().enumerate() {
+        let node_coord = order.node.unwrap_or(capital);
+        let checked = if batch.iter().any(|o| o.node_coord == node_coord) {
+            Err(format!("Node {} already has an order in this batch", node_coord))
+        } else {
+            state.validate_attack(Some(player_id), node_coord, order.target, now).map_err(|e| e.to_string())
+        };
+        match checked {
+            Ok(()) => batch.push(AttackOrder { node_coord, target: Some(AttackTarget::Coordinate(order.target)) }),
+            Err(reason) => rejected.push(RejectedOrder { index, reason }),
+        }
+    }
+    (batch, rejected)
+}
+
+/// Commands GET /my/history keeps, newest first
+pub const COMMAND_HISTORY_LEN: usize = 50;
+
+/// Where a submitted command stands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CommandStatus {
+    /// Still on its way to the leader (cancellable)
+    Pending,
+    Committed { log_index: u64 },
+    Failed { error: String },
+    /// Given up at the player's request; it may still commit if the leader already had it
+    Cancelled,
+}
+
+/// One command the joined player sent, as GET /my/history lists it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub id: u64,
+    pub player_id: u64,
+    /// Event kind, e.g. "SetNodeTarget"
+    pub kind: String,
+    /// What it orders, e.g. "0,0 -> 1,0"
+    pub summary: String,
+    /// Unix seconds
+    pub submitted_at: u64,
+    #[serde(flatten)]
+    pub status: CommandStatus,
+}
+
+/// Why POST /my/history/{id}/cancel refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CancelError {
+    /// No such command in the player's history
+    NotFound,
+    /// It already committed, failed or was cancelled
+    NotPending(CommandStatus),
+}
+
+impl fmt::Display for CancelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CancelError::NotFound => write!(f, "No such command"),
+            CancelError::NotPending(status) => write!(f, "Command is no longer pending ({:?})", status),
+        }
+    }
+}
+
+/// What `event` orders, in a line
+pub fn describe_command(event: &GameEvent) -> String {
+    if let GameEvent::Command(CommandEvent::SetNodeDefense { node_coord, defense, .. }) = event {
+        return match defense {
+            Some(DefenseOrder { assist: Some(assist) }) => format!("{} defends, assisting {}", node_coord, assist),
+            Some(DefenseOrder { assist: None }) => format!("{} defends", node_coord),
+            None => format!("{} stands down", node_coord),
+        };
+    }
+    let orders = event.attack_orders();
+    if orders.is_empty() {
+        return event.kind().to_string();
+    }
+    orders
+        .iter()
+        .map(|order| match order.target {
+            Some(AttackTarget::Coordinate(target)) => format!("{} -> {}", order.node_coord, target),
+            Some(AttackTarget::Player(player_id)) => format!("{} -> player {}", order.node_coord, player_id),
+            None => format!("{} stops", order.node_coord),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The client's last COMMAND_HISTORY_LEN commands and their commit status
+///
+/// Each submission runs as its own task, registered here, so a command still
+/// waiting on the leader (e.g. across a failover) can be aborted. Cancelling is
+/// best-effort: a command the leader already received may commit anyway.
+#[derive(Debug, Default)]
+pub struct CommandHistory {
+    next_id: u64,
+    records: VecDeque<CommandRecord>,
+    /// Submission tasks of pending commands
+    pending: HashMap<u64, AbortHandle>,
+}
+
+impl CommandHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `event`, about to be submitted for `player_id`; returns its id
+    pub fn start(&mut self, player_id: u64, event: &GameEvent, now: u64) -> u64 {
+        self.next_id += 1;
+        self.records.push_front(CommandRecord {
+            id: self.next_id,
+            player_id,
+            kind: event.kind().to_string(),
+            summary: describe_command(event),
+            submitted_at: now,
+            status: CommandStatus::Pending,
+        });
+        while self.records.len() > COMMAND_HISTORY_LEN {
+            if let Some(dropped) = self.records.pop_back() {
+                self.pending.remove(&dropped.id);
+            }
+        }
+        self.next_id
+    }
+
+    /// The task submitting command `id`, so `cancel` can abort it
+    pub fn track(&mut self, id: u64, submission: AbortHandle) {
+        if self.status(id) == Some(&CommandStatus::Pending) {
+            self.pending.insert(id, submission);
+        }
+    }
+
+    /// Command `id` committed at a log index or failed; a cancelled command stays cancelled
+    pub fn finish(&mut self, id: u64, result: Result<u64, String>) {
+        self.pending.remove(&id);
+        if let Some(record) = self.records.iter_mut().find(|r| r.id == id && r.status == CommandStatus::Pending) {
+            record.status = match result {
+                Ok(log_index) => CommandStatus::Committed { log_index },
+                Err(error) => CommandStatus::Failed { error },
+            };
+        }
+    }
+
+    /// Abort `player_id`'s pending command `id`
+    pub fn cancel(&mut self, player_id: u64, id: u64) -> Result<(), CancelError> {
+        let record = self
+            .records
+            .iter_mut()
+            .find(|r| r.id == id && r.player_id == player_id)
+            .ok_or(CancelError::NotFound)?;
+        if record.status != CommandStatus::Pending {
+            return Err(CancelError::NotPending(record.status.clone()));
+        }
+        record.status = CommandStatus::Cancelled;
+        if let Some(submission) = self.pending.remove(&id) {
+            submission.abort();
+        }
+        Ok(())
+    }
+
+    /// `player_id`'s commands, newest first
+    pub fn records(&self, player_id: u64) -> Vec<CommandRecord> {
+        self.records.iter().filter(|r| r.player_id == player_id).cloned().collect()
+    }
+
+    fn status(&self, id: u64) -> Option<&CommandStatus> {
+        self.records.iter().find(|r| r.id == id).map(|r| &r.status)
+    }
+}
+
+/// Top-level fields the client's GET /game/state can be trimmed to (`?fields=`)
+pub const CLIENT_GAME_STATE_FIELDS: [&str; 5] = ["players", "nodes", "total_events", "phase", "features"];
+
+/// Response for GET /game/state (what the frontend renders)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientGameState {
+    pub players: Vec<PlayerView>,
+    pub nodes: Vec<NodeView>,
+    pub total_events: usize,
+    #[serde(default)]
+    pub phase: GamePhase,
+    #[serde(default)]
+    pub features: FeatureFlags,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub player_id: u64,
+    pub name: String,
+    pub capital_coord: NodeCoord,
+    pub alive: bool,
+    pub join_time: u64,
+    pub node_count: usize,
+    /// The player's client stopped heartbeating, so their nodes stand idle
+    #[serde(default)]
+    pub idle: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeView {
+    pub coord: NodeCoord,
+    pub owner_id: u64,
+    pub current_target: Option<TargetView>,
+    pub bandwidth_in: Option<u64>,
+    pub packet_loss: Option<f32>,
+    /// Combined-arms speed-up on this node's capture timer, if boosted
+    #[serde(default)]
+    pub capture_multiplier: Option<f32>,
+}
+
+/// Every player, as GET /game/state and the /ws `players` topic show them
+pub fn player_views(state: &GameState) -> Vec<PlayerView> {
+    state
+        .players
+        .iter()
+        .map(|(id, p)| PlayerView {
+            player_id: *id,
+            name: p.name.clone(),
+            capital_coord: p.capital_coord,
+            alive: p.alive,
+            join_time: p.join_time,
+            node_count: state.nodes.values().filter(|n| n.owner_id == *id).count(),
+            idle: p.idle_since.is_some(),
+        })
+        .collect()
+}
+
+/// Every node with its latest metrics, as GET /game/state and the /ws `nodes` topic show them
+pub fn node_views(state: &GameState) -> Vec<NodeView> {
+    state
+        .nodes
+        .iter()
+        .map(|(coord, node)| {
+            let metrics = state.node_metrics.get(coord);
+            NodeView {
+                coord: *coord,
+                owner_id: node.owner_id,
+                current_target: node.current_target.as_ref().map(TargetView::from),
+                bandwidth_in: metrics.map(|m| m.bandwidth_in),
+                packet_loss: metrics.map(|m| m.packet_loss),
+                capture_multiplier: state.capture_boosts.get(coord).map(|b| b.multiplier),
+            }
+        })
+        .collect()
+}
+
+/// A banner still showing, as /ws pushes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnouncementView {
+    /// Log index of the Announcement
+    pub id: u64,
+    pub text: String,
+    pub severity: AnnouncementSeverity,
+    /// Unix seconds, on the leader's clock
+    pub expires_at: u64,
+    /// Seconds left when it was pushed, for clients whose clock differs from the leader's
+    pub remaining_secs: u64,
+}
+
+/// Announcements still showing at `now` (unix seconds), oldest first
+pub fn announcement_views(state: &GameState, now: u64) -> Vec<AnnouncementView> {
+    state
+        .active_announcements(now)
+        .map(|(id, a)| AnnouncementView {
+            id,
+            text: a.text.clone(),
+            severity: a.severity,
+            expires_at: a.expires_at,
+            remaining_secs: a.expires_at - now,
+        })
+        .collect()
+}
+
+/// Attack target as the frontend expects it: `{q, r}` or `{player_id}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TargetView {
+    Coordinate(NodeCoord),
+    Player { player_id: u64 },
+}
+
+impl From<&AttackTarget> for TargetView {
+    fn from(target: &AttackTarget) -> Self {
+        match target {
+            AttackTarget::Coordinate(coord) => TargetView::Coordinate(*coord),
+            AttackTarget::Player(player_id) => TargetView::Player { player_id: *player_id },
+        }
+    }
+}
+
+/// Message pushed on the /ws WebSocket whenever applied events change game state
+/// (the whole feed of a socket that never subscribes, the `state` topic otherwise)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateUpdate {
+    pub log_index: u64,
+    pub event_count: usize,
+    pub player_count: usize,
+    pub node_count: usize,
+    pub alive_players: usize,
+    #[serde(default)]
+    pub phase: GamePhase,
+    pub latest_event: Option<String>,
+    /// The joined player's nodes under attack right now (empty when none are)
+    #[serde(default)]
+    pub attack_warnings: Vec<AttackWarning>,
+    /// Banners for every player, still showing at the push
+    #[serde(default)]
+    pub announcements: Vec<AnnouncementView>,
+}
+
+/// One of the player's nodes under attack, as pushed on /ws
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackWarning {
+    pub coord: NodeCoord,
+    pub attackers: Vec<AttackerView>,
+    /// Packet loss on the node in percent (0-100), None until recent metrics arrive
+    pub loss_percent: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackerView {
+    pub coord: NodeCoord,
+    pub owner_id: u64,
+    /// Side of the node the attack comes from ("east", "north_west", ...)
+    pub direction: Option<HexDirection>,
+}
+
+impl AttackWarning {
+    pub fn new(coord: NodeCoord, status: &UnderAttack) -> Self {
+        let attackers = status
+            .attackers
+            .iter()
+            .map(|a| AttackerView { coord: a.coord, owner_id: a.owner_id, direction: a.direction })
+            .collect();
+        Self { coord, attackers, loss_percent: status.packet_loss.map(|loss| loss * 100.0) }
+    }
+}
+
+// ============= /ws protocol =============
+//
+// A socket that never sends anything gets a bare StateUpdate per applied-log advance,
+// as before the protocol existed. Once it sends a ClientMessage it only gets
+// versioned ServerMessages, for the topics it subscribed to (see client_ws).
+
+/// `v` of every ServerMessage; bumped when a message's layout changes
+pub const WS_PROTOCOL_VERSION: u32 = 1;
+
+/// What a /ws client can subscribe to, written "state", "nodes", "players" or "metrics:q,r"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Topic {
+    /// StateUpdate summaries, with the player's attack warnings
+    State,
+    /// All nodes, whenever any of them changes
+    Nodes,
+    /// All players, whenever any of them changes
+    Players,
+    /// One node's latest committed metrics
+    Metrics(NodeCoord),
+}
+
+impl FromStr for Topic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "state" => Ok(Topic::State),
+            "nodes" => Ok(Topic::Nodes),
+            "players" => Ok(Topic::Players),
+            _ => {
+                let coord = s.strip_prefix("metrics:").ok_or_else(|| format!("Unknown topic '{}'", s))?;
+                let coord = coord
+                    .parse()
+                    .map_err(|_| format!("Invalid coordinate in topic '{}', expected metrics:q,r", s))?;
+                Ok(Topic::Metrics(coord))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::State => write!(f, "state"),
+            Topic::Nodes => write!(f, "nodes"),
+            Topic::Players => write!(f, "players"),
+            Topic::Metrics(coord) => write!(f, "metrics:{}", coord),
+        }
+    }
+}
+
+impl Serialize for Topic {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Topic {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Sent by a /ws client: `{"type": "subscribe", "topics": ["nodes", "metrics:1,0"]}`
+/// Topics stay strings here so one unknown topic is reported, not the whole message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { topics: Vec<String> },
+    Unsubscribe { topics: Vec<String> },
+    /// Push cadence and encoding; fields left out keep their current value
+    Configure {
+        /// At most one push per this many ms (clamped to MIN/MAX_PUSH_INTERVAL_MS)
+        #[serde(default)]
+        interval_ms: Option<u64>,
+        /// Send `nodes`/`players` as changes between keyframes
+        #[serde(default)]
+        delta: Option<bool>,
+    },
+    /// Send every topic in full on the next push (after a lost or misapplied delta)
+    Resync,
+}
+
+/// Sent to a /ws client that has subscribed: `{"v": 1, "type": "nodes", ...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerMessage {
+    pub v: u32,
+    #[serde(flatten)]
+    pub body: ServerBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerBody {
+    /// Every topic the socket is now subscribed to (reply to each ClientMessage)
+    Subscribed { topics: Vec<Topic> },
+    /// `state` topic
+    State(StateUpdate),
+    /// `nodes` topic
+    Nodes { log_index: u64, nodes: Vec<NodeView> },
+    /// `players` topic
+    Players { log_index: u64, players: Vec<PlayerView> },
+    /// `nodes` topic with delta on: nodes added or changed since the last push, and gone ones
+    NodesDelta { log_index: u64, changed: Vec<NodeView>, removed: Vec<NodeCoord> },
+    /// `players` topic with delta on
+    PlayersDelta { log_index: u64, changed: Vec<PlayerView>, removed: Vec<u64> },
+    /// Push settings now in effect (reply to Configure)
+    Configured { interval_ms: u64, delta: bool, keyframe_secs: u64 },
+    /// `metrics:q,r` topic - None until the node has reported
+    Metrics { log_index: u64, coord: NodeCoord, sample: Option<MetricsSample> },
+    /// A new banner - sent once to every socket, whatever it subscribed to
+    Announcement(AnnouncementView),
+    /// A message the client couldn't use (bad JSON, unknown topic)
+    Error { message: String },
+}
+
+impl From<ServerBody> for ServerMessage {
+    fn from(body: ServerBody) -> Self {
+        Self { v: WS_PROTOCOL_VERSION, body }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_parse_and_print() {
+        for topic in ["state", "nodes", "players", "metrics:1,-2"] {
+            assert_eq!(topic.parse::<Topic>().unwrap().to_string(), topic);
+        }
+        assert_eq!("metrics: 3, 4".parse(), Ok(Topic::Metrics(NodeCoord::new(3, 4))));
+        assert!("chat".parse::<Topic>().is_err());
+        assert!("metrics:x".parse::<Topic>().is_err());
+    }
+
+    #[test]
+    fn test_protocol_messages() {
+        let subscribe: ClientMessage = serde_json::from_str(r#"{"type":"subscribe","topics":["nodes"]}"#).unwrap();
+        assert!(matches!(subscribe, ClientMessage::Subscribe { topics } if topics == ["nodes"]));
+
+        let message = ServerMessage::from(ServerBody::Subscribed { topics: vec![Topic::Metrics(NodeCoord::new(1, 0))] });
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json, serde_json::json!({"v": 1, "type": "subscribed", "topics": ["metrics:1,0"]}));
+        let back: ServerMessage = serde_json::from_value(json).unwrap();
+        assert!(matches!(back.body, ServerBody::Subscribed { .. }));
+    }
+
+    #[test]
+    fn test_attack_request_shapes() {
+        let old: SetAttackRequest = serde_json::from_str(r#"{"target_q":1,"target_r":0}"#).unwrap();
+        assert_eq!((old.target, old.node), (NodeCoord::new(1, 0), None));
+
+        let new: SetAttackRequest = serde_json::from_str(r#"{"target":"1,0","node":{"q":0,"r":0}}"#).unwrap();
+        assert_eq!((new.target, new.node), (NodeCoord::new(1, 0), Some(NodeCoord::new(0, 0))));
+
+        let json = serde_json::to_value(&new).unwrap();
+        assert_eq!(json, serde_json::json!({"target": {"q": 1, "r": 0}, "node": {"q": 0, "r": 0}}));
+    }
+
+    #[test]
+    fn test_plan_bulk_attack() {
+        use crate::game::{CommandEvent, FactEvent};
+
+        let mut state = GameState::new();
+        state.process_event(
+            CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: None,
+                client_endpoint: None,
+                timestamp: 0,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }
+            .into(),
+            1,
+        );
+        state.process_event(
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(1, 0), owner_id: 1, timestamp: 0 }.into(),
+            2,
+        );
+        state.process_event(
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(3, 0), owner_id: 2, timestamp: 0 }.into(),
+            3,
+        );
+
+        let order = |node: Option<(i32, i32)>, target: (i32, i32)| SetAttackRequest {
+            target: NodeCoord::new(target.0, target.1),
+            node: node.map(|(q, r)| NodeCoord::new(q, r)),
+        };
+        let orders = [
+            order(None, (0, 1)),          // from the capital
+            order(Some((1, 0)), (2, 0)),
+            order(Some((1, 0)), (1, 1)),  // same node again
+            order(Some((3, 0)), (4, 0)),  // not Alice's
+            order(Some((1, 0)), (5, 0)),  // not adjacent (and a repeat)
+        ];
+        let (batch, rejected) = plan_bulk_attack(&state, 1, NodeCoord::new(0, 0), &orders, 0);
+
+        assert_eq!(
+            batch,
+            vec![
+                AttackOrder { node_coord: NodeCoord::new(0, 0), target: Some(AttackTarget::Coordinate(NodeCoord::new(0, 1))) },
+                AttackOrder { node_coord: NodeCoord::new(1, 0), target: Some(AttackTarget::Coordinate(NodeCoord::new(2, 0))) },
+            ]
+        );
+        assert_eq!(rejected.iter().map(|r| r.index).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(rejected[1].reason, "You don't own this node");
+    }
+
+    #[tokio::test]
+    async fn test_command_history() {
+        let attack = |q: i32| {
+            GameEvent::Command(CommandEvent::SetNodeTarget {
+                node_coord: NodeCoord::new(0, 0),
+                target: Some(AttackTarget::Coordinate(NodeCoord::new(q, 0))),
+                timestamp: 0,
+            })
+        };
+        let mut history = CommandHistory::new();
+        let committed = history.start(1, &attack(1), 100);
+        let misclick = history.start(1, &attack(2), 101);
+        let submission = tokio::spawn(std::future::pending::<()>());
+        history.track(misclick, submission.abort_handle());
+        history.finish(committed, Ok(42));
+
+        // Only a pending command of the player's own can be cancelled, and only once
+        assert_eq!(history.cancel(2, misclick), Err(CancelError::NotFound));
+        assert_eq!(
+            history.cancel(1, committed),
+            Err(CancelError::NotPending(CommandStatus::Committed { log_index: 42 }))
+        );
+        assert_eq!(history.cancel(1, misclick), Ok(()));
+        assert!(submission.await.unwrap_err().is_cancelled());
+        history.finish(misclick, Err("aborted".to_string()));
+
+        let records = history.records(1);
+        assert_eq!(records.iter().map(|r| r.id).collect::<Vec<_>>(), vec![misclick, committed]);
+        assert_eq!(records[0].status, CommandStatus::Cancelled);
+        assert_eq!(records[0].summary, "0,0 -> 2,0");
+        assert!(history.records(2).is_empty());
+
+        let json = serde_json::to_value(&records[1]).unwrap();
+        assert_eq!((json["status"].as_str(), json["log_index"].as_u64()), (Some("committed"), Some(42)));
+
+        for q in 0..COMMAND_HISTORY_LEN as i32 {
+            history.start(1, &attack(q), 200);
+        }
+        assert_eq!(history.records(1).len(), COMMAND_HISTORY_LEN);
+    }
+}
@@ -0,0 +1,368 @@
+// Per-socket state of the client's /ws protocol (message types in client_api)
+//
+// A socket starts on the bare StateUpdate feed; its first ClientMessage switches it
+// to topics. A push is due once game state has changed (the state machine's
+// change_version, bumped only by events that report StateChanges) or new metrics
+// arrived, and sends each subscribed topic only if it changed since the last one. With delta on, `nodes` and `players` go out as just the entries that changed
+// (NodesDelta/PlayersDelta), with a full keyframe every KEYFRAME_INTERVAL so a client
+// that dropped or misapplied a delta converges anyway. Pushes come at most once per
+// interval, which the client picks within MIN/MAX_PUSH_INTERVAL_MS. Announcements
+// aren't a topic: every protocol socket gets each one once, while it's showing.
+
+use crate::client_api::{
+    node_views, player_views, ClientMessage, NodeView, PlayerView, ServerBody, ServerMessage, StateUpdate, Topic,
+};
+use crate::game::{GameState, MetricsSample, NodeCoord};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+/// Push interval until the client configures one
+pub const DEFAULT_PUSH_INTERVAL_MS: u64 = 500;
+
+/// Fastest cadence a client can ask for (also how often the socket checks for changes)
+pub const MIN_PUSH_INTERVAL_MS: u64 = 100;
+
+pub const MAX_PUSH_INTERVAL_MS: u64 = 60_000;
+
+/// How often a delta socket gets `nodes` and `players` in full
+pub const KEYFRAME_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a topic last sent the client
+#[derive(Debug, PartialEq)]
+enum Sent {
+    /// `state` (its log index) and `metrics` (its sample as JSON)
+    Payload(String),
+    Nodes(BTreeMap<NodeCoord, NodeView>),
+    Players(BTreeMap<u64, PlayerView>),
+}
+
+/// One /ws socket's subscriptions, cadence and what it has been sent
+pub struct WsSession {
+    /// None until the first ClientMessage: the bare StateUpdate feed
+    topics: Option<BTreeSet<Topic>>,
+    interval: Duration,
+    delta: bool,
+    last_push: Option<Instant>,
+    /// GameStateMachine::change_version at the last push
+    last_change_version: Option<u64>,
+    /// MetricsView::version at the last push - metrics change without the log moving
+    last_metrics_version: Option<u64>,
+    /// None forces the next push to be a keyframe
+    last_keyframe: Option<Instant>,
+    sent: HashMap<Topic, Sent>,
+    /// Announcements already sent (ids of those still showing)
+    announced: BTreeSet<u64>,
+}
+
+impl Default for WsSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WsSession {
+    pub fn new() -> Self {
+        Self {
+            topics: None,
+            interval: Duration::from_millis(DEFAULT_PUSH_INTERVAL_MS),
+            delta: false,
+            last_push: None,
+            last_change_version: None,
+            last_metrics_version: None,
+            last_keyframe: None,
+            sent: HashMap::new(),
+            announced: BTreeSet::new(),
+        }
+    }
+
+    /// Whether to push at `now` for state at `change_version` with metrics at `metrics_version`:
+    /// the interval has passed and the state or the metrics changed, or a topic hasn't been sent yet
+    pub fn due(&self, change_version: u64, metrics_version: u64, now: Instant) -> bool {
+        if self.last_push.is_some_and(|t| now.duration_since(t) < self.interval) {
+            return false;
+        }
+        self.last_change_version != Some(change_version)
+            || self.last_metrics_version != Some(metrics_version)
+            || self.topics.iter().flatten().any(|t| !self.sent.contains_key(t))
+    }
+
+    /// Apply a message from the client, returning the replies
+    pub fn handle(&mut self, text: &str) -> Vec<ServerMessage> {
+        // Any message switches the socket to the protocol
+        let subscribed = self.topics.get_or_insert_with(BTreeSet::new);
+        let mut replies = Vec::new();
+        match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::Subscribe { topics }) => {
+                replies.extend(update_topics(subscribed, &topics, true));
+                replies.push(self.subscribed());
+            }
+            Ok(ClientMessage::Unsubscribe { topics }) => {
+                replies.extend(update_topics(subscribed, &topics, false));
+                replies.push(self.subscribed());
+            }
+            Ok(ClientMessage::Configure { interval_ms, delta }) => {
+                if let Some(ms) = interval_ms {
+                    self.interval = Duration::from_millis(ms.clamp(MIN_PUSH_INTERVAL_MS, MAX_PUSH_INTERVAL_MS));
+                }
+                if let Some(delta) = delta {
+                    self.delta = delta;
+                    self.last_keyframe = None;
+                }
+                replies.push(ServerBody::Configured {
+                    interval_ms: self.interval.as_millis() as u64,
+                    delta: self.delta,
+                    keyframe_secs: KEYFRAME_INTERVAL.as_secs(),
+                });
+            }
+            Ok(ClientMessage::Resync) => {
+                self.sent.clear();
+                self.announced.clear();
+                self.last_keyframe = None;
+            }
+            Err(e) => replies.push(ServerBody::Error { message: format!("Invalid message: {}", e) }),
+        }
+        if let Some(topics) = &self.topics {
+            self.sent.retain(|topic, _| topics.contains(topic));
+        }
+        replies.into_iter().map(ServerMessage::from).collect()
+    }
+
+    fn subscribed(&self) -> ServerBody {
+        ServerBody::Subscribed { topics: self.topics.iter().flatten().copied().collect() }
+    }
+
+    /// Messages (as JSON) for everything that changed, and mark the push done
+    /// `update` is the StateUpdate the bare feed sends; call when `due`.
+    pub fn push(
+        &mut self,
+        game_state: &GameState,
+        update: StateUpdate,
+        change_version: u64,
+        metrics_version: u64,
+        now: Instant,
+    ) -> Vec<String> {
+        self.last_push = Some(now);
+        self.last_change_version = Some(change_version);
+        self.last_metrics_version = Some(metrics_version);
+        let Some(topics) = self.topics.clone() else {
+            return serde_json::to_string(&update).into_iter().collect();
+        };
+
+        let keyframe = self.last_keyframe.is_none_or(|t| now.duration_since(t) >= KEYFRAME_INTERVAL);
+        if keyframe {
+            self.last_keyframe = Some(now);
+        }
+        let log_index = update.log_index;
+
+        let mut messages = Vec::new();
+        self.announced.retain(|id| update.announcements.iter().any(|a| a.id == *id));
+        for announcement in &update.announcements {
+            if self.announced.insert(announcement.id) {
+                messages.extend(serde_json::to_string(&ServerMessage::from(ServerBody::Announcement(announcement.clone()))));
+            }
+        }
+        for topic in topics {
+            let (sent, body) = match topic {
+                Topic::State => (Sent::Payload(log_index.to_string()), ServerBody::State(update.clone())),
+                Topic::Nodes => {
+                    let nodes: BTreeMap<_, _> = node_views(game_state).into_iter().map(|n| (n.coord, n)).collect();
+                    let body = match self.sent.get(&topic) {
+                        Some(Sent::Nodes(old)) if self.delta && !keyframe => {
+                            let (changed, removed) = diff(old, &nodes);
+                            ServerBody::NodesDelta { log_index, changed, removed }
+                        }
+                        _ => ServerBody::Nodes { log_index, nodes: nodes.values().cloned().collect() },
+                    };
+                    (Sent::Nodes(nodes), body)
+                }
+                Topic::Players => {
+                    let players: BTreeMap<_, _> =
+                        player_views(game_state).into_iter().map(|p| (p.player_id, p)).collect();
+                    let body = match self.sent.get(&topic) {
+                        Some(Sent::Players(old)) if self.delta && !keyframe => {
+                            let (changed, removed) = diff(old, &players);
+                            ServerBody::PlayersDelta { log_index, changed, removed }
+                        }
+                        _ => ServerBody::Players { log_index, players: players.values().cloned().collect() },
+                    };
+                    (Sent::Players(players), body)
+                }
+                Topic::Metrics(coord) => {
+                    let sample = game_state.node_metrics.get(&coord).map(|m| MetricsSample {
+                        timestamp: m.timestamp,
+                        bandwidth_in: m.bandwidth_in,
+                        packet_loss: m.packet_loss,
+                        queued_attackers: m.queued_attackers.len(),
+                        cpu_utilization: m.cpu_utilization,
+                        memory_pressure: m.memory_pressure,
+                    });
+                    let payload = serde_json::to_string(&sample).unwrap_or_default();
+                    (Sent::Payload(payload), ServerBody::Metrics { log_index, coord, sample })
+                }
+            };
+
+            // Unchanged since the last push - a delta socket's keyframe still resends nodes and players
+            let resend = self.delta && keyframe && matches!(topic, Topic::Nodes | Topic::Players);
+            if self.sent.get(&topic) == Some(&sent) && !resend {
+                continue;
+            }
+            self.sent.insert(topic, sent);
+            messages.extend(serde_json::to_string(&ServerMessage::from(body)));
+        }
+        messages
+    }
+}
+
+/// Apply a subscribe (or unsubscribe) to `subscribed`, with an Error for unknown topics
+/// Valid topics are applied even when others in the same message aren't.
+fn update_topics(subscribed: &mut BTreeSet<Topic>, names: &[String], subscribe: bool) -> Option<ServerBody> {
+    let mut errors = Vec::new();
+    for name in names {
+        match name.parse::<Topic>() {
+            Ok(topic) if subscribe => {
+                subscribed.insert(topic);
+            }
+            Ok(topic) => {
+                subscribed.remove(&topic);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    (!errors.is_empty()).then(|| ServerBody::Error { message: errors.join("; ") })
+}
+
+/// Entries of `new` that are new or differ from `old`, and keys `new` no longer has
+fn diff<K: Ord + Copy, V: PartialEq + Clone>(old: &BTreeMap<K, V>, new: &BTreeMap<K, V>) -> (Vec<V>, Vec<K>) {
+    let changed = new.iter().filter(|(k, v)| old.get(k) != Some(v)).map(|(_, v)| v.clone()).collect();
+    let removed = old.keys().filter(|k| !new.contains_key(k)).copied().collect();
+    (changed, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_api::AnnouncementView;
+    use crate::game::state::NodeInitState;
+    use crate::game::{GamePhase, Node, NodeType};
+
+    fn node(q: i32, owner_id: u64) -> Node {
+        Node {
+            coord: NodeCoord::new(q, 0),
+            owner_id,
+            node_type: NodeType::Regular,
+            current_target: None,
+            init_state: NodeInitState::Ready,
+        }
+    }
+
+    fn update(log_index: u64) -> StateUpdate {
+        StateUpdate {
+            log_index,
+            event_count: 0,
+            player_count: 0,
+            node_count: 0,
+            alive_players: 0,
+            phase: GamePhase::Lobby,
+            latest_event: None,
+            attack_warnings: Vec::new(),
+            announcements: Vec::new(),
+        }
+    }
+
+    fn bodies(messages: Vec<String>) -> Vec<ServerBody> {
+        messages.iter().map(|m| serde_json::from_str::<ServerMessage>(m).unwrap().body).collect()
+    }
+
+    #[test]
+    fn test_deltas_between_keyframes() {
+        let mut game_state = GameState::new();
+        for q in 0..3 {
+            game_state.nodes.insert(NodeCoord::new(q, 0), node(q, 1));
+        }
+        let mut session = WsSession::new();
+        session.handle(r#"{"type":"subscribe","topics":["nodes"]}"#);
+        let configured = session.handle(r#"{"type":"configure","delta":true,"interval_ms":5}"#);
+        assert!(matches!(configured[0].body, ServerBody::Configured { interval_ms: MIN_PUSH_INTERVAL_MS, delta: true, .. }));
+
+        let start = Instant::now();
+        let first = bodies(session.push(&game_state, update(1), 1, 0, start));
+        assert!(matches!(&first[..], [ServerBody::Nodes { nodes, .. }] if nodes.len() == 3));
+
+        // Nothing changed: nothing sent
+        let later = start + Duration::from_secs(1);
+        assert!(!session.due(1, 0, later));
+        // New metrics arrive without the log moving
+        assert!(session.due(1, 1, later));
+        assert!(session.push(&game_state, update(1), 1, 0, later).is_empty());
+
+        game_state.nodes.insert(NodeCoord::new(1, 0), node(1, 2));
+        game_state.nodes.remove(&NodeCoord::new(2, 0));
+        assert!(session.due(2, 0, later + Duration::from_secs(1)));
+        let delta = bodies(session.push(&game_state, update(2), 2, 0, later + Duration::from_secs(1)));
+        match &delta[..] {
+            [ServerBody::NodesDelta { changed, removed, .. }] => {
+                assert_eq!(changed.iter().map(|n| (n.coord, n.owner_id)).collect::<Vec<_>>(), [(NodeCoord::new(1, 0), 2)]);
+                assert_eq!(removed, &[NodeCoord::new(2, 0)]);
+            }
+            other => panic!("expected a delta, got {:?}", other),
+        }
+
+        // A keyframe is full even when nothing changed, as is the push after a resync
+        let keyframe = bodies(session.push(&game_state, update(2), 2, 0, start + KEYFRAME_INTERVAL));
+        assert!(matches!(&keyframe[..], [ServerBody::Nodes { nodes, .. }] if nodes.len() == 2));
+        session.handle(r#"{"type":"resync"}"#);
+        assert!(session.due(2, 0, start + KEYFRAME_INTERVAL + Duration::from_secs(1)));
+        let resync = bodies(session.push(&game_state, update(2), 2, 0, start + KEYFRAME_INTERVAL + Duration::from_secs(1)));
+        assert!(matches!(&resync[..], [ServerBody::Nodes { .. }]));
+    }
+
+    #[test]
+    fn test_announcements_sent_once() {
+        let mut session = WsSession::new();
+        session.handle(r#"{"type":"subscribe","topics":["nodes"]}"#);
+        let banner = AnnouncementView {
+            id: 7,
+            text: "5 minutes remaining".to_string(),
+            severity: crate::game::events::AnnouncementSeverity::Warning,
+            expires_at: 1030,
+            remaining_secs: 30,
+        };
+        let with_banner = StateUpdate { announcements: vec![banner.clone()], ..update(1) };
+
+        // Sent whatever the socket subscribed to, then not again while it shows
+        let start = Instant::now();
+        let first = bodies(session.push(&GameState::new(), with_banner.clone(), 1, 0, start));
+        assert!(matches!(&first[..], [ServerBody::Announcement(a), ServerBody::Nodes { .. }] if a == &banner));
+        let later = start + Duration::from_secs(1);
+        assert!(session.push(&GameState::new(), with_banner.clone(), 2, 0, later).is_empty());
+
+        // A resync sends it again
+        session.handle(r#"{"type":"resync"}"#);
+        let resent = bodies(session.push(&GameState::new(), with_banner, 2, 0, later + Duration::from_secs(1)));
+        assert!(matches!(&resent[0], ServerBody::Announcement(a) if a.id == 7));
+    }
+
+    #[test]
+    fn test_push_interval() {
+        let mut session = WsSession::new();
+        let start = Instant::now();
+        assert!(session.due(0, 0, start));
+        let bare = session.push(&GameState::new(), update(1), 1, 0, start);
+        assert!(serde_json::from_str::<StateUpdate>(&bare[0]).is_ok());
+
+        // Nothing changed, however far the log moved on
+        assert!(!session.due(1, 0, start + Duration::from_secs(5)));
+
+        // Too soon, even though state changed
+        assert!(!session.due(2, 0, start + Duration::from_millis(DEFAULT_PUSH_INTERVAL_MS - 1)));
+        assert!(session.due(2, 0, start + Duration::from_millis(DEFAULT_PUSH_INTERVAL_MS)));
+
+        session.handle(r#"{"type":"configure","interval_ms":999999}"#);
+        assert!(!session.due(2, 0, start + Duration::from_millis(MAX_PUSH_INTERVAL_MS - 1)));
+        assert!(session.due(2, 0, start + Duration::from_millis(MAX_PUSH_INTERVAL_MS)));
+
+        let replies = session.handle(r#"{"type":"subscribe","topics":["state","chat"]}"#);
+        assert!(matches!(&replies[0].body, ServerBody::Error { .. }));
+        assert!(matches!(&replies[1].body, ServerBody::Subscribed { topics } if topics == &[Topic::State]));
+    }
+}
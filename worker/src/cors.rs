@@ -0,0 +1,35 @@
+// Cross-origin policy for the browser-facing APIs (worker and client)
+//
+// The ALLOWED_ORIGINS policy and its CORS layer live in camhack-web, shared with
+// the master, and are re-exported here.
+//
+// Browsers don't apply CORS to WebSockets: any page can open one and the browser
+// sends its cookies along. So `check_websocket_origin` refuses an upgrade whose
+// Origin the policy doesn't allow. Requests without an Origin (bots, curl, other
+// workers) are never affected by any of this.
+
+pub use camhack_web::cors::{AllowedOrigins, CorsPolicy};
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+/// Middleware: refuse WebSocket upgrades from browser origins the policy doesn't allow (403)
+pub async fn check_websocket_origin(State(policy): State<Arc<CorsPolicy>>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let upgrade = headers
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    if upgrade {
+        if let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) {
+            if !policy.allows(origin) {
+                eprintln!("[CORS] Refused WebSocket upgrade from origin {}", origin);
+                return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+            }
+        }
+    }
+    next.run(request).await
+}
@@ -0,0 +1,319 @@
+// End-of-game awards - superlatives for the closing ceremony screen
+//
+// Everything is read back out of the committed log by replaying it, so every node
+// computes the same awards and nothing new has to be committed:
+//
+//   fastest_capture  shortest time from a player's first node targeting a hex to capturing it
+//   most_bandwidth   most flood traffic landing on enemy nodes a player's nodes were attacking
+//   longest_held     longest a captured (non-capital) node stayed with the player who took it
+//   comeback         biggest climb in node count from a low point after losing nodes
+//
+// Bandwidth isn't in the log any more (see raft::metrics_view): it comes from
+// committed NodeMetricsReports in older logs plus whatever metrics history this node
+// has kept, so `most_bandwidth` covers the last few minutes on a modern game and can
+// differ a little between nodes. Nothing after the game is over counts.
+
+use super::events::{AttackTarget, FactEvent, GameEvent, NodeCoord, NodeType, TelemetryEvent};
+use super::history::{MetricsSample, HISTORY_INTERVAL_SECS};
+use super::state::{GameState, StateChange};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One superlative and who earned it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Award {
+    pub player_id: u64,
+    pub name: String,
+    /// The hex it was earned on, for awards about one node
+    pub coord: Option<NodeCoord>,
+    /// Seconds, bytes or nodes, depending on the award
+    pub value: u64,
+    /// Ready to show: "captured 3,-1 in 12s"
+    pub detail: String,
+}
+
+/// Every award of a game; an award nobody qualified for is None
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Awards {
+    /// Whether the game is over - before then these are standings so far
+    pub game_over: bool,
+    /// `value` in seconds
+    pub fastest_capture: Option<Award>,
+    /// `value` in bytes
+    pub most_bandwidth: Option<Award>,
+    /// `value` in seconds
+    pub longest_held: Option<Award>,
+    /// `value` in nodes regained
+    pub comeback: Option<Award>,
+}
+
+/// Best candidate so far for one award (the first to reach a value keeps it on a tie)
+#[derive(Default)]
+struct Best {
+    player_id: u64,
+    coord: Option<NodeCoord>,
+    value: u64,
+    detail: String,
+    set: bool,
+}
+
+impl Best {
+    fn offer(&mut self, better: impl Fn(u64, u64) -> bool, player_id: u64, coord: Option<NodeCoord>, value: u64, detail: impl FnOnce() -> String) {
+        if !self.set || better(value, self.value) {
+            *self = Best { player_id, coord, value, detail: detail(), set: true };
+        }
+    }
+
+    fn award(self, state: &GameState) -> Option<Award> {
+        self.set.then(|| Award {
+            player_id: self.player_id,
+            name: state.players.get(&self.player_id).map(|p| p.name.clone()).unwrap_or_default(),
+            coord: self.coord,
+            value: self.value,
+            detail: self.detail,
+        })
+    }
+}
+
+/// A player's node count over the game, for the comeback
+#[derive(Default)]
+struct Standing {
+    peak: u64,
+    /// Lowest count since dropping below the peak
+    low: Option<u64>,
+}
+
+impl Awards {
+    /// Replay `events` (at their leader stamps `event_times`) and work out the awards
+    /// `samples` are bandwidth samples kept outside the log, by node.
+    pub fn compute(events: &[GameEvent], event_times: &[u64], samples: &[(NodeCoord, MetricsSample)]) -> Self {
+        let mut samples = samples.to_vec();
+        samples.sort_by_key(|(coord, sample)| (sample.timestamp, *coord));
+        let mut samples = samples.into_iter().peekable();
+
+        let mut state = GameState::new();
+        let mut fastest = Best::default();
+        let mut longest = Best::default();
+        let mut comeback = Best::default();
+        // (target, attacking player) -> when that player first aimed a node at it
+        let mut attacked_since: BTreeMap<(NodeCoord, u64), u64> = BTreeMap::new();
+        // Captured nodes -> (owner, since)
+        let mut held_since: BTreeMap<NodeCoord, (u64, u64)> = BTreeMap::new();
+        let mut bandwidth = Bandwidth::default();
+        let mut standings: BTreeMap<u64, Standing> = BTreeMap::new();
+
+        for (position, event) in events.iter().enumerate() {
+            let now = event_times.get(position).copied().unwrap_or(0).max(state.clock);
+            while let Some((coord, sample)) = samples.next_if(|(_, s)| s.timestamp < now) {
+                bandwidth.credit(&state, coord, sample.bandwidth_in, sample.timestamp);
+            }
+            if let GameEvent::Telemetry(TelemetryEvent::NodeMetricsReport { node_coord, bandwidth_in, .. }) = event {
+                bandwidth.credit(&state, *node_coord, *bandwidth_in, now);
+            }
+
+            let captured = matches!(event, GameEvent::Fact(FactEvent::NodeCaptured { .. }));
+            let changes = state.process_committed(event.clone(), position as u64 + 1, event_times.get(position).copied().unwrap_or(0));
+            let now = now.max(state.clock);
+            let mut ownership_changed = false;
+            for change in changes {
+                match change {
+                    StateChange::NodeTargetChanged { coord, target: Some(AttackTarget::Coordinate(target)) } => {
+                        if let Some(attacker) = state.nodes.get(&coord) {
+                            attacked_since.entry((target, attacker.owner_id)).or_insert(now);
+                        }
+                    }
+                    StateChange::NodeOwnerChanged { coord, new_owner_id, .. } => {
+                        ownership_changed = true;
+                        if let Some((owner, since)) = held_since.remove(&coord) {
+                            longest.offer(|a, b| a > b, owner, Some(coord), now - since, || format!("held {} for {}s", coord, now - since));
+                        }
+                        if captured && new_owner_id != 0 {
+                            if let Some(since) = attacked_since.get(&(coord, new_owner_id)) {
+                                let secs = now - since;
+                                fastest.offer(|a, b| a < b, new_owner_id, Some(coord), secs, || format!("captured {} in {}s", coord, secs));
+                            }
+                            if state.nodes.get(&coord).is_some_and(|n| n.node_type == NodeType::Regular) {
+                                held_since.insert(coord, (new_owner_id, now));
+                            }
+                        }
+                        attacked_since.retain(|(target, _), _| *target != coord);
+                    }
+                    StateChange::NodeRemoved { coord } => {
+                        ownership_changed = true;
+                        held_since.remove(&coord);
+                        attacked_since.retain(|(target, _), _| *target != coord);
+                    }
+                    StateChange::NodeAdded { .. } | StateChange::PlayerAdded { .. } => ownership_changed = true,
+                    _ => {}
+                }
+            }
+
+            if ownership_changed {
+                for player_id in state.players.keys() {
+                    let count = state.nodes.values().filter(|n| n.owner_id == *player_id).count() as u64;
+                    let standing = standings.entry(*player_id).or_default();
+                    standing.peak = standing.peak.max(count);
+                    if count < standing.peak {
+                        standing.low = Some(standing.low.map_or(count, |low| low.min(count)));
+                    }
+                    if let Some(low) = standing.low.filter(|low| count > *low) {
+                        comeback.offer(|a, b| a > b, *player_id, None, count - low, || format!("climbed from {} to {} nodes", low, count));
+                    }
+                }
+            }
+
+            if state.game_over {
+                break;
+            }
+        }
+        if !state.game_over {
+            for (coord, sample) in samples {
+                bandwidth.credit(&state, coord, sample.bandwidth_in, sample.timestamp);
+            }
+        }
+
+        // Nodes still held count up to the end
+        let end = state.clock;
+        for (coord, (owner, since)) in held_since {
+            let secs = end.saturating_sub(since);
+            longest.offer(|a, b| a > b, owner, Some(coord), secs, || format!("held {} for {}s", coord, secs));
+        }
+
+        Self {
+            game_over: state.game_over,
+            fastest_capture: fastest.award(&state),
+            most_bandwidth: bandwidth.award(&state),
+            longest_held: longest.award(&state),
+            comeback: comeback.award(&state),
+        }
+    }
+}
+
+/// Bytes delivered per player, credited from samples of the nodes they attack
+#[derive(Default)]
+struct Bandwidth {
+    delivered: BTreeMap<u64, u64>,
+    /// Time of each node's previous sample, to know how long a sample stands for
+    last_sample: BTreeMap<NodeCoord, u64>,
+}
+
+impl Bandwidth {
+    /// `bandwidth_in` (bytes/sec) arrived at `coord` at `at`: split it between the enemy nodes attacking it
+    /// A sample stands for the time since the node's previous one, at most HISTORY_INTERVAL_SECS.
+    fn credit(&mut self, state: &GameState, coord: NodeCoord, bandwidth_in: u64, at: u64) {
+        let span = match self.last_sample.insert(coord, at) {
+            Some(previous) => at.saturating_sub(previous).min(HISTORY_INTERVAL_SECS),
+            None => HISTORY_INTERVAL_SECS,
+        };
+        let Some(target) = state.nodes.get(&coord) else {
+            return;
+        };
+        let attackers: Vec<u64> = state
+            .nodes
+            .values()
+            .filter(|n| n.current_target == Some(AttackTarget::Coordinate(coord)))
+            .map(|n| n.owner_id)
+            .filter(|owner| *owner != 0 && *owner != target.owner_id)
+            .collect();
+        if attackers.is_empty() {
+            return;
+        }
+        let share = bandwidth_in.saturating_mul(span) / attackers.len() as u64;
+        for owner in attackers {
+            *self.delivered.entry(owner).or_default() += share;
+        }
+    }
+
+    fn award(self, state: &GameState) -> Option<Award> {
+        let mut best = Best::default();
+        for (player_id, bytes) in self.delivered {
+            if bytes > 0 {
+                best.offer(|a, b| a > b, player_id, None, bytes, || format!("delivered {:.1} MB", bytes as f64 / 1_000_000.0));
+            }
+        }
+        best.award(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::CommandEvent;
+
+    fn target(from: NodeCoord, to: NodeCoord) -> GameEvent {
+        CommandEvent::SetNodeTarget { node_coord: from, target: Some(AttackTarget::Coordinate(to)), timestamp: 0 }.into()
+    }
+
+    fn capture(coord: NodeCoord, owner: u64) -> GameEvent {
+        FactEvent::NodeCaptured { node_coord: coord, new_owner_id: owner, timestamp: 0 }.into()
+    }
+
+    #[test]
+    fn test_awards_from_log() {
+        let (a, b, hex) = (NodeCoord::new(0, 0), NodeCoord::new(2, 0), NodeCoord::new(1, 0));
+        let events = vec![
+            CommandEvent::test_join(1, 0).into(),
+            CommandEvent::test_join(2, 2).into(),
+            FactEvent::NodeInitializationStarted { node_coord: hex, owner_id: 0, timestamp: 0 }.into(),
+            target(a, hex),
+            capture(hex, 1),
+            target(b, hex),
+            capture(hex, 2),
+            target(hex, a),
+            capture(a, 2),
+        ];
+        let times = [100, 100, 101, 110, 130, 140, 145, 150, 200];
+        // The hex, now player 2's, floods player 1's capital at 1 MB/s
+        let samples = [(a, MetricsSample {
+            timestamp: 160,
+            bandwidth_in: 1_000_000,
+            packet_loss: 0.5,
+            queued_attackers: 0,
+            cpu_utilization: None,
+            memory_pressure: None,
+        })];
+
+        let awards = Awards::compute(&events, &times, &samples);
+        assert!(awards.game_over);
+
+        let fastest = awards.fastest_capture.unwrap();
+        assert_eq!((fastest.player_id, fastest.coord, fastest.value), (2, Some(hex), 5));
+        assert_eq!(fastest.name, "p2");
+        assert_eq!(fastest.detail, "captured 1,0 in 5s");
+
+        // Player 1 held the hex 15s, player 2 from its capture to the end of the game
+        let longest = awards.longest_held.unwrap();
+        assert_eq!((longest.player_id, longest.value), (2, 55));
+
+        let bandwidth = awards.most_bandwidth.unwrap();
+        assert_eq!((bandwidth.player_id, bandwidth.value), (2, 5_000_000));
+
+        // Player 1 only ever lost ground
+        assert_eq!(awards.comeback, None);
+    }
+
+    #[test]
+    fn test_comeback() {
+        let (hex, other) = (NodeCoord::new(1, 0), NodeCoord::new(0, 1));
+        let events = vec![
+            CommandEvent::test_join(1, 0).into(),
+            CommandEvent::test_join(2, 3).into(),
+            FactEvent::NodeInitializationStarted { node_coord: hex, owner_id: 0, timestamp: 0 }.into(),
+            FactEvent::NodeInitializationStarted { node_coord: other, owner_id: 0, timestamp: 0 }.into(),
+            capture(hex, 1),
+            capture(other, 1),
+            capture(hex, 2),
+            capture(other, 2),
+            capture(hex, 1),
+            capture(other, 1),
+        ];
+        let times: Vec<u64> = (0..events.len() as u64).map(|i| 100 + i).collect();
+        let awards = Awards::compute(&events, &times, &[]);
+        assert!(!awards.game_over);
+        assert!(awards.most_bandwidth.is_none());
+
+        let comeback = awards.comeback.unwrap();
+        assert_eq!((comeback.player_id, comeback.value), (1, 2));
+        assert_eq!(comeback.detail, "climbed from 1 to 3 nodes");
+    }
+}
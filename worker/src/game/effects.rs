@@ -0,0 +1,218 @@
+// Leader-only side effects, driven by the committed log
+//
+// Spawning workers, final kills and shutdown happen outside the state machine, so
+// they can't just run wherever the triggering code happens to be. GameLogic commits
+// an EffectRequested fact instead; the leader's executor runs every requested effect
+// that has no committed EffectCompleted yet, then commits that acknowledgment.
+//
+// Completed effects are never redone, on any leader. An effect cut short by a leader
+// change has no acknowledgment, so the next leader runs it again - the remote calls
+// behind effects (master spawn, kill, final kill) must tolerate a repeat.
+
+use super::events::{Effect, FactEvent, GameEvent};
+use super::state::GameState;
+use crate::supervisor::Supervisor;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// What running an effect produced: facts to commit before its acknowledgment
+pub type EffectResult = anyhow::Result<Vec<GameEvent>>;
+
+/// A finished effect, ready to be committed in order
+#[derive(Debug)]
+pub struct EffectCompletion {
+    pub effect_id: u64,
+    /// Facts the effect produced, then its EffectCompleted
+    pub events: Vec<GameEvent>,
+}
+
+/// Runs pending effects on the leader, at most once per leadership term
+pub struct EffectsExecutor {
+    /// Effects started here and not yet acknowledged in committed state
+    started: HashSet<u64>,
+    running: HashMap<u64, JoinHandle<()>>,
+    done_tx: mpsc::UnboundedSender<(u64, EffectResult)>,
+    done_rx: mpsc::UnboundedReceiver<(u64, EffectResult)>,
+    /// Completions whose commit failed, retried before new ones
+    uncommitted: Vec<EffectCompletion>,
+}
+
+impl EffectsExecutor {
+    pub fn new() -> Self {
+        let (done_tx, done_rx) = mpsc::unbounded_channel();
+        Self {
+            started: HashSet::new(),
+            running: HashMap::new(),
+            done_tx,
+            done_rx,
+            uncommitted: Vec::new(),
+        }
+    }
+
+    /// Pending effects in committed state that aren't running here yet, oldest first
+    pub fn due(&mut self, game_state: &GameState) -> Vec<(u64, Effect)> {
+        // Acknowledged effects are done for good
+        let pending: HashSet<u64> = game_state.pending_effects().map(|(id, _)| id).collect();
+        self.started.retain(|id| pending.contains(id));
+
+        game_state
+            .pending_effects()
+            .filter(|(id, _)| !self.started.contains(id))
+            .map(|(id, effect)| (id, effect.clone()))
+            .collect()
+    }
+
+    /// Run effect `effect_id` in the background
+    pub fn start<Fut>(&mut self, effect_id: u64, run: Fut)
+    where
+        Fut: Future<Output = EffectResult> + Send + 'static,
+    {
+        self.started.insert(effect_id);
+        let done_tx = self.done_tx.clone();
+        let handle = Supervisor::global().spawn_transient("effect", async move {
+            let _ = done_tx.send((effect_id, run.await));
+            Ok(())
+        });
+        self.running.insert(effect_id, handle);
+    }
+
+    /// Effects that finished since the last call, as events to commit
+    pub fn take_completed(&mut self) -> Vec<EffectCompletion> {
+        let mut completions = std::mem::take(&mut self.uncommitted);
+
+        while let Ok((effect_id, result)) = self.done_rx.try_recv() {
+            self.running.remove(&effect_id);
+            let (mut events, error) = match result {
+                Ok(facts) => (facts, None),
+                Err(e) => {
+                    eprintln!("[Effects] Effect {} failed: {:#}", effect_id, e);
+                    (Vec::new(), Some(format!("{:#}", e)))
+                }
+            };
+            events.push(GameEvent::Fact(FactEvent::EffectCompleted {
+                effect_id,
+                error,
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            }));
+            completions.push(EffectCompletion { effect_id, events });
+        }
+
+        completions
+    }
+
+    /// Keep a completion whose commit failed after its first `committed` events went
+    /// through, to retry the rest next tick (committed facts mustn't apply twice)
+    pub fn retry(&mut self, mut completion: EffectCompletion, committed: usize) {
+        completion.events.drain(..committed.min(completion.events.len()));
+        self.uncommitted.push(completion);
+    }
+
+    /// Leadership lost: stop everything, the new leader picks up unacknowledged effects
+    pub fn step_down(&mut self) {
+        if !self.started.is_empty() {
+            println!("[Effects] Stepping down, abandoning {} unacknowledged effect(s)", self.started.len());
+        }
+        for (_, handle) in self.running.drain() {
+            handle.abort();
+        }
+        self.started.clear();
+        self.uncommitted.clear();
+        while self.done_rx.try_recv().is_ok() {}
+    }
+
+    /// Effects running here right now
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+}
+
+impl Default for EffectsExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::NodeCoord;
+    use std::time::Duration;
+
+    fn request(state: &mut GameState, effect: Effect, index: u64) {
+        state.process_event(FactEvent::EffectRequested { effect, timestamp: 0 }.into(), index);
+    }
+
+    async fn wait_completed(executor: &mut EffectsExecutor) -> Vec<EffectCompletion> {
+        for _ in 0..500 {
+            let completed = executor.take_completed();
+            if !completed.is_empty() {
+                return completed;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        panic!("effect never finished");
+    }
+
+    #[tokio::test]
+    async fn test_runs_once_until_acknowledged() {
+        let mut state = GameState::new();
+        let mut executor = EffectsExecutor::new();
+        request(&mut state, Effect::SpawnNode { node_coord: NodeCoord::new(1, 0) }, 5);
+
+        let due = executor.due(&state);
+        assert_eq!(due.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![5]);
+        let fact = GameEvent::Fact(FactEvent::OwnerIdle { player_id: 1, neutralize: false, timestamp: 0 });
+        executor.start(5, async move { Ok(vec![fact]) });
+
+        // Started but not acknowledged - not started again
+        assert!(executor.due(&state).is_empty());
+
+        let completed = wait_completed(&mut executor).await;
+        assert_eq!(completed.len(), 1);
+        assert!(matches!(
+            completed[0].events.as_slice(),
+            [GameEvent::Fact(FactEvent::OwnerIdle { .. }), GameEvent::Fact(FactEvent::EffectCompleted { effect_id: 5, error: None, .. })]
+        ));
+
+        // The fact committed but the acknowledgment didn't: only the acknowledgment
+        // comes back, and the effect doesn't rerun
+        let completion = completed.into_iter().next().unwrap();
+        executor.retry(completion, 1);
+        assert!(executor.due(&state).is_empty());
+        let completion = executor.take_completed().pop().unwrap();
+        assert!(matches!(completion.events.as_slice(), [GameEvent::Fact(FactEvent::EffectCompleted { effect_id: 5, .. })]));
+
+        for event in completion.events {
+            state.process_event(event, 6);
+        }
+        assert!(executor.due(&state).is_empty());
+        assert!(executor.started.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_new_leader_resumes_unacknowledged() {
+        let mut state = GameState::new();
+        request(&mut state, Effect::ShutdownGame, 3);
+
+        let mut old_leader = EffectsExecutor::new();
+        old_leader.start(3, std::future::pending());
+        assert_eq!(old_leader.running(), 1);
+        old_leader.step_down();
+        assert_eq!(old_leader.running(), 0);
+
+        // No acknowledgment committed, so it's due again (here or on the new leader)
+        let mut new_leader = EffectsExecutor::new();
+        assert_eq!(new_leader.due(&state), vec![(3, Effect::ShutdownGame)]);
+        assert_eq!(old_leader.due(&state), vec![(3, Effect::ShutdownGame)]);
+
+        new_leader.start(3, async { anyhow::bail!("master unreachable") });
+        let completed = wait_completed(&mut new_leader).await;
+        assert!(matches!(
+            completed[0].events.as_slice(),
+            [GameEvent::Fact(FactEvent::EffectCompleted { effect_id: 3, error: Some(_), .. })]
+        ));
+    }
+}
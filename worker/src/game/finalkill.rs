@@ -1,142 +1,241 @@
+// Final kill - flooding a beaten player's client over WebSockets
+//
+// Each of the attacking player's nodes gets one reverse connection to the client's
+// /finalkill endpoint. Instead of writing frames as fast as the sink takes them (and
+// piling them up in local buffers while the client feels nothing), every connection
+// paces itself: each PACE_INTERVAL it sends the Pacer's budget and flushes, speeding
+// up while flushes drain quickly and halving when they back up. The socket's write
+// buffer is capped (MAX_BUFFERED_BYTES), so a stalled client costs a bounded amount
+// of memory. When the flood ends, the bytes each connection got flushed are summed
+// into a FinalKillReport, which the leader commits.
+//
+// On the dry-run transport no connection is opened: each attacker node counts what
+// a connection at the dry-run flooder rate would have delivered.
+//
+// The client's /finalkill is otherwise an open door onto a player's laptop, so it
+// only lets a flood in while committed state says one is under way against that
+// player (an uncompleted FinalKill effect; see `expected_final_kill`). Only the
+// captor's attacker nodes and the leader, which runs the effect, may connect. There
+// is one connection per attacker node at most (FINALKILL_MAX_CONNECTIONS at most),
+// and past FINALKILL_MAX_BYTES in total every connection is closed early
+// (ReceiveBudget).
+
 use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
-use std::collections::HashMap;
-use std::sync::Arc;
+use futures_util::SinkExt;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::RwLock;
-use tokio::time::{sleep, Instant};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio::sync::{broadcast, RwLock};
+use tokio::time::{sleep, sleep_until, timeout, Instant};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::connect_async_with_config;
+
+use super::events::{Effect, NodeCoord};
+use super::state::GameState;
+use super::transport::{DryRunConfig, TransportMode};
+use crate::supervisor::Supervisor;
+
+/// How long a final kill floods the client (seconds of game time - a sped-up game
+/// scales it with GameState::scaled_secs)
+pub const FINAL_KILL_SECS: u64 = 10;
+
+/// Size of one flood frame
+pub const FRAME_BYTES: usize = 8 * 1024;
+
+/// How often a connection sends a batch
+pub const PACE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Per-connection send rate to start at, and its bounds (bytes/sec)
+pub const INITIAL_RATE: u64 = 1_000_000;
+pub const MIN_RATE: u64 = 64 * 1024;
+pub const MAX_RATE: u64 = 200_000_000;
+
+/// Most unflushed bytes a connection may hold: the biggest batch, and the socket's write buffer cap
+pub const MAX_BUFFERED_BYTES: usize = 512 * 1024;
+
+/// A batch that hasn't drained by then means the client stopped reading
+const STALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Most flood connections a client takes at once, however many nodes the captor has
+pub const DEFAULT_RECEIVE_MAX_CONNECTIONS: usize = 128;
 
-use super::events::NodeCoord;
+/// Most bytes a client reads from one final kill before closing every connection
+pub const DEFAULT_RECEIVE_MAX_BYTES: u64 = 512 * 1024 * 1024;
 
-/// Manages 10-second final kill attacks on client nodes
+/// Largest frame or message the client reads (the flood's are FRAME_BYTES)
+pub const RECEIVE_MAX_FRAME_BYTES: usize = 4 * FRAME_BYTES;
+
+/// Adapts one connection's send rate to how fast its batches drain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacer {
+    /// Bytes per second
+    pub rate: u64,
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Self { rate: INITIAL_RATE }
+    }
+}
+
+impl Pacer {
+    /// Whole frames to send this interval (at least one, at most MAX_BUFFERED_BYTES)
+    pub fn frames(&self) -> usize {
+        let budget = self.rate as u128 * PACE_INTERVAL.as_millis() / 1000;
+        (budget as usize).clamp(FRAME_BYTES, MAX_BUFFERED_BYTES) / FRAME_BYTES
+    }
+
+    /// The last batch took `took` to flush: a quarter faster if it drained in half
+    /// an interval, half as fast if it took longer than one
+    pub fn flushed(&mut self, took: Duration) {
+        if took <= PACE_INTERVAL / 2 {
+            self.rate = (self.rate + self.rate / 4).min(MAX_RATE);
+        } else if took > PACE_INTERVAL {
+            self.backed_up();
+        }
+    }
+
+    /// The batch didn't drain, or the write buffer is full
+    pub fn backed_up(&mut self) {
+        self.rate = (self.rate / 2).max(MIN_RATE);
+    }
+}
+
+/// What one connection of a final kill achieved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionReport {
+    pub node_coord: NodeCoord,
+    pub connected: bool,
+    /// Bytes flushed to the client
+    pub bytes_sent: u64,
+    /// Rate the pacer had settled on when the flood ended (bytes/sec)
+    pub final_rate: u64,
+    pub error: Option<String>,
+}
+
+/// The whole final kill, summed over its connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalKillReport {
+    pub player_id: u64,
+    pub bytes_delivered: u64,
+    pub duration: Duration,
+    pub connections: Vec<ConnectionReport>,
+}
+
+impl FinalKillReport {
+    pub fn connected(&self) -> usize {
+        self.connections.iter().filter(|c| c.connected).count()
+    }
+}
+
+/// Manages final kill attacks on client nodes
 /// Uses WebSocket reverse connections (attacker connects to client)
 pub struct FinalKillManager {
     /// Active final kill attacks (player_id -> attack handle)
     active_kills: Arc<RwLock<HashMap<u64, FinalKillHandle>>>,
+    /// Real WebSockets, or simulated connections on the dry-run transport
+    transport: TransportMode,
 }
 
 /// Handle for an active final kill attack
 struct FinalKillHandle {
-    player_id: u64,
-    client_ip: String,
-    start_time: Instant,
-    stop_signal: tokio::sync::broadcast::Sender<()>,
+    stop_signal: broadcast::Sender<()>,
 }
 
 impl FinalKillManager {
     pub fn new() -> Self {
+        Self::with_transport(TransportMode::Udp)
+    }
+
+    /// A manager flooding over `transport` (the NetworkManager's)
+    pub fn with_transport(transport: TransportMode) -> Self {
         Self {
             active_kills: Arc::new(RwLock::new(HashMap::new())),
+            transport,
         }
     }
 
-    /// Start a 10-second final kill attack on a player's client
-    /// all_attacker_nodes: all nodes owned by the attacking player
+    /// Flood a player's client for `duration` (or until `stop_final_kill`) and report what got through
+    /// all_attacker_nodes: all nodes owned by the attacking player, one connection each
+    /// None if this player is already being attacked.
     pub async fn start_final_kill(
         &self,
         player_id: u64,
         client_ip: String,
         all_attacker_nodes: Vec<NodeCoord>,
-    ) -> Result<()> {
-        // Check if already attacking this player
-        let kills = self.active_kills.read().await;
-        if kills.contains_key(&player_id) {
-            println!("[FinalKill] Already attacking player {}, skipping", player_id);
-            return Ok(());
+        duration: Duration,
+    ) -> Result<Option<FinalKillReport>> {
+        let (stop_tx, _) = broadcast::channel::<()>(1);
+        {
+            let mut kills = self.active_kills.write().await;
+            if kills.contains_key(&player_id) {
+                println!("[FinalKill] Already attacking player {}, skipping", player_id);
+                return Ok(None);
+            }
+            kills.insert(player_id, FinalKillHandle { stop_signal: stop_tx.clone() });
         }
-        drop(kills);
-
-        let (stop_tx, _) = tokio::sync::broadcast::channel::<()>(1);
-        let handle = FinalKillHandle {
-            player_id,
-            client_ip: client_ip.clone(),
-            start_time: Instant::now(),
-            stop_signal: stop_tx.clone(),
-        };
-
-        // Store handle
-        let mut kills = self.active_kills.write().await;
-        kills.insert(player_id, handle);
-        drop(kills);
 
         println!(
-            "[FinalKill] Starting 10-second attack on player {} at {}",
-            player_id, client_ip
+            "[FinalKill] Starting {:?} attack on player {} at {}",
+            duration, player_id, client_ip
         );
+        let started = Instant::now();
+        let ws_url = crate::tls::peer_ws_url(&format!("{}:8080", client_ip), "/finalkill");
 
-        // Spawn WebSocket connections from each attacker node to the client
+        // One paced connection from each attacker node to the client
         let num_connections = all_attacker_nodes.len();
-        for (idx, node_coord) in all_attacker_nodes.into_iter().enumerate() {
-            let client_ip_clone = client_ip.clone();
-            let mut stop_rx = stop_tx.subscribe();
-
-            tokio::spawn(async move {
-                let ws_url = format!("ws://{}:8080/finalkill", client_ip_clone);
-                println!(
-                    "[FinalKill] Node {:?} ({}/{}) connecting to {}",
-                    node_coord,
-                    idx + 1,
-                    num_connections,
-                    ws_url
-                );
-
-                // Try to connect
-                match connect_async(&ws_url).await {
-                    Ok((mut ws_stream, _)) => {
-                        println!("[FinalKill] Node {:?} connected, flooding...", node_coord);
-
-                        // Prepare 1KB flood data
-                        let flood_data = vec![0u8; 1024];
-                        let flood_msg = Message::Binary(flood_data);
-
-                        loop {
-                            tokio::select! {
-                                // Stop signal received
-                                _ = stop_rx.recv() => {
-                                    println!("[FinalKill] Node {:?} stopping attack", node_coord);
-                                    let _ = ws_stream.close(None).await;
-                                    break;
-                                }
-                                // Send flood data
-                                result = ws_stream.send(flood_msg.clone()) => {
-                                    match result {
-                                        Ok(_) => {
-                                            // Continue flooding with no delay (true flood)
-                                        }
-                                        Err(e) => {
-                                            eprintln!("[FinalKill] Node {:?} send error: {}", node_coord, e);
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "[FinalKill] Node {:?} failed to connect to {}: {}",
-                            node_coord, ws_url, e
-                        );
-                    }
+        let tasks: Vec<_> = all_attacker_nodes
+            .into_iter()
+            .enumerate()
+            .map(|(idx, node_coord)| {
+                let (ws_url, stop_rx) = (ws_url.clone(), stop_tx.subscribe());
+                let supervisor = Supervisor::global();
+                if let TransportMode::DryRun(config) = self.transport {
+                    return supervisor.spawn_scoped("final_kill_connection", simulate_connection(config, node_coord, stop_rx));
                 }
-            });
-        }
+                println!("[FinalKill] Node {:?} ({}/{}) connecting to {}", node_coord, idx + 1, num_connections, ws_url);
+                supervisor.spawn_scoped("final_kill_connection", flood_connection(ws_url, node_coord, stop_rx))
+            })
+            .collect();
 
-        // Spawn timer task to stop after 10 seconds
+        // Stop after the duration, in its own task so the flood ends even if nobody
+        // waits for the report (the leader stepped down and dropped the effect)
         let active_kills = self.active_kills.clone();
-        tokio::spawn(async move {
-            sleep(Duration::from_secs(10)).await;
-            println!("[FinalKill] 10 seconds elapsed, stopping attack on player {}", player_id);
-
-            // Send stop signal and remove handle
-            let mut kills = active_kills.write().await;
-            if let Some(handle) = kills.remove(&player_id) {
+        Supervisor::global().spawn_transient("final_kill_timer", async move {
+            sleep(duration).await;
+            if let Some(handle) = active_kills.write().await.remove(&player_id) {
+                println!("[FinalKill] {:?} elapsed, stopping attack on player {}", duration, player_id);
                 let _ = handle.stop_signal.send(());
             }
+            Ok(())
         });
 
-        Ok(())
+        // Every connection ends on the stop signal (or its own failure, or shutdown)
+        let mut connections = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            match task.await {
+                Ok(Some(report)) => connections.push(report),
+                Ok(None) => {}
+                Err(e) => eprintln!("[FinalKill] Connection task failed: {}", e),
+            }
+        }
+        let report = FinalKillReport {
+            player_id,
+            bytes_delivered: connections.iter().map(|c| c.bytes_sent).sum(),
+            duration: started.elapsed(),
+            connections,
+        };
+        println!(
+            "[FinalKill] Delivered {} bytes to player {} in {:?} over {}/{} connections",
+            report.bytes_delivered,
+            player_id,
+            report.duration,
+            report.connected(),
+            num_connections
+        );
+        Ok(Some(report))
     }
 
     /// Stop an active final kill attack early (e.g., if player already eliminated)
@@ -160,3 +259,349 @@ impl Default for FinalKillManager {
         Self::new()
     }
 }
+
+/// Flood `ws_url` in paced batches until `stop_rx` fires or the connection fails
+async fn flood_connection(ws_url: String, node_coord: NodeCoord, mut stop_rx: broadcast::Receiver<()>) -> ConnectionReport {
+    let mut pacer = Pacer::default();
+    let mut report = ConnectionReport { node_coord, connected: false, bytes_sent: 0, final_rate: pacer.rate, error: None };
+
+    // A bounded write buffer: feeding past it fails instead of growing
+    let config = WebSocketConfig {
+        write_buffer_size: FRAME_BYTES,
+        max_write_buffer_size: MAX_BUFFERED_BYTES + FRAME_BYTES,
+        ..Default::default()
+    };
+    let mut ws_stream = tokio::select! {
+        _ = stop_rx.recv() => return report,
+        result = connect_async_with_config(&ws_url, Some(config), true) => match result {
+            Ok((ws_stream, _)) => ws_stream,
+            Err(e) => {
+                eprintln!("[FinalKill] Node {:?} failed to connect to {}: {}", node_coord, ws_url, e);
+                report.error = Some(e.to_string());
+                return report;
+            }
+        },
+    };
+    report.connected = true;
+    println!("[FinalKill] Node {:?} connected, flooding...", node_coord);
+
+    let frame = Message::Binary(vec![0u8; FRAME_BYTES]);
+    let mut next_batch = Instant::now();
+    loop {
+        let frames = pacer.frames();
+        let batch_started = Instant::now();
+        let batch = async {
+            for _ in 0..frames {
+                ws_stream.feed(frame.clone()).await?;
+            }
+            ws_stream.flush().await
+        };
+        tokio::select! {
+            _ = stop_rx.recv() => break,
+            result = timeout(STALL_TIMEOUT, batch) => match result {
+                Ok(Ok(())) => {
+                    report.bytes_sent += (frames * FRAME_BYTES) as u64;
+                    pacer.flushed(batch_started.elapsed());
+                }
+                // The client isn't keeping up - slow down, keep what's queued
+                Ok(Err(WsError::WriteBufferFull(_))) | Err(_) => pacer.backed_up(),
+                Ok(Err(e)) => {
+                    eprintln!("[FinalKill] Node {:?} send error: {}", node_coord, e);
+                    report.error = Some(e.to_string());
+                    break;
+                }
+            },
+        }
+
+        // Next batch on the interval grid; a late batch doesn't make up for lost time
+        next_batch = (next_batch + PACE_INTERVAL).max(Instant::now());
+        tokio::select! {
+            _ = stop_rx.recv() => break,
+            _ = sleep_until(next_batch) => {}
+        }
+    }
+
+    println!("[FinalKill] Node {:?} stopping attack after {} bytes", node_coord, report.bytes_sent);
+    report.final_rate = pacer.rate;
+    let _ = timeout(STALL_TIMEOUT, ws_stream.close(None)).await;
+    report
+}
+
+/// Dry-run stand-in for `flood_connection`: counts what `config`'s flooder rate
+/// would have delivered each PACE_INTERVAL until `stop_rx` fires
+async fn simulate_connection(config: DryRunConfig, node_coord: NodeCoord, mut stop_rx: broadcast::Receiver<()>) -> ConnectionReport {
+    let rate = config.flooder_bytes_per_sec;
+    let mut report = ConnectionReport { node_coord, connected: true, bytes_sent: 0, final_rate: rate, error: None };
+    let per_interval = (rate as f64 * PACE_INTERVAL.as_secs_f64()) as u64;
+    let mut interval = tokio::time::interval(PACE_INTERVAL);
+    interval.tick().await;
+    loop {
+        tokio::select! {
+            _ = stop_rx.recv() => break,
+            _ = interval.tick() => report.bytes_sent += per_interval,
+        }
+    }
+    println!("[FinalKill] Node {:?} stopping dry-run attack after {} bytes", node_coord, report.bytes_sent);
+    report
+}
+
+/// The final kill committed state has under way against a player
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedFinalKill {
+    /// Log index of the FinalKill effect
+    pub effect_id: u64,
+    /// Hosts allowed to connect: the captor's attacker nodes, then the leader
+    pub sources: Vec<String>,
+    /// One per attacker node
+    pub connections: usize,
+}
+
+/// The uncompleted FinalKill against `player_id`, if there is one
+/// `leader_host` is the node running the effect, which opens the connections.
+/// Attacker nodes the captor has since lost aren't allowed to connect.
+pub fn expected_final_kill(game_state: &GameState, player_id: u64, leader_host: Option<&str>) -> Option<ExpectedFinalKill> {
+    let (effect_id, attacker_nodes) = game_state.effects.iter().find_map(|(id, record)| match &record.effect {
+        Effect::FinalKill { player_id: target, attacker_nodes, .. } if *target == player_id && record.completion.is_none() => {
+            Some((*id, attacker_nodes))
+        }
+        _ => None,
+    })?;
+    let capital = game_state.players.get(&player_id)?.capital_coord;
+    let captor = game_state.nodes.get(&capital)?.owner_id;
+    let mut sources: Vec<String> = attacker_nodes
+        .iter()
+        .filter(|coord| game_state.nodes.get(coord).is_some_and(|n| n.owner_id == captor))
+        .filter_map(|coord| game_state.node_ips.get(coord).cloned())
+        .collect();
+    sources.extend(leader_host.map(str::to_string));
+    Some(ExpectedFinalKill { effect_id, sources, connections: attacker_nodes.len() })
+}
+
+/// Addresses of `hosts` (IPs as they are, names looked up)
+pub async fn resolve_sources(hosts: &[String]) -> HashSet<IpAddr> {
+    let mut addrs = HashSet::new();
+    for host in hosts {
+        match host.parse::<IpAddr>() {
+            Ok(ip) => {
+                addrs.insert(ip);
+            }
+            Err(_) => match tokio::net::lookup_host((host.as_str(), 0)).await {
+                Ok(resolved) => addrs.extend(resolved.map(|addr| addr.ip())),
+                Err(e) => eprintln!("[FinalKill] Can't resolve allowed source {}: {}", host, e),
+            },
+        }
+    }
+    addrs
+}
+
+/// Why a flood connection was turned away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiveRefusal {
+    /// As many connections as the final kill has attacker nodes (or the cap) are open
+    TooManyConnections,
+    /// The final kill's byte budget is spent
+    BudgetSpent,
+}
+
+/// What a client lets in from final kills: open connections and bytes read, per kill
+#[derive(Debug, Clone)]
+pub struct ReceiveBudget {
+    max_connections: usize,
+    max_bytes: u64,
+    usage: Arc<Mutex<ReceiveUsage>>,
+}
+
+#[derive(Debug, Default)]
+struct ReceiveUsage {
+    effect_id: u64,
+    open: usize,
+    bytes: u64,
+}
+
+impl ReceiveBudget {
+    pub fn new(max_connections: usize, max_bytes: u64) -> Self {
+        Self { max_connections, max_bytes, usage: Arc::new(Mutex::new(ReceiveUsage::default())) }
+    }
+
+    /// FINALKILL_MAX_CONNECTIONS (default: 128), FINALKILL_MAX_BYTES (default: 512 MiB)
+    pub fn from_env() -> Self {
+        let env = |name: &str| std::env::var(name).ok().and_then(|s| s.parse::<u64>().ok()).filter(|n| *n > 0);
+        Self::new(
+            env("FINALKILL_MAX_CONNECTIONS").map_or(DEFAULT_RECEIVE_MAX_CONNECTIONS, |n| n as usize),
+            env("FINALKILL_MAX_BYTES").unwrap_or(DEFAULT_RECEIVE_MAX_BYTES),
+        )
+    }
+
+    /// Take a connection for `kill`, released when the permit drops
+    pub fn admit(&self, kill: &ExpectedFinalKill) -> Result<ReceivePermit, ReceiveRefusal> {
+        let mut usage = self.usage.lock().unwrap();
+        if usage.effect_id != kill.effect_id {
+            // A new final kill (a revived player lost again) starts a new budget
+            *usage = ReceiveUsage { effect_id: kill.effect_id, open: usage.open, bytes: 0 };
+        }
+        if usage.bytes >= self.max_bytes {
+            return Err(ReceiveRefusal::BudgetSpent);
+        }
+        if usage.open >= kill.connections.min(self.max_connections) {
+            return Err(ReceiveRefusal::TooManyConnections);
+        }
+        usage.open += 1;
+        Ok(ReceivePermit { budget: self.clone(), effect_id: kill.effect_id })
+    }
+}
+
+/// One admitted flood connection
+#[derive(Debug)]
+pub struct ReceivePermit {
+    budget: ReceiveBudget,
+    effect_id: u64,
+}
+
+impl ReceivePermit {
+    /// Count `bytes` read; false once the kill's budget is spent and the connection should close
+    pub fn received(&self, bytes: usize) -> bool {
+        let mut usage = self.budget.usage.lock().unwrap();
+        if usage.effect_id == self.effect_id {
+            usage.bytes = usage.bytes.saturating_add(bytes as u64);
+        }
+        usage.effect_id == self.effect_id && usage.bytes < self.budget.max_bytes
+    }
+}
+
+impl Drop for ReceivePermit {
+    fn drop(&mut self) {
+        let mut usage = self.budget.usage.lock().unwrap();
+        usage.open = usage.open.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[test]
+    fn test_pacer_adapts() {
+        let mut pacer = Pacer::default();
+        assert_eq!(pacer.frames(), (INITIAL_RATE as usize / 20) / FRAME_BYTES);
+
+        pacer.flushed(Duration::from_millis(5));
+        assert_eq!(pacer.rate, INITIAL_RATE * 5 / 4);
+        // In between: hold
+        pacer.flushed(Duration::from_millis(40));
+        assert_eq!(pacer.rate, INITIAL_RATE * 5 / 4);
+        pacer.flushed(Duration::from_millis(80));
+        assert_eq!(pacer.rate, INITIAL_RATE * 5 / 8);
+
+        for _ in 0..100 {
+            pacer.backed_up();
+        }
+        assert_eq!((pacer.rate, pacer.frames()), (MIN_RATE, 1));
+        for _ in 0..200 {
+            pacer.flushed(Duration::ZERO);
+        }
+        assert_eq!((pacer.rate, pacer.frames()), (MAX_RATE, MAX_BUFFERED_BYTES / FRAME_BYTES));
+    }
+
+    #[tokio::test]
+    async fn test_flood_counts_what_the_client_reads() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/finalkill", listener.local_addr().unwrap());
+        let client = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut received = 0u64;
+            while let Some(Ok(message)) = ws.next().await {
+                if let Message::Binary(data) = message {
+                    received += data.len() as u64;
+                }
+            }
+            received
+        });
+
+        let (stop_tx, stop_rx) = broadcast::channel(1);
+        let flood = tokio::spawn(flood_connection(url, NodeCoord::new(1, 0), stop_rx));
+        sleep(Duration::from_millis(300)).await;
+        stop_tx.send(()).unwrap();
+
+        let report = flood.await.unwrap();
+        assert!(report.connected && report.error.is_none());
+        assert!(report.bytes_sent > 0);
+        // A batch cut short by the stop still reaches the client, uncounted
+        assert!(client.await.unwrap() >= report.bytes_sent);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_opens_no_connections() {
+        let config = DryRunConfig { link_bytes_per_sec: 0, flooder_bytes_per_sec: 1_000_000 };
+        let manager = FinalKillManager::with_transport(TransportMode::DryRun(config));
+        // A documentation address: a real flood would fail to connect
+        let nodes = vec![NodeCoord::new(1, 0), NodeCoord::new(2, 0)];
+        let report = manager
+            .start_final_kill(7, "192.0.2.1".to_string(), nodes, Duration::from_millis(300))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(report.connected(), 2);
+        assert!(report.connections.iter().all(|c| c.error.is_none() && c.bytes_sent > 0));
+        assert_eq!(report.bytes_delivered, report.connections.iter().map(|c| c.bytes_sent).sum::<u64>());
+        assert!(!manager.is_attacking(7).await);
+    }
+
+    #[test]
+    fn test_only_the_captor_may_flood() {
+        use crate::game::{CommandEvent, FactEvent, GameEvent};
+
+        let mut state = GameState::new();
+        let (victim, captor) = (NodeCoord::new(0, 0), NodeCoord::new(1, 0));
+        for (player_id, coord, ip) in [(1, victim, "10.0.0.1"), (2, captor, "10.0.0.2")] {
+            let join = CommandEvent::PlayerJoin {
+                player_id,
+                name: format!("p{}", player_id),
+                capital_coord: coord,
+                capital_ip: Some(ip.to_string()),
+                client_endpoint: Some("192.168.1.1".to_string()),
+                timestamp: 1000,
+                raft_node_id: player_id,
+                reconnect_key: None,
+            };
+            state.process_event(join.into(), player_id);
+        }
+        assert_eq!(expected_final_kill(&state, 1, Some("10.0.0.9")), None);
+
+        let effect = Effect::FinalKill { player_id: 1, client_endpoint: "192.168.1.1".to_string(), attacker_nodes: vec![captor] };
+        state.process_event(FactEvent::EffectRequested { effect, timestamp: 1001 }.into(), 3);
+        // The capital hasn't fallen, so the requester's nodes aren't the captor's
+        let kill = expected_final_kill(&state, 1, Some("10.0.0.9")).unwrap();
+        assert_eq!((kill.effect_id, kill.sources.clone(), kill.connections), (3, vec!["10.0.0.9".to_string()], 1));
+
+        state.process_event(GameEvent::Fact(FactEvent::NodeCaptured { node_coord: victim, new_owner_id: 2, timestamp: 1002 }), 4);
+        let kill = expected_final_kill(&state, 1, Some("10.0.0.9")).unwrap();
+        assert_eq!(kill.sources, ["10.0.0.2", "10.0.0.9"]);
+
+        state.process_event(FactEvent::EffectCompleted { effect_id: 3, error: None, timestamp: 1012 }.into(), 5);
+        assert_eq!(expected_final_kill(&state, 1, None), None);
+    }
+
+    #[test]
+    fn test_receive_budget() {
+        let budget = ReceiveBudget::new(2, 100);
+        let kill = ExpectedFinalKill { effect_id: 3, sources: Vec::new(), connections: 5 };
+        let first = budget.admit(&kill).unwrap();
+        let second = budget.admit(&kill).unwrap();
+        assert_eq!(budget.admit(&kill).unwrap_err(), ReceiveRefusal::TooManyConnections);
+        drop(second);
+
+        let third = budget.admit(&kill).unwrap();
+        assert!(first.received(60));
+        assert!(!third.received(60));
+        drop((first, third));
+        assert_eq!(budget.admit(&kill).unwrap_err(), ReceiveRefusal::BudgetSpent);
+
+        // A later final kill gets its own budget, and no more connections than nodes
+        let next = ExpectedFinalKill { effect_id: 9, sources: Vec::new(), connections: 1 };
+        let _only = budget.admit(&next).unwrap();
+        assert_eq!(budget.admit(&next).unwrap_err(), ReceiveRefusal::TooManyConnections);
+    }
+}
@@ -0,0 +1,128 @@
+// Recent metrics per node, derived from the telemetry plane
+//
+// The metrics view keeps only the latest sample per node. The frontend wants to
+// graph bandwidth and loss over the last few minutes, so the view also keeps a
+// bounded ring buffer per node, one sample every HISTORY_INTERVAL_SECS. Telemetry
+// isn't in the log, so each node's series is what it has seen itself.
+
+use super::events::NodeCoord;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Default samples kept per node (10 minutes at one sample every 5s)
+pub const DEFAULT_HISTORY_LEN: usize = 120;
+
+/// Minimum spacing of a node's samples in the history
+pub const HISTORY_INTERVAL_SECS: u64 = 5;
+
+/// One metrics sample, as stored in the history
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSample {
+    pub timestamp: u64,
+    pub bandwidth_in: u64,
+    pub packet_loss: f32,
+    /// Attackers that were waiting for a flooder slot
+    pub queued_attackers: usize,
+    /// The node's own CPU use (0.0 to 1.0), if its worker reported it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_utilization: Option<f32>,
+    /// Share of the node's memory in use (0.0 to 1.0), if its worker reported it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_pressure: Option<f32>,
+}
+
+/// Bounded per-node ring buffer of metrics samples
+#[derive(Debug, Clone)]
+pub struct MetricsHistory {
+    capacity: usize,
+    samples: HashMap<NodeCoord, VecDeque<MetricsSample>>,
+}
+
+impl MetricsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Load the buffer size from the environment
+    /// METRICS_HISTORY_LEN (default: 120 samples per node)
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("METRICS_HISTORY_LEN")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_HISTORY_LEN);
+        Self::new(capacity)
+    }
+
+    /// Add `sample` for `coord`, unless the last one is under HISTORY_INTERVAL_SECS older
+    pub fn record(&mut self, coord: NodeCoord, sample: MetricsSample) {
+        let series = self.samples.entry(coord).or_default();
+        if series.back().is_some_and(|last| sample.timestamp < last.timestamp + HISTORY_INTERVAL_SECS) {
+            return;
+        }
+        if series.len() == self.capacity {
+            series.pop_front();
+        }
+        series.push_back(sample);
+    }
+
+    /// Samples for `coord` at or after `since` (unix seconds), oldest first
+    pub fn series(&self, coord: &NodeCoord, since: u64) -> Vec<MetricsSample> {
+        self.samples
+            .get(coord)
+            .map(|series| series.iter().filter(|s| s.timestamp >= since).copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every kept sample, by node, oldest first within each node
+    pub fn all(&self) -> Vec<(NodeCoord, MetricsSample)> {
+        self.samples
+            .iter()
+            .flat_map(|(coord, series)| series.iter().map(move |sample| (*coord, *sample)))
+            .collect()
+    }
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_LEN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64) -> MetricsSample {
+        MetricsSample {
+            timestamp,
+            bandwidth_in: timestamp * 10,
+            packet_loss: 0.1,
+            queued_attackers: 0,
+            cpu_utilization: None,
+            memory_pressure: None,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_latest_samples() {
+        let mut history = MetricsHistory::new(3);
+        for ts in 1..=5 {
+            history.record(NodeCoord::new(0, 0), sample(ts * HISTORY_INTERVAL_SECS));
+        }
+        // Too close to the previous sample
+        history.record(NodeCoord::new(0, 0), sample(5 * HISTORY_INTERVAL_SECS + 1));
+        history.record(NodeCoord::new(1, 0), sample(100));
+
+        let series = history.series(&NodeCoord::new(0, 0), 0);
+        assert_eq!(series.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![15, 20, 25]);
+        assert_eq!(series[2].bandwidth_in, 250);
+
+        // Window filter, and other nodes are kept separately
+        assert_eq!(history.series(&NodeCoord::new(0, 0), 25).len(), 1);
+        assert_eq!(history.series(&NodeCoord::new(1, 0), 0)[0].timestamp, 100);
+        assert!(history.series(&NodeCoord::new(2, 0), 0).is_empty());
+    }
+}
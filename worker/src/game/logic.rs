@@ -1,131 +1,862 @@
-use super::events::{AttackTarget, GameEvent, NodeCoord};
-use super::state::GameState;
-use std::collections::HashMap;
+use super::capture::{CaptureRules, CaptureTracker, TierScaling};
+use super::events::{AnnouncementSeverity, AttackTarget, Effect, EliminationPolicy, FactEvent, GameEvent, GamePhase, NodeCoord, NodeType};
+use super::features::{Feature, FeatureFlags};
+use super::settings::{parse_elimination_policy, MAX_MAP_RADIUS, MAX_RETARGET_COOLDOWN_SECS};
+use super::state::{GameState, DEFAULT_RETARGET_COOLDOWN_SECS, MAX_METRICS_INTERVAL_MS, MAX_TIME_SCALE, MIN_METRICS_INTERVAL_MS};
+use super::supply::SupplyConfig;
+use super::terrain::{self, Terrain};
+use crate::raft::metrics_view::{live_metrics_interval, DEFAULT_LIVE_METRICS_MS};
+use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Configuration for game logic
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GameConfig {
     /// How long a node must be overloaded before it's captured (seconds)
     pub overload_duration_secs: u64,
     /// Packet loss threshold to consider a node overloaded (0.0 to 1.0)
     pub overload_threshold: f32,
+    /// Cap on the combined-arms capture speed-up (1.0 = attackers don't stack)
+    pub max_capture_multiplier: f32,
+    /// How the two thresholds above (a regular node's) scale for neutral nodes and capitals
+    pub capacity_tiers: TierScaling,
+    /// How often the leader spawns neutral neighbors around owned nodes (seconds)
+    pub neighbor_expansion_interval_secs: u64,
+    /// How long a player whose capital falls has to recapture it before they're
+    /// eliminated (seconds, 0 = losing the capital is instantly fatal)
+    pub capital_grace_secs: u64,
+    /// Generated terrain, committed at game start (None = uniform plane)
+    pub map: Option<MapConfig>,
+    /// Silence from a player's client before their nodes stop attacking (seconds, 0 = never)
+    pub client_idle_secs: u64,
+    /// How long a player stays idle before their regular nodes go neutral (seconds, 0 = never)
+    pub idle_neutralize_secs: u64,
+    /// Game time runs this many times faster than wall time, committed at game start
+    /// (1 = real time). Every duration above, and the rules' own, shrinks by it.
+    pub time_scale: u32,
+    /// Least time between one node's target changes (seconds), committed at game start
+    /// when it differs from the rules default - faster retargets are refused
+    pub retarget_cooldown_secs: u64,
+    /// What an eliminated player's nodes do, committed at game start when set
+    /// (None = they stay the dead player's)
+    pub elimination_policy: Option<EliminationPolicy>,
+    /// Attacks weaken with distance from the attacker's capital (None = they don't)
+    pub supply: Option<SupplyConfig>,
+    /// Game time from the second player joining until the leader calls sudden death
+    /// (seconds, 0 = no limit)
+    pub time_limit_secs: u64,
+    /// Experimental subsystems the game runs, committed at game start when they
+    /// differ from the defaults
+    pub features: FeatureFlags,
+    /// How much a target's CPU saturation adds to its loss for the capture rule: 0 at
+    /// CPU_SATURATION_START, this much at 100% (0.0 = CPU doesn't count)
+    pub cpu_overload_weight: f32,
+    /// How often workers stream metric samples to the leader (milliseconds), committed
+    /// at game start when it differs from the default
+    pub metrics_interval_ms: u64,
+}
+
+/// CPU use from which a node counts as saturating (see GameConfig::cpu_overload_weight)
+pub const CPU_SATURATION_START: f32 = 0.8;
+
+/// Game time before sudden death at which the leader warns everyone it's coming
+pub const TIME_WARNING_SECS: u64 = 300;
+
+/// How long the leader's own announcements show (seconds)
+pub const LEADER_ANNOUNCEMENT_TTL_SECS: u64 = 30;
+
+/// Default time between two of an eliminated player's nodes decaying (seconds)
+pub const DEFAULT_DECAY_INTERVAL_SECS: u64 = 10;
+
+/// Terrain generation for a game (see camhack_rules::terrain)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapConfig {
+    pub seed: u64,
+    /// Hexes within this distance of the origin get terrain
+    pub radius: u32,
+}
+
+impl MapConfig {
+    /// MAP_RADIUS enables terrain (default: 0 = off, at most MAX_MAP_RADIUS), MAP_SEED picks the map
+    /// (default: the current time, so every game gets a new one)
+    pub fn from_env() -> Option<Self> {
+        let radius = std::env::var("MAP_RADIUS").ok().and_then(|s| s.parse().ok()).unwrap_or(0u32).min(MAX_MAP_RADIUS);
+        if radius == 0 {
+            return None;
+        }
+        let seed = std::env::var("MAP_SEED").ok().and_then(|s| s.parse().ok()).unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64
+        });
+        Some(Self { seed, radius })
+    }
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
-        Self {
-            overload_duration_secs: 5,
-            overload_threshold: 0.2, // 20% packet loss
+        Self::with_capture(CaptureRules::STANDARD, 10, 30)
+    }
+}
+
+impl GameConfig {
+    /// The capture half of this config, as the rules core (and the frontend) sees it
+    pub fn capture_rules(&self) -> CaptureRules {
+        CaptureRules {
+            overload_duration_secs: self.overload_duration_secs,
+            overload_threshold: self.overload_threshold,
+            max_capture_multiplier: self.max_capture_multiplier,
+            tiers: self.capacity_tiers,
+        }
+    }
+
+    /// Load the preset named by GAME_RULES (default: standard)
+    /// Every worker in a game must use the same rules - the master passes it on spawn
+    /// CAPITAL_GRACE_SECS overrides the preset's capital grace window,
+    /// CLIENT_IDLE_SECS and IDLE_NEUTRALIZE_SECS the idle-owner hand-off,
+    /// TIME_SCALE speeds the game up for playtesting (1 to MAX_TIME_SCALE),
+    /// RETARGET_COOLDOWN_SECS sets the retarget cooldown (0 = none, at most MAX_RETARGET_COOLDOWN_SECS),
+    /// ELIMINATION_POLICY and ELIMINATION_DECAY_SECS what eliminations do to nodes,
+    /// SUPPLY_FALLOFF (and friends) turn on supply lines,
+    /// TIME_LIMIT_SECS sets when sudden death starts (0 = never),
+    /// FEATURES switches features on (`name`) and off (`-name`),
+    /// CPU_OVERLOAD_WEIGHT counts CPU saturation towards overload (0 = not at all)
+    pub fn from_env() -> Self {
+        let mut config = std::env::var("GAME_RULES")
+            .ok()
+            .and_then(|name| {
+                let config = Self::preset(&name);
+                if config.is_none() {
+                    eprintln!("[GameLogic] Unknown rules preset '{}', using standard", name);
+                }
+                config
+            })
+            .unwrap_or_default();
+
+        if let Some(grace) = std::env::var("CAPITAL_GRACE_SECS").ok().and_then(|s| s.parse().ok()) {
+            config.capital_grace_secs = grace;
         }
+        config.map = MapConfig::from_env();
+        config.supply = SupplyConfig::from_env();
+        if let Some(secs) = std::env::var("CLIENT_IDLE_SECS").ok().and_then(|s| s.parse().ok()) {
+            config.client_idle_secs = secs;
+        }
+        if let Some(secs) = std::env::var("IDLE_NEUTRALIZE_SECS").ok().and_then(|s| s.parse().ok()) {
+            config.idle_neutralize_secs = secs;
+        }
+        if let Some(scale) = std::env::var("TIME_SCALE").ok().and_then(|s| s.parse::<u32>().ok()) {
+            config.time_scale = scale.clamp(1, MAX_TIME_SCALE);
+        }
+        if let Some(secs) = std::env::var("RETARGET_COOLDOWN_SECS").ok().and_then(|s| s.parse::<u64>().ok()) {
+            config.retarget_cooldown_secs = secs.min(MAX_RETARGET_COOLDOWN_SECS);
+        }
+        if let Some(secs) = std::env::var("TIME_LIMIT_SECS").ok().and_then(|s| s.parse().ok()) {
+            config.time_limit_secs = secs;
+        }
+        if let Some(weight) = std::env::var("CPU_OVERLOAD_WEIGHT").ok().and_then(|s| s.parse::<f32>().ok()) {
+            config.cpu_overload_weight = weight.clamp(0.0, 1.0);
+        }
+        config.metrics_interval_ms = (live_metrics_interval().as_millis() as u64).clamp(MIN_METRICS_INTERVAL_MS, MAX_METRICS_INTERVAL_MS);
+        if let Ok(list) = std::env::var("FEATURES") {
+            match FeatureFlags::parse(&list) {
+                Ok(features) => {
+                    // Flags ship ahead of their subsystems: these are committed, but nothing reads them yet
+                    for feature in features.iter().filter(|f| matches!(f, Feature::FogOfWar | Feature::Economy | Feature::QuicAttacks)) {
+                        eprintln!("[GameLogic] Feature {} is on but not implemented by this build yet", feature.name());
+                    }
+                    config.features = features;
+                }
+                Err(name) => eprintln!("[GameLogic] Unknown feature '{}' in FEATURES, using the defaults", name),
+            }
+        }
+        if let Ok(name) = std::env::var("ELIMINATION_POLICY") {
+            let interval_secs = std::env::var("ELIMINATION_DECAY_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_DECAY_INTERVAL_SECS);
+            match parse_elimination_policy(&name, interval_secs) {
+                Ok(policy) => config.elimination_policy = policy,
+                Err(_) => eprintln!("[GameLogic] Unknown elimination policy '{}', nodes stay with the eliminated", name),
+            }
+        }
+        config
     }
 }
 
-/// Track ongoing attacks and their start times
-#[derive(Debug, Clone)]
-struct AttackTracker {
-    /// Map of (target_coord -> (attacker_id, overload_start_time))
-    overload_start_times: HashMap<NodeCoord, (u64, u64)>,
+/// What a leader-generated event was emitted under, so a write that fails (or is held
+/// back) can be forgotten and the event emitted again on a later tick
+#[derive(Debug, Clone, PartialEq)]
+pub enum Requested {
+    Init(NodeCoord),
+    Effect(Effect),
+    Elimination(u64),
+    Idle(u64),
+    Decay(u64),
+    Scheduled(u64),
+    Map,
+    TimeScale,
+    Cooldown,
+    Policy,
+    MetricsInterval,
+    Features,
+    SuddenDeath,
+    TimeWarning,
 }
 
-impl AttackTracker {
-    fn new() -> Self {
-        Self {
-            overload_start_times: HashMap::new(),
-        }
+impl Requested {
+    /// The request `event` answers, for the events `tick` emits once per request
+    fn of(event: &GameEvent) -> Option<Self> {
+        let GameEvent::Fact(fact) = event else { return None };
+        Some(match fact {
+            FactEvent::NodeInitializationStarted { node_coord, .. } => Requested::Init(*node_coord),
+            FactEvent::EffectRequested { effect, .. } => Requested::Effect(effect.clone()),
+            FactEvent::PlayerEliminated { player_id, .. } => Requested::Elimination(*player_id),
+            FactEvent::OwnerIdle { player_id, .. } => Requested::Idle(*player_id),
+            FactEvent::NodeDecayed { player_id, .. } => Requested::Decay(*player_id),
+            FactEvent::ScheduledEventFired { schedule_id, .. } => Requested::Scheduled(*schedule_id),
+            FactEvent::MapGenerated { .. } => Requested::Map,
+            FactEvent::TimeScaleSet { .. } => Requested::TimeScale,
+            FactEvent::RetargetCooldownSet { .. } => Requested::Cooldown,
+            FactEvent::EliminationPolicySet { .. } => Requested::Policy,
+            FactEvent::MetricsIntervalSet { .. } => Requested::MetricsInterval,
+            FactEvent::FeaturesSet { .. } => Requested::Features,
+            FactEvent::PhaseChanged { phase: GamePhase::SuddenDeath, .. } => Requested::SuddenDeath,
+            // The leader's only warning is the one before sudden death
+            FactEvent::Announcement { severity: AnnouncementSeverity::Warning, .. } => Requested::TimeWarning,
+            _ => return None,
+        })
     }
 }
 
 /// Game logic evaluator - runs on leader only
 pub struct GameLogic {
     config: GameConfig,
-    attack_tracker: AttackTracker,
+    capture_tracker: CaptureTracker,
+    /// Coords this leader already emitted NodeInitializationStarted for,
+    /// kept until the event is visible in committed state
+    init_requested: HashSet<NodeCoord>,
+    /// Effects this leader already requested, kept until visible in committed state
+    effects_requested: Vec<Effect>,
+    /// Players this leader already eliminated, kept until visible in committed state
+    eliminations_requested: HashSet<u64>,
+    /// OwnerIdle events this leader already emitted: player -> (neutralize, their
+    /// last heartbeat at the time), kept until visible or until the client speaks up
+    idle_requested: HashMap<u64, (bool, u64)>,
+    /// MapGenerated already emitted by this leader (a game only ever gets one map)
+    map_requested: bool,
+    /// TimeScaleSet already emitted by this leader
+    time_scale_requested: bool,
+    /// RetargetCooldownSet already emitted by this leader
+    cooldown_requested: bool,
+    /// EliminationPolicySet already emitted by this leader
+    policy_requested: bool,
+    /// MetricsIntervalSet already emitted by this leader
+    metrics_interval_requested: bool,
+    /// NodeDecayed events this leader already emitted: player -> (how many of their
+    /// nodes had decayed at the time, the node), kept until that node is no longer next
+    decays_requested: HashMap<u64, (u32, NodeCoord)>,
+    /// PhaseChanged into sudden death already emitted by this leader
+    sudden_death_requested: bool,
+    /// Sudden death warning already emitted (or found committed) by this leader
+    time_warning_requested: bool,
+    /// FeaturesSet already emitted by this leader
+    features_requested: bool,
+    /// Scheduled commands this leader already fired, kept until they leave the queue
+    scheduled_fired: HashSet<u64>,
+    /// Last time neutral neighbors were spawned around owned nodes
+    last_neighbor_expansion: u64,
 }
 
 impl GameLogic {
     pub fn new(config: GameConfig) -> Self {
         Self {
             config,
-            attack_tracker: AttackTracker::new(),
+            capture_tracker: CaptureTracker::new(),
+            init_requested: HashSet::new(),
+            effects_requested: Vec::new(),
+            eliminations_requested: HashSet::new(),
+            idle_requested: HashMap::new(),
+            map_requested: false,
+            time_scale_requested: false,
+            cooldown_requested: false,
+            policy_requested: false,
+            metrics_interval_requested: false,
+            decays_requested: HashMap::new(),
+            sudden_death_requested: false,
+            time_warning_requested: false,
+            features_requested: false,
+            scheduled_fired: HashSet::new(),
+            last_neighbor_expansion: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
         }
     }
 
-    /// Evaluate game state and generate capture events if conditions are met
-    /// This should be called periodically by the leader
-    pub fn tick(&mut self, game_state: &GameState) -> Vec<GameEvent> {
-        let mut events = Vec::new();
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    /// Rules this logic runs with
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// This node just became leader: capture timers start over, and boosts an earlier
+    /// leader committed are tracked so they're ended when their attack is. Whatever this
+    /// node requested in an earlier term may never have committed, so it's forgotten
+    /// and decided again from committed state.
+    pub fn take_leadership(&mut self, game_state: &GameState) {
+        self.capture_tracker = CaptureTracker::resume(game_state);
+        self.init_requested.clear();
+        self.effects_requested.clear();
+        self.eliminations_requested.clear();
+        self.idle_requested.clear();
+        self.decays_requested.clear();
+        self.scheduled_fired.clear();
+        self.map_requested = false;
+        self.time_scale_requested = false;
+        self.cooldown_requested = false;
+        self.policy_requested = false;
+        self.metrics_interval_requested = false;
+        self.features_requested = false;
+        self.sudden_death_requested = false;
+        self.time_warning_requested = false;
+    }
+
+    /// An event emitted under `request` didn't commit (its write failed or was held
+    /// back): emit it again when it's next due
+    pub fn forget(&mut self, request: &Requested) {
+        match request {
+            Requested::Init(coord) => {
+                self.init_requested.remove(coord);
+            }
+            Requested::Effect(effect) => self.effects_requested.retain(|e| e != effect),
+            Requested::Elimination(player_id) => {
+                self.eliminations_requested.remove(player_id);
+            }
+            Requested::Idle(player_id) => {
+                self.idle_requested.remove(player_id);
+            }
+            Requested::Decay(player_id) => {
+                self.decays_requested.remove(player_id);
+            }
+            Requested::Scheduled(schedule_id) => {
+                self.scheduled_fired.remove(schedule_id);
+            }
+            Requested::Map => self.map_requested = false,
+            Requested::TimeScale => self.time_scale_requested = false,
+            Requested::Cooldown => self.cooldown_requested = false,
+            Requested::Policy => self.policy_requested = false,
+            Requested::MetricsInterval => self.metrics_interval_requested = false,
+            Requested::Features => self.features_requested = false,
+            Requested::SuddenDeath => self.sudden_death_requested = false,
+            Requested::TimeWarning => self.time_warning_requested = false,
+        }
+    }
+
+    /// Lazy initialization - decide which hexes need a node spawned
+    ///
+    /// Committed SetNodeTarget events pointing at a hex with no node get that hex
+    /// initialized right away; owned nodes get their missing neighbors filled in
+    /// every `neighbor_expansion_interval_secs`. Each hex is emitted once - after
+    /// commit it exists in state, and until then `init_requested` suppresses repeats.
+    /// Each NodeInitializationStarted is followed by the SpawnNode effect for it.
+    fn lazy_init(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        // Forget requests that have landed in committed state
+        self.init_requested.retain(|coord| !game_state.nodes.contains_key(coord));
+
+        let mut to_init = Vec::new();
 
-        // Check all nodes that are being attacked
         for node in game_state.nodes.values() {
-            let target_coord = node.coord;
+            if let Some(AttackTarget::Coordinate(target)) = node.current_target {
+                let open = !game_state.nodes.contains_key(&target) && game_state.terrain_at(target).is_passable();
+                if open && self.init_requested.insert(target) {
+                    println!("[Lazy Init] Target {:?} has no node, initializing", target);
+                    to_init.push(target);
+                }
+            }
+        }
+
+        let expansion_interval = game_state.scaled_secs(self.config.neighbor_expansion_interval_secs);
+        if current_time.saturating_sub(self.last_neighbor_expansion) >= expansion_interval {
+            self.last_neighbor_expansion = current_time;
+
+            for node in game_state.nodes.values().filter(|n| n.owner_id != 0) {
+                for neighbor in node.coord.neighbors() {
+                    let open = !game_state.nodes.contains_key(&neighbor) && game_state.terrain_at(neighbor).is_passable();
+                    if open && self.init_requested.insert(neighbor) {
+                        to_init.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        if !to_init.is_empty() {
+            println!("[Lazy Init] Initializing {} nodes", to_init.len());
+        }
 
-            // Find who's attacking this node (only grid coordinate attacks)
-            let attackers: Vec<_> = game_state
+        to_init
+            .into_iter()
+            .flat_map(|coord| {
+                [
+                    GameEvent::Fact(FactEvent::NodeInitializationStarted {
+                        node_coord: coord,
+                        owner_id: 0,  // Neutral/unowned
+                        timestamp: current_time,
+                    }),
+                    GameEvent::Fact(FactEvent::EffectRequested {
+                        effect: Effect::SpawnNode { node_coord: coord },
+                        timestamp: current_time,
+                    }),
+                ]
+            })
+            .collect()
+    }
+
+    /// Side effects the committed state calls for that nobody has requested yet:
+    /// a final kill on each eliminated player's client (shutdown is the ShutdownCoordinator's)
+    fn request_effects(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        // Forget requests that have landed in committed state
+        self.effects_requested.retain(|effect| !game_state.effect_requested(|e| e == effect));
+
+        let mut effects = Vec::new();
+
+        for player in game_state.players.values().filter(|p| !p.alive) {
+            let Some(client_endpoint) = &player.client_endpoint else { continue };
+            let Some(capital) = game_state.nodes.get(&player.capital_coord) else { continue };
+            let already = |e: &Effect| matches!(e, Effect::FinalKill { player_id, .. } if *player_id == player.player_id);
+            if game_state.effect_requested(already) || self.effects_requested.iter().any(already) {
+                continue;
+            }
+
+            // Every node of whoever captured the capital joins in
+            let attacker_nodes: Vec<NodeCoord> = game_state
                 .nodes
                 .values()
-                .filter(|n| n.current_target == Some(AttackTarget::Coordinate(target_coord)))
+                .filter(|n| n.owner_id == capital.owner_id)
+                .map(|n| n.coord)
                 .collect();
+            if attacker_nodes.is_empty() {
+                continue;
+            }
+
+            println!(
+                "[Effects] Player {} lost their capital, requesting final kill from {} nodes",
+                player.player_id,
+                attacker_nodes.len()
+            );
+            effects.push(Effect::FinalKill {
+                player_id: player.player_id,
+                client_endpoint: client_endpoint.clone(),
+                attacker_nodes,
+            });
+        }
+
+        self.effects_requested.extend(effects.iter().cloned());
+        effects
+            .into_iter()
+            .map(|effect| GameEvent::Fact(FactEvent::EffectRequested { effect, timestamp: current_time }))
+            .collect()
+    }
+
+    /// Commit the map once, at game start
+    /// Chasms under nodes that already exist (capitals of players who joined before
+    /// the first tick) are left out, so nobody starts on impassable ground.
+    fn generate_map(&mut self, game_state: &GameState, current_time: u64) -> Option<GameEvent> {
+        let map = self.config.map?;
+        if self.map_requested {
+            return None;
+        }
+        self.map_requested = true;
+
+        let tiles: Vec<_> = terrain::generate(map.seed, map.radius)
+            .into_iter()
+            .filter(|tile| tile.terrain != Terrain::Chasm || !game_state.nodes.contains_key(&tile.coord))
+            .collect();
+        println!(
+            "[GameLogic] Generated map (seed {}, radius {}): {} terrain hexes",
+            map.seed,
+            map.radius,
+            tiles.len()
+        );
+        Some(GameEvent::Fact(FactEvent::MapGenerated {
+            seed: map.seed,
+            radius: map.radius,
+            tiles,
+            timestamp: current_time,
+        }))
+    }
+
+    /// Commit the configured time scale once, before anything else happens
+    /// A game already running at a committed scale keeps it, whatever this leader's config.
+    fn set_time_scale(&mut self, current_time: u64) -> Option<GameEvent> {
+        if self.time_scale_requested {
+            return None;
+        }
+        self.time_scale_requested = true;
+        println!("[GameLogic] Game time runs at {}x", self.config.time_scale);
+        Some(GameEvent::Fact(FactEvent::TimeScaleSet { scale: self.config.time_scale, timestamp: current_time }))
+    }
+
+    /// Commit the configured retarget cooldown once, if it isn't the rules default
+    /// (which the state uses until one is committed)
+    fn set_retarget_cooldown(&mut self, game_state: &GameState, current_time: u64) -> Option<GameEvent> {
+        let secs = self.config.retarget_cooldown_secs;
+        if self.cooldown_requested || game_state.retarget_cooldown_secs.is_some() || secs == DEFAULT_RETARGET_COOLDOWN_SECS {
+            return None;
+        }
+        self.cooldown_requested = true;
+        println!("[GameLogic] Retarget cooldown: {}s", secs);
+        Some(GameEvent::Fact(FactEvent::RetargetCooldownSet { secs, timestamp: current_time }))
+    }
+
+    /// Commit the configured metrics interval once, if it isn't the default (until
+    /// one is committed each worker streams at its own LIVE_METRICS_MS)
+    fn set_metrics_interval(&mut self, game_state: &GameState, current_time: u64) -> Option<GameEvent> {
+        let interval_ms = self.config.metrics_interval_ms;
+        if self.metrics_interval_requested || game_state.metrics_interval_ms.is_some() || interval_ms == DEFAULT_LIVE_METRICS_MS {
+            return None;
+        }
+        self.metrics_interval_requested = true;
+        println!("[GameLogic] Metrics interval: {}ms", interval_ms);
+        Some(GameEvent::Fact(FactEvent::MetricsIntervalSet { interval_ms, timestamp: current_time }))
+    }
+
+    /// Commit the configured elimination policy once, if there is one
+    fn set_elimination_policy(&mut self, game_state: &GameState, current_time: u64) -> Option<GameEvent> {
+        let policy = self.config.elimination_policy?;
+        if self.policy_requested || game_state.elimination_policy.is_some() {
+            return None;
+        }
+        self.policy_requested = true;
+        println!("[GameLogic] Elimination policy: {:?}", policy);
+        Some(GameEvent::Fact(FactEvent::EliminationPolicySet { policy, timestamp: current_time }))
+    }
+
+    /// Commit the configured features once, if they aren't the defaults
+    fn set_features(&mut self, game_state: &GameState, current_time: u64) -> Option<GameEvent> {
+        let features = self.config.features;
+        if features == FeatureFlags::default() || self.features_requested || game_state.features.is_some() {
+            return None;
+        }
+        self.features_requested = true;
+        let names: Vec<&str> = features.iter().map(|f| f.name()).collect();
+        println!("[GameLogic] Features: {}", if names.is_empty() { "none".to_string() } else { names.join(", ") });
+        Some(GameEvent::Fact(FactEvent::FeaturesSet { features, timestamp: current_time }))
+    }
+
+    /// Features the game runs: the committed set, or this leader's until it commits
+    pub fn features(&self, game_state: &GameState) -> FeatureFlags {
+        game_state.features.unwrap_or(self.config.features)
+    }
+
+    /// Call sudden death once a live game has run past its time limit
+    /// Along with the phase change, every player is told
+    fn start_sudden_death(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        let Some(due) = game_state.sudden_death_at(self.config.time_limit_secs) else {
+            return Vec::new();
+        };
+        if self.sudden_death_requested || due > current_time || !self.features(game_state).contains(Feature::SuddenDeath) {
+            return Vec::new();
+        }
+        self.sudden_death_requested = true;
+        println!("[GameLogic] Time limit reached: sudden death, captures take half as long");
+        vec![
+            GameEvent::Fact(FactEvent::PhaseChanged { phase: GamePhase::SuddenDeath, timestamp: current_time }),
+            announcement("Sudden death! Captures take half as long", AnnouncementSeverity::Critical, current_time),
+        ]
+    }
+
+    /// Warn everyone TIME_WARNING_SECS (game time) before sudden death, in games whose
+    /// limit is longer than that. A leader elected after the warning went out finds it
+    /// in the committed announcements.
+    fn warn_time_limit(&mut self, game_state: &GameState, current_time: u64) -> Option<GameEvent> {
+        if self.time_warning_requested || self.config.time_limit_secs <= TIME_WARNING_SECS {
+            return None;
+        }
+        let due = game_state.sudden_death_at(self.config.time_limit_secs)?;
+        let warn_at = due.saturating_sub(game_state.scaled_secs(TIME_WARNING_SECS));
+        if current_time < warn_at || current_time >= due {
+            return None;
+        }
+        self.time_warning_requested = true;
+        if game_state.announcements.values().any(|a| a.posted_at >= warn_at && a.severity == AnnouncementSeverity::Warning) {
+            return None;
+        }
+        let text = format!("{} minutes until sudden death", TIME_WARNING_SECS / 60);
+        println!("[GameLogic] {}", text);
+        Some(announcement(&text, AnnouncementSeverity::Warning, current_time))
+    }
+
+    /// Fire each scheduled command whose time has come
+    /// One the leader would refuse if it were submitted now (a capital that has become
+    /// spawn-protected, a name someone took meanwhile) still leaves the queue, with the
+    /// reason in the log instead of its effects.
+    fn fire_scheduled(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        self.scheduled_fired.retain(|id| game_state.scheduled.contains_key(id));
 
-            if attackers.is_empty() {
-                // No one attacking, clear any tracking
-                self.attack_tracker.overload_start_times.remove(&target_coord);
+        let mut events = Vec::new();
+        for (schedule_id, scheduled) in game_state.due_scheduled(current_time) {
+            if !self.scheduled_fired.insert(schedule_id) {
                 continue;
             }
+            let command = GameEvent::Command(scheduled.command.clone());
+            let refused = crate::raft::api::refuse_event(&command, game_state, current_time).map(|(_, reason)| reason);
+            match &refused {
+                Some(reason) => println!("[GameLogic] Scheduled {} #{} refused: {}", command.kind(), schedule_id, reason),
+                None => println!("[GameLogic] Scheduled {} #{} fires", command.kind(), schedule_id),
+            }
+            events.push(GameEvent::Fact(FactEvent::ScheduledEventFired { schedule_id, refused, timestamp: current_time }));
+        }
+        events
+    }
 
-            // Multiple attackers: only first attacker succeeds, rest get reflected (per game rules)
-            // For now, just take the first attacker
-            let attacker = attackers[0];
-
-            // Check if node is overloaded
-            if let Some(metrics) = game_state.node_metrics.get(&target_coord) {
-                let is_overloaded = metrics.packet_loss >= self.config.overload_threshold;
-
-                if is_overloaded {
-                    // Track when overload started
-                    let (tracked_attacker, overload_start) = self
-                        .attack_tracker
-                        .overload_start_times
-                        .entry(target_coord)
-                        .or_insert((attacker.owner_id, current_time));
-
-                    // Check if sustained long enough
-                    let overload_duration = current_time.saturating_sub(*overload_start);
-                    if overload_duration >= self.config.overload_duration_secs {
-                        // Capture!
-                        events.push(GameEvent::NodeCaptured {
-                            node_coord: target_coord,
-                            new_owner_id: *tracked_attacker,
-                            timestamp: current_time,
-                        });
-
-                        // Clear tracking after capture
-                        self.attack_tracker.overload_start_times.remove(&target_coord);
-                    }
-                } else {
-                    // Not overloaded anymore, reset tracking
-                    self.attack_tracker.overload_start_times.remove(&target_coord);
+    /// Under the decay policy, turn each eliminated player's next node neutral once it's due
+    fn decay_nodes(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        // Forget requests that have landed, or whose node someone captured first
+        self.decays_requested.retain(|id, (decayed, coord)| {
+            game_state.decaying.get(id).is_some_and(|d| d.decayed == *decayed)
+                && game_state.next_decay(*id).is_some_and(|(next, _)| next == *coord)
+        });
+
+        let mut events = Vec::new();
+        for (&player_id, decay) in &game_state.decaying {
+            let Some((node_coord, due)) = game_state.next_decay(player_id) else { continue };
+            if due > current_time || self.decays_requested.contains_key(&player_id) {
+                continue;
+            }
+            self.decays_requested.insert(player_id, (decay.decayed, node_coord));
+            println!("[GameLogic] Node {:?} of eliminated player {} decays to neutral", node_coord, player_id);
+            events.push(GameEvent::Fact(FactEvent::NodeDecayed { node_coord, player_id, timestamp: current_time }));
+        }
+        events
+    }
+
+    /// Last stand - give each capital about to be captured a grace window
+    ///
+    /// A CapitalContested goes in right before the capital's NodeCaptured, so once
+    /// both commit the former owner is still alive and can win the hex back. The
+    /// deadline is part of the committed event, so a new leader honours it as-is.
+    fn contest_capitals(&self, game_state: &GameState, captures: Vec<FactEvent>, current_time: u64) -> Vec<GameEvent> {
+        let mut events = Vec::with_capacity(captures.len());
+        let grace_secs = game_state.scaled_secs(self.config.capital_grace_secs);
+
+        for capture in captures {
+            if let FactEvent::NodeCaptured { node_coord, .. } = &capture {
+                let capital_owner = game_state
+                    .nodes
+                    .get(node_coord)
+                    .filter(|n| n.node_type == NodeType::Capital)
+                    .and_then(|n| game_state.players.get(&n.owner_id))
+                    .filter(|p| p.alive);
+
+                if let Some(player) = capital_owner.filter(|_| grace_secs > 0) {
+                    println!(
+                        "[GameLogic] Capital {:?} of player {} falls, {}s to retake it",
+                        node_coord, player.player_id, grace_secs
+                    );
+                    events.push(GameEvent::Fact(FactEvent::CapitalContested {
+                        node_coord: *node_coord,
+                        player_id: player.player_id,
+                        expires_at: current_time + grace_secs,
+                        timestamp: current_time,
+                    }));
                 }
             }
+            events.push(GameEvent::Fact(capture));
         }
 
         events
     }
+
+    /// Eliminate players whose contested capital wasn't retaken in time
+    /// The final kill follows through `request_effects` once the elimination commits.
+    fn expire_contests(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        // Forget eliminations that have landed in committed state
+        self.eliminations_requested
+            .retain(|id| game_state.players.get(id).is_some_and(|p| p.alive));
+
+        let mut events = Vec::new();
+        for player in game_state.players.values().filter(|p| p.alive) {
+            let Some((coord, contest)) = game_state.capital_contest(player.player_id) else { continue };
+            if contest.expires_at > current_time || !self.eliminations_requested.insert(player.player_id) {
+                continue;
+            }
+
+            println!(
+                "[GameLogic] Player {} didn't retake capital {:?} in time, eliminated",
+                player.player_id, coord
+            );
+            events.push(GameEvent::Fact(FactEvent::PlayerEliminated {
+                player_id: player.player_id,
+                timestamp: current_time,
+            }));
+        }
+
+        events
+    }
+
+    /// Hand off orphaned nodes - a player whose client stopped heartbeating has
+    /// nobody steering their nodes, so they stop attacking (OwnerIdle) and, after
+    /// `idle_neutralize_secs` more, their regular nodes turn neutral. Only players
+    /// with a client are watched; capital-only players (tests, bots) never heartbeat.
+    fn detect_idle_owners(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        // Forget requests that have landed, or that a newer heartbeat made moot
+        self.idle_requested.retain(|id, (neutralize, heartbeat)| {
+            game_state.players.get(id).is_some_and(|p| {
+                let landed = if *neutralize { p.idle_neutralized } else { p.idle_since.is_some() };
+                p.alive && !landed && p.last_heartbeat == *heartbeat
+            })
+        });
+
+        if self.config.client_idle_secs == 0 {
+            return Vec::new();
+        }
+        let idle_secs = game_state.scaled_secs(self.config.client_idle_secs);
+        let neutralize_secs = game_state.scaled_secs(self.config.idle_neutralize_secs);
+
+        let mut events = Vec::new();
+        for player in game_state.players.values().filter(|p| p.alive && p.client_endpoint.is_some()) {
+            let neutralize = match player.idle_since {
+                None if player.last_heartbeat + idle_secs <= current_time => false,
+                Some(since)
+                    if !player.idle_neutralized && neutralize_secs > 0 && since + neutralize_secs <= current_time =>
+                {
+                    true
+                }
+                _ => continue,
+            };
+            if self.idle_requested.contains_key(&player.player_id) {
+                continue;
+            }
+            self.idle_requested.insert(player.player_id, (neutralize, player.last_heartbeat));
+
+            println!(
+                "[GameLogic] No heartbeat from player {}'s client since {}, {}",
+                player.player_id,
+                player.last_heartbeat,
+                if neutralize { "neutralizing their nodes" } else { "standing their nodes down" }
+            );
+            events.push(GameEvent::Fact(FactEvent::OwnerIdle {
+                player_id: player.player_id,
+                neutralize,
+                timestamp: current_time,
+            }));
+        }
+
+        events
+    }
+
+    /// Evaluate game state and generate capture, initialization and effect-request events
+    /// This should be called periodically by the leader (also after game over, for final kills)
+    /// Time is the leader's own clock - the one stamping committed entries - held at
+    /// the last committed stamp, so a new leader with a slower clock can't rewind timers.
+    /// Each event comes with the request it was emitted under, if any, to `forget` when
+    /// it doesn't commit.
+    pub fn tick(&mut self, game_state: &GameState) -> Vec<(GameEvent, Option<Requested>)> {
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .max(game_state.clock);
+        self.tick_at(game_state, current_time)
+            .into_iter()
+            .map(|event| {
+                let request = Requested::of(&event);
+                (event, request)
+            })
+            .collect()
+    }
+
+    fn tick_at(&mut self, game_state: &GameState, current_time: u64) -> Vec<GameEvent> {
+        let mut events = self.request_effects(game_state, current_time);
+        if game_state.game_over {
+            return events;
+        }
+
+        // Nothing spawns or falls until the map and the game's speed are known
+        let scale_pending = self.config.time_scale > 1 && game_state.time_scale == 1;
+        let map_pending = self.config.map.is_some() && game_state.map_seed.is_none();
+        if scale_pending || map_pending {
+            if scale_pending {
+                events.extend(self.set_time_scale(current_time));
+            }
+            if map_pending {
+                events.extend(self.generate_map(game_state, current_time));
+            }
+            return events;
+        }
+
+        events.extend(self.set_retarget_cooldown(game_state, current_time));
+        events.extend(self.set_elimination_policy(game_state, current_time));
+        events.extend(self.set_metrics_interval(game_state, current_time));
+        events.extend(self.set_features(game_state, current_time));
+        events.extend(self.warn_time_limit(game_state, current_time));
+        events.extend(self.start_sudden_death(game_state, current_time));
+        events.extend(self.expire_contests(game_state, current_time));
+        events.extend(self.decay_nodes(game_state, current_time));
+        events.extend(self.fire_scheduled(game_state, current_time));
+        events.extend(self.detect_idle_owners(game_state, current_time));
+        events.extend(self.lazy_init(game_state, current_time));
+
+        let rules = self.config.capture_rules();
+        // With supply lines, far-flung attackers count for less
+        let weighted;
+        let capture_state = match self.config.supply {
+            Some(supply) => {
+                weighted = supply.weight_metrics(game_state);
+                &weighted
+            }
+            None => game_state,
+        };
+        // A pinned CPU counts towards overload too, if the game says so
+        let loaded;
+        let capture_state = if self.config.cpu_overload_weight > 0.0 {
+            loaded = cpu_weighted(capture_state, self.config.cpu_overload_weight);
+            &loaded
+        } else {
+            capture_state
+        };
+        let captures = self.capture_tracker.tick(&rules, capture_state, current_time);
+        events.extend(self.contest_capitals(game_state, captures, current_time));
+
+        events
+    }
+}
+
+/// An announcement of the leader's own, shown for LEADER_ANNOUNCEMENT_TTL_SECS
+fn announcement(text: &str, severity: AnnouncementSeverity, timestamp: u64) -> GameEvent {
+    GameEvent::Fact(FactEvent::Announcement {
+        text: text.to_string(),
+        severity,
+        ttl_secs: LEADER_ANNOUNCEMENT_TTL_SECS,
+        timestamp,
+    })
+}
+
+/// `game_state` with each node's loss raised by how saturated its CPU is: nothing up to
+/// CPU_SATURATION_START, `weight` at 100% (loss stays within 1.0). Nodes whose worker
+/// doesn't report its CPU are left as they are.
+fn cpu_weighted(game_state: &GameState, weight: f32) -> GameState {
+    let mut weighted = game_state.clone();
+    for metrics in weighted.node_metrics.values_mut() {
+        let Some(cpu) = metrics.cpu_utilization else { continue };
+        let saturation = ((cpu - CPU_SATURATION_START) / (1.0 - CPU_SATURATION_START)).clamp(0.0, 1.0);
+        metrics.packet_loss = (metrics.packet_loss + weight * saturation).min(1.0);
+    }
+    weighted
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::game::events::NodeType;
-    use crate::game::state::{Node, NodeMetrics, Player};
+    use crate::game::events::{CommandEvent, NodeType, TelemetryEvent};
+    use crate::game::state::{Node, NodeInitState, NodeMetrics, Player};
 
     #[test]
     fn test_capture_after_sustained_overload() {
         let mut logic = GameLogic::new(GameConfig {
             overload_duration_secs: 5,
             overload_threshold: 0.5,
+            // The target is a capital - keep it to the thresholds above
+            capacity_tiers: TierScaling::FLAT,
+            capital_grace_secs: 0,
+            ..GameConfig::default()
         });
 
         let mut game_state = GameState::new();
@@ -137,8 +868,14 @@ mod tests {
                 player_id: 1,
                 name: "Alice".to_string(),
                 capital_coord: NodeCoord::new(0, 0),
+                client_endpoint: None,
                 alive: true,
                 join_time: 1000,
+                protected_until: 0,
+                last_heartbeat: 1000,
+                idle_since: None,
+                idle_neutralized: false,
+                reconnect_key: None,
             },
         );
         game_state.players.insert(
@@ -147,8 +884,14 @@ mod tests {
                 player_id: 2,
                 name: "Bob".to_string(),
                 capital_coord: NodeCoord::new(1, 0),
+                client_endpoint: None,
                 alive: true,
                 join_time: 1001,
+                protected_until: 0,
+                last_heartbeat: 1001,
+                idle_since: None,
+                idle_neutralized: false,
+                reconnect_key: None,
             },
         );
 
@@ -160,7 +903,7 @@ mod tests {
                 owner_id: 1,
                 node_type: NodeType::Capital,
                 current_target: None,
-                is_client: false,
+                init_state: NodeInitState::Ready,
             },
         );
         game_state.nodes.insert(
@@ -170,7 +913,7 @@ mod tests {
                 owner_id: 2,
                 node_type: NodeType::Capital,
                 current_target: Some(AttackTarget::Coordinate(NodeCoord::new(0, 0))), // Bob attacks Alice
-                is_client: false,
+                init_state: NodeInitState::Ready,
             },
         );
 
@@ -181,27 +924,710 @@ mod tests {
                 bandwidth_in: 20_000_000,
                 packet_loss: 0.7, // 70% loss
                 timestamp: 2000,
+                queued_attackers: Vec::new(),
+                cpu_utilization: None,
+                memory_pressure: None,
             },
         );
 
         // First tick - should not capture yet (not sustained)
-        let events = logic.tick(&game_state);
+        let now = logic.last_neighbor_expansion;
+        let events = logic.tick_at(&game_state, now);
         assert_eq!(events.len(), 0);
 
-        // Simulate time passing (advance tracker manually for test)
-        if let Some((_, start_time)) = logic.attack_tracker.overload_start_times.get_mut(&NodeCoord::new(0, 0)) {
-            *start_time -= 10; // Pretend it started 10 seconds ago
-        }
-
-        // Second tick - should capture now (sustained overload)
-        let events = logic.tick(&game_state);
+        // Second tick 6 seconds later - should capture now (sustained overload)
+        let events = logic.tick_at(&game_state, now + 6);
         assert_eq!(events.len(), 1);
         match &events[0] {
-            GameEvent::NodeCaptured { node_coord, new_owner_id, .. } => {
+            GameEvent::Fact(FactEvent::NodeCaptured { node_coord, new_owner_id, .. }) => {
                 assert_eq!(*node_coord, NodeCoord::new(0, 0));
                 assert_eq!(*new_owner_id, 2); // Bob captures
             }
             _ => panic!("Expected NodeCaptured event"),
         }
     }
+
+    #[test]
+    fn test_cpu_saturation_counts_towards_overload() {
+        let metrics = |packet_loss: f32, cpu_utilization: Option<f32>| NodeMetrics {
+            bandwidth_in: 20_000_000,
+            packet_loss,
+            timestamp: 2000,
+            queued_attackers: Vec::new(),
+            cpu_utilization,
+            memory_pressure: None,
+        };
+        let mut game_state = GameState::new();
+        game_state.node_metrics.insert(NodeCoord::new(0, 0), metrics(0.3, Some(0.9)));
+        game_state.node_metrics.insert(NodeCoord::new(1, 0), metrics(0.3, Some(0.5)));
+        game_state.node_metrics.insert(NodeCoord::new(2, 0), metrics(0.3, None));
+        game_state.node_metrics.insert(NodeCoord::new(3, 0), metrics(0.9, Some(1.0)));
+
+        let weighted = cpu_weighted(&game_state, 0.5);
+        let loss = |q| weighted.node_metrics[&NodeCoord::new(q, 0)].packet_loss;
+        // Half-way to saturation adds half the weight, which tips 30% loss over a 50% threshold
+        assert!((loss(0) - 0.55).abs() < 1e-5);
+        // Below the saturation point, or with no CPU reported, loss is untouched
+        assert_eq!(loss(1), 0.3);
+        assert_eq!(loss(2), 0.3);
+        // Never past total loss
+        assert_eq!(loss(3), 1.0);
+    }
+
+    #[test]
+    fn test_supply_lines_weaken_distant_attacks() {
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 5)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 0,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        // Bob reaches all the way over to Alice's doorstep, 4 hops from home
+        let target = NodeCoord::new(1, 1);
+        game_state.process_event(FactEvent::NodeInitializationStarted { node_coord: target, owner_id: 1, timestamp: 0 }.into(), 3);
+        for (index, q) in (4..).zip([4, 3, 2, 1]) {
+            let node_coord = NodeCoord::new(q, 0);
+            game_state.process_event(FactEvent::NodeInitializationStarted { node_coord, owner_id: 2, timestamp: 0 }.into(), index);
+        }
+        let attack = Some(AttackTarget::Coordinate(target));
+        game_state.process_event(CommandEvent::SetNodeTarget { node_coord: NodeCoord::new(1, 0), target: attack, timestamp: 0 }.into(), 8);
+        let report = TelemetryEvent::NodeMetricsReport {
+            node_coord: target,
+            bandwidth_in: 1_000_000,
+            packet_loss: 0.7,
+            timestamp: 0,
+            queued_attackers: Vec::new(),
+        };
+        game_state.process_event(report.into(), 9);
+
+        let config = GameConfig {
+            overload_duration_secs: 5,
+            overload_threshold: 0.5,
+            capacity_tiers: TierScaling::FLAT,
+            client_idle_secs: 0,
+            neighbor_expansion_interval_secs: 1_000_000,
+            ..GameConfig::default()
+        };
+        let captures = |config: GameConfig| {
+            let mut logic = GameLogic::new(config);
+            logic.last_neighbor_expansion = 100;
+            logic.tick_at(&game_state, 100);
+            logic
+                .tick_at(&game_state, 106)
+                .into_iter()
+                .filter(|e| matches!(e, GameEvent::Fact(FactEvent::NodeCaptured { .. })))
+                .count()
+        };
+
+        // Full strength it falls; at half strength 70% loss only counts as 35%
+        assert_eq!(captures(config), 1);
+        let supply = SupplyConfig { free_hops: 2, falloff_per_hop: 0.25, min_effectiveness: 0.25 };
+        assert_eq!(captures(GameConfig { supply: Some(supply), ..config }), 0);
+    }
+
+    #[test]
+    fn test_parse_elimination_policy() {
+        assert_eq!(parse_elimination_policy(" Keep ", 10), Ok(None));
+        assert_eq!(parse_elimination_policy("decay", 0), Ok(Some(EliminationPolicy::Decay { interval_secs: 1 })));
+        assert_eq!(parse_elimination_policy("donate", 10), Err("donate".to_string()));
+    }
+
+    #[test]
+    fn test_time_limit_calls_sudden_death() {
+        let mut logic = GameLogic::new(GameConfig { time_limit_secs: 60, client_idle_secs: 0, ..GameConfig::default() });
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 100,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        assert_eq!(game_state.phase, GamePhase::Live);
+
+        // Live since 100 with a minute on the clock: sudden death at 160, asked for once
+        assert!(logic.start_sudden_death(&game_state, 159).is_empty());
+        let events = logic.start_sudden_death(&game_state, 160);
+        assert!(matches!(events[0], GameEvent::Fact(FactEvent::PhaseChanged { phase: GamePhase::SuddenDeath, .. })));
+        assert!(matches!(&events[1], GameEvent::Fact(FactEvent::Announcement { severity: AnnouncementSeverity::Critical, .. })));
+        assert!(logic.start_sudden_death(&game_state, 161).is_empty());
+        for (index, event) in (3..).zip(events) {
+            game_state.process_event(event, index);
+        }
+        assert_eq!(game_state.phase, GamePhase::SuddenDeath);
+        let mut next_leader = GameLogic::new(*logic.config());
+        assert!(next_leader.start_sudden_death(&game_state, 200).is_empty());
+
+        // A minute's limit is too short for a warning
+        assert!(logic.warn_time_limit(&game_state, 150).is_none());
+
+        // No limit, no sudden death
+        let mut untimed = GameLogic::new(GameConfig::default());
+        assert!(untimed.start_sudden_death(&GameState::new(), u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_time_warning_before_sudden_death() {
+        let config = GameConfig { time_limit_secs: 600, client_idle_secs: 0, ..GameConfig::default() };
+        let mut logic = GameLogic::new(config);
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 100,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+
+        // Sudden death at 700, so the warning goes out at 400, once
+        assert!(logic.warn_time_limit(&game_state, 399).is_none());
+        let Some(warning) = logic.warn_time_limit(&game_state, 400) else {
+            panic!("expected an Announcement");
+        };
+        assert!(logic.warn_time_limit(&game_state, 401).is_none());
+        game_state.process_event(warning, 3);
+        assert_eq!(game_state.active_announcements(401).count(), 1);
+
+        // The next leader finds it committed
+        let mut next_leader = GameLogic::new(config);
+        assert!(next_leader.warn_time_limit(&game_state, 450).is_none());
+    }
+
+    #[test]
+    fn test_features_gate_sudden_death() {
+        let features = FeatureFlags::default().without(Feature::SuddenDeath);
+        let config = GameConfig { time_limit_secs: 60, features, ..GameConfig::default() };
+        assert_eq!(config.preset_name(), "standard");
+        let mut logic = GameLogic::new(config);
+        let mut game_state = GameState::new();
+
+        // Committed once, and the default set is never committed
+        let Some(event) = logic.set_features(&game_state, 100) else {
+            panic!("expected FeaturesSet");
+        };
+        assert!(logic.set_features(&game_state, 101).is_none());
+        assert!(GameLogic::new(GameConfig::default()).set_features(&game_state, 100).is_none());
+        game_state.process_event(event, 1);
+        assert_eq!(game_state.features, Some(features));
+
+        for (id, q) in [(2, 0), (3, 3)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 100,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        // The committed set wins over a later leader's own
+        let mut next_leader = GameLogic::new(GameConfig { time_limit_secs: 60, ..GameConfig::default() });
+        assert!(logic.start_sudden_death(&game_state, 1000).is_empty());
+        assert!(next_leader.start_sudden_death(&game_state, 1000).is_empty());
+        assert_eq!(next_leader.features(&game_state), features);
+    }
+
+    #[test]
+    fn test_elimination_policy_and_decay() {
+        let policy = EliminationPolicy::Decay { interval_secs: 10 };
+        let mut logic = GameLogic::new(GameConfig { elimination_policy: Some(policy), client_idle_secs: 0, ..GameConfig::default() });
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1), (3, 2)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 0,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        for coord in [NodeCoord::new(0, 1), NodeCoord::new(0, 2)] {
+            game_state.process_event(FactEvent::NodeInitializationStarted { node_coord: coord, owner_id: 1, timestamp: 0 }.into(), 4);
+        }
+
+        // Committed once; a new leader sees it
+        let Some(event) = logic.set_elimination_policy(&game_state, 100) else {
+            panic!("expected EliminationPolicySet");
+        };
+        assert!(logic.set_elimination_policy(&game_state, 101).is_none());
+        game_state.process_event(event, 5);
+        let mut next_leader = GameLogic::new(*logic.config());
+        assert!(next_leader.set_elimination_policy(&game_state, 102).is_none());
+
+        // Alice falls at 200: a node decays at 210, asked for once, the next at 220
+        game_state.process_event(FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 200 }.into(), 6);
+        assert!(logic.decay_nodes(&game_state, 209).is_empty());
+        let events = logic.decay_nodes(&game_state, 210);
+        let [GameEvent::Fact(FactEvent::NodeDecayed { node_coord, player_id: 1, .. })] = &events[..] else {
+            panic!("expected one NodeDecayed, got {:?}", events);
+        };
+        assert_eq!(*node_coord, NodeCoord::new(0, 2));
+        assert!(logic.decay_nodes(&game_state, 211).is_empty());
+        game_state.process_event(events[0].clone(), 7);
+        assert!(logic.decay_nodes(&game_state, 219).is_empty());
+
+        // Bob grabs the last one first: the pending decay is dropped, nothing is left
+        let events = logic.decay_nodes(&game_state, 220);
+        assert_eq!(events.len(), 1);
+        game_state.process_event(FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 1), new_owner_id: 2, timestamp: 220 }.into(), 8);
+        game_state.process_event(events[0].clone(), 9);
+        assert!(logic.decay_nodes(&game_state, 300).is_empty());
+        assert!(logic.decays_requested.is_empty());
+        assert_eq!(game_state.nodes[&NodeCoord::new(0, 1)].owner_id, 2);
+    }
+
+    #[test]
+    fn test_retarget_cooldown_committed_once() {
+        let mut state = GameState::new();
+        let mut logic = GameLogic::new(GameConfig::default());
+        assert!(logic.set_retarget_cooldown(&state, 100).is_none());
+
+        let mut logic = GameLogic::new(GameConfig { retarget_cooldown_secs: 10, ..GameConfig::default() });
+        let Some(event) = logic.set_retarget_cooldown(&state, 100) else {
+            panic!("expected RetargetCooldownSet");
+        };
+        assert!(matches!(event, GameEvent::Fact(FactEvent::RetargetCooldownSet { secs: 10, .. })));
+        assert!(logic.set_retarget_cooldown(&state, 101).is_none());
+
+        // A new leader sees it committed
+        state.process_event(event, 1);
+        let mut logic = GameLogic::new(GameConfig { retarget_cooldown_secs: 10, ..GameConfig::default() });
+        assert!(logic.set_retarget_cooldown(&state, 102).is_none());
+    }
+
+    #[test]
+    fn test_uncommitted_requests_are_emitted_again() {
+        let state = GameState::new();
+        let mut logic = GameLogic::new(GameConfig { retarget_cooldown_secs: 10, ..GameConfig::default() });
+        let events = logic.tick_at(&state, 100);
+        assert!(matches!(events[..], [GameEvent::Fact(FactEvent::RetargetCooldownSet { secs: 10, .. })]));
+        assert!(logic.tick_at(&state, 101).is_empty());
+
+        // The write failed: forgotten, it goes out again
+        let request = Requested::of(&events[0]).unwrap();
+        assert_eq!(request, Requested::Cooldown);
+        logic.forget(&request);
+        assert_eq!(logic.tick_at(&state, 102).len(), 1);
+        assert!(logic.tick_at(&state, 103).is_empty());
+
+        // Nor does a request from an earlier term outlive a new one
+        logic.take_leadership(&state);
+        assert_eq!(logic.tick_at(&state, 104).len(), 1);
+    }
+
+    #[test]
+    fn test_metrics_interval_committed_once() {
+        let mut state = GameState::new();
+        let mut logic = GameLogic::new(GameConfig::default());
+        assert!(logic.set_metrics_interval(&state, 100).is_none());
+
+        let mut logic = GameLogic::new(GameConfig { metrics_interval_ms: 500, ..GameConfig::default() });
+        let Some(event) = logic.set_metrics_interval(&state, 100) else {
+            panic!("expected MetricsIntervalSet");
+        };
+        assert!(matches!(event, GameEvent::Fact(FactEvent::MetricsIntervalSet { interval_ms: 500, .. })));
+        assert!(logic.set_metrics_interval(&state, 101).is_none());
+
+        state.process_event(event, 1);
+        let mut logic = GameLogic::new(GameConfig { metrics_interval_ms: 500, ..GameConfig::default() });
+        assert!(logic.set_metrics_interval(&state, 102).is_none());
+    }
+
+    #[test]
+    fn test_time_scale_committed_first() {
+        let mut logic = GameLogic::new(GameConfig { capital_grace_secs: 30, client_idle_secs: 0, time_scale: 10, ..GameConfig::default() });
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 0,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        logic.last_neighbor_expansion = 0;
+
+        // Nothing else happens until the scale is committed, and it's only asked for once
+        let events = logic.tick_at(&game_state, 100);
+        let [GameEvent::Fact(FactEvent::TimeScaleSet { scale: 10, .. })] = &events[..] else {
+            panic!("expected only the time scale, got {:?}", events);
+        };
+        assert!(logic.tick_at(&game_state, 101).is_empty());
+        game_state.process_event(events[0].clone(), 3);
+        assert!(!logic.tick_at(&game_state, 102).is_empty());
+
+        // The 30s capital grace window is 3s of wall time now
+        let capture = FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 110 };
+        let events = logic.contest_capitals(&game_state, vec![capture], 110);
+        assert!(matches!(
+            events[0],
+            GameEvent::Fact(FactEvent::CapitalContested { player_id: 1, expires_at: 113, .. })
+        ));
+    }
+
+    #[test]
+    fn test_map_generated_once_and_chasms_stay_empty() {
+        let map = MapConfig { seed: 42, radius: 6 };
+        let mut logic = GameLogic::new(GameConfig { map: Some(map), ..GameConfig::default() });
+        let mut game_state = GameState::new();
+
+        // Find a chasm next to a passable hex, and put an owned node on the passable one
+        let tiles = terrain::generate(map.seed, map.radius);
+        let (home, chasm) = tiles
+            .iter()
+            .filter(|t| t.terrain == Terrain::Chasm)
+            .find_map(|t| {
+                let home = t.coord.neighbors().into_iter().find(|n| tiles.iter().all(|o| o.coord != *n))?;
+                Some((home, t.coord))
+            })
+            .expect("a chasm with a plain neighbor");
+        game_state.nodes.insert(
+            home,
+            Node {
+                coord: home,
+                owner_id: 1,
+                node_type: NodeType::Regular,
+                current_target: Some(AttackTarget::Coordinate(chasm)),
+                init_state: NodeInitState::Ready,
+            },
+        );
+
+        let events: Vec<_> = logic.tick(&game_state).into_iter().map(|(event, _)| event).collect();
+        let [GameEvent::Fact(FactEvent::MapGenerated { seed: 42, .. })] = &events[..] else {
+            panic!("expected only the map, got {:?}", events);
+        };
+        assert!(logic.tick(&game_state).is_empty());
+
+        // Committed: no second map, and neighbor expansion never spawns into the chasm
+        game_state.process_event(events[0].clone(), 1);
+        logic.last_neighbor_expansion -= 10;
+        let spawned: Vec<_> = logic
+            .tick(&game_state)
+            .into_iter()
+            .filter_map(|(e, _)| match e {
+                GameEvent::Fact(FactEvent::NodeInitializationStarted { node_coord, .. }) => Some(node_coord),
+                GameEvent::Fact(FactEvent::MapGenerated { .. }) => panic!("map generated twice"),
+                _ => None,
+            })
+            .collect();
+        assert!(!spawned.is_empty());
+        assert!(!spawned.contains(&chasm));
+    }
+
+    #[test]
+    fn test_lazy_init_target_once() {
+        let mut logic = GameLogic::new(GameConfig::default());
+        let mut game_state = GameState::new();
+
+        game_state.nodes.insert(
+            NodeCoord::new(0, 0),
+            Node {
+                coord: NodeCoord::new(0, 0),
+                owner_id: 1,
+                node_type: NodeType::Capital,
+                current_target: Some(AttackTarget::Coordinate(NodeCoord::new(1, 0))), // No node there yet
+                init_state: NodeInitState::Ready,
+            },
+        );
+
+        let events: Vec<_> = logic.tick(&game_state).into_iter().map(|(event, _)| event).collect();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            GameEvent::Fact(FactEvent::NodeInitializationStarted { node_coord, owner_id: 0, .. })
+                if node_coord == NodeCoord::new(1, 0)
+        ));
+        assert!(matches!(
+            &events[1],
+            GameEvent::Fact(FactEvent::EffectRequested { effect: Effect::SpawnNode { node_coord }, .. })
+                if *node_coord == NodeCoord::new(1, 0)
+        ));
+
+        // Not committed yet - must not be emitted again
+        assert!(logic.tick(&game_state).is_empty());
+
+        // Commit it; the request is forgotten once the node exists
+        game_state.process_event(events[0].clone(), 1);
+        assert!(logic.tick(&game_state).is_empty());
+        assert!(logic.init_requested.is_empty());
+    }
+
+    #[test]
+    fn test_neighbor_expansion_interval() {
+        let mut logic = GameLogic::new(GameConfig::default());
+        let mut game_state = GameState::new();
+
+        game_state.nodes.insert(
+            NodeCoord::new(0, 0),
+            Node {
+                coord: NodeCoord::new(0, 0),
+                owner_id: 1,
+                node_type: NodeType::Capital,
+                current_target: None,
+                init_state: NodeInitState::Ready,
+            },
+        );
+
+        // Interval hasn't elapsed since construction
+        assert!(logic.tick(&game_state).is_empty());
+
+        logic.last_neighbor_expansion -= 10;
+        assert_eq!(logic.tick(&game_state).len(), 12);  // Init + spawn request per neighbor
+    }
+
+    #[test]
+    fn test_final_kill_requested_once() {
+        let mut logic = GameLogic::new(GameConfig::default());
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: Some(format!("192.168.1.{}", id)),
+                    timestamp: 0,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        let capture = FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 5 };
+        game_state.process_event(capture.into(), 3);
+        assert!(game_state.game_over);
+
+        let events: Vec<_> = logic.tick(&game_state).into_iter().map(|(event, _)| event).collect();
+        let effects: Vec<_> = events
+            .iter()
+            .map(|e| match e {
+                GameEvent::Fact(FactEvent::EffectRequested { effect, .. }) => effect.clone(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            effects,
+            vec![
+                Effect::FinalKill {
+                    player_id: 1,
+                    client_endpoint: "192.168.1.1".to_string(),
+                    attacker_nodes: vec![NodeCoord::new(0, 0), NodeCoord::new(1, 0)],
+                },
+            ]
+        );
+
+        // It doesn't repeat, before or after commit
+        assert!(logic.tick(&game_state).is_empty());
+        for (i, event) in events.into_iter().enumerate() {
+            game_state.process_event(event, 4 + i as u64);
+        }
+        assert!(logic.tick(&game_state).is_empty());
+        assert!(logic.effects_requested.is_empty());
+    }
+
+    #[test]
+    fn test_capital_grace_defers_elimination() {
+        // Nobody heartbeats here - keep the idle hand-off out of the way
+        let mut logic =
+            GameLogic::new(GameConfig { capital_grace_secs: 30, client_idle_secs: 0, ..GameConfig::default() });
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: Some(format!("192.168.1.{}", id)),
+                    timestamp: 0,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+
+        // The capture is preceded by a contest, so Alice survives it
+        let capture = FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 100 };
+        let events = logic.contest_capitals(&game_state, vec![capture], 100);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            GameEvent::Fact(FactEvent::CapitalContested { player_id: 1, expires_at: 130, .. })
+        ));
+        for (i, event) in events.into_iter().enumerate() {
+            game_state.process_event(event, 3 + i as u64);
+        }
+        assert!(game_state.players[&1].alive);
+        assert!(!game_state.game_over);
+
+        // Nothing happens while the window is open, one elimination when it closes
+        assert!(logic.tick_at(&game_state, 129).is_empty());
+        let events = logic.tick_at(&game_state, 130);
+        assert!(matches!(events[..], [GameEvent::Fact(FactEvent::PlayerEliminated { player_id: 1, .. })]));
+        assert!(logic.tick_at(&game_state, 131).is_empty());
+
+        // Once it commits, the final kill follows as usual
+        game_state.process_event(events[0].clone(), 5);
+        assert!(game_state.game_over);
+        let kinds: Vec<_> = logic
+            .tick_at(&game_state, 132)
+            .iter()
+            .map(|e| match e {
+                GameEvent::Fact(FactEvent::EffectRequested { effect, .. }) => effect.kind(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(kinds, vec!["FinalKill"]);
+    }
+
+    #[test]
+    fn test_idle_owner_stood_down_then_neutralized() {
+        let mut logic =
+            GameLogic::new(GameConfig { client_idle_secs: 30, idle_neutralize_secs: 60, ..GameConfig::default() });
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: Some(format!("192.168.1.{}", id)),
+                    timestamp: 100,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        game_state.process_event(GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id: 2, timestamp: 125 }), 3);
+
+        // Alice's client has been quiet for 30s, Bob's hasn't
+        assert!(logic.detect_idle_owners(&game_state, 129).is_empty());
+        let events = logic.detect_idle_owners(&game_state, 130);
+        assert!(matches!(
+            events[..],
+            [GameEvent::Fact(FactEvent::OwnerIdle { player_id: 1, neutralize: false, .. })]
+        ));
+        assert!(logic.detect_idle_owners(&game_state, 131).is_empty());
+        game_state.process_event(events[0].clone(), 4);
+
+        // A minute later her nodes go neutral - once (Bob's client keeps talking)
+        game_state.process_event(GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id: 2, timestamp: 185 }), 5);
+        assert!(matches!(
+            logic.detect_idle_owners(&game_state, 190)[..],
+            [GameEvent::Fact(FactEvent::OwnerIdle { player_id: 1, neutralize: true, .. })]
+        ));
+        assert!(logic.detect_idle_owners(&game_state, 191).is_empty());
+
+        // Her client comes back before that lands: the request is dropped
+        game_state.process_event(GameEvent::Telemetry(TelemetryEvent::ClientHeartbeat { player_id: 1, timestamp: 191 }), 6);
+        assert!(logic.detect_idle_owners(&game_state, 192).is_empty());
+        assert!(logic.idle_requested.is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_events_fire_once() {
+        let mut logic = GameLogic::new(GameConfig::default());
+        let mut game_state = GameState::new();
+        for (id, q) in [(1, 0), (2, 1)] {
+            game_state.process_event(
+                CommandEvent::PlayerJoin {
+                    player_id: id,
+                    name: format!("P{}", id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", id)),
+                    client_endpoint: None,
+                    timestamp: 100,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }
+                .into(),
+                id,
+            );
+        }
+        let schedule = |target: AttackTarget, execute_at: u64| CommandEvent::ScheduleEvent {
+            execute_at,
+            command: Box::new(CommandEvent::SetNodeTarget { node_coord: NodeCoord::new(0, 0), target: Some(target), timestamp: 100 }),
+            timestamp: 100,
+        };
+        // Bob's capital is spawn-protected until 160; the second one fires after that
+        game_state.process_event(schedule(AttackTarget::Coordinate(NodeCoord::new(1, 0)), 120).into(), 3);
+        game_state.process_event(schedule(AttackTarget::Coordinate(NodeCoord::new(1, 0)), 200).into(), 4);
+
+        assert!(logic.fire_scheduled(&game_state, 119).is_empty());
+        let events = logic.fire_scheduled(&game_state, 120);
+        assert!(matches!(
+            &events[..],
+            [GameEvent::Fact(FactEvent::ScheduledEventFired { schedule_id: 3, refused: Some(_), .. })]
+        ));
+        assert!(logic.fire_scheduled(&game_state, 121).is_empty());
+        game_state.process_event(events[0].clone(), 5);
+        assert_eq!(game_state.nodes[&NodeCoord::new(0, 0)].current_target, None);
+
+        let events = logic.fire_scheduled(&game_state, 200);
+        assert!(matches!(
+            &events[..],
+            [GameEvent::Fact(FactEvent::ScheduledEventFired { schedule_id: 4, refused: None, .. })]
+        ));
+        game_state.process_committed(events[0].clone(), 6, 200);
+        assert!(game_state.nodes[&NodeCoord::new(0, 0)].current_target.is_some());
+        assert!(logic.fire_scheduled(&game_state, 201).is_empty());
+        assert!(logic.scheduled_fired.is_empty());
+    }
 }
@@ -1,13 +1,41 @@
-pub mod events;
+// Rules core lives in camhack-rules (no_std, also compiled to WASM for the frontend)
+pub use camhack_rules::{capture, coord, events, features, grid, invariants, settings, state, terrain, upcast};
+
+pub mod awards;
+pub mod effects;
 pub mod finalkill;
-pub mod grid;
+pub mod history;
 pub mod logic;
+pub mod netsim;
 pub mod network;
-pub mod state;
+pub mod pathfinding;
+pub mod presets;
+pub mod replay;
+pub mod shutdown;
+pub mod spawner;
+pub mod summary;
+pub mod supply;
+pub mod timetravel;
+pub mod transport;
 pub mod udp;
+pub mod udp_batch;
 
-pub use events::{AttackTarget, GameEvent, NodeCoord, NodeType};
+pub use events::{
+    AttackOrder, AttackTarget, CommandEvent, DefenseOrder, Effect, EventFamily, FactEvent, GameEvent, GamePhase, NodeCoord, NodeType,
+    RefereeOverride, TelemetryEvent,
+};
+pub use awards::Awards;
+pub use effects::EffectsExecutor;
+pub use features::{Feature, FeatureFlags};
 pub use finalkill::FinalKillManager;
+pub use history::{MetricsHistory, MetricsSample};
+pub use capture::{CaptureRules, CaptureTracker, RULES_PRESETS};
 pub use logic::{GameConfig, GameLogic};
+pub use netsim::NetSimConfig;
 pub use network::NetworkManager;
-pub use state::{GameState, Node, Player};
+pub use pathfinding::{PathCosts, PathPlan};
+pub use shutdown::{ShutdownAction, ShutdownCoordinator, ShutdownPhase};
+pub use spawner::{spawn_with_retries, SpawnRetryPolicy};
+pub use state::{AttackError, GameState, Node, Player, StateChange};
+pub use terrain::{SpawnTier, Terrain, TerrainTile};
+pub use summary::{GameSetup, GameSummary, LeaderInfo};
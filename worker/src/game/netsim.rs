@@ -0,0 +1,209 @@
+use rand::Rng;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Artificial network conditions for the UDP attack layer
+///
+/// Lets capture thresholds, reflection and metric windows be tuned on a laptop,
+/// where loopback never drops or delays anything. Applied on the receiving side
+/// of each hop: the responder sees impaired attack packets, the attacker sees
+/// impaired ACKs. Latency is one-way, so the ACK round trip is ~2x `latency`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetSimConfig {
+    /// Base one-way delay
+    pub latency: Duration,
+    /// Extra uniformly random delay added on top of `latency`
+    pub jitter: Duration,
+    /// Probability (0.0-1.0) that a packet is dropped
+    pub loss: f32,
+}
+
+impl NetSimConfig {
+    /// No impairment - packets go straight through
+    pub fn disabled() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            loss: 0.0,
+        }
+    }
+
+    /// Look up a named profile
+    /// off | lan | wifi | congested | lossy
+    pub fn profile(name: &str) -> Option<Self> {
+        let (latency_ms, jitter_ms, loss) = match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" | "" => return Some(Self::disabled()),
+            "lan" => (1, 1, 0.0),
+            "wifi" => (15, 10, 0.01),
+            "congested" => (80, 40, 0.05),
+            "lossy" => (30, 20, 0.25),
+            _ => return None,
+        };
+
+        Some(Self {
+            latency: Duration::from_millis(latency_ms),
+            jitter: Duration::from_millis(jitter_ms),
+            loss,
+        })
+    }
+
+    /// Load settings from the environment
+    /// NETSIM_PROFILE: named starting point (default: off)
+    /// NETSIM_LATENCY_MS / NETSIM_JITTER_MS / NETSIM_LOSS: override individual fields
+    pub fn from_env() -> Self {
+        let mut config = std::env::var("NETSIM_PROFILE")
+            .ok()
+            .and_then(|name| {
+                let profile = Self::profile(&name);
+                if profile.is_none() {
+                    eprintln!("[NetSim] Unknown profile '{}', ignoring", name);
+                }
+                profile
+            })
+            .unwrap_or_else(Self::disabled);
+
+        if let Some(ms) = std::env::var("NETSIM_LATENCY_MS").ok().and_then(|s| s.parse().ok()) {
+            config.latency = Duration::from_millis(ms);
+        }
+        if let Some(ms) = std::env::var("NETSIM_JITTER_MS").ok().and_then(|s| s.parse().ok()) {
+            config.jitter = Duration::from_millis(ms);
+        }
+        if let Some(loss) = std::env::var("NETSIM_LOSS").ok().and_then(|s| s.parse::<f32>().ok()) {
+            config.loss = loss.clamp(0.0, 1.0);
+        }
+
+        if config.is_enabled() {
+            println!(
+                "[NetSim] Enabled: latency={:?} jitter={:?} loss={:.1}%",
+                config.latency,
+                config.jitter,
+                config.loss * 100.0
+            );
+        }
+
+        config
+    }
+
+    /// Whether any impairment is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero() || self.loss > 0.0
+    }
+
+    /// Roll whether the next packet should be dropped
+    pub fn should_drop(&self) -> bool {
+        self.loss > 0.0 && rand::thread_rng().gen::<f32>() < self.loss
+    }
+
+    /// Sample the delay for the next packet
+    pub fn sample_delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.latency;
+        }
+        let jitter_us = rand::thread_rng().gen_range(0..=self.jitter.as_micros() as u64);
+        self.latency + Duration::from_micros(jitter_us)
+    }
+}
+
+/// Temporary impairment of this node's responder, set through POST /debug/handicap
+///
+/// For demos: makes a node visibly weaker - its attackers see loss climb and ACKs
+/// slow down - so it gets captured on stage without real traffic volumes. Applied on
+/// top of the responder's NetSimConfig until it expires.
+#[derive(Debug, Clone, Default)]
+pub struct Handicap {
+    active: Arc<Mutex<Option<(NetSimConfig, Instant)>>>,
+}
+
+impl Handicap {
+    /// Drop `loss` (0.0-1.0) of inbound attack packets and delay ACKs by `delay` for `duration`
+    pub fn set(&self, loss: f32, delay: Duration, duration: Duration) {
+        let config = NetSimConfig { latency: delay, jitter: Duration::ZERO, loss: loss.clamp(0.0, 1.0) };
+        *self.active.lock().unwrap() = Some((config, Instant::now() + duration));
+    }
+
+    pub fn clear(&self) {
+        *self.active.lock().unwrap() = None;
+    }
+
+    /// The impairment in force and when it ends, None once it has expired
+    pub fn current(&self) -> Option<(NetSimConfig, Instant)> {
+        let mut active = self.active.lock().unwrap();
+        if active.is_some_and(|(_, until)| Instant::now() >= until) {
+            println!("[NetSim] Handicap expired");
+            *active = None;
+        }
+        *active
+    }
+
+    /// `base` with the current handicap (if any) on top
+    pub fn apply(&self, base: NetSimConfig) -> NetSimConfig {
+        match self.current() {
+            None => base,
+            Some((extra, _)) => NetSimConfig {
+                latency: base.latency + extra.latency,
+                jitter: base.jitter + extra.jitter,
+                // Dropped by either
+                loss: 1.0 - (1.0 - base.loss) * (1.0 - extra.loss),
+            },
+        }
+    }
+}
+
+impl Default for NetSimConfig {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_passes_everything() {
+        let config = NetSimConfig::disabled();
+        assert!(!config.is_enabled());
+        assert!((0..1000).all(|_| !config.should_drop()));
+        assert_eq!(config.sample_delay(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_full_loss_drops_everything() {
+        let config = NetSimConfig { loss: 1.0, ..NetSimConfig::disabled() };
+        assert!(config.is_enabled());
+        assert!((0..1000).all(|_| config.should_drop()));
+    }
+
+    #[test]
+    fn test_handicap_stacks_and_expires() {
+        let handicap = Handicap::default();
+        let base = NetSimConfig { latency: Duration::from_millis(10), jitter: Duration::ZERO, loss: 0.5 };
+        assert_eq!(handicap.apply(base), base);
+
+        handicap.set(0.5, Duration::from_millis(200), Duration::from_secs(60));
+        let impaired = handicap.apply(base);
+        assert_eq!(impaired.latency, Duration::from_millis(210));
+        assert!((impaired.loss - 0.75).abs() < 1e-6);
+
+        handicap.set(1.0, Duration::ZERO, Duration::ZERO);
+        assert!(handicap.current().is_none());
+        assert_eq!(handicap.apply(base), base);
+    }
+
+    #[test]
+    fn test_delay_within_jitter_bounds() {
+        let config = NetSimConfig::profile("congested").unwrap();
+        for _ in 0..1000 {
+            let delay = config.sample_delay();
+            assert!(delay >= config.latency);
+            assert!(delay <= config.latency + config.jitter);
+        }
+    }
+
+    #[test]
+    fn test_profiles() {
+        assert_eq!(NetSimConfig::profile("OFF"), Some(NetSimConfig::disabled()));
+        assert!(NetSimConfig::profile("wifi").unwrap().is_enabled());
+        assert_eq!(NetSimConfig::profile("carrier-pigeon"), None);
+    }
+}
@@ -1,13 +1,296 @@
-use super::events::{GameEvent, NodeCoord};
-use super::state::GameState;
-use super::udp::{udp_responder, udp_attacker, PacketLossTracker};
-use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use super::capture::attack_capacity;
+use super::events::NodeCoord;
+use super::netsim::NetSimConfig;
+use super::state::{GameState, StateChange};
+use super::transport::{dry_run_attacker, dry_run_responder, DryRunLink, TransportMode};
+use super::udp::{open_flooder_socket, udp_responder, udp_attacker, InboundStats, PacketLossTracker};
+use super::udp_batch::SocketPool;
+use crate::host_load::{HostLoad, HostLoadSampler};
+use crate::raft::hooks::ApplyHook;
+use crate::raft::storage::GameEventRequest;
+use crate::supervisor::{RestartPolicy, Supervisor};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::net::UdpSocket;
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+/// Default max concurrent UDP flooders per process
+pub const DEFAULT_MAX_FLOODERS: usize = 32;
+
+/// Default max UDP sockets per process (responder + one per flooder)
+pub const DEFAULT_MAX_SOCKETS: usize = 64;
+
+/// Default interval between rotations of queued attackers into flooder slots
+pub const DEFAULT_ROTATION_SECS: u64 = 10;
+
+/// Default share of the egress budget kept back for Raft replication and the HTTP API
+pub const DEFAULT_EGRESS_RESERVED: f32 = 0.2;
+
+/// Limits on how many attack connections one process may hold open
+///
+/// Fargate tasks have modest file descriptor and egress caps, so a target swarmed
+/// by many owned nodes can't flood every attacker at once. Excess attackers are
+/// queued and cycled through the available slots by `schedule_attackers`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkBudget {
+    /// Max concurrent flooder tasks
+    pub max_flooders: usize,
+    /// Max UDP sockets, including the responder
+    pub max_sockets: usize,
+    /// How long a batch of attackers holds its slots before the next batch gets a turn
+    pub rotation_interval: Duration,
+    /// Egress of a regular-capacity task in bytes/sec, 0 = flooders aren't paced
+    /// Scaled by the node's capacity, so capitals and high ground get more.
+    pub egress_bytes_per_sec: u64,
+    /// Share of the egress kept back for consensus and API traffic (0.0 to 1.0)
+    pub egress_reserved: f32,
+    /// Packets/sec a regular-capacity task's responder takes, 0 = unlimited
+    /// Scaled by the node's capacity and raised by defend orders; the rest is dropped unACKed.
+    pub max_packets_per_second: u64,
+}
+
+impl NetworkBudget {
+    /// Load settings from the environment
+    /// NET_MAX_FLOODERS (default: 32), NET_MAX_SOCKETS (default: 64), NET_ROTATION_SECS (default: 10),
+    /// NET_EGRESS_MBPS (default: 0 = unpaced), NET_EGRESS_RESERVED (default: 0.2),
+    /// NET_RESPONDER_PPS (default: 0 = unlimited)
+    pub fn from_env() -> Self {
+        let max_flooders = std::env::var("NET_MAX_FLOODERS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_FLOODERS);
+
+        let max_sockets = std::env::var("NET_MAX_SOCKETS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_SOCKETS);
+
+        let rotation_secs = std::env::var("NET_ROTATION_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_ROTATION_SECS);
+
+        let egress_mbps: f64 = std::env::var("NET_EGRESS_MBPS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0.0);
+
+        let egress_reserved: f32 = std::env::var("NET_EGRESS_RESERVED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_EGRESS_RESERVED);
+
+        let max_packets_per_second = std::env::var("NET_RESPONDER_PPS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+
+        Self {
+            max_flooders,
+            max_sockets,
+            rotation_interval: Duration::from_secs(rotation_secs),
+            egress_bytes_per_sec: (egress_mbps.max(0.0) * 1_000_000.0 / 8.0) as u64,
+            egress_reserved: egress_reserved.clamp(0.0, 1.0),
+            max_packets_per_second,
+        }
+    }
+
+    /// Number of flooders that can run at once (one socket is reserved for the responder)
+    pub fn flooder_slots(&self) -> usize {
+        self.max_flooders.min(self.max_sockets.saturating_sub(1))
+    }
+
+    /// Send rate for each of `flooders` on a node of `capacity`, in bytes/sec
+    /// None when egress isn't budgeted. The reserved slice is taken off the top and
+    /// the rest shared evenly; every flooder gets at least 1 byte/sec.
+    pub fn flooder_rate(&self, capacity: f32, flooders: usize) -> Option<u64> {
+        if self.egress_bytes_per_sec == 0 {
+            return None;
+        }
+        let task = self.egress_bytes_per_sec as f64 * capacity.max(0.0) as f64;
+        let usable = task * (1.0 - self.egress_reserved.clamp(0.0, 1.0) as f64);
+        Some(((usable / flooders.max(1) as f64).round() as u64).max(1))
+    }
+
+    /// Packets/sec the responder of a node of `capacity` takes, with `multiplier`
+    /// from its defense (GameState::responder_multiplier); None when unlimited
+    pub fn responder_limit(&self, capacity: f32, multiplier: f32) -> Option<u64> {
+        if self.max_packets_per_second == 0 {
+            return None;
+        }
+        let limit = self.max_packets_per_second as f64 * capacity.max(0.0) as f64 * multiplier.max(1.0) as f64;
+        Some((limit.round() as u64).max(1))
+    }
+}
+
+impl Default for NetworkBudget {
+    fn default() -> Self {
+        Self {
+            max_flooders: DEFAULT_MAX_FLOODERS,
+            max_sockets: DEFAULT_MAX_SOCKETS,
+            rotation_interval: Duration::from_secs(DEFAULT_ROTATION_SECS),
+            egress_bytes_per_sec: 0,
+            egress_reserved: DEFAULT_EGRESS_RESERVED,
+            max_packets_per_second: 0,
+        }
+    }
+}
+
+/// Split attackers into (active, queued) for the current rotation
+///
+/// Attackers are sorted so every call sees the same order, then a window of
+/// `slots` attackers starting at `offset` (wrapping around) is made active.
+/// Advancing `offset` by `slots` each rotation gives every attacker a turn.
+pub fn schedule_attackers(
+    attackers: &[NodeCoord],
+    slots: usize,
+    offset: usize,
+) -> (Vec<NodeCoord>, Vec<NodeCoord>) {
+    let mut sorted = attackers.to_vec();
+    sorted.sort_by_key(|c| (c.q, c.r));
+    sorted.dedup();
+
+    if sorted.len() <= slots {
+        return (sorted, Vec::new());
+    }
+
+    let start = offset % sorted.len();
+    sorted.rotate_left(start);
+    let queued = sorted.split_off(slots);
+    (sorted, queued)
+}
+
+/// Egress capacity of the task serving `coord`, relative to a regular node on plain
+/// ground - the same capacity the master sized the task by
+fn egress_capacity(game_state: &GameState, coord: NodeCoord) -> f32 {
+    let node_capacity = game_state
+        .nodes
+        .get(&coord)
+        .map(|node| attack_capacity(node.node_type))
+        .unwrap_or(1.0);
+    node_capacity * game_state.terrain_at(coord).capacity_multiplier().max(1.0)
+}
+
+/// Nodes in committed state that are attacking `target`
+fn attackers_of(game_state: &GameState, target: NodeCoord) -> Vec<NodeCoord> {
+    game_state
+        .nodes
+        .values()
+        .filter(|node| node.current_target == Some(super::events::AttackTarget::Coordinate(target)))
+        .map(|node| node.coord)
+        .collect()
+}
+
+/// A running attack connection, as the reconciler sees it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionView {
+    pub target_ip: String,
+    /// False once the flooder task has exited (e.g. its target didn't resolve)
+    pub running: bool,
+}
+
+/// Discrepancies found by one reconciliation pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    /// Scheduled attackers with no connection (restarted)
+    pub missing: usize,
+    /// Connections to nodes that are no longer scheduled (stopped)
+    pub orphaned: usize,
+    /// Connections whose flooder task had exited (restarted)
+    pub dead: usize,
+    /// Connections to an attacker's old IP (restarted)
+    pub stale_ip: usize,
+    /// The UDP responder had exited and was restarted
+    pub responder_restarted: bool,
+    /// This node's coordinate in committed state changed
+    pub coord_changed: bool,
+}
+
+impl ReconcileReport {
+    /// Whether in-memory state already matched committed state
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Cumulative reconciliation counters for this process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileStats {
+    pub runs: u64,
+    /// Runs that found any drift
+    pub drifted_runs: u64,
+    pub missing: u64,
+    pub orphaned: u64,
+    pub dead: u64,
+    pub stale_ip: u64,
+    pub responder_restarts: u64,
+}
+
+impl ReconcileStats {
+    fn record(&mut self, report: &ReconcileReport) {
+        self.runs += 1;
+        if !report.is_clean() {
+            self.drifted_runs += 1;
+        }
+        self.missing += report.missing as u64;
+        self.orphaned += report.orphaned as u64;
+        self.dead += report.dead as u64;
+        self.stale_ip += report.stale_ip as u64;
+        self.responder_restarts += report.responder_restarted as u64;
+    }
+}
+
+/// Connections to stop and start so `active` matches the `desired` attackers
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReconcilePlan {
+    pub stop: Vec<NodeCoord>,
+    pub start: Vec<NodeCoord>,
+    pub report: ReconcileReport,
+}
+
+/// Compare running connections against the attackers committed state says should
+/// be flooding us. Dead and stale connections are stopped and started again;
+/// attackers without an IP yet are left for a later pass, as in `sync_with_game_state`.
+pub fn plan_reconcile(
+    desired: &[NodeCoord],
+    ip_map: &BTreeMap<NodeCoord, String>,
+    active: &HashMap<NodeCoord, ConnectionView>,
+) -> ReconcilePlan {
+    let mut plan = ReconcilePlan::default();
+
+    let mut active_coords: Vec<&NodeCoord> = active.keys().collect();
+    active_coords.sort_by_key(|c| (c.q, c.r));
+    for coord in active_coords {
+        let connection = &active[coord];
+        if !desired.contains(coord) {
+            plan.report.orphaned += 1;
+            plan.stop.push(*coord);
+        } else if !connection.running {
+            plan.report.dead += 1;
+            plan.stop.push(*coord);
+        } else if ip_map.get(coord).is_some_and(|ip| *ip != connection.target_ip) {
+            plan.report.stale_ip += 1;
+            plan.stop.push(*coord);
+        }
+    }
+
+    for coord in desired {
+        if !ip_map.contains_key(coord) {
+            continue;
+        }
+        if !active.contains_key(coord) {
+            plan.report.missing += 1;
+            plan.start.push(*coord);
+        } else if plan.stop.contains(coord) {
+            plan.start.push(*coord);
+        }
+    }
+
+    plan
+}
 
 /// Manages hybrid UDP/WebSocket attack connections and metrics
 /// Capacity determined by actual network infrastructure
@@ -17,45 +300,203 @@ pub struct NetworkManager {
     active_attacks: Arc<RwLock<HashMap<NodeCoord, AttackConnection>>>,
     /// Packet loss trackers for each active UDP attack
     packet_trackers: Arc<RwLock<HashMap<NodeCoord, PacketLossTracker>>>,
-    /// Total bytes received from all attacks (UDP + WebSocket)
-    bytes_received: Arc<AtomicU64>,
-    /// Last measurement time for bandwidth calculation
-    last_measurement: Arc<RwLock<SystemTime>>,
-    /// UDP socket for sending attack packets to workers
-    udp_socket: Option<Arc<UdpSocket>>,
+    /// Bytes received from all attacks since startup, in total and by source
+    inbound: InboundStats,
+    /// Bandwidth since the last streamed sample
+    live_meter: Arc<RwLock<RateMeter>>,
+    /// This worker's CPU and memory use since the last streamed sample
+    host_load: Arc<RwLock<HostLoadSampler>>,
+    /// Simulated latency/loss applied to the UDP attack path (local testing)
+    netsim: NetSimConfig,
+    /// Connection limits for this process
+    budget: NetworkBudget,
+    /// Start of the active window into the sorted attacker list
+    rotation_offset: usize,
+    /// When the active window last advanced
+    last_rotation: Instant,
+    /// Attackers waiting for a flooder slot (reported in metrics)
+    queued_attackers: Vec<NodeCoord>,
+    /// UDP responder task, supervised; `reconcile` respawns it if it was aborted
+    responder: JoinHandle<()>,
+    /// Drift found by `reconcile` since startup
+    reconcile_stats: ReconcileStats,
+    /// Capacity of this task from committed state (see `egress_capacity`)
+    capacity: f32,
+    /// Bytes/sec each flooder may send, 0 = unpaced; read by every flooder on each send
+    flooder_rate: Arc<AtomicU64>,
+    /// Packets/sec the responder takes, 0 = unlimited; read by the responder per batch
+    responder_pps: Arc<AtomicU64>,
+    /// Flooder sockets, one per target host
+    sockets: SocketPool,
+    /// Set by `NetworkChangeHook` when a committed change may alter attack connections
+    sync_pending: Arc<AtomicBool>,
+    /// Real UDP, or dry-run flooders that only simulate their traffic
+    transport: TransportMode,
+    /// Synthetic uplink the dry-run flooders share (unused over UDP)
+    dry_run_link: DryRunLink,
+}
+
+/// Whether `change` can start, stop or reshape an attack connection
+fn affects_network(change: &StateChange) -> bool {
+    matches!(
+        change,
+        StateChange::NodeTargetChanged { .. }
+            | StateChange::NodeOwnerChanged { .. }
+            | StateChange::NodeDefenseChanged { .. }
+            | StateChange::NodeReady { .. }
+            | StateChange::NodeRemoved { .. }
+            | StateChange::MapGenerated
+    )
+}
+
+/// Apply hook marking the manager for a sync when committed changes touch attacks
+///
+/// Runs under the state machine lock, so it only flips a flag; the network tick
+/// does the actual sync.
+pub struct NetworkChangeHook {
+    sync_pending: Arc<AtomicBool>,
+}
+
+impl ApplyHook for NetworkChangeHook {
+    fn name(&self) -> &str {
+        "network"
+    }
+
+    fn on_apply(&self, _log_index: u64, _request: &GameEventRequest, changes: &[StateChange], _game_state: &GameState) {
+        if changes.iter().any(affects_network) {
+            self.sync_pending.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn on_snapshot(&self, _log_index: u64, _game_state: &GameState) {
+        self.sync_pending.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Bandwidth over a running byte total, reading by reading
+#[derive(Debug)]
+struct RateMeter {
+    at: Instant,
+    total: u64,
+}
+
+impl RateMeter {
+    fn new() -> Self {
+        Self { at: Instant::now(), total: 0 }
+    }
+
+    /// Bytes/sec between the previous reading and `total` now
+    fn read(&mut self, total: u64) -> u64 {
+        let now = Instant::now();
+        let secs = now.duration_since(self.at).as_secs_f64().max(0.001);
+        let rate = (total.saturating_sub(self.total) as f64 / secs) as u64;
+        self.at = now;
+        self.total = total;
+        rate
+    }
 }
 
 /// Represents an active UDP attack connection to a grid node
 struct AttackConnection {
-    target_coord: NodeCoord,
     target_ip: String,
     /// Handle to stop the connection
     stop_signal: tokio::sync::broadcast::Sender<()>,
+    /// Flooder task, finished if the attack died on its own
+    task: JoinHandle<()>,
 }
 
 impl NetworkManager {
     pub fn new() -> Self {
+        Self::with_transport(NetSimConfig::from_env(), NetworkBudget::from_env(), TransportMode::from_env())
+    }
+
+    /// Create a manager with explicit network simulation and connection limits
+    pub fn with_config(netsim: NetSimConfig, budget: NetworkBudget) -> Self {
+        Self::with_transport(netsim, budget, TransportMode::Udp)
+    }
+
+    /// Create a manager on an explicit transport (see `TransportMode`)
+    pub fn with_transport(netsim: NetSimConfig, budget: NetworkBudget, transport: TransportMode) -> Self {
         // Start UDP responder to receive incoming attack packets
         // This runs independently and doesn't need to know our coordinate
-        let bytes_received = Arc::new(AtomicU64::new(0));
-        let bytes_received_clone = bytes_received.clone();
-
-        tokio::spawn(async move {
-            if let Err(e) = udp_responder(bytes_received_clone).await {
-                eprintln!("[Network] UDP responder error: {}", e);
-            }
-        });
+        let inbound = InboundStats::new();
+        let responder_pps = Arc::new(AtomicU64::new(0));
+        let responder = Self::spawn_responder(transport, inbound.clone(), netsim, responder_pps.clone());
+        let dry_run_link = DryRunLink::default();
+        if let TransportMode::DryRun(config) = transport {
+            dry_run_link.rate.store(config.link_rate(1.0, 1.0), Ordering::Relaxed);
+        }
 
         Self {
             my_coord: None,
             active_attacks: Arc::new(RwLock::new(HashMap::new())),
             packet_trackers: Arc::new(RwLock::new(HashMap::new())),
-            bytes_received,
-            last_measurement: Arc::new(RwLock::new(SystemTime::now())),
-            udp_socket: None,
+            inbound,
+            live_meter: Arc::new(RwLock::new(RateMeter::new())),
+            host_load: Arc::new(RwLock::new(HostLoadSampler::new())),
+            netsim,
+            budget,
+            rotation_offset: 0,
+            last_rotation: Instant::now(),
+            queued_attackers: Vec::new(),
+            responder,
+            reconcile_stats: ReconcileStats::default(),
+            capacity: 1.0,
+            flooder_rate: Arc::new(AtomicU64::new(0)),
+            responder_pps,
+            sockets: SocketPool::new(),
+            sync_pending: Arc::new(AtomicBool::new(true)),
+            transport,
+            dry_run_link,
+        }
+    }
+
+    /// Hook to register on the storage so committed changes schedule a sync
+    pub fn change_hook(&self) -> Arc<NetworkChangeHook> {
+        Arc::new(NetworkChangeHook { sync_pending: self.sync_pending.clone() })
+    }
+
+    /// Whether `sync_with_game_state` has anything to do, clearing the pending flag
+    /// True after relevant committed changes, while our coordinate is still unknown,
+    /// or while queued attackers wait for their rotation
+    pub fn take_sync_due(&self) -> bool {
+        let pending = self.sync_pending.swap(false, Ordering::SeqCst);
+        pending || self.my_coord.is_none() || !self.queued_attackers.is_empty()
+    }
+
+    fn spawn_responder(transport: TransportMode, inbound: InboundStats, netsim: NetSimConfig, max_pps: Arc<AtomicU64>) -> JoinHandle<()> {
+        if transport.is_dry_run() {
+            return Supervisor::global().spawn("udp_responder", RestartPolicy::always(), dry_run_responder);
+        }
+        Supervisor::global().spawn("udp_responder", RestartPolicy::always(), move || {
+            udp_responder(inbound.clone(), netsim, max_pps.clone())
+        })
+    }
+
+    /// Real UDP or dry run
+    pub fn transport(&self) -> TransportMode {
+        self.transport
+    }
+
+    /// Serve `coord` from now on, without waiting to find our address in committed state
+    /// For a node whose NodeInitializationComplete was just committed (lazy init, adoption).
+    pub fn adopt(&mut self, coord: NodeCoord) {
+        if self.my_coord != Some(coord) {
+            println!("[Network] Adopted coordinate {:?}", coord);
+            self.my_coord = Some(coord);
         }
     }
 
+    /// Drift found by `reconcile` since startup
+    pub fn reconcile_stats(&self) -> ReconcileStats {
+        self.reconcile_stats
+    }
+
+    /// Handle on the responder's inbound counters, total and per source
+    pub fn inbound(&self) -> InboundStats {
+        self.inbound.clone()
+    }
+
     /// Start a UDP attack on a target node
     async fn start_udp_attack(
         &self,
@@ -64,30 +505,51 @@ impl NetworkManager {
     ) -> Result<()> {
         let (stop_tx, stop_rx) = broadcast::channel(1);
 
-        // Create packet loss tracker for this attack
-        let tracker = PacketLossTracker::new();
-
-        // Store tracker
+        // Empty packet loss tracker until the flooder has its socket's
         let mut trackers = self.packet_trackers.write().await;
-        trackers.insert(target_coord, tracker.clone());
+        trackers.insert(target_coord, PacketLossTracker::new());
         drop(trackers);
 
         let target_ip_for_spawn = target_ip.clone();
+        let netsim = self.netsim;
+        let rate = self.flooder_rate.clone();
+        let sockets = self.sockets.clone();
+        let trackers = self.packet_trackers.clone();
+
+        if let TransportMode::DryRun(config) = self.transport {
+            let tracker = trackers.read().await[&target_coord].clone();
+            let (link, inbound) = (self.dry_run_link.clone(), self.inbound.clone());
+            let task = Supervisor::global().spawn_transient("udp_attacker", async move {
+                println!("[Network] Starting dry-run attack on {:?} at {}", target_coord, target_ip_for_spawn);
+                dry_run_attacker(config, link, tracker, inbound, netsim, rate, stop_rx).await
+            });
+            let connection = AttackConnection { target_ip, stop_signal: stop_tx, task };
+            self.active_attacks.write().await.insert(target_coord, connection);
+            return Ok(());
+        }
 
         // Spawn UDP attacker task
-        tokio::spawn(async move {
+        let task = Supervisor::global().spawn_transient("udp_attacker", async move {
             println!("[Network] Starting UDP attack on {:?} at {}", target_coord, target_ip_for_spawn);
 
-            if let Err(e) = udp_attacker(target_ip_for_spawn, tracker, stop_rx).await {
-                eprintln!("[Network] UDP attacker error on {:?}: {}", target_coord, e);
+            let shared = open_flooder_socket(&sockets, &target_ip_for_spawn, netsim)
+                .await
+                .with_context(|| format!("attack on {:?}", target_coord))?;
+            // Loss is tracked per socket; swap it in unless the attack was already stopped
+            if let Some(tracker) = trackers.write().await.get_mut(&target_coord) {
+                *tracker = shared.tracker.clone();
             }
+
+            udp_attacker(shared, stop_rx, rate)
+                .await
+                .with_context(|| format!("attack on {:?}", target_coord))
         });
 
         // Store connection handle
         let connection = AttackConnection {
-            target_coord,
             target_ip,
             stop_signal: stop_tx,
+            task,
         };
 
         let mut attacks = self.active_attacks.write().await;
@@ -96,6 +558,42 @@ impl NetworkManager {
         Ok(())
     }
 
+    /// Split the egress budget across the flooders now running
+    /// Running flooders pick the new rate up on their next send.
+    async fn rebalance_egress(&mut self, game_state: &GameState) {
+        if let Some(my_coord) = self.my_coord {
+            self.capacity = egress_capacity(game_state, my_coord);
+        }
+        let flooders = self.active_attacks.read().await.len();
+        self.dry_run_link.flooders.store(flooders, Ordering::Relaxed);
+        let rate = self.budget.flooder_rate(self.capacity, flooders).unwrap_or(0);
+        if self.flooder_rate.swap(rate, Ordering::Relaxed) != rate && rate > 0 && flooders > 0 {
+            println!(
+                "[Network] Egress budget: {} flooders at {} KB/s each (capacity x{})",
+                flooders,
+                rate / 1024,
+                self.capacity
+            );
+        }
+    }
+
+    /// Set the responder's packet limit from our capacity and the defend orders in
+    /// committed state - ours, and neighbours assisting us
+    fn rebalance_responder(&mut self, game_state: &GameState) {
+        let Some(my_coord) = self.my_coord else {
+            return;
+        };
+        self.capacity = egress_capacity(game_state, my_coord);
+        let multiplier = game_state.responder_multiplier(my_coord);
+        if let TransportMode::DryRun(config) = self.transport {
+            self.dry_run_link.rate.store(config.link_rate(self.capacity, multiplier), Ordering::Relaxed);
+        }
+        let limit = self.budget.responder_limit(self.capacity, multiplier).unwrap_or(0);
+        if self.responder_pps.swap(limit, Ordering::Relaxed) != limit && limit > 0 {
+            println!("[Network] Responder limit: {} packets/s (defense x{})", limit, multiplier);
+        }
+    }
+
     /// Stop an attack connection
     async fn stop_attack_connection(&self, target_coord: NodeCoord) {
         let mut attacks = self.active_attacks.write().await;
@@ -110,48 +608,30 @@ impl NetworkManager {
         trackers.remove(&target_coord);
     }
 
-    /// Get current metrics for all active attacks
-    pub async fn get_metrics(&self) -> Vec<GameEvent> {
-        let my_coord = match self.my_coord {
-            Some(c) => c,
-            None => return Vec::new(),
-        };
-
-        let attacks = self.active_attacks.read().await;
-        if attacks.is_empty() {
-            return Vec::new();
+    /// Bandwidth, loss, queued attackers and host load right now, for the telemetry stream
+    /// to the leader (coord, bytes/sec since the previous sample, loss, queued, load);
+    /// None when not under attack
+    pub async fn live_sample(&self) -> Option<(NodeCoord, u64, f32, Vec<NodeCoord>, Option<HostLoad>)> {
+        let my_coord = self.my_coord?;
+        if self.active_attacks.read().await.is_empty() && self.queued_attackers.is_empty() {
+            return None;
         }
 
-        // Calculate bandwidth over the last measurement period
-        let now = SystemTime::now();
-        let mut last_measurement = self.last_measurement.write().await;
-        let duration = now.duration_since(*last_measurement).unwrap_or(Duration::from_secs(1));
-        *last_measurement = now;
-
-        let bytes = self.bytes_received.swap(0, Ordering::Relaxed);
-        let bandwidth_in = (bytes as f64 / duration.as_secs_f64()) as u64;
+        let total = self.inbound.bytes.load(Ordering::Relaxed);
+        let bandwidth_in = self.live_meter.write().await.read(total);
+        let host_load = self.host_load.write().await.sample();
+        Some((my_coord, bandwidth_in, self.packet_loss().await, self.queued_attackers.clone(), host_load))
+    }
 
-        // Calculate average packet loss across all active attacks
+    /// Average packet loss across all active attacks
+    async fn packet_loss(&self) -> f32 {
         let trackers = self.packet_trackers.read().await;
-        let packet_loss = if trackers.is_empty() {
+        if trackers.is_empty() {
             0.0
         } else {
             let total_loss: f32 = trackers.values().map(|t| t.calculate_loss()).sum();
             total_loss / trackers.len() as f32
-        };
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        // Report metrics for this node (being attacked)
-        vec![GameEvent::NodeMetricsReport {
-            node_coord: my_coord,
-            bandwidth_in,
-            packet_loss,
-            timestamp,
-        }]
+        }
     }
 
     /// Update attacks based on current game state
@@ -159,7 +639,7 @@ impl NetworkManager {
     pub async fn sync_with_game_state(
         &mut self,
         game_state: &GameState,
-        ip_map: &HashMap<NodeCoord, String>,
+        ip_map: &BTreeMap<NodeCoord, String>,
         my_ip: &str,
     ) {
         // Auto-discover my coordinate if not yet known
@@ -186,13 +666,27 @@ impl NetworkManager {
             }
         };
 
+        // Defend orders apply whether or not anyone is attacking yet
+        self.rebalance_responder(game_state);
+
         // Find all nodes that are attacking ME
-        let attackers: Vec<(NodeCoord, u64)> = game_state
-            .nodes
-            .values()
-            .filter(|node| node.current_target == Some(super::events::AttackTarget::Coordinate(my_coord)))
-            .map(|node| (node.coord, node.owner_id))
-            .collect();
+        let all_attackers = attackers_of(game_state, my_coord);
+
+        // Cycle attackers through the flooder budget
+        let slots = self.budget.flooder_slots();
+        if all_attackers.len() > slots && self.last_rotation.elapsed() >= self.budget.rotation_interval {
+            self.rotation_offset = self.rotation_offset.wrapping_add(slots);
+            self.last_rotation = Instant::now();
+        }
+        let (attackers, queued) = schedule_attackers(&all_attackers, slots, self.rotation_offset);
+        if !queued.is_empty() && queued != self.queued_attackers {
+            println!(
+                "[Network] Budget full: {} active, {} queued attackers",
+                attackers.len(),
+                queued.len()
+            );
+        }
+        self.queued_attackers = queued;
 
         if attackers.is_empty() {
             // Not being attacked, close all connections
@@ -206,8 +700,22 @@ impl NetworkManager {
             return;
         }
 
+        // Stop connections to nodes that are no longer attacking (or lost their slot)
+        // Done first so a rotation frees slots before new flooders take them
+        let attacks = self.active_attacks.read().await;
+        let to_stop: Vec<NodeCoord> = attacks
+            .keys()
+            .filter(|coord| !attackers.contains(coord))
+            .copied()
+            .collect();
+        drop(attacks);
+
+        for target in to_stop {
+            self.stop_attack_connection(target).await;
+        }
+
         // For each attacking coordinate, open a 1-to-1 UDP connection
-        for (attacker_coord, _attacker_owner) in &attackers {
+        for attacker_coord in &attackers {
             // Check if we already have a connection to this attacker
             let attacks = self.active_attacks.read().await;
             if attacks.contains_key(attacker_coord) {
@@ -230,23 +738,105 @@ impl NetworkManager {
             }
         }
 
-        // Stop connections to nodes that are no longer attacking
-        let attacker_coords: Vec<NodeCoord> = attackers
+        self.rebalance_egress(game_state).await;
+    }
+}
+
+impl NetworkManager {
+    /// Full reconciliation against committed state
+    ///
+    /// `sync_with_game_state` only acts on differences it can see, so it never
+    /// notices a flooder that died, an attacker that came back on a new IP, or a
+    /// responder that failed to bind. This rebuilds the expected picture from scratch
+    /// (our coordinate, the scheduled attackers for the current rotation) and fixes
+    /// whatever doesn't match. Rotation isn't advanced here.
+    pub async fn reconcile(
+        &mut self,
+        game_state: &GameState,
+        ip_map: &BTreeMap<NodeCoord, String>,
+        my_ip: &str,
+    ) -> ReconcileReport {
+        let mut report = ReconcileReport::default();
+
+        if self.responder.is_finished() {
+            self.responder = Self::spawn_responder(self.transport, self.inbound.clone(), self.netsim, self.responder_pps.clone());
+            report.responder_restarted = true;
+        }
+
+        let found_coord = ip_map
             .iter()
-            .map(|(coord, _)| *coord)
-            .collect();
+            .find(|(_, ip)| ip.as_str() == my_ip)
+            .map(|(coord, _)| *coord);
+        if found_coord.is_some() && found_coord != self.my_coord {
+            report.coord_changed = self.my_coord.is_some();
+            self.my_coord = found_coord;
+        }
+
+        let desired = match self.my_coord {
+            Some(my_coord) => {
+                let all_attackers = attackers_of(game_state, my_coord);
+                let (active, queued) = schedule_attackers(&all_attackers, self.budget.flooder_slots(), self.rotation_offset);
+                self.queued_attackers = queued;
+                active
+            }
+            None => Vec::new(),
+        };
 
         let attacks = self.active_attacks.read().await;
-        let to_stop: Vec<NodeCoord> = attacks
-            .keys()
-            .filter(|coord| !attacker_coords.contains(coord))
-            .copied()
+        let active: HashMap<NodeCoord, ConnectionView> = attacks
+            .iter()
+            .map(|(coord, connection)| {
+                let view = ConnectionView {
+                    target_ip: connection.target_ip.clone(),
+                    running: !connection.task.is_finished(),
+                };
+                (*coord, view)
+            })
             .collect();
         drop(attacks);
 
-        for target in to_stop {
-            self.stop_attack_connection(target).await;
+        let plan = plan_reconcile(&desired, ip_map, &active);
+        for coord in &plan.stop {
+            self.stop_attack_connection(*coord).await;
+        }
+        for coord in &plan.start {
+            if let Some(target_ip) = ip_map.get(coord) {
+                if let Err(e) = self.start_udp_attack(*coord, target_ip.clone()).await {
+                    eprintln!("[Reconcile] Failed to restart attack from {:?}: {}", coord, e);
+                }
+            }
+        }
+        self.rebalance_egress(game_state).await;
+        self.rebalance_responder(game_state);
+
+        report.missing = plan.report.missing;
+        report.orphaned = plan.report.orphaned;
+        report.dead = plan.report.dead;
+        report.stale_ip = plan.report.stale_ip;
+        self.reconcile_stats.record(&report);
+
+        if !report.is_clean() {
+            let stats = self.reconcile_stats;
+            println!(
+                "[Reconcile] Drift fixed: missing={} orphaned={} dead={} stale_ip={} responder_restarted={} coord_changed={} \
+                 (totals: runs={} drifted={} missing={} orphaned={} dead={} stale_ip={} responder_restarts={})",
+                report.missing,
+                report.orphaned,
+                report.dead,
+                report.stale_ip,
+                report.responder_restarted,
+                report.coord_changed,
+                stats.runs,
+                stats.drifted_runs,
+                stats.missing,
+                stats.orphaned,
+                stats.dead,
+                stats.stale_ip,
+                stats.responder_restarts,
+            );
         }
+
+        report
     }
 }
 
@@ -255,3 +845,131 @@ impl Default for NetworkManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(n: i32) -> Vec<NodeCoord> {
+        (0..n).map(|q| NodeCoord::new(q, 0)).collect()
+    }
+
+    #[test]
+    fn test_schedule_within_budget() {
+        let (active, queued) = schedule_attackers(&coords(3), 5, 7);
+        assert_eq!(active, coords(3));
+        assert!(queued.is_empty());
+    }
+
+    #[test]
+    fn test_schedule_cycles_every_attacker() {
+        let attackers = coords(5);
+        let mut seen = Vec::new();
+
+        // Two slots, advancing by two each rotation - everyone gets a turn within 3 rotations
+        for rotation in 0..3 {
+            let (active, queued) = schedule_attackers(&attackers, 2, rotation * 2);
+            assert_eq!(active.len(), 2);
+            assert_eq!(queued.len(), 3);
+            seen.extend(active);
+        }
+
+        seen.sort_by_key(|c| (c.q, c.r));
+        seen.dedup();
+        assert_eq!(seen, attackers);
+    }
+
+    fn running(ip: &str) -> ConnectionView {
+        ConnectionView { target_ip: ip.to_string(), running: true }
+    }
+
+    #[test]
+    fn test_reconcile_clean_when_in_sync() {
+        let ip_map = BTreeMap::from([(NodeCoord::new(1, 0), "10.0.0.1".to_string())]);
+        let active = HashMap::from([(NodeCoord::new(1, 0), running("10.0.0.1"))]);
+
+        let plan = plan_reconcile(&[NodeCoord::new(1, 0)], &ip_map, &active);
+        assert_eq!(plan, ReconcilePlan::default());
+        assert!(plan.report.is_clean());
+    }
+
+    #[test]
+    fn test_reconcile_fixes_drift() {
+        let ip_map = BTreeMap::from([
+            (NodeCoord::new(0, 1), "10.0.0.1".to_string()),
+            (NodeCoord::new(1, 0), "10.0.0.2".to_string()),
+            (NodeCoord::new(2, 0), "10.0.0.9".to_string()),
+            (NodeCoord::new(3, 0), "10.0.0.4".to_string()),
+        ]);
+        let active = HashMap::from([
+            // Dead flooder
+            (NodeCoord::new(0, 1), ConnectionView { target_ip: "10.0.0.1".to_string(), running: false }),
+            // Attacker came back on a new IP
+            (NodeCoord::new(2, 0), running("10.0.0.3")),
+            // Orphan - no longer attacking
+            (NodeCoord::new(5, 0), running("10.0.0.5")),
+        ]);
+        // (1, 0) is missing; (4, 0) has no IP yet and is left alone
+        let desired = [NodeCoord::new(0, 1), NodeCoord::new(1, 0), NodeCoord::new(2, 0), NodeCoord::new(4, 0)];
+
+        let plan = plan_reconcile(&desired, &ip_map, &active);
+        assert_eq!(plan.stop, vec![NodeCoord::new(0, 1), NodeCoord::new(2, 0), NodeCoord::new(5, 0)]);
+        assert_eq!(plan.start, vec![NodeCoord::new(0, 1), NodeCoord::new(1, 0), NodeCoord::new(2, 0)]);
+        assert_eq!(
+            plan.report,
+            ReconcileReport { missing: 1, orphaned: 1, dead: 1, stale_ip: 1, ..Default::default() }
+        );
+    }
+
+    #[test]
+    fn test_flooder_slots_reserve_responder_socket() {
+        let budget = NetworkBudget {
+            max_flooders: 32,
+            max_sockets: 8,
+            rotation_interval: Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert_eq!(budget.flooder_slots(), 7);
+    }
+
+    #[test]
+    fn test_flooder_rate_keeps_reserved_slice() {
+        assert_eq!(NetworkBudget::default().flooder_rate(1.0, 4), None);
+
+        let budget = NetworkBudget {
+            egress_bytes_per_sec: 1_000_000,
+            egress_reserved: 0.2,
+            ..Default::default()
+        };
+        // 800 KB/s left for flooding, shared four ways
+        assert_eq!(budget.flooder_rate(1.0, 4), Some(200_000));
+        // A capital's task has twice the egress
+        assert_eq!(budget.flooder_rate(2.0, 4), Some(400_000));
+        // No flooders yet is treated as one
+        assert_eq!(budget.flooder_rate(1.0, 0), Some(800_000));
+    }
+
+    #[test]
+    fn test_responder_limit_scales_with_defense() {
+        assert_eq!(NetworkBudget::default().responder_limit(2.0, 2.0), None);
+
+        let budget = NetworkBudget { max_packets_per_second: 10_000, ..Default::default() };
+        assert_eq!(budget.responder_limit(1.0, 1.0), Some(10_000));
+        // A defending capital
+        assert_eq!(budget.responder_limit(2.0, 2.0), Some(40_000));
+        // Assisting a neighbour keeps half the bonus
+        assert_eq!(budget.responder_limit(1.0, 1.5), Some(15_000));
+    }
+
+    #[test]
+    fn test_rate_meters_read_independently() {
+        // Two meters over the same running total don't steal each other's bytes
+        let (mut report, mut live) = (RateMeter::new(), RateMeter::new());
+        live.at -= Duration::from_secs(1);
+        report.at -= Duration::from_secs(2);
+        let live_rate = live.read(1000);
+        assert!((900..=1000).contains(&live_rate));
+        let report_rate = report.read(1000);
+        assert!((450..=500).contains(&report_rate));
+    }
+}
@@ -0,0 +1,249 @@
+use super::events::{NodeCoord, NodeType};
+use super::state::GameState;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Give up after expanding this many hexes (the grid is unbounded)
+pub const MAX_EXPANSIONS: usize = 10_000;
+
+/// Cost of stepping onto a hex, by who holds it
+///
+/// Owned hexes are cheap corridors, empty hexes need a capture, and enemy hexes
+/// cost more the bigger the instance behind them (capitals run on larger tasks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathCosts {
+    pub owned: u32,
+    pub empty: u32,
+    pub enemy_regular: u32,
+    pub enemy_capital: u32,
+}
+
+impl Default for PathCosts {
+    fn default() -> Self {
+        Self {
+            owned: 1,
+            empty: 4,
+            enemy_regular: 8,
+            enemy_capital: 16,
+        }
+    }
+}
+
+impl PathCosts {
+    /// Cost for `player_id` to step onto `coord`
+    /// Enemy nodes already losing packets are discounted - they're partway to capture
+    pub fn step_cost(&self, game_state: &GameState, player_id: u64, coord: &NodeCoord) -> u32 {
+        let Some(node) = game_state.nodes.get(coord) else {
+            return self.empty;
+        };
+
+        if node.owner_id == player_id {
+            return self.owned;
+        }
+        if node.owner_id == 0 {
+            return self.empty;
+        }
+
+        let base = match node.node_type {
+            NodeType::Capital => self.enemy_capital,
+            _ => self.enemy_regular,
+        };
+        let loss = game_state
+            .node_metrics
+            .get(coord)
+            .map(|m| m.packet_loss.clamp(0.0, 1.0))
+            .unwrap_or(0.0);
+
+        // Up to half off for a node at 100% loss, never cheaper than an empty hex
+        let discount = (base as f32 * loss / 2.0) as u32;
+        (base - discount).max(self.empty)
+    }
+
+    /// Cheapest possible step, used to keep the A* heuristic admissible
+    fn min_step(&self) -> u32 {
+        self.owned.min(self.empty).min(self.enemy_regular).min(self.enemy_capital)
+    }
+}
+
+/// One hex on a planned path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathStep {
+    pub coord: NodeCoord,
+    /// Current owner (0 = neutral or not yet spawned)
+    pub owner_id: u64,
+    /// Cost of stepping onto this hex (0 for the start)
+    pub step_cost: u32,
+}
+
+/// Cheapest attack corridor between two hexes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathPlan {
+    pub steps: Vec<PathStep>,
+    pub total_cost: u32,
+}
+
+impl PathPlan {
+    /// First hex on the path the player doesn't own - the next thing to attack
+    pub fn next_target(&self, player_id: u64) -> Option<NodeCoord> {
+        self.steps
+            .iter()
+            .skip(1)
+            .find(|s| s.owner_id != player_id)
+            .map(|s| s.coord)
+    }
+}
+
+/// A* from `from` to `to`, costed from `player_id`'s point of view
+/// Returns None if `to` is impassable or the search exceeds MAX_EXPANSIONS
+pub fn find_path(
+    game_state: &GameState,
+    player_id: u64,
+    from: NodeCoord,
+    to: NodeCoord,
+    costs: &PathCosts,
+) -> Option<PathPlan> {
+    if !game_state.terrain_at(to).is_passable() {
+        return None;
+    }
+    let heuristic = |c: &NodeCoord| c.distance(&to) * costs.min_step();
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<NodeCoord, NodeCoord> = HashMap::new();
+    let mut best: HashMap<NodeCoord, u32> = HashMap::new();
+    let mut expansions = 0;
+
+    best.insert(from, 0);
+    // Tie-break on lower heuristic so equal-cost paths head toward the goal
+    open.push(Reverse((heuristic(&from), heuristic(&from), from.q, from.r)));
+
+    while let Some(Reverse((_, _, q, r))) = open.pop() {
+        let current = NodeCoord::new(q, r);
+        if current == to {
+            return Some(build_plan(game_state, player_id, &came_from, from, to, costs));
+        }
+
+        expansions += 1;
+        if expansions > MAX_EXPANSIONS {
+            return None;
+        }
+
+        let current_cost = best[&current];
+        for next in current.neighbors() {
+            // Chasms can't be crossed
+            if !game_state.terrain_at(next).is_passable() {
+                continue;
+            }
+            let cost = current_cost + costs.step_cost(game_state, player_id, &next);
+            if best.get(&next).is_some_and(|&b| b <= cost) {
+                continue;
+            }
+
+            best.insert(next, cost);
+            came_from.insert(next, current);
+            let h = heuristic(&next);
+            open.push(Reverse((cost + h, h, next.q, next.r)));
+        }
+    }
+
+    None
+}
+
+/// Walk `came_from` back from `to` and annotate each step
+fn build_plan(
+    game_state: &GameState,
+    player_id: u64,
+    came_from: &HashMap<NodeCoord, NodeCoord>,
+    from: NodeCoord,
+    to: NodeCoord,
+    costs: &PathCosts,
+) -> PathPlan {
+    let mut coords = vec![to];
+    let mut current = to;
+    while current != from {
+        current = came_from[&current];
+        coords.push(current);
+    }
+    coords.reverse();
+
+    let steps: Vec<PathStep> = coords
+        .iter()
+        .enumerate()
+        .map(|(i, coord)| PathStep {
+            coord: *coord,
+            owner_id: game_state.nodes.get(coord).map(|n| n.owner_id).unwrap_or(0),
+            step_cost: if i == 0 { 0 } else { costs.step_cost(game_state, player_id, coord) },
+        })
+        .collect();
+    let total_cost = steps.iter().map(|s| s.step_cost).sum();
+
+    PathPlan { steps, total_cost }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::state::{Node, NodeInitState};
+    use crate::game::Terrain;
+
+    fn place(state: &mut GameState, coord: NodeCoord, owner_id: u64, node_type: NodeType) {
+        state.nodes.insert(
+            coord,
+            Node {
+                coord,
+                owner_id,
+                node_type,
+                current_target: None,
+                init_state: NodeInitState::Ready,
+            },
+        );
+    }
+
+    #[test]
+    fn test_straight_path_on_empty_grid() {
+        let state = GameState::new();
+        let plan = find_path(&state, 1, NodeCoord::new(0, 0), NodeCoord::new(3, 0), &PathCosts::default()).unwrap();
+
+        assert_eq!(plan.steps.len(), 4);
+        assert_eq!(plan.total_cost, 3 * PathCosts::default().empty);
+        assert!(plan.steps.windows(2).all(|w| w[0].coord.is_adjacent(&w[1].coord)));
+    }
+
+    #[test]
+    fn test_routes_around_enemy_capital() {
+        let mut state = GameState::new();
+        place(&mut state, NodeCoord::new(1, 0), 2, NodeType::Capital);
+
+        let plan = find_path(&state, 1, NodeCoord::new(0, 0), NodeCoord::new(2, 0), &PathCosts::default()).unwrap();
+
+        // Detour through three empty hexes beats the capital plus one empty hex
+        assert!(plan.steps.iter().all(|s| s.coord != NodeCoord::new(1, 0)));
+        assert_eq!(plan.total_cost, 3 * PathCosts::default().empty);
+    }
+
+    #[test]
+    fn test_routes_around_chasms() {
+        let mut state = GameState::new();
+        state.terrain.insert(NodeCoord::new(1, 0), Terrain::Chasm);
+        state.terrain.insert(NodeCoord::new(3, 0), Terrain::Chasm);
+
+        let plan = find_path(&state, 1, NodeCoord::new(0, 0), NodeCoord::new(2, 0), &PathCosts::default()).unwrap();
+        assert!(plan.steps.iter().all(|s| s.coord != NodeCoord::new(1, 0)));
+        assert_eq!(plan.total_cost, 3 * PathCosts::default().empty);
+
+        // A chasm itself can't be reached
+        assert!(find_path(&state, 1, NodeCoord::new(0, 0), NodeCoord::new(3, 0), &PathCosts::default()).is_none());
+    }
+
+    #[test]
+    fn test_prefers_owned_corridor_and_reports_next_target() {
+        let mut state = GameState::new();
+        // Owned corridor bending away from the straight line
+        place(&mut state, NodeCoord::new(0, 1), 1, NodeType::Regular);
+        place(&mut state, NodeCoord::new(1, 1), 1, NodeType::Regular);
+        place(&mut state, NodeCoord::new(2, 1), 1, NodeType::Regular);
+
+        let plan = find_path(&state, 1, NodeCoord::new(0, 0), NodeCoord::new(3, 0), &PathCosts::default()).unwrap();
+        assert_eq!(plan.next_target(1), Some(NodeCoord::new(3, 0)));
+    }
+}
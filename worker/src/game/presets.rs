@@ -0,0 +1,104 @@
+// Named rules presets (GAME_RULES)
+//
+// A preset fixes the capture thresholds (from camhack-rules), neighbor expansion and
+// capital grace. Every worker in a game must play the same one, so the leader reports
+// the preset its config matches in game summaries and the master hands that name to
+// every worker it spawns. A config no preset matches is "custom".
+
+use super::capture::{CaptureRules, RULES_PRESETS};
+use super::features::FeatureFlags;
+use super::logic::GameConfig;
+use super::state::DEFAULT_RETARGET_COOLDOWN_SECS;
+use crate::raft::metrics_view::DEFAULT_LIVE_METRICS_MS;
+
+impl GameConfig {
+    pub(super) fn with_capture(capture: CaptureRules, neighbor_expansion_interval_secs: u64, capital_grace_secs: u64) -> Self {
+        Self {
+            overload_duration_secs: capture.overload_duration_secs,
+            overload_threshold: capture.overload_threshold,
+            max_capture_multiplier: capture.max_capture_multiplier,
+            capacity_tiers: capture.tiers,
+            neighbor_expansion_interval_secs,
+            capital_grace_secs,
+            map: None,
+            client_idle_secs: 30,
+            idle_neutralize_secs: 0,
+            time_scale: 1,
+            retarget_cooldown_secs: DEFAULT_RETARGET_COOLDOWN_SECS,
+            elimination_policy: None,
+            supply: None,
+            time_limit_secs: 0,
+            features: FeatureFlags::default(),
+            cpu_overload_weight: 0.0,
+            metrics_interval_ms: DEFAULT_LIVE_METRICS_MS,
+        }
+    }
+
+    /// Look up a named rules preset (capture thresholds come from camhack-rules)
+    /// standard: defaults | blitz: fast captures | siege: nodes hold out longer
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "standard" | "" => Some(Self::default()),
+            "blitz" => Some(Self::with_capture(CaptureRules::BLITZ, 5, 15)),
+            "siege" => Some(Self::with_capture(CaptureRules::SIEGE, 20, 60)),
+            _ => None,
+        }
+    }
+
+    /// Name of the preset this config matches, or "custom"
+    /// The map, time scale, retarget cooldown, elimination policy and features aren't part of a preset - they're committed to the log,
+    /// not passed to workers - and neither are the idle hand-off, supply lines, time limit and CPU weight, which only the leader acts on
+    pub fn preset_name(&self) -> &'static str {
+        let same_rules = |p: GameConfig| GameConfig {
+            map: self.map,
+            client_idle_secs: self.client_idle_secs,
+            idle_neutralize_secs: self.idle_neutralize_secs,
+            time_scale: self.time_scale,
+            retarget_cooldown_secs: self.retarget_cooldown_secs,
+            elimination_policy: self.elimination_policy,
+            supply: self.supply,
+            time_limit_secs: self.time_limit_secs,
+            features: self.features,
+            cpu_overload_weight: self.cpu_overload_weight,
+            metrics_interval_ms: self.metrics_interval_ms,
+            ..p
+        };
+        RULES_PRESETS
+            .iter()
+            .find(|name| Self::preset(name).map(same_rules).as_ref() == Some(self))
+            .copied()
+            .unwrap_or("custom")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::capture::TierScaling;
+    use crate::game::events::EliminationPolicy;
+    use crate::game::logic::MapConfig;
+
+    #[test]
+    fn test_rules_presets() {
+        assert_eq!(GameConfig::preset("standard"), Some(GameConfig::default()));
+        assert_eq!(GameConfig::preset("Blitz").unwrap().preset_name(), "blitz");
+        assert_eq!(GameConfig::preset("capture-the-flag"), None);
+
+        let tweaked = GameConfig { overload_threshold: 0.5, ..GameConfig::default() };
+        assert_eq!(tweaked.preset_name(), "custom");
+        let flat = GameConfig { capacity_tiers: TierScaling::FLAT, ..GameConfig::default() };
+        assert_eq!(flat.preset_name(), "custom");
+
+        // Terrain doesn't make a config custom
+        let mapped = GameConfig { map: Some(MapConfig { seed: 1, radius: 5 }), ..GameConfig::default() };
+        assert_eq!(mapped.preset_name(), "standard");
+        let fast = GameConfig { time_scale: 10, ..GameConfig::default() };
+        assert_eq!(fast.preset_name(), "standard");
+        let patient = GameConfig { retarget_cooldown_secs: 10, ..GameConfig::default() };
+        assert_eq!(patient.preset_name(), "standard");
+        let ruthless = GameConfig { elimination_policy: Some(EliminationPolicy::Captor), ..GameConfig::default() };
+        assert_eq!(ruthless.preset_name(), "standard");
+        let timed = GameConfig { time_limit_secs: 600, ..GameConfig::default() };
+        assert_eq!(timed.preset_name(), "standard");
+    }
+}
@@ -0,0 +1,136 @@
+// Replay files - rebuild a GameState offline from a saved event log
+//
+// A replay file is whatever a worker handed out: the body of GET /events, a bare
+// JSON array, or one event per line. Events go through the same decoder as the
+// Raft log, so files saved by older builds are upcast to the current layout. A GET
+// /events body also carries the leader's stamp on each event, and `ReplayLog::state`
+// applies them the way the node did (tests/replays holds such logs from past games).
+
+use super::events::GameEvent;
+use super::state::GameState;
+use anyhow::Context;
+use std::ops::Range;
+use std::path::Path;
+
+/// GET /events body, or a bare array
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum EventBatch {
+    Response {
+        events: Vec<GameEvent>,
+        #[serde(default)]
+        event_times: Vec<u64>,
+    },
+    List(Vec<GameEvent>),
+}
+
+/// A saved log's events, with the leader's stamps when the file has them
+#[derive(Debug, Clone, Default)]
+pub struct ReplayLog {
+    pub events: Vec<GameEvent>,
+    /// Leader stamp of each event (unix seconds, 0 = unstamped; empty without stamps)
+    pub event_times: Vec<u64>,
+}
+
+impl ReplayLog {
+    /// The state a node held after applying the whole log at the recorded stamps
+    /// Without stamps the events keep their own timestamps, as in `replay`.
+    pub fn state(&self) -> GameState {
+        let mut state = GameState::new();
+        replay_committed(&mut state, 0..self.events.len(), &self.events, &[], &self.event_times);
+        state
+    }
+}
+
+/// Parse a replay file's contents into events, in log order
+pub fn parse_replay(text: &str) -> anyhow::Result<Vec<GameEvent>> {
+    parse_replay_log(text).map(|log| log.events)
+}
+
+/// Parse a replay file's contents, keeping any stamps a GET /events body has
+pub fn parse_replay_log(text: &str) -> anyhow::Result<ReplayLog> {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('[') || trimmed.starts_with("{\"events\"") {
+        let batch: EventBatch = serde_json::from_str(text).context("Invalid event list")?;
+        return Ok(match batch {
+            EventBatch::Response { events, event_times } => ReplayLog { events, event_times },
+            EventBatch::List(events) => ReplayLog { events, event_times: Vec::new() },
+        });
+    }
+
+    // JSON lines
+    let events = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).with_context(|| format!("Invalid event on line {}", i + 1)))
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ReplayLog { events, event_times: Vec::new() })
+}
+
+/// Read and parse a replay file
+pub fn load_replay(path: impl AsRef<Path>) -> anyhow::Result<Vec<GameEvent>> {
+    load_replay_log(path).map(|log| log.events)
+}
+
+/// Read and parse a replay file, keeping its stamps
+pub fn load_replay_log(path: impl AsRef<Path>) -> anyhow::Result<ReplayLog> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_replay_log(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Apply events to a fresh GameState, numbering them from log index 1
+pub fn replay(events: impl IntoIterator<Item = GameEvent>) -> GameState {
+    let mut state = GameState::new();
+    for (i, event) in events.into_iter().enumerate() {
+        state.process_event(event, i as u64 + 1);
+    }
+    state
+}
+
+/// Apply the committed events at positions `range` onto `state`, at the log indices
+/// and leader stamps they were applied with (see GameStateMachine), so the result
+/// matches what the node held after the last of them
+/// Positions without a recorded index fall back to numbering from 1, like `replay`.
+pub fn replay_committed(state: &mut GameState, range: Range<usize>, events: &[GameEvent], event_indices: &[u64], event_times: &[u64]) {
+    for position in range {
+        let log_index = event_indices.get(position).copied().unwrap_or(position as u64 + 1);
+        let committed_at = event_times.get(position).copied().unwrap_or(0);
+        state.process_committed(events[position].clone(), log_index, committed_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::NodeCoord;
+
+    #[test]
+    fn test_replay_mixed_versions() {
+        // One event per format: pre-envelope, v1 envelope, current envelope
+        let lines = [
+            r#"{"PlayerJoin":{"player_id":1,"name":"Alice","capital_coord":{"q":0,"r":0},"node_ip":"10.0.0.1","is_client":false,"timestamp":0}}"#,
+            r#"{"v":1,"family":"command","event":{"PlayerJoin":{"player_id":2,"name":"Bob","capital_coord":{"q":1,"r":0},"node_ip":"192.168.1.2","is_client":true,"timestamp":0}}}"#,
+            r#"{"v":2,"family":"command","event":{"SetNodeTarget":{"node_coord":{"q":1,"r":0},"target":{"Coordinate":{"q":0,"r":0}},"timestamp":1}}}"#,
+        ];
+        let events = parse_replay(&lines.join("\n")).unwrap();
+        assert_eq!(events.len(), 3);
+
+        // The same events as a GET /events body
+        let body = format!(r#"{{"events":[{}],"count":3}}"#, lines.join(","));
+        assert_eq!(parse_replay(&body).unwrap().len(), 3);
+
+        // With the leader's stamps, replayed at them
+        let body = format!(r#"{{"events":[{}],"event_times":[5,6,7],"count":3}}"#, lines.join(","));
+        let log = parse_replay_log(&body).unwrap();
+        assert_eq!(log.event_times, [5, 6, 7]);
+        assert_eq!(log.state().clock, 7);
+
+        let state = replay(events);
+        assert_eq!(state.last_applied_log_index, 3);
+        assert_eq!(state.players[&2].client_endpoint.as_deref(), Some("192.168.1.2"));
+        assert_eq!(state.node_ips.get(&NodeCoord::new(0, 0)).map(String::as_str), Some("10.0.0.1"));
+        assert!(!state.node_ips.contains_key(&NodeCoord::new(1, 0)));
+    }
+}
@@ -0,0 +1,237 @@
+// Game-over shutdown, as a state machine over committed state
+//
+// Tearing the game down the moment it ends races followers still applying the last
+// entries. Instead the shutdown moves through phases read off the log alone, so a
+// new leader picks up wherever the old one stopped:
+//
+//   Running      the game isn't over
+//   Ending       it is; the leader commits GameEnded, naming the Raft voters it waits for
+//   AwaitingAcks each member commits GameOverAcknowledged once it has applied GameEnded;
+//                the leader waits for all of them, or until the ack timeout runs out
+//   TearingDown  the leader requests the ShutdownGame effect: archive the final
+//                summary with the master, then have the master tear the game down
+//   Done         ShutdownGame is acknowledged, the leader exits
+//
+// Player clients are learners and aren't waited for.
+
+use super::events::{Effect, FactEvent, GameEvent};
+use super::state::GameState;
+
+/// Default wait for members' acknowledgments before tearing down anyway (seconds)
+pub const DEFAULT_ACK_TIMEOUT_SECS: u64 = 30;
+
+/// Where a game's shutdown stands, derived from committed state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownPhase {
+    Running,
+    Ending,
+    /// `pending` members haven't acknowledged; the leader stops waiting at `deadline`
+    AwaitingAcks { pending: Vec<u64>, deadline: u64 },
+    TearingDown,
+    Done,
+}
+
+impl ShutdownPhase {
+    /// The phase `game_state` is in at `now` (unix seconds)
+    pub fn of(game_state: &GameState, now: u64, ack_timeout_secs: u64) -> Self {
+        if !game_state.game_over {
+            return ShutdownPhase::Running;
+        }
+        let Some(end) = &game_state.game_end else {
+            return ShutdownPhase::Ending;
+        };
+        let shutdown = |e: &Effect| *e == Effect::ShutdownGame;
+        if game_state.effect_completed(shutdown) {
+            return ShutdownPhase::Done;
+        }
+
+        let pending = end.pending();
+        let deadline = end.ended_at + ack_timeout_secs;
+        if game_state.effect_requested(shutdown) || pending.is_empty() || now >= deadline {
+            ShutdownPhase::TearingDown
+        } else {
+            ShutdownPhase::AwaitingAcks { pending, deadline }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ShutdownPhase::Running => "running",
+            ShutdownPhase::Ending => "ending",
+            ShutdownPhase::AwaitingAcks { .. } => "awaiting acknowledgments",
+            ShutdownPhase::TearingDown => "tearing down",
+            ShutdownPhase::Done => "done",
+        }
+    }
+}
+
+/// What this node has to do next to move the shutdown along
+#[derive(Debug, Clone)]
+pub enum ShutdownAction {
+    /// Commit `event` (through the leader if this node isn't it)
+    Commit(GameEvent),
+    /// Shutdown is complete - exit the process
+    Exit,
+}
+
+/// Steps one node through the shutdown; every worker runs one, leader or not
+pub struct ShutdownCoordinator {
+    node_id: u64,
+    ack_timeout_secs: u64,
+    /// Last phase seen, to log transitions once
+    phase: ShutdownPhase,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(node_id: u64, ack_timeout_secs: u64) -> Self {
+        Self { node_id, ack_timeout_secs, phase: ShutdownPhase::Running }
+    }
+
+    /// Coordinator for Raft node `node_id`
+    /// GAME_OVER_ACK_TIMEOUT_SECS sets how long the leader waits for acknowledgments (default: 30)
+    pub fn from_env(node_id: u64) -> Self {
+        let timeout = std::env::var("GAME_OVER_ACK_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_ACK_TIMEOUT_SECS);
+        Self::new(node_id, timeout)
+    }
+
+    /// What to do at `now`, given committed state and (on the leader) the current Raft voters
+    /// Actions are recomputed from committed state every step, so a failed commit is
+    /// simply retried on the next one; duplicates are ignored when applied.
+    pub fn step(&mut self, game_state: &GameState, is_leader: bool, voters: &[u64], now: u64) -> Vec<ShutdownAction> {
+        let phase = ShutdownPhase::of(game_state, now, self.ack_timeout_secs);
+        if phase.name() != self.phase.name() {
+            println!("[Shutdown] Game shutdown {}", phase.name());
+        }
+        self.phase = phase.clone();
+
+        let mut actions = Vec::new();
+        if let Some(end) = &game_state.game_end {
+            if end.members.contains(&self.node_id) && !end.acknowledged.contains(&self.node_id) {
+                actions.push(ShutdownAction::Commit(GameEvent::Fact(FactEvent::GameOverAcknowledged {
+                    raft_node_id: self.node_id,
+                    timestamp: now,
+                })));
+            }
+        }
+        if !is_leader {
+            return actions;
+        }
+
+        match phase {
+            ShutdownPhase::Running | ShutdownPhase::AwaitingAcks { .. } => {}
+            ShutdownPhase::Ending => {
+                actions.push(ShutdownAction::Commit(GameEvent::Fact(FactEvent::GameEnded {
+                    winner_id: game_state.winner(),
+                    members: voters.to_vec(),
+                    timestamp: now,
+                })));
+            }
+            ShutdownPhase::TearingDown => {
+                if !game_state.effect_requested(|e| *e == Effect::ShutdownGame) {
+                    if let Some(end) = &game_state.game_end {
+                        let pending = end.pending();
+                        if !pending.is_empty() {
+                            eprintln!("[Shutdown] Gave up waiting for members {:?}", pending);
+                        }
+                    }
+                    actions.push(ShutdownAction::Commit(GameEvent::Fact(FactEvent::EffectRequested {
+                        effect: Effect::ShutdownGame,
+                        timestamp: now,
+                    })));
+                }
+            }
+            ShutdownPhase::Done => actions.push(ShutdownAction::Exit),
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{CommandEvent, NodeCoord};
+
+    fn apply(state: &mut GameState, event: GameEvent) {
+        let index = state.last_applied_log_index + 1;
+        state.process_event(event, index);
+        state.last_applied_log_index = index;
+    }
+
+    /// Step `node` (as the leader when given voters) and commit what it asks for, returning the kinds in order
+    fn step(node: &mut ShutdownCoordinator, state: &mut GameState, voters: &[u64], now: u64) -> Vec<&'static str> {
+        node.step(state, !voters.is_empty(), voters, now)
+            .into_iter()
+            .map(|action| match action {
+                ShutdownAction::Commit(event) => {
+                    let kind = event.kind();
+                    apply(state, event);
+                    kind
+                }
+                ShutdownAction::Exit => "Exit",
+            })
+            .collect()
+    }
+
+    fn finished_game() -> GameState {
+        let mut state = GameState::new();
+        for (player_id, q) in [(1, 0), (2, 3)] {
+            apply(
+                &mut state,
+                GameEvent::Command(CommandEvent::PlayerJoin {
+                    player_id,
+                    name: format!("p{}", player_id),
+                    capital_coord: NodeCoord::new(q, 0),
+                    capital_ip: Some(format!("10.0.0.{}", player_id)),
+                    client_endpoint: None,
+                    timestamp: 100,
+                    raft_node_id: 0,
+                    reconnect_key: None,
+                }),
+            );
+        }
+        apply(&mut state, FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 200 }.into());
+        assert!(state.game_over);
+        state
+    }
+
+    #[test]
+    fn test_teardown_waits_for_members() {
+        let mut state = finished_game();
+        let mut leader = ShutdownCoordinator::new(1, 30);
+        let mut follower = ShutdownCoordinator::new(2, 30);
+
+        // Leader ends the game, then acknowledges it itself
+        assert!(step(&mut follower, &mut state, &[], 300).is_empty());
+        assert_eq!(step(&mut leader, &mut state, &[1, 2], 300), vec!["GameEnded"]);
+        assert_eq!(state.game_end.as_ref().unwrap().winner_id, Some(2));
+        assert_eq!(step(&mut leader, &mut state, &[1, 2], 301), vec!["GameOverAcknowledged"]);
+        assert_eq!(ShutdownPhase::of(&state, 301, 30), ShutdownPhase::AwaitingAcks { pending: vec![2], deadline: 330 });
+        assert!(step(&mut leader, &mut state, &[1, 2], 302).is_empty());
+
+        // The follower's acknowledgment lets the teardown go ahead
+        assert_eq!(step(&mut follower, &mut state, &[], 303), vec!["GameOverAcknowledged"]);
+        assert_eq!(step(&mut leader, &mut state, &[1, 2], 303), vec!["EffectRequested"]);
+        assert!(step(&mut leader, &mut state, &[1, 2], 304).is_empty());
+
+        let effect_id = state.pending_effects().next().unwrap().0;
+        apply(&mut state, FactEvent::EffectCompleted { effect_id, error: None, timestamp: 305 }.into());
+        assert_eq!(step(&mut leader, &mut state, &[1, 2], 305), vec!["Exit"]);
+        assert!(step(&mut follower, &mut state, &[], 305).is_empty());
+    }
+
+    #[test]
+    fn test_ack_timeout() {
+        let mut state = finished_game();
+        let mut leader = ShutdownCoordinator::new(1, 30);
+        step(&mut leader, &mut state, &[1, 2, 3], 300);
+        step(&mut leader, &mut state, &[1, 2, 3], 301);
+
+        // Members 2 and 3 never answer: the leader tears down at the deadline regardless
+        assert!(step(&mut leader, &mut state, &[1, 2, 3], 329).is_empty());
+        assert_eq!(ShutdownPhase::of(&state, 330, 30), ShutdownPhase::TearingDown);
+        assert_eq!(step(&mut leader, &mut state, &[1, 2, 3], 330), vec!["EffectRequested"]);
+    }
+}
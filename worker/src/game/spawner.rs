@@ -0,0 +1,149 @@
+use super::events::NodeCoord;
+use std::future::Future;
+use std::time::Duration;
+
+/// Retry settings for master spawn calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnRetryPolicy {
+    /// Attempts before giving up and reporting NodeInitializationFailed
+    pub max_attempts: u32,
+    /// Delay before the first retry (doubles each attempt)
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl SpawnRetryPolicy {
+    /// Load settings from the environment
+    /// SPAWN_MAX_ATTEMPTS (default: 5), SPAWN_BACKOFF_MS (default: 1000), SPAWN_MAX_BACKOFF_MS (default: 30000)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let max_attempts = std::env::var("SPAWN_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.max_attempts);
+
+        let initial_backoff = std::env::var("SPAWN_BACKOFF_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.initial_backoff);
+
+        let max_backoff = std::env::var("SPAWN_MAX_BACKOFF_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.max_backoff);
+
+        Self { max_attempts, initial_backoff, max_backoff }
+    }
+
+    /// Delay after the given (1-based) failed attempt
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+impl Default for SpawnRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A spawn that exhausted its retries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedSpawn {
+    pub coord: NodeCoord,
+    pub attempts: u32,
+    pub reason: String,
+}
+
+/// Call `spawn` until the master accepts, retrying with exponential backoff
+/// Returns the attempts it took, or the failure to report as NodeInitializationFailed
+/// after `max_attempts`. Runs as the leader's SpawnNode effect, so a hex is only
+/// ever spawned by one call at a time.
+pub async fn spawn_with_retries<F, Fut>(policy: SpawnRetryPolicy, coord: NodeCoord, spawn: F) -> Result<u32, FailedSpawn>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match spawn().await {
+            Ok(()) => {
+                println!("[Spawner] Spawn for {:?} accepted after {} attempt(s)", coord, attempts);
+                return Ok(attempts);
+            }
+            Err(e) if attempts >= policy.max_attempts => {
+                eprintln!("[Spawner] Giving up on {:?} after {} attempts: {}", coord, attempts, e);
+                return Err(FailedSpawn { coord, attempts, reason: e.to_string() });
+            }
+            Err(e) => {
+                let delay = policy.backoff(attempts);
+                eprintln!("[Spawner] Spawn for {:?} failed (attempt {}), retrying in {:?}: {}", coord, attempts, delay, e);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn fast_policy(max_attempts: u32) -> SpawnRetryPolicy {
+        SpawnRetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = SpawnRetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let c = calls.clone();
+        let result = spawn_with_retries(fast_policy(5), NodeCoord::new(2, -1), move || {
+            let c = c.clone();
+            async move {
+                // Master is down for the first two calls
+                if c.fetch_add(1, Ordering::SeqCst) < 2 {
+                    anyhow::bail!("connection refused");
+                }
+                Ok(())
+            }
+        })
+        .await;
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let coord = NodeCoord::new(0, 4);
+        let result = spawn_with_retries(fast_policy(3), coord, || async { anyhow::bail!("master unavailable") }).await;
+        assert_eq!(result, Err(FailedSpawn { coord, attempts: 3, reason: "master unavailable".to_string() }));
+    }
+}
@@ -0,0 +1,237 @@
+use super::awards::Awards;
+use super::events::{CommandEvent, EventFamily, FactEvent, GameEvent, GamePhase, NodeCoord};
+use super::state::GameState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Most committed events shipped in one summary
+pub const MAX_EVENTS_PER_SUMMARY: usize = 50;
+
+/// Per-player line in a game summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerSummary {
+    pub player_id: u64,
+    pub name: String,
+    pub alive: bool,
+    pub node_count: usize,
+}
+
+/// One committed event, reduced to what an overview needs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventSummary {
+    /// Position in the committed event history (1-based)
+    pub seq: u64,
+    pub family: EventFamily,
+    pub kind: String,
+}
+
+/// Where a player's capital was placed when they joined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapitalPlacement {
+    pub name: String,
+    pub coord: NodeCoord,
+}
+
+/// Committed terrain parameters (see camhack_rules::terrain::generate)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapSetup {
+    pub seed: u64,
+    pub radius: u32,
+}
+
+/// How a game was set up - what the master needs to stage a rematch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSetup {
+    pub map: Option<MapSetup>,
+    /// In join order
+    pub capitals: Vec<CapitalPlacement>,
+}
+
+impl GameSetup {
+    /// Read the setup back out of the committed event history
+    pub fn from_events(events: &[GameEvent]) -> Self {
+        let mut setup = Self::default();
+        for event in events {
+            match event {
+                GameEvent::Command(CommandEvent::PlayerJoin { name, capital_coord, .. }) => {
+                    setup.capitals.push(CapitalPlacement { name: name.clone(), coord: *capital_coord });
+                }
+                GameEvent::Fact(FactEvent::MapGenerated { seed, radius, .. }) => {
+                    setup.map = Some(MapSetup { seed: *seed, radius: *radius });
+                }
+                _ => {}
+            }
+        }
+        setup
+    }
+}
+
+/// Parse CAPITAL_PLACEMENTS (`name:q,r;name:q,r`), the capitals a rematch reserves
+/// Malformed entries are skipped.
+pub fn parse_capital_placements(value: &str) -> HashMap<String, NodeCoord> {
+    value
+        .split(';')
+        .filter_map(|entry| {
+            let (name, coord) = entry.trim().rsplit_once(':')?;
+            Some((name.to_string(), coord.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Who is reporting, and under which rules
+#[derive(Debug, Clone)]
+pub struct LeaderInfo {
+    pub game_id: String,
+    pub node_id: u64,
+    /// Leader's HTTP API address (ip:port) - spectators read /game/state here
+    pub api_addr: String,
+    pub rules_preset: String,
+}
+
+/// Snapshot of a game the leader ships to the master for observability
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub game_id: String,
+    pub leader_node_id: u64,
+    pub leader_api_addr: String,
+    pub rules_preset: String,
+    pub phase: GamePhase,
+    pub players: Vec<PlayerSummary>,
+    pub node_count: usize,
+    /// Furthest hex from the origin that holds a node
+    pub map_radius: u32,
+    pub total_events: u64,
+    /// Command and fact events committed since the previous summary (telemetry is skipped)
+    pub recent_events: Vec<EventSummary>,
+    /// Map and capital placements, kept by the master for rematches
+    #[serde(default)]
+    pub setup: GameSetup,
+    /// End-of-game awards, in the final summary only
+    #[serde(default)]
+    pub awards: Option<Awards>,
+    pub timestamp: u64,
+}
+
+impl GameSummary {
+    /// Build a summary from committed state
+    /// `events` is the full committed history; entries after `since` are shipped
+    pub fn build(
+        leader: &LeaderInfo,
+        game_state: &GameState,
+        events: &[GameEvent],
+        since: u64,
+        timestamp: u64,
+    ) -> Self {
+        let mut players: Vec<PlayerSummary> = game_state
+            .players
+            .values()
+            .map(|p| PlayerSummary {
+                player_id: p.player_id,
+                name: p.name.clone(),
+                alive: p.alive,
+                node_count: game_state.get_player_nodes(p.player_id).len(),
+            })
+            .collect();
+        players.sort_by_key(|p| p.player_id);
+
+        let mut recent_events: Vec<EventSummary> = events
+            .iter()
+            .enumerate()
+            .skip(since as usize)
+            .filter(|(_, e)| e.family() != EventFamily::Telemetry)
+            .map(|(i, e)| EventSummary {
+                seq: i as u64 + 1,
+                family: e.family(),
+                kind: e.kind().to_string(),
+            })
+            .collect();
+        // Keep the newest if a lot happened
+        if recent_events.len() > MAX_EVENTS_PER_SUMMARY {
+            recent_events.drain(..recent_events.len() - MAX_EVENTS_PER_SUMMARY);
+        }
+
+        let origin = NodeCoord::new(0, 0);
+        let map_radius = game_state.nodes.keys().map(|c| c.distance(&origin)).max().unwrap_or(0);
+
+        Self {
+            game_id: leader.game_id.clone(),
+            leader_node_id: leader.node_id,
+            leader_api_addr: leader.api_addr.clone(),
+            rules_preset: leader.rules_preset.clone(),
+            phase: game_state.phase,
+            players,
+            node_count: game_state.nodes.len(),
+            map_radius,
+            total_events: events.len() as u64,
+            recent_events,
+            setup: GameSetup::from_events(events),
+            awards: None,
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::events::{CommandEvent, TelemetryEvent};
+
+    #[test]
+    fn test_summary_phase_and_recent_events() {
+        let mut state = GameState::new();
+        let mut events: Vec<GameEvent> = vec![
+            CommandEvent::test_join(1, 0).into(),
+            TelemetryEvent::NodeMetricsReport {
+                node_coord: NodeCoord::new(0, 0),
+                bandwidth_in: 0,
+                packet_loss: 0.0,
+                timestamp: 0,
+                queued_attackers: Vec::new(),
+            }
+            .into(),
+        ];
+        for (i, e) in events.iter().enumerate() {
+            state.process_event(e.clone(), i as u64 + 1);
+        }
+
+        let leader = LeaderInfo {
+            game_id: "g1".to_string(),
+            node_id: 7,
+            api_addr: "10.0.0.1:8080".to_string(),
+            rules_preset: "standard".to_string(),
+        };
+        let summary = GameSummary::build(&leader, &state, &events, 0, 100);
+        assert_eq!(summary.phase, GamePhase::Lobby);
+        assert_eq!(summary.players.len(), 1);
+        assert_eq!(summary.players[0].node_count, 1);
+        // Telemetry is left out
+        assert_eq!(summary.recent_events.len(), 1);
+        assert_eq!(summary.recent_events[0].kind, "PlayerJoin");
+
+        events.push(CommandEvent::test_join(2, 5).into());
+        state.process_event(events[2].clone(), 3);
+
+        // Only what's new since the previous summary
+        let summary = GameSummary::build(&leader, &state, &events, 2, 105);
+        assert_eq!(summary.phase, GamePhase::Live);
+        assert_eq!(summary.map_radius, 5);
+        assert_eq!(summary.recent_events.len(), 1);
+        assert_eq!(summary.recent_events[0].seq, 3);
+        assert_eq!(
+            summary.setup.capitals,
+            vec![
+                CapitalPlacement { name: "p1".to_string(), coord: NodeCoord::new(0, 0) },
+                CapitalPlacement { name: "p2".to_string(), coord: NodeCoord::new(5, 0) },
+            ]
+        );
+        assert_eq!(summary.setup.map, None);
+    }
+
+    #[test]
+    fn test_parse_capital_placements() {
+        let placements = parse_capital_placements("alice:0,0; bob:3,-1;broken;carol:x,1");
+        assert_eq!(placements.len(), 2);
+        assert_eq!(placements["alice"], NodeCoord::new(0, 0));
+        assert_eq!(placements["bob"], NodeCoord::new(3, -1));
+    }
+}
@@ -0,0 +1,179 @@
+// Supply lines - attacks weaken the further they are from home
+//
+// An optional rule (SUPPLY_FALLOFF): a node's supply distance is how many hops it is
+// from its owner's capital through that owner's own nodes. Beyond SUPPLY_FREE_HOPS
+// every further hop takes SUPPLY_FALLOFF off the node's attack effectiveness, down
+// to SUPPLY_MIN_EFFECTIVENESS; a node with no friendly path home (or whose owner has
+// lost their capital) is cut off and gets the minimum. The leader weights each
+// target's reported bandwidth and loss by its attackers' effectiveness before the
+// capture rule sees them, so a long thin salient struggles to take anything while a
+// compact territory defends at full strength.
+
+use super::events::{AttackTarget, NodeCoord, NodeType};
+use super::state::GameState;
+use std::collections::{HashMap, VecDeque};
+
+/// Supply falloff settings (see the module docs)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SupplyConfig {
+    /// Hops from the capital at full effectiveness
+    pub free_hops: u32,
+    /// Effectiveness lost per hop beyond `free_hops` (0.0 to 1.0)
+    pub falloff_per_hop: f32,
+    /// Least effectiveness, also what cut-off nodes get
+    pub min_effectiveness: f32,
+}
+
+impl SupplyConfig {
+    /// SUPPLY_FALLOFF enables the rule (default: 0 = off), SUPPLY_FREE_HOPS (default: 2)
+    /// and SUPPLY_MIN_EFFECTIVENESS (default: 0.25) shape it
+    pub fn from_env() -> Option<Self> {
+        let falloff: f32 = std::env::var("SUPPLY_FALLOFF").ok().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+        if falloff <= 0.0 {
+            return None;
+        }
+        let free_hops = std::env::var("SUPPLY_FREE_HOPS").ok().and_then(|s| s.parse().ok()).unwrap_or(2);
+        let min_effectiveness = std::env::var("SUPPLY_MIN_EFFECTIVENESS").ok().and_then(|s| s.parse().ok()).unwrap_or(0.25);
+        Some(Self {
+            free_hops,
+            falloff_per_hop: falloff.min(1.0),
+            min_effectiveness: f32::clamp(min_effectiveness, 0.0, 1.0),
+        })
+    }
+
+    /// Attack effectiveness at supply distance `hops` (None = cut off)
+    pub fn effectiveness(&self, hops: Option<u32>) -> f32 {
+        let Some(hops) = hops else {
+            return self.min_effectiveness;
+        };
+        let beyond = hops.saturating_sub(self.free_hops) as f32;
+        (1.0 - self.falloff_per_hop * beyond).clamp(self.min_effectiveness, 1.0)
+    }
+
+    /// `game_state` with each attacked target's metrics weighted by the average
+    /// effectiveness of the nodes attacking it
+    pub fn weight_metrics(&self, game_state: &GameState) -> GameState {
+        let distances = supply_distances(game_state);
+        let mut attackers: HashMap<NodeCoord, Vec<f32>> = HashMap::new();
+        for node in game_state.nodes.values() {
+            if let Some(AttackTarget::Coordinate(target)) = node.current_target {
+                let effectiveness = self.effectiveness(distances.get(&node.coord).copied());
+                attackers.entry(target).or_default().push(effectiveness);
+            }
+        }
+
+        let mut weighted = game_state.clone();
+        for (target, effectiveness) in attackers {
+            let Some(metrics) = weighted.node_metrics.get_mut(&target) else { continue };
+            let weight = effectiveness.iter().sum::<f32>() / effectiveness.len() as f32;
+            metrics.bandwidth_in = (metrics.bandwidth_in as f64 * weight as f64) as u64;
+            metrics.packet_loss *= weight;
+        }
+        weighted
+    }
+}
+
+/// Every owned node's hops from its owner's capital through the owner's own nodes
+/// Nodes with no such path - or whose owner doesn't hold their capital - are left out.
+pub fn supply_distances(game_state: &GameState) -> HashMap<NodeCoord, u32> {
+    let mut distances = HashMap::new();
+    for player in game_state.players.values().filter(|p| p.alive) {
+        let home = game_state
+            .nodes
+            .get(&player.capital_coord)
+            .is_some_and(|n| n.owner_id == player.player_id && n.node_type == NodeType::Capital);
+        if !home {
+            continue;
+        }
+
+        distances.insert(player.capital_coord, 0);
+        let mut queue = VecDeque::from([player.capital_coord]);
+        while let Some(coord) = queue.pop_front() {
+            let hops = distances[&coord] + 1;
+            for neighbor in coord.neighbors() {
+                let friendly = game_state.nodes.get(&neighbor).is_some_and(|n| n.owner_id == player.player_id);
+                if friendly && !distances.contains_key(&neighbor) {
+                    distances.insert(neighbor, hops);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::events::{CommandEvent, FactEvent, GameEvent, TelemetryEvent};
+
+    fn game() -> GameState {
+        let mut state = GameState::new();
+        state.process_event(
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".to_string(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".to_string()),
+                client_endpoint: None,
+                timestamp: 0,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            1,
+        );
+        // A line east from the capital, and an island cut off from it
+        for (index, q) in (2..).zip([1, 2, 3, 4, 6]) {
+            let node_coord = NodeCoord::new(q, 0);
+            state.process_event(FactEvent::NodeInitializationStarted { node_coord, owner_id: 1, timestamp: 0 }.into(), index);
+        }
+        state
+    }
+
+    #[test]
+    fn test_supply_distances() {
+        let mut state = game();
+        let distances = supply_distances(&state);
+        assert_eq!(distances[&NodeCoord::new(0, 0)], 0);
+        assert_eq!(distances[&NodeCoord::new(4, 0)], 4);
+        assert!(!distances.contains_key(&NodeCoord::new(6, 0)));
+
+        // Lose the capital and the whole territory is cut off
+        let capture = FactEvent::CapitalContested { node_coord: NodeCoord::new(0, 0), player_id: 1, expires_at: 100, timestamp: 0 };
+        state.process_event(capture.into(), 7);
+        state.process_event(FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 0 }.into(), 8);
+        assert!(supply_distances(&state).is_empty());
+    }
+
+    #[test]
+    fn test_effectiveness_and_weighting() {
+        let supply = SupplyConfig { free_hops: 2, falloff_per_hop: 0.25, min_effectiveness: 0.25 };
+        assert_eq!(supply.effectiveness(Some(2)), 1.0);
+        assert_eq!(supply.effectiveness(Some(3)), 0.75);
+        assert_eq!(supply.effectiveness(Some(9)), 0.25);
+        assert_eq!(supply.effectiveness(None), 0.25);
+
+        let mut state = game();
+        let (near, far) = (NodeCoord::new(1, 1), NodeCoord::new(5, 0));
+        for (index, (from, to)) in (10..).zip([(NodeCoord::new(1, 0), near), (NodeCoord::new(4, 0), far), (NodeCoord::new(6, 0), far)]) {
+            let target = Some(AttackTarget::Coordinate(to));
+            state.process_event(CommandEvent::SetNodeTarget { node_coord: from, target, timestamp: 0 }.into(), index);
+        }
+        for (index, coord) in (20..).zip([near, far]) {
+            let report = TelemetryEvent::NodeMetricsReport {
+                node_coord: coord,
+                bandwidth_in: 1000,
+                packet_loss: 0.8,
+                timestamp: 0,
+                queued_attackers: Vec::new(),
+            };
+            state.process_event(report.into(), index);
+        }
+
+        let weighted = supply.weight_metrics(&state);
+        assert_eq!(weighted.node_metrics[&near].packet_loss, 0.8);
+        // 4 hops out (0.5) and cut off (0.25): weighted by 0.375
+        assert_eq!(weighted.node_metrics[&far].bandwidth_in, 375);
+        assert!((weighted.node_metrics[&far].packet_loss - 0.3).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,230 @@
+// Time-travel debugging - the game state as it was after any committed event
+//
+// GET /debug/state-at replays the stored events up to a position with
+// `replay::replay_committed` and shows the result; GET /debug/state-diff replays up
+// to two positions and lists what changed in between. Bisecting with the diff
+// answers "when exactly did Bob's node flip?" without reading the raw log.
+//
+// Positions are the ones GET /events pages by: 0 is the first committed event.
+
+use super::events::{AttackTarget, GamePhase, NodeCoord, NodeType};
+use super::replay::replay_committed;
+use super::state::{GameState, NodeInitState};
+use super::GameEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A player as of some position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub player_id: u64,
+    pub name: String,
+    pub capital_coord: NodeCoord,
+    pub alive: bool,
+    pub client_endpoint: Option<String>,
+    pub protected_until: u64,
+    pub idle_since: Option<u64>,
+}
+
+/// A node as of some position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeView {
+    pub coord: NodeCoord,
+    pub owner_id: u64,
+    pub node_type: NodeType,
+    pub current_target: Option<AttackTarget>,
+    pub ready: bool,
+    pub ip: Option<String>,
+    pub defending: bool,
+}
+
+/// The game state after the event at `index` was applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateView {
+    /// Position of the last applied event
+    pub index: usize,
+    /// Raft log index it was applied at
+    pub log_index: u64,
+    /// Leader clock at that point (unix seconds)
+    pub clock: u64,
+    pub game_over: bool,
+    #[serde(default)]
+    pub phase: GamePhase,
+    pub players: Vec<PlayerView>,
+    pub nodes: Vec<NodeView>,
+    /// Effects requested and not yet completed
+    pub pending_effects: usize,
+    /// `GameState::digest` of the replayed state
+    pub digest: u64,
+}
+
+impl StateView {
+    pub fn of(index: usize, state: &GameState) -> Self {
+        let players = state
+            .players
+            .values()
+            .map(|p| PlayerView {
+                player_id: p.player_id,
+                name: p.name.clone(),
+                capital_coord: p.capital_coord,
+                alive: p.alive,
+                client_endpoint: p.client_endpoint.clone(),
+                protected_until: p.protected_until,
+                idle_since: p.idle_since,
+            })
+            .collect();
+        let nodes = state
+            .nodes
+            .values()
+            .map(|n| NodeView {
+                coord: n.coord,
+                owner_id: n.owner_id,
+                node_type: n.node_type,
+                current_target: n.current_target,
+                ready: n.init_state == NodeInitState::Ready,
+                ip: state.node_ips.get(&n.coord).cloned(),
+                defending: state.defenses.contains_key(&n.coord),
+            })
+            .collect();
+        Self {
+            index,
+            log_index: state.last_applied_log_index,
+            clock: state.clock,
+            game_over: state.game_over,
+            phase: state.phase,
+            players,
+            nodes,
+            pending_effects: state.pending_effects().count(),
+            digest: state.digest(),
+        }
+    }
+}
+
+/// One thing in two states: what it was, and what it became
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Changed<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// What changed between the states at positions `from` and `to`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub from: usize,
+    pub to: usize,
+    pub players_added: Vec<PlayerView>,
+    pub players_removed: Vec<u64>,
+    pub players_changed: Vec<Changed<PlayerView>>,
+    pub nodes_added: Vec<NodeView>,
+    pub nodes_removed: Vec<NodeCoord>,
+    pub nodes_changed: Vec<Changed<NodeView>>,
+    /// The game moved to another phase in between
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<Changed<GamePhase>>,
+}
+
+impl StateDiff {
+    pub fn between(before: &StateView, after: &StateView) -> Self {
+        let (players_added, players_removed, players_changed) =
+            diff_by(&before.players, &after.players, |p| p.player_id);
+        let (nodes_added, nodes_removed, nodes_changed) = diff_by(&before.nodes, &after.nodes, |n| n.coord);
+        Self {
+            from: before.index,
+            to: after.index,
+            players_added,
+            players_removed,
+            players_changed,
+            nodes_added,
+            nodes_removed,
+            nodes_changed,
+            phase: (before.phase != after.phase).then_some(Changed { before: before.phase, after: after.phase }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players_added.is_empty()
+            && self.players_removed.is_empty()
+            && self.players_changed.is_empty()
+            && self.nodes_added.is_empty()
+            && self.nodes_removed.is_empty()
+            && self.nodes_changed.is_empty()
+            && self.phase.is_none()
+    }
+}
+
+/// Added, removed (by key) and changed items, in key order
+fn diff_by<T: Clone + PartialEq, K: Ord>(before: &[T], after: &[T], key: impl Fn(&T) -> K) -> (Vec<T>, Vec<K>, Vec<Changed<T>>) {
+    let before: BTreeMap<K, &T> = before.iter().map(|item| (key(item), item)).collect();
+    let mut after: BTreeMap<K, &T> = after.iter().map(|item| (key(item), item)).collect();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for (k, old) in before {
+        match after.remove(&k) {
+            None => removed.push(k),
+            Some(new) if new != old => changed.push(Changed { before: old.clone(), after: new.clone() }),
+            Some(_) => {}
+        }
+    }
+    (after.into_values().cloned().collect(), removed, changed)
+}
+
+/// Replay the committed log up to and including each position in `at` (ascending),
+/// returning the state after each
+/// Positions past the end of the log are an error naming how many events there are.
+pub fn states_at(at: &[usize], events: &[GameEvent], event_indices: &[u64], event_times: &[u64]) -> Result<Vec<StateView>, String> {
+    let mut state = GameState::new();
+    let mut replayed = 0;
+    let mut views = Vec::with_capacity(at.len());
+    for &index in at {
+        if index >= events.len() {
+            return Err(format!("Index {} is past the end of the log ({} events)", index, events.len()));
+        }
+        replay_committed(&mut state, replayed..index + 1, events, event_indices, event_times);
+        replayed = replayed.max(index + 1);
+        views.push(StateView::of(index, &state));
+    }
+    Ok(views)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{CommandEvent, FactEvent};
+
+    #[test]
+    fn test_state_diff() {
+        let events: Vec<GameEvent> = vec![
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id: 1,
+                name: "Alice".into(),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: Some("10.0.0.1".into()),
+                client_endpoint: None,
+                timestamp: 100,
+                raft_node_id: 0,
+                reconnect_key: None,
+            }),
+            FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(1, 0), owner_id: 0, timestamp: 101 }.into(),
+            FactEvent::NodeCaptured { node_coord: NodeCoord::new(1, 0), new_owner_id: 1, timestamp: 102 }.into(),
+        ];
+        let indices = [3, 5, 9];
+        let times = [100, 101, 102];
+
+        let views = states_at(&[0, 1, 2], &events, &indices, &times).unwrap();
+        assert_eq!((views[0].index, views[0].log_index), (0, 3));
+        assert_eq!(views[1].nodes.len(), 2);
+        assert_eq!(views[2].log_index, 9);
+
+        let diff = StateDiff::between(&views[0], &views[1]);
+        assert_eq!(diff.nodes_added.len(), 1);
+        assert!(diff.nodes_changed.is_empty() && diff.players_changed.is_empty());
+
+        // The capture shows as the node flipping owner
+        let diff = StateDiff::between(&views[1], &views[2]);
+        assert_eq!(diff.nodes_changed.len(), 1);
+        assert_eq!((diff.nodes_changed[0].before.owner_id, diff.nodes_changed[0].after.owner_id), (0, 1));
+        assert!(StateDiff::between(&views[2], &views[2]).is_empty());
+
+        assert!(states_at(&[3], &events, &indices, &times).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+// Attack transports - real packets, or a dry run that only pretends
+//
+// The real transport floods UDP (flooders and the responder on 8081) and opens
+// WebSockets for the final kill. That's fine in a game's own VPC but not on a shared
+// box or a CI runner. In dry-run mode nothing is sent or bound: each flooder models
+// its traffic against a synthetic uplink and writes the result into the same loss
+// tracker and inbound byte counter the real path feeds, so telemetry, capture
+// progress and captures run exactly as they would over the wire.
+//
+// The model: this node's uplink carries DRY_RUN_LINK_MBPS, scaled by its capacity and
+// defense like the responder's packet limit, shared by every flooder it runs. Each
+// flooder offers its egress share (or DRY_RUN_FLOODER_MBPS when egress is unpaced);
+// what doesn't fit is lost. With the defaults a regular node holds two attackers and
+// starts losing packets at the third. NETSIM_* and /debug/handicap loss come on top.
+
+use super::netsim::NetSimConfig;
+use super::udp::{InboundStats, PacketLossTracker};
+use anyhow::Result;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Default synthetic uplink of a regular-capacity node, megabits/sec
+pub const DEFAULT_DRY_RUN_LINK_MBPS: f64 = 8.0;
+
+/// Default rate each synthetic flooder offers when egress isn't budgeted, megabits/sec
+pub const DEFAULT_DRY_RUN_FLOODER_MBPS: f64 = 3.0;
+
+/// Size of a synthetic attack packet (as the real flooders send)
+pub const DRY_RUN_PACKET_BYTES: u64 = 1024;
+
+/// How often a synthetic flooder accounts for its traffic
+pub const DRY_RUN_TICK: Duration = Duration::from_millis(100);
+
+/// Which transport attack traffic takes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TransportMode {
+    /// Real UDP floods and final kill WebSockets
+    #[default]
+    Udp,
+    /// No packets - flooders and final kills simulate their traffic
+    DryRun(DryRunConfig),
+}
+
+impl TransportMode {
+    /// NET_TRANSPORT: `udp` (default) or `dry-run`
+    /// Builds with the `dry-run` feature always dry-run, whatever NET_TRANSPORT says.
+    pub fn from_env() -> Self {
+        let requested = std::env::var("NET_TRANSPORT").ok();
+        let dry_run = match requested.as_deref().map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("udp") => false,
+            Some("dry-run") | Some("dryrun") | Some("dry_run") => true,
+            Some(other) => {
+                eprintln!("[Transport] Unknown NET_TRANSPORT '{}', using udp", other);
+                false
+            }
+        };
+        if cfg!(feature = "dry-run") && !dry_run {
+            if requested.is_some() {
+                eprintln!("[Transport] Built with the dry-run feature, ignoring NET_TRANSPORT");
+            }
+            return Self::dry_run_from_env();
+        }
+        if dry_run {
+            Self::dry_run_from_env()
+        } else {
+            Self::Udp
+        }
+    }
+
+    fn dry_run_from_env() -> Self {
+        let config = DryRunConfig::from_env();
+        println!(
+            "[Transport] Dry run: no attack packets leave this process (link {} KB/s, flooders {} KB/s)",
+            config.link_bytes_per_sec / 1024,
+            config.flooder_bytes_per_sec / 1024
+        );
+        Self::DryRun(config)
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        matches!(self, Self::DryRun(_))
+    }
+}
+
+/// Parameters of the dry-run traffic model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DryRunConfig {
+    /// Synthetic uplink of a regular-capacity node in bytes/sec
+    pub link_bytes_per_sec: u64,
+    /// What a flooder offers when egress isn't budgeted, in bytes/sec
+    pub flooder_bytes_per_sec: u64,
+}
+
+impl DryRunConfig {
+    /// DRY_RUN_LINK_MBPS (default: 8) and DRY_RUN_FLOODER_MBPS (default: 3)
+    pub fn from_env() -> Self {
+        let mbps = |name: &str, default: f64| {
+            let mbps: f64 = std::env::var(name).ok().and_then(|s| s.parse().ok()).unwrap_or(default);
+            (mbps.max(0.0) * 1_000_000.0 / 8.0) as u64
+        };
+        Self {
+            link_bytes_per_sec: mbps("DRY_RUN_LINK_MBPS", DEFAULT_DRY_RUN_LINK_MBPS),
+            flooder_bytes_per_sec: mbps("DRY_RUN_FLOODER_MBPS", DEFAULT_DRY_RUN_FLOODER_MBPS),
+        }
+    }
+
+    /// The uplink of a node of `capacity`, with `multiplier` from its defense
+    pub fn link_rate(&self, capacity: f32, multiplier: f32) -> u64 {
+        (self.link_bytes_per_sec as f64 * capacity.max(0.0) as f64 * multiplier.max(1.0) as f64) as u64
+    }
+
+    /// What one of `flooders` sharing a `link` bytes/sec uplink gets through in `elapsed`
+    /// `rate` is the flooder's egress share (0 = unpaced, offering `flooder_bytes_per_sec`)
+    /// and `loss` the impairment on top. Returns (offered, delivered) bytes.
+    pub fn traffic(&self, link: u64, flooders: usize, rate: u64, loss: f32, elapsed: Duration) -> (u64, u64) {
+        let rate = if rate == 0 { self.flooder_bytes_per_sec } else { rate };
+        let secs = elapsed.as_secs_f64();
+        let offered = (rate as f64 * secs) as u64;
+        let share = (link as f64 / flooders.max(1) as f64 * secs) as u64;
+        let delivered = offered.min(share) as f64 * (1.0 - loss.clamp(0.0, 1.0) as f64);
+        (offered, delivered as u64)
+    }
+}
+
+impl Default for DryRunConfig {
+    fn default() -> Self {
+        Self {
+            link_bytes_per_sec: (DEFAULT_DRY_RUN_LINK_MBPS * 1_000_000.0 / 8.0) as u64,
+            flooder_bytes_per_sec: (DEFAULT_DRY_RUN_FLOODER_MBPS * 1_000_000.0 / 8.0) as u64,
+        }
+    }
+}
+
+/// The synthetic uplink flooders share, kept current by NetworkManager
+#[derive(Debug, Clone, Default)]
+pub struct DryRunLink {
+    /// This node's uplink in bytes/sec (see `DryRunConfig::link_rate`)
+    pub rate: Arc<AtomicU64>,
+    /// Flooders running
+    pub flooders: Arc<AtomicUsize>,
+}
+
+/// Stand-in for the responder: binds nothing, runs until aborted
+pub async fn dry_run_responder() -> Result<()> {
+    println!("[Transport] Dry run: responder not bound");
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+/// Dry-run flooder - accounts for the packets a real one would send and get ACKed
+/// Counts them into `tracker` (for loss) and the delivered bytes into `inbound`
+/// (for bandwidth) every DRY_RUN_TICK until stopped.
+pub async fn dry_run_attacker(
+    config: DryRunConfig,
+    link: DryRunLink,
+    tracker: PacketLossTracker,
+    inbound: InboundStats,
+    netsim: NetSimConfig,
+    rate: Arc<AtomicU64>,
+    mut stop_signal: broadcast::Receiver<()>,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(DRY_RUN_TICK);
+    let mut last = Instant::now();
+    loop {
+        tokio::select! {
+            _ = stop_signal.recv() => {
+                println!("[Transport] Dry-run attack stopped, final loss: {:.2}%", tracker.calculate_loss() * 100.0);
+                return Ok(());
+            }
+            _ = interval.tick() => {
+                let now = Instant::now();
+                let loss = inbound.handicap.apply(netsim).loss;
+                let (offered, delivered) = config.traffic(
+                    link.rate.load(Ordering::Relaxed),
+                    link.flooders.load(Ordering::Relaxed),
+                    rate.load(Ordering::Relaxed),
+                    loss,
+                    now.duration_since(last),
+                );
+                last = now;
+                tracker.sent.fetch_add(offered / DRY_RUN_PACKET_BYTES, Ordering::Relaxed);
+                tracker.acked.fetch_add(delivered / DRY_RUN_PACKET_BYTES, Ordering::Relaxed);
+                inbound.bytes.fetch_add(delivered, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_traffic_model() {
+        let config = DryRunConfig { link_bytes_per_sec: 1_000_000, flooder_bytes_per_sec: 400_000 };
+        let link = config.link_rate(1.0, 1.0);
+
+        // Two flooders fit on the link
+        assert_eq!(config.traffic(link, 2, 0, 0.0, SECOND), (400_000, 400_000));
+        // A third overloads it: each gets a third of the link
+        assert_eq!(config.traffic(link, 3, 0, 0.0, SECOND), (400_000, 333_333));
+        // A paced flooder offers its egress share instead
+        assert_eq!(config.traffic(link, 4, 100_000, 0.0, SECOND), (100_000, 100_000));
+        // Handicap/netsim loss comes on top
+        assert_eq!(config.traffic(link, 1, 0, 0.5, SECOND), (400_000, 200_000));
+
+        // Defense and capacity widen the link
+        assert_eq!(config.link_rate(2.0, 1.5), 3_000_000);
+        assert_eq!(config.link_rate(1.0, 0.0), 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_attacker_reports_loss() {
+        let config = DryRunConfig { link_bytes_per_sec: 1_000_000, flooder_bytes_per_sec: 1_000_000 };
+        let link = DryRunLink::default();
+        link.rate.store(config.link_rate(1.0, 1.0), Ordering::Relaxed);
+        // Four flooders on the link: three quarters of what each offers is lost
+        link.flooders.store(4, Ordering::Relaxed);
+        let (tracker, inbound) = (PacketLossTracker::new(), InboundStats::new());
+        let (stop_tx, stop_rx) = broadcast::channel(1);
+
+        let task = tokio::spawn(dry_run_attacker(
+            config,
+            link,
+            tracker.clone(),
+            inbound.clone(),
+            NetSimConfig::disabled(),
+            Arc::new(AtomicU64::new(0)),
+            stop_rx,
+        ));
+        tokio::time::sleep(DRY_RUN_TICK * 4).await;
+        stop_tx.send(()).unwrap();
+        task.await.unwrap().unwrap();
+
+        assert!(tracker.sent.load(Ordering::Relaxed) > 0);
+        assert!((tracker.calculate_loss() - 0.75).abs() < 0.05, "loss {}", tracker.calculate_loss());
+        assert!(inbound.bytes.load(Ordering::Relaxed) > 0);
+    }
+}
@@ -1,6 +1,10 @@
+use super::netsim::{Handicap, NetSimConfig};
+use super::udp_batch::{recv_batch, send_batch, BatchMode, RecvBatch, SharedSocket, SocketPool, SEND_BATCH};
 use anyhow::Result;
+use crate::supervisor::Supervisor;
+use bincode::Options;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -8,8 +12,20 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::net::UdpSocket;
 use tokio::sync::broadcast;
 
+/// Most bytes a datagram decode reads (an attack packet is ~1KB; recv buffers are 2KB)
+pub const MAX_DATAGRAM_BYTES: u64 = 2048;
+
+/// bincode as `bincode::serialize` writes it, but refusing to read past MAX_DATAGRAM_BYTES
+/// Datagrams come from anyone, so a length prefix can't make a decode allocate more.
+fn datagram_codec() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_DATAGRAM_BYTES)
+}
+
 /// UDP attack packet sent to flood target
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UdpAttackPacket {
     pub seq: u64,            // Sequence number
     pub timestamp: u64,       // Unix timestamp (microseconds)
@@ -17,12 +33,34 @@ pub struct UdpAttackPacket {
 }
 
 /// ACK packet sent back to attacker
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UdpAckPacket {
     pub ack_seq: u64,       // Highest contiguous sequence received
     pub received_count: u64, // Total packets received
 }
 
+impl UdpAttackPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("attack packets always serialize")
+    }
+
+    /// Parse a received datagram, None if it isn't an attack packet
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        datagram_codec().deserialize(bytes).ok()
+    }
+}
+
+impl UdpAckPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("ACK packets always serialize")
+    }
+
+    /// Parse a received datagram, None if it isn't an ACK
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        datagram_codec().deserialize(bytes).ok()
+    }
+}
+
 /// Shared state for tracking packet loss
 #[derive(Clone)]
 pub struct PacketLossTracker {
@@ -50,73 +88,251 @@ impl PacketLossTracker {
     }
 }
 
+impl Default for PacketLossTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How often the responder ACKs each source and publishes per-source counts
+const ACK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest a blocking receive waits, so ACKs still go out when traffic pauses
+const RECV_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// Receive buffer requested for the responder, to ride out bursts between batches
+const RECV_BUFFER_BYTES: usize = 8 * 1024 * 1024;
+
+/// How long a source can go quiet before the responder forgets it
+const SOURCE_IDLE: Duration = Duration::from_secs(30);
+
+/// Attack traffic from one source address, as the responder counts it
+#[derive(Debug, Clone, Copy)]
+pub struct SourceStats {
+    pub packets: u64,
+    pub bytes: u64,
+    /// Highest sequence number seen (flooders sharing a socket interleave theirs)
+    pub highest_seq: u64,
+    pub last_seen: Instant,
+}
+
+/// What the responder has received, shared with the rest of the process
+/// `bytes` is bumped by the receive thread without taking a lock; per-source counts
+/// are published every ACK_INTERVAL.
+#[derive(Clone, Default)]
+pub struct InboundStats {
+    pub bytes: Arc<AtomicU64>,
+    sources: Arc<std::sync::RwLock<HashMap<SocketAddr, SourceStats>>>,
+    /// Demo impairment on top of the responder's netsim (POST /debug/handicap)
+    pub handicap: Handicap,
+}
+
+impl InboundStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sources heard from in the last SOURCE_IDLE, as of the last publish
+    pub fn sources(&self) -> HashMap<SocketAddr, SourceStats> {
+        self.sources.read().unwrap().clone()
+    }
+}
+
 /// UDP responder - receives attack packets and sends ACKs
-/// Runs on port 8081
-pub async fn udp_responder(
-    bytes_received: Arc<AtomicU64>,
-) -> Result<()> {
-    let socket = UdpSocket::bind("0.0.0.0:8081").await?;
+/// Runs on port 8081, receiving on a dedicated blocking thread (see `receive_loop`)
+/// `netsim` (plus any `inbound.handicap`) drops inbound attack packets and delays
+/// outgoing ACKs when enabled; `max_pps` caps the packets/sec taken (0 = unlimited), see `PacketGate`
+pub async fn udp_responder(inbound: InboundStats, netsim: NetSimConfig, max_pps: Arc<AtomicU64>) -> Result<()> {
+    let socket = std::net::UdpSocket::bind((crate::metadata::bind_ip(), 8081))?;
+    socket.set_read_timeout(Some(RECV_TIMEOUT))?;
+    if let Err(e) = socket2::SockRef::from(&socket).set_recv_buffer_size(RECV_BUFFER_BYTES) {
+        // The kernel caps it at rmem_max - smaller is fine, just drops sooner
+        eprintln!("[UDP] Couldn't raise receive buffer: {}", e);
+    }
     println!("[UDP] Responder listening on port 8081");
 
-    let mut buf = [0u8; 2048];
-    let mut sequences: HashSet<u64> = HashSet::new();
+    tokio::task::spawn_blocking(move || receive_loop(socket, inbound, netsim, max_pps)).await?
+}
+
+/// Holds the responder to its packets/sec limit, counted in one-second windows
+/// Packets over the limit are dropped without being counted or ACKed, so the
+/// flooders sending them see loss - the limit is what a defend order raises.
+#[derive(Debug)]
+pub struct PacketGate {
+    window: Instant,
+    admitted: u64,
+}
+
+impl PacketGate {
+    pub fn new(now: Instant) -> Self {
+        Self { window: now, admitted: 0 }
+    }
+
+    /// Whether one more packet fits under `limit` packets/sec (0 = unlimited)
+    pub fn admit(&mut self, limit: u64, now: Instant) -> bool {
+        if limit == 0 {
+            return true;
+        }
+        if now.duration_since(self.window) >= Duration::from_secs(1) {
+            *self = Self::new(now);
+        }
+        if self.admitted >= limit {
+            return false;
+        }
+        self.admitted += 1;
+        true
+    }
+}
+
+/// The responder's receive thread
+///
+/// Takes packets a batch at a time (recvmmsg), drops those over the packets/sec limit,
+/// adds the rest's bytes to the shared counter and counts them per source. Every ACK_INTERVAL each source heard from since the
+/// last round is ACKed with its own totals, so a flooder's loss reflects only its
+/// own packets.
+fn receive_loop(
+    socket: std::net::UdpSocket,
+    inbound: InboundStats,
+    netsim: NetSimConfig,
+    max_pps: Arc<AtomicU64>,
+) -> Result<()> {
+    let mode = BatchMode::from_env();
+    let mut batch = RecvBatch::new();
+    let mut gate = PacketGate::new(Instant::now());
+    let mut sources: HashMap<SocketAddr, SourceStats> = HashMap::new();
+    // ACKs held back by netsim: (due, ack, peer)
+    let mut delayed_acks: Vec<(Instant, Vec<u8>, SocketAddr)> = Vec::new();
     let mut last_ack = Instant::now();
-    let mut packets_received = 0u64;
 
     loop {
-        match socket.recv_from(&mut buf).await {
-            Ok((len, peer)) => {
-                // Track bytes
-                bytes_received.fetch_add(len as u64, Ordering::Relaxed);
-
-                // Try to parse packet
-                if let Ok(packet) = bincode::deserialize::<UdpAttackPacket>(&buf[..len]) {
-                    sequences.insert(packet.seq);
-                    packets_received += 1;
-
-                    // Send ACK every 100ms
-                    if last_ack.elapsed() > Duration::from_millis(100) {
-                        let ack = UdpAckPacket {
-                            ack_seq: *sequences.iter().max().unwrap_or(&0),
-                            received_count: packets_received,
-                        };
-
-                        if let Ok(ack_bytes) = bincode::serialize(&ack) {
-                            let _ = socket.send_to(&ack_bytes, peer).await;
-                        }
-
-                        last_ack = Instant::now();
+        match recv_batch(&socket, &mut batch, mode) {
+            Ok(_) => {
+                let now = Instant::now();
+                let limit = max_pps.load(Ordering::Relaxed);
+                let netsim = inbound.handicap.apply(netsim);
+                for (packet, peer) in batch.packets() {
+                    // Simulated loss - pretend the packet never arrived
+                    if netsim.should_drop() {
+                        continue;
+                    }
+                    // Over the responder's limit - dropped like a full queue would
+                    if !gate.admit(limit, now) {
+                        continue;
+                    }
+                    inbound.bytes.fetch_add(packet.len() as u64, Ordering::Relaxed);
+
+                    if let Some(attack) = UdpAttackPacket::decode(packet) {
+                        let source = sources.entry(peer).or_insert(SourceStats {
+                            packets: 0,
+                            bytes: 0,
+                            highest_seq: 0,
+                            last_seen: now,
+                        });
+                        source.packets += 1;
+                        source.bytes += packet.len() as u64;
+                        source.highest_seq = source.highest_seq.max(attack.seq);
+                        source.last_seen = now;
                     }
                 }
             }
-            Err(e) => {
-                eprintln!("[UDP] Responder error: {}", e);
+            // Read timeout - nothing arrived, fall through to the ACKs
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => eprintln!("[UDP] Responder error: {}", e),
+        }
+
+        let now = Instant::now();
+        if now.duration_since(last_ack) >= ACK_INTERVAL {
+            let netsim = inbound.handicap.apply(netsim);
+            for (peer, source) in sources.iter().filter(|(_, s)| s.last_seen >= last_ack) {
+                let ack = UdpAckPacket { ack_seq: source.highest_seq, received_count: source.packets };
+                let ack_bytes = ack.encode();
+                if netsim.is_enabled() {
+                    // Simulated latency - send the ACK later without stalling receives
+                    delayed_acks.push((now + netsim.sample_delay(), ack_bytes, *peer));
+                } else {
+                    let _ = socket.send_to(&ack_bytes, peer);
+                }
             }
+            sources.retain(|_, s| now.duration_since(s.last_seen) < SOURCE_IDLE);
+            *inbound.sources.write().unwrap() = sources.clone();
+            last_ack = now;
         }
+
+        delayed_acks.retain(|(due, ack_bytes, peer)| {
+            if *due > now {
+                return true;
+            }
+            let _ = socket.send_to(ack_bytes, peer);
+            false
+        });
     }
 }
 
-/// UDP attacker - sends attack packets to target
-pub async fn udp_attacker(
-    target_ip: String,
-    tracker: PacketLossTracker,
-    mut stop_signal: broadcast::Receiver<()>,
-) -> Result<()> {
-    let socket = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
-    let target: SocketAddr = format!("{}:8081", target_ip).parse()?;
+/// Keeps a flooder at or under its share of the egress budget
+///
+/// Tokio's timer is millisecond-grained and a 1KB packet at a few MB/s is due every
+/// few hundred microseconds, so rather than sleeping per packet the pacer lets sends
+/// run until they are ahead of `rate * elapsed` and then sleeps off the lead.
+#[derive(Debug)]
+pub struct EgressPacer {
+    rate: u64,
+    since: Instant,
+    sent: u64,
+}
 
-    println!("[UDP] Starting attack on {}", target);
+impl EgressPacer {
+    pub fn new() -> Self {
+        Self { rate: 0, since: Instant::now(), sent: 0 }
+    }
 
-    // Spawn ACK receiver
-    let socket_clone = socket.clone();
-    let tracker_clone = tracker.clone();
-    tokio::spawn(async move {
-        if let Err(e) = ack_receiver(socket_clone, tracker_clone).await {
-            eprintln!("[UDP] ACK receiver error: {}", e);
+    /// Count `bytes` sent at `rate` bytes/sec (0 = unpaced), returning how long to wait
+    /// A changed rate starts a fresh window so the old rate's lead isn't carried over.
+    pub fn record(&mut self, bytes: usize, rate: u64, now: Instant) -> Option<Duration> {
+        if rate != self.rate {
+            *self = Self { rate, since: now, sent: 0 };
+        }
+        if rate == 0 {
+            return None;
         }
-    });
+        self.sent += bytes as u64;
+        let due = Duration::from_secs_f64(self.sent as f64 / rate as f64);
+        let elapsed = now.duration_since(self.since);
+        // Sub-millisecond leads are left to build up into one sleep
+        due.checked_sub(elapsed).filter(|lead| *lead >= Duration::from_millis(1))
+    }
+}
+
+impl Default for EgressPacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resolve `target_ip`'s responder and take the pool's socket to it
+/// Resolved once per attack - targets may be DNS names (DNS discovery mode)
+pub async fn open_flooder_socket(pool: &SocketPool, target_ip: &str, netsim: NetSimConfig) -> Result<Arc<SharedSocket>> {
+    let target: SocketAddr = tokio::net::lookup_host(format!("{}:8081", target_ip))
+        .await?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No address for {}", target_ip))?;
+    pool.get(target, netsim)
+}
+
+/// UDP attacker - sends attack packets to target in batches of SEND_BATCH
+/// `rate` is this flooder's share of the egress budget in bytes/sec (0 = as fast as
+/// possible), set by NetworkManager
+pub async fn udp_attacker(
+    shared: Arc<SharedSocket>,
+    mut stop_signal: broadcast::Receiver<()>,
+    rate: Arc<AtomicU64>,
+) -> Result<()> {
+    println!("[UDP] Starting attack on {}", shared.target);
 
+    let mode = BatchMode::from_env();
+    let tracker = &shared.tracker;
     let mut seq = 0u64;
+    let mut pacer = EgressPacer::new();
+    let mut batch: Vec<Vec<u8>> = Vec::with_capacity(SEND_BATCH);
 
     loop {
         tokio::select! {
@@ -126,24 +342,26 @@ pub async fn udp_attacker(
                 break;
             }
 
-            // Send packet
+            // Send a batch
             _ = async {
-                let packet = UdpAttackPacket {
-                    seq,
-                    timestamp: SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_micros() as u64,
-                    payload: vec![0u8; 1024],
-                };
-
-                if let Ok(bytes) = bincode::serialize(&packet) {
-                    let _ = socket.send_to(&bytes, target).await;
-                    tracker.sent.fetch_add(1, Ordering::Relaxed);
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_micros() as u64;
+                batch.clear();
+                for _ in 0..SEND_BATCH {
+                    batch.push(UdpAttackPacket { seq, timestamp, payload: vec![0u8; 1024] }.encode());
                     seq += 1;
                 }
 
-                Ok::<_, anyhow::Error>(())
+                // Send errors are ignored as before - what doesn't arrive shows up as loss
+                let _ = send_batch(&shared.socket, shared.target, &batch, mode).await;
+                tracker.sent.fetch_add(batch.len() as u64, Ordering::Relaxed);
+
+                let bytes = batch.iter().map(Vec::len).sum();
+                if let Some(wait) = pacer.record(bytes, rate.load(Ordering::Relaxed), Instant::now()) {
+                    tokio::time::sleep(wait).await;
+                }
             } => {}
         }
     }
@@ -152,17 +370,33 @@ pub async fn udp_attacker(
 }
 
 /// ACK receiver - listens for ACKs from target and updates packet loss
-async fn ack_receiver(
+pub(crate) async fn ack_receiver(
     socket: Arc<UdpSocket>,
     tracker: PacketLossTracker,
+    netsim: NetSimConfig,
 ) -> Result<()> {
     let mut buf = [0u8; 256];
 
     loop {
         match socket.recv_from(&mut buf).await {
             Ok((len, _peer)) => {
-                if let Ok(ack) = bincode::deserialize::<UdpAckPacket>(&buf[..len]) {
-                    tracker.acked.store(ack.received_count, Ordering::Relaxed);
+                if netsim.should_drop() {
+                    continue;
+                }
+
+                if let Some(ack) = UdpAckPacket::decode(&buf[..len]) {
+                    if netsim.is_enabled() {
+                        // Delayed ACKs can land out of order under jitter - keep the highest count
+                        let acked = tracker.acked.clone();
+                        let delay = netsim.sample_delay();
+                        Supervisor::global().spawn_transient("udp_delayed_ack", async move {
+                            tokio::time::sleep(delay).await;
+                            acked.fetch_max(ack.received_count, Ordering::Relaxed);
+                            Ok(())
+                        });
+                    } else {
+                        tracker.acked.store(ack.received_count, Ordering::Relaxed);
+                    }
                 }
             }
             Err(e) => {
@@ -174,3 +408,76 @@ async fn ack_receiver(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_attack_packet_roundtrip(seq: u64, timestamp: u64, payload in vec(any::<u8>(), 0..1100)) {
+            let packet = UdpAttackPacket { seq, timestamp, payload };
+            prop_assert_eq!(UdpAttackPacket::decode(&packet.encode()), Some(packet));
+        }
+
+        #[test]
+        fn prop_ack_packet_roundtrip(ack_seq: u64, received_count: u64) {
+            let ack = UdpAckPacket { ack_seq, received_count };
+            prop_assert_eq!(UdpAckPacket::decode(&ack.encode()), Some(ack));
+        }
+
+        #[test]
+        fn prop_garbage_datagrams_decode_or_fail_cleanly(bytes in vec(any::<u8>(), 0..4096)) {
+            let _ = UdpAttackPacket::decode(&bytes);
+            let _ = UdpAckPacket::decode(&bytes);
+        }
+
+        #[test]
+        fn prop_truncated_attack_packets_are_rejected(payload in vec(any::<u8>(), 1..1100), cut in any::<prop::sample::Index>()) {
+            let bytes = UdpAttackPacket { seq: 1, timestamp: 2, payload }.encode();
+            let cut = cut.index(bytes.len());
+            prop_assert_eq!(UdpAttackPacket::decode(&bytes[..cut]), None);
+        }
+    }
+
+    #[test]
+    fn test_oversized_length_prefix_is_rejected() {
+        // seq, timestamp, then a payload claiming u64::MAX bytes
+        let mut bytes = vec![0u8; 16];
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 64]);
+        assert_eq!(UdpAttackPacket::decode(&bytes), None);
+    }
+
+    #[test]
+    fn test_pacer_holds_flooder_to_rate() {
+        let mut pacer = EgressPacer::new();
+        let start = Instant::now();
+
+        // Unpaced never waits
+        assert_eq!(pacer.record(1024, 0, start), None);
+
+        // 100 KB/s: ten 1KB packets at once are 100ms ahead of schedule
+        let waits: Vec<_> = (0..10).map(|_| pacer.record(1000, 100_000, start)).collect();
+        assert_eq!(waits[9], Some(Duration::from_millis(100)));
+        // ...and caught up once that time has passed
+        assert_eq!(pacer.record(100, 100_000, start + Duration::from_millis(200)), None);
+
+        // A new rate forgets the old lead
+        assert_eq!(pacer.record(1000, 10_000_000, start + Duration::from_millis(200)), None);
+    }
+
+    #[test]
+    fn test_gate_holds_responder_to_limit() {
+        let start = Instant::now();
+        let mut gate = PacketGate::new(start);
+
+        assert!((0..1000).all(|_| gate.admit(0, start)));
+        let admitted = (0..10).filter(|_| gate.admit(4, start)).count();
+        assert_eq!(admitted, 4);
+        // A fresh window next second
+        assert!(gate.admit(4, start + Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,367 @@
+// Batched UDP sending and receiving for flooders and the responder
+//
+// A flooder used to bind its own socket and pay one send_to syscall per 1KB packet,
+// which tops out well below what a Fargate task's network can carry. Flooders now
+// send a batch of packets per syscall (sendmmsg on Linux, a try_send loop elsewhere)
+// on a socket they share with every other flooder aimed at the same host, so a
+// swarm of attackers behind one address costs one socket and one ACK receiver.
+// The responder likewise takes a batch per recvmmsg call (`recv_batch`).
+
+use super::netsim::NetSimConfig;
+use super::udp::{ack_receiver, PacketLossTracker};
+use crate::supervisor::Supervisor;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::io::Interest;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+/// Packets a flooder hands to the kernel per send
+pub const SEND_BATCH: usize = 32;
+
+/// Send buffer requested for flooder sockets, so a batch doesn't hit a full queue
+const SEND_BUFFER_BYTES: usize = 4 * 1024 * 1024;
+
+/// How a batch reaches the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// One sendmmsg/recvmmsg call per batch (Linux only)
+    Batched,
+    /// One send per packet - the portable fallback
+    PerPacket,
+}
+
+/// Set once sendmmsg or recvmmsg has failed as unsupported, so later batches skip them
+static MMSG_UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+impl BatchMode {
+    /// Batched where the platform has it, unless NET_SENDMMSG=0
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("NET_SENDMMSG").map(|v| v != "0").unwrap_or(true);
+        if enabled && cfg!(target_os = "linux") && !MMSG_UNSUPPORTED.load(Ordering::Relaxed) {
+            BatchMode::Batched
+        } else {
+            BatchMode::PerPacket
+        }
+    }
+}
+
+/// A non-blocking UDP socket for sending to `target`, with a large send buffer
+/// Left unconnected like the old per-flooder sockets: a connected socket would turn
+/// ICMP port-unreachable into errors that kill the ACK receiver.
+pub fn bind_socket(target: SocketAddr) -> io::Result<UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(target), Type::DGRAM, Some(Protocol::UDP))?;
+    if let Err(e) = socket.set_send_buffer_size(SEND_BUFFER_BYTES) {
+        // The kernel caps it at wmem_max - smaller is fine, just slower
+        eprintln!("[UDP] Couldn't raise send buffer: {}", e);
+    }
+    socket.set_nonblocking(true)?;
+    let local: SocketAddr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }.parse().unwrap();
+    socket.bind(&local.into())?;
+    UdpSocket::from_std(socket.into())
+}
+
+/// Send every packet in `packets` to `target`, waiting for buffer space as needed
+pub async fn send_batch(
+    socket: &UdpSocket,
+    target: SocketAddr,
+    packets: &[Vec<u8>],
+    mut mode: BatchMode,
+) -> io::Result<()> {
+    let mut next = 0;
+    while next < packets.len() {
+        socket.writable().await?;
+        let sent = match mode {
+            BatchMode::Batched => socket.try_io(Interest::WRITABLE, || sendmmsg(socket, target, &packets[next..])),
+            BatchMode::PerPacket => send_each(socket, target, &packets[next..]),
+        };
+        match sent {
+            Ok(n) => next += n,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            // ENOSYS maps to Unsupported - e.g. under a sandbox that filters the syscall
+            Err(e) if mode == BatchMode::Batched && e.kind() == io::ErrorKind::Unsupported => {
+                eprintln!("[UDP] sendmmsg unsupported, falling back to one send per packet");
+                MMSG_UNSUPPORTED.store(true, Ordering::Relaxed);
+                mode = BatchMode::PerPacket;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// try_send until the batch is done or the socket is full (WouldBlock if nothing went)
+fn send_each(socket: &UdpSocket, target: SocketAddr, packets: &[Vec<u8>]) -> io::Result<usize> {
+    for (sent, packet) in packets.iter().enumerate() {
+        match socket.try_send_to(packet, target) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock && sent > 0 => return Ok(sent),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(packets.len())
+}
+
+/// One sendmmsg call for as much of `packets` as the kernel takes
+#[cfg(target_os = "linux")]
+fn sendmmsg(socket: &UdpSocket, target: SocketAddr, packets: &[Vec<u8>]) -> io::Result<usize> {
+    use std::os::fd::AsRawFd;
+
+    let address = socket2::SockAddr::from(target);
+    let mut iovecs: Vec<libc::iovec> = packets
+        .iter()
+        .map(|p| libc::iovec { iov_base: p.as_ptr() as *mut libc::c_void, iov_len: p.len() })
+        .collect();
+    let mut headers: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .map(|iov| {
+            // SAFETY: mmsghdr is plain C data; all-zero is a valid empty header
+            let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            header.msg_hdr.msg_name = address.as_ptr() as *mut libc::c_void;
+            header.msg_hdr.msg_namelen = address.len();
+            header.msg_hdr.msg_iov = iov;
+            header.msg_hdr.msg_iovlen = 1;
+            header
+        })
+        .collect();
+
+    // SAFETY: the headers point into `address`, `iovecs` and `packets`, all alive for the call
+    let sent = unsafe { libc::sendmmsg(socket.as_raw_fd(), headers.as_mut_ptr(), headers.len() as _, 0 as _) };
+    if sent < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(sent as usize)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sendmmsg(_socket: &UdpSocket, _target: SocketAddr, _packets: &[Vec<u8>]) -> io::Result<usize> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Packets the responder takes from the kernel per receive
+pub const RECV_BATCH: usize = 32;
+
+/// Largest datagram the responder reads whole (attack packets are ~1KB)
+const RECV_BUFFER_BYTES: usize = 2048;
+
+/// Reusable buffers for `recv_batch`
+pub struct RecvBatch {
+    buffers: Vec<[u8; RECV_BUFFER_BYTES]>,
+    /// (buffer index, length, sender) per datagram received
+    received: Vec<(usize, usize, SocketAddr)>,
+}
+
+impl RecvBatch {
+    pub fn new() -> Self {
+        Self { buffers: vec![[0u8; RECV_BUFFER_BYTES]; RECV_BATCH], received: Vec::with_capacity(RECV_BATCH) }
+    }
+
+    /// Datagrams from the last receive, with their senders
+    pub fn packets(&self) -> impl Iterator<Item = (&[u8], SocketAddr)> {
+        self.received.iter().map(|(index, len, peer)| (&self.buffers[*index][..*len], *peer))
+    }
+}
+
+impl Default for RecvBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Block until at least one datagram arrives on `socket`, then take up to RECV_BATCH
+/// Returns how many arrived; a read timeout on the socket surfaces as WouldBlock/TimedOut.
+pub fn recv_batch(socket: &std::net::UdpSocket, batch: &mut RecvBatch, mode: BatchMode) -> io::Result<usize> {
+    batch.received.clear();
+    if mode == BatchMode::Batched {
+        match recvmmsg(socket, batch) {
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                eprintln!("[UDP] recvmmsg unsupported, falling back to one receive per packet");
+                MMSG_UNSUPPORTED.store(true, Ordering::Relaxed);
+            }
+            result => return result,
+        }
+    }
+    let (len, peer) = socket.recv_from(&mut batch.buffers[0])?;
+    batch.received.push((0, len, peer));
+    Ok(1)
+}
+
+/// One recvmmsg call: waits for the first datagram, then takes whatever else is queued
+#[cfg(target_os = "linux")]
+fn recvmmsg(socket: &std::net::UdpSocket, batch: &mut RecvBatch) -> io::Result<usize> {
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: sockaddr_storage is plain C data; all-zero is valid
+    let mut addresses: Vec<libc::sockaddr_storage> = vec![unsafe { std::mem::zeroed() }; RECV_BATCH];
+    let mut iovecs: Vec<libc::iovec> = batch
+        .buffers
+        .iter_mut()
+        .map(|buf| libc::iovec { iov_base: buf.as_mut_ptr() as *mut libc::c_void, iov_len: buf.len() })
+        .collect();
+    let mut headers: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .zip(addresses.iter_mut())
+        .map(|(iov, address)| {
+            // SAFETY: as in `sendmmsg`
+            let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            header.msg_hdr.msg_name = address as *mut libc::sockaddr_storage as *mut libc::c_void;
+            header.msg_hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+            header.msg_hdr.msg_iov = iov;
+            header.msg_hdr.msg_iovlen = 1;
+            header
+        })
+        .collect();
+
+    // SAFETY: the headers point into `addresses`, `iovecs` and the batch's buffers, all
+    // alive for the call; MSG_WAITFORONE blocks only until the first datagram
+    let received = unsafe {
+        libc::recvmmsg(
+            socket.as_raw_fd(),
+            headers.as_mut_ptr(),
+            headers.len() as _,
+            libc::MSG_WAITFORONE as _,
+            std::ptr::null_mut(),
+        )
+    };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    for (index, (header, address)) in headers.iter().zip(&addresses).take(received as usize).enumerate() {
+        // SAFETY: the kernel filled `address` and set its length
+        let peer = unsafe { socket2::SockAddr::new(*address, header.msg_hdr.msg_namelen) };
+        if let Some(peer) = peer.as_socket() {
+            batch.received.push((index, header.msg_len as usize, peer));
+        }
+    }
+    Ok(batch.received.len())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn recvmmsg(_socket: &std::net::UdpSocket, _batch: &mut RecvBatch) -> io::Result<usize> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// A socket to one target host, shared by every flooder sending there
+///
+/// ACKs come back to the socket rather than to a flooder, so the host's flooders also
+/// share one loss tracker fed by one ACK receiver. The receiver stops when the last
+/// flooder drops its handle.
+pub struct SharedSocket {
+    pub socket: Arc<UdpSocket>,
+    pub target: SocketAddr,
+    pub tracker: PacketLossTracker,
+    ack_task: JoinHandle<()>,
+}
+
+impl Drop for SharedSocket {
+    fn drop(&mut self) {
+        self.ack_task.abort();
+    }
+}
+
+/// Sockets in use by flooders, by target address
+#[derive(Clone, Default)]
+pub struct SocketPool {
+    sockets: Arc<Mutex<HashMap<SocketAddr, Weak<SharedSocket>>>>,
+}
+
+impl SocketPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The socket to `target`, opening one if no flooder holds it
+    pub fn get(&self, target: SocketAddr, netsim: NetSimConfig) -> Result<Arc<SharedSocket>> {
+        let mut sockets = self.sockets.lock().unwrap();
+        if let Some(shared) = sockets.get(&target).and_then(Weak::upgrade) {
+            return Ok(shared);
+        }
+
+        let socket = Arc::new(bind_socket(target)?);
+        let tracker = PacketLossTracker::new();
+        let receiver = socket.clone();
+        let ack_tracker = tracker.clone();
+        let ack_task = Supervisor::global().spawn_transient("udp_ack_receiver", ack_receiver(receiver, ack_tracker, netsim));
+
+        let shared = Arc::new(SharedSocket { socket, target, tracker, ack_task });
+        sockets.retain(|_, socket| socket.strong_count() > 0);
+        sockets.insert(target, Arc::downgrade(&shared));
+        Ok(shared)
+    }
+
+    /// Sockets currently held open by flooders
+    pub fn open_sockets(&self) -> usize {
+        self.sockets.lock().unwrap().values().filter(|s| s.strong_count() > 0).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flooders_share_a_socket_per_host() {
+        let pool = SocketPool::new();
+        let (a, b): (SocketAddr, SocketAddr) = ("127.0.0.1:9001".parse().unwrap(), "127.0.0.1:9002".parse().unwrap());
+
+        let first = pool.get(a, NetSimConfig::default()).unwrap();
+        let second = pool.get(a, NetSimConfig::default()).unwrap();
+        let other = pool.get(b, NetSimConfig::default()).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(pool.open_sockets(), 2);
+
+        drop((first, second));
+        assert_eq!(pool.open_sockets(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batches_arrive_in_both_modes() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = receiver.local_addr().unwrap();
+        let sender = bind_socket(target).unwrap();
+        let packets: Vec<Vec<u8>> = (0..SEND_BATCH as u8).map(|i| vec![i; 64]).collect();
+
+        for mode in [BatchMode::PerPacket, BatchMode::from_env()] {
+            send_batch(&sender, target, &packets, mode).await.unwrap();
+            let mut buf = [0u8; 128];
+            for i in 0..SEND_BATCH as u8 {
+                let (len, _) = receiver.recv_from(&mut buf).await.unwrap();
+                assert_eq!(&buf[..len], &[i; 64][..], "{:?}", mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recv_batch_reports_each_sender() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(std::time::Duration::from_secs(1))).unwrap();
+        let target = receiver.local_addr().unwrap();
+        let senders: Vec<std::net::UdpSocket> = (0..2).map(|_| std::net::UdpSocket::bind("127.0.0.1:0").unwrap()).collect();
+
+        for mode in [BatchMode::PerPacket, BatchMode::from_env()] {
+            for (i, sender) in senders.iter().enumerate() {
+                sender.send_to(&[i as u8; 16], target).unwrap();
+            }
+
+            let mut batch = RecvBatch::new();
+            let mut seen = Vec::new();
+            while seen.len() < senders.len() {
+                recv_batch(&receiver, &mut batch, mode).unwrap();
+                seen.extend(batch.packets().map(|(packet, peer)| (packet.to_vec(), peer)));
+            }
+            for (i, sender) in senders.iter().enumerate() {
+                let expected = (vec![i as u8; 16], sender.local_addr().unwrap());
+                assert!(seen.contains(&expected), "{:?}", mode);
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Host load - the worker's own CPU and memory use, for the telemetry plane
+//
+// Packet loss only shows what reaches the responder; a worker whose CPU is pinned
+// by the flood can drop work long before the loss numbers say so. Each streamed
+// sample therefore carries the worker's CPU utilization and memory pressure, both
+// 0.0 to 1.0, and the leader can count CPU saturation towards overload
+// (CPU_OVERLOAD_WEIGHT, see GameConfig).
+//
+// In a container the cgroup (v2) is the truth: CPU is `usage_usec` over the task's
+// CPU quota (`cpu.max`), memory `memory.current` over `memory.max`. Without one
+// (a laptop, cgroup v1) the whole machine is read from /proc/stat and /proc/meminfo.
+// CPU is a rate, so the first reading only primes the sampler.
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+const CGROUP_DIR: &str = "/sys/fs/cgroup";
+
+/// The worker's own load (both 0.0 to 1.0)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HostLoad {
+    /// Share of the CPU the worker may use that it did use since the previous reading
+    pub cpu_utilization: f32,
+    /// Share of the memory limit in use
+    pub memory_pressure: f32,
+}
+
+/// A cumulative CPU counter reading
+#[derive(Debug, Clone, Copy)]
+enum CpuCounter {
+    /// cgroup CPU time (microseconds) at a wall-clock instant, over `cpus` worth of quota
+    Cgroup { usage_usec: u64, at: Instant, cpus: f64 },
+    /// Busy and total jiffies across all CPUs (/proc/stat)
+    Proc { busy: u64, total: u64 },
+}
+
+/// Reads the host load, remembering the last CPU counter to take a rate from
+#[derive(Debug, Default)]
+pub struct HostLoadSampler {
+    last: Option<CpuCounter>,
+}
+
+impl HostLoadSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load since the previous call; None on the first call or where nothing is readable
+    pub fn sample(&mut self) -> Option<HostLoad> {
+        let counter = read_cpu_counter()?;
+        let previous = self.last.replace(counter);
+        let cpu_utilization = cpu_between(previous?, counter)?;
+        let memory_pressure = read_memory_pressure()?;
+        Some(HostLoad { cpu_utilization, memory_pressure })
+    }
+}
+
+/// Utilization between two readings of the same counter
+fn cpu_between(previous: CpuCounter, current: CpuCounter) -> Option<f32> {
+    let share = match (previous, current) {
+        (CpuCounter::Cgroup { usage_usec: before, at: since, .. }, CpuCounter::Cgroup { usage_usec, at, cpus }) => {
+            let wall_usec = at.duration_since(since).as_micros() as f64 * cpus;
+            if wall_usec <= 0.0 {
+                return None;
+            }
+            usage_usec.saturating_sub(before) as f64 / wall_usec
+        }
+        (CpuCounter::Proc { busy: busy_before, total: total_before }, CpuCounter::Proc { busy, total }) => {
+            let total = total.saturating_sub(total_before);
+            if total == 0 {
+                return None;
+            }
+            busy.saturating_sub(busy_before) as f64 / total as f64
+        }
+        _ => return None,
+    };
+    Some(share.clamp(0.0, 1.0) as f32)
+}
+
+fn read(path: &str) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+fn read_cpu_counter() -> Option<CpuCounter> {
+    let cgroup = read(&format!("{}/cpu.stat", CGROUP_DIR)).and_then(|stat| parse_cgroup_usage(&stat));
+    if let Some(usage_usec) = cgroup {
+        let available = std::thread::available_parallelism().map_or(1.0, |n| n.get() as f64);
+        let quota = read(&format!("{}/cpu.max", CGROUP_DIR)).and_then(|max| parse_cpu_max(&max));
+        let cpus = quota.unwrap_or(available).min(available);
+        return Some(CpuCounter::Cgroup { usage_usec, at: Instant::now(), cpus });
+    }
+    let (busy, total) = parse_proc_stat(&read("/proc/stat")?)?;
+    Some(CpuCounter::Proc { busy, total })
+}
+
+fn read_memory_pressure() -> Option<f32> {
+    let current = read(&format!("{}/memory.current", CGROUP_DIR));
+    let max = read(&format!("{}/memory.max", CGROUP_DIR));
+    if let Some(pressure) = current.zip(max).and_then(|(current, max)| parse_cgroup_memory(&current, &max)) {
+        return Some(pressure);
+    }
+    parse_meminfo(&read("/proc/meminfo")?)
+}
+
+/// `usage_usec` from a cgroup's cpu.stat
+fn parse_cgroup_usage(stat: &str) -> Option<u64> {
+    stat.lines().find_map(|line| line.strip_prefix("usage_usec ")?.trim().parse().ok())
+}
+
+/// CPUs a cgroup's cpu.max ("<quota> <period>") allows, None without a quota ("max ...")
+fn parse_cpu_max(max: &str) -> Option<f64> {
+    let mut fields = max.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next()?.parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Busy and total jiffies from the aggregate `cpu` line of /proc/stat
+/// Idle and iowait count as idle; guest time is already inside user and nice.
+fn parse_proc_stat(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let fields: Vec<u64> = line.split_whitespace().skip(1).take(8).map(|f| f.parse().ok()).collect::<Option<_>>()?;
+    let total: u64 = fields.iter().sum();
+    let idle = fields.get(3)? + fields.get(4).copied().unwrap_or(0);
+    Some((total.saturating_sub(idle), total))
+}
+
+/// memory.current over memory.max, None without a limit ("max")
+fn parse_cgroup_memory(current: &str, max: &str) -> Option<f32> {
+    let current: f64 = current.trim().parse().ok()?;
+    let max: f64 = max.trim().parse().ok()?;
+    (max > 0.0).then(|| (current / max).clamp(0.0, 1.0) as f32)
+}
+
+/// Share of memory not available, from /proc/meminfo
+fn parse_meminfo(meminfo: &str) -> Option<f32> {
+    let field = |name: &str| -> Option<f64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line[name.len()..].split_whitespace().next()?.parse().ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    (total > 0.0).then(|| (1.0 - available / total).clamp(0.0, 1.0) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_host_load() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 100 0 50 800 50 0 0 0 0 0\n";
+        assert_eq!(parse_proc_stat(stat), Some((150, 1000)));
+        assert_eq!(parse_cgroup_usage("usage_usec 123456\nuser_usec 100000\n"), Some(123456));
+        assert_eq!(parse_cpu_max("25000 100000\n"), Some(0.25));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_cgroup_memory("268435456\n", "536870912\n"), Some(0.5));
+        assert_eq!(parse_cgroup_memory("268435456\n", "max\n"), None);
+        let meminfo = "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    4000000 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(0.75));
+
+        // A quarter vCPU busy for half the interval is 50% of what the task may use
+        let at = Instant::now();
+        let before = CpuCounter::Cgroup { usage_usec: 0, at, cpus: 0.25 };
+        let after = CpuCounter::Cgroup { usage_usec: 125_000, at: at + Duration::from_secs(1), cpus: 0.25 };
+        assert_eq!(cpu_between(before, after), Some(0.5));
+        let before = CpuCounter::Proc { busy: 150, total: 1000 };
+        assert_eq!(cpu_between(before, CpuCounter::Proc { busy: 450, total: 2000 }), Some(0.3));
+        assert_eq!(cpu_between(before, before), None);
+    }
+}
@@ -0,0 +1,144 @@
+// Compression and HTTP caching for the big state endpoints (worker and client)
+//
+// GET /game/state grows with the map - hundreds of KB in a long game - and the
+// frontend polls it. Both routers gzip responses for clients that accept it
+// (`compression`). On top of that the state endpoints carry an ETag naming what the
+// body was built from: the last applied log index, plus the metrics view's version
+// when the body has node metrics in it. A poller sending it back in If-None-Match gets
+// a bodiless 304 until something changed. `?fields=nodes,players` trims the body to
+// the top-level fields asked for.
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use tower_http::compression::CompressionLayer;
+
+/// Gzip for every response a client accepts it for (WebSocket upgrades and tiny
+/// bodies are left alone)
+pub fn compression() -> CompressionLayer {
+    CompressionLayer::new()
+}
+
+/// `?fields=` of a state endpoint: top-level fields to keep, comma-separated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldsQuery {
+    #[serde(default)]
+    pub fields: Option<String>,
+}
+
+/// Top-level fields a response is trimmed to (None = all of them)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fields(Option<Vec<String>>);
+
+impl FieldsQuery {
+    /// The fields asked for, each checked against `known` (an error names the first unknown one)
+    pub fn parse(&self, known: &[&str]) -> Result<Fields, String> {
+        let Some(list) = self.fields.as_deref().map(str::trim).filter(|s| !s.is_empty()) else {
+            return Ok(Fields(None));
+        };
+        let mut fields = Vec::new();
+        for field in list.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            if !known.contains(&field) {
+                return Err(format!("Unknown field '{}' (known: {})", field, known.join(", ")));
+            }
+            if !fields.iter().any(|f| f == field) {
+                fields.push(field.to_string());
+            }
+        }
+        Ok(Fields(Some(fields)))
+    }
+}
+
+impl Fields {
+    /// Whether `field` is in the response
+    pub fn has(&self, field: &str) -> bool {
+        self.0.as_ref().is_none_or(|fields| fields.iter().any(|f| f == field))
+    }
+
+    /// `body` as JSON with only the selected fields
+    pub fn select<T: Serialize>(&self, body: &T) -> serde_json::Value {
+        let mut value = serde_json::to_value(body).unwrap_or_default();
+        if let (Some(fields), Some(object)) = (&self.0, value.as_object_mut()) {
+            object.retain(|key, _| fields.contains(key));
+        }
+        value
+    }
+}
+
+/// Weak ETag over what a state body was built from, e.g. `W/"142-37"`
+/// Weak, because the same body goes out gzipped or not.
+pub fn etag(parts: &[u64]) -> String {
+    let parts: Vec<String> = parts.iter().map(u64::to_string).collect();
+    format!("W/\"{}\"", parts.join("-"))
+}
+
+/// Whether the request's If-None-Match already names `etag` (weak comparison)
+pub fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// 304 if the client has `etag` already, otherwise `body` trimmed to `fields`
+/// Both carry the ETag; Cache-Control makes browsers revalidate every time.
+pub fn respond<T: Serialize>(headers: &HeaderMap, etag: String, fields: &Fields, body: impl FnOnce() -> T) -> Response {
+    let mut response = if not_modified(headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        axum::Json(fields.select(&body())).into_response()
+    };
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let known = ["players", "nodes", "total_events"];
+        let query = |fields: &str| FieldsQuery { fields: Some(fields.to_string()) };
+        assert_eq!(FieldsQuery::default().parse(&known), Ok(Fields(None)));
+        assert_eq!(query(" ").parse(&known), Ok(Fields(None)));
+
+        let fields = query("nodes, players,nodes").parse(&known).unwrap();
+        assert!(fields.has("players") && !fields.has("total_events"));
+        let body = serde_json::json!({ "players": [], "nodes": [1], "total_events": 3 });
+        assert_eq!(fields.select(&body), serde_json::json!({ "players": [], "nodes": [1] }));
+        assert!(query("nodes,terrain").parse(&known).unwrap_err().contains("'terrain'"));
+    }
+
+    #[test]
+    fn test_etag_and_if_none_match() {
+        let tag = etag(&[142, 37]);
+        assert_eq!(tag, "W/\"142-37\"");
+
+        let request = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert!(!not_modified(&HeaderMap::new(), &tag));
+        assert!(not_modified(&request("W/\"142-37\""), &tag));
+        // Weak comparison: a proxy may have dropped the W/; lists and * match too
+        assert!(not_modified(&request("\"142-37\""), &tag));
+        assert!(not_modified(&request("\"1\", W/\"142-37\""), &tag));
+        assert!(not_modified(&request("*"), &tag));
+        assert!(!not_modified(&request("W/\"143-37\""), &tag));
+
+        let fields = Fields(None);
+        let response = respond(&request(&tag), tag.clone(), &fields, || -> serde_json::Value { unreachable!() });
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], tag.as_str());
+        let response = respond(&HeaderMap::new(), tag, &fields, || serde_json::json!({ "nodes": [] }));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -1,13 +1,28 @@
 // Library interface for sharing Raft and game logic components
 // between worker and client binaries
 
+pub mod adopt;
+pub mod app;
+pub mod bench;
+pub mod client_api;
+pub mod client_ws;
+pub mod cors;
 pub mod game;
+pub mod host_load;
+pub mod http_cache;
 pub mod metadata;
+pub mod observer;
 pub mod raft;
+pub mod reconnect;
 pub mod registry;
+pub mod request_id;
+pub mod scheduler;
+pub mod supervisor;
+pub mod tls;
 
 // Re-export commonly used types for convenience
 pub use raft::{RaftNode, generate_node_id, bootstrap_cluster, join_cluster};
 pub use raft::storage::{MemStorage, GameStateMachine, GameEventRequest};
 pub use raft::node_registry::NodeRegistry;
+pub use raft::command_client::CommandClient;
 pub use game::{GameState, GameEvent, GameConfig, GameLogic};
@@ -1,15 +1,16 @@
-mod game;
-mod metadata;
-mod raft;
-mod registry;
-
 use anyhow::Result;
+use futures_util::future::{BoxFuture, FutureExt};
 use game::{FinalKillManager, GameConfig, GameLogic, NetworkManager};
 use raft::storage::GameEventRequest;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::RwLock;
-use tokio::time::sleep;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
+use worker::app::App;
+use worker::request_id::RequestIdExt;
+use worker::scheduler::{TickIntervals, TickScheduler};
+use worker::supervisor::{RestartPolicy, Supervisor};
+use worker::{adopt, game, metadata, observer, raft, registry, request_id};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -30,324 +31,703 @@ async fn main() -> Result<()> {
     let task_arn = metadata::get_task_arn().await?;
     println!("✓ Task ARN: {}", task_arn);
 
-    // Step 4: Get game ID from environment
-    let game_id = std::env::var("GAME_ID")
-        .unwrap_or_else(|_| "default-game".to_string());
+    // Placement and size, when running in ECS (skipped when both are overridden)
+    if std::env::var("NODE_IP").is_err() || std::env::var("TASK_ARN").is_err() {
+        if let Ok(task) = metadata::get_task_metadata().await {
+            println!(
+                "✓ Task {}:{} in {} ({} vCPU, {} MiB, capacity {})",
+                task.family,
+                task.revision,
+                task.availability_zone.as_deref().unwrap_or("unknown AZ"),
+                task.limits.cpu.map_or("?".to_string(), |cpu| cpu.to_string()),
+                task.limits.memory.map_or("?".to_string(), |mem| mem.to_string()),
+                task.capacity_multiplier().map_or("?".to_string(), |c| format!("{:.1}x", c)),
+            );
+            for eni in task.enis() {
+                println!(
+                    "  ENI {} {:?} subnet {} ({})",
+                    eni.mac_address.as_deref().unwrap_or("-"),
+                    eni.ipv4_addresses,
+                    eni.subnet_cidr.as_deref().unwrap_or("-"),
+                    eni.private_dns_name.as_deref().unwrap_or("-"),
+                );
+            }
+        }
+    }
+
+    // Cluster-internal API (/events, /status, /registry, /adopt) on the VPC interface
+    let internal_api = raft::internal::InternalApiConfig::from_env(&my_ip);
+
+    // Step 4: Get game ID (and coordinate, for lazily-initialized nodes) from environment
+    // A warm pool worker started without GAME_ID waits for the master's /adopt instead
+    let warm_pool = std::env::var("WARM_POOL").is_ok_and(|v| v == "1");
+    let env_coord = match (
+        std::env::var("NODE_COORD_Q").ok().and_then(|s| s.parse::<i32>().ok()),
+        std::env::var("NODE_COORD_R").ok().and_then(|s| s.parse::<i32>().ok()),
+    ) {
+        (Some(q), Some(r)) => Some(game::NodeCoord::new(q, r)),
+        _ => None,
+    };
+    let (game_id, node_coord, adopted_by) = match std::env::var("GAME_ID") {
+        Ok(game_id) => (game_id, env_coord, None),
+        Err(_) if warm_pool => {
+            println!("\n[3/6] Warm pool worker without a game, waiting to be adopted...");
+            let assignment = adopt::wait_for_assignment(&internal_api).await?;
+            (assignment.game_id, Some(assignment.coord), assignment.request_id)
+        }
+        Err(_) => ("default-game".to_string(), env_coord, None),
+    };
     println!("\n[3/6] Game ID: {}", game_id);
 
+    // Startup calls to the master run under the id of the master request that started
+    // (REQUEST_ID) or adopted this worker, so its logs line up with the master's
+    let startup_request = adopted_by
+        .or_else(|| std::env::var("REQUEST_ID").ok().filter(|id| !id.is_empty()))
+        .unwrap_or_else(request_id::new_id);
+    println!("✓ Request id: {}", startup_request);
+
+    // Take back the identity of an earlier run in this game, if there was one (see identity)
+    let identity_path = raft::identity::NodeIdentity::default_path();
+    let (identity, identity_source) = raft::identity::NodeIdentity::resolve(identity_path.as_deref(), &game_id, node_coord).await;
+    if identity_source != raft::identity::IdentitySource::Fresh {
+        println!("✓ Node identity {} ({:?}), coordinate {:?}", identity.node_id, identity_source, identity.coord);
+    }
+    identity.remember().await;
+    let node_coord = identity.coord;
+
     // Step 5: Register with master and get peer
     println!("\n[4/6] Registering with master...");
-    let peer = registry::register_and_get_peer(worker_id.clone(), task_arn, my_ip.clone(), game_id.clone()).await?;
+    let registration = registry::register_and_get_peer(worker_id.clone(), task_arn, my_ip.clone(), game_id.clone());
+    let registration = request_id::scope(startup_request.clone(), registration).await?;
+    // What peers use to reach us: a DNS name in DNS discovery mode, otherwise our IP
+    let my_host = registration.advertised_host(&my_ip);
 
     // Step 6: Initialize Raft node
     println!("\n[5/6] Initializing Raft node...");
-    let node_id = raft::generate_node_id();
+    let node_id = identity.node_id;
 
     // Create node registry for peer address resolution
-    let registry = raft::node_registry::NodeRegistry::new();
+    // Committed addresses are persisted under RAFT_DATA_DIR if set
+    let registry = raft::node_registry::NodeRegistry::from_env().await;
+    // Snapshots are kept on disk too if RAFT_SNAPSHOT_DIR or RAFT_DATA_DIR is set
+    let snapshots = raft::snapshot_store::SnapshotStore::from_env(&game_id);
 
-    let raft_node = if let Some(peer_info) = peer {
+    let raft_node = if let Some(peer_info) = registration.peer {
         // Join existing cluster
-        raft::join_cluster(node_id, my_ip.clone(), peer_info, registry).await?
+        raft::join_cluster(node_id, my_host.clone(), peer_info, registry, snapshots).await?
     } else {
         // Bootstrap new cluster
-        raft::bootstrap_cluster(node_id, my_ip.clone(), registry).await?
+        raft::bootstrap_cluster(node_id, my_host.clone(), registry, snapshots).await?
     };
 
     // Step 7: Check if this is a lazy-initialized node and submit completion event
-    let coord_q_result = std::env::var("NODE_COORD_Q").ok().and_then(|s| s.parse::<i32>().ok());
-    let coord_r_result = std::env::var("NODE_COORD_R").ok().and_then(|s| s.parse::<i32>().ok());
+    // Warm pool workers without a coordinate announce themselves and wait for /adopt
+    let announce_warm = warm_pool && node_coord.is_none();
 
-    if let (Some(coord_q), Some(coord_r)) = (coord_q_result, coord_r_result) {
-        println!("\n[6/7] Lazy-initialized node detected: ({}, {})", coord_q, coord_r);
+    let mut initialized_coord = node_coord;
+    if let Some(node_coord) = node_coord {
+        println!("\n[6/7] Lazy-initialized node detected: {:?}", node_coord);
         println!("Submitting NodeInitializationComplete event...");
 
-        use game::NodeCoord;
-        use game::GameEvent;
-        use raft::storage::GameEventRequest;
-
-        let node_coord = NodeCoord { q: coord_q, r: coord_r };
-        let event = GameEvent::NodeInitializationComplete {
+        let event = game::GameEvent::Fact(game::FactEvent::NodeInitializationComplete {
             node_coord,
-            node_ip: my_ip.clone(),
+            node_ip: my_host.clone(),
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
-        };
-
-        let request = GameEventRequest { event };
-        match raft_node.raft.client_write(request).await {
-            Ok(_) => println!("✓ NodeInitializationComplete event submitted"),
-            Err(e) => eprintln!("⚠ Failed to submit NodeInitializationComplete: {}", e),
+            raft_node_id: node_id,
+        });
+
+        // Usually a follower, so this goes through the leader
+        match raft::commit_via_leader(&raft_node.raft, &raft_node.registry, event).await {
+            Ok(()) => println!("✓ NodeInitializationComplete event submitted"),
+            Err(e) => {
+                eprintln!("⚠ Failed to submit NodeInitializationComplete: {:#}", e);
+                initialized_coord = None;
+            }
         }
     }
 
+    // Initialize network manager (for UDP attack connections and metrics)
+    let mut network_manager = NetworkManager::new();
+    if let Some(coord) = initialized_coord {
+        network_manager.adopt(coord);
+    }
+    let network_manager = Arc::new(RwLock::new(network_manager));
+
     // Step 8: Start HTTP API server for event submission
     println!("\n[7/7] Starting HTTP API server...");
     let api_raft = raft_node.raft.clone();
     let api_storage = raft_node.storage.clone();
-    let api_addr = format!("0.0.0.0:8080");
+    let api_registry = raft_node.registry.clone();
     let master_url = std::env::var("MASTER_URL")
         .unwrap_or_else(|_| "http://localhost:8080".to_string());
-    let api_game_id = game_id.clone();
-    tokio::spawn(async move {
-        if let Err(e) = raft::api::start_api_server(api_raft, api_storage, api_addr, master_url, api_game_id).await {
-            eprintln!("HTTP API server error: {}", e);
-        }
+    let api_config = raft::api::ApiServerConfig {
+        addr: format!("{}:8080", metadata::bind_ip()),
+        internal: internal_api.clone(),
+        master_url: master_url.clone(),
+        game_id: game_id.clone(),
+        node_host: my_host.clone(),
+    };
+    let api_metrics_view = raft_node.metrics_view.clone();
+    let api_inbound = network_manager.read().await.inbound();
+    Supervisor::global().spawn("http_api", RestartPolicy::always(), move || {
+        raft::api::start_api_server(
+            api_raft.clone(),
+            api_storage.clone(),
+            api_registry.clone(),
+            api_metrics_view.clone(),
+            api_inbound.clone(),
+            api_config.clone(),
+        )
     });
 
+    if announce_warm {
+        println!("\nWarm pool worker: announcing to master, waiting to adopt a coordinate...");
+        match request_id::scope(startup_request, registry::announce_warm(&master_url, &game_id, &worker_id)).await {
+            Ok(()) => println!("✓ Announced to warm pool"),
+            Err(e) => eprintln!("⚠ Failed to join warm pool: {}", e),
+        }
+    }
+
     println!("\n[7/7] Worker startup complete!");
     println!("\n=== Worker Node Ready ===");
     println!("  Worker ID: {}", worker_id);
     println!("  Node ID: {}", node_id);
     println!("  IP: {}", my_ip);
+    println!("  Advertised as: {}", my_host);
     println!("  Is Leader: {}", raft_node.is_leader().await);
     println!("  Raft Port: 5000");
     println!("  HTTP API Port: 8080");
+    println!("  Internal API: {}", internal_api.addr());
     println!("========================\n");
 
     // Initialize game logic (used when this node is leader)
-    let mut game_logic = GameLogic::new(GameConfig::default());
+    let game_config = GameConfig::from_env();
+    let game_logic = GameLogic::new(game_config);
+
+
+    // Stream metric samples to the leader and mirror its view - metrics never go through the log
+    {
+        let cadence = raft::metrics_view::StreamCadence::new(raft_node.storage.read().await.state_machine());
+        let (raft, registry, view, network) = (
+            raft_node.raft.clone(),
+            raft_node.registry.clone(),
+            raft_node.metrics_view.clone(),
+            network_manager.clone(),
+        );
+        Supervisor::global().spawn("telemetry", RestartPolicy::always(), move || {
+            raft::metrics_view::stream_to_leader(raft.clone(), registry.clone(), view.clone(), Some(network.clone()), cadence.clone())
+        });
+    }
 
-    // Initialize network manager (for UDP attack connections and metrics)
-    let network_manager = Arc::new(RwLock::new(NetworkManager::new()));
+    // Runs committed effect requests (spawns, final kills, shutdown) - leader only
+    let effects_executor = Arc::new(Mutex::new(game::EffectsExecutor::new()));
 
-    // Initialize final kill manager (for 10-second client kill attacks)
-    let final_kill_manager = Arc::new(FinalKillManager::new());
+    // Initialize final kill manager (for client kill attacks, FINAL_KILL_SECS of game time)
+    // Floods over the same transport as the UDP attacks (simulated on a dry run)
+    let final_kill_manager = Arc::new(FinalKillManager::with_transport(network_manager.read().await.transport()));
 
     // TODO: Auto-join the game or wait for manual join via API
     // For now, NetworkManager will be initialized with node coord/capacity when the first
     // PlayerJoin event for this worker is processed
 
-    // Main loop - run game logic tick and show status
-    let mut tick_count = 0;
-    let mut metrics_tick = 0;
-    let mut lazy_init_tick = 0;
-    let mut final_kills_started = std::collections::HashSet::new();
-
-    loop {
-        sleep(Duration::from_secs(1)).await;
-        tick_count += 1;
-        metrics_tick += 1;
-        lazy_init_tick += 1;
-
-        let is_leader = raft_node.is_leader().await;
-
-        // Get current game state
-        let storage = raft_node.storage.read().await;
-        let state_machine_arc = storage.state_machine();
-        drop(storage);
-        let sm = state_machine_arc.read().await;
-        let game_state = sm.game_state.clone();
-        drop(sm);
-
-        // Check for players who lost their capital and trigger final kill on their client
-        for (player_id, player) in &game_state.players {
-            if !player.alive && !final_kills_started.contains(player_id) {
-                // Player lost their capital, trigger final kill on their client
-                if let Some(client_ip) = game_state.client_ips.get(player_id) {
-                    // Find who captured their capital (the new owner)
-                    if let Some(capital_node) = game_state.nodes.get(&player.capital_coord) {
-                        let attacker_id = capital_node.owner_id;
-
-                        // Get all nodes owned by the attacker
-                        let attacker_nodes: Vec<_> = game_state.nodes.values()
-                            .filter(|n| n.owner_id == attacker_id)
-                            .map(|n| n.coord)
-                            .collect();
-
-                        if !attacker_nodes.is_empty() {
-                            println!("[Main] Player {} lost capital, triggering final kill from {} nodes",
-                                player_id, attacker_nodes.len());
-
-                            let fkm = final_kill_manager.clone();
-                            let client_ip_clone = client_ip.clone();
-                            let pid = *player_id;
-                            tokio::spawn(async move {
-                                if let Err(e) = fkm.start_final_kill(pid, client_ip_clone, attacker_nodes).await {
-                                    eprintln!("[Main] Failed to start final kill on player {}: {}", pid, e);
-                                }
-                            });
-
-                            final_kills_started.insert(*player_id);
-                        }
-                    }
+    let leader_info = game::LeaderInfo {
+        game_id: game_id.clone(),
+        node_id,
+        api_addr: format!("{}:8080", my_host),
+        rules_preset: game_logic.config().preset_name().to_string(),
+    };
+    let effect_ctx = Arc::new(EffectContext {
+        master_url: master_url.clone(),
+        game_id: game_id.clone(),
+        spawn_policy: game::SpawnRetryPolicy::from_env(),
+        final_kill_manager: final_kill_manager.clone(),
+        leader_info: leader_info.clone(),
+        metrics_view: raft_node.metrics_view.clone(),
+    });
+
+    let raft_node = Arc::new(raft_node);
+    let state_machine = raft_node.storage.read().await.state_machine();
+    let network_hook = network_manager.read().await.change_hook();
+    raft_node.storage.read().await.hooks().register(network_hook).await;
+
+    // Play-by-play for the audience's chat channel (OBSERVER_WEBHOOK_URL), posted by the leader
+    if let Some(config) = observer::ObserverConfig::from_env() {
+        println!("[Observer] Announcing to a {:?} webhook", config.format);
+        let (hook, rx) = observer::ObserverHook::new();
+        raft_node.storage.read().await.hooks().register(hook).await;
+        let (raft_node, game_id, rx) = (Arc::clone(&*raft_node), game_id.clone(), Arc::new(Mutex::new(rx)));
+        Supervisor::global().spawn("observer", RestartPolicy::always(), move || {
+            observer::run(config.clone(), game_id.clone(), Arc::clone(&raft_node), rx.clone())
+        });
+    }
+    let game_logic = Arc::new(Mutex::new(game_logic));
+    let was_leader = Arc::new(AtomicBool::new(false));
+    // Committed events already shipped (index into the event history)
+    let summary_shipped = Arc::new(AtomicU64::new(0));
+
+    // Periodic tasks, each on its own interval (see scheduler::TickIntervals), sped up
+    // along with the game - the rules themselves follow the committed time scale
+    let intervals = TickIntervals::from_env().scaled(game_config.time_scale);
+    let mut scheduler = TickScheduler::new();
+    let scheduler_stats = scheduler.stats();
+    let started = Instant::now();
+
+    // Sync network manager with game state (start/stop attacks) when committed
+    // changes touch attacks, and reconcile right away whenever leadership changes hands
+    {
+        let (raft_node, state_machine, network_manager, was_leader, my_host) =
+            (raft_node.clone(), state_machine.clone(), network_manager.clone(), was_leader.clone(), my_host.clone());
+        scheduler.every_from_start("network", intervals.network, move || {
+            let (raft_node, state_machine, network_manager, was_leader, my_host) =
+                (raft_node.clone(), state_machine.clone(), network_manager.clone(), was_leader.clone(), my_host.clone());
+            async move {
+                let is_leader = raft_node.is_leader().await;
+                let leadership_changed = was_leader.swap(is_leader, Ordering::SeqCst) != is_leader;
+                let sync_due = network_manager.read().await.take_sync_due();
+                if !sync_due && !leadership_changed {
+                    return;
+                }
+                let game_state = state_machine.read().await.game_state.clone();
+                let mut network = network_manager.write().await;
+                if sync_due {
+                    network.sync_with_game_state(&game_state, &game_state.node_ips, &my_host).await;
+                }
+                if leadership_changed {
+                    network.reconcile(&game_state, &game_state.node_ips, &my_host).await;
                 }
             }
-        }
-
-        // Every 10 seconds, spawn neighbors for all non-neutral nodes (lazy initialization)
-        // Only leader runs this to avoid duplicate spawning
-        if is_leader && lazy_init_tick >= 10 && !game_state.game_over {
-            use game::NodeCoord;
-            use game::GameEvent;
-            use raft::storage::GameEventRequest;
-
-            lazy_init_tick = 0;
-
-            let mut nodes_to_spawn = std::collections::HashMap::<NodeCoord, u64>::new();
+            .boxed()
+        });
+    }
 
-            // Find all non-neutral nodes and check their neighbors
-            for (coord, node) in &game_state.nodes {
-                if node.owner_id != 0 {  // Non-neutral (owned by someone)
-                    // Get all 6 neighbors
-                    let neighbors = coord.neighbors();
+    // Catch drift the diff-based sync can't see (dead flooders, stale IPs, restarts)
+    // Starts due, so a restarted process reconciles on its first pass
+    {
+        let (state_machine, network_manager, my_host) = (state_machine.clone(), network_manager.clone(), my_host.clone());
+        scheduler.every_from_start("reconcile", intervals.reconcile, move || {
+            let (state_machine, network_manager, my_host) = (state_machine.clone(), network_manager.clone(), my_host.clone());
+            async move {
+                let game_state = state_machine.read().await.game_state.clone();
+                network_manager.write().await.reconcile(&game_state, &game_state.node_ips, &my_host).await;
+            }
+            .boxed()
+        });
+    }
 
-                    // Check which neighbors don't exist
-                    for neighbor_coord in neighbors {
-                        if !game_state.nodes.contains_key(&neighbor_coord) && !nodes_to_spawn.contains_key(&neighbor_coord) {
-                            nodes_to_spawn.insert(neighbor_coord, 0); // Neutral owner
+    // If leader, run game logic to check for captures, lazy initialization and effects
+    {
+        let leading = Arc::new(AtomicBool::new(false));
+        let (raft_node, state_machine, game_logic) = (raft_node.clone(), state_machine.clone(), game_logic.clone());
+        scheduler.every("game_logic", intervals.game_logic, move || {
+            let (raft_node, state_machine, game_logic, leading) =
+                (raft_node.clone(), state_machine.clone(), game_logic.clone(), leading.clone());
+            async move {
+                if !raft_node.is_leader().await {
+                    leading.store(false, Ordering::SeqCst);
+                    return;
+                }
+                // Committed state, with the metrics view's samples in place
+                let mut game_state = state_machine.read().await.game_state.clone();
+                raft_node.metrics_view.overlay(&mut game_state).await;
+
+                // Generate capture, initialization and effect-request events, picking up
+                // the previous leader's committed capture boosts when this node takes over
+                let events = {
+                    let mut logic = game_logic.lock().await;
+                    if !leading.swap(true, Ordering::SeqCst) {
+                        logic.take_leadership(&game_state);
+                    }
+                    logic.tick(&game_state)
+                };
+
+                // Submit each generated event back to Raft, unless a member couldn't decode it yet
+                // An event that doesn't commit is forgotten, so a later tick emits it again
+                let mut uncommitted = Vec::new();
+                for (event, request) in events {
+                    if !game_state.permits(&event) {
+                        eprintln!(
+                            "[GameLogic] Holding back {}: needs cluster version {}, the cluster is at {}",
+                            event.kind(), event.min_cluster_version(), game_state.cluster_version()
+                        );
+                        uncommitted.extend(request);
+                        continue;
+                    }
+                    match raft_node.raft.client_write(GameEventRequest::new(event)).await {
+                        Ok(response) => {
+                            println!(
+                                "[GameLogic] Auto-generated event committed at log index {}",
+                                response.log_id.index
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("[GameLogic] Failed to submit event: {}", e);
+                            uncommitted.extend(request);
                         }
                     }
                 }
+                if !uncommitted.is_empty() {
+                    let mut logic = game_logic.lock().await;
+                    for request in &uncommitted {
+                        logic.forget(request);
+                    }
+                }
             }
+            .boxed()
+        });
+    }
 
-            if !nodes_to_spawn.is_empty() {
-                println!("[Lazy Init] Found {} neighbors to spawn around non-neutral nodes", nodes_to_spawn.len());
+    // Run requested effects and commit their acknowledgments (leader only)
+    {
+        let (raft_node, state_machine, effects_executor, effect_ctx, summary_shipped) =
+            (raft_node.clone(), state_machine.clone(), effects_executor.clone(), effect_ctx.clone(), summary_shipped.clone());
+        scheduler.every("effects", intervals.game_logic, move || {
+            let (raft_node, state_machine, effects_executor, effect_ctx, summary_shipped) =
+                (raft_node.clone(), state_machine.clone(), effects_executor.clone(), effect_ctx.clone(), summary_shipped.clone());
+            async move {
+                let mut executor = effects_executor.lock().await;
+                if !raft_node.is_leader().await {
+                    executor.step_down();
+                    return;
+                }
+                let game_state = state_machine.read().await.game_state.clone();
+
+                for completion in executor.take_completed() {
+                    // Events commit one by one; a failure retries only those not yet in
+                    let mut committed = 0;
+                    for event in &completion.events {
+                        if let Err(e) = raft_node.raft.client_write(GameEventRequest::new(event.clone())).await {
+                            eprintln!("[Effects] Failed to commit completion of effect {}: {}", completion.effect_id, e);
+                            break;
+                        }
+                        committed += 1;
+                    }
+                    if committed < completion.events.len() {
+                        executor.retry(completion, committed);
+                    }
+                }
 
-                // Submit initialization events
-                let timestamp = std::time::SystemTime::now()
+                for (effect_id, effect) in executor.due(&game_state) {
+                    println!("[Effects] Running effect {} ({})", effect_id, effect.kind());
+                    let shipped = summary_shipped.load(Ordering::SeqCst);
+                    let run = run_effect(effect, &effect_ctx, &state_machine, shipped).await;
+                    executor.start(effect_id, run);
+                }
+            }
+            .boxed()
+        });
+    }
+
+    // Game-over shutdown: every node acknowledges the end, the leader ends the game,
+    // requests the teardown once members have acknowledged, and exits when it's done
+    {
+        let (raft_node, state_machine) = (raft_node.clone(), state_machine.clone());
+        let coordinator = Arc::new(Mutex::new(game::ShutdownCoordinator::from_env(raft_node.node_id)));
+        scheduler.every("shutdown", intervals.game_logic, move || {
+            let (raft_node, state_machine, coordinator) = (raft_node.clone(), state_machine.clone(), coordinator.clone());
+            async move {
+                let is_leader = raft_node.is_leader().await;
+                let voters: Vec<u64> = raft_node.raft.metrics().borrow().membership_config.membership().voter_ids().collect();
+                let now = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
                     .as_secs();
-
-                for (coord, owner) in &nodes_to_spawn {
-                    let event = GameEvent::NodeInitializationStarted {
-                        node_coord: *coord,
-                        owner_id: *owner,
-                        timestamp,
-                    };
-                    let request = GameEventRequest { event };
-
-                    if let Err(e) = raft_node.raft.client_write(request).await {
-                        eprintln!("[Lazy Init] Failed to submit initialization for {:?}: {}", coord, e);
+                let actions = {
+                    let sm = state_machine.read().await;
+                    coordinator.lock().await.step(&sm.game_state, is_leader, &voters, now.max(sm.game_state.clock))
+                };
+
+                for action in actions {
+                    match action {
+                        game::ShutdownAction::Commit(event) => {
+                            let kind = event.kind();
+                            if let Err(e) = raft::commit_via_leader(&raft_node.raft, &raft_node.registry, event).await {
+                                eprintln!("[Shutdown] Failed to commit {}: {}", kind, e);
+                            }
+                        }
+                        game::ShutdownAction::Exit => {
+                            println!("Game infrastructure shutdown complete!");
+                            println!("Exiting worker...");
+                            std::process::exit(0);
+                        }
                     }
                 }
+            }
+            .boxed()
+        });
+    }
 
-                // Spawn workers on master (async)
-                let master_url = std::env::var("MASTER_URL")
-                    .unwrap_or_else(|_| "http://localhost:8080".to_string());
-                let game_id_clone = game_id.clone();
-                let nodes_to_spawn_clone = nodes_to_spawn.clone();
-
-                tokio::spawn(async move {
-                    for (coord, _) in nodes_to_spawn_clone {
-                        if let Err(e) = worker::raft::api::spawn_node_on_master(&master_url, &game_id_clone, coord.q, coord.r, false).await {
-                            eprintln!("[Lazy Init] Failed to spawn node {:?}: {}", coord, e);
-                        }
-                    }
-                });
+    // Ship committed events and a state overview to the master (leader only)
+    {
+        let (raft_node, state_machine, summary_shipped, master_url) =
+            (raft_node.clone(), state_machine.clone(), summary_shipped.clone(), master_url.clone());
+        scheduler.every("summary", intervals.summary, move || {
+            let (raft_node, state_machine, summary_shipped, master_url, leader_info) =
+                (raft_node.clone(), state_machine.clone(), summary_shipped.clone(), master_url.clone(), leader_info.clone());
+            async move {
+                if !raft_node.is_leader().await {
+                    return;
+                }
+                let sm = state_machine.read().await;
+                let summary = game::GameSummary::build(
+                    &leader_info,
+                    &sm.game_state,
+                    &sm.events,
+                    summary_shipped.load(Ordering::SeqCst),
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                );
+                drop(sm);
+
+                // Events are only marked shipped once the master has them
+                match registry::report_summary(&master_url, &summary).await {
+                    Ok(()) => summary_shipped.store(summary.total_events, Ordering::SeqCst),
+                    Err(e) => eprintln!("[Summary] Failed to report to master: {}", e),
+                }
             }
-        }
+            .boxed()
+        });
+    }
 
-        // Check if game is over (only leader initiates shutdown)
-        if game_state.game_over && is_leader {
-            println!("\n=== GAME OVER ===");
-            println!("Only one player remains!");
-            println!("Shutting down all infrastructure...");
-
-            // Get master URL from environment
-            let master_url = std::env::var("MASTER_URL")
-                .unwrap_or_else(|_| "http://localhost:8080".to_string());
-
-            // Call master to kill all workers
-            println!("Calling master to shutdown all workers...");
-            let client = reqwest::Client::new();
-            match client.post(format!("{}/kill_workers", master_url)).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        println!("✓ All workers shutdown initiated");
-                    } else {
-                        eprintln!("✗ Failed to shutdown workers: {}", response.status());
-                    }
+    // Commit the leader's newest state digest, for every follower to check its own against
+    {
+        let raft_node = raft_node.clone();
+        let digests = raft_node.storage.read().await.digests().clone();
+        let committed = Arc::new(AtomicU64::new(0));
+        scheduler.every("digest", intervals.digest, move || {
+            let (raft_node, digests, committed) = (raft_node.clone(), digests.clone(), committed.clone());
+            async move {
+                if !raft_node.is_leader().await {
+                    return;
                 }
-                Err(e) => {
-                    eprintln!("✗ Error calling master: {}", e);
+                let Some((log_index, digest)) = digests.latest() else {
+                    return;
+                };
+                if log_index <= committed.load(Ordering::SeqCst) {
+                    return;
+                }
+                let timestamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                let event = game::GameEvent::Fact(game::FactEvent::StateDigest { log_index, digest, timestamp });
+                match raft::commit_via_leader(&raft_node.raft, &raft_node.registry, event).await {
+                    Ok(_) => committed.store(log_index, Ordering::SeqCst),
+                    Err(e) => eprintln!("[Digest] Failed to commit digest for log index {}: {}", log_index, e),
                 }
             }
+            .boxed()
+        });
+    }
 
-            // Call master to kill itself
-            println!("Calling master to shutdown...");
-            match client.post(format!("{}/kill", master_url)).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        println!("✓ Master shutdown initiated");
-                    } else {
-                        eprintln!("✗ Failed to shutdown master: {}", response.status());
-                    }
+    // Raise the committed cluster version once every member's Hello says it applies a
+    // newer one, so events that need it may be proposed (rolling upgrades)
+    {
+        let (raft_node, state_machine) = (raft_node.clone(), state_machine.clone());
+        scheduler.every("version_gate", intervals.game_logic, move || {
+            let (raft_node, state_machine) = (raft_node.clone(), state_machine.clone());
+            async move {
+                if !raft_node.is_leader().await {
+                    return;
                 }
-                Err(e) => {
-                    eprintln!("✗ Error calling master: {}", e);
+                let members: Vec<u64> = raft_node.raft.metrics().borrow().membership_config.membership().nodes().map(|(id, _)| *id).collect();
+                let committed = state_machine.read().await.game_state.cluster_version();
+                let Some(version) = raft::capabilities::members_cluster_version(&raft_node.registry, raft_node.node_id, members)
+                    .filter(|version| *version > committed)
+                else {
+                    return;
+                };
+                let timestamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                let event = game::GameEvent::Fact(game::FactEvent::ClusterVersionSet { version, timestamp });
+                match raft_node.raft.client_write(GameEventRequest::new(event)).await {
+                    Ok(_) => println!("[VersionGate] Every member applies cluster version {}, raised from {}", version, committed),
+                    Err(e) => eprintln!("[VersionGate] Failed to commit cluster version {}: {}", version, e),
                 }
             }
+            .boxed()
+        });
+    }
 
-            println!("Game infrastructure shutdown complete!");
-            println!("Exiting worker...");
-            std::process::exit(0);
-        }
+    // Status line, plus any task that's overrunning its interval or panicking
+    {
+        let (raft_node, state_machine) = (raft_node.clone(), state_machine.clone());
+        scheduler.every("status", intervals.status, move || {
+            let (raft_node, state_machine, scheduler_stats, worker_id) =
+                (raft_node.clone(), state_machine.clone(), scheduler_stats.clone(), worker_id.clone());
+            async move {
+                let is_leader = raft_node.is_leader().await;
+                let sm = state_machine.read().await;
+
+                // Access derived game state
+                let event_count = sm.events.len();
+                let player_count = sm.game_state.players.len();
+                let node_count = sm.game_state.nodes.len();
+                let alive_players = sm.game_state.players.values().filter(|p| p.alive).count();
+                drop(sm);
+
+                println!(
+                    "[Tick {}] Worker {} | Leader: {} | Events: {} | Players: {}/{} | Nodes: {}",
+                    started.elapsed().as_secs(), worker_id, is_leader, event_count, alive_players, player_count, node_count
+                );
+
+                let stats = scheduler_stats.lock().unwrap().clone();
+                for (name, task) in stats.iter().filter(|(_, t)| t.overruns > 0 || t.panics > 0) {
+                    println!(
+                        "[Scheduler] {}: {} runs, mean {:.1}ms, max {:.1}ms, {} overruns, {} panics",
+                        name, task.runs, task.mean_ms(), task.max_ms, task.overruns, task.panics
+                    );
+                }
+            }
+            .boxed()
+        });
+    }
 
-        // Sync network manager with game state (start/stop attacks)
-        network_manager.write().await.sync_with_game_state(&game_state, &game_state.node_ips, &my_ip).await;
+    // Until SIGTERM: then every supervised task is cancelled before Raft stops
+    let result = App::new().run(scheduler.run().map(Ok)).await;
+    if let Err(e) = raft_node.raft.shutdown().await {
+        eprintln!("[Raft] Shutdown failed: {}", e);
+    }
+    result
+}
 
-        // Every 5 seconds, submit metrics reports
-        if metrics_tick >= 5 {
-            metrics_tick = 0;
-            let metrics_events = network_manager.read().await.get_metrics().await;
+/// What the leader needs to carry out effects
+struct EffectContext {
+    master_url: String,
+    game_id: String,
+    spawn_policy: game::SpawnRetryPolicy,
+    final_kill_manager: Arc<FinalKillManager>,
+    leader_info: game::LeaderInfo,
+    metrics_view: raft::metrics_view::MetricsView,
+}
 
-            for event in metrics_events {
-                let request = GameEventRequest { event: event.clone() };
-                match raft_node.raft.client_write(request).await {
-                    Ok(_) => {
-                        // Metrics submitted successfully
-                    }
-                    Err(e) => {
-                        eprintln!("[Network] Failed to submit metrics: {}", e);
-                    }
+/// Build the task that carries out `effect`
+/// Anything read from the state machine is captured now, so the task owns its inputs.
+async fn run_effect(
+    effect: game::Effect,
+    ctx: &EffectContext,
+    state_machine: &Arc<RwLock<raft::storage::GameStateMachine>>,
+    summary_shipped: u64,
+) -> BoxFuture<'static, game::effects::EffectResult> {
+    match effect {
+        game::Effect::SpawnNode { node_coord } => {
+            let master_url = ctx.master_url.clone();
+            let game_id = ctx.game_id.clone();
+            let policy = ctx.spawn_policy;
+            // Hexes by a capital or on high ground ask the master for a bigger task, the frontier a smaller one
+            let (capacity, tier) = {
+                let sm = state_machine.read().await;
+                (sm.game_state.terrain_at(node_coord).capacity_multiplier(), sm.game_state.spawn_tier(node_coord))
+            };
+            async move {
+                println!("[Lazy Init] Spawning {:?} worker for {:?}...", tier, node_coord);
+                let spawn = || {
+                    worker::raft::api::spawn_node_on_master(&master_url, &game_id, node_coord, false, capacity, tier)
+                };
+                match game::spawn_with_retries(policy, node_coord, spawn).await {
+                    Ok(_) => Ok(Vec::new()),
+                    // Report it so the hex isn't stuck initializing
+                    Err(failed) => Ok(vec![game::GameEvent::Fact(game::FactEvent::NodeInitializationFailed {
+                        node_coord,
+                        attempts: failed.attempts,
+                        reason: failed.reason,
+                        timestamp: std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .unwrap()
+                            .as_secs(),
+                    })]),
                 }
             }
+            .boxed()
         }
 
-        // If leader, run game logic tick to check for captures
-        if is_leader {
-            // Generate capture events based on overload conditions
-            let events = game_logic.tick(&game_state);
-
-            // Submit each generated event back to Raft
-            for event in events {
-                let request = GameEventRequest { event: event.clone() };
-                match raft_node.raft.client_write(request).await {
-                    Ok(response) => {
-                        println!(
-                            "[GameLogic] Auto-generated event committed at log index {}",
-                            response.log_id.index
-                        );
-                    }
-                    Err(e) => {
-                        eprintln!("[GameLogic] Failed to submit capture event: {}", e);
-                    }
-                }
+        game::Effect::FinalKill { player_id, client_endpoint, attacker_nodes } => {
+            let fkm = ctx.final_kill_manager.clone();
+            let secs = state_machine.read().await.game_state.scaled_secs(game::finalkill::FINAL_KILL_SECS);
+            async move {
+                let report = fkm
+                    .start_final_kill(player_id, client_endpoint, attacker_nodes, std::time::Duration::from_secs(secs))
+                    .await?;
+                // Committed before the effect's acknowledgment, so the log shows what the flood achieved
+                Ok(report
+                    .map(|report| {
+                        game::GameEvent::Fact(game::FactEvent::FinalKillReport {
+                            player_id,
+                            bytes_delivered: report.bytes_delivered,
+                            duration_ms: report.duration.as_millis() as u64,
+                            connections: report.connections.len() as u32,
+                            connected: report.connected() as u32,
+                            timestamp: std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap()
+                                .as_secs(),
+                        })
+                    })
+                    .into_iter()
+                    .collect())
             }
+            .boxed()
         }
 
-        // Show status every 30 seconds
-        if tick_count % 30 == 0 {
-            let storage = raft_node.storage.read().await;
-            let state_machine_arc = storage.state_machine();
-            drop(storage);
-            let sm = state_machine_arc.read().await;
-
-            // Access derived game state
-            let event_count = sm.events.len();
-            let player_count = sm.game_state.players.len();
-            let node_count = sm.game_state.nodes.len();
-            let alive_players = sm.game_state.players.values().filter(|p| p.alive).count();
+        game::Effect::ShutdownGame => {
+            // Final summary so the master records the finished game, with its awards
+            let awards = worker::raft::api::game_awards(state_machine, &ctx.metrics_view).await;
+            let sm = state_machine.read().await;
+            let mut summary = game::GameSummary::build(
+                &ctx.leader_info,
+                &sm.game_state,
+                &sm.events,
+                summary_shipped,
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+            );
             drop(sm);
+            summary.awards = Some(awards);
+            let master_url = ctx.master_url.clone();
 
-            println!(
-                "[Tick {}] Worker {} | Leader: {} | Events: {} | Players: {}/{} | Nodes: {}",
-                tick_count, worker_id, is_leader, event_count, alive_players, player_count, node_count
-            );
+            async move {
+                println!("\n=== GAME OVER ===");
+                println!("Only one player remains!");
+                println!("Shutting down all infrastructure...");
+
+                if let Err(e) = registry::report_summary(&master_url, &summary).await {
+                    eprintln!("[Summary] Failed to report final summary: {}", e);
+                }
+
+                // Call master to kill all workers, then itself
+                let client = reqwest::Client::new();
+                let mut failures = Vec::new();
+                for (path, what) in [("kill_workers", "workers"), ("kill", "master")] {
+                    println!("Calling master to shutdown {}...", what);
+                    match client.post(format!("{}/{}", master_url, path)).request_id().send().await {
+                        Ok(response) if response.status().is_success() => {
+                            println!("✓ Shutdown of {} initiated", what);
+                        }
+                        Ok(response) => {
+                            eprintln!("✗ Failed to shutdown {}: {}", what, response.status());
+                            failures.push(format!("{}: {}", path, response.status()));
+                        }
+                        Err(e) => {
+                            eprintln!("✗ Error calling master: {}", e);
+                            failures.push(format!("{}: {}", path, e));
+                        }
+                    }
+                }
+
+                if !failures.is_empty() {
+                    anyhow::bail!("shutdown incomplete ({})", failures.join(", "));
+                }
+                Ok(Vec::new())
+            }
+            .boxed()
         }
     }
 }
@@ -1,102 +1,144 @@
+// ECS task metadata: own address, task ARN, and the task's placement and size
+//
+// Fargate injects ECS_CONTAINER_METADATA_URI_V4 (platform 1.4+) or
+// ECS_CONTAINER_METADATA_URI (v3); older platforms only serve the fixed v2
+// endpoint. Each is tried in that order, and all three answer `/task` with the same
+// shape, v4 adding the ENI details (MAC, subnet, private DNS name). NODE_IP and
+// TASK_ARN still override what metadata reports, for local runs.
+
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
-const ECS_METADATA_URI: &str = "http://169.254.170.2/v2/metadata";
-const ECS_TASK_URI: &str = "http://169.254.170.2/v2/task";
+const ECS_TASK_URI_V2: &str = "http://169.254.170.2/v2/task";
+
+/// vCPUs of a regular-capacity worker task (task-definition.json: 256 CPU units)
+const REGULAR_TASK_VCPU: f64 = 0.25;
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct EcsMetadata {
-    #[serde(rename = "Cluster")]
-    cluster: String,
+/// The task this process runs in, as ECS metadata describes it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TaskMetadata {
+    #[serde(rename = "Cluster", default)]
+    pub cluster: String,
     #[serde(rename = "TaskARN")]
-    task_arn: String,
-    #[serde(rename = "Family")]
-    family: String,
+    pub task_arn: String,
+    #[serde(rename = "Family", default)]
+    pub family: String,
+    #[serde(rename = "Revision", default)]
+    pub revision: String,
+    /// e.g. us-east-1a (v4, and v2 on recent platforms)
+    #[serde(rename = "AvailabilityZone", default)]
+    pub availability_zone: Option<String>,
+    #[serde(rename = "Limits", default)]
+    pub limits: TaskLimits,
+    #[serde(rename = "Containers", default)]
+    pub containers: Vec<ContainerMetadata>,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct EcsTask {
-    #[serde(rename = "Cluster")]
-    cluster: String,
-    #[serde(rename = "TaskARN")]
-    task_arn: String,
-    #[serde(rename = "Family")]
-    family: String,
-    #[serde(rename = "Revision")]
-    revision: String,
-    #[serde(rename = "DesiredStatus")]
-    desired_status: String,
-    #[serde(rename = "KnownStatus")]
-    known_status: String,
-    #[serde(rename = "Containers")]
-    containers: Vec<EcsContainer>,
+/// Task-level resource limits
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct TaskLimits {
+    /// vCPUs (0.25 for a 256-unit task)
+    #[serde(rename = "CPU", default)]
+    pub cpu: Option<f64>,
+    /// MiB
+    #[serde(rename = "Memory", default)]
+    pub memory: Option<u64>,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct EcsContainer {
-    #[serde(rename = "DockerId")]
-    docker_id: String,
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "DockerName")]
-    docker_name: String,
-    #[serde(rename = "Image")]
-    image: String,
-    #[serde(rename = "Networks")]
-    networks: Vec<EcsNetwork>,
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ContainerMetadata {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Networks", default)]
+    pub networks: Vec<NetworkInterface>,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct EcsNetwork {
-    #[serde(rename = "NetworkMode")]
-    network_mode: String,
-    #[serde(rename = "IPv4Addresses")]
-    ipv4_addresses: Vec<String>,
+/// One network attachment (the task's ENI in awsvpc mode)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NetworkInterface {
+    #[serde(rename = "NetworkMode", default)]
+    pub network_mode: String,
+    #[serde(rename = "IPv4Addresses", default)]
+    pub ipv4_addresses: Vec<String>,
+    /// v4 only, like the rest of the ENI details below
+    #[serde(rename = "MACAddress", default)]
+    pub mac_address: Option<String>,
+    #[serde(rename = "IPv4SubnetCIDRBlock", default)]
+    pub subnet_cidr: Option<String>,
+    #[serde(rename = "PrivateDNSName", default)]
+    pub private_dns_name: Option<String>,
 }
 
-/// Get the task's private IP address from ECS metadata service
-/// Falls back to 127.0.0.1 for local development
-pub async fn get_task_ip() -> Result<String> {
-    // Check for environment variable override first
-    if let Ok(ip) = std::env::var("NODE_IP") {
-        println!("Using NODE_IP from environment: {}", ip);
-        return Ok(ip);
+impl TaskMetadata {
+    /// Every network attachment of the task's containers (in awsvpc mode they share one ENI)
+    pub fn enis(&self) -> impl Iterator<Item = &NetworkInterface> {
+        self.containers.iter().flat_map(|c| &c.networks)
     }
 
+    /// The task's private IPv4 address
+    pub fn private_ip(&self) -> Option<&str> {
+        self.enis().flat_map(|n| &n.ipv4_addresses).next().map(String::as_str)
+    }
+
+    /// CPU relative to a regular-capacity worker task (1.0, capitals 2.0), if reported
+    pub fn capacity_multiplier(&self) -> Option<f32> {
+        self.limits.cpu.filter(|cpu| *cpu > 0.0).map(|cpu| (cpu / REGULAR_TASK_VCPU) as f32)
+    }
+}
+
+/// Task metadata endpoints to try, newest first
+fn task_metadata_uris() -> Vec<String> {
+    let mut uris: Vec<String> = ["ECS_CONTAINER_METADATA_URI_V4", "ECS_CONTAINER_METADATA_URI"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok().filter(|s| !s.is_empty()))
+        .map(|base| format!("{}/task", base.trim_end_matches('/')))
+        .collect();
+    uris.push(ECS_TASK_URI_V2.to_string());
+    uris
+}
+
+/// Fetch this task's metadata from the first endpoint that answers
+pub async fn get_task_metadata() -> Result<TaskMetadata> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(2))
         .build()?;
 
-    // Try to fetch task metadata
-    let task_result = client
-        .get(ECS_TASK_URI)
-        .send()
-        .await;
+    let mut failures = Vec::new();
+    for uri in task_metadata_uris() {
+        let response = match client.get(&uri).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                failures.push(format!("{}: {}", uri, response.status()));
+                continue;
+            }
+            Err(e) => {
+                failures.push(format!("{}: {}", uri, e));
+                continue;
+            }
+        };
+        return response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse ECS task metadata from {}", uri));
+    }
+    anyhow::bail!("ECS task metadata unavailable ({})", failures.join(", "))
+}
 
-    match task_result {
-        Ok(response) => {
-            let task: EcsTask = response
-                .json()
-                .await
-                .context("Failed to parse ECS task metadata")?;
+/// Get the task's private IP address from ECS metadata service
+/// Outside ECS, NODE_IP must be set
+pub async fn get_task_ip() -> Result<String> {
+    // Check for environment variable override first
+    if let Ok(ip) = std::env::var("NODE_IP") {
+        println!("Using NODE_IP from environment: {}", ip);
+        return Ok(ip);
+    }
 
-            // Extract IP from first container's first network
+    match get_task_metadata().await {
+        Ok(task) => {
             let ip = task
-                .containers
-                .first()
-                .context("No containers found in task metadata")?
-                .networks
-                .first()
-                .context("No networks found in container metadata")?
-                .ipv4_addresses
-                .first()
-                .context("No IPv4 addresses found in network metadata")?
-                .clone();
-
+                .private_ip()
+                .context("No IPv4 addresses found in task metadata")?
+                .to_string();
             println!("Detected task IP from ECS metadata: {}", ip);
             Ok(ip)
         }
@@ -116,6 +158,16 @@ pub async fn get_task_ip() -> Result<String> {
     }
 }
 
+/// Address to listen on: BIND_IP, or every interface
+/// A local cluster gives each process its own loopback address (127.0.x.y) through
+/// this, since every node uses the same ports.
+pub fn bind_ip() -> String {
+    std::env::var("BIND_IP")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "0.0.0.0".to_string())
+}
+
 /// Get the task ARN from ECS metadata service
 /// Falls back to a local identifier for local development
 pub async fn get_task_arn() -> Result<String> {
@@ -125,24 +177,10 @@ pub async fn get_task_arn() -> Result<String> {
         return Ok(arn);
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(2))
-        .build()?;
-
-    let metadata_result = client
-        .get(ECS_METADATA_URI)
-        .send()
-        .await;
-
-    match metadata_result {
-        Ok(response) => {
-            let metadata: EcsMetadata = response
-                .json()
-                .await
-                .context("Failed to parse ECS metadata")?;
-
-            println!("Detected task ARN: {}", metadata.task_arn);
-            Ok(metadata.task_arn)
+    match get_task_metadata().await {
+        Ok(task) => {
+            println!("Detected task ARN: {}", task.task_arn);
+            Ok(task.task_arn)
         }
         Err(_) => {
             // Fallback for local development
@@ -154,3 +192,48 @@ pub async fn get_task_arn() -> Result<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v4_task() {
+        let body = r#"{
+            "Cluster": "arn:aws:ecs:us-east-1:123456789012:cluster/camhack",
+            "TaskARN": "arn:aws:ecs:us-east-1:123456789012:task/camhack/abc",
+            "Family": "udp-node-capital",
+            "Revision": "7",
+            "DesiredStatus": "RUNNING",
+            "KnownStatus": "RUNNING",
+            "Limits": { "CPU": 0.5, "Memory": 1024 },
+            "AvailabilityZone": "us-east-1b",
+            "LaunchType": "FARGATE",
+            "Containers": [{
+                "DockerId": "1",
+                "Name": "udp-node-capital",
+                "Networks": [{
+                    "NetworkMode": "awsvpc",
+                    "IPv4Addresses": ["10.0.2.106"],
+                    "AttachmentIndex": 0,
+                    "MACAddress": "0e:9e:32:c7:48:85",
+                    "IPv4SubnetCIDRBlock": "10.0.2.0/24",
+                    "PrivateDNSName": "ip-10-0-2-106.ec2.internal"
+                }]
+            }]
+        }"#;
+        let task: TaskMetadata = serde_json::from_str(body).unwrap();
+        assert_eq!(task.private_ip(), Some("10.0.2.106"));
+        assert_eq!(task.availability_zone.as_deref(), Some("us-east-1b"));
+        assert_eq!(task.limits, TaskLimits { cpu: Some(0.5), memory: Some(1024) });
+        assert_eq!(task.capacity_multiplier(), Some(2.0));
+        let eni = task.enis().next().unwrap();
+        assert_eq!(eni.subnet_cidr.as_deref(), Some("10.0.2.0/24"));
+
+        // v2 has no limits or ENI details
+        let v2 = r#"{ "TaskARN": "arn:task", "Containers": [{ "Networks": [{ "NetworkMode": "awsvpc", "IPv4Addresses": ["10.0.0.5"] }] }] }"#;
+        let task: TaskMetadata = serde_json::from_str(v2).unwrap();
+        assert_eq!((task.private_ip(), task.capacity_multiplier()), (Some("10.0.0.5"), None));
+        assert_eq!(task.enis().next().unwrap().mac_address, None);
+    }
+}
@@ -0,0 +1,290 @@
+// Observer webhook - live play-by-play for the audience's Discord or Slack channel
+//
+// OBSERVER_WEBHOOK_URL names an incoming webhook. Every worker registers an
+// ObserverHook on its storage, which turns the major committed events (a player
+// joining, a capital falling, an elimination, game over) into one-line
+// announcements; a task posts them, but only while this node is the Raft leader, so
+// the channel hears each one once. Game over is followed by the awards.
+//
+// Hooks can't block, so the hook only formats text and hands it over a channel.
+// Entries applied long after they were committed (a restarted node replaying its
+// log) aren't announced again.
+
+use crate::game::events::{FactEvent, GameEvent, GamePhase, NodeType};
+use crate::game::state::StateChange;
+use crate::game::{Awards, GameState};
+use crate::raft::api::game_awards;
+use crate::raft::hooks::ApplyHook;
+use crate::raft::storage::GameEventRequest;
+use crate::raft::RaftNode;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Mutex};
+
+/// Entries committed longer ago than this when applied are old news
+pub const STALE_SECS: u64 = 60;
+
+/// Longest wait for a rate-limited webhook before dropping the message
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Which chat service a webhook belongs to (their payloads differ)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    Discord,
+    Slack,
+}
+
+/// Where announcements go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObserverConfig {
+    pub url: String,
+    pub format: WebhookFormat,
+}
+
+impl ObserverConfig {
+    /// OBSERVER_WEBHOOK_URL (unset = no announcements) and OBSERVER_WEBHOOK_FORMAT
+    /// (`discord` or `slack`; default: guessed from the URL)
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("OBSERVER_WEBHOOK_URL").ok().filter(|url| !url.trim().is_empty())?;
+        let format = std::env::var("OBSERVER_WEBHOOK_FORMAT").ok();
+        Some(Self::new(url.trim(), format.as_deref()))
+    }
+
+    pub fn new(url: &str, format: Option<&str>) -> Self {
+        let format = match format.map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+            Some("slack") => WebhookFormat::Slack,
+            Some("discord") => WebhookFormat::Discord,
+            _ if url.contains("hooks.slack.com") => WebhookFormat::Slack,
+            _ => WebhookFormat::Discord,
+        };
+        Self { url: url.to_string(), format }
+    }
+
+    /// The webhook's JSON body for `text`
+    /// Discord gets mentions switched off, Slack gets its control characters escaped,
+    /// so a player named "@everyone" or "<!channel>" pings nobody.
+    pub fn payload(&self, text: &str) -> serde_json::Value {
+        match self.format {
+            WebhookFormat::Discord => json!({ "content": text, "allowed_mentions": { "parse": [] } }),
+            WebhookFormat::Slack => {
+                let escaped = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                json!({ "text": escaped })
+            }
+        }
+    }
+}
+
+/// Something worth telling the audience
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Announcement {
+    Text(String),
+    /// Posted with the awards after it
+    GameOver(String),
+}
+
+/// What `request`'s `changes` are worth announcing, given the state after it
+pub fn announcements(request: &GameEventRequest, changes: &[StateChange], game_state: &GameState) -> Vec<Announcement> {
+    let name = |player_id: u64| {
+        game_state.players.get(&player_id).map(|p| p.name.clone()).unwrap_or_else(|| format!("player {}", player_id))
+    };
+    let captured = matches!(request.event, GameEvent::Fact(FactEvent::NodeCaptured { .. }));
+
+    let mut out = Vec::new();
+    for change in changes {
+        match change {
+            StateChange::PlayerAdded { player_id } => {
+                let capital = game_state.players.get(player_id).map(|p| p.capital_coord);
+                let players = match game_state.players.len() {
+                    1 => "1 player".to_string(),
+                    n => format!("{} players", n),
+                };
+                out.push(Announcement::Text(match capital {
+                    Some(coord) => format!("{} joined the game (capital at {}) - {}", name(*player_id), coord, players),
+                    None => format!("{} joined the game - {}", name(*player_id), players),
+                }));
+            }
+            StateChange::NodeOwnerChanged { coord, old_owner_id, new_owner_id } if captured && *old_owner_id != 0 && *new_owner_id != 0 => {
+                let capital = game_state.players.get(old_owner_id).is_some_and(|p| p.capital_coord == *coord)
+                    || game_state.nodes.get(coord).is_some_and(|n| n.node_type == NodeType::Capital);
+                if capital {
+                    out.push(Announcement::Text(format!(
+                        "{} captured {}'s capital at {}",
+                        name(*new_owner_id),
+                        name(*old_owner_id),
+                        coord
+                    )));
+                }
+            }
+            StateChange::PlayerEliminated { player_id } => {
+                out.push(Announcement::Text(format!("{} was eliminated", name(*player_id))));
+            }
+            StateChange::PhaseChanged { phase: GamePhase::SuddenDeath } => {
+                out.push(Announcement::Text("Sudden death - time is up, captures now take half as long".to_string()));
+            }
+            StateChange::GameOver => {
+                out.push(Announcement::GameOver(match game_state.winner() {
+                    Some(winner) => format!("Game over - {} wins!", name(winner)),
+                    None => "Game over - no winner".to_string(),
+                }));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The awards as announcement lines
+pub fn award_lines(awards: &Awards) -> Vec<String> {
+    [
+        ("Fastest capture", &awards.fastest_capture),
+        ("Most bandwidth", &awards.most_bandwidth),
+        ("Longest held", &awards.longest_held),
+        ("Comeback of the game", &awards.comeback),
+    ]
+    .into_iter()
+    .filter_map(|(title, award)| award.as_ref().map(|a| format!("{}: {} - {}", title, a.name, a.detail)))
+    .collect()
+}
+
+/// Apply hook turning committed events into announcements for `run`
+pub struct ObserverHook {
+    tx: mpsc::UnboundedSender<Announcement>,
+}
+
+impl ObserverHook {
+    /// The hook and the receiver `run` posts from
+    pub fn new() -> (Arc<Self>, mpsc::UnboundedReceiver<Announcement>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Arc::new(Self { tx }), rx)
+    }
+}
+
+impl ApplyHook for ObserverHook {
+    fn name(&self) -> &str {
+        "observer"
+    }
+
+    fn on_apply(&self, _log_index: u64, request: &GameEventRequest, changes: &[StateChange], game_state: &GameState) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        if request.committed_at != 0 && request.committed_at + STALE_SECS < now {
+            return;
+        }
+        for announcement in announcements(request, changes, game_state) {
+            let _ = self.tx.send(announcement);
+        }
+    }
+}
+
+/// Post announcements to the webhook while this node leads; prefixed with the game id
+/// Followers drain theirs unposted. Failed posts are logged and dropped.
+pub async fn run(
+    config: ObserverConfig,
+    game_id: String,
+    raft_node: Arc<RaftNode>,
+    rx: Arc<Mutex<mpsc::UnboundedReceiver<Announcement>>>,
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let mut rx = rx.lock().await;
+    while let Some(announcement) = rx.recv().await {
+        if !raft_node.is_leader().await {
+            continue;
+        }
+        let lines = match announcement {
+            Announcement::Text(text) => vec![text],
+            Announcement::GameOver(text) => {
+                let state_machine = raft_node.storage.read().await.state_machine();
+                let awards = game_awards(&state_machine, &raft_node.metrics_view).await;
+                std::iter::once(text).chain(award_lines(&awards)).collect()
+            }
+        };
+        let text = format!("[{}] {}", game_id, lines.join("\n"));
+        if let Err(e) = post(&client, &config, &text).await {
+            eprintln!("[Observer] Failed to post announcement: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Post `text`, waiting out one rate limit (429) if the webhook asks for it
+async fn post(client: &reqwest::Client, config: &ObserverConfig, text: &str) -> anyhow::Result<()> {
+    let payload = config.payload(text);
+    for attempt in 0..2 {
+        let response = client.post(&config.url).json(&payload).timeout(Duration::from_secs(10)).send().await?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt == 0 {
+            let wait = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<f64>().ok())
+                .map_or(Duration::from_secs(1), Duration::from_secs_f64);
+            tokio::time::sleep(wait.min(MAX_RETRY_AFTER)).await;
+            continue;
+        }
+        response.error_for_status()?;
+        return Ok(());
+    }
+    anyhow::bail!("still rate limited")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{CommandEvent, NodeCoord};
+
+    fn apply(state: &mut GameState, event: GameEvent) -> Vec<Announcement> {
+        let index = state.last_applied_log_index + 1;
+        let changes = state.process_event(event.clone(), index);
+        let request = GameEventRequest { event, committed_at: 0, origin: None };
+        announcements(&request, &changes, state)
+    }
+
+    fn join(state: &mut GameState, player_id: u64, name: &str, q: i32) -> Vec<Announcement> {
+        let event = CommandEvent::PlayerJoin {
+            player_id,
+            name: name.to_string(),
+            capital_coord: NodeCoord::new(q, 0),
+            capital_ip: None,
+            client_endpoint: None,
+            timestamp: 100,
+            raft_node_id: 0,
+            reconnect_key: None,
+        };
+        apply(state, event.into())
+    }
+
+    #[test]
+    fn test_announcements() {
+        let mut state = GameState::new();
+        assert_eq!(
+            join(&mut state, 1, "alice", 0),
+            vec![Announcement::Text("alice joined the game (capital at 0,0) - 1 player".to_string())]
+        );
+        join(&mut state, 2, "bob", 2);
+
+        // A neutral hex changing hands isn't news
+        assert!(apply(&mut state, FactEvent::NodeInitializationStarted { node_coord: NodeCoord::new(1, 0), owner_id: 0, timestamp: 101 }.into()).is_empty());
+        assert!(apply(&mut state, FactEvent::NodeCaptured { node_coord: NodeCoord::new(1, 0), new_owner_id: 2, timestamp: 102 }.into()).is_empty());
+
+        let sudden_death = apply(&mut state, FactEvent::PhaseChanged { phase: GamePhase::SuddenDeath, timestamp: 102 }.into());
+        assert_eq!(sudden_death, vec![Announcement::Text("Sudden death - time is up, captures now take half as long".to_string())]);
+
+        let fall = apply(&mut state, FactEvent::NodeCaptured { node_coord: NodeCoord::new(0, 0), new_owner_id: 2, timestamp: 103 }.into());
+        assert_eq!(fall.first(), Some(&Announcement::Text("bob captured alice's capital at 0,0".to_string())));
+        assert_eq!(fall.last(), Some(&Announcement::GameOver("Game over - bob wins!".to_string())));
+    }
+
+    #[test]
+    fn test_webhook_payloads() {
+        let discord = ObserverConfig::new("https://discord.com/api/webhooks/1/abc", None);
+        assert_eq!(discord.format, WebhookFormat::Discord);
+        assert_eq!(discord.payload("@everyone joined")["content"], "@everyone joined");
+        assert_eq!(discord.payload("x")["allowed_mentions"]["parse"], json!([]));
+
+        let slack = ObserverConfig::new("https://hooks.slack.com/services/T/B/x", None);
+        assert_eq!(slack.format, WebhookFormat::Slack);
+        assert_eq!(slack.payload("<!channel> & co")["text"], "&lt;!channel&gt; &amp; co");
+
+        assert_eq!(ObserverConfig::new("https://example.com/hook", Some("Slack")).format, WebhookFormat::Slack);
+    }
+}
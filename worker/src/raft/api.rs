@@ -1,27 +1,72 @@
-use crate::game::{GameEvent, NodeCoord, Player, Node};
-use crate::game::events::AttackTarget;
-use crate::raft::storage::{GameEventRequest, GameRaftTypeConfig};
+use crate::cors::{self, CorsPolicy};
+use crate::game::invariants::Violation;
+use crate::game::{Awards, CommandEvent, EventFamily, FactEvent, FeatureFlags, GameEvent, GamePhase, GameState, MetricsSample, NodeCoord, RefereeOverride, SpawnTier, TerrainTile};
+use crate::game::events::{AnnouncementSeverity, AttackTarget};
+use crate::game::state::{JoinError, NodeInitState, MAX_ANNOUNCEMENT_LEN, MAX_ANNOUNCEMENT_TTL_SECS, MAX_SCHEDULED_EVENTS};
+use crate::game::timetravel::{self, StateDiff, StateView};
+use crate::game::udp::InboundStats;
+use crate::http_cache::{self, FieldsQuery};
+use crate::raft::audit::{self, AuditQuery, AuditResponse, CommandOrigin};
+use crate::raft::digest::DigestStatus;
+use crate::raft::identity::NodeIdentity;
+use crate::raft::internal::{self, InternalApiConfig};
+use crate::raft::metrics_view::MetricsView;
+use crate::raft::capabilities::PeerCapabilities;
+use crate::raft::node_registry::{NodeRegistry, RegistrySource};
+use crate::raft::ratelimit::{self, RateLimitConfig, RateLimitStats, RateLimiter};
+use crate::raft::read_view::ReadView;
+use crate::raft::bootstrap::SNAPSHOT_CHUNK_SIZE;
+use crate::raft::snapshot_store::SnapshotFile;
+use crate::raft::spectate::{SnapshotShipper, SNAPSHOT_CONTENT_TYPE, SNAPSHOT_INDEX_HEADER};
+use crate::raft::storage::{DeadLetter, GameEventRequest, GameRaftTypeConfig, GameStateMachine};
+use crate::raft::write_error::{RaftWriteError, WriteErrorInfo};
+use crate::reconnect::reconnect_key;
+use crate::request_id::{self, RequestIdExt};
+use crate::supervisor::{AbortOnDrop, Supervisor, SubsystemStatus};
 use anyhow::Result;
 use axum::{
-    extract::{State, WebSocketUpgrade},
-    http::StatusCode,
+    extract::{ConnectInfo, Query, State, WebSocketUpgrade},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use futures_util::SinkExt;
+use futures_util::{FutureExt, SinkExt};
 use openraft::Raft;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
 
 /// HTTP API state shared across handlers
 #[derive(Clone)]
 pub struct ApiState {
     pub raft: Arc<Raft<GameRaftTypeConfig>>,
     pub storage: Arc<tokio::sync::RwLock<crate::raft::storage::MemStorage>>,
+    pub registry: NodeRegistry,
     pub master_url: String,
     pub game_id: String,
+    /// Address peers and attackers reach this node at (IP, or DNS name)
+    pub node_host: String,
+    /// Per-IP and per-player budgets for the write endpoints
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Capitals kept for returning players in a rematch (CAPITAL_PLACEMENTS, name -> coord)
+    pub reserved_capitals: Arc<HashMap<String, NodeCoord>>,
+    /// Token for /admin/override and /admin/announce (REFEREE_TOKEN, granted by the master at game creation)
+    pub referee_token: Option<Arc<str>>,
+    /// Node metrics from the telemetry plane (the leader's, mirrored elsewhere)
+    pub metrics_view: MetricsView,
+    /// What this node's UDP responder is receiving, by source
+    pub inbound: InboundStats,
+    /// Token the internal listener requires (INTERNAL_API_TOKEN, None = unchecked)
+    pub internal_token: Option<Arc<str>>,
+    /// Browser origins allowed on the public listener (ALLOWED_ORIGINS)
+    pub cors: Arc<CorsPolicy>,
+    /// This node's latest snapshot, compressed for spectators
+    pub snapshots: SnapshotShipper,
+    /// The applied state /status and /game/state read, copied off the apply path
+    pub reads: ReadView,
 }
 
 /// Request to submit a new game event
@@ -36,13 +81,101 @@ pub struct SubmitEventResponse {
     pub success: bool,
     pub message: String,
     pub log_index: Option<u64>,
+    /// Why Raft didn't commit it, for callers deciding whether and where to retry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<WriteErrorInfo>,
+}
+
+/// Events per GET /events page when the query sets no limit
+pub const DEFAULT_EVENTS_LIMIT: usize = 500;
+
+/// Most events one GET /events page returns
+pub const MAX_EVENTS_LIMIT: usize = 5000;
+
+/// Query for GET /events (worker and client): one page of the committed log
+/// Every filter given must match. Pass a page's `next_after_index` as `after_index`
+/// to get the next one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventsQuery {
+    /// Only events after this log position (0-based index into the event list)
+    pub after_index: Option<usize>,
+    /// Page size (default DEFAULT_EVENTS_LIMIT, at most MAX_EVENTS_LIMIT)
+    pub limit: Option<usize>,
+    /// Comma-separated event kinds ("NodeCaptured,PlayerEliminated") or families ("fact")
+    pub event_type: Option<String>,
+    /// Only events about this hex (`coord=q,r`, or `q` and `r`)
+    #[serde(flatten, with = "crate::game::coord::optional_fields")]
+    pub coord: Option<NodeCoord>,
+    /// Only events naming this player
+    pub player: Option<u64>,
+}
+
+impl EventsQuery {
+    fn matches(&self, event: &GameEvent) -> bool {
+        if let Some(types) = &self.event_type {
+            let family = match event.family() {
+                EventFamily::Command => "command",
+                EventFamily::Fact => "fact",
+                EventFamily::Telemetry => "telemetry",
+            };
+            if !types.split(',').map(str::trim).any(|t| t == event.kind() || t == family) {
+                return false;
+            }
+        }
+        if let Some(coord) = self.coord {
+            if !event.coords().contains(&coord) {
+                return false;
+            }
+        }
+        if let Some(player) = self.player {
+            if !event.player_ids().contains(&player) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The page of `events` (with their leader stamps) this query asks for
+    pub fn page(&self, events: &[GameEvent], event_times: &[u64]) -> EventsResponse {
+        let limit = self.limit.unwrap_or(DEFAULT_EVENTS_LIMIT).clamp(1, MAX_EVENTS_LIMIT);
+        let start = self.after_index.map_or(0, |i| i.saturating_add(1));
+
+        let mut page = EventsResponse { total: events.len(), ..Default::default() };
+        for (index, event) in events.iter().enumerate().skip(start) {
+            if page.events.len() == limit {
+                // Something is left to scan: resume after the last event returned
+                page.next_after_index = page.indices.last().copied();
+                break;
+            }
+            if self.matches(event) {
+                page.indices.push(index);
+                page.events.push(event.clone());
+                page.event_times.push(event_times.get(index).copied().unwrap_or(0));
+            }
+        }
+        page.count = page.events.len();
+        page
+    }
 }
 
 /// Response for querying events
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EventsResponse {
     pub events: Vec<GameEvent>,
+    /// Leader stamp of each event (unix seconds, 0 = unstamped)
+    #[serde(default)]
+    pub event_times: Vec<u64>,
+    /// Log position of each event (what `after_index` refers to)
+    #[serde(default)]
+    pub indices: Vec<usize>,
+    /// Events in this page
     pub count: usize,
+    /// Events in the whole log
+    #[serde(default)]
+    pub total: usize,
+    /// Cursor for the next page, None once the log has been read to the end
+    #[serde(default)]
+    pub next_after_index: Option<usize>,
 }
 
 /// Status response showing cluster state
@@ -53,6 +186,128 @@ pub struct StatusResponse {
     pub current_leader: Option<u64>,
     pub current_term: u64,
     pub event_count: usize,
+    /// Requests allowed and throttled by the rate limiter
+    #[serde(default)]
+    pub rate_limit: RateLimitStats,
+    /// Attack traffic reaching this node's responder, heaviest source first
+    #[serde(default)]
+    pub udp_sources: Vec<InboundSource>,
+    /// How this node's state digests compare with the leader's
+    #[serde(default)]
+    pub state_digest: DigestStatus,
+}
+
+/// One source of attack traffic, as this node's responder counts it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundSource {
+    pub addr: String,
+    pub packets: u64,
+    pub bytes: u64,
+    /// Time since its last packet
+    pub idle_ms: u64,
+}
+
+/// Subsystem health, from the process-wide Supervisor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub node_id: u64,
+    /// Every subsystem is running (or exited cleanly)
+    pub healthy: bool,
+    pub subsystems: Vec<SubsystemStatus>,
+    /// Tasks the supervisor is tracking (subsystems and everything short-lived)
+    pub tasks: usize,
+}
+
+/// GameState invariants on this node (see camhack_rules::invariants)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvariantsResponse {
+    pub node_id: u64,
+    /// Log index the state was checked at
+    pub log_index: u64,
+    pub ok: bool,
+    pub violations: Vec<Violation>,
+}
+
+/// Snapshot files on this node's disk (see snapshot_store)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotsResponse {
+    pub node_id: u64,
+    /// Snapshot dir, None when snapshots are only kept in memory
+    pub dir: Option<String>,
+    /// Oldest first
+    pub snapshots: Vec<SnapshotFile>,
+}
+
+/// Committed entries this node's rules had to skip (see storage::DeadLetter)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLettersResponse {
+    pub node_id: u64,
+    /// Oldest first, at most MAX_DEAD_LETTERS
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+/// GET /debug/state-at query: the event position to stop after
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateAtQuery {
+    pub index: usize,
+}
+
+/// GET /debug/state-diff query: the two event positions to compare
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateDiffQuery {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// How long a handicap lasts when the request doesn't say (seconds)
+pub const DEFAULT_HANDICAP_SECS: u64 = 60;
+
+/// POST /debug/handicap: degrade this node's responder for a while
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandicapRequest {
+    /// Inbound attack packets dropped (0-100)
+    #[serde(default)]
+    pub loss_pct: f32,
+    /// Added to every ACK
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Default DEFAULT_HANDICAP_SECS
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+}
+
+/// The handicap in force on this node's responder
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HandicapResponse {
+    pub active: bool,
+    pub loss_pct: f32,
+    pub delay_ms: u64,
+    pub remaining_secs: u64,
+}
+
+/// One node's address as this node believes it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryEntryInfo {
+    pub node_id: u64,
+    pub addr: String,
+    pub source: RegistrySource,
+    /// `addr` resolved right now (differs from `addr` for DNS names)
+    pub resolved: Option<String>,
+    /// Whether the node is a voter in the committed membership
+    pub is_voter: bool,
+    /// Hex the node serves, if a committed event said so
+    #[serde(default)]
+    pub coord: Option<NodeCoord>,
+    /// What the node said it supports in a Hello, unknown until one was exchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<PeerCapabilities>,
+}
+
+/// Registry response - the id→address map this node uses for Raft RPCs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryResponse {
+    pub node_id: u64,
+    pub entries: Vec<RegistryEntryInfo>,
 }
 
 // ============= Game Command Types =============
@@ -62,6 +317,34 @@ pub struct StatusResponse {
 pub struct JoinGameRequest {
     pub player_name: String,
     pub node_ip: String,  // IP address of the joining worker/node
+    /// The joining worker's Raft node id, registered at `node_ip` once the join
+    /// commits (0 = not a cluster member, nothing to register). Only the internal
+    /// listener's /game/join honours it; the public one always commits 0.
+    #[serde(default)]
+    pub raft_node_id: u64,
+}
+
+/// Request for POST /admin/override
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefereeOverrideRequest {
+    pub action: RefereeOverride,
+    /// Why - recorded in the RefereeAction event for the audit trail
+    pub reason: String,
+}
+
+/// Request for POST /admin/announce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnounceRequest {
+    pub text: String,
+    #[serde(default)]
+    pub severity: AnnouncementSeverity,
+    /// How long the banner shows (default 30s, at most MAX_ANNOUNCEMENT_TTL_SECS)
+    #[serde(default = "default_announcement_ttl")]
+    pub ttl_secs: u64,
+}
+
+fn default_announcement_ttl() -> u64 {
+    30
 }
 
 /// Response from joining the game
@@ -71,6 +354,19 @@ pub struct JoinGameResponse {
     pub message: String,
     pub player_id: Option<u64>,
     pub capital_coord: Option<NodeCoord>,
+    /// Why Raft didn't commit the join (see SubmitEventResponse::error)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<WriteErrorInfo>,
+}
+
+/// Master handing a warm pool worker the coordinate it now serves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdoptRequest {
+    /// Game the coordinate belongs to; a worker already in a game can't switch
+    #[serde(default)]
+    pub game_id: Option<String>,
+    #[serde(flatten, with = "crate::game::coord::fields")]
+    pub coord: NodeCoord,
 }
 
 /// Request to attack a neighbor
@@ -91,6 +387,9 @@ pub struct StopAttackRequest {
 pub struct CommandResponse {
     pub success: bool,
     pub message: String,
+    /// Why Raft didn't commit the command (see SubmitEventResponse::error)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<WriteErrorInfo>,
 }
 
 /// Game state snapshot for queries
@@ -99,6 +398,14 @@ pub struct GameStateResponse {
     pub players: Vec<PlayerInfo>,
     pub nodes: Vec<NodeInfo>,
     pub total_events: usize,
+    /// Non-plain hexes of the generated map (empty = uniform plane)
+    #[serde(default)]
+    pub terrain: Vec<TerrainTile>,
+    #[serde(default)]
+    pub phase: GamePhase,
+    /// Features the game runs (see camhack_rules::features)
+    #[serde(default)]
+    pub features: FeatureFlags,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +415,15 @@ pub struct PlayerInfo {
     pub capital_coord: NodeCoord,
     pub alive: bool,
     pub node_count: usize,
+    /// Capital lost: eliminated at this time unless it's recaptured first
+    #[serde(default)]
+    pub contested_until: Option<u64>,
+    /// Seconds until this player's capital can be attacked (None = not protected)
+    #[serde(default)]
+    pub spawn_protection_secs: Option<u64>,
+    /// The player's client stopped heartbeating, so their nodes stand idle
+    #[serde(default)]
+    pub idle: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,29 +433,170 @@ pub struct NodeInfo {
     pub current_target: Option<AttackTarget>,
     pub bandwidth_in: Option<u64>,
     pub packet_loss: Option<f32>,
+    /// Combined-arms speed-up on this node's capture timer, if boosted
+    #[serde(default)]
+    pub capture_multiplier: Option<f32>,
+    /// The node's own CPU use (0.0 to 1.0), while its worker streams samples
+    #[serde(default)]
+    pub cpu_utilization: Option<f32>,
+    /// Share of the node's memory in use (0.0 to 1.0), while its worker streams samples
+    #[serde(default)]
+    pub memory_pressure: Option<f32>,
 }
 
-/// Create the HTTP API router
+// ============= Grid Types =============
+
+/// Query for GET /grid/neighbors
+#[derive(Debug, Clone, Deserialize)]
+pub struct GridNeighborsQuery {
+    #[serde(flatten, with = "crate::game::coord::fields")]
+    pub coord: NodeCoord,
+    /// Ring radius (default 1 = adjacent nodes)
+    pub radius: Option<u32>,
+    /// Hex size in pixels - if set, each cell includes its pixel centre
+    pub size: Option<f64>,
+}
+
+/// Query for GET /grid/path
+#[derive(Debug, Clone, Deserialize)]
+pub struct GridPathQuery {
+    #[serde(flatten, deserialize_with = "from_fields")]
+    pub from: NodeCoord,
+    #[serde(flatten, deserialize_with = "to_fields")]
+    pub to: NodeCoord,
+    /// Hex size in pixels - if set, each cell includes its pixel centre
+    pub size: Option<f64>,
+}
+
+/// `from=q,r`, or the older `from_q`/`from_r`
+fn from_fields<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<NodeCoord, D::Error> {
+    crate::game::coord::deserialize_fields(deserializer, "from")?.ok_or_else(|| serde::de::Error::missing_field("from"))
+}
+
+/// `to=q,r`, or the older `to_q`/`to_r`
+fn to_fields<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<NodeCoord, D::Error> {
+    crate::game::coord::deserialize_fields(deserializer, "to")?.ok_or_else(|| serde::de::Error::missing_field("to"))
+}
+
+/// Pixel position (pointy-top layout, same as the frontend's hexToPixel)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PixelPos {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A grid coordinate, optionally with its pixel centre
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridCell {
+    pub coord: NodeCoord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pixel: Option<PixelPos>,
+}
+
+impl GridCell {
+    fn new(coord: NodeCoord, size: Option<f64>) -> Self {
+        let pixel = size.map(|size| {
+            let (x, y) = coord.to_pixel(size);
+            PixelPos { x, y }
+        });
+        Self { coord, pixel }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridNeighborsResponse {
+    pub center: GridCell,
+    pub radius: u32,
+    pub cells: Vec<GridCell>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridPathResponse {
+    pub from: NodeCoord,
+    pub to: NodeCoord,
+    pub distance: u32,
+    /// Straight hex line from `from` to `to`, inclusive
+    pub path: Vec<GridCell>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsQuery {
+    #[serde(flatten, with = "crate::game::coord::fields")]
+    pub coord: NodeCoord,
+    /// Seconds of history to return (default 300)
+    pub window: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSeriesResponse {
+    pub coord: NodeCoord,
+    pub window_secs: u64,
+    /// Oldest first
+    pub samples: Vec<MetricsSample>,
+}
+
+/// Create the public (player-facing) router: commands, state, grid math, referee
 pub fn create_router(state: ApiState) -> Router {
+    // Internet-reachable writes are throttled per source IP and per player (429)
+    let throttle = middleware::from_fn_with_state(state.clone(), ratelimit::throttle);
+
     Router::new()
-        // Legacy event submission endpoints
-        .route("/events", post(submit_event))
-        .route("/events", get(get_events))
-        .route("/status", get(get_status))
+        .route("/healthz", get(get_health))
+        // Stateless hex math for the frontend and bots
+        .route("/grid/neighbors", get(grid_neighbors))
+        .route("/grid/path", get(grid_path))
         // Game command endpoints
-        .route("/game/join", post(handle_join_game))
-        .route("/game/attack", post(handle_attack_command))
-        .route("/game/stop-attack", post(handle_stop_attack))
+        .route("/game/join", post(handle_join_game).layer(throttle.clone()))
+        .route("/game/attack", post(handle_attack_command).layer(throttle.clone()))
+        .route("/game/stop-attack", post(handle_stop_attack).layer(throttle))
         .route("/game/state", get(handle_get_game_state))
+        .route("/game/metrics", get(handle_get_metrics))
+        .route("/awards", get(handle_get_awards))
+        // Referee corrections (Authorization: Bearer <REFEREE_TOKEN>)
+        .route("/admin/override", post(handle_referee_override))
+        .route("/admin/announce", post(handle_announce))
+        .route("/admin/audit", get(handle_get_audit))
         // WebSocket attack endpoint
         .route("/attack", get(handle_attack))
-        .layer(CorsLayer::permissive())  // Enable CORS for frontend
+        .layer(middleware::from_fn_with_state(state.cors.clone(), cors::check_websocket_origin))
+        .layer(state.cors.layer())
+        .layer(http_cache::compression())
+        .layer(middleware::from_fn(request_id::propagate))
+        .with_state(state)
+}
+
+/// Create the cluster-internal router (see internal): the log, node status, adoption
+/// Every request needs INTERNAL_API_TOKEN when one is configured; no CORS.
+pub fn create_internal_router(state: ApiState) -> Router {
+    let require_token = middleware::from_fn_with_state(state.internal_token.clone(), internal::require_token);
+
+    Router::new()
+        // Event submission (peers forwarding to the leader) and the committed log
+        .route("/events", post(submit_event).get(get_events))
+        .route("/status", get(get_status))
+        .route("/healthz", get(get_health))
+        .route("/registry", get(get_registry))
+        .route("/debug/invariants", get(get_invariants))
+        .route("/debug/snapshots", get(get_snapshots))
+        .route("/debug/dead-letters", get(get_dead_letters))
+        .route("/debug/state-at", get(get_state_at))
+        .route("/debug/state-diff", get(get_state_diff))
+        .route("/debug/handicap", get(get_handicap).post(set_handicap).delete(clear_handicap))
+        // Compressed snapshot for spectators and joining learners (see spectate)
+        .route("/spectate/snapshot", get(get_spectate_snapshot))
+        // Warm pool: the master assigns this idle worker a coordinate
+        .route("/adopt", post(handle_adopt))
+        // A cluster member joining as a player, registered under its raft_node_id
+        .route("/game/join", post(handle_member_join))
+        .layer(require_token)
+        .layer(middleware::from_fn(request_id::propagate))
         .with_state(state)
 }
 
 /// Submit a game event for consensus
 async fn submit_event(
     State(state): State<ApiState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     Json(req): Json<SubmitEventRequest>,
 ) -> impl IntoResponse {
     // Check if this node is the leader
@@ -147,21 +604,40 @@ async fn submit_event(
 
     if metrics.current_leader != Some(metrics.id) {
         // Not the leader - return error with leader info
+        let error = RaftWriteError::not_leader(metrics.current_leader, &state.registry).await;
         let response = SubmitEventResponse {
             success: false,
-            message: format!(
-                "Not the leader. Current leader: {:?}",
-                metrics.current_leader
-            ),
+            message: error.to_string(),
+            log_index: None,
+            error: Some(error.info()),
+        };
+        return (error.status(), Json(response));
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let storage = state.storage.read().await;
+    let sm_arc = storage.state_machine();
+    drop(storage);
+    let sm = sm_arc.read().await;
+    let refused = refuse_event(&req.event, &sm.game_state, now);
+    let origin = CommandOrigin::new(peer.ip(), audit::command_player(&req.event, &sm.game_state));
+    drop(sm);
+
+    if let Some((status, message)) = refused {
+        let response = SubmitEventResponse {
+            success: false,
+            message,
             log_index: None,
+            error: None,
         };
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+        return (status, Json(response));
     }
 
     // Submit to Raft for consensus
-    let request = GameEventRequest {
-        event: req.event.clone(),
-    };
+    let request = GameEventRequest::with_origin(req.event.clone(), origin);
 
     match state.raft.client_write(request).await {
         Ok(response) => {
@@ -170,75 +646,586 @@ async fn submit_event(
                 success: true,
                 message: format!("Event committed at log index {}", log_index),
                 log_index: Some(log_index),
+                error: None,
             };
             (StatusCode::OK, Json(response))
         }
         Err(e) => {
+            let error = RaftWriteError::from_client_write(e, &state.registry).await;
             let response = SubmitEventResponse {
                 success: false,
-                message: format!("Failed to commit event: {}", e),
+                message: format!("Failed to commit event: {}", error),
                 log_index: None,
+                error: Some(error.info()),
             };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+            (error.status(), Json(response))
+        }
+    }
+}
+
+/// Why a submitted event can't be committed (with its HTTP status), None if it can
+/// Shared by POST /events and the SubmitCommand RPC, so both refuse the same events.
+pub(crate) fn refuse_event(event: &GameEvent, game_state: &GameState, now: u64) -> Option<(StatusCode, String)> {
+    // Not until every member runs a build that decodes it
+    if !game_state.permits(event) {
+        return Some((
+            StatusCode::CONFLICT,
+            format!(
+                "{} needs cluster version {} (the cluster is at {})",
+                event.kind(),
+                event.min_cluster_version(),
+                game_state.cluster_version()
+            ),
+        ));
+    }
+    match event {
+        // One live player per name; a player already in the game reconnects instead
+        GameEvent::Command(CommandEvent::PlayerJoin { player_id, name, .. }) => {
+            game_state.validate_join(*player_id, name).err().map(|e| (StatusCode::CONFLICT, e.to_string()))
+        }
+        // Only the client holding the player's reconnect token gets it back
+        GameEvent::Command(CommandEvent::PlayerReconnected { player_id, token, .. }) => {
+            game_state.validate_reconnect(*player_id, &reconnect_key(token)).err().map(|e| {
+                let status = match e {
+                    JoinError::UnknownPlayer(_) => StatusCode::NOT_FOUND,
+                    _ => StatusCode::FORBIDDEN,
+                };
+                (status, e.to_string())
+            })
+        }
+        // Overrides and announcements need the referee token
+        GameEvent::Fact(FactEvent::RefereeAction { .. }) => Some((
+            StatusCode::FORBIDDEN,
+            "Referee actions go through POST /admin/override".to_string(),
+        )),
+        GameEvent::Fact(FactEvent::Announcement { .. }) => Some((
+            StatusCode::FORBIDDEN,
+            "Announcements go through POST /admin/announce".to_string(),
+        )),
+        GameEvent::Fact(FactEvent::ClusterVersionSet { .. }) => Some((
+            StatusCode::FORBIDDEN,
+            "The leader raises the cluster version once every member supports it".to_string(),
+        )),
+        // Raw target changes can't aim at a spawn-protected capital either, or
+        // retarget a node still in its cooldown (the state would drop it anyway)
+        GameEvent::Command(CommandEvent::SetNodeTarget { .. }) | GameEvent::Command(CommandEvent::SetNodeTargets { .. }) => {
+            event.attack_orders().into_iter().find_map(|order| {
+                let target = order.target?;
+                if let Some(remaining_secs) = game_state.retarget_cooldown(order.node_coord, now) {
+                    let error = crate::game::AttackError::Cooldown { remaining_secs };
+                    return Some((StatusCode::TOO_MANY_REQUESTS, error.to_string()));
+                }
+                match target {
+                    AttackTarget::Coordinate(target) => game_state.spawn_protection(target, now).map(|remaining_secs| {
+                        (StatusCode::BAD_REQUEST, crate::game::AttackError::SpawnProtected { remaining_secs }.to_string())
+                    }),
+                    AttackTarget::Player(_) => None,
+                }
+            })
+        }
+        // A schedule holds one plain command, and the queue is bounded; a reconnect
+        // can't wait, its token is checked when it's submitted. The command itself
+        // goes through the checks above when it fires.
+        GameEvent::Command(CommandEvent::ScheduleEvent { command, .. }) => match **command {
+            CommandEvent::ScheduleEvent { .. } => {
+                Some((StatusCode::BAD_REQUEST, "A scheduled event can't schedule another".to_string()))
+            }
+            CommandEvent::PlayerReconnected { .. } => {
+                Some((StatusCode::BAD_REQUEST, "Reconnects can't be scheduled".to_string()))
+            }
+            _ if game_state.scheduled.len() >= MAX_SCHEDULED_EVENTS => Some((
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("At most {} events can be scheduled at once", MAX_SCHEDULED_EVENTS),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the request carries the game's referee token (Authorization: Bearer <REFEREE_TOKEN>)
+fn check_referee(state: &ApiState, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = &state.referee_token else {
+        return Err((StatusCode::NOT_FOUND, "This game has no referee".to_string()));
+    };
+    if !internal::bearer_matches(expected, headers) {
+        return Err((StatusCode::UNAUTHORIZED, "Missing or wrong referee token".to_string()));
+    }
+    Ok(())
+}
+
+/// Who sent each committed command, for the referee (see audit)
+async fn handle_get_audit(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditResponse>, (StatusCode, String)> {
+    check_referee(&state, &headers)?;
+
+    let storage = state.storage.read().await;
+    let sm_arc = storage.state_machine();
+    drop(storage);
+    let sm = sm_arc.read().await;
+    Ok(Json(query.page(&sm.audit, &sm.events, &sm.event_times)))
+}
+
+/// Commit a referee's correction as a RefereeAction
+async fn handle_referee_override(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<RefereeOverrideRequest>,
+) -> impl IntoResponse {
+    let reply = |status: StatusCode, message: String, log_index: Option<u64>| {
+        (status, Json(SubmitEventResponse { success: status == StatusCode::OK, message, log_index, error: None }))
+    };
+    let write_failed = |error: RaftWriteError, message: String| {
+        (error.status(), Json(SubmitEventResponse { success: false, message, log_index: None, error: Some(error.info()) }))
+    };
+
+    if let Err((status, message)) = check_referee(&state, &headers) {
+        return reply(status, message, None);
+    }
+
+    let metrics = state.raft.metrics().borrow().clone();
+    if metrics.current_leader != Some(metrics.id) {
+        let error = RaftWriteError::not_leader(metrics.current_leader, &state.registry).await;
+        return write_failed(error.clone(), error.to_string());
+    }
+
+    // The override has to point at something that exists, in a game still running
+    {
+        let storage = state.storage.read().await;
+        let sm_arc = storage.state_machine();
+        drop(storage);
+        let sm = sm_arc.read().await;
+        let game_state = &sm.game_state;
+
+        let missing = match &req.action {
+            RefereeOverride::RevertCapture { node_coord, owner_id } => {
+                if !game_state.nodes.contains_key(node_coord) {
+                    Some(format!("Node ({}, {}) does not exist", node_coord.q, node_coord.r))
+                } else if *owner_id != 0 && !game_state.players.contains_key(owner_id) {
+                    Some(format!("Player {} does not exist", owner_id))
+                } else {
+                    None
+                }
+            }
+            RefereeOverride::NeutralizeNode { node_coord } => (!game_state.nodes.contains_key(node_coord))
+                .then(|| format!("Node ({}, {}) does not exist", node_coord.q, node_coord.r)),
+            RefereeOverride::ExtendTime { player_id, .. } => {
+                (!game_state.players.contains_key(player_id)).then(|| format!("Player {} does not exist", player_id))
+            }
+        };
+        if let Some(message) = missing {
+            return reply(StatusCode::BAD_REQUEST, message, None);
+        }
+        if game_state.game_over {
+            return reply(StatusCode::CONFLICT, "Game is already over".to_string(), None);
+        }
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    println!("[Referee] {} ({:?}): {}", req.action.kind(), req.action, req.reason);
+    let event = GameEvent::Fact(FactEvent::RefereeAction { action: req.action, reason: req.reason, timestamp });
+
+    match state.raft.client_write(GameEventRequest::new(event)).await {
+        Ok(response) => {
+            let log_index = response.log_id.index;
+            reply(StatusCode::OK, format!("Override committed at log index {}", log_index), Some(log_index))
+        }
+        Err(e) => {
+            let error = RaftWriteError::from_client_write(e, &state.registry).await;
+            write_failed(error.clone(), format!("Failed to commit override: {}", error))
         }
     }
 }
 
-/// Get all committed events
-async fn get_events(State(state): State<ApiState>) -> impl IntoResponse {
+/// Commit a referee's announcement, shown to every player as a banner
+/// (needs REFEREE_TOKEN, like an override)
+async fn handle_announce(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(req): Json<AnnounceRequest>,
+) -> impl IntoResponse {
+    let reply = |status: StatusCode, message: String, log_index: Option<u64>| {
+        (status, Json(SubmitEventResponse { success: status == StatusCode::OK, message, log_index, error: None }))
+    };
+    let write_failed = |error: RaftWriteError, message: String| {
+        (error.status(), Json(SubmitEventResponse { success: false, message, log_index: None, error: Some(error.info()) }))
+    };
+
+    if let Err((status, message)) = check_referee(&state, &headers) {
+        return reply(status, message, None);
+    }
+
+    let metrics = state.raft.metrics().borrow().clone();
+    if metrics.current_leader != Some(metrics.id) {
+        let error = RaftWriteError::not_leader(metrics.current_leader, &state.registry).await;
+        return write_failed(error.clone(), error.to_string());
+    }
+
+    let text = req.text.trim();
+    if text.is_empty() {
+        return reply(StatusCode::BAD_REQUEST, "Announcement text is empty".to_string(), None);
+    }
+    if text.chars().count() > MAX_ANNOUNCEMENT_LEN {
+        let message = format!("Announcements are at most {} characters", MAX_ANNOUNCEMENT_LEN);
+        return reply(StatusCode::BAD_REQUEST, message, None);
+    }
+    if req.ttl_secs == 0 || req.ttl_secs > MAX_ANNOUNCEMENT_TTL_SECS {
+        let message = format!("ttl_secs must be between 1 and {}", MAX_ANNOUNCEMENT_TTL_SECS);
+        return reply(StatusCode::BAD_REQUEST, message, None);
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    println!("[Referee] Announcement ({}, {}s): {}", req.severity.as_str(), req.ttl_secs, text);
+    let event = GameEvent::Fact(FactEvent::Announcement {
+        text: text.to_string(),
+        severity: req.severity,
+        ttl_secs: req.ttl_secs,
+        timestamp,
+    });
+
+    match state.raft.client_write(GameEventRequest::new(event)).await {
+        Ok(response) => {
+            let log_index = response.log_id.index;
+            reply(StatusCode::OK, format!("Announcement committed at log index {}", log_index), Some(log_index))
+        }
+        Err(e) => {
+            let error = RaftWriteError::from_client_write(e, &state.registry).await;
+            write_failed(error.clone(), format!("Failed to commit announcement: {}", error))
+        }
+    }
+}
+
+/// Get a page of committed events (see EventsQuery)
+async fn get_events(State(state): State<ApiState>, Query(query): Query<EventsQuery>) -> impl IntoResponse {
     let storage = state.storage.read().await;
     let state_machine = storage.state_machine();
     drop(storage);
 
     let sm = state_machine.read().await;
-    let events = sm.events.clone();
-    let count = events.len();
+    let response = query.page(&sm.events, &sm.event_times);
     drop(sm);
 
-    let response = EventsResponse { events, count };
     (StatusCode::OK, Json(response))
 }
 
 /// Get cluster status
 async fn get_status(State(state): State<ApiState>) -> impl IntoResponse {
-    let metrics = state.raft.metrics().borrow().clone();
+    let (node_id, current_leader, current_term) = {
+        let metrics = state.raft.metrics();
+        let metrics = metrics.borrow();
+        (metrics.id, metrics.current_leader, metrics.current_term)
+    };
+    let state_digest = state.storage.read().await.digests().status();
 
-    let storage = state.storage.read().await;
-    let state_machine = storage.state_machine();
-    drop(storage);
+    let response = StatusResponse {
+        node_id,
+        is_leader: current_leader == Some(node_id),
+        current_leader,
+        current_term,
+        event_count: state.reads.latest().event_count,
+        rate_limit: state.rate_limiter.stats(),
+        udp_sources: inbound_sources(&state.inbound),
+        state_digest,
+    };
+
+    (StatusCode::OK, Json(response))
+}
+
+/// Check the applied state's invariants - a non-empty list means this node has diverged
+async fn get_invariants(State(state): State<ApiState>) -> Json<InvariantsResponse> {
+    let node_id = state.raft.metrics().borrow().id;
+    let state_machine = state.storage.read().await.state_machine();
+    let sm = state_machine.read().await;
+    let violations = sm.game_state.check_invariants();
+    Json(InvariantsResponse { node_id, log_index: sm.last_applied_log_index, ok: violations.is_empty(), violations })
+}
 
+/// List the snapshot files this node has written, checking each one's checksum
+async fn get_snapshots(State(state): State<ApiState>) -> Result<Json<SnapshotsResponse>, (StatusCode, String)> {
+    let node_id = state.raft.metrics().borrow().id;
+    let Some(store) = state.storage.read().await.snapshot_store().cloned() else {
+        return Ok(Json(SnapshotsResponse { node_id, dir: None, snapshots: Vec::new() }));
+    };
+    let snapshots = store.list().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(SnapshotsResponse { node_id, dir: Some(store.dir().display().to_string()), snapshots }))
+}
+
+/// List the committed entries this node skipped instead of applying
+async fn get_dead_letters(State(state): State<ApiState>) -> Json<DeadLettersResponse> {
+    let node_id = state.raft.metrics().borrow().id;
+    let state_machine = state.storage.read().await.state_machine();
+    let dead_letters = state_machine.read().await.dead_letters.clone();
+    Json(DeadLettersResponse { node_id, dead_letters })
+}
+
+/// Replay the stored events up to each of `at` (ascending), without holding the state machine meanwhile
+async fn replay_states(state: &ApiState, at: &[usize]) -> Result<Vec<StateView>, (StatusCode, String)> {
+    let state_machine = state.storage.read().await.state_machine();
     let sm = state_machine.read().await;
-    let event_count = sm.events.len();
+    let end = at.iter().max().map_or(0, |i| i + 1).min(sm.events.len());
+    let total = sm.events.len();
+    let events = sm.events[..end].to_vec();
+    let (indices, times) = (sm.event_indices.clone(), sm.event_times.clone());
     drop(sm);
 
-    let response = StatusResponse {
+    if let Some(index) = at.iter().find(|i| **i >= total) {
+        return Err((StatusCode::NOT_FOUND, format!("Index {} is past the end of the log ({} events)", index, total)));
+    }
+    timetravel::states_at(at, &events, &indices, &times).map_err(|e| (StatusCode::NOT_FOUND, e))
+}
+
+/// The game state right after the event at `index` (a position, as in GET /events)
+async fn get_state_at(State(state): State<ApiState>, Query(query): Query<StateAtQuery>) -> Result<Json<StateView>, (StatusCode, String)> {
+    let mut views = replay_states(&state, &[query.index]).await?;
+    Ok(Json(views.remove(0)))
+}
+
+/// What changed between the states after events `from` and `to`
+async fn get_state_diff(
+    State(state): State<ApiState>,
+    Query(query): Query<StateDiffQuery>,
+) -> Result<Json<StateDiff>, (StatusCode, String)> {
+    let views = replay_states(&state, &[query.from.min(query.to), query.from.max(query.to)]).await?;
+    let (first, last) = (&views[0], &views[1]);
+    Ok(Json(if query.from <= query.to {
+        StateDiff::between(first, last)
+    } else {
+        StateDiff::between(last, first)
+    }))
+}
+
+fn handicap_status(inbound: &InboundStats) -> HandicapResponse {
+    match inbound.handicap.current() {
+        None => HandicapResponse::default(),
+        Some((config, until)) => HandicapResponse {
+            active: true,
+            loss_pct: config.loss * 100.0,
+            delay_ms: config.latency.as_millis() as u64,
+            remaining_secs: until.saturating_duration_since(std::time::Instant::now()).as_secs(),
+        },
+    }
+}
+
+/// The handicap on this node's responder, if any
+async fn get_handicap(State(state): State<ApiState>) -> Json<HandicapResponse> {
+    Json(handicap_status(&state.inbound))
+}
+
+/// Degrade this node's responder: its attackers see `loss_pct` loss and ACKs `delay_ms` late
+async fn set_handicap(
+    State(state): State<ApiState>,
+    Json(request): Json<HandicapRequest>,
+) -> Result<Json<HandicapResponse>, (StatusCode, String)> {
+    if !(0.0..=100.0).contains(&request.loss_pct) {
+        return Err((StatusCode::BAD_REQUEST, format!("loss_pct must be 0-100, got {}", request.loss_pct)));
+    }
+    let duration = request.duration_secs.unwrap_or(DEFAULT_HANDICAP_SECS);
+    println!(
+        "[NetSim] Handicap: {:.0}% loss, +{}ms ACK delay for {}s",
+        request.loss_pct, request.delay_ms, duration
+    );
+    state.inbound.handicap.set(
+        request.loss_pct / 100.0,
+        std::time::Duration::from_millis(request.delay_ms),
+        std::time::Duration::from_secs(duration),
+    );
+    Ok(Json(handicap_status(&state.inbound)))
+}
+
+/// Lift the handicap early
+async fn clear_handicap(State(state): State<ApiState>) -> Json<HandicapResponse> {
+    println!("[NetSim] Handicap cleared");
+    state.inbound.handicap.clear();
+    Json(handicap_status(&state.inbound))
+}
+
+fn inbound_sources(inbound: &InboundStats) -> Vec<InboundSource> {
+    let mut sources: Vec<InboundSource> = inbound
+        .sources()
+        .into_iter()
+        .map(|(addr, stats)| InboundSource {
+            addr: addr.to_string(),
+            packets: stats.packets,
+            bytes: stats.bytes,
+            idle_ms: stats.last_seen.elapsed().as_millis() as u64,
+        })
+        .collect();
+    sources.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.addr.cmp(&b.addr)));
+    sources
+}
+
+/// Subsystem health - 503 if any subsystem failed or is waiting to restart
+async fn get_health(State(state): State<ApiState>) -> impl IntoResponse {
+    let supervisor = Supervisor::global();
+    let response = HealthResponse {
+        node_id: state.raft.metrics().borrow().id,
+        healthy: supervisor.healthy(),
+        subsystems: supervisor.status(),
+        tasks: supervisor.tasks(),
+    };
+    let status = if response.healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(response))
+}
+
+/// Stream this node's latest shipped snapshot (zstd, see spectate)
+/// The ETag names its index, so a watcher that already has it gets a 304.
+async fn get_spectate_snapshot(State(state): State<ApiState>, headers: HeaderMap) -> Response {
+    let shipped = match state.snapshots.latest().await {
+        Ok(shipped) => shipped,
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, format!("No snapshot to ship: {:#}", e)).into_response(),
+    };
+    let etag = http_cache::etag(&[shipped.index]);
+    let mut response = if http_cache::not_modified(&headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let chunks: Vec<Result<axum::body::Bytes, std::io::Error>> = (0..shipped.bytes.len())
+            .step_by(SNAPSHOT_CHUNK_SIZE)
+            .map(|start| Ok(shipped.bytes.slice(start..(start + SNAPSHOT_CHUNK_SIZE).min(shipped.bytes.len()))))
+            .collect();
+        let mut response = axum::body::Body::from_stream(futures_util::stream::iter(chunks)).into_response();
+        response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(SNAPSHOT_CONTENT_TYPE));
+        response.headers_mut().insert(header::CONTENT_LENGTH, HeaderValue::from(shipped.bytes.len()));
+        response
+    };
+    let headers = response.headers_mut();
+    headers.insert(SNAPSHOT_INDEX_HEADER, HeaderValue::from(shipped.index));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+/// Get the node registry (id→address map) this node believes in
+async fn get_registry(State(state): State<ApiState>) -> impl IntoResponse {
+    let metrics = state.raft.metrics().borrow().clone();
+    let voters: Vec<u64> = metrics.membership_config.membership().voter_ids().collect();
+
+    let mut entries = Vec::new();
+    for (node_id, entry) in state.registry.entries().await {
+        entries.push(RegistryEntryInfo {
+            node_id,
+            resolved: state.registry.resolve(node_id).await.map(|a| a.to_string()),
+            addr: entry.addr,
+            source: entry.source,
+            is_voter: voters.contains(&node_id),
+            coord: entry.coord,
+            capabilities: if node_id == metrics.id {
+                Some(PeerCapabilities::local())
+            } else {
+                state.registry.capabilities(node_id)
+            },
+        });
+    }
+
+    let response = RegistryResponse {
         node_id: metrics.id,
-        is_leader: metrics.current_leader == Some(metrics.id),
-        current_leader: metrics.current_leader,
-        current_term: metrics.current_term,
-        event_count,
+        entries,
     };
 
     (StatusCode::OK, Json(response))
 }
 
+// ============= Grid Handlers =============
+// Stateless - also mounted by the client's API server
+
+/// Largest ring radius and path distance the grid endpoints compute (400 past it)
+/// Both come straight from the query string, and the answer grows with them.
+pub const MAX_GRID_SPAN: u32 = 64;
+
+/// GET /grid/neighbors?coord=q,r[&radius=][&size=] - ring of hexes around a coordinate
+pub async fn grid_neighbors(Query(query): Query<GridNeighborsQuery>) -> Result<Json<GridNeighborsResponse>, (StatusCode, String)> {
+    let center = query.coord;
+    let radius = query.radius.unwrap_or(1);
+    if radius > MAX_GRID_SPAN {
+        return Err((StatusCode::BAD_REQUEST, format!("radius must be at most {}", MAX_GRID_SPAN)));
+    }
+
+    let response = GridNeighborsResponse {
+        center: GridCell::new(center, query.size),
+        radius,
+        cells: center
+            .ring(radius)
+            .into_iter()
+            .map(|c| GridCell::new(c, query.size))
+            .collect(),
+    };
+
+    Ok(Json(response))
+}
+
+/// GET /grid/path?from=q,r&to=q,r[&size=] - straight hex line between two coordinates
+pub async fn grid_path(Query(query): Query<GridPathQuery>) -> Result<Json<GridPathResponse>, (StatusCode, String)> {
+    let (from, to) = (query.from, query.to);
+    let distance = from.distance(&to);
+    if distance > MAX_GRID_SPAN {
+        return Err((StatusCode::BAD_REQUEST, format!("from and to must be at most {} apart", MAX_GRID_SPAN)));
+    }
+
+    let response = GridPathResponse {
+        from,
+        to,
+        distance,
+        path: from
+            .line_to(&to)
+            .into_iter()
+            .map(|c| GridCell::new(c, query.size))
+            .collect(),
+    };
+
+    Ok(Json(response))
+}
+
 // ============= Game Command Handlers =============
 
-/// Handle player joining the game
+/// Handle player joining the game (public API)
+/// Anyone can call this, so the joiner's `raft_node_id` is dropped: committing it
+/// would move that id's address across the whole cluster. Cluster members join on
+/// the internal listener instead.
 async fn handle_join_game(
     State(state): State<ApiState>,
+    peer: ConnectInfo<SocketAddr>,
+    Json(req): Json<JoinGameRequest>,
+) -> impl IntoResponse {
+    join_game(state, peer, JoinGameRequest { raft_node_id: 0, ..req }).await
+}
+
+/// Handle a cluster member joining the game (internal API, token-checked)
+async fn handle_member_join(
+    State(state): State<ApiState>,
+    peer: ConnectInfo<SocketAddr>,
     Json(req): Json<JoinGameRequest>,
 ) -> impl IntoResponse {
+    join_game(state, peer, req).await
+}
+
+async fn join_game(
+    state: ApiState,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: JoinGameRequest,
+) -> (StatusCode, Json<JoinGameResponse>) {
     // Check if this node is the leader
     let metrics = state.raft.metrics().borrow().clone();
     if metrics.current_leader != Some(metrics.id) {
+        let error = RaftWriteError::not_leader(metrics.current_leader, &state.registry).await;
         let response = JoinGameResponse {
             success: false,
-            message: format!("Not the leader. Current leader: {:?}", metrics.current_leader),
+            message: error.to_string(),
             player_id: None,
             capital_coord: None,
+            error: Some(error.info()),
         };
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+        return (error.status(), Json(response));
     }
 
     // Generate player ID from timestamp
@@ -252,10 +1239,31 @@ async fn handle_join_game(
     let sm_arc = storage.state_machine();
     drop(storage);
     let sm = sm_arc.read().await;
+    if let Err(e) = sm.game_state.validate_join(player_id, &req.player_name) {
+        let response = JoinGameResponse {
+            success: false,
+            message: e.to_string(),
+            player_id: None,
+            capital_coord: None,
+            error: None,
+        };
+        return (StatusCode::CONFLICT, Json(response));
+    }
     let player_count = sm.game_state.players.len() as i32;
-    drop(sm);
 
-    let capital_coord = NodeCoord::new(player_count * 3, 0);
+    // A rematch puts returning players back where they started, if the hex is free
+    let reserved = state.reserved_capitals.get(&req.player_name).copied().filter(|coord| {
+        !sm.game_state.nodes.contains_key(coord) && sm.game_state.terrain_at(*coord).is_passable()
+    });
+    let capital_coord = reserved.unwrap_or_else(|| {
+        // Step past any chasm on the spot
+        let mut coord = NodeCoord::new(player_count * 3, 0);
+        while !sm.game_state.terrain_at(coord).is_passable() {
+            coord.q += 1;
+        }
+        coord
+    });
+    drop(sm);
 
     // Create PlayerJoin event
     let timestamp = std::time::SystemTime::now()
@@ -263,16 +1271,18 @@ async fn handle_join_game(
         .unwrap()
         .as_secs();
 
-    let event = GameEvent::PlayerJoin {
+    let event = GameEvent::Command(CommandEvent::PlayerJoin {
         player_id,
         name: req.player_name.clone(),
         capital_coord,
-        node_ip: req.node_ip,
-        is_client: false,  // Worker node, not client
+        capital_ip: Some(req.node_ip),  // The joining worker is the capital
+        client_endpoint: None,
         timestamp,
-    };
+        raft_node_id: req.raft_node_id,
+        reconnect_key: None,
+    });
 
-    let request = GameEventRequest { event };
+    let request = GameEventRequest::with_origin(event, CommandOrigin::new(peer.ip(), Some(player_id)));
 
     match state.raft.client_write(request).await {
         Ok(_) => {
@@ -281,17 +1291,88 @@ async fn handle_join_game(
                 message: format!("Player {} joined successfully", req.player_name),
                 player_id: Some(player_id),
                 capital_coord: Some(capital_coord),
+                error: None,
             };
             (StatusCode::OK, Json(response))
         }
         Err(e) => {
+            let error = RaftWriteError::from_client_write(e, &state.registry).await;
             let response = JoinGameResponse {
                 success: false,
-                message: format!("Failed to join game: {}", e),
+                message: format!("Failed to join game: {}", error),
                 player_id: None,
                 capital_coord: None,
+                error: Some(error.info()),
             };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+            (error.status(), Json(response))
+        }
+    }
+}
+
+/// Take over an initializing coordinate (warm pool workers only)
+/// Commits the NodeInitializationComplete a cold-started node would have sent at
+/// startup, forwarding it to the leader when this node isn't it.
+async fn handle_adopt(
+    State(state): State<ApiState>,
+    Json(req): Json<AdoptRequest>,
+) -> impl IntoResponse {
+    let node_coord = req.coord;
+
+    if let Some(game_id) = req.game_id.as_ref().filter(|g| **g != state.game_id) {
+        let message = format!("This worker is in game {}, not {}", state.game_id, game_id);
+        return (StatusCode::CONFLICT, Json(CommandResponse { success: false, message, error: None }));
+    }
+
+    let storage = state.storage.read().await;
+    let sm_arc = storage.state_machine();
+    drop(storage);
+    let sm = sm_arc.read().await;
+    let conflict = if sm.game_state.node_ips.values().any(|ip| *ip == state.node_host) {
+        Some("This worker already serves a coordinate".to_string())
+    } else if !sm.game_state.nodes.get(&node_coord).is_some_and(|n| n.init_state == NodeInitState::Initializing) {
+        Some(format!("Node {:?} isn't waiting for a worker", node_coord))
+    } else {
+        None
+    };
+    drop(sm);
+
+    if let Some(message) = conflict {
+        return (StatusCode::CONFLICT, Json(CommandResponse { success: false, message, error: None }));
+    }
+
+    let metrics = state.raft.metrics().borrow().clone();
+    let event = GameEvent::Fact(FactEvent::NodeInitializationComplete {
+        node_coord,
+        node_ip: state.node_host.clone(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        raft_node_id: metrics.id,
+    });
+
+    match crate::raft::commit_via_leader(&state.raft, &state.registry, event).await {
+        Ok(()) => {
+            println!("[API] Adopted {:?}", node_coord);
+            // A restart takes the coordinate back with the identity
+            let identity = NodeIdentity { node_id: metrics.id, game_id: state.game_id.clone(), coord: Some(node_coord) };
+            identity.remember().await;
+            let response = CommandResponse {
+                success: true,
+                message: format!("Adopted {:?}", node_coord),
+                error: None,
+            };
+            (StatusCode::OK, Json(response))
+        }
+        Err(e) => {
+            let error = e.downcast_ref::<RaftWriteError>();
+            let status = error.map_or(StatusCode::INTERNAL_SERVER_ERROR, RaftWriteError::status);
+            let response = CommandResponse {
+                success: false,
+                message: format!("Failed to adopt {:?}: {:#}", node_coord, e),
+                error: error.map(RaftWriteError::info),
+            };
+            (status, Json(response))
         }
     }
 }
@@ -299,16 +1380,19 @@ async fn handle_join_game(
 /// Handle attack command
 async fn handle_attack_command(
     State(state): State<ApiState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     Json(req): Json<AttackRequest>,
 ) -> impl IntoResponse {
     // Check if this node is the leader
     let metrics = state.raft.metrics().borrow().clone();
     if metrics.current_leader != Some(metrics.id) {
+        let error = RaftWriteError::not_leader(metrics.current_leader, &state.registry).await;
         let response = CommandResponse {
             success: false,
-            message: format!("Not the leader. Current leader: {:?}", metrics.current_leader),
+            message: error.to_string(),
+            error: Some(error.info()),
         };
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+        return (error.status(), Json(response));
     }
 
     // Validate: check if nodes exist and are neighbors
@@ -317,110 +1401,54 @@ async fn handle_attack_command(
     drop(storage);
     let sm = sm_arc.read().await;
 
-    if !sm.game_state.nodes.contains_key(&req.node_coord) {
-        drop(sm);
-        let response = CommandResponse {
-            success: false,
-            message: format!("Attacker node {:?} does not exist", req.node_coord),
-        };
-        return (StatusCode::BAD_REQUEST, Json(response));
-    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
 
-    // Check if they're neighbors
-    if !req.node_coord.is_adjacent(&req.target_coord) {
-        drop(sm);
+    // Node must exist, be adjacent to the target, and the target can't be a
+    // spawn-protected capital (ownership isn't checked here)
+    let valid = sm.game_state.validate_attack(None, req.node_coord, req.target_coord, timestamp);
+    let owner_id = sm.game_state.nodes.get(&req.node_coord).map(|n| n.owner_id);
+    drop(sm);
+    if let Err(e) = valid {
         let response = CommandResponse {
             success: false,
-            message: "Nodes are not adjacent".to_string(),
+            message: e.to_string(),
+            error: None,
         };
         return (StatusCode::BAD_REQUEST, Json(response));
     }
 
-    // If target doesn't exist, trigger lazy initialization
-    if !sm.game_state.nodes.contains_key(&req.target_coord) {
-        println!("[Lazy Init] Target {:?} doesn't exist, initializing neighbors...", req.target_coord);
-
-        // Get all 6 neighbors of the target
-        let neighbors = req.target_coord.neighbors();
-        let mut nodes_to_init = Vec::new();
-
-        // Find neighbors that don't exist in game state
-        for neighbor in &neighbors {
-            if !sm.game_state.nodes.contains_key(neighbor) {
-                nodes_to_init.push(*neighbor);
-            }
-        }
-
-        println!("[Lazy Init] Found {} uninitialized neighbors to spawn", nodes_to_init.len());
-        drop(sm);
-
-        // Submit NodeInitializationStarted events for all neighbors to init
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        for coord in &nodes_to_init {
-            let event = GameEvent::NodeInitializationStarted {
-                node_coord: *coord,
-                owner_id: 0,  // Neutral/unowned
-                timestamp,
-            };
-            let request = GameEventRequest { event };
-
-            // Submit event (fire and forget, errors logged)
-            if let Err(e) = state.raft.client_write(request).await {
-                eprintln!("[Lazy Init] Failed to submit initialization event for {:?}: {}", coord, e);
-            } else {
-                println!("[Lazy Init] Submitted initialization event for {:?}", coord);
-            }
-        }
-
-        // Call master API to spawn EC2 instances (async, don't wait)
-        let master_url = state.master_url.clone();
-        let game_id = state.game_id.clone();
-        tokio::spawn(async move {
-            for coord in nodes_to_init {
-                println!("[Lazy Init] Spawning EC2 instance for {:?}...", coord);
-                if let Err(e) = spawn_node_on_master(&master_url, &game_id, coord.q, coord.r, false).await {
-                    eprintln!("[Lazy Init] Failed to spawn node {:?}: {}", coord, e);
-                } else {
-                    println!("[Lazy Init] Successfully triggered spawn for {:?}", coord);
-                }
-            }
-        });
-    } else {
-        drop(sm);
-    }
+    // Missing targets are initialized by the leader's GameLogic::tick once this commits
 
     // Create SetNodeTarget event
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
 
-    let event = GameEvent::SetNodeTarget {
+    let event = GameEvent::Command(CommandEvent::SetNodeTarget {
         node_coord: req.node_coord,
         target: Some(AttackTarget::Coordinate(req.target_coord)),
         timestamp,
-    };
+    });
 
-    let request = GameEventRequest { event };
+    let request = GameEventRequest::with_origin(event, CommandOrigin::new(peer.ip(), owner_id));
 
     match state.raft.client_write(request).await {
         Ok(_) => {
             let response = CommandResponse {
                 success: true,
                 message: format!("Node {:?} now attacking {:?}", req.node_coord, req.target_coord),
+                error: None,
             };
             (StatusCode::OK, Json(response))
         }
         Err(e) => {
+            let error = RaftWriteError::from_client_write(e, &state.registry).await;
             let response = CommandResponse {
                 success: false,
-                message: format!("Failed to set attack: {}", e),
+                message: format!("Failed to set attack: {}", error),
+                error: Some(error.info()),
             };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+            (error.status(), Json(response))
         }
     }
 }
@@ -428,16 +1456,19 @@ async fn handle_attack_command(
 /// Handle stop attack command
 async fn handle_stop_attack(
     State(state): State<ApiState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     Json(req): Json<StopAttackRequest>,
 ) -> impl IntoResponse {
     // Check if this node is the leader
     let metrics = state.raft.metrics().borrow().clone();
     if metrics.current_leader != Some(metrics.id) {
+        let error = RaftWriteError::not_leader(metrics.current_leader, &state.registry).await;
         let response = CommandResponse {
             success: false,
-            message: format!("Not the leader. Current leader: {:?}", metrics.current_leader),
+            message: error.to_string(),
+            error: Some(error.info()),
         };
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+        return (error.status(), Json(response));
     }
 
     let timestamp = std::time::SystemTime::now()
@@ -445,95 +1476,177 @@ async fn handle_stop_attack(
         .unwrap()
         .as_secs();
 
-    let event = GameEvent::SetNodeTarget {
+    let event = GameEvent::Command(CommandEvent::SetNodeTarget {
         node_coord: req.node_coord,
         target: None,
         timestamp,
-    };
+    });
 
-    let request = GameEventRequest { event };
+    let storage = state.storage.read().await;
+    let sm_arc = storage.state_machine();
+    drop(storage);
+    let owner_id = audit::command_player(&event, &sm_arc.read().await.game_state);
+    let request = GameEventRequest::with_origin(event, CommandOrigin::new(peer.ip(), owner_id));
 
     match state.raft.client_write(request).await {
         Ok(_) => {
             let response = CommandResponse {
                 success: true,
                 message: format!("Node {:?} stopped attacking", req.node_coord),
+                error: None,
             };
             (StatusCode::OK, Json(response))
         }
         Err(e) => {
+            let error = RaftWriteError::from_client_write(e, &state.registry).await;
             let response = CommandResponse {
                 success: false,
-                message: format!("Failed to stop attack: {}", e),
+                message: format!("Failed to stop attack: {}", error),
+                error: Some(error.info()),
             };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
+            (error.status(), Json(response))
         }
     }
 }
 
 /// Get current game state
-async fn handle_get_game_state(State(state): State<ApiState>) -> impl IntoResponse {
-    let storage = state.storage.read().await;
-    let sm_arc = storage.state_machine();
-    drop(storage);
-    let sm = sm_arc.read().await;
+/// Top-level fields GET /game/state can be trimmed to (`?fields=`)
+pub const GAME_STATE_FIELDS: [&str; 6] = ["players", "nodes", "total_events", "terrain", "phase", "features"];
 
-    let players: Vec<PlayerInfo> = sm
-        .game_state
-        .players
-        .values()
-        .map(|p| {
-            let node_count = sm
-                .game_state
-                .nodes
-                .values()
-                .filter(|n| n.owner_id == p.player_id)
-                .count();
-
-            PlayerInfo {
-                player_id: p.player_id,
-                name: p.name.clone(),
-                capital_coord: p.capital_coord,
-                alive: p.alive,
-                node_count,
-            }
-        })
-        .collect();
+async fn handle_get_game_state(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(query): Query<FieldsQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let fields = query.parse(&GAME_STATE_FIELDS).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    // Metrics come from the telemetry plane; committed reports only in older logs
+    let live = state.metrics_view.fresh().await;
+    let metrics_version = state.metrics_view.version();
+    let sm = state.reads.latest();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
 
-    let nodes: Vec<NodeInfo> = sm
-        .game_state
-        .nodes
-        .values()
-        .map(|n| {
-            // Get metrics for this node if available
-            let metrics = sm.game_state.node_metrics.get(&n.coord);
-
-            NodeInfo {
-                coord: n.coord,
-                owner_id: n.owner_id,
-                current_target: n.current_target,
-                bandwidth_in: metrics.map(|m| m.bandwidth_in),
-                packet_loss: metrics.map(|m| m.packet_loss),
-            }
-        })
-        .collect();
+    // Node metrics change between commits, and so does a spawn protection countdown
+    let counting_down = fields.has("players") && sm.game_state.players.values().any(|p| p.spawn_protection_remaining(now).is_some());
+    let etag = http_cache::etag(&[
+        sm.game_state.last_applied_log_index,
+        if fields.has("nodes") { metrics_version } else { 0 },
+        if counting_down { now } else { 0 },
+    ]);
+
+    Ok(http_cache::respond(&headers, etag, &fields, || {
+        let players: Vec<PlayerInfo> = sm
+            .game_state
+            .players
+            .values()
+            .map(|p| {
+                let node_count = sm
+                    .game_state
+                    .nodes
+                    .values()
+                    .filter(|n| n.owner_id == p.player_id)
+                    .count();
+
+                PlayerInfo {
+                    player_id: p.player_id,
+                    name: p.name.clone(),
+                    capital_coord: p.capital_coord,
+                    alive: p.alive,
+                    node_count,
+                    contested_until: sm.game_state.capital_contest(p.player_id).map(|(_, c)| c.expires_at),
+                    spawn_protection_secs: p.spawn_protection_remaining(now),
+                    idle: p.idle_since.is_some(),
+                }
+            })
+            .collect();
+
+        let nodes: Vec<NodeInfo> = sm
+            .game_state
+            .nodes
+            .values()
+            .map(|n| {
+                // Get metrics for this node if available
+                let metrics = match live.get(&n.coord) {
+                    Some(sample) => Some((sample.bandwidth_in, sample.packet_loss)),
+                    None => sm.game_state.node_metrics.get(&n.coord).map(|m| (m.bandwidth_in, m.packet_loss)),
+                };
+
+                NodeInfo {
+                    coord: n.coord,
+                    owner_id: n.owner_id,
+                    current_target: n.current_target,
+                    bandwidth_in: metrics.map(|(bandwidth_in, _)| bandwidth_in),
+                    packet_loss: metrics.map(|(_, packet_loss)| packet_loss),
+                    capture_multiplier: sm.game_state.capture_boosts.get(&n.coord).map(|b| b.multiplier),
+                    cpu_utilization: live.get(&n.coord).and_then(|s| s.host_load).map(|load| load.cpu_utilization),
+                    memory_pressure: live.get(&n.coord).and_then(|s| s.host_load).map(|load| load.memory_pressure),
+                }
+            })
+            .collect();
+
+        let terrain = sm
+            .game_state
+            .terrain
+            .iter()
+            .map(|(coord, terrain)| TerrainTile { coord: *coord, terrain: *terrain })
+            .collect();
+
+        GameStateResponse {
+            players,
+            nodes,
+            total_events: sm.event_count,
+            terrain,
+            phase: sm.game_state.phase,
+            features: sm.game_state.features(),
+        }
+    }))
+}
 
-    let total_events = sm.events.len();
+/// Recent metrics for one node, for graphing bandwidth and loss over time
+/// The window is measured back from now; the series is what this node's metrics
+/// view has seen, and samples older than the history buffer are gone
+async fn handle_get_metrics(
+    State(state): State<ApiState>,
+    Query(query): Query<MetricsQuery>,
+) -> impl IntoResponse {
+    let coord = query.coord;
+    let window_secs = query.window.unwrap_or(300);
 
-    drop(sm);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let samples = state.metrics_view.series(&coord, now.saturating_sub(window_secs)).await;
 
-    let response = GameStateResponse {
-        players,
-        nodes,
-        total_events,
+    let response = MetricsSeriesResponse {
+        coord,
+        window_secs,
+        samples,
     };
 
     (StatusCode::OK, Json(response))
 }
 
+/// The game's awards from the committed log and this node's metrics history
+/// The log is copied out first, so the replay doesn't hold up applying.
+pub async fn game_awards(state_machine: &tokio::sync::RwLock<GameStateMachine>, metrics_view: &MetricsView) -> Awards {
+    let sm = state_machine.read().await;
+    let (events, times) = (sm.events.clone(), sm.event_times.clone());
+    drop(sm);
+    let samples = metrics_view.history().await;
+    Awards::compute(&events, &times, &samples)
+}
+
+async fn handle_get_awards(State(state): State<ApiState>) -> Json<Awards> {
+    let state_machine = state.storage.read().await.state_machine();
+    Json(game_awards(&state_machine, &state.metrics_view).await)
+}
+
 /// Handle WebSocket upgrade for attack connections
 async fn handle_attack(ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(|socket| handle_attack_websocket_axum(socket))
+    ws.on_upgrade(handle_attack_websocket_axum)
 }
 
 /// Handle WebSocket attack connection (Axum-specific)
@@ -546,17 +1659,8 @@ async fn handle_attack_websocket_axum(socket: axum::extract::ws::WebSocket) {
     let (mut sender, _receiver) = socket.split();
     let flood_data = vec![0u8; 1024]; // 1KB per message
 
-    loop {
-        // Send data as fast as possible - true flooding
-        match sender.send(Message::Binary(flood_data.clone())).await {
-            Ok(_) => {
-                // Data sent successfully, continue flooding
-            }
-            Err(_) => {
-                break;
-            }
-        }
-    }
+    // Send data as fast as possible - true flooding
+    while sender.send(Message::Binary(flood_data.clone())).await.is_ok() {}
 
     println!("[Network] Attack WebSocket connection closed");
 }
@@ -565,16 +1669,21 @@ async fn handle_attack_websocket_axum(socket: axum::extract::ws::WebSocket) {
 pub async fn spawn_node_on_master(
     master_url: &str,
     game_id: &str,
-    q: i32,
-    r: i32,
+    coord: NodeCoord,
     is_capital: bool,
+    capacity_multiplier: f32,
+    tier: SpawnTier,
 ) -> Result<()> {
     #[derive(Serialize)]
     struct SpawnSingleNodeRequest {
         game_id: String,
         is_capital: bool,
-        q: i32,
-        r: i32,
+        coord: NodeCoord,
+        /// Terrain hint: how much capacity the hex's node should have (1.0 = regular)
+        /// Kept for masters from before `tier`, which size by it alone.
+        capacity_multiplier: f32,
+        /// Task size the rules pick for the hex (GameState::spawn_tier)
+        tier: SpawnTier,
     }
 
     let client = reqwest::Client::new();
@@ -582,12 +1691,14 @@ pub async fn spawn_node_on_master(
     let body = SpawnSingleNodeRequest {
         game_id: game_id.to_string(),
         is_capital,
-        q,
-        r,
+        coord,
+        capacity_multiplier,
+        tier,
     };
 
     let response = client
         .post(&url)
+        .request_id()
         .json(&body)
         .send()
         .await?;
@@ -599,28 +1710,204 @@ pub async fn spawn_node_on_master(
     }
 }
 
-/// Start the HTTP API server
+/// Where the API servers listen and what this worker tells the master about itself
+#[derive(Debug, Clone)]
+pub struct ApiServerConfig {
+    /// Public listener address (host:port)
+    pub addr: String,
+    pub internal: InternalApiConfig,
+    pub master_url: String,
+    pub game_id: String,
+    /// This worker's address as its peers and the master see it
+    pub node_host: String,
+}
+
+/// Start the HTTP API servers: public on `config.addr`, cluster-internal per `config.internal`
 pub async fn start_api_server(
     raft: Arc<Raft<GameRaftTypeConfig>>,
     storage: Arc<tokio::sync::RwLock<crate::raft::storage::MemStorage>>,
-    addr: String,
-    master_url: String,
-    game_id: String,
+    registry: NodeRegistry,
+    metrics_view: MetricsView,
+    inbound: InboundStats,
+    config: ApiServerConfig,
 ) -> Result<()> {
-    let state = ApiState { raft, storage, master_url, game_id };
-    let app = create_router(state);
-
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    println!("HTTP API server listening on {}", addr);
-
-    axum::serve(listener, app).await?;
-
+    let ApiServerConfig { addr, internal, master_url, game_id, node_host } = config;
+    let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig::from_env()));
+    let reserved_capitals = std::env::var("CAPITAL_PLACEMENTS")
+        .map(|value| crate::game::summary::parse_capital_placements(&value))
+        .unwrap_or_default();
+    let snapshots = SnapshotShipper::new(storage.read().await.clone_storage());
+    // Refreshes for as long as this run of the server, not past a restart
+    let refresh = Supervisor::global().spawn_transient("spectate_refresh", snapshots.clone().run().map(Ok));
+    let _refresh = AbortOnDrop(refresh.abort_handle());
+    let reads = ReadView::new(storage.read().await.clone_storage()).await;
+    let copy = Supervisor::global().spawn_transient("read_view", reads.clone().run().map(Ok));
+    let _copy = AbortOnDrop(copy.abort_handle());
+    if internal.token.is_none() {
+        eprintln!("[API] INTERNAL_API_TOKEN not set, internal API on {} is unauthenticated", internal.addr());
+    }
+    let state = ApiState {
+        raft,
+        storage,
+        registry,
+        master_url,
+        game_id,
+        node_host,
+        rate_limiter,
+        reserved_capitals: Arc::new(reserved_capitals),
+        referee_token: std::env::var("REFEREE_TOKEN").ok().filter(|t| !t.is_empty()).map(Arc::from),
+        metrics_view,
+        inbound,
+        internal_token: internal.token.clone(),
+        cors: Arc::new(CorsPolicy::from_env()),
+        snapshots,
+        reads,
+    };
+    let public = create_router(state.clone());
+    let internal_app = create_internal_router(state);
+    let internal_addr = internal.addr();
+
+    tokio::try_join!(
+        crate::tls::serve(&addr, public),
+        crate::tls::serve_peers(&internal_addr, internal_app),
+    )?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::TelemetryEvent;
+    use crate::raft::storage::MemStorage;
+    use crate::raft::RaftNode;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    const LEADER_ADDR: &str = "10.0.0.1:5000";
+
+    /// ApiState over a one-node cluster, led by node 1 at LEADER_ADDR
+    async fn leader_state() -> ApiState {
+        let registry = NodeRegistry::new();
+        registry.register(1, LEADER_ADDR.to_string()).await;
+        let node = RaftNode::with_storage(1, registry.clone(), MemStorage::with_registry(registry.clone())).await.unwrap();
+        node.raft.initialize(BTreeMap::from([(1, ())])).await.unwrap();
+        node.raft.wait(Some(Duration::from_secs(5))).current_leader(1, "leader").await.unwrap();
+
+        let storage = node.storage.read().await.clone_storage();
+        ApiState {
+            raft: node.raft.clone(),
+            storage: node.storage.clone(),
+            registry,
+            master_url: String::new(),
+            game_id: "test".to_string(),
+            node_host: "10.0.0.1".to_string(),
+            rate_limiter: Arc::new(RateLimiter::new(RateLimitConfig::default())),
+            reserved_capitals: Arc::default(),
+            referee_token: None,
+            metrics_view: node.metrics_view.clone(),
+            inbound: InboundStats::default(),
+            internal_token: None,
+            cors: Arc::new(CorsPolicy::parse("")),
+            snapshots: SnapshotShipper::new(storage.clone_storage()),
+            reads: ReadView::new(storage).await,
+        }
+    }
+
+    fn peer() -> ConnectInfo<SocketAddr> {
+        ConnectInfo(SocketAddr::from(([10, 0, 0, 9], 40000)))
+    }
+
+    #[tokio::test]
+    async fn test_join_registers_the_joiner_not_the_leader() {
+        let state = leader_state().await;
+        let req = JoinGameRequest { player_name: "alice".to_string(), node_ip: "10.0.0.2".to_string(), raft_node_id: 2 };
+        let response = handle_member_join(State(state.clone()), peer(), Json(req)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert_eq!(state.registry.get_address(1).await.as_deref(), Some(LEADER_ADDR));
+        assert_eq!(state.registry.get_address(2).await.as_deref(), Some("10.0.0.2:5000"));
+
+        // A joiner outside the cluster (the frontend) registers nothing
+        let req = JoinGameRequest { player_name: "bob".to_string(), node_ip: "0.0.0.0".to_string(), raft_node_id: 0 };
+        let response = handle_join_game(State(state.clone()), peer(), Json(req)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.registry.get_address(1).await.as_deref(), Some(LEADER_ADDR));
+        state.raft.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_public_join_cannot_move_a_node() {
+        let state = leader_state().await;
+        let req = JoinGameRequest { player_name: "alice".to_string(), node_ip: "10.0.0.2".to_string(), raft_node_id: 2 };
+        let response = handle_member_join(State(state.clone()), peer(), Json(req)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for (name, raft_node_id) in [("mallory", 2), ("trudy", 1)] {
+            let req = JoinGameRequest { player_name: name.to_string(), node_ip: "203.0.113.7".to_string(), raft_node_id };
+            let response = handle_join_game(State(state.clone()), peer(), Json(req)).await.into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(state.registry.get_address(1).await.as_deref(), Some(LEADER_ADDR));
+        assert_eq!(state.registry.get_address(2).await.as_deref(), Some("10.0.0.2:5000"));
+        state.raft.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_override_needs_the_referee_token() {
+        let mut state = leader_state().await;
+        let override_with = |state: &ApiState, token: Option<&str>, player_id: u64| {
+            let mut headers = HeaderMap::new();
+            if let Some(token) = token {
+                headers.insert(axum::http::header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+            }
+            let req = RefereeOverrideRequest { action: RefereeOverride::ExtendTime { player_id, secs: 30 }, reason: "lag".to_string() };
+            handle_referee_override(State(state.clone()), headers, Json(req))
+        };
+
+        // No referee for this game at all
+        assert_eq!(override_with(&state, Some("ref"), 1).await.into_response().status(), StatusCode::NOT_FOUND);
+
+        state.referee_token = Some(Arc::from("ref"));
+        let req = JoinGameRequest { player_name: "alice".to_string(), node_ip: "10.0.0.2".to_string(), raft_node_id: 0 };
+        let response = handle_join_game(State(state.clone()), peer(), Json(req)).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let alice = serde_json::from_slice::<JoinGameResponse>(&body).unwrap().player_id.unwrap();
+
+        for wrong in [None, Some("re"), Some("fer"), Some("REF")] {
+            assert_eq!(override_with(&state, wrong, alice).await.into_response().status(), StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(override_with(&state, Some("ref"), alice).await.into_response().status(), StatusCode::OK);
+        state.raft.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_game_state_flags_idle_players() {
+        let state = leader_state().await;
+        let req = JoinGameRequest { player_name: "alice".to_string(), node_ip: "10.0.0.2".to_string(), raft_node_id: 0 };
+        let response = handle_join_game(State(state.clone()), peer(), Json(req)).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let alice = serde_json::from_slice::<JoinGameResponse>(&body).unwrap().player_id.unwrap();
+
+        let commit_then_read = |event: GameEvent| {
+            let state = state.clone();
+            async move {
+                let response = submit_event(State(state.clone()), peer(), Json(SubmitEventRequest { event })).await.into_response();
+                assert_eq!(response.status(), StatusCode::OK);
+                state.reads.refresh().await;
+                let response = handle_get_game_state(State(state.clone()), HeaderMap::new(), Query(FieldsQuery::default())).await.unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let game = serde_json::from_slice::<GameStateResponse>(&body).unwrap();
+                game.players.iter().find(|p| p.player_id == alice).unwrap().idle
+            }
+        };
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        let went_idle = FactEvent::OwnerIdle { player_id: alice, neutralize: false, timestamp: now };
+        assert!(commit_then_read(went_idle.into()).await);
+        let heartbeat = TelemetryEvent::ClientHeartbeat { player_id: alice, timestamp: now + 1 };
+        assert!(!commit_then_read(heartbeat.into()).await);
+        state.raft.shutdown().await.unwrap();
+    }
 
     #[test]
     fn test_response_serialization() {
@@ -628,6 +1915,7 @@ mod tests {
             success: true,
             message: "Event committed".to_string(),
             log_index: Some(42),
+            error: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -635,6 +1923,71 @@ mod tests {
         assert!(json.contains("\"log_index\":42"));
     }
 
+    #[test]
+    fn test_events_pages_and_filters() {
+        let events: Vec<GameEvent> = (0..10)
+            .map(|i| {
+                if i % 2 == 0 {
+                    GameEvent::from(FactEvent::NodeCaptured { node_coord: NodeCoord::new(i, 0), new_owner_id: 1, timestamp: 0 })
+                } else {
+                    GameEvent::from(CommandEvent::SetNodeTarget { node_coord: NodeCoord::new(i, 0), target: None, timestamp: 0 })
+                }
+            })
+            .collect();
+        let times: Vec<u64> = (100..110).collect();
+
+        // Walk the whole log three events at a time
+        let mut query = EventsQuery { limit: Some(3), ..Default::default() };
+        let mut seen = Vec::new();
+        loop {
+            let page = query.page(&events, &times);
+            assert_eq!(page.total, 10);
+            seen.extend(page.indices.iter().copied());
+            match page.next_after_index {
+                Some(cursor) => query.after_index = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+
+        let query = EventsQuery { event_type: Some("NodeCaptured".to_string()), after_index: Some(3), ..Default::default() };
+        let page = query.page(&events, &times);
+        assert_eq!(page.indices, vec![4, 6, 8]);
+        assert_eq!(page.event_times, vec![104, 106, 108]);
+        assert_eq!(page.next_after_index, None);
+
+        let query = EventsQuery { event_type: Some("command".to_string()), coord: Some(NodeCoord::new(3, 0)), ..Default::default() };
+        assert_eq!(query.page(&events, &times).indices, vec![3]);
+        let query = EventsQuery { player: Some(1), limit: Some(2), ..Default::default() };
+        let page = query.page(&events, &times);
+        assert_eq!((page.indices, page.next_after_index), (vec![0, 2], Some(2)));
+    }
+
+    #[test]
+    fn test_metrics_query_takes_either_coord_form() {
+        for uri in ["/game/metrics?coord=2,-1&window=60", "/game/metrics?q=2&r=-1&window=60"] {
+            let Query(query) = Query::<MetricsQuery>::try_from_uri(&uri.parse().unwrap()).unwrap();
+            assert_eq!((query.coord, query.window), (NodeCoord::new(2, -1), Some(60)));
+        }
+        assert!(Query::<MetricsQuery>::try_from_uri(&"/game/metrics?coord=2".parse().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grid_queries_are_capped() {
+        let neighbors = |uri: &str| grid_neighbors(Query::try_from_uri(&uri.parse().unwrap()).unwrap());
+        assert_eq!(neighbors("/grid/neighbors?coord=0,0&radius=64").await.unwrap().cells.len(), 6 * 64);
+        for radius in [65, u32::MAX] {
+            let uri = format!("/grid/neighbors?coord=0,0&radius={}", radius);
+            assert_eq!(neighbors(&uri).await.unwrap_err().0, StatusCode::BAD_REQUEST);
+        }
+
+        let path = |uri: &str| grid_path(Query::try_from_uri(&uri.parse().unwrap()).unwrap());
+        assert_eq!(path("/grid/path?from=0,0&to=64,0").await.unwrap().path.len(), 65);
+        assert_eq!(path("/grid/path?from=0,0&to=65,0").await.unwrap_err().0, StatusCode::BAD_REQUEST);
+        let far = format!("/grid/path?from={},0&to={},0", i32::MIN, i32::MAX);
+        assert_eq!(path(&far).await.unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_status_response() {
         let status = StatusResponse {
@@ -643,6 +1996,9 @@ mod tests {
             current_leader: Some(1),
             current_term: 5,
             event_count: 100,
+            rate_limit: RateLimitStats::default(),
+            udp_sources: Vec::new(),
+            state_digest: DigestStatus::default(),
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -0,0 +1,161 @@
+use crate::raft::conversions::proto::raft_service_client::RaftServiceClient;
+use crate::raft::conversions::proto::{FetchSnapshotRequest, SnapshotChunk};
+use crate::raft::storage::NodeId;
+use anyhow::{Context, Result};
+use openraft::SnapshotMeta;
+
+/// Bytes of snapshot data per streamed chunk
+pub const SNAPSHOT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// gRPC metadata key a follower uses to point a joiner at the leader
+pub const LEADER_ADDR_METADATA: &str = "x-raft-leader";
+
+/// Whether joining nodes fetch a snapshot before starting Raft
+/// RAFT_HOT_BOOTSTRAP (default: true)
+pub fn hot_bootstrap_enabled() -> bool {
+    std::env::var("RAFT_HOT_BOOTSTRAP")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Split a serialized snapshot into stream chunks (meta rides on the first one)
+pub fn split_snapshot(meta: Vec<u8>, data: &[u8], chunk_size: usize) -> Vec<SnapshotChunk> {
+    let mut chunks: Vec<SnapshotChunk> = data
+        .chunks(chunk_size.max(1))
+        .map(|part| SnapshotChunk {
+            meta: Vec::new(),
+            data: part.to_vec(),
+            done: false,
+        })
+        .collect();
+
+    // An empty snapshot still needs one chunk to carry the meta
+    if chunks.is_empty() {
+        chunks.push(SnapshotChunk { meta: Vec::new(), data: Vec::new(), done: false });
+    }
+    chunks[0].meta = meta;
+    if let Some(last) = chunks.last_mut() {
+        last.done = true;
+    }
+    chunks
+}
+
+/// Reassemble streamed chunks into the snapshot meta and data
+pub fn assemble_snapshot(chunks: Vec<SnapshotChunk>) -> Result<(SnapshotMeta<NodeId, ()>, Vec<u8>)> {
+    let mut meta = None;
+    let mut data = Vec::new();
+    let mut done = false;
+
+    for chunk in chunks {
+        if meta.is_none() {
+            meta = Some(bincode::deserialize(&chunk.meta).context("Invalid snapshot meta")?);
+        }
+        data.extend_from_slice(&chunk.data);
+        done = chunk.done;
+    }
+
+    if !done {
+        anyhow::bail!("Snapshot stream ended before the last chunk");
+    }
+    let meta = meta.context("Empty snapshot stream")?;
+    Ok((meta, data))
+}
+
+/// Fetch the latest snapshot from the leader, starting at `addr`
+/// A follower answers with the leader's address, which is tried once
+pub async fn fetch_snapshot(addr: &str, requester_id: NodeId) -> Result<(SnapshotMeta<NodeId, ()>, Vec<u8>)> {
+    match fetch_from(addr, requester_id).await {
+        Err(FetchError::Redirect(leader_addr)) => {
+            println!("[Bootstrap] {} is not the leader, fetching from {}", addr, leader_addr);
+            fetch_from(&leader_addr, requester_id).await.map_err(FetchError::into_anyhow)
+        }
+        result => result.map_err(FetchError::into_anyhow),
+    }
+}
+
+enum FetchError {
+    /// Peer isn't the leader - retry at this address
+    Redirect(String),
+    Other(anyhow::Error),
+}
+
+impl FetchError {
+    fn into_anyhow(self) -> anyhow::Error {
+        match self {
+            FetchError::Redirect(addr) => anyhow::anyhow!("Redirected again (to {})", addr),
+            FetchError::Other(e) => e,
+        }
+    }
+}
+
+async fn fetch_from(addr: &str, requester_id: NodeId) -> Result<(SnapshotMeta<NodeId, ()>, Vec<u8>), FetchError> {
+    let mut client = RaftServiceClient::connect(format!("http://{}", addr))
+        .await
+        .map_err(|e| FetchError::Other(anyhow::anyhow!("Failed to connect to {}: {}", addr, e)))?;
+
+    let mut stream = match client.fetch_snapshot(FetchSnapshotRequest { requester_id }).await {
+        Ok(response) => response.into_inner(),
+        Err(status) => {
+            let leader = status
+                .metadata()
+                .get(LEADER_ADDR_METADATA)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            return Err(match leader {
+                Some(leader_addr) if status.code() == tonic::Code::FailedPrecondition => FetchError::Redirect(leader_addr),
+                _ => FetchError::Other(anyhow::anyhow!("FetchSnapshot failed: {}", status)),
+            });
+        }
+    };
+
+    let mut chunks = Vec::new();
+    while let Some(chunk) = stream
+        .message()
+        .await
+        .map_err(|e| FetchError::Other(anyhow::anyhow!("Snapshot stream failed: {}", e)))?
+    {
+        chunks.push(chunk);
+    }
+
+    assemble_snapshot(chunks).map_err(FetchError::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openraft::{CommittedLeaderId, LogId};
+
+    fn meta_at(index: u64) -> SnapshotMeta<NodeId, ()> {
+        SnapshotMeta {
+            last_log_id: Some(LogId::new(CommittedLeaderId::new(3, 1), index)),
+            last_membership: Default::default(),
+            snapshot_id: format!("snapshot-{}", index),
+        }
+    }
+
+    #[test]
+    fn test_split_and_assemble_roundtrip() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let chunks = split_snapshot(bincode::serialize(&meta_at(42)).unwrap(), &data, 300);
+
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks[1..].iter().all(|c| c.meta.is_empty()));
+        assert!(chunks.last().unwrap().done);
+
+        let (meta, assembled) = assemble_snapshot(chunks).unwrap();
+        assert_eq!(meta, meta_at(42));
+        assert_eq!(assembled, data);
+    }
+
+    #[test]
+    fn test_empty_snapshot_and_truncated_stream() {
+        let chunks = split_snapshot(bincode::serialize(&meta_at(0)).unwrap(), &[], 300);
+        assert_eq!(chunks.len(), 1);
+        assert!(assemble_snapshot(chunks).unwrap().1.is_empty());
+
+        let mut chunks = split_snapshot(bincode::serialize(&meta_at(7)).unwrap(), &[1; 600], 300);
+        chunks.pop();
+        assert!(assemble_snapshot(chunks).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// Capability negotiation between workers - the Hello RPC
+//
+// A rolling upgrade runs two builds side by side, and a newer one mustn't send an
+// older one something it can't read. When a node opens a gRPC channel to a peer it
+// says Hello with its build version, the proto features it supports (chunked
+// snapshots, compression codecs) and its capacity; the peer records that and answers
+// with its own. Both sides keep what they learned in the NodeRegistry until the peer
+// moves or is retired (a restarted node may be another build).
+//
+// A build older than Hello answers UNIMPLEMENTED and is recorded as legacy: nothing is
+// assumed of it beyond what it advertises on its own (AppendEntries codecs).
+//
+// Each build also says which cluster version it applies (CLUSTER_VERSION). Once every
+// member says it applies a newer one than the committed, the leader commits it
+// (ClusterVersionSet), and only then proposes event variants that need it - so a
+// follower still on the old image never meets a variant it can't decode.
+
+use crate::game::events::CLUSTER_VERSION;
+use crate::raft::compression::{self, parse_codec};
+use crate::raft::conversions::proto::{Capabilities as ProtoCapabilities, Compression};
+use crate::raft::node_registry::NodeRegistry;
+use crate::raft::storage::NodeId;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// This worker's build version
+pub const BUILD_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long a peer may take to answer Hello before the channel is used without it
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What a node said it supports in its Hello
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerCapabilities {
+    /// Build version, None for a build older than Hello
+    pub build_version: Option<String>,
+    /// Serves FetchSnapshot as a stream of chunks
+    pub chunked_snapshots: bool,
+    /// Codecs it can decode in AppendEntries bodies (`gzip`, `zstd`)
+    pub compression: Vec<String>,
+    /// CPU cores it can use (0 = unknown)
+    pub capacity: u32,
+    /// Newest cluster version it applies (1 for builds from before the version gate)
+    pub cluster_version: u32,
+}
+
+impl PeerCapabilities {
+    /// What this build supports
+    pub fn local() -> Self {
+        Self {
+            build_version: Some(BUILD_VERSION.to_string()),
+            chunked_snapshots: true,
+            compression: compression::supported_codecs().iter().map(|c| codec_name(*c)).collect(),
+            capacity: std::thread::available_parallelism().map_or(0, |n| n.get() as u32),
+            cluster_version: CLUSTER_VERSION,
+        }
+    }
+
+    /// A peer that answered Hello with UNIMPLEMENTED
+    pub fn legacy() -> Self {
+        Self { build_version: None, chunked_snapshots: false, compression: Vec::new(), capacity: 0, cluster_version: 1 }
+    }
+
+    /// Whether the peer runs another build than this one (legacy builds always do)
+    pub fn is_other_build(&self) -> bool {
+        self.build_version.as_deref() != Some(BUILD_VERSION)
+    }
+
+    /// Codecs the peer can decode, skipping names this build doesn't know
+    pub fn codecs(&self) -> Vec<Compression> {
+        self.compression
+            .iter()
+            .filter_map(|name| parse_codec(name))
+            .filter(|c| *c != Compression::None)
+            .collect()
+    }
+
+    pub fn to_proto(&self) -> ProtoCapabilities {
+        ProtoCapabilities {
+            build_version: self.build_version.clone().unwrap_or_default(),
+            chunked_snapshots: self.chunked_snapshots,
+            compression: self.codecs().into_iter().map(|c| c as i32).collect(),
+            capacity: self.capacity,
+            cluster_version: self.cluster_version,
+        }
+    }
+
+    /// Capabilities from a Hello; codecs a newer build added and this one can't
+    /// name are dropped, since they couldn't be used anyway
+    pub fn from_proto(proto: &ProtoCapabilities) -> Self {
+        Self {
+            build_version: Some(proto.build_version.clone()).filter(|v| !v.is_empty()),
+            chunked_snapshots: proto.chunked_snapshots,
+            compression: proto.compression().map(codec_name).filter(|name| name != "none").collect(),
+            capacity: proto.capacity,
+            cluster_version: proto.cluster_version.max(1),
+        }
+    }
+}
+
+/// Newest cluster version every one of `members` applies, None while one of them
+/// (other than `self_id`) hasn't been greeted since it last connected
+pub fn members_cluster_version(registry: &NodeRegistry, self_id: NodeId, members: impl IntoIterator<Item = NodeId>) -> Option<u32> {
+    members
+        .into_iter()
+        .filter(|id| *id != self_id)
+        .try_fold(CLUSTER_VERSION, |version, id| Some(version.min(registry.capabilities(id)?.cluster_version)))
+}
+
+/// Lowercase codec name, as RAFT_COMPRESSION spells it
+fn codec_name(codec: Compression) -> String {
+    codec.as_str_name().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_round_trip() {
+        let local = PeerCapabilities::local();
+        assert_eq!(local.compression, ["gzip", "zstd"]);
+        assert!(!local.is_other_build());
+        assert_eq!(PeerCapabilities::from_proto(&local.to_proto()), local);
+
+        // An older build's reply: no version, fewer codecs, an unknown codec number
+        let older = ProtoCapabilities { compression: vec![Compression::Gzip as i32, 9], ..Default::default() };
+        let older = PeerCapabilities::from_proto(&older);
+        assert!(older.is_other_build());
+        assert_eq!(older.codecs(), [Compression::Gzip]);
+        assert!(PeerCapabilities::legacy().codecs().is_empty());
+        assert_eq!(older.cluster_version, 1);
+    }
+
+    #[test]
+    fn test_members_cluster_version() {
+        let registry = NodeRegistry::new();
+        registry.set_capabilities(2, PeerCapabilities::local());
+
+        // Node 3 hasn't said hello yet: nothing can be raised
+        assert_eq!(members_cluster_version(&registry, 1, [1, 2, 3]), None);
+
+        // One member still on a build from before the gate holds the cluster back
+        registry.set_capabilities(3, PeerCapabilities::legacy());
+        assert_eq!(members_cluster_version(&registry, 1, [1, 2, 3]), Some(1));
+        registry.set_capabilities(3, PeerCapabilities::local());
+        assert_eq!(members_cluster_version(&registry, 1, [1, 2, 3]), Some(CLUSTER_VERSION));
+    }
+}
@@ -0,0 +1,155 @@
+// Command channel - player commands go straight to the leader over gRPC
+//
+// A player client joins the game's Raft group as a learner, and a learner can't
+// propose: `client_write` on it fails. CommandClient finds the leader from this
+// node's Raft metrics, submits through the leader's SubmitCommand RPC and keeps
+// the connection for the next command. When leadership moves - a FAILED_PRECONDITION
+// naming the new leader, a dead connection, or metrics showing someone else leads -
+// it reconnects and tries again. A command sent for an API caller carries its
+// CommandOrigin, which the leader commits with it (see audit).
+
+use crate::game::GameEvent;
+use crate::raft::audit::CommandOrigin;
+use crate::raft::bootstrap::LEADER_ADDR_METADATA;
+use crate::raft::conversions::proto::raft_service_client::RaftServiceClient;
+use crate::raft::conversions::proto::CommandRequest;
+use crate::raft::node_registry::NodeRegistry;
+use crate::raft::storage::{GameEventRequest, GameRaftTypeConfig};
+use anyhow::{Context, Result};
+use openraft::Raft;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tonic::transport::{Channel, Endpoint};
+use tonic::Code;
+
+/// Tries per command, each after re-discovering the leader
+pub const SUBMIT_ATTEMPTS: u32 = 3;
+
+/// Pause before a retry, for an election to settle
+const REDISCOVER_DELAY: Duration = Duration::from_millis(250);
+
+/// A client connected to the leader, with the address it was opened to
+type LeaderConnection = (String, RaftServiceClient<Channel>);
+
+/// Submits commands to whichever node leads; cheap to clone
+#[derive(Clone)]
+pub struct CommandClient {
+    raft: Arc<Raft<GameRaftTypeConfig>>,
+    registry: NodeRegistry,
+    /// Open connection to the leader, if any
+    leader: Arc<Mutex<Option<LeaderConnection>>>,
+}
+
+impl CommandClient {
+    pub fn new(raft: Arc<Raft<GameRaftTypeConfig>>, registry: NodeRegistry) -> Self {
+        Self { raft, registry, leader: Arc::new(Mutex::new(None)) }
+    }
+
+    /// Commit `event` through the leader, returning its log index
+    /// Refusals (spawn protection, referee-only events) are returned without retrying.
+    pub async fn submit(&self, event: GameEvent) -> Result<u64> {
+        self.submit_from(event, None).await
+    }
+
+    /// `submit`, recording who sent the command in the audit trail
+    pub async fn submit_from(&self, event: GameEvent, origin: Option<CommandOrigin>) -> Result<u64> {
+        let body = serde_json::to_vec(&event).context("Failed to encode event")?;
+        let origin_body = match &origin {
+            Some(origin) => serde_json::to_vec(origin).context("Failed to encode origin")?,
+            None => Vec::new(),
+        };
+        let mut redirect: Option<String> = None;
+        let mut last_error = anyhow::anyhow!("No leader");
+
+        for attempt in 0..SUBMIT_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(REDISCOVER_DELAY).await;
+            }
+
+            // A client that bootstrapped the game may lead it itself
+            let metrics = self.raft.metrics().borrow().clone();
+            if metrics.current_leader == Some(metrics.id) {
+                let request = match origin {
+                    Some(origin) => GameEventRequest::with_origin(event, origin),
+                    None => GameEventRequest::new(event),
+                };
+                let response = self.raft.client_write(request).await?;
+                return Ok(response.log_id.index);
+            }
+
+            let addr = match redirect.take() {
+                Some(addr) => addr,
+                None => match metrics.current_leader {
+                    Some(leader) => match self.registry.get_address(leader).await {
+                        Some(addr) => addr,
+                        None => {
+                            last_error = anyhow::anyhow!("No address for leader {}", leader);
+                            continue;
+                        }
+                    },
+                    None => continue,
+                },
+            };
+
+            let mut client = match self.connect(&addr).await {
+                Ok(client) => client,
+                Err(e) => {
+                    last_error = e;
+                    continue;
+                }
+            };
+            match client.submit_command(CommandRequest { event: body.clone(), origin: origin_body.clone() }).await {
+                Ok(reply) => return Ok(reply.into_inner().log_index),
+                Err(status) if status.code() == Code::FailedPrecondition => {
+                    // Leadership moved on; the old leader names the new one if it knows
+                    self.forget(&addr).await;
+                    redirect = status
+                        .metadata()
+                        .get(LEADER_ADDR_METADATA)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string);
+                    println!("[Commands] {} is not the leader, re-discovering", addr);
+                    last_error = anyhow::anyhow!("{} is not the leader", addr);
+                }
+                Err(status) if status.code() == Code::Unavailable => {
+                    self.forget(&addr).await;
+                    last_error = anyhow::anyhow!("Leader at {} unavailable: {}", addr, status.message());
+                }
+                Err(status) => anyhow::bail!("Leader refused command: {}", status.message()),
+            }
+        }
+
+        anyhow::bail!("Command not committed after {} attempts: {}", SUBMIT_ATTEMPTS, last_error)
+    }
+
+    /// The open connection if it goes to `addr`, otherwise a new one (replacing it)
+    async fn connect(&self, addr: &str) -> Result<RaftServiceClient<Channel>> {
+        let mut leader = self.leader.lock().await;
+        if let Some((open_addr, client)) = leader.as_ref() {
+            if open_addr == addr {
+                return Ok(client.clone());
+            }
+        }
+
+        let channel = Endpoint::from_shared(format!("http://{}", addr))
+            .with_context(|| format!("Invalid leader address {}", addr))?
+            .connect_timeout(Duration::from_secs(2))
+            .timeout(Duration::from_secs(5))
+            .connect()
+            .await
+            .with_context(|| format!("Failed to connect to leader at {}", addr))?;
+        println!("[Commands] Submitting commands to leader at {}", addr);
+        let client = RaftServiceClient::new(channel);
+        *leader = Some((addr.to_string(), client.clone()));
+        Ok(client)
+    }
+
+    /// Drop the connection to `addr`, if that's the open one
+    async fn forget(&self, addr: &str) {
+        let mut leader = self.leader.lock().await;
+        if leader.as_ref().is_some_and(|(open_addr, _)| open_addr == addr) {
+            *leader = None;
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::raft::conversions::proto::{
+    AppendEntriesRequest as ProtoAppendEntriesRequest, Compression, LogEntry as ProtoLogEntry,
+    LogEntryBatch,
+};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use prost::Message;
+use std::io::{Read, Write};
+
+/// Batches smaller than this are sent inline - compression overhead isn't worth it
+pub const DEFAULT_MIN_COMPRESS_BYTES: usize = 4096;
+
+/// zstd level used for AppendEntries bodies (fast, decent ratio)
+const ZSTD_LEVEL: i32 = 3;
+
+/// Most bytes a compressed body may expand to
+/// gRPC caps the body itself at 4MB, but a few KB of compressed zeros can claim gigabytes.
+pub const MAX_DECOMPRESSED_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest zstd window a body may ask for (2^24 = MAX_DECOMPRESSED_BYTES), so a crafted
+/// frame header can't make the decoder allocate more
+const ZSTD_WINDOW_LOG_MAX: u32 = 24;
+
+/// Compression settings for outgoing AppendEntries bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// Preferred codec (NONE disables compression)
+    pub codec: Compression,
+    /// Only compress batches whose encoded size is at least this many bytes
+    pub min_bytes: usize,
+}
+
+impl CompressionConfig {
+    /// Compression disabled - all entries are sent inline
+    pub fn disabled() -> Self {
+        Self {
+            codec: Compression::None,
+            min_bytes: DEFAULT_MIN_COMPRESS_BYTES,
+        }
+    }
+
+    /// Load settings from the environment
+    /// RAFT_COMPRESSION: none | gzip | zstd (default: none)
+    /// RAFT_COMPRESSION_MIN_BYTES: threshold in bytes (default: 4096)
+    pub fn from_env() -> Self {
+        let codec = std::env::var("RAFT_COMPRESSION")
+            .ok()
+            .and_then(|s| parse_codec(&s))
+            .unwrap_or(Compression::None);
+
+        let min_bytes = std::env::var("RAFT_COMPRESSION_MIN_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MIN_COMPRESS_BYTES);
+
+        Self { codec, min_bytes }
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Parse a codec name (case-insensitive)
+pub fn parse_codec(name: &str) -> Option<Compression> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "none" | "" => Some(Compression::None),
+        "gzip" => Some(Compression::Gzip),
+        "zstd" => Some(Compression::Zstd),
+        _ => None,
+    }
+}
+
+/// Codecs this build can decode, advertised to leaders in AppendEntries responses
+pub fn supported_codecs() -> Vec<Compression> {
+    vec![Compression::Gzip, Compression::Zstd]
+}
+
+/// Compress a byte buffer with the given codec
+pub fn compress(codec: Compression, data: &[u8]) -> Result<Vec<u8>> {
+    match codec {
+        Compression::None => Ok(data.to_vec()),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Compression::Zstd => Ok(zstd::encode_all(data, ZSTD_LEVEL)?),
+    }
+}
+
+/// Decompress a byte buffer produced by `compress`
+/// Bodies come from peers, so anything expanding past MAX_DECOMPRESSED_BYTES is refused
+/// (whatever the codec), and the decoders never produce more than one byte past it.
+pub fn decompress(codec: Compression, data: &[u8]) -> Result<Vec<u8>> {
+    // One byte over the cap is enough to tell it was exceeded
+    let limit = MAX_DECOMPRESSED_BYTES as u64 + 1;
+    let mut out = Vec::new();
+    match codec {
+        Compression::None => {
+            data.take(limit).read_to_end(&mut out)?;
+        }
+        Compression::Gzip => {
+            GzDecoder::new(data).take(limit).read_to_end(&mut out).context("gzip decode failed")?;
+        }
+        Compression::Zstd => {
+            let mut decoder = zstd::stream::read::Decoder::new(data).context("zstd decode failed")?;
+            decoder.window_log_max(ZSTD_WINDOW_LOG_MAX).context("zstd decode failed")?;
+            decoder.take(limit).read_to_end(&mut out).context("zstd decode failed")?;
+        }
+    }
+    if out.len() > MAX_DECOMPRESSED_BYTES {
+        anyhow::bail!("Compressed body expands past {} bytes", MAX_DECOMPRESSED_BYTES);
+    }
+    Ok(out)
+}
+
+/// Move the request's entries into a compressed body if the peer supports the
+/// configured codec and the batch is large enough. Returns true if compressed.
+/// On an error the entries are still inline, so the request can be sent as is.
+pub fn compress_request(
+    req: &mut ProtoAppendEntriesRequest,
+    config: &CompressionConfig,
+    peer_codecs: &[Compression],
+) -> Result<bool> {
+    compress_request_with(req, config, peer_codecs, compress)
+}
+
+fn compress_request_with(
+    req: &mut ProtoAppendEntriesRequest,
+    config: &CompressionConfig,
+    peer_codecs: &[Compression],
+    compress: impl FnOnce(Compression, &[u8]) -> Result<Vec<u8>>,
+) -> Result<bool> {
+    if config.codec == Compression::None
+        || req.entries.is_empty()
+        || !peer_codecs.contains(&config.codec)
+    {
+        return Ok(false);
+    }
+
+    let batch = LogEntryBatch {
+        entries: std::mem::take(&mut req.entries),
+    };
+    let encoded = batch.encode_to_vec();
+    // The entries stay inline until a compressed body has replaced them
+    req.entries = batch.entries;
+
+    if encoded.len() < config.min_bytes {
+        // Too small to bother
+        return Ok(false);
+    }
+
+    req.compressed_entries = compress(config.codec, &encoded)?;
+    req.entries.clear();
+    req.set_entries_compression(config.codec);
+    Ok(true)
+}
+
+/// Take the request's entries, decompressing the batch body if one was sent
+pub fn take_entries(req: &mut ProtoAppendEntriesRequest) -> Result<Vec<ProtoLogEntry>> {
+    if req.compressed_entries.is_empty() {
+        return Ok(std::mem::take(&mut req.entries));
+    }
+
+    let codec = Compression::try_from(req.entries_compression)
+        .map_err(|_| anyhow::anyhow!("Unknown compression codec {}", req.entries_compression))?;
+    let encoded = decompress(codec, &req.compressed_entries)?;
+    let batch = LogEntryBatch::decode(encoded.as_slice())
+        .context("Failed to decode compressed log entry batch")?;
+
+    Ok(batch.entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_request(entry_count: u64) -> ProtoAppendEntriesRequest {
+        ProtoAppendEntriesRequest {
+            term: 3,
+            leader_id: 1,
+            entries: (1..=entry_count)
+                .map(|i| ProtoLogEntry {
+                    index: i,
+                    term: 3,
+                    data: vec![7u8; 128],
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let data = b"NodeMetricsReport".repeat(100);
+        for codec in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let compressed = compress(codec, &data).unwrap();
+            assert_eq!(decompress(codec, &compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_decompression_is_capped() {
+        let mut bomb = vec![0u8; MAX_DECOMPRESSED_BYTES];
+        for codec in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            // Right at the cap is fine
+            let compressed = compress(codec, &bomb).unwrap();
+            assert_eq!(decompress(codec, &compressed).unwrap().len(), MAX_DECOMPRESSED_BYTES);
+        }
+        bomb.push(0);
+        for codec in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let compressed = compress(codec, &bomb).unwrap();
+            let err = decompress(codec, &compressed).unwrap_err();
+            assert!(err.to_string().contains("expands past"), "{}: {:#}", codec.as_str_name(), err);
+        }
+        for codec in [Compression::Gzip, Compression::Zstd] {
+            let compressed = compress(codec, &bomb).unwrap();
+            assert!(decompress(codec, &compressed[..compressed.len() / 2]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_compress_request_roundtrip() {
+        let config = CompressionConfig {
+            codec: Compression::Zstd,
+            min_bytes: 0,
+        };
+        let mut req = sample_request(300);
+        let original = req.entries.clone();
+
+        assert!(compress_request(&mut req, &config, &supported_codecs()).unwrap());
+        assert!(req.entries.is_empty());
+        assert_eq!(req.entries_compression(), Compression::Zstd);
+
+        assert_eq!(take_entries(&mut req).unwrap(), original);
+    }
+
+    #[test]
+    fn test_no_compression_without_peer_support() {
+        let config = CompressionConfig {
+            codec: Compression::Gzip,
+            min_bytes: 0,
+        };
+        let mut req = sample_request(10);
+
+        // Peer hasn't advertised any codecs yet
+        assert!(!compress_request(&mut req, &config, &[]).unwrap());
+        assert_eq!(req.entries.len(), 10);
+        assert!(req.compressed_entries.is_empty());
+    }
+
+    #[test]
+    fn test_small_batches_stay_inline() {
+        let config = CompressionConfig {
+            codec: Compression::Gzip,
+            min_bytes: 1_000_000,
+        };
+        let mut req = sample_request(2);
+
+        assert!(!compress_request(&mut req, &config, &supported_codecs()).unwrap());
+        assert_eq!(req.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_failed_compression_leaves_entries_inline() {
+        let config = CompressionConfig {
+            codec: Compression::Gzip,
+            min_bytes: 0,
+        };
+        let mut req = sample_request(10);
+        let original = req.entries.clone();
+
+        let broken = |_: Compression, _: &[u8]| -> Result<Vec<u8>> { anyhow::bail!("codec failed") };
+        assert!(compress_request_with(&mut req, &config, &supported_codecs(), broken).is_err());
+        assert!(req.compressed_entries.is_empty());
+        assert_eq!(take_entries(&mut req).unwrap(), original);
+    }
+
+    #[test]
+    fn test_parse_codec() {
+        assert_eq!(parse_codec("ZSTD"), Some(Compression::Zstd));
+        assert_eq!(parse_codec("gzip"), Some(Compression::Gzip));
+        assert_eq!(parse_codec("none"), Some(Compression::None));
+        assert_eq!(parse_codec("lz4"), None);
+    }
+}
@@ -36,6 +36,9 @@ impl From<openraft::raft::AppendEntriesRequest<GameRaftTypeConfig>> for ProtoApp
                 .as_ref()
                 .map(|id| id.index)
                 .unwrap_or(0),
+            // Compression is applied per-peer by the network layer
+            entries_compression: proto::Compression::None as i32,
+            compressed_entries: Vec::new(),
         }
     }
 }
@@ -44,8 +47,9 @@ impl From<openraft::raft::AppendEntriesRequest<GameRaftTypeConfig>> for ProtoApp
 impl TryFrom<ProtoAppendEntriesRequest> for openraft::raft::AppendEntriesRequest<GameRaftTypeConfig> {
     type Error = anyhow::Error;
 
-    fn try_from(req: ProtoAppendEntriesRequest) -> Result<Self, Self::Error> {
-        let vote = Vote::new(req.term, req.leader_id);
+    fn try_from(mut req: ProtoAppendEntriesRequest) -> Result<Self, Self::Error> {
+        // Only an established leader sends entries, so its vote is committed
+        let vote = Vote::new_committed(req.term, req.leader_id);
 
         let prev_log_id = if req.prev_log_index > 0 {
             Some(LogId::new(
@@ -56,10 +60,17 @@ impl TryFrom<ProtoAppendEntriesRequest> for openraft::raft::AppendEntriesRequest
             None
         };
 
-        let entries: Result<Vec<_>, _> = req
-            .entries
+        // Entries may arrive in a compressed batch instead of inline
+        // They carry only their term: attribute them to the sending leader, as
+        // prev_log_id is, so the follower stores the log ids the leader compares
+        let entries: Result<Vec<Entry<GameRaftTypeConfig>>, _> = crate::raft::compression::take_entries(&mut req)?
             .into_iter()
-            .map(|e| e.try_into())
+            .map(|e| {
+                Entry::try_from(e).map(|mut entry| {
+                    entry.log_id.leader_id.node_id = req.leader_id;
+                    entry
+                })
+            })
             .collect();
 
         let leader_commit = if req.leader_commit > 0 {
@@ -90,14 +101,17 @@ impl From<openraft::raft::AppendEntriesResponse<NodeId>> for ProtoAppendEntriesR
             Success | PartialSuccess(_) => ProtoAppendEntriesResponse {
                 term: 0, // Success responses don't carry term info
                 success: true,
+                supported_compression: Vec::new(),
             },
             Conflict => ProtoAppendEntriesResponse {
                 term: 0,
                 success: false,
+                supported_compression: Vec::new(),
             },
             HigherVote(vote) => ProtoAppendEntriesResponse {
                 term: vote.leader_id().term,
                 success: false,
+                supported_compression: Vec::new(),
             },
         }
     }
@@ -203,7 +217,7 @@ impl TryFrom<ProtoInstallSnapshotRequest>
     type Error = anyhow::Error;
 
     fn try_from(req: ProtoInstallSnapshotRequest) -> Result<Self, Self::Error> {
-        let vote = Vote::new(req.term, req.leader_id);
+        let vote = Vote::new_committed(req.term, req.leader_id);
 
         let last_log_id = if req.last_included_index > 0 {
             Some(LogId::new(
@@ -300,7 +314,7 @@ impl TryFrom<ProtoLogEntry> for Entry<GameRaftTypeConfig> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::game::GameEvent;
+    use crate::game::{CommandEvent, GameEvent};
 
     #[test]
     fn test_vote_request_roundtrip() {
@@ -324,18 +338,22 @@ mod tests {
     fn test_log_entry_roundtrip() {
         use crate::game::events::NodeCoord;
 
-        let event = GameEvent::PlayerJoin {
+        let event = GameEvent::Command(CommandEvent::PlayerJoin {
             player_id: 12345,
             name: "Alice".to_string(),
             capital_coord: NodeCoord::new(0, 0),
-            node_ip: "10.0.0.1".to_string(),
-            is_client: false,
+            capital_ip: Some("10.0.0.1".to_string()),
+            client_endpoint: None,
             timestamp: 1234567890,
-        };
+            raft_node_id: 100,
+            reconnect_key: None,
+        });
 
+        let request = GameEventRequest::new(event.clone());
+        let committed_at = request.committed_at;
         let original = Entry::<GameRaftTypeConfig> {
             log_id: LogId::new(openraft::LeaderId::new(3, 100), 10),
-            payload: EntryPayload::Normal(GameEventRequest { event: event.clone() }),
+            payload: EntryPayload::Normal(request),
         };
 
         let proto: ProtoLogEntry = original.clone().into();
@@ -345,7 +363,8 @@ mod tests {
         assert_eq!(original.log_id.leader_id.term, converted.log_id.leader_id.term);
 
         if let EntryPayload::Normal(req) = converted.payload {
-            if let GameEvent::PlayerJoin { player_id, name, .. } = req.event {
+            assert_eq!(req.committed_at, committed_at);
+            if let GameEvent::Command(CommandEvent::PlayerJoin { player_id, name, .. }) = req.event {
                 assert_eq!(player_id, 12345);
                 assert_eq!(name, "Alice");
             } else {
@@ -360,13 +379,221 @@ mod tests {
     fn test_append_entries_response_success() {
         let resp = openraft::raft::AppendEntriesResponse::<NodeId>::Success;
         let proto: ProtoAppendEntriesResponse = resp.into();
-        assert_eq!(proto.success, true);
+        assert!(proto.success);
     }
 
     #[test]
     fn test_append_entries_response_conflict() {
         let resp = openraft::raft::AppendEntriesResponse::<NodeId>::Conflict;
         let proto: ProtoAppendEntriesResponse = resp.into();
-        assert_eq!(proto.success, false);
+        assert!(!proto.success);
+    }
+
+    // Property tests: every conversion keeps what the other side relies on, and no
+    // bytes a peer can send make a TryFrom panic (see also fuzz/)
+    mod props {
+        use super::*;
+        use crate::game::{AttackOrder, AttackTarget, DefenseOrder, NodeCoord};
+        use crate::raft::audit::CommandOrigin;
+        use crate::raft::compression::{self, CompressionConfig};
+        use openraft::LeaderId;
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+        use prost::Message;
+        use proto::Compression;
+
+        /// Log ids as they go over the wire - index 0 means "none"
+        fn log_id() -> impl Strategy<Value = LogId<NodeId>> {
+            (any::<u64>(), any::<u64>(), 1..u64::MAX)
+                .prop_map(|(term, node_id, index)| LogId::new(LeaderId::new(term, node_id), index))
+        }
+
+        fn coord() -> impl Strategy<Value = NodeCoord> {
+            (any::<i32>(), any::<i32>()).prop_map(|(q, r)| NodeCoord::new(q, r))
+        }
+
+        fn target() -> impl Strategy<Value = AttackTarget> {
+            prop_oneof![
+                coord().prop_map(AttackTarget::Coordinate),
+                any::<u64>().prop_map(AttackTarget::Player),
+            ]
+        }
+
+        fn event() -> impl Strategy<Value = GameEvent> {
+            let order = (coord(), proptest::option::of(target()))
+                .prop_map(|(node_coord, target)| AttackOrder { node_coord, target });
+            prop_oneof![
+                (any::<u64>(), ".{0,16}", coord(), proptest::option::of("[0-9.:]{0,21}"), any::<u64>(), any::<u64>())
+                    .prop_map(|(player_id, name, capital_coord, capital_ip, timestamp, raft_node_id)| {
+                        GameEvent::from(CommandEvent::PlayerJoin {
+                            player_id,
+                            name,
+                            capital_coord,
+                            capital_ip,
+                            client_endpoint: None,
+                            timestamp,
+                            raft_node_id,
+                            reconnect_key: None,
+                        })
+                    }),
+                (coord(), proptest::option::of(target()), any::<u64>()).prop_map(|(node_coord, target, timestamp)| {
+                    GameEvent::from(CommandEvent::SetNodeTarget { node_coord, target, timestamp })
+                }),
+                (vec(order, 0..8), any::<u64>()).prop_map(|(orders, timestamp)| {
+                    GameEvent::from(CommandEvent::SetNodeTargets { orders, timestamp })
+                }),
+                (coord(), proptest::option::of(proptest::option::of(coord())), any::<u64>()).prop_map(
+                    |(node_coord, defense, timestamp)| {
+                        let defense = defense.map(|assist| DefenseOrder { assist });
+                        GameEvent::from(CommandEvent::SetNodeDefense { node_coord, defense, timestamp })
+                    }
+                ),
+            ]
+        }
+
+        fn entry() -> impl Strategy<Value = Entry<GameRaftTypeConfig>> {
+            let origin = proptest::option::of((any::<[u8; 4]>(), proptest::option::of(any::<u64>())))
+                .prop_map(|origin| origin.map(|(ip, player_id)| CommandOrigin::new(ip.into(), player_id)));
+            let payload = prop_oneof![
+                1 => Just(EntryPayload::Blank),
+                4 => (event(), any::<u64>(), origin).prop_map(|(event, committed_at, origin)| {
+                    EntryPayload::Normal(GameEventRequest { event, committed_at, origin })
+                }),
+            ];
+            (log_id(), payload).prop_map(|(log_id, payload)| Entry { log_id, payload })
+        }
+
+        /// What a receiver can rely on from an entry: position, term and payload
+        fn entry_key(entry: &Entry<GameRaftTypeConfig>) -> (u64, u64, Option<serde_json::Value>) {
+            let payload = match &entry.payload {
+                EntryPayload::Normal(request) => Some(serde_json::json!({
+                    "event": request.event,
+                    "committed_at": request.committed_at,
+                    "origin": request.origin,
+                })),
+                _ => None,
+            };
+            (entry.log_id.index, entry.log_id.leader_id.term, payload)
+        }
+
+        fn index_and_term(log_id: &Option<LogId<NodeId>>) -> Option<(u64, u64)> {
+            log_id.as_ref().map(|id| (id.index, id.leader_id.term))
+        }
+
+        proptest! {
+            #[test]
+            fn prop_vote_roundtrip(term: u64, node_id: u64, last_log_id in proptest::option::of(log_id()), granted: bool) {
+                let request = openraft::raft::VoteRequest::<NodeId> { vote: Vote::new(term, node_id), last_log_id };
+                let back = openraft::raft::VoteRequest::<NodeId>::from(ProtoVoteRequest::from(request.clone()));
+                prop_assert_eq!(back.vote, request.vote);
+                prop_assert_eq!(index_and_term(&back.last_log_id), index_and_term(&request.last_log_id));
+
+                let response = openraft::raft::VoteResponse::<NodeId> {
+                    vote: Vote::new(term, node_id),
+                    vote_granted: granted,
+                    last_log_id: None,
+                };
+                let back = openraft::raft::VoteResponse::<NodeId>::from(ProtoVoteResponse::from(response));
+                prop_assert_eq!((back.vote.leader_id().term, back.vote_granted), (term, granted));
+            }
+
+            #[test]
+            fn prop_append_entries_roundtrip(
+                term: u64,
+                leader_id: u64,
+                prev_log_id in proptest::option::of(log_id()),
+                leader_commit in proptest::option::of(log_id()),
+                entries in vec(entry(), 0..8),
+                codec in prop_oneof![Just(Compression::None), Just(Compression::Gzip), Just(Compression::Zstd)],
+            ) {
+                let request = openraft::raft::AppendEntriesRequest::<GameRaftTypeConfig> {
+                    vote: Vote::new_committed(term, leader_id),
+                    prev_log_id,
+                    entries,
+                    leader_commit,
+                };
+                let mut proto = ProtoAppendEntriesRequest::from(request.clone());
+                let config = CompressionConfig { codec, min_bytes: 0 };
+                compression::compress_request(&mut proto, &config, &compression::supported_codecs()).unwrap();
+
+                let back = openraft::raft::AppendEntriesRequest::<GameRaftTypeConfig>::try_from(proto).unwrap();
+                prop_assert_eq!(back.vote, request.vote);
+                prop_assert_eq!(index_and_term(&back.prev_log_id), index_and_term(&request.prev_log_id));
+                prop_assert_eq!(
+                    back.leader_commit.map(|id| id.index),
+                    request.leader_commit.map(|id| id.index)
+                );
+                prop_assert_eq!(
+                    back.entries.iter().map(entry_key).collect::<Vec<_>>(),
+                    request.entries.iter().map(entry_key).collect::<Vec<_>>()
+                );
+            }
+
+            #[test]
+            fn prop_append_entries_response_roundtrip(term in 1..u64::MAX, kind in 0..3u8) {
+                use openraft::raft::AppendEntriesResponse;
+                let response = || match kind {
+                    0 => AppendEntriesResponse::<NodeId>::Success,
+                    1 => AppendEntriesResponse::Conflict,
+                    _ => AppendEntriesResponse::HigherVote(Vote::new(term, 0)),
+                };
+                let back = AppendEntriesResponse::<NodeId>::from(ProtoAppendEntriesResponse::from(response()));
+                prop_assert_eq!(back, response());
+            }
+
+            #[test]
+            fn prop_install_snapshot_roundtrip(
+                term: u64,
+                leader_id: u64,
+                last_log_id in proptest::option::of(log_id()),
+                data in vec(any::<u8>(), 0..256),
+                done: bool,
+            ) {
+                let request = openraft::raft::InstallSnapshotRequest::<GameRaftTypeConfig> {
+                    vote: Vote::new_committed(term, leader_id),
+                    meta: openraft::SnapshotMeta { last_log_id, ..Default::default() },
+                    offset: 0,
+                    data,
+                    done,
+                };
+                let proto = ProtoInstallSnapshotRequest::from(request.clone());
+                let back = openraft::raft::InstallSnapshotRequest::<GameRaftTypeConfig>::try_from(proto).unwrap();
+                prop_assert_eq!(back.vote, request.vote);
+                prop_assert_eq!(index_and_term(&back.meta.last_log_id), index_and_term(&request.meta.last_log_id));
+                prop_assert_eq!((back.data, back.done), (request.data, request.done));
+
+                let response = openraft::raft::InstallSnapshotResponse::<NodeId> { vote: Vote::new(term, leader_id) };
+                let back = openraft::raft::InstallSnapshotResponse::<NodeId>::from(ProtoInstallSnapshotResponse::from(response));
+                prop_assert_eq!(back.vote.leader_id().term, term);
+            }
+
+            #[test]
+            fn prop_garbage_entries_fail_cleanly(
+                entries in vec((any::<u64>(), any::<u64>(), vec(any::<u8>(), 0..96)), 0..4),
+                codec in -1i32..4,
+                compressed in vec(any::<u8>(), 0..512),
+            ) {
+                let proto = ProtoAppendEntriesRequest {
+                    entries: entries.into_iter().map(|(index, term, data)| ProtoLogEntry { index, term, data }).collect(),
+                    entries_compression: codec,
+                    compressed_entries: compressed,
+                    ..Default::default()
+                };
+                let _ = openraft::raft::AppendEntriesRequest::<GameRaftTypeConfig>::try_from(proto);
+            }
+
+            #[test]
+            fn prop_undecodable_messages_fail_cleanly(bytes in vec(any::<u8>(), 0..1024)) {
+                if let Ok(proto) = ProtoAppendEntriesRequest::decode(bytes.as_slice()) {
+                    let _ = openraft::raft::AppendEntriesRequest::<GameRaftTypeConfig>::try_from(proto);
+                }
+                if let Ok(proto) = ProtoInstallSnapshotRequest::decode(bytes.as_slice()) {
+                    let _ = openraft::raft::InstallSnapshotRequest::<GameRaftTypeConfig>::try_from(proto);
+                }
+                if let Ok(proto) = ProtoLogEntry::decode(bytes.as_slice()) {
+                    let _ = Entry::<GameRaftTypeConfig>::try_from(proto);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,124 @@
+// State digests - catching a node whose state has drifted from the leader's
+//
+// Every node hashes its GameState (`GameState::digest`) after applying each entry
+// whose log index is a multiple of DIGEST_EVERY, and keeps the last few. The leader
+// periodically commits its newest one as a StateDigest fact; when a follower applies
+// it, it compares the leader's hash with its own at the same index and reports any
+// mismatch loudly. Nodes that never hashed that index (one restored from a later
+// snapshot, say) have nothing to compare and skip it.
+//
+// The log lives on the storage and is fed from apply, like the invariant checks: it
+// must see every entry in order, and is as cheap as a hash every DIGEST_EVERY entries.
+
+use crate::game::{FactEvent, GameEvent, GameState};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Hash the state after every this many log entries
+pub const DIGEST_EVERY: u64 = 100;
+
+/// Digests kept for comparing against the leader's (a StateDigest older than these is skipped)
+const DIGESTS_KEPT: usize = 16;
+
+/// The leader's digest didn't match this node's at the same index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Divergence {
+    pub log_index: u64,
+    pub ours: u64,
+    pub leaders: u64,
+}
+
+/// How this node's state compares with the leader's, for /status
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestStatus {
+    /// StateDigest facts compared against our own digest
+    pub checked: u64,
+    /// How many of those didn't match
+    pub diverged: u64,
+    pub last_divergence: Option<Divergence>,
+    /// Newest digest of our own: (log index, digest)
+    pub latest: Option<(u64, u64)>,
+}
+
+#[derive(Default)]
+struct Inner {
+    digests: VecDeque<(u64, u64)>,
+    status: DigestStatus,
+}
+
+/// This node's recent digests, shared by all clones of the storage
+#[derive(Clone, Default)]
+pub struct DigestLog {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl DigestLog {
+    /// `event` was applied at `log_index`, leaving `game_state`
+    pub fn applied(&self, log_index: u64, event: &GameEvent, game_state: &GameState) {
+        let mut inner = self.inner.lock().unwrap();
+        if log_index.is_multiple_of(DIGEST_EVERY) {
+            let digest = game_state.digest();
+            inner.digests.push_back((log_index, digest));
+            while inner.digests.len() > DIGESTS_KEPT {
+                inner.digests.pop_front();
+            }
+            inner.status.latest = Some((log_index, digest));
+        }
+
+        if let GameEvent::Fact(FactEvent::StateDigest { log_index: at, digest: leaders, .. }) = event {
+            let Some(&(_, ours)) = inner.digests.iter().find(|(index, _)| index == at) else {
+                return;
+            };
+            inner.status.checked += 1;
+            if ours != *leaders {
+                eprintln!(
+                    "[Digest] !!! STATE DIVERGED at log index {}: ours {:016x}, leader's {:016x} !!!",
+                    at, ours, leaders
+                );
+                inner.status.diverged += 1;
+                inner.status.last_divergence = Some(Divergence { log_index: *at, ours, leaders: *leaders });
+            }
+        }
+    }
+
+    /// Newest digest of our own, (log index, digest)
+    pub fn latest(&self) -> Option<(u64, u64)> {
+        self.inner.lock().unwrap().status.latest
+    }
+
+    pub fn status(&self) -> DigestStatus {
+        self.inner.lock().unwrap().status.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest_fact(log_index: u64, digest: u64) -> GameEvent {
+        FactEvent::StateDigest { log_index, digest, timestamp: 0 }.into()
+    }
+
+    #[test]
+    fn test_digest_log() {
+        let log = DigestLog::default();
+        let state = GameState::new();
+        let tick = FactEvent::GameOverAcknowledged { raft_node_id: 1, timestamp: 0 }.into();
+        log.applied(99, &tick, &state);
+        assert_eq!(log.latest(), None);
+        log.applied(100, &tick, &state);
+        assert_eq!(log.latest(), Some((100, state.digest())));
+
+        // A matching digest counts as checked, one for an index we never hashed is skipped
+        log.applied(101, &digest_fact(100, state.digest()), &state);
+        log.applied(102, &digest_fact(50, 7), &state);
+        assert_eq!(log.status().checked, 1);
+        assert_eq!(log.status().diverged, 0);
+
+        log.applied(103, &digest_fact(100, 7), &state);
+        let status = log.status();
+        assert_eq!(status.diverged, 1);
+        assert_eq!(status.last_divergence, Some(Divergence { log_index: 100, ours: state.digest(), leaders: 7 }));
+    }
+}
@@ -2,29 +2,79 @@ use crate::raft::conversions::proto::raft_service_server::{RaftService, RaftServ
 use crate::raft::conversions::proto::{
     AppendEntriesRequest as ProtoAppendEntriesRequest,
     AppendEntriesResponse as ProtoAppendEntriesResponse,
+    CommandReply as ProtoCommandReply, CommandRequest as ProtoCommandRequest,
+    FetchSnapshotRequest as ProtoFetchSnapshotRequest,
+    HelloReply as ProtoHelloReply, HelloRequest as ProtoHelloRequest,
     InstallSnapshotRequest as ProtoInstallSnapshotRequest,
     InstallSnapshotResponse as ProtoInstallSnapshotResponse,
+    MetricSample as ProtoMetricSample, MetricsAck as ProtoMetricsAck,
+    SnapshotChunk as ProtoSnapshotChunk,
     VoteRequest as ProtoVoteRequest, VoteResponse as ProtoVoteResponse,
 };
-use crate::raft::storage::GameRaftTypeConfig;
+use crate::raft::capabilities::PeerCapabilities;
+use crate::raft::bootstrap::{self, LEADER_ADDR_METADATA, SNAPSHOT_CHUNK_SIZE};
+use crate::raft::metrics_view::{MetricsView, ACK_INTERVAL};
+use crate::raft::node_registry::NodeRegistry;
+use crate::game::GameEvent;
+use crate::raft::api::refuse_event;
+use crate::raft::audit::CommandOrigin;
+use crate::raft::storage::{GameEventRequest, GameRaftTypeConfig, MemStorage};
+use crate::raft::write_error::RaftWriteError;
+use crate::supervisor::Supervisor;
+use futures_util::{FutureExt, Stream};
 use openraft::Raft;
+use std::pin::Pin;
 use std::sync::Arc;
-use tonic::{Request, Response, Status};
+use tonic::{Request, Response, Status, Streaming};
 
 /// gRPC service implementation for Raft RPCs
 pub struct RaftGrpcService {
     raft: Arc<Raft<GameRaftTypeConfig>>,
+    /// Shares state with the Raft instance's storage - read for hot-bootstrap snapshots
+    storage: MemStorage,
+    registry: NodeRegistry,
+    /// Where streamed metric samples land while this node leads
+    metrics_view: MetricsView,
 }
 
 impl RaftGrpcService {
     /// Create a new gRPC service wrapping a Raft instance
-    pub fn new(raft: Arc<Raft<GameRaftTypeConfig>>) -> Self {
-        Self { raft }
+    pub fn new(
+        raft: Arc<Raft<GameRaftTypeConfig>>,
+        storage: MemStorage,
+        registry: NodeRegistry,
+        metrics_view: MetricsView,
+    ) -> Self {
+        Self { raft, storage, registry, metrics_view }
+    }
+
+    /// FailedPrecondition carrying the leader's address, unless this node leads
+    async fn not_leader(&self) -> Option<Status> {
+        let metrics = self.raft.metrics().borrow().clone();
+        if metrics.current_leader == Some(metrics.id) {
+            return None;
+        }
+
+        let mut status = Status::failed_precondition("Not the leader");
+        if let Some(leader_id) = metrics.current_leader {
+            if let Some(value) = self
+                .registry
+                .get_address(leader_id)
+                .await
+                .and_then(|addr| addr.parse().ok())
+            {
+                status.metadata_mut().insert(LEADER_ADDR_METADATA, value);
+            }
+        }
+        Some(status)
     }
 }
 
 #[tonic::async_trait]
 impl RaftService for RaftGrpcService {
+    type FetchSnapshotStream = Pin<Box<dyn Stream<Item = Result<ProtoSnapshotChunk, Status>> + Send>>;
+    type StreamMetricsStream = Pin<Box<dyn Stream<Item = Result<ProtoMetricsAck, Status>> + Send>>;
+
     /// Handle AppendEntries RPC - used for log replication and heartbeats
     async fn append_entries(
         &self,
@@ -44,8 +94,12 @@ impl RaftService for RaftGrpcService {
             .await
             .map_err(|e| Status::internal(format!("Raft append_entries failed: {}", e)))?;
 
-        // Convert OpenRaft response to proto
-        let proto_resp: ProtoAppendEntriesResponse = raft_resp.into();
+        // Convert OpenRaft response to proto, advertising the codecs we can decode
+        let mut proto_resp: ProtoAppendEntriesResponse = raft_resp.into();
+        proto_resp.supported_compression = crate::raft::compression::supported_codecs()
+            .into_iter()
+            .map(|c| c as i32)
+            .collect();
 
         Ok(Response::new(proto_resp))
     }
@@ -97,31 +151,200 @@ impl RaftService for RaftGrpcService {
 
         Ok(Response::new(proto_resp))
     }
+
+    /// Handle FetchSnapshot RPC - streams the latest applied state to a joining node
+    /// Only the leader serves; followers point the joiner at the leader instead
+    async fn fetch_snapshot(
+        &self,
+        request: Request<ProtoFetchSnapshotRequest>,
+    ) -> Result<Response<Self::FetchSnapshotStream>, Status> {
+        let requester_id = request.into_inner().requester_id;
+
+        if let Some(status) = self.not_leader().await {
+            return Err(status);
+        }
+
+        let (meta, data) = self
+            .storage
+            .export_snapshot()
+            .await
+            .map_err(|e| Status::internal(format!("Failed to build snapshot: {}", e)))?;
+        let meta_bytes = bincode::serialize(&meta)
+            .map_err(|e| Status::internal(format!("Failed to encode snapshot meta: {}", e)))?;
+
+        println!(
+            "[Bootstrap] Streaming snapshot up to {:?} ({} bytes) to node {}",
+            meta.last_log_id, data.len(), requester_id
+        );
+
+        let chunks = bootstrap::split_snapshot(meta_bytes, &data, SNAPSHOT_CHUNK_SIZE);
+        Ok(Response::new(Box::pin(futures_util::stream::iter(chunks.into_iter().map(Ok)))))
+    }
+
+    /// Handle StreamMetrics RPC - keeps a worker's latest sample in the metrics view,
+    /// and sends the whole view back every ACK_INTERVAL for the worker to mirror
+    /// Followers refuse like FetchSnapshot does; a leader that steps down mid-stream
+    /// says so in its last ack and ends the stream, and the worker reconnects
+    async fn stream_metrics(
+        &self,
+        request: Request<Streaming<ProtoMetricSample>>,
+    ) -> Result<Response<Self::StreamMetricsStream>, Status> {
+        if let Some(status) = self.not_leader().await {
+            return Err(status);
+        }
+
+        let mut samples = request.into_inner();
+        let (raft, view) = (self.raft.clone(), self.metrics_view.clone());
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<ProtoMetricsAck, Status>>(4);
+        Supervisor::global().spawn_transient("metrics_stream", async move {
+            let mut received = 0u64;
+            // Acks go out on a timer, so a node that only mirrors (sends nothing) still gets the view
+            let mut acks = tokio::time::interval(ACK_INTERVAL);
+            loop {
+                tokio::select! {
+                    sample = samples.message() => {
+                        let Ok(Some(sample)) = sample else {
+                            return;
+                        };
+                        received += 1;
+                        view.record_proto(ProtoMetricSample { age_ms: 0, ..sample }).await;
+                    }
+                    _ = acks.tick() => {
+                        let metrics = raft.metrics().borrow().clone();
+                        let stepped_down = metrics.current_leader != Some(metrics.id);
+                        let view = if stepped_down { Vec::new() } else { view.to_proto().await };
+                        if tx.send(Ok(ProtoMetricsAck { received, stepped_down, view })).await.is_err() || stepped_down {
+                            return;
+                        }
+                    }
+                }
+            }
+        }.map(Ok));
+
+        let acks = futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|ack| (ack, rx)) });
+        Ok(Response::new(Box::pin(acks)))
+    }
+
+    /// Handle Hello RPC - record what the caller supports and answer with our own
+    async fn hello(
+        &self,
+        request: Request<ProtoHelloRequest>,
+    ) -> Result<Response<ProtoHelloReply>, Status> {
+        let request = request.into_inner();
+        let capabilities = PeerCapabilities::from_proto(&request.capabilities.unwrap_or_default());
+        if capabilities.is_other_build() {
+            println!(
+                "[Raft] Node {} says hello from build {}",
+                request.node_id,
+                capabilities.build_version.as_deref().unwrap_or("unknown")
+            );
+        }
+        // Rolling back below the committed version leaves the node unable to apply the log
+        let committed = self.storage.state_machine().read().await.game_state.cluster_version();
+        if capabilities.cluster_version < committed {
+            eprintln!(
+                "[Raft] Node {} applies cluster version {}, older than the committed {} - it will stop at the first event it can't decode",
+                request.node_id, capabilities.cluster_version, committed
+            );
+        }
+        self.registry.set_capabilities(request.node_id, capabilities);
+
+        Ok(Response::new(ProtoHelloReply {
+            node_id: self.raft.metrics().borrow().id,
+            capabilities: Some(PeerCapabilities::local().to_proto()),
+        }))
+    }
+
+    async fn submit_command(
+        &self,
+        request: Request<ProtoCommandRequest>,
+    ) -> Result<Response<ProtoCommandReply>, Status> {
+        if let Some(status) = self.not_leader().await {
+            return Err(status);
+        }
+
+        let relay = request.remote_addr().map(|addr| addr.ip().to_string());
+        let request = request.into_inner();
+        let event: GameEvent = serde_json::from_slice(&request.event)
+            .map_err(|e| Status::invalid_argument(format!("Invalid event: {}", e)))?;
+        let mut origin: CommandOrigin = if request.origin.is_empty() {
+            CommandOrigin::default()
+        } else {
+            serde_json::from_slice(&request.origin)
+                .map_err(|e| Status::invalid_argument(format!("Invalid origin: {}", e)))?
+        };
+        origin.relay = relay;
+
+        // Same refusals as POST /events
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let sm_arc = self.storage.state_machine();
+        let refused = refuse_event(&event, &sm_arc.read().await.game_state, now);
+        if let Some((status, message)) = refused {
+            return Err(match status {
+                axum::http::StatusCode::FORBIDDEN => Status::permission_denied(message),
+                axum::http::StatusCode::TOO_MANY_REQUESTS => Status::resource_exhausted(message),
+                axum::http::StatusCode::CONFLICT => Status::already_exists(message),
+                axum::http::StatusCode::NOT_FOUND => Status::not_found(message),
+                _ => Status::invalid_argument(message),
+            });
+        }
+
+        match self.raft.client_write(GameEventRequest::with_origin(event, origin)).await {
+            Ok(response) => Ok(Response::new(ProtoCommandReply { log_index: response.log_id.index })),
+            Err(e) => Err(match RaftWriteError::from_client_write(e, &self.registry).await {
+                // Lost leadership mid-write: the caller re-discovers the leader
+                RaftWriteError::NotLeader { .. } => {
+                    self.not_leader().await.unwrap_or_else(|| Status::failed_precondition("Not the leader"))
+                }
+                error @ RaftWriteError::Storage { .. } => Status::internal(format!("Failed to commit event: {}", error)),
+                error => Status::unavailable(format!("Failed to commit event: {}", error)),
+            }),
+        }
+    }
 }
 
-/// Start the gRPC server for Raft communication
-/// Returns a JoinHandle that can be awaited or aborted
-pub async fn start_grpc_server(
+/// Serve the Raft gRPC service on `addr` until the server stops
+/// Run under the Supervisor, which restarts it if it errors or panics.
+pub async fn serve_grpc(
     raft: Arc<Raft<GameRaftTypeConfig>>,
+    storage: MemStorage,
+    registry: NodeRegistry,
+    metrics_view: MetricsView,
     addr: String,
-) -> Result<tokio::task::JoinHandle<Result<(), tonic::transport::Error>>, Box<dyn std::error::Error>> {
-    let service = RaftGrpcService::new(raft);
-    let server = RaftServiceServer::new(service);
-
-    let socket_addr = addr
+) -> anyhow::Result<()> {
+    let socket_addr: std::net::SocketAddr = addr
         .parse()
-        .map_err(|e| format!("Invalid address {}: {}", addr, e))?;
+        .map_err(|e| anyhow::anyhow!("Invalid address {}: {}", addr, e))?;
+    let listener = tokio::net::TcpListener::bind(socket_addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to bind {}: {}", socket_addr, e))?;
+
+    serve_grpc_on(raft, storage, registry, metrics_view, listener).await
+}
 
-    println!("Starting Raft gRPC server on {}", socket_addr);
+/// Serve the Raft gRPC service on an already bound listener (e.g. port 0 in the bench harness)
+pub async fn serve_grpc_on(
+    raft: Arc<Raft<GameRaftTypeConfig>>,
+    storage: MemStorage,
+    registry: NodeRegistry,
+    metrics_view: MetricsView,
+    listener: tokio::net::TcpListener,
+) -> anyhow::Result<()> {
+    let service = RaftGrpcService::new(raft, storage, registry, metrics_view);
+    let server = RaftServiceServer::new(service);
 
-    let handle = tokio::spawn(async move {
-        tonic::transport::Server::builder()
-            .add_service(server)
-            .serve(socket_addr)
-            .await
-    });
+    println!("Starting Raft gRPC server on {}", listener.local_addr()?);
 
-    Ok(handle)
+    let incoming = tonic::transport::server::TcpIncoming::from_listener(listener, true, None)
+        .map_err(|e| anyhow::anyhow!("Failed to accept on listener: {}", e))?;
+    tonic::transport::Server::builder()
+        .add_service(server)
+        .serve_with_incoming(incoming)
+        .await?;
+    Ok(())
 }
 
 #[cfg(test)]
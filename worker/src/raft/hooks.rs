@@ -0,0 +1,101 @@
+// Apply hooks - subsystems reacting to committed events
+//
+// Stats, notifications, the effects executor and archives all care about what was
+// just committed. Instead of each one being wired into apply_to_state_machine, they
+// register an ApplyHook on the storage (`MemStorage::hooks`), and the storage calls
+// every hook after each event is applied, in registration order, with the
+// StateChanges the event made - so a hook never has to diff state to see what moved.
+//
+// Hooks run inside apply, with the state machine locked and Raft waiting on it: they
+// must be quick and must not block or panic. Anything slow (network calls, disk)
+// belongs in a task fed by a channel - `ForwardHook` is that channel.
+//
+// A snapshot install replaces the state machine wholesale; hooks hear about it once
+// through `on_snapshot`, not once per event it contains, so a new follower doesn't
+// re-announce the whole game.
+
+use crate::game::state::StateChange;
+use crate::game::GameState;
+use crate::raft::storage::GameEventRequest;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+
+/// Something that reacts to committed events as they are applied
+pub trait ApplyHook: Send + Sync {
+    /// Name for logs
+    fn name(&self) -> &str;
+
+    /// `request` was committed at `log_index` and `game_state` already includes it
+    /// `changes` is what it changed (empty for an event that changed nothing visible)
+    fn on_apply(&self, log_index: u64, request: &GameEventRequest, changes: &[StateChange], game_state: &GameState);
+
+    /// The state machine was replaced by a snapshot up to `log_index`
+    fn on_snapshot(&self, _log_index: u64, _game_state: &GameState) {}
+}
+
+/// The hooks registered on a storage, shared by all its clones
+#[derive(Clone, Default)]
+pub struct ApplyHooks {
+    hooks: Arc<RwLock<Vec<Arc<dyn ApplyHook>>>>,
+}
+
+impl ApplyHooks {
+    /// Call `hook` for every event applied from now on
+    pub async fn register(&self, hook: Arc<dyn ApplyHook>) {
+        println!("[Hooks] Registered apply hook {}", hook.name());
+        self.hooks.write().await.push(hook);
+    }
+
+    pub async fn len(&self) -> usize {
+        self.hooks.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.hooks.read().await.is_empty()
+    }
+
+    pub(crate) async fn applied(&self, log_index: u64, request: &GameEventRequest, changes: &[StateChange], game_state: &GameState) {
+        for hook in self.hooks.read().await.iter() {
+            hook.on_apply(log_index, request, changes, game_state);
+        }
+    }
+
+    pub(crate) async fn snapshot_installed(&self, log_index: u64, game_state: &GameState) {
+        for hook in self.hooks.read().await.iter() {
+            hook.on_snapshot(log_index, game_state);
+        }
+    }
+}
+
+/// A committed event handed to a ForwardHook's receiver
+#[derive(Debug, Clone)]
+pub struct AppliedEvent {
+    pub log_index: u64,
+    pub request: GameEventRequest,
+    pub changes: Vec<StateChange>,
+}
+
+/// Hands every applied event to a task, for subsystems that do slow work with it
+pub struct ForwardHook {
+    name: String,
+    tx: mpsc::UnboundedSender<AppliedEvent>,
+}
+
+impl ForwardHook {
+    /// A hook named `name` and the receiver it forwards to
+    pub fn new(name: impl Into<String>) -> (Arc<Self>, mpsc::UnboundedReceiver<AppliedEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Arc::new(Self { name: name.into(), tx }), rx)
+    }
+}
+
+impl ApplyHook for ForwardHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_apply(&self, log_index: u64, request: &GameEventRequest, changes: &[StateChange], _game_state: &GameState) {
+        // A dropped receiver just means the subsystem is gone
+        let _ = self.tx.send(AppliedEvent { log_index, request: request.clone(), changes: changes.to_vec() });
+    }
+}
@@ -0,0 +1,142 @@
+// Persistent worker identity - the same Raft node after a container restart
+//
+// A worker's Raft node id used to be generated fresh at every start. When ECS
+// replaces a crashed container, the new process came up as an unrelated node and
+// the old id lingered in every registry as a zombie pointing at a dead address.
+//
+// Now a worker's id comes from, in order:
+//   RAFT_NODE_ID         pinned by the orchestrator
+//   identity.json        saved under RAFT_DATA_DIR by an earlier run in the same game
+//                        (which also remembers a coordinate adopted through /adopt)
+//   GAME_ID + NODE_COORD derived, so a replacement task spawned with the same
+//                        environment comes back as the same node even without a volume
+//   fresh                anything else (warm pool workers, the bootstrap node)
+// The worker then commits NodeInitializationComplete again, which moves its registry
+// entry to the new address (see NodeRegistry::register_committed) rather than
+// adding a new node.
+
+use crate::game::NodeCoord;
+use crate::raft::storage::NodeId;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where a worker's identity came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentitySource {
+    Pinned,
+    Saved,
+    Derived,
+    Fresh,
+}
+
+/// Who this worker is in its game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeIdentity {
+    pub node_id: NodeId,
+    pub game_id: String,
+    /// Hex this worker serves, None until it has one
+    #[serde(default)]
+    pub coord: Option<NodeCoord>,
+}
+
+impl NodeIdentity {
+    /// Where identities are saved ($RAFT_DATA_DIR/identity.json), None if RAFT_DATA_DIR is unset
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var("RAFT_DATA_DIR").ok().map(|dir| PathBuf::from(dir).join("identity.json"))
+    }
+
+    /// This worker's identity in `game_id` (see the module comment for the order)
+    /// A saved identity's coordinate fills in for a missing `coord`.
+    pub async fn resolve(path: Option<&Path>, game_id: &str, coord: Option<NodeCoord>) -> (Self, IdentitySource) {
+        let saved = match path {
+            Some(path) => Self::load(path).await.filter(|saved| saved.game_id == game_id),
+            None => None,
+        };
+        let coord = coord.or(saved.as_ref().and_then(|s| s.coord));
+        let identity = |node_id| Self { node_id, game_id: game_id.to_string(), coord };
+
+        if let Some(node_id) = std::env::var("RAFT_NODE_ID").ok().and_then(|s| s.parse().ok()).filter(|id| *id != 0) {
+            return (identity(node_id), IdentitySource::Pinned);
+        }
+        if let Some(saved) = saved {
+            return (identity(saved.node_id), IdentitySource::Saved);
+        }
+        match coord {
+            Some(coord) => (identity(derive_node_id(game_id, coord)), IdentitySource::Derived),
+            None => (identity(super::generate_node_id()), IdentitySource::Fresh),
+        }
+    }
+
+    async fn load(path: &Path) -> Option<Self> {
+        let bytes = match tokio::fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                eprintln!("[Identity] Failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        serde_json::from_slice(&bytes)
+            .map_err(|e| eprintln!("[Identity] Ignoring corrupt identity file {}: {}", path.display(), e))
+            .ok()
+    }
+
+    /// Write to `path` via a temp file + rename, like the registry
+    pub async fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+
+    /// Save to the default path, if there is one, logging any failure
+    pub async fn remember(&self) {
+        if let Some(path) = Self::default_path() {
+            if let Err(e) = self.save(&path).await {
+                eprintln!("[Identity] Failed to save to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Node id for the worker serving `coord` in `game_id` - FNV-1a, never 0
+pub fn derive_node_id(game_id: &str, coord: NodeCoord) -> NodeId {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in format!("{}/{}", game_id, coord).bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_identity_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("camhack-identity-{}", std::process::id()));
+        let path = dir.join("identity.json");
+
+        let (first, source) = NodeIdentity::resolve(Some(&path), "game1", None).await;
+        assert_eq!(source, IdentitySource::Fresh);
+        let adopted = NodeIdentity { coord: Some(NodeCoord::new(2, -1)), ..first.clone() };
+        adopted.save(&path).await.unwrap();
+
+        // Restarted in the same game: same id, and the coordinate it had adopted
+        let (again, source) = NodeIdentity::resolve(Some(&path), "game1", None).await;
+        assert_eq!(source, IdentitySource::Saved);
+        assert_eq!(again, adopted);
+
+        // Nothing saved for this game, but the spawn environment names the hex
+        let coord = NodeCoord::new(3, 0);
+        let (derived, source) = NodeIdentity::resolve(Some(&path), "game2", Some(coord)).await;
+        assert_eq!(source, IdentitySource::Derived);
+        assert_eq!(derived.node_id, derive_node_id("game2", coord));
+        assert_ne!(derived.node_id, derive_node_id("game1", coord));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,85 @@
+// Cluster-internal HTTP listener
+//
+// The worker API is split in two. Player-facing routes (/game/*, /grid/*, /admin/*,
+// the /attack WebSocket) stay on the public port 8080 with permissive CORS and rate
+// limiting. Operations only other processes of the cluster call - event submission
+// and the committed log (/events), /status, /registry and the master's /adopt - are
+// served on a second listener bound to the VPC interface (the task IP, not 0.0.0.0),
+// without CORS. With INTERNAL_API_TOKEN set, every internal request must carry it as
+// `Authorization: Bearer <token>`; the master hands the same token to every task it
+// spawns, and peers attach it when forwarding to the leader.
+
+pub use camhack_web::auth::{authorized, bearer_matches};
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Port of the internal listener on every worker (INTERNAL_API_PORT, default 8082)
+pub const DEFAULT_INTERNAL_PORT: u16 = 8082;
+
+/// Where the internal listener binds and what it requires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternalApiConfig {
+    pub bind_ip: String,
+    pub port: u16,
+    /// Shared cluster secret (None = no token check, the VPC binding is the only guard)
+    pub token: Option<Arc<str>>,
+}
+
+impl InternalApiConfig {
+    /// Load from the environment, binding to `task_ip` unless overridden
+    /// INTERNAL_BIND_IP (default: BIND_IP if set, else the task's VPC address),
+    /// INTERNAL_API_PORT (default: 8082), INTERNAL_API_TOKEN (default: none)
+    pub fn from_env(task_ip: &str) -> Self {
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+        let bind_ip = non_empty("INTERNAL_BIND_IP")
+            .or_else(|| non_empty("BIND_IP"))
+            .unwrap_or_else(|| task_ip.to_string());
+        Self { bind_ip, port: internal_port(), token: internal_token() }
+    }
+
+    /// host:port the listener binds
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.bind_ip, self.port)
+    }
+}
+
+/// Port of the internal listener (INTERNAL_API_PORT, default 8082)
+/// Every worker uses the same one, so peers find it from the host alone.
+pub fn internal_port() -> u16 {
+    std::env::var("INTERNAL_API_PORT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_INTERNAL_PORT)
+}
+
+/// The cluster's internal API token (INTERNAL_API_TOKEN), if any
+pub fn internal_token() -> Option<Arc<str>> {
+    std::env::var("INTERNAL_API_TOKEN").ok().filter(|t| !t.is_empty()).map(Arc::from)
+}
+
+/// URL of `path` on the internal listener of the worker at `host`
+pub fn peer_internal_url(host: &str, path: &str) -> String {
+    crate::tls::peer_http_url(&format!("{}:{}", host, internal_port()), path)
+}
+
+/// `request` with this process's internal token attached, if it has one
+pub fn with_token(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match internal_token() {
+        Some(token) => request.bearer_auth(&*token),
+        None => request,
+    }
+}
+
+/// Middleware refusing internal requests without the cluster token (401)
+pub async fn require_token(State(token): State<Option<Arc<str>>>, request: Request, next: Next) -> Response {
+    if !authorized(token.as_deref(), request.headers()) {
+        return (StatusCode::UNAUTHORIZED, "Missing or wrong internal API token").into_response();
+    }
+    next.run(request).await
+}
@@ -0,0 +1,545 @@
+// Telemetry plane - node metrics outside the replicated log
+//
+// Bandwidth, loss and queued attackers change every few hundred milliseconds and
+// only matter while they're fresh, so they never go through consensus (the log is
+// for events that change ownership, membership or rules). Every worker under attack
+// streams a sample every LIVE_METRICS_MS to the current leader over gRPC
+// (StreamMetrics); the leader keeps the latest one per node in its MetricsView and
+// overlays the fresh ones on committed state for its tick. Each ack it sends back
+// carries its whole view, so every streaming node - players' clients included -
+// mirrors it for /game/state, /game/metrics and the client's own displays. Samples
+// go stale when their stream stops; when leadership moves the old leader says so and
+// the node reconnects to the new one.
+//
+// Samples also carry the worker's own CPU and memory use (host_load.rs), which the
+// leader can count towards overload.
+//
+// Workers mustn't stream in lockstep: each starts at a random offset into the interval
+// and jitters every sleep by METRICS_JITTER_PCT. The interval is the game's committed
+// one (MetricsIntervalSet) once there is one. When a stream to the leader fails the
+// node samples less often, doubling per failure, until the leader acks again.
+//
+// Committed NodeMetricsReports from older logs are still applied by the rules; the
+// view's fresh samples replace them wherever both exist.
+
+use crate::game::history::{MetricsHistory, MetricsSample};
+use crate::game::network::NetworkManager;
+use crate::game::state::{NodeMetrics, MIN_METRICS_INTERVAL_MS};
+use crate::game::{GameState, NodeCoord};
+use crate::host_load::HostLoad;
+use crate::raft::conversions::proto::raft_service_client::RaftServiceClient;
+use crate::raft::conversions::proto::{HexCoord, HostLoadSample, MetricSample};
+use crate::raft::node_registry::NodeRegistry;
+use crate::raft::storage::{GameRaftTypeConfig, GameStateMachine, NodeId};
+use anyhow::{Context, Result};
+use openraft::Raft;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Default interval between streamed samples
+pub const DEFAULT_LIVE_METRICS_MS: u64 = 250;
+
+/// A sample older than this means its stream has stopped - the node has no metrics
+pub const LIVE_SAMPLE_MAX_AGE: Duration = Duration::from_secs(2);
+
+/// How far apart two mirrored copies of one sample may land once their ages are taken
+/// off (network delay between acks); samples are streamed at least MIN_METRICS_INTERVAL_MS apart
+const SAME_SAMPLE_SLACK: Duration = Duration::from_millis(MIN_METRICS_INTERVAL_MS / 2);
+
+/// How often the leader acknowledges a stream with its view (also how fast it notices a dead worker)
+pub const ACK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default spread of each sleep around the interval (percent either way)
+pub const DEFAULT_METRICS_JITTER_PCT: u32 = 20;
+
+/// Most a failing stream's interval doubles (2^this times slower)
+pub const MAX_BACKOFF_DOUBLINGS: u32 = 5;
+
+/// Slowest a backed-off node samples
+pub const MAX_STREAM_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Interval between streamed samples
+/// LIVE_METRICS_MS (default: 250; the plane can't be turned off, 0 means the default)
+pub fn live_metrics_interval() -> Duration {
+    let ms = std::env::var("LIVE_METRICS_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|ms| *ms > 0)
+        .unwrap_or(DEFAULT_LIVE_METRICS_MS);
+    Duration::from_millis(ms)
+}
+
+/// METRICS_JITTER_PCT (default: 20, at most 50; 0 streams on the exact interval)
+fn metrics_jitter() -> f64 {
+    let pct = std::env::var("METRICS_JITTER_PCT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_METRICS_JITTER_PCT);
+    f64::from(pct.min(50)) / 100.0
+}
+
+/// `interval` stretched for `failures` failed streams in a row
+pub fn backed_off(interval: Duration, failures: u32) -> Duration {
+    if failures == 0 {
+        return interval;
+    }
+    interval.saturating_mul(1 << failures.min(MAX_BACKOFF_DOUBLINGS)).min(MAX_STREAM_BACKOFF.max(interval))
+}
+
+/// When a node samples: the game's committed interval (its own LIVE_METRICS_MS until
+/// one is committed), jittered, and backed off while its stream keeps failing
+#[derive(Clone)]
+pub struct StreamCadence {
+    state_machine: Arc<RwLock<GameStateMachine>>,
+    fallback: Duration,
+    jitter: f64,
+    /// Failed streams since the leader last acked
+    failures: Arc<AtomicU32>,
+}
+
+impl StreamCadence {
+    pub fn new(state_machine: Arc<RwLock<GameStateMachine>>) -> Self {
+        Self::with_jitter(state_machine, live_metrics_interval(), metrics_jitter())
+    }
+
+    pub fn with_jitter(state_machine: Arc<RwLock<GameStateMachine>>, fallback: Duration, jitter: f64) -> Self {
+        Self { state_machine, fallback, jitter, failures: Arc::new(AtomicU32::new(0)) }
+    }
+
+    /// The interval before jitter and backoff
+    pub async fn interval(&self) -> Duration {
+        let committed = self.state_machine.read().await.game_state.metrics_interval_ms;
+        committed.map(Duration::from_millis).unwrap_or(self.fallback)
+    }
+
+    /// How long to wait before the next sample
+    pub async fn next(&self) -> Duration {
+        let interval = backed_off(self.interval().await, self.failures.load(Ordering::Relaxed));
+        if self.jitter == 0.0 {
+            return interval;
+        }
+        interval.mul_f64(rand::thread_rng().gen_range(1.0 - self.jitter..=1.0 + self.jitter))
+    }
+
+    /// A random share of one interval, so nodes that connect together don't sample together
+    pub async fn offset(&self) -> Duration {
+        self.interval().await.mul_f64(rand::thread_rng().gen_range(0.0..1.0))
+    }
+
+    /// Count a failed stream, returning how many have failed in a row
+    pub fn failed(&self) -> u32 {
+        self.failures.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// The leader acked - back to the full rate
+    pub fn recovered(&self) {
+        if self.failures.swap(0, Ordering::Relaxed) > 0 {
+            println!("[Telemetry] Leader acking again, back to the full sample rate");
+        }
+    }
+}
+
+/// One streamed sample, as a view keeps it
+#[derive(Debug, Clone)]
+pub struct LiveSample {
+    pub bandwidth_in: u64,
+    pub packet_loss: f32,
+    /// Attackers waiting for a flooder slot on the node
+    pub queued_attackers: Vec<NodeCoord>,
+    /// The worker's own load, None until it has a reading
+    pub host_load: Option<HostLoad>,
+    pub received_at: Instant,
+}
+
+/// Latest metrics per node, and their recent history - derived, never committed
+#[derive(Clone)]
+pub struct MetricsView {
+    samples: Arc<RwLock<HashMap<NodeCoord, LiveSample>>>,
+    history: Arc<RwLock<MetricsHistory>>,
+    /// Bumped on every sample (and when samples age out), so pollers can tell the view changed
+    version: Arc<AtomicU64>,
+}
+
+impl Default for MetricsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsView {
+    pub fn new() -> Self {
+        Self {
+            samples: Arc::new(RwLock::new(HashMap::new())),
+            history: Arc::new(RwLock::new(MetricsHistory::from_env())),
+            version: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Keep a sample for `coord` that is `age` old already
+    /// One no newer than the sample already held is dropped, history included.
+    pub async fn record(
+        &self,
+        coord: NodeCoord,
+        bandwidth_in: u64,
+        packet_loss: f32,
+        queued_attackers: Vec<NodeCoord>,
+        host_load: Option<HostLoad>,
+        age: Duration,
+    ) {
+        let received_at = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+        let entry = MetricsSample {
+            timestamp: (SystemTime::now() - age).duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            bandwidth_in,
+            packet_loss,
+            queued_attackers: queued_attackers.len(),
+            cpu_utilization: host_load.map(|load| load.cpu_utilization),
+            memory_pressure: host_load.map(|load| load.memory_pressure),
+        };
+
+        {
+            let mut samples = self.samples.write().await;
+            // Every ack repeats the leader's view: a sample taken no later than the one
+            // held is that one again (or an older one), not news
+            if samples.get(&coord).is_some_and(|held| received_at <= held.received_at + SAME_SAMPLE_SLACK) {
+                return;
+            }
+            samples.insert(coord, LiveSample { bandwidth_in, packet_loss, queued_attackers, host_load, received_at });
+        }
+        self.history.write().await.record(coord, entry);
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Samples young enough to trust, by node
+    pub async fn fresh(&self) -> HashMap<NodeCoord, LiveSample> {
+        let mut samples = self.samples.write().await;
+        let before = samples.len();
+        samples.retain(|_, s| s.received_at.elapsed() <= LIVE_SAMPLE_MAX_AGE);
+        if samples.len() != before {
+            self.version.fetch_add(1, Ordering::Relaxed);
+        }
+        samples.clone()
+    }
+
+    /// Changes whenever a sample arrives or ages out
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// History for `coord` at or after `since` (unix seconds), oldest first
+    pub async fn series(&self, coord: &NodeCoord, since: u64) -> Vec<MetricsSample> {
+        self.history.read().await.series(coord, since)
+    }
+
+    /// The whole history, every node's series
+    pub async fn history(&self) -> Vec<(NodeCoord, MetricsSample)> {
+        self.history.read().await.all()
+    }
+
+    /// Put fresh samples into `game_state.node_metrics`, for anything reading metrics from state
+    pub async fn overlay(&self, game_state: &mut GameState) {
+        let clock = game_state.clock;
+        for (coord, sample) in self.fresh().await {
+            if !game_state.nodes.contains_key(&coord) {
+                continue;
+            }
+            let metrics = game_state.node_metrics.entry(coord).or_insert_with(|| NodeMetrics {
+                bandwidth_in: 0,
+                packet_loss: 0.0,
+                timestamp: clock,
+                queued_attackers: Vec::new(),
+                cpu_utilization: None,
+                memory_pressure: None,
+            });
+            metrics.bandwidth_in = sample.bandwidth_in;
+            metrics.packet_loss = sample.packet_loss;
+            metrics.queued_attackers = sample.queued_attackers;
+            metrics.cpu_utilization = sample.host_load.map(|load| load.cpu_utilization);
+            metrics.memory_pressure = sample.host_load.map(|load| load.memory_pressure);
+            metrics.timestamp = metrics.timestamp.max(clock);
+        }
+    }
+
+    /// Fresh samples as the leader sends them back on every stream
+    pub async fn to_proto(&self) -> Vec<MetricSample> {
+        self.fresh()
+            .await
+            .into_iter()
+            .map(|(coord, sample)| MetricSample {
+                node_id: 0,
+                q: coord.q,
+                r: coord.r,
+                bandwidth_in: sample.bandwidth_in,
+                packet_loss: sample.packet_loss,
+                queued_attackers: sample.queued_attackers.iter().map(|c| HexCoord { q: c.q, r: c.r }).collect(),
+                age_ms: sample.received_at.elapsed().as_millis() as u32,
+                host_load: sample.host_load.map(host_load_to_proto),
+            })
+            .collect()
+    }
+
+    /// Keep a sample from a stream or from the leader's view
+    pub async fn record_proto(&self, sample: MetricSample) {
+        let queued = sample.queued_attackers.iter().map(|c| NodeCoord::new(c.q, c.r)).collect();
+        let age = Duration::from_millis(sample.age_ms.into());
+        let host_load = sample.host_load.map(|load| HostLoad {
+            cpu_utilization: load.cpu_utilization.clamp(0.0, 1.0),
+            memory_pressure: load.memory_pressure.clamp(0.0, 1.0),
+        });
+        self.record(NodeCoord::new(sample.q, sample.r), sample.bandwidth_in, sample.packet_loss, queued, host_load, age).await;
+    }
+}
+
+fn host_load_to_proto(load: HostLoad) -> HostLoadSample {
+    HostLoadSample { cpu_utilization: load.cpu_utilization, memory_pressure: load.memory_pressure }
+}
+
+/// Stream this node's samples to whichever node leads and mirror its view,
+/// following leadership as it moves
+/// A node with no `network` (a player's client) only mirrors. Runs under the
+/// Supervisor: a failed connection returns and is retried with backoff, and the
+/// node samples more slowly until the leader acks again (see StreamCadence).
+pub async fn stream_to_leader(
+    raft: Arc<Raft<GameRaftTypeConfig>>,
+    registry: NodeRegistry,
+    view: MetricsView,
+    network: Option<Arc<RwLock<NetworkManager>>>,
+    cadence: StreamCadence,
+) -> Result<()> {
+    loop {
+        let metrics = raft.metrics().borrow().clone();
+        let Some(leader) = metrics.current_leader else {
+            tokio::time::sleep(cadence.next().await).await;
+            continue;
+        };
+
+        // The leader samples straight into its own view
+        if leader == metrics.id {
+            if let Some(network) = &network {
+                if let Some((coord, bandwidth_in, packet_loss, queued, host_load)) = network.read().await.live_sample().await {
+                    view.record(coord, bandwidth_in, packet_loss, queued, host_load, Duration::ZERO).await;
+                }
+            }
+            tokio::time::sleep(cadence.next().await).await;
+            continue;
+        }
+
+        let addr = registry
+            .get_address(leader)
+            .await
+            .with_context(|| format!("No address for leader {}", leader))?;
+        if let Err(e) = stream_once(&raft, metrics.id, leader, &addr, &view, network.clone(), cadence.clone()).await {
+            let failures = cadence.failed();
+            let interval = backed_off(cadence.interval().await, failures);
+            println!("[Telemetry] Stream failed {} time(s) in a row, sampling every ~{:?} until the leader acks", failures, interval);
+            return Err(e);
+        }
+    }
+}
+
+/// One StreamMetrics call to `leader`, until it steps down or we see someone else lead
+async fn stream_once(
+    raft: &Arc<Raft<GameRaftTypeConfig>>,
+    node_id: NodeId,
+    leader: NodeId,
+    addr: &str,
+    view: &MetricsView,
+    network: Option<Arc<RwLock<NetworkManager>>>,
+    cadence: StreamCadence,
+) -> Result<()> {
+    let mut client = RaftServiceClient::connect(format!("http://{}", addr))
+        .await
+        .with_context(|| format!("Failed to connect to leader at {}", addr))?;
+    println!("[Telemetry] Streaming samples to leader {} at {}", leader, addr);
+
+    // Ends (closing our half of the stream) once leadership has moved on; the first
+    // sample waits a random offset, the rest the cadence's jittered interval
+    let first = Some(cadence.offset().await);
+    let state = (raft.clone(), network, cadence.clone(), first);
+    let samples = futures_util::stream::unfold(state, move |(raft, network, cadence, mut first)| async move {
+        loop {
+            let wait = match first.take() {
+                Some(offset) => offset,
+                None => cadence.next().await,
+            };
+            tokio::time::sleep(wait).await;
+            let current_leader = raft.metrics().borrow().current_leader;
+            if current_leader != Some(leader) {
+                return None;
+            }
+            let Some(manager) = &network else {
+                continue;
+            };
+            let sample = manager.read().await.live_sample().await;
+            if let Some((coord, bandwidth_in, packet_loss, queued, host_load)) = sample {
+                let sample = MetricSample {
+                    node_id,
+                    q: coord.q,
+                    r: coord.r,
+                    bandwidth_in,
+                    packet_loss,
+                    queued_attackers: queued.iter().map(|c| HexCoord { q: c.q, r: c.r }).collect(),
+                    age_ms: 0,
+                    host_load: host_load.map(host_load_to_proto),
+                };
+                return Some((sample, (raft, network, cadence, None)));
+            }
+        }
+    });
+
+    let mut acks = client
+        .stream_metrics(samples)
+        .await
+        .context("StreamMetrics refused")?
+        .into_inner();
+    while let Some(ack) = acks.message().await.context("Metrics stream failed")? {
+        cadence.recovered();
+        if ack.stepped_down {
+            println!("[Telemetry] {} stepped down as leader, reconnecting", addr);
+            break;
+        }
+        for sample in ack.view {
+            view.record_proto(sample).await;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Node, NodeType};
+    use crate::game::state::NodeInitState;
+
+    #[tokio::test]
+    async fn test_overlay_prefers_fresh_samples() {
+        let mut game_state = GameState::new();
+        for q in 0..2 {
+            let coord = NodeCoord::new(q, 0);
+            let node = Node {
+                coord,
+                owner_id: 1,
+                node_type: NodeType::Regular,
+                current_target: None,
+                init_state: NodeInitState::Ready,
+            };
+            game_state.nodes.insert(coord, node);
+        }
+        // A report committed by an older worker
+        let committed = NodeMetrics {
+            bandwidth_in: 10,
+            packet_loss: 0.0,
+            timestamp: 100,
+            queued_attackers: vec![NodeCoord::new(2, 0)],
+            cpu_utilization: None,
+            memory_pressure: None,
+        };
+        game_state.node_metrics.insert(NodeCoord::new(0, 0), committed);
+
+        let view = MetricsView::new();
+        view.record(NodeCoord::new(0, 0), 5000, 0.6, vec![NodeCoord::new(3, 0)], None, Duration::ZERO).await;
+        view.record(NodeCoord::new(1, 0), 7000, 0.3, Vec::new(), None, Duration::ZERO).await;
+        view.record(NodeCoord::new(9, 9), 1, 1.0, Vec::new(), None, Duration::ZERO).await; // No such node
+        view.overlay(&mut game_state).await;
+
+        let metrics = &game_state.node_metrics[&NodeCoord::new(0, 0)];
+        assert_eq!((metrics.bandwidth_in, metrics.packet_loss), (5000, 0.6));
+        assert_eq!(metrics.queued_attackers, vec![NodeCoord::new(3, 0)]);
+        assert_eq!(game_state.node_metrics[&NodeCoord::new(1, 0)].packet_loss, 0.3);
+        assert!(!game_state.node_metrics.contains_key(&NodeCoord::new(9, 9)));
+        assert_eq!(view.version(), 3);
+        assert_eq!(view.series(&NodeCoord::new(0, 0), 0).await[0].queued_attackers, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_samples_are_dropped() {
+        let view = MetricsView::new();
+        view.record(NodeCoord::new(0, 0), 5000, 0.6, Vec::new(), None, Duration::ZERO).await;
+        view.samples.write().await.get_mut(&NodeCoord::new(0, 0)).unwrap().received_at -=
+            LIVE_SAMPLE_MAX_AGE + Duration::from_millis(1);
+        assert!(view.fresh().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mirrored_view_keeps_the_leaders_age() {
+        let leader = MetricsView::new();
+        let load = HostLoad { cpu_utilization: 0.95, memory_pressure: 0.4 };
+        leader.record(NodeCoord::new(0, 0), 5000, 0.6, vec![NodeCoord::new(1, 0)], Some(load), Duration::ZERO).await;
+        leader.record(NodeCoord::new(2, 0), 100, 0.1, Vec::new(), None, LIVE_SAMPLE_MAX_AGE - Duration::from_millis(50)).await;
+
+        let mirror = MetricsView::new();
+        for sample in leader.to_proto().await {
+            mirror.record_proto(sample).await;
+        }
+        let fresh = mirror.fresh().await;
+        assert_eq!(fresh[&NodeCoord::new(0, 0)].queued_attackers, vec![NodeCoord::new(1, 0)]);
+        assert_eq!(fresh[&NodeCoord::new(0, 0)].host_load, Some(load));
+        assert_eq!(fresh[&NodeCoord::new(2, 0)].host_load, None);
+
+        // Nearly stale at the leader, so nearly stale here too
+        mirror.samples.write().await.get_mut(&NodeCoord::new(2, 0)).unwrap().received_at -= Duration::from_millis(100);
+        assert!(!mirror.fresh().await.contains_key(&NodeCoord::new(2, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_acks_record_a_sample_once() {
+        let leader = MetricsView::new();
+        leader.record(NodeCoord::new(0, 0), 5000, 0.6, Vec::new(), None, Duration::from_secs(1)).await;
+        let acked = leader.to_proto().await;
+
+        let mirror = MetricsView::new();
+        for sample in &acked {
+            mirror.record_proto(sample.clone()).await;
+        }
+        let version = mirror.version();
+        let history = mirror.history().await;
+        // Stamped when the leader got it, not when the ack arrived
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!(history[0].1.timestamp < now);
+
+        // A second later the next ack carries the same sample, a second older
+        mirror.samples.write().await.get_mut(&NodeCoord::new(0, 0)).unwrap().received_at -= Duration::from_secs(1);
+        for sample in &acked {
+            mirror.record_proto(MetricSample { age_ms: sample.age_ms + 1000, ..sample.clone() }).await;
+        }
+        assert_eq!(mirror.version(), version);
+        assert_eq!(mirror.history().await, history);
+
+        // A newer sample still lands
+        mirror.record_proto(MetricSample { age_ms: 0, ..acked[0].clone() }).await;
+        assert_eq!(mirror.version(), version + 1);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_a_cap() {
+        let interval = Duration::from_millis(250);
+        assert_eq!(backed_off(interval, 0), interval);
+        assert_eq!(backed_off(interval, 1), Duration::from_millis(500));
+        assert_eq!(backed_off(interval, 3), Duration::from_secs(2));
+        assert_eq!(backed_off(interval, 40), MAX_STREAM_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_cadence_follows_the_committed_interval() {
+        let state_machine = crate::raft::storage::MemStorage::new().state_machine();
+        let cadence = StreamCadence::with_jitter(state_machine.clone(), Duration::from_millis(250), 0.0);
+        assert_eq!(cadence.next().await, Duration::from_millis(250));
+
+        state_machine.write().await.game_state.metrics_interval_ms = Some(400);
+        assert_eq!(cadence.next().await, Duration::from_millis(400));
+        assert_eq!(cadence.clone().failed(), 1);
+        assert_eq!(cadence.next().await, Duration::from_millis(800));
+        cadence.recovered();
+        assert_eq!(cadence.next().await, Duration::from_millis(400));
+
+        let jittered = StreamCadence::with_jitter(state_machine, Duration::from_millis(250), 0.2);
+        for _ in 0..20 {
+            let wait = jittered.next().await;
+            assert!(wait >= Duration::from_millis(320) && wait <= Duration::from_millis(480), "{:?}", wait);
+            assert!(jittered.offset().await < Duration::from_millis(400));
+        }
+    }
+}
@@ -1,16 +1,37 @@
 pub mod api;
+pub mod audit;
+pub mod bootstrap;
+pub mod capabilities;
+pub mod command_client;
+pub mod compression;
 pub mod conversions;
+pub mod digest;
 pub mod grpc_server;
+pub mod hooks;
+pub mod identity;
+pub mod internal;
+pub mod metrics_view;
 pub mod network;
 pub mod node_registry;
+pub mod ratelimit;
+pub mod read_view;
+pub mod snapshot_store;
+pub mod spectate;
 pub mod storage;
+pub mod write_error;
 
 use crate::registry::PeerInfo;
+use crate::request_id::RequestIdExt;
+use crate::supervisor::{RestartPolicy, Supervisor};
 use anyhow::Result;
+use command_client::CommandClient;
+use futures_util::FutureExt;
+use metrics_view::MetricsView;
 use network::GrpcNetworkFactory;
 use node_registry::NodeRegistry;
 use openraft::storage::Adaptor;
 use openraft::{Config, Raft};
+use snapshot_store::SnapshotStore;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 use storage::{GameRaftTypeConfig, MemStorage};
@@ -23,6 +44,10 @@ pub struct RaftNode {
     pub raft: Arc<Raft<GameRaftTypeConfig>>,
     pub registry: NodeRegistry,
     pub storage: Arc<tokio::sync::RwLock<MemStorage>>,
+    /// Node metrics from the telemetry plane, outside the log (see metrics_view)
+    pub metrics_view: MetricsView,
+    /// Submits commands through the leader (a learner can't propose itself)
+    pub commands: CommandClient,
 }
 
 impl RaftNode {
@@ -31,16 +56,27 @@ impl RaftNode {
         node_id: NodeId,
         _my_ip: String,
         registry: NodeRegistry,
+        snapshots: Option<SnapshotStore>,
     ) -> Result<Self> {
         // Create storage - keep a reference for queries
-        let storage = MemStorage::new();
+        // The registry is updated by storage as node announcements are committed
+        let storage = MemStorage::with_registry(registry.clone()).with_snapshot_store(snapshots);
+        Self::with_storage(node_id, registry, storage).await
+    }
 
+    /// Create a Raft node on top of already-populated storage (e.g. a hot-bootstrap snapshot)
+    pub async fn with_storage(
+        node_id: NodeId,
+        registry: NodeRegistry,
+        storage: MemStorage,
+    ) -> Result<Self> {
         // Clone storage for Adaptor (both share the same underlying Arc references)
         let storage_for_adaptor = storage.clone_storage();
         let (log_store, state_machine) = Adaptor::new(storage_for_adaptor);
 
-        // Create network factory
-        let network = GrpcNetworkFactory::new(registry.clone());
+        // Create network factory, following the registry so moved peers get a new channel
+        let network = GrpcNetworkFactory::new(node_id, registry.clone());
+        Supervisor::global().spawn_transient("registry_watch", network.clone().watch_registry().map(Ok));
 
         // Configure OpenRaft with appropriate timeouts
         let config = Arc::new(Config {
@@ -57,11 +93,14 @@ impl RaftNode {
         let raft = Raft::new(node_id, config, network, log_store, state_machine)
             .await?;
 
+        let raft = Arc::new(raft);
         Ok(Self {
             node_id,
-            raft: Arc::new(raft),
+            commands: CommandClient::new(raft.clone(), registry.clone()),
+            raft,
             registry,
             storage: Arc::new(tokio::sync::RwLock::new(storage)),
+            metrics_view: MetricsView::new(),
         })
     }
 
@@ -79,10 +118,12 @@ impl RaftNode {
 }
 
 /// Bootstrap a new Raft cluster (first worker)
+/// Snapshots are written to `snapshots`, but none is loaded: a new cluster starts empty.
 pub async fn bootstrap_cluster(
     node_id: NodeId,
     my_ip: String,
     registry: NodeRegistry,
+    snapshots: Option<SnapshotStore>,
 ) -> Result<Arc<RaftNode>> {
     println!("Bootstrapping new Raft cluster as node {}", node_id);
     println!("This node will become the initial leader");
@@ -91,7 +132,7 @@ pub async fn bootstrap_cluster(
     registry.register(node_id, format!("{}:5000", my_ip)).await;
 
     // Create Raft node
-    let node = RaftNode::new(node_id, my_ip.clone(), registry).await?;
+    let node = RaftNode::new(node_id, my_ip.clone(), registry, snapshots).await?;
 
     // Initialize as single-node cluster
     let mut members = BTreeMap::new();
@@ -106,22 +147,80 @@ pub async fn bootstrap_cluster(
 
     // Start gRPC server for Raft communication
     let raft_clone = node.raft.clone();
-    let addr = format!("0.0.0.0:5000");
-    tokio::spawn(async move {
-        if let Err(e) = grpc_server::start_grpc_server(raft_clone, addr).await {
-            eprintln!("gRPC server error: {}", e);
-        }
-    });
+    let storage_clone = node.storage.read().await.clone_storage();
+    let registry_clone = node.registry.clone();
+    let addr = format!("{}:5000", crate::metadata::bind_ip());
+    spawn_grpc_server(raft_clone, storage_clone, registry_clone, node.metrics_view.clone(), addr);
 
     Ok(Arc::new(node))
 }
 
+/// Serve Raft gRPC under the global supervisor, restarted if it errors or panics
+fn spawn_grpc_server(
+    raft: Arc<Raft<GameRaftTypeConfig>>,
+    storage: MemStorage,
+    registry: NodeRegistry,
+    metrics_view: MetricsView,
+    addr: String,
+) {
+    Supervisor::global().spawn("raft_grpc", RestartPolicy::always(), move || {
+        grpc_server::serve_grpc(raft.clone(), storage.clone_storage(), registry.clone(), metrics_view.clone(), addr.clone())
+    });
+}
+
+/// Commit `event`, through the leader's POST /events if this node isn't the leader
+/// For facts only a follower knows, e.g. a node announcing itself ready.
+pub async fn commit_via_leader(
+    raft: &Raft<GameRaftTypeConfig>,
+    registry: &NodeRegistry,
+    event: crate::game::GameEvent,
+) -> Result<()> {
+    let metrics = raft.metrics().borrow().clone();
+    let leader = metrics
+        .current_leader
+        .ok_or(write_error::RaftWriteError::NotLeader { leader_id: None, leader_api_addr: None })?;
+
+    if leader == metrics.id {
+        if let Err(e) = raft.client_write(storage::GameEventRequest::new(event)).await {
+            return Err(write_error::RaftWriteError::from_client_write(e, registry).await.into());
+        }
+        return Ok(());
+    }
+
+    // The leader's internal API listens on the host its Raft address names
+    let raft_addr = registry
+        .get_address(leader)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("No address for leader {}", leader))?;
+    let host = raft_addr.rsplit_once(':').map_or(raft_addr.as_str(), |(host, _)| host);
+
+    let request = reqwest::Client::new().post(internal::peer_internal_url(host, "/events"));
+    let response: api::SubmitEventResponse = internal::with_token(request)
+        .request_id()
+        .json(&api::SubmitEventRequest { event })
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await?
+        .json()
+        .await?;
+    if !response.success {
+        // Keep the leader's typed error, so callers can still tell why it failed
+        return Err(match response.error {
+            Some(info) => anyhow::Error::new(info.error).context("Leader rejected event"),
+            None => anyhow::anyhow!("Leader rejected event: {}", response.message),
+        });
+    }
+    Ok(())
+}
+
 /// Join an existing Raft cluster (subsequent workers)
+/// Starts from the newest snapshot in `snapshots` if there is one, else from the leader's.
 pub async fn join_cluster(
     node_id: NodeId,
     my_ip: String,
     peer: PeerInfo,
     registry: NodeRegistry,
+    snapshots: Option<SnapshotStore>,
 ) -> Result<Arc<RaftNode>> {
     println!("Joining existing Raft cluster as node {}", node_id);
     println!("Connecting to peer: {}:{}", peer.ip, peer.port);
@@ -132,17 +231,46 @@ pub async fn join_cluster(
     // Register the peer we know about
     registry.register(999, format!("{}:{}", peer.ip, peer.port)).await; // Temporary ID for peer
 
+    // A snapshot this node saved before a restart saves fetching one
+    let mut storage = MemStorage::with_registry(registry.clone()).with_snapshot_store(snapshots.clone());
+    let local = match &snapshots {
+        Some(store) => store.load_latest().await,
+        None => None,
+    };
+    let mut restored = false;
+    if let Some((meta, data)) = local {
+        match storage.install_bootstrap_snapshot(&meta, data).await {
+            Ok(()) => {
+                println!("✓ Restored local snapshot up to {:?}", meta.last_log_id);
+                restored = true;
+            }
+            Err(e) => eprintln!("⚠ Local snapshot unusable, starting without it: {}", e),
+        }
+    }
+
+    // Hot bootstrap: start from the leader's snapshot instead of replaying the whole log
+    if !restored && bootstrap::hot_bootstrap_enabled() {
+        let peer_addr = format!("{}:{}", peer.ip, peer.port);
+        match bootstrap::fetch_snapshot(&peer_addr, node_id).await {
+            Ok((meta, data)) if meta.last_log_id.is_some() => {
+                let bytes = data.len();
+                storage.install_bootstrap_snapshot(&meta, data).await?;
+                println!("✓ Installed leader snapshot up to {:?} ({} bytes)", meta.last_log_id, bytes);
+            }
+            Ok(_) => println!("Leader has no state yet, starting from an empty log"),
+            Err(e) => eprintln!("⚠ Hot bootstrap unavailable, replaying log instead: {}", e),
+        }
+    }
+
     // Create Raft node
-    let node = RaftNode::new(node_id, my_ip.clone(), registry).await?;
+    let node = RaftNode::with_storage(node_id, registry, storage).await?;
 
     // Start gRPC server for Raft communication BEFORE joining
     let raft_clone = node.raft.clone();
-    let addr = format!("0.0.0.0:5000");
-    tokio::spawn(async move {
-        if let Err(e) = grpc_server::start_grpc_server(raft_clone, addr).await {
-            eprintln!("gRPC server error: {}", e);
-        }
-    });
+    let storage_clone = node.storage.read().await.clone_storage();
+    let registry_clone = node.registry.clone();
+    let addr = format!("{}:5000", crate::metadata::bind_ip());
+    spawn_grpc_server(raft_clone, storage_clone, registry_clone, node.metrics_view.clone(), addr);
 
     // Give server a moment to start
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -1,4 +1,7 @@
+use crate::raft::capabilities::{PeerCapabilities, HELLO_TIMEOUT};
+use crate::raft::compression::{self, CompressionConfig};
 use crate::raft::conversions::proto::raft_service_client::RaftServiceClient;
+use crate::raft::conversions::proto::{Compression, HelloRequest};
 use crate::raft::node_registry::NodeRegistry;
 use crate::raft::storage::{GameRaftTypeConfig, NodeId};
 use openraft::error::{InstallSnapshotError, RPCError, RaftError};
@@ -8,8 +11,15 @@ use openraft::raft::{
     VoteRequest, VoteResponse,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tonic::transport::Channel;
+use tonic::transport::{Channel, Endpoint};
+
+/// How long opening a channel to a peer may take
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A peer's gRPC client, with the address it was opened to
+type CachedClient = (String, RaftServiceClient<Channel>);
 
 /// Simple network error wrapper
 #[derive(Debug, Clone)]
@@ -34,31 +44,50 @@ impl std::error::Error for NetworkError {}
 /// Network factory for creating gRPC connections to Raft peers
 #[derive(Clone)]
 pub struct GrpcNetworkFactory {
+    /// This node, as it introduces itself in Hello
+    node_id: NodeId,
     /// Registry mapping NodeId to network address
     registry: NodeRegistry,
     /// Cached gRPC clients for reuse
-    clients: Arc<RwLock<std::collections::HashMap<NodeId, RaftServiceClient<Channel>>>>,
+    clients: Arc<RwLock<std::collections::HashMap<NodeId, CachedClient>>>,
+    /// AppendEntries compression settings
+    compression: CompressionConfig,
+    /// Codecs each peer advertised in its last AppendEntries response
+    peer_codecs: Arc<RwLock<std::collections::HashMap<NodeId, Vec<Compression>>>>,
 }
 
 impl GrpcNetworkFactory {
     /// Create a new network factory with the given node registry
-    pub fn new(registry: NodeRegistry) -> Self {
+    /// Compression settings are read from RAFT_COMPRESSION / RAFT_COMPRESSION_MIN_BYTES
+    pub fn new(node_id: NodeId, registry: NodeRegistry) -> Self {
+        Self::with_compression(node_id, registry, CompressionConfig::from_env())
+    }
+
+    /// Create a new network factory with explicit compression settings
+    pub fn with_compression(node_id: NodeId, registry: NodeRegistry, compression: CompressionConfig) -> Self {
         Self {
+            node_id,
             registry,
             clients: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            compression,
+            peer_codecs: Arc::new(RwLock::new(std::collections::HashMap::new())),
         }
     }
 
+    /// Codecs the target has advertised (empty until its first response)
+    async fn peer_codecs(&self, target: NodeId) -> Vec<Compression> {
+        self.peer_codecs
+            .read()
+            .await
+            .get(&target)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Get or create a gRPC client for the target node
+    /// A cached client is only reused while the registry still has the address it
+    /// was opened to, in case `watch_registry` missed a change.
     async fn get_client(&self, target: NodeId) -> Result<RaftServiceClient<Channel>, RPCError<NodeId, (), RaftError<NodeId>>> {
-        // Check if we have a cached client
-        {
-            let clients = self.clients.read().await;
-            if let Some(client) = clients.get(&target) {
-                return Ok(client.clone());
-            }
-        }
-
         // Get the address from registry
         let addr = self
             .registry
@@ -70,21 +99,111 @@ impl GrpcNetworkFactory {
                 )))
             })?;
 
+        // Check if we have a cached client for that address
+        if let Some((open_addr, client)) = self.clients.read().await.get(&target) {
+            if *open_addr == addr {
+                return Ok(client.clone());
+            }
+        }
+
         // Create new client
         let endpoint = format!("http://{}", addr);
-        let client = RaftServiceClient::connect(endpoint.clone())
+        let unreachable = |e: &dyn std::fmt::Display| {
+            RPCError::Unreachable(openraft::error::Unreachable::new(&NetworkError::new(
+                format!("Failed to connect to {}: {}", endpoint, e)
+            )))
+        };
+        let channel = Endpoint::from_shared(endpoint.clone())
+            .map_err(|e| unreachable(&e))?
+            .connect_timeout(CONNECT_TIMEOUT)
+            .connect()
             .await
-            .map_err(|e| {
-                RPCError::Unreachable(openraft::error::Unreachable::new(&NetworkError::new(
-                    format!("Failed to connect to {}: {}", endpoint, e)
-                )))
-            })?;
+            .map_err(|e| unreachable(&e))?;
+        let mut client = RaftServiceClient::new(channel);
+        self.hello(target, &mut client).await;
 
         // Cache the client
-        self.clients.write().await.insert(target, client.clone());
+        self.clients.write().await.insert(target, (addr, client.clone()));
 
         Ok(client)
     }
+
+    /// Exchange capabilities with `target` on a freshly opened channel
+    /// Its codecs seed `peer_codecs`, so compression can start before its first
+    /// AppendEntries response. A peer that doesn't answer in time is left unknown and
+    /// greeted again on the next channel; the channel is used either way.
+    async fn hello(&self, target: NodeId, client: &mut RaftServiceClient<Channel>) {
+        let request = HelloRequest { node_id: self.node_id, capabilities: Some(PeerCapabilities::local().to_proto()) };
+        let capabilities = match tokio::time::timeout(HELLO_TIMEOUT, client.hello(request)).await {
+            Ok(Ok(reply)) => PeerCapabilities::from_proto(&reply.into_inner().capabilities.unwrap_or_default()),
+            Ok(Err(status)) if status.code() == tonic::Code::Unimplemented => PeerCapabilities::legacy(),
+            Ok(Err(status)) => {
+                eprintln!("[Raft] Hello to node {} failed: {}", target, status.message());
+                return;
+            }
+            Err(_) => {
+                eprintln!("[Raft] Hello to node {} timed out", target);
+                return;
+            }
+        };
+
+        if capabilities.is_other_build() {
+            println!(
+                "[Raft] Node {} runs build {} (this is {})",
+                target,
+                capabilities.build_version.as_deref().unwrap_or("older than Hello"),
+                crate::raft::capabilities::BUILD_VERSION
+            );
+        }
+        let codecs = capabilities.codecs();
+        if !codecs.is_empty() {
+            self.peer_codecs.write().await.insert(target, codecs);
+        }
+        self.registry.set_capabilities(target, capabilities);
+    }
+
+    /// Pass an RPC result through, dropping the channel to `target` if it couldn't be
+    /// reached: a restarted peer is greeted again when it's back, as it may run
+    /// another build now (its capabilities are unknown until then)
+    async fn check_reachable<T>(&self, target: NodeId, result: Result<T, tonic::Status>) -> Result<T, tonic::Status> {
+        if let Err(status) = &result {
+            if status.code() == tonic::Code::Unavailable && self.clients.read().await.contains_key(&target) {
+                self.forget(target).await;
+                self.registry.forget_capabilities(target);
+            }
+        }
+        result
+    }
+
+    /// Drop the cached client and advertised codecs of `target` (a restarted node may
+    /// be another build)
+    async fn forget(&self, target: NodeId) {
+        self.clients.write().await.remove(&target);
+        self.peer_codecs.write().await.remove(&target);
+    }
+
+    /// Follow the registry's address changes for the life of the process: drop the
+    /// channel to a node's old address right away and open one to the new address, so
+    /// the next RPC neither hits the dead channel nor waits for a connect
+    /// Subscribes when called, so no change made after this returns is missed.
+    pub fn watch_registry(self) -> impl std::future::Future<Output = ()> + Send {
+        let mut changes = self.registry.subscribe();
+        async move {
+            while changes.changed().await.is_ok() {
+                let Some(change) = changes.borrow_and_update().clone() else {
+                    continue;
+                };
+                self.forget(change.node_id).await;
+                if let Some(addr) = change.addr {
+                    match self.get_client(change.node_id).await {
+                        Ok(_) => println!("[Raft] Reconnected to node {} at {}", change.node_id, addr),
+                        // Raft retries on its next RPC
+                        Err(e) => eprintln!("[Raft] Node {} moved to {}, not reachable yet: {}", change.node_id, addr, e),
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl RaftNetworkFactory<GameRaftTypeConfig> for GrpcNetworkFactory {
@@ -113,11 +232,20 @@ impl RaftNetwork<GameRaftTypeConfig> for GrpcNetwork {
         let mut client = self.factory.get_client(self.target).await?;
 
         // Convert OpenRaft request to proto
-        let proto_req: crate::raft::conversions::proto::AppendEntriesRequest = req.into();
+        let mut proto_req: crate::raft::conversions::proto::AppendEntriesRequest = req.into();
+
+        // Compress large batches if the peer has advertised support for our codec
+        let peer_codecs = self.factory.peer_codecs(self.target).await;
+        if let Err(e) = compression::compress_request(&mut proto_req, &self.factory.compression, &peer_codecs) {
+            // Entries are left inline on failure, so the request is still valid
+            eprintln!("[Raft] Failed to compress AppendEntries for node {}: {}", self.target, e);
+        }
 
         // Send gRPC request
-        let response = client
-            .append_entries(proto_req)
+        let response = client.append_entries(proto_req).await;
+        let response = self
+            .factory
+            .check_reachable(self.target, response)
             .await
             .map_err(|e| {
                 RPCError::Network(openraft::error::NetworkError::new(&NetworkError::new(
@@ -127,6 +255,13 @@ impl RaftNetwork<GameRaftTypeConfig> for GrpcNetwork {
 
         // Convert proto response to OpenRaft
         let proto_resp = response.into_inner();
+
+        // Remember which codecs this peer can decode for subsequent requests
+        let advertised: Vec<Compression> = proto_resp.supported_compression().collect();
+        if advertised != peer_codecs {
+            self.factory.peer_codecs.write().await.insert(self.target, advertised);
+        }
+
         Ok(proto_resp.into())
     }
 
@@ -138,11 +273,14 @@ impl RaftNetwork<GameRaftTypeConfig> for GrpcNetwork {
         let mut client = self.factory.get_client(self.target).await?;
 
         // Convert OpenRaft request to proto
+        let candidate = req.vote;
         let proto_req: crate::raft::conversions::proto::VoteRequest = req.into();
 
         // Send gRPC request
-        let response = client
-            .request_vote(proto_req)
+        let response = client.request_vote(proto_req).await;
+        let response = self
+            .factory
+            .check_reachable(self.target, response)
             .await
             .map_err(|e| {
                 RPCError::Network(openraft::error::NetworkError::new(&NetworkError::new(
@@ -151,8 +289,13 @@ impl RaftNetwork<GameRaftTypeConfig> for GrpcNetwork {
             })?;
 
         // Convert proto response to OpenRaft
-        let proto_resp = response.into_inner();
-        Ok(proto_resp.into())
+        // The proto carries only the voter's term; a granted vote is the candidate's
+        // own, and openraft counts it toward the quorum only if it says so
+        let mut resp: VoteResponse<NodeId> = response.into_inner().into();
+        if resp.vote_granted {
+            resp.vote = candidate;
+        }
+        Ok(resp)
     }
 
     async fn install_snapshot(
@@ -175,8 +318,10 @@ impl RaftNetwork<GameRaftTypeConfig> for GrpcNetwork {
         let proto_req: crate::raft::conversions::proto::InstallSnapshotRequest = req.into();
 
         // Send gRPC request
-        let response = client
-            .install_snapshot(proto_req)
+        let response = client.install_snapshot(proto_req).await;
+        let response = self
+            .factory
+            .check_reachable(self.target, response)
             .await
             .map_err(|e| {
                 RPCError::Network(openraft::error::NetworkError::new(&NetworkError::new(
@@ -197,7 +342,7 @@ mod tests {
     #[tokio::test]
     async fn test_network_factory_creation() {
         let registry = NodeRegistry::new();
-        let factory = GrpcNetworkFactory::new(registry);
+        let factory = GrpcNetworkFactory::new(0, registry);
 
         // Factory should be created successfully
         assert_eq!(factory.clients.read().await.len(), 0);
@@ -206,7 +351,7 @@ mod tests {
     #[tokio::test]
     async fn test_get_client_not_in_registry() {
         let registry = NodeRegistry::new();
-        let factory = GrpcNetworkFactory::new(registry);
+        let factory = GrpcNetworkFactory::new(0, registry);
 
         // Trying to get client for non-existent node should fail
         let result = factory.get_client(999).await;
@@ -218,7 +363,7 @@ mod tests {
         let registry = NodeRegistry::new();
         registry.register(1, "127.0.0.1:5000".to_string()).await;
 
-        let factory = GrpcNetworkFactory::new(registry);
+        let factory = GrpcNetworkFactory::new(0, registry);
 
         // First call attempts connection (will fail since no server)
         // but we can verify the caching logic
@@ -228,12 +373,57 @@ mod tests {
         // (In real scenario with server, client would be cached)
     }
 
+    #[tokio::test]
+    async fn test_address_change_drops_cached_client() {
+        let registry = NodeRegistry::new();
+        registry.register(1, "127.0.0.1:5000".to_string()).await;
+        let factory = GrpcNetworkFactory::new(0, registry.clone());
+
+        // A client cached for the old address, and the codecs it advertised
+        let channel = Endpoint::from_static("http://127.0.0.1:5000").connect_lazy();
+        factory.clients.write().await.insert(1, ("127.0.0.1:5000".to_string(), RaftServiceClient::new(channel)));
+        factory.peer_codecs.write().await.insert(1, compression::supported_codecs());
+        let watcher = tokio::spawn(factory.clone().watch_registry());
+
+        // The node moves somewhere nothing listens: the stale client is never handed out
+        registry.register(1, "127.0.0.1:1".to_string()).await;
+        for _ in 0..50 {
+            if factory.clients.read().await.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(factory.clients.read().await.is_empty());
+        assert!(factory.peer_codecs(1).await.is_empty());
+        assert!(factory.get_client(1).await.is_err());
+        watcher.abort();
+    }
+
+    #[tokio::test]
+    async fn test_peer_codecs_unknown_until_advertised() {
+        let registry = NodeRegistry::new();
+        let factory = GrpcNetworkFactory::with_compression(
+            0,
+            registry,
+            CompressionConfig {
+                codec: Compression::Zstd,
+                min_bytes: 0,
+            },
+        );
+
+        // No response seen yet - leader must not compress
+        assert!(factory.peer_codecs(1).await.is_empty());
+
+        factory.peer_codecs.write().await.insert(1, compression::supported_codecs());
+        assert!(factory.peer_codecs(1).await.contains(&Compression::Zstd));
+    }
+
     #[tokio::test]
     async fn test_network_creation() {
         let registry = NodeRegistry::new();
         registry.register(1, "127.0.0.1:5000".to_string()).await;
 
-        let mut factory = GrpcNetworkFactory::new(registry);
+        let mut factory = GrpcNetworkFactory::new(0, registry);
 
         // Create network for target node
         let network = factory.new_client(1, &()).await;
@@ -1,13 +1,60 @@
+use crate::game::NodeCoord;
+use crate::raft::capabilities::PeerCapabilities;
 use crate::raft::storage::NodeId;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
-/// Registry mapping NodeId to network address (IP:PORT)
+/// Where a registry entry came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrySource {
+    /// Registered locally (self, bootstrap peer) - not agreed by the cluster
+    Local,
+    /// Derived from a committed Raft event - every node agrees on it
+    Committed,
+}
+
+/// A node's network address and its provenance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub addr: String,
+    pub source: RegistrySource,
+    /// Hex the node serves, when a committed event said so
+    #[serde(default)]
+    pub coord: Option<NodeCoord>,
+}
+
+/// A node's address changed, or it was retired (`addr` None)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressChange {
+    pub node_id: NodeId,
+    pub addr: Option<String>,
+}
+
+/// Registry mapping NodeId to network address (IP:PORT, or HOST:PORT in DNS discovery mode)
 /// Thread-safe for concurrent access from multiple Raft network connections
+///
+/// Committed entries are fed by the state machine as events are applied, so the
+/// registry is a view over committed state rather than something populated ad hoc.
+/// Address changes are announced on a watch channel (`subscribe`), so connections
+/// cached for a node's old address can be dropped as soon as it moves.
+///
+/// Alongside each address it keeps what the node said it supports in a Hello
+/// (capabilities.rs). That is local knowledge, never persisted, and forgotten when the
+/// node moves or is retired.
 #[derive(Clone)]
 pub struct NodeRegistry {
-    nodes: Arc<RwLock<HashMap<NodeId, String>>>,
+    nodes: Arc<RwLock<HashMap<NodeId, RegistryEntry>>>,
+    /// Capabilities each peer announced (held only briefly, never across an await)
+    capabilities: Arc<std::sync::Mutex<HashMap<NodeId, PeerCapabilities>>>,
+    /// File committed entries are persisted to (None = in-memory only)
+    persist_path: Option<Arc<PathBuf>>,
+    /// Latest address change (None until the first)
+    changes: Arc<watch::Sender<Option<AddressChange>>>,
 }
 
 impl NodeRegistry {
@@ -15,46 +62,213 @@ impl NodeRegistry {
     pub fn new() -> Self {
         Self {
             nodes: Arc::new(RwLock::new(HashMap::new())),
+            capabilities: Arc::default(),
+            persist_path: None,
+            changes: Arc::new(watch::channel(None).0),
+        }
+    }
+
+    /// Create a registry persisted to `path`, loading any committed entries
+    /// saved by a previous run of this process
+    pub async fn with_persistence(path: PathBuf) -> Self {
+        let mut nodes = HashMap::new();
+
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => match serde_json::from_slice::<HashMap<NodeId, RegistryEntry>>(&bytes) {
+                Ok(saved) => {
+                    println!("[Registry] Loaded {} entries from {}", saved.len(), path.display());
+                    nodes = saved;
+                }
+                Err(e) => eprintln!("[Registry] Ignoring corrupt registry file {}: {}", path.display(), e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!("[Registry] Failed to read {}: {}", path.display(), e),
+        }
+
+        Self {
+            nodes: Arc::new(RwLock::new(nodes)),
+            capabilities: Arc::default(),
+            persist_path: Some(Arc::new(path)),
+            changes: Arc::new(watch::channel(None).0),
+        }
+    }
+
+    /// Create a registry from the environment
+    /// If RAFT_DATA_DIR is set, committed entries are persisted to $RAFT_DATA_DIR/registry.json
+    pub async fn from_env() -> Self {
+        match std::env::var("RAFT_DATA_DIR") {
+            Ok(dir) => Self::with_persistence(PathBuf::from(dir).join("registry.json")).await,
+            Err(_) => Self::new(),
         }
     }
 
     /// Register a node with its network address
     /// Format: "IP:PORT" (e.g., "10.0.1.5:5000")
+    /// An address the cluster has committed wins: only `register_committed` moves it.
     pub async fn register(&self, node_id: NodeId, addr: String) {
-        self.nodes.write().await.insert(node_id, addr);
+        let mut nodes = self.nodes.write().await;
+
+        // Never downgrade an address the cluster has agreed on
+        if nodes.get(&node_id).is_some_and(|e| e.source == RegistrySource::Committed) {
+            return;
+        }
+
+        let coord = nodes.get(&node_id).and_then(|e| e.coord);
+        let moved = nodes.get(&node_id).is_some_and(|e| e.addr != addr);
+        nodes.insert(node_id, RegistryEntry { addr: addr.clone(), source: RegistrySource::Local, coord });
+        if moved {
+            self.announce(node_id, Some(addr));
+        }
+    }
+
+    /// Watch address changes: each one is announced after the registry has it
+    /// A watch channel keeps only the latest change, so a subscriber that falls behind
+    /// can miss one - it should still compare addresses when it uses a connection.
+    pub fn subscribe(&self) -> watch::Receiver<Option<AddressChange>> {
+        self.changes.subscribe()
+    }
+
+    fn announce(&self, node_id: NodeId, addr: Option<String>) {
+        self.forget_capabilities(node_id);
+        self.changes.send_replace(Some(AddressChange { node_id, addr }));
+    }
+
+    /// Record an address derived from a committed event and persist it
+    /// A node that restarted under its old id (see identity) just moves to its new
+    /// address. One that came back under a new id for `coord` replaces the id that
+    /// served the hex before, so the dead one doesn't linger.
+    pub async fn register_committed(&self, node_id: NodeId, addr: String, coord: Option<NodeCoord>) {
+        let mut nodes = self.nodes.write().await;
+        let coord = coord.or(nodes.get(&node_id).and_then(|e| e.coord));
+        let entry = RegistryEntry { addr, source: RegistrySource::Committed, coord };
+        if nodes.get(&node_id) == Some(&entry) {
+            return;
+        }
+
+        if let Some(previous) = nodes.get(&node_id).filter(|e| e.source == RegistrySource::Committed && e.addr != entry.addr) {
+            println!("[Registry] Node {} moved from {} to {}", node_id, previous.addr, entry.addr);
+        }
+        let moved = nodes.get(&node_id).is_some_and(|e| e.addr != entry.addr);
+        let mut retired = Vec::new();
+        if coord.is_some() {
+            retired = nodes
+                .iter()
+                .filter(|(id, e)| **id != node_id && e.source == RegistrySource::Committed && e.coord == coord)
+                .map(|(id, _)| *id)
+                .collect();
+            for old in &retired {
+                println!("[Registry] Node {} replaced node {} at {:?}, retiring it", node_id, old, coord);
+                nodes.remove(old);
+            }
+        }
+        let addr = entry.addr.clone();
+        nodes.insert(node_id, entry);
+        for old in retired {
+            self.announce(old, None);
+        }
+        if moved {
+            self.announce(node_id, Some(addr));
+        }
+
+        if let Some(path) = &self.persist_path {
+            let committed: HashMap<NodeId, RegistryEntry> = nodes
+                .iter()
+                .filter(|(_, e)| e.source == RegistrySource::Committed)
+                .map(|(id, e)| (*id, e.clone()))
+                .collect();
+            drop(nodes);
+
+            if let Err(e) = Self::persist(path, &committed).await {
+                eprintln!("[Registry] Failed to persist to {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Write entries to disk via a temp file + rename so a crash never leaves a partial file
+    async fn persist(path: &Path, entries: &HashMap<NodeId, RegistryEntry>) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let bytes = serde_json::to_vec_pretty(entries)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, path).await
     }
 
     /// Get the network address for a given node ID
     /// Returns None if node is not registered
     pub async fn get_address(&self, node_id: NodeId) -> Option<String> {
-        self.nodes.read().await.get(&node_id).cloned()
+        self.nodes.read().await.get(&node_id).map(|e| e.addr.clone())
+    }
+
+    /// Resolve a node's address to a socket address
+    /// Names are looked up on every call rather than cached, so a task that moved
+    /// is reachable as soon as its DNS record points at the new IP
+    pub async fn resolve(&self, node_id: NodeId) -> Option<SocketAddr> {
+        let addr = self.get_address(node_id).await?;
+        tokio::net::lookup_host(addr).await.ok()?.next()
     }
 
     /// Remove a node from the registry
     /// Currently unused but planned for graceful shutdown implementation
     #[allow(dead_code)]
     pub async fn unregister(&self, node_id: NodeId) -> Option<String> {
-        self.nodes.write().await.remove(&node_id)
+        let removed = self.nodes.write().await.remove(&node_id).map(|e| e.addr);
+        if removed.is_some() {
+            self.announce(node_id, None);
+        }
+        removed
+    }
+
+    /// Record what `node_id` said it supports in a Hello
+    pub fn set_capabilities(&self, node_id: NodeId, capabilities: PeerCapabilities) {
+        self.capabilities.lock().unwrap().insert(node_id, capabilities);
+    }
+
+    /// What `node_id` supports, None until it has been greeted since it last moved
+    pub fn capabilities(&self, node_id: NodeId) -> Option<PeerCapabilities> {
+        self.capabilities.lock().unwrap().get(&node_id).cloned()
+    }
+
+    /// Forget what `node_id` supports, until it's greeted again
+    pub fn forget_capabilities(&self, node_id: NodeId) {
+        self.capabilities.lock().unwrap().remove(&node_id);
     }
 
     /// Get all registered nodes
-    /// Currently unused but planned for cluster visibility endpoints
-    #[allow(dead_code)]
     pub async fn get_all_nodes(&self) -> Vec<(NodeId, String)> {
         self.nodes
             .read()
             .await
             .iter()
-            .map(|(id, addr)| (*id, addr.clone()))
+            .map(|(id, e)| (*id, e.addr.clone()))
             .collect()
     }
 
+    /// Get all entries with their provenance (for the /registry endpoint)
+    pub async fn entries(&self) -> Vec<(NodeId, RegistryEntry)> {
+        let mut entries: Vec<_> = self
+            .nodes
+            .read()
+            .await
+            .iter()
+            .map(|(id, e)| (*id, e.clone()))
+            .collect();
+        entries.sort_by_key(|(id, _)| *id);
+        entries
+    }
+
     /// Get the number of registered nodes
     /// Currently unused but planned for monitoring/status endpoints
     #[allow(dead_code)]
     pub async fn len(&self) -> usize {
         self.nodes.read().await.len()
     }
+
+    /// Check whether the registry has no nodes
+    pub async fn is_empty(&self) -> bool {
+        self.nodes.read().await.is_empty()
+    }
 }
 
 impl Default for NodeRegistry {
@@ -154,4 +368,112 @@ mod tests {
         assert_eq!(registry.get_address(1).await, Some("10.0.1.99:5000".to_string()));
         assert_eq!(registry.len().await, 1); // Still only one node
     }
+
+    #[tokio::test]
+    async fn test_local_registration_never_moves_a_committed_address() {
+        let registry = NodeRegistry::new();
+        let changes = registry.subscribe();
+        registry.register_committed(1, "10.0.1.5:5000".to_string(), None).await;
+
+        registry.register(1, "10.0.1.99:5000".to_string()).await;
+        assert_eq!(registry.get_address(1).await, Some("10.0.1.5:5000".to_string()));
+        assert_eq!(registry.entries().await[0].1.source, RegistrySource::Committed);
+        assert!(!changes.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_names_and_ips() {
+        let registry = NodeRegistry::new();
+        registry.register(1, "127.0.0.1:5000".to_string()).await;
+        registry.register(2, "localhost:5000".to_string()).await;
+
+        assert_eq!(registry.resolve(1).await, Some("127.0.0.1:5000".parse().unwrap()));
+        // Names are kept as-is and only looked up on use
+        assert_eq!(registry.get_address(2).await, Some("localhost:5000".to_string()));
+        assert_eq!(registry.resolve(2).await.map(|a| a.port()), Some(5000));
+        assert_eq!(registry.resolve(3).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_restarted_node_replaces_its_old_entry() {
+        let registry = NodeRegistry::new();
+        let hex = Some(NodeCoord::new(1, 0));
+        registry.register_committed(1, "10.0.1.5:5000".to_string(), None).await;
+        registry.register_committed(7, "10.0.1.7:5000".to_string(), hex).await;
+
+        // Same identity, new task: the address moves, the coordinate stays
+        registry.register_committed(7, "10.0.1.8:5000".to_string(), None).await;
+        assert_eq!(registry.get_address(7).await, Some("10.0.1.8:5000".to_string()));
+        assert_eq!(registry.entries().await[1].1.coord, hex);
+
+        // New identity for the same hex: the old one is retired
+        registry.register_committed(9, "10.0.1.9:5000".to_string(), hex).await;
+        assert_eq!(registry.get_address(7).await, None);
+        assert_eq!(registry.get_address(1).await, Some("10.0.1.5:5000".to_string()));
+        assert_eq!(registry.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_address_changes_are_announced() {
+        let registry = NodeRegistry::new();
+        let mut changes = registry.subscribe();
+        let hex = Some(NodeCoord::new(1, 0));
+
+        // A new node isn't a change, nor is the same address again
+        registry.register(1, "10.0.1.5:5000".to_string()).await;
+        registry.register_committed(1, "10.0.1.5:5000".to_string(), hex).await;
+        assert!(!changes.has_changed().unwrap());
+
+        registry.register_committed(1, "10.0.1.6:5000".to_string(), None).await;
+        assert!(changes.has_changed().unwrap());
+        let change = AddressChange { node_id: 1, addr: Some("10.0.1.6:5000".to_string()) };
+        assert_eq!(*changes.borrow_and_update(), Some(change));
+
+        // A new identity for the hex retires the old one
+        registry.register_committed(2, "10.0.1.7:5000".to_string(), hex).await;
+        assert_eq!(*changes.borrow_and_update(), Some(AddressChange { node_id: 1, addr: None }));
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_forgotten_when_node_moves() {
+        let registry = NodeRegistry::new();
+        registry.register(1, "10.0.1.5:5000".to_string()).await;
+        registry.register(2, "10.0.1.6:5000".to_string()).await;
+        registry.set_capabilities(1, PeerCapabilities::local());
+        registry.set_capabilities(2, PeerCapabilities::legacy());
+
+        // Same address again: still the same process
+        registry.register_committed(1, "10.0.1.5:5000".to_string(), None).await;
+        assert_eq!(registry.capabilities(1), Some(PeerCapabilities::local()));
+
+        // A moved node may have been upgraded
+        registry.register_committed(2, "10.0.1.7:5000".to_string(), None).await;
+        assert_eq!(registry.capabilities(2), None);
+        registry.unregister(1).await;
+        assert_eq!(registry.capabilities(1), None);
+    }
+
+    #[tokio::test]
+    async fn test_committed_entries_persist_across_restarts() {
+        let dir = std::env::temp_dir().join(format!("registry-test-{}", std::process::id()));
+        let path = dir.join("registry.json");
+
+        let registry = NodeRegistry::with_persistence(path.clone()).await;
+        registry.register(1, "10.0.1.5:5000".to_string()).await; // Local - not persisted
+        registry.register_committed(2, "10.0.1.6:5000".to_string(), None).await;
+
+        // Simulate a process restart
+        let reloaded = NodeRegistry::with_persistence(path).await;
+        assert_eq!(reloaded.get_address(1).await, None);
+        assert_eq!(
+            reloaded.entries().await,
+            vec![(2, RegistryEntry {
+                addr: "10.0.1.6:5000".to_string(),
+                source: RegistrySource::Committed,
+                coord: None,
+            })]
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
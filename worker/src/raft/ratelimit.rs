@@ -0,0 +1,400 @@
+// Rate limiting for the worker API's write endpoints
+//
+// Workers get public IPs, so /events and the /game command endpoints can be hit
+// by anyone. Each request spends a token from its source IP's bucket and, when the
+// request proves which player sent it, from that player's bucket; an empty bucket
+// means 429 with Retry-After. Only a join (for a player id not taken yet) or a
+// reconnect with a valid token proves that - a node command names a node, not its
+// sender, so charging the node's owner would let anyone drain a victim's budget by
+// naming their capital. Those pay the IP budget alone.
+//
+// A command is charged once, by the node that takes it from the player. Peers pass
+// commands on to the leader over its internal listener (/events) or gRPC, neither
+// throttled, and a request on a throttled route carrying the cluster's internal token
+// is a peer's, not a player's: it skips both budgets instead of spending the
+// forwarding node's IP bucket for every player behind it.
+
+use super::api::{ApiState, CommandResponse, SubmitEventRequest};
+use super::internal;
+use crate::game::{CommandEvent, GameEvent, GameState};
+use crate::reconnect::reconnect_key;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Largest request body inspected for a player (bigger ones are rejected)
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Buckets tracked per kind before idle ones are swept
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Sustained rate and burst for one kind of key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    /// Tokens refilled per second (0 = unlimited)
+    pub per_sec: f64,
+    /// Bucket size
+    pub burst: u32,
+}
+
+impl Quota {
+    fn unlimited(&self) -> bool {
+        self.per_sec <= 0.0 || self.burst == 0
+    }
+}
+
+/// Request budgets for the throttled endpoints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    pub per_ip: Quota,
+    pub per_player: Quota,
+}
+
+impl RateLimitConfig {
+    /// Load budgets from the environment
+    /// RATE_LIMIT_IP_PER_SEC (default: 20), RATE_LIMIT_IP_BURST (40),
+    /// RATE_LIMIT_PLAYER_PER_SEC (5), RATE_LIMIT_PLAYER_BURST (10); a rate of 0 disables that limit
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+        Self {
+            per_ip: Quota {
+                per_sec: env_or("RATE_LIMIT_IP_PER_SEC", defaults.per_ip.per_sec),
+                burst: env_or("RATE_LIMIT_IP_BURST", defaults.per_ip.burst),
+            },
+            per_player: Quota {
+                per_sec: env_or("RATE_LIMIT_PLAYER_PER_SEC", defaults.per_player.per_sec),
+                burst: env_or("RATE_LIMIT_PLAYER_BURST", defaults.per_player.burst),
+            },
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_ip: Quota { per_sec: 20.0, burst: 40 },
+            per_player: Quota { per_sec: 5.0, burst: 10 },
+        }
+    }
+}
+
+/// Throttling counters, reported on GET /status
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitStats {
+    pub allowed: u64,
+    pub throttled_ip: u64,
+    pub throttled_player: u64,
+    pub tracked_ips: usize,
+    pub tracked_players: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets for one kind of key
+struct Buckets<K> {
+    quota: Quota,
+    buckets: Mutex<HashMap<K, Bucket>>,
+}
+
+impl<K: Eq + Hash + Copy> Buckets<K> {
+    fn new(quota: Quota) -> Self {
+        Self { quota, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Spend a token for `key`, or say how long until one is available
+    fn take(&self, key: K, now: Instant) -> Result<(), Duration> {
+        if self.quota.unlimited() {
+            return Ok(());
+        }
+        let burst = self.quota.burst as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Full buckets carry no state, so idle keys can be forgotten
+        if buckets.len() >= SWEEP_THRESHOLD {
+            let full_after = Duration::from_secs_f64(burst / self.quota.per_sec);
+            buckets.retain(|_, b| now.saturating_duration_since(b.updated) < full_after);
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket { tokens: burst, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.quota.per_sec).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.quota.per_sec))
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+}
+
+/// Which budget ran out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttled {
+    Ip(Duration),
+    Player(Duration),
+}
+
+/// Per-IP and per-player token buckets
+pub struct RateLimiter {
+    ips: Buckets<IpAddr>,
+    players: Buckets<u64>,
+    allowed: AtomicU64,
+    throttled_ip: AtomicU64,
+    throttled_player: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            ips: Buckets::new(config.per_ip),
+            players: Buckets::new(config.per_player),
+            allowed: AtomicU64::new(0),
+            throttled_ip: AtomicU64::new(0),
+            throttled_player: AtomicU64::new(0),
+        }
+    }
+
+    /// Charge one request from `ip`, on behalf of `player` if known
+    /// A request refused by the IP budget doesn't spend the player's.
+    pub fn check(&self, ip: IpAddr, player: Option<u64>, now: Instant) -> Result<(), Throttled> {
+        if let Err(wait) = self.ips.take(ip, now) {
+            self.throttled_ip.fetch_add(1, Ordering::Relaxed);
+            return Err(Throttled::Ip(wait));
+        }
+        if let Some(player) = player {
+            if let Err(wait) = self.players.take(player, now) {
+                self.throttled_player.fetch_add(1, Ordering::Relaxed);
+                return Err(Throttled::Player(wait));
+            }
+        }
+        self.allowed.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn stats(&self) -> RateLimitStats {
+        RateLimitStats {
+            allowed: self.allowed.load(Ordering::Relaxed),
+            throttled_ip: self.throttled_ip.load(Ordering::Relaxed),
+            throttled_player: self.throttled_player.load(Ordering::Relaxed),
+            tracked_ips: self.ips.len(),
+            tracked_players: self.players.len(),
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+/// Middleware for the throttled routes
+pub async fn throttle(
+    State(state): State<ApiState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if forwarded_by_peer(state.internal_token.as_deref(), request.headers()) {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_BODY_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+
+    let player = player_for(&state, &bytes).await;
+    match state.rate_limiter.check(peer.ip(), player, Instant::now()) {
+        Ok(()) => next.run(Request::from_parts(parts, Body::from(bytes))).await,
+        Err(throttled) => {
+            let (wait, message) = match throttled {
+                Throttled::Ip(wait) => (wait, format!("Too many requests from {}", peer.ip())),
+                Throttled::Player(wait) => (wait, format!("Too many requests for player {}", player.unwrap_or_default())),
+            };
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            let response = CommandResponse { success: false, message, error: None };
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(response),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Whether a request comes from another process of the cluster, which charged the
+/// player it acts for already (only when INTERNAL_API_TOKEN is set - without one,
+/// anybody would pass)
+fn forwarded_by_peer(token: Option<&str>, headers: &HeaderMap) -> bool {
+    token.is_some() && internal::authorized(token, headers)
+}
+
+/// The player a request is authenticated as, if any
+async fn player_for(state: &ApiState, body: &[u8]) -> Option<u64> {
+    let storage = state.storage.read().await;
+    let sm_arc = storage.state_machine();
+    drop(storage);
+    let sm = sm_arc.read().await;
+    authenticated_player(&sm.game_state, body)
+}
+
+/// A joining player (whose id nobody holds yet), or a reconnecting one presenting
+/// their token; any other request only says which node it's for
+fn authenticated_player(game_state: &GameState, body: &[u8]) -> Option<u64> {
+    let event = match serde_json::from_slice::<SubmitEventRequest>(body).ok()?.event {
+        // A scheduled command counts against whoever it acts for
+        GameEvent::Command(CommandEvent::ScheduleEvent { command, .. }) => *command,
+        GameEvent::Command(command) => command,
+        _ => return None,
+    };
+    match event {
+        CommandEvent::PlayerJoin { player_id, .. } if !game_state.players.contains_key(&player_id) => Some(player_id),
+        CommandEvent::PlayerReconnected { player_id, token, .. } => {
+            game_state.validate_reconnect(player_id, &reconnect_key(&token)).ok().map(|_| player_id)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(ip_burst: u32, player_burst: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_ip: Quota { per_sec: 1.0, burst: ip_burst },
+            per_player: Quota { per_sec: 1.0, burst: player_burst },
+        })
+    }
+
+    #[test]
+    fn test_ip_budget_refills() {
+        let limiter = limiter(2, 100);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+
+        assert!(limiter.check(ip, None, now).is_ok());
+        assert!(limiter.check(ip, None, now).is_ok());
+        assert!(matches!(limiter.check(ip, None, now), Err(Throttled::Ip(wait)) if wait == Duration::from_secs(1)));
+
+        // Another address has its own bucket; ours refills a token per second
+        assert!(limiter.check("203.0.113.8".parse().unwrap(), None, now).is_ok());
+        assert!(limiter.check(ip, None, now + Duration::from_secs(1)).is_ok());
+
+        let stats = limiter.stats();
+        assert_eq!((stats.allowed, stats.throttled_ip, stats.tracked_ips), (4, 1, 2));
+    }
+
+    #[test]
+    fn test_player_budget_spans_addresses() {
+        let limiter = limiter(100, 2);
+        let now = Instant::now();
+        let ips: Vec<IpAddr> = ["10.0.0.1", "10.0.0.2", "10.0.0.3"].iter().map(|s| s.parse().unwrap()).collect();
+
+        assert!(limiter.check(ips[0], Some(7), now).is_ok());
+        assert!(limiter.check(ips[1], Some(7), now).is_ok());
+        assert!(matches!(limiter.check(ips[2], Some(7), now), Err(Throttled::Player(_))));
+        // Unknown players only pay the IP budget
+        assert!(limiter.check(ips[2], None, now).is_ok());
+        assert_eq!(limiter.stats().throttled_player, 1);
+    }
+
+    #[test]
+    fn test_commands_charge_only_proven_players() {
+        use crate::game::{FactEvent, NodeCoord};
+
+        let mut game_state = GameState::new();
+        let join = |player_id: u64| {
+            GameEvent::Command(CommandEvent::PlayerJoin {
+                player_id,
+                name: format!("Player {}", player_id),
+                capital_coord: NodeCoord::new(0, 0),
+                capital_ip: None,
+                client_endpoint: None,
+                timestamp: 1000,
+                raft_node_id: 0,
+                reconnect_key: Some(reconnect_key("token-7")),
+            })
+        };
+        let body = |event: GameEvent| serde_json::to_vec(&SubmitEventRequest { event }).unwrap();
+        assert_eq!(authenticated_player(&game_state, &body(join(7))), Some(7));
+        game_state.process_event(join(7), 1);
+
+        // Naming the victim's capital (or their id in a join) says nothing about the sender
+        let attack = serde_json::json!({ "node_coord": NodeCoord::new(0, 0), "target_coord": NodeCoord::new(1, 0) });
+        assert_eq!(authenticated_player(&game_state, attack.to_string().as_bytes()), None);
+        assert_eq!(authenticated_player(&game_state, &body(join(7))), None);
+        let defend = GameEvent::Command(CommandEvent::SetNodeDefense { node_coord: NodeCoord::new(0, 0), defense: None, timestamp: 1010 });
+        assert_eq!(authenticated_player(&game_state, &body(defend)), None);
+        let fact = GameEvent::Fact(FactEvent::OwnerIdle { player_id: 7, neutralize: false, timestamp: 1010 });
+        assert_eq!(authenticated_player(&game_state, &body(fact)), None);
+
+        // Only the reconnect token proves who's asking
+        let reconnect = |token: &str| {
+            GameEvent::Command(CommandEvent::PlayerReconnected {
+                player_id: 7,
+                token: token.to_string(),
+                next_key: reconnect_key("token-8"),
+                client_endpoint: None,
+                timestamp: 1020,
+                raft_node_id: 0,
+            })
+        };
+        assert_eq!(authenticated_player(&game_state, &body(reconnect("guess"))), None);
+        assert_eq!(authenticated_player(&game_state, &body(reconnect("token-7"))), Some(7));
+    }
+
+    #[test]
+    fn test_peers_are_not_charged() {
+        let mut headers = HeaderMap::new();
+        assert!(!forwarded_by_peer(Some("s3cret"), &headers));
+        // Without a cluster token there's no telling a peer from a player
+        assert!(!forwarded_by_peer(None, &headers));
+
+        headers.insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert!(!forwarded_by_peer(Some("s3cret"), &headers));
+        headers.insert(header::AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        assert!(forwarded_by_peer(Some("s3cret"), &headers));
+        assert!(!forwarded_by_peer(None, &headers));
+    }
+
+    #[test]
+    fn test_zero_rate_disables() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_ip: Quota { per_sec: 0.0, burst: 1 },
+            per_player: Quota { per_sec: 0.0, burst: 1 },
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        for _ in 0..100 {
+            assert!(limiter.check(ip, Some(1), Instant::now()).is_ok());
+        }
+        assert_eq!(limiter.stats().tracked_ips, 0);
+    }
+}
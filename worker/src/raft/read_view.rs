@@ -0,0 +1,126 @@
+// Read view - the applied state the hot endpoints poll, kept off the apply path
+//
+// Every open frontend polls GET /game/state several times a second, and dashboards
+// poll GET /status. Each poll used to read-lock the state machine for as long as it
+// took to build the response, so apply_to_state_machine queued behind the watchers
+// for its write lock. `ReadView` instead keeps a copy of what those endpoints read
+// as an immutable `ReadSnapshot` behind an Arc: a poll clones the Arc and never
+// touches the lock, and a newer copy replaces it rather than changing it.
+//
+// Storage announces each apply on a watch channel (`MemStorage::subscribe_applied`).
+// The view's task wakes on it, copies the state under one short read lock, and
+// swaps the copy in. The channel keeps only the latest index, so a burst of applies
+// costs one copy, and copies are at least READ_VIEW_INTERVAL apart. A poll can see
+// state up to that far behind this node's applied log - never ahead of it, and never
+// older than what it saw last.
+
+use crate::game::GameState;
+use crate::raft::storage::MemStorage;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Least time between two copies of the applied state
+pub const READ_VIEW_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What the hot endpoints read, as of one applied index
+#[derive(Debug, Clone)]
+pub struct ReadSnapshot {
+    /// Last log index applied when the copy was taken (the view's version)
+    pub applied_index: u64,
+    /// Committed events so far
+    pub event_count: usize,
+    pub game_state: GameState,
+}
+
+/// This node's applied state, copied out for readers
+#[derive(Clone)]
+pub struct ReadView {
+    storage: Arc<MemStorage>,
+    latest: Arc<RwLock<Arc<ReadSnapshot>>>,
+}
+
+impl ReadView {
+    /// A view of `storage`, holding a copy of what it has applied so far
+    pub async fn new(storage: MemStorage) -> Self {
+        let latest = Arc::new(RwLock::new(Arc::new(copy(&storage).await)));
+        Self { storage: Arc::new(storage), latest }
+    }
+
+    /// The latest copy
+    pub fn latest(&self) -> Arc<ReadSnapshot> {
+        self.latest.read().unwrap().clone()
+    }
+
+    /// Replace the copy with the state applied now
+    pub async fn refresh(&self) {
+        let snapshot = Arc::new(copy(&self.storage).await);
+        *self.latest.write().unwrap() = snapshot;
+    }
+
+    /// Copy the state each time storage applies something, for the life of the process
+    pub async fn run(self) {
+        // Subscribed before the first refresh, so nothing applied after it is missed
+        let mut applied = self.storage.subscribe_applied();
+        loop {
+            self.refresh().await;
+            tokio::time::sleep(READ_VIEW_INTERVAL).await;
+            if applied.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn copy(storage: &MemStorage) -> ReadSnapshot {
+    let state_machine = storage.state_machine();
+    let sm = state_machine.read().await;
+    ReadSnapshot { applied_index: sm.last_applied_log_index, event_count: sm.events.len(), game_state: sm.game_state.clone() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::events::{CommandEvent, GameEvent};
+    use crate::game::NodeCoord;
+    use crate::raft::storage::GameEventRequest;
+    use openraft::{Entry, EntryPayload, LogId, RaftStorage};
+
+    fn join(index: u64) -> Entry<crate::raft::storage::GameRaftTypeConfig> {
+        let event = GameEvent::Command(CommandEvent::PlayerJoin {
+            player_id: index,
+            name: format!("player{}", index),
+            capital_coord: NodeCoord::new(index as i32 * 4, 0),
+            capital_ip: None,
+            client_endpoint: None,
+            timestamp: 0,
+            raft_node_id: 0,
+            reconnect_key: None,
+        });
+        Entry { log_id: LogId::new(openraft::LeaderId::new(1, 1), index), payload: EntryPayload::Normal(GameEventRequest::new(event)) }
+    }
+
+    #[tokio::test]
+    async fn test_view_follows_applies_without_changing_old_copies() {
+        let mut storage = MemStorage::new();
+        storage.apply_to_state_machine(&[join(1)]).await.unwrap();
+
+        let view = ReadView::new(storage.clone_storage()).await;
+        let before = view.latest();
+        assert_eq!((before.applied_index, before.event_count), (1, 1));
+
+        let task = tokio::spawn(view.clone().run());
+        storage.apply_to_state_machine(&[join(2), join(3)]).await.unwrap();
+        let started = tokio::time::Instant::now();
+        while view.latest().applied_index < 3 {
+            assert!(started.elapsed() < Duration::from_secs(5), "View never caught up");
+            tokio::time::sleep(READ_VIEW_INTERVAL).await;
+        }
+        task.abort();
+
+        let after = view.latest();
+        assert_eq!(after.event_count, 3);
+        assert_eq!(after.game_state.players.len(), 3);
+        // A reader holding the old copy still has the state it was handed
+        assert_eq!(before.game_state.players.len(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+// Snapshot store - Raft snapshots on this node's disk
+//
+// Snapshots used to live only in memory, so a worker that installed one and then
+// restarted had to fetch it (or replay the whole log) again. With a snapshot dir
+// configured, every snapshot the node builds or installs is also written there, and
+// join_cluster installs the newest one before it joins - the leader then only
+// replicates what came after it.
+//
+// Each file holds one typed record (meta, game id, serialized state) behind a SHA-256
+// of the record, and is written to a temp file first and renamed into place, so a
+// crash mid-write leaves the previous snapshot intact. Files whose checksum doesn't
+// match, or that belong to another game, are skipped on load. Saves are serialized
+// through a lock, since a snapshot build and an install can race; only the newest
+// RAFT_SNAPSHOT_KEEP files are kept.
+
+use crate::raft::storage::NodeId;
+use openraft::SnapshotMeta;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const CHECKSUM_LEN: usize = 32;
+
+/// What a snapshot file holds, after its checksum
+#[derive(Serialize, Deserialize)]
+struct SnapshotRecord {
+    game_id: String,
+    meta: SnapshotMeta<NodeId, ()>,
+    /// The serialized GameStateSnapshot
+    data: Vec<u8>,
+}
+
+/// A snapshot file in the store, as GET /debug/snapshots lists it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub file: String,
+    /// Last log index the snapshot covers
+    pub index: u64,
+    pub bytes: u64,
+    /// Whether the checksum matches and the record belongs to this game
+    pub valid: bool,
+}
+
+/// Snapshot files of one game in one directory; cheap to clone
+#[derive(Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+    game_id: String,
+    keep: usize,
+    lock: Arc<Mutex<()>>,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>, game_id: &str, keep: usize) -> Self {
+        Self { dir: dir.into(), game_id: game_id.to_string(), keep: keep.max(1), lock: Arc::new(Mutex::new(())) }
+    }
+
+    /// RAFT_SNAPSHOT_DIR (default: $RAFT_DATA_DIR/snapshots), None if neither is set
+    /// RAFT_SNAPSHOT_KEEP files are kept (default: 2)
+    pub fn from_env(game_id: &str) -> Option<Self> {
+        let dir = std::env::var("RAFT_SNAPSHOT_DIR")
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var("RAFT_DATA_DIR").ok().map(|dir| PathBuf::from(dir).join("snapshots")))?;
+        let keep = std::env::var("RAFT_SNAPSHOT_KEEP").ok().and_then(|s| s.parse().ok()).unwrap_or(2);
+        Some(Self::new(dir, game_id, keep))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn file_name(index: u64) -> String {
+        // Zero-padded so names sort like indices
+        format!("snapshot-{:020}.bin", index)
+    }
+
+    fn index_of(file_name: &str) -> Option<u64> {
+        file_name.strip_prefix("snapshot-")?.strip_suffix(".bin")?.parse().ok()
+    }
+
+    /// Write a snapshot (temp file + rename) and drop all but the newest `keep`
+    /// Snapshots of an empty log are not worth a file and are skipped.
+    pub async fn save(&self, meta: &SnapshotMeta<NodeId, ()>, data: &[u8]) -> std::io::Result<()> {
+        let Some(log_id) = meta.last_log_id else {
+            return Ok(());
+        };
+        let record = SnapshotRecord { game_id: self.game_id.clone(), meta: meta.clone(), data: data.to_vec() };
+        let body = bincode::serialize(&record).map_err(std::io::Error::other)?;
+        let mut bytes = ring::digest::digest(&ring::digest::SHA256, &body).as_ref().to_vec();
+        bytes.extend_from_slice(&body);
+
+        let _guard = self.lock.lock().await;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(Self::file_name(log_id.index));
+        let tmp = path.with_extension("bin.tmp");
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        for (_, stale) in self.files().await?.into_iter().rev().skip(self.keep) {
+            let _ = tokio::fs::remove_file(stale).await;
+        }
+        Ok(())
+    }
+
+    /// The newest snapshot of this game whose checksum matches, if any
+    pub async fn load_latest(&self) -> Option<(SnapshotMeta<NodeId, ()>, Vec<u8>)> {
+        let _guard = self.lock.lock().await;
+        let files = match self.files().await {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("[Snapshots] Failed to read {}: {}", self.dir.display(), e);
+                return None;
+            }
+        };
+        for (_, path) in files.into_iter().rev() {
+            match self.read(&path).await {
+                Ok(record) => return Some((record.meta, record.data)),
+                Err(e) => eprintln!("[Snapshots] Skipping {}: {}", path.display(), e),
+            }
+        }
+        None
+    }
+
+    /// Every snapshot file in the store, oldest first
+    pub async fn list(&self) -> std::io::Result<Vec<SnapshotFile>> {
+        let _guard = self.lock.lock().await;
+        let mut listed = Vec::new();
+        for (index, path) in self.files().await? {
+            let bytes = tokio::fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+            listed.push(SnapshotFile {
+                file: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                index,
+                bytes,
+                valid: self.read(&path).await.is_ok(),
+            });
+        }
+        Ok(listed)
+    }
+
+    /// Snapshot files by index, oldest first (a missing dir has none)
+    async fn files(&self) -> std::io::Result<Vec<(u64, PathBuf)>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(index) = entry.file_name().to_str().and_then(Self::index_of) {
+                files.push((index, entry.path()));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Read and check one file: checksum first, then the game it belongs to
+    async fn read(&self, path: &Path) -> Result<SnapshotRecord, String> {
+        let bytes = tokio::fs::read(path).await.map_err(|e| e.to_string())?;
+        if bytes.len() < CHECKSUM_LEN {
+            return Err("truncated".to_string());
+        }
+        let (checksum, body) = bytes.split_at(CHECKSUM_LEN);
+        if ring::digest::digest(&ring::digest::SHA256, body).as_ref() != checksum {
+            return Err("checksum mismatch".to_string());
+        }
+        let record: SnapshotRecord = bincode::deserialize(body).map_err(|e| e.to_string())?;
+        if record.game_id != self.game_id {
+            return Err(format!("belongs to game {}", record.game_id));
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openraft::LogId;
+
+    fn meta_at(index: u64) -> SnapshotMeta<NodeId, ()> {
+        SnapshotMeta {
+            last_log_id: Some(LogId::new(openraft::LeaderId::new(2, 1), index)),
+            last_membership: Default::default(),
+            snapshot_id: format!("snapshot-{}", index),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_load_and_prune() {
+        let dir = std::env::temp_dir().join(format!("camhack-snapshots-{}", std::process::id()));
+        let store = SnapshotStore::new(&dir, "game1", 2);
+        assert!(store.load_latest().await.is_none());
+
+        for index in [5, 12, 30] {
+            store.save(&meta_at(index), &[index as u8; 64]).await.unwrap();
+        }
+        let (meta, data) = store.load_latest().await.unwrap();
+        assert_eq!(meta, meta_at(30));
+        assert_eq!(data, [30; 64]);
+        let listed = store.list().await.unwrap();
+        assert_eq!(listed.iter().map(|f| f.index).collect::<Vec<_>>(), [12, 30]);
+        assert!(listed.iter().all(|f| f.valid));
+
+        // A corrupted newest file falls back to the one before it
+        let newest = dir.join(SnapshotStore::file_name(30));
+        let mut bytes = std::fs::read(&newest).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&newest, bytes).unwrap();
+        assert_eq!(store.load_latest().await.unwrap().0, meta_at(12));
+        assert!(!store.list().await.unwrap()[1].valid);
+
+        // Another game's snapshots are never loaded
+        assert!(SnapshotStore::new(&dir, "game2", 2).load_latest().await.is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
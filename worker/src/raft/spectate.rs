@@ -0,0 +1,195 @@
+// Spectator snapshots - the current state for watchers, without the log
+//
+// A spectator, or a learner client about to join, wants to draw the board now rather
+// than after replaying the log or waiting for replication to catch it up. Every node
+// ships its latest applied snapshot on GET /spectate/snapshot (internal listener):
+// a zstd frame of bincode `(SnapshotMeta, data)`, the same pair hot bootstrap
+// installs. Any node serves it, not just the leader, so watchers don't load the
+// leader; a follower's copy is as fresh as its applied log.
+//
+// Building and compressing one means serializing every event, so requests never
+// trigger it: `SnapshotShipper` keeps one ready, rebuilt in the background at most
+// every SPECTATE_REFRESH while the log moves and someone fetched one in the last
+// SPECTATE_IDLE. Only the very first request builds. The receiver renders from
+// `state_from_snapshot` at once and catches up from the snapshot's index (Raft for a
+// learner, the ETag-polled /game/state for a spectator).
+
+use crate::game::GameState;
+use crate::raft::storage::{MemStorage, NodeId};
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use openraft::SnapshotMeta;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Least time between two rebuilds of the shipped snapshot
+pub const SPECTATE_REFRESH: Duration = Duration::from_secs(2);
+
+/// Rebuilding stops this long after the last fetch
+pub const SPECTATE_IDLE: Duration = Duration::from_secs(60);
+
+/// Most bytes a shipped snapshot may expand to when decoded
+pub const MAX_SPECTATE_SNAPSHOT_BYTES: usize = 256 * 1024 * 1024;
+
+/// Content-Type of GET /spectate/snapshot
+pub const SNAPSHOT_CONTENT_TYPE: &str = "application/zstd";
+
+/// Response header naming the last log index the snapshot includes
+pub const SNAPSHOT_INDEX_HEADER: &str = "x-snapshot-index";
+
+/// zstd level for shipped snapshots (built rarely, sent to every watcher)
+const ZSTD_LEVEL: i32 = 9;
+
+/// A compressed snapshot ready to send
+#[derive(Debug)]
+pub struct ShippedSnapshot {
+    /// Last log index it includes (0 = empty state)
+    pub index: u64,
+    /// zstd frame of bincode `(SnapshotMeta, data)`
+    pub bytes: Bytes,
+    /// Size before compression
+    pub raw_bytes: usize,
+}
+
+/// Keeps this node's latest snapshot compressed and ready for spectators
+#[derive(Clone)]
+pub struct SnapshotShipper {
+    storage: Arc<MemStorage>,
+    latest: Arc<Mutex<Option<Arc<ShippedSnapshot>>>>,
+    /// When a snapshot was last handed out (None = never)
+    fetched_at: Arc<std::sync::Mutex<Option<Instant>>>,
+}
+
+impl SnapshotShipper {
+    pub fn new(storage: MemStorage) -> Self {
+        Self { storage: Arc::new(storage), latest: Arc::default(), fetched_at: Arc::default() }
+    }
+
+    /// The shipped snapshot, built now only if there is none yet
+    pub async fn latest(&self) -> Result<Arc<ShippedSnapshot>> {
+        *self.fetched_at.lock().unwrap() = Some(Instant::now());
+        let mut latest = self.latest.lock().await;
+        if let Some(shipped) = latest.as_ref() {
+            return Ok(shipped.clone());
+        }
+        let shipped = Arc::new(self.build().await?);
+        *latest = Some(shipped.clone());
+        Ok(shipped)
+    }
+
+    /// Rebuild the shipped snapshot if the applied log has moved past it
+    /// Returns whether it was rebuilt.
+    pub async fn refresh(&self) -> Result<bool> {
+        let applied = self.storage.state_machine().read().await.last_applied_log_index;
+        let mut latest = self.latest.lock().await;
+        if latest.as_ref().is_some_and(|shipped| shipped.index >= applied) {
+            return Ok(false);
+        }
+        *latest = Some(Arc::new(self.build().await?));
+        Ok(true)
+    }
+
+    /// Keep the shipped snapshot fresh while anyone is fetching it, for the life of the process
+    pub async fn run(self) {
+        let mut ticks = tokio::time::interval(SPECTATE_REFRESH);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            let watched = self.fetched_at.lock().unwrap().is_some_and(|at| at.elapsed() < SPECTATE_IDLE);
+            if !watched {
+                continue;
+            }
+            if let Err(e) = self.refresh().await {
+                eprintln!("[Spectate] Failed to rebuild the shipped snapshot: {:#}", e);
+            }
+        }
+    }
+
+    async fn build(&self) -> Result<ShippedSnapshot> {
+        let (meta, data) = self.storage.export_snapshot().await.context("Failed to build snapshot")?;
+        let index = meta.last_log_id.map_or(0, |log_id| log_id.index);
+        tokio::task::spawn_blocking(move || {
+            let raw = bincode::serialize(&(meta, data)).context("Failed to encode snapshot")?;
+            let bytes = zstd::encode_all(raw.as_slice(), ZSTD_LEVEL).context("Failed to compress snapshot")?;
+            Ok(ShippedSnapshot { index, bytes: Bytes::from(bytes), raw_bytes: raw.len() })
+        })
+        .await?
+    }
+}
+
+/// Snapshot meta and data from a shipped snapshot's body
+pub fn decode(bytes: &[u8]) -> Result<(SnapshotMeta<NodeId, ()>, Vec<u8>)> {
+    let mut raw = Vec::new();
+    zstd::stream::read::Decoder::new(bytes)
+        .and_then(|decoder| decoder.take(MAX_SPECTATE_SNAPSHOT_BYTES as u64 + 1).read_to_end(&mut raw))
+        .context("Invalid snapshot compression")?;
+    if raw.len() > MAX_SPECTATE_SNAPSHOT_BYTES {
+        anyhow::bail!("Snapshot expands past {} bytes", MAX_SPECTATE_SNAPSHOT_BYTES);
+    }
+    bincode::deserialize(&raw).context("Invalid snapshot")
+}
+
+/// The game state a snapshot holds, rebuilt the way a joining node installs it
+pub async fn state_from_snapshot(meta: &SnapshotMeta<NodeId, ()>, data: Vec<u8>) -> Result<GameState> {
+    let mut storage = MemStorage::new();
+    storage.install_bootstrap_snapshot(meta, data).await.context("Failed to install snapshot")?;
+    let state = storage.state_machine().read().await.game_state.clone();
+    Ok(state)
+}
+
+/// Fetch and decode the snapshot a node ships, from its internal listener at `base_url`
+pub async fn fetch(base_url: &str, token: Option<&str>) -> Result<(SnapshotMeta<NodeId, ()>, Vec<u8>)> {
+    let url = format!("{}/spectate/snapshot", base_url.trim_end_matches('/'));
+    let mut request = reqwest::Client::new().get(&url).timeout(Duration::from_secs(30));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.with_context(|| format!("GET {} failed", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!("GET {} returned {}", url, response.status());
+    }
+    let bytes = response.bytes().await.with_context(|| format!("Failed to read {}", url))?;
+    decode(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::events::{CommandEvent, GameEvent};
+    use crate::game::NodeCoord;
+    use crate::raft::storage::GameEventRequest;
+    use openraft::{Entry, EntryPayload, LogId, RaftStorage};
+
+    #[tokio::test]
+    async fn test_shipped_snapshot_renders_the_state() {
+        let mut storage = MemStorage::new();
+        let join = GameEvent::Command(CommandEvent::PlayerJoin {
+            player_id: 1,
+            name: "alice".to_string(),
+            capital_coord: NodeCoord::new(0, 0),
+            capital_ip: None,
+            client_endpoint: None,
+            timestamp: 0,
+            raft_node_id: 0,
+            reconnect_key: None,
+        });
+        let entry = Entry {
+            log_id: LogId::new(openraft::LeaderId::new(1, 1), 1),
+            payload: EntryPayload::Normal(GameEventRequest::new(join)),
+        };
+        storage.apply_to_state_machine(&[entry]).await.unwrap();
+
+        let shipper = SnapshotShipper::new(storage.clone_storage());
+        let shipped = shipper.latest().await.unwrap();
+        assert_eq!(shipped.index, 1);
+        assert!(!shipper.refresh().await.unwrap());
+
+        let (meta, data) = decode(&shipped.bytes).unwrap();
+        let state = state_from_snapshot(&meta, data).await.unwrap();
+        assert_eq!(state.players[&1].name, "alice");
+
+        assert!(decode(b"not zstd").is_err());
+    }
+}
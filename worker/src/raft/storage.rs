@@ -1,4 +1,9 @@
-use crate::game::{GameEvent, GameState};
+use crate::game::{CommandEvent, FactEvent, GameEvent, GameState, NodeCoord, StateChange};
+use crate::raft::audit::CommandOrigin;
+use crate::raft::digest::DigestLog;
+use crate::raft::hooks::ApplyHooks;
+use crate::raft::node_registry::NodeRegistry;
+use crate::raft::snapshot_store::SnapshotStore;
 use openraft::storage::{LogState, Snapshot};
 use openraft::{
     Entry, EntryPayload, ErrorSubject, ErrorVerb, LogId, RaftLogReader, RaftSnapshotBuilder,
@@ -10,7 +15,7 @@ use std::fmt::Debug;
 use std::io::Cursor;
 use std::ops::RangeBounds;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 /// Node ID type
 pub type NodeId = u64;
@@ -19,6 +24,31 @@ pub type NodeId = u64;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameEventRequest {
     pub event: GameEvent,
+    /// Proposer's clock when the entry was proposed (unix seconds, 0 = unstamped)
+    /// Only the leader can append, so a committed stamp is always the leader's clock.
+    #[serde(default)]
+    pub committed_at: u64,
+    /// Who sent the command, for the audit trail (None for everything else)
+    #[serde(default)]
+    pub origin: Option<CommandOrigin>,
+}
+
+impl GameEventRequest {
+    /// Propose `event`, stamped with this node's clock
+    pub fn new(event: GameEvent) -> Self {
+        let committed_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Self { event, committed_at, origin: None }
+    }
+
+    /// Propose a command on behalf of an API caller (see audit)
+    /// The origin is dropped for events that aren't commands.
+    pub fn with_origin(event: GameEvent, origin: CommandOrigin) -> Self {
+        let origin = CommandOrigin::applies_to(&event).then_some(origin);
+        Self { origin, ..Self::new(event) }
+    }
 }
 
 /// Application response type
@@ -27,10 +57,36 @@ pub struct GameEventResponse {
     pub success: bool,
 }
 
+/// Dead letters kept per node; the oldest go first
+pub const MAX_DEAD_LETTERS: usize = 1000;
+
+/// A committed entry the rules couldn't apply (see GameState::skip_reason)
+/// It's still in the log and counted as applied - the rules treat it as a no-op.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub log_index: u64,
+    /// Position in the event list, as in GET /events
+    pub position: usize,
+    /// Event variant (GameEvent::kind)
+    pub kind: String,
+    pub reason: String,
+    /// Leader stamp of the entry (unix seconds, 0 = unstamped)
+    pub committed_at: u64,
+}
+
 /// Snapshot data type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameStateSnapshot {
     pub events: Vec<GameEvent>,  // For replay/audit
+    /// Leader stamp of each event (see GameStateMachine::event_times)
+    #[serde(default)]
+    pub event_times: Vec<u64>,
+    /// Log index of each event (see GameStateMachine::event_indices)
+    #[serde(default)]
+    pub event_indices: Vec<u64>,
+    /// Origins of audited commands (see GameStateMachine::audit)
+    #[serde(default)]
+    pub audit: BTreeMap<usize, CommandOrigin>,
     pub last_applied_log_index: u64,
 }
 
@@ -74,6 +130,24 @@ pub struct MemStorage {
 
     /// Committed membership
     committed: Arc<RwLock<Option<StoredMembership<NodeId, ()>>>>,
+
+    /// Last log id removed from the log (compacted or covered by a hot-bootstrap snapshot)
+    last_purged: Arc<RwLock<Option<LogId<NodeId>>>>,
+
+    /// Node registry fed from committed events as they are applied
+    registry: NodeRegistry,
+
+    /// Subsystems called after each applied event (see hooks)
+    hooks: ApplyHooks,
+
+    /// This node's recent state digests, checked against the leader's (see digest)
+    digests: DigestLog,
+
+    /// Where built and installed snapshots are also written, if anywhere (see snapshot_store)
+    snapshot_store: Option<SnapshotStore>,
+
+    /// Last applied log index, announced after each apply and snapshot install
+    applied: Arc<watch::Sender<u64>>,
 }
 
 /// Game state machine - derived state + event history
@@ -84,26 +158,86 @@ pub struct GameStateMachine {
     /// All game events in order (for replay/audit)
     pub events: Vec<GameEvent>,
 
+    /// Leader stamp of each entry in `events` (unix seconds, 0 = unstamped)
+    pub event_times: Vec<u64>,
+
+    /// Raft log index of each entry in `events`, so a snapshot replays them at the
+    /// indices they were applied at (effect ids and digests depend on it)
+    pub event_indices: Vec<u64>,
+
+    /// Who sent each audited command, by position in `events`
+    pub audit: BTreeMap<usize, CommandOrigin>,
+
     /// Last applied log index
     pub last_applied_log_index: u64,
+
+    /// Bumped by every applied event that changed game state, and by a snapshot
+    /// install - what /ws pushes watch instead of the log index
+    pub change_version: u64,
+
+    /// Last applied log id, including its term (any entry type)
+    pub last_applied_log_id: Option<LogId<NodeId>>,
+
+    /// Entries the rules had to skip, oldest first (see DeadLetter)
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+impl GameStateMachine {
+    /// Apply the event at `position` in `events` to the game state, recording it as
+    /// a dead letter if the rules can't make sense of it
+    fn apply_event(&mut self, position: usize, log_index: u64, committed_at: u64) -> Vec<StateChange> {
+        let event = self.events[position].clone();
+        if let Some(reason) = self.game_state.skip_reason(&event) {
+            eprintln!("[DeadLetter] Skipping {} at log index {}: {}", event.kind(), log_index, reason);
+            if self.dead_letters.len() >= MAX_DEAD_LETTERS {
+                self.dead_letters.remove(0);
+            }
+            let kind = event.kind().to_string();
+            self.dead_letters.push(DeadLetter { log_index, position, kind, reason: reason.to_string(), committed_at });
+        }
+        self.game_state.process_committed(event, log_index, committed_at)
+    }
 }
 
 impl MemStorage {
     pub fn new() -> Self {
+        Self::with_registry(NodeRegistry::new())
+    }
+
+    /// Create storage that keeps `registry` in sync with committed events
+    pub fn with_registry(registry: NodeRegistry) -> Self {
         Self {
             vote: Arc::new(RwLock::new(None)),
             log: Arc::new(RwLock::new(BTreeMap::new())),
             state_machine: Arc::new(RwLock::new(GameStateMachine {
                 game_state: GameState::new(),
                 events: Vec::new(),
+                event_times: Vec::new(),
+                event_indices: Vec::new(),
+                audit: BTreeMap::new(),
                 last_applied_log_index: 0,
+                change_version: 0,
+                last_applied_log_id: None,
+                dead_letters: Vec::new(),
             })),
             snapshot: Arc::new(RwLock::new(None)),
             snapshot_meta: Arc::new(RwLock::new(None)),
             committed: Arc::new(RwLock::new(None)),
+            last_purged: Arc::new(RwLock::new(None)),
+            registry,
+            hooks: ApplyHooks::default(),
+            digests: DigestLog::default(),
+            snapshot_store: None,
+            applied: Arc::new(watch::channel(0).0),
         }
     }
 
+    /// Also write every snapshot built or installed to `store`
+    pub fn with_snapshot_store(mut self, store: Option<SnapshotStore>) -> Self {
+        self.snapshot_store = store;
+        self
+    }
+
     /// Get the state machine for reading game events
     pub fn state_machine(&self) -> Arc<RwLock<GameStateMachine>> {
         self.state_machine.clone()
@@ -117,8 +251,104 @@ impl MemStorage {
             snapshot: self.snapshot.clone(),
             snapshot_meta: self.snapshot_meta.clone(),
             committed: self.committed.clone(),
+            last_purged: self.last_purged.clone(),
+            registry: self.registry.clone(),
+            hooks: self.hooks.clone(),
+            digests: self.digests.clone(),
+            snapshot_store: self.snapshot_store.clone(),
+            applied: self.applied.clone(),
         }
     }
+
+    /// Hooks called as committed events are applied; register on any clone
+    pub fn hooks(&self) -> &ApplyHooks {
+        &self.hooks
+    }
+
+    /// This node's state digests and how they compare with the leader's
+    pub fn digests(&self) -> &DigestLog {
+        &self.digests
+    }
+
+    /// Watch the last applied log index, announced once the state machine is unlocked
+    /// A watch channel keeps only the latest index, so a burst of applies wakes a
+    /// subscriber once (see read_view).
+    pub fn subscribe_applied(&self) -> watch::Receiver<u64> {
+        self.applied.subscribe()
+    }
+
+    /// The on-disk snapshot store, if one is configured
+    pub fn snapshot_store(&self) -> Option<&SnapshotStore> {
+        self.snapshot_store.as_ref()
+    }
+
+    /// Write a snapshot to the store; a failure only costs the copy on disk
+    async fn persist_snapshot(&self, meta: &SnapshotMeta<NodeId, ()>, data: &[u8]) {
+        if let Some(store) = &self.snapshot_store {
+            if let Err(e) = store.save(meta, data).await {
+                eprintln!("[Snapshots] Failed to save {} to {}: {}", meta.snapshot_id, store.dir().display(), e);
+            }
+        }
+    }
+
+    /// Get the committed cluster membership
+    pub async fn committed_membership(&self) -> Option<StoredMembership<NodeId, ()>> {
+        self.committed.read().await.clone()
+    }
+
+    /// Snapshot of everything applied so far, serialized for a hot-bootstrap transfer
+    pub async fn export_snapshot(&self) -> Result<(SnapshotMeta<NodeId, ()>, Vec<u8>), StorageError<NodeId>> {
+        let snapshot = self.clone_storage().build_snapshot().await?;
+        Ok((snapshot.meta, snapshot.snapshot.into_inner()))
+    }
+
+    /// Install a snapshot fetched from the leader before Raft starts (hot bootstrap)
+    ///
+    /// Besides the state machine, the log is marked as purged up to the snapshot so
+    /// the new Raft instance starts from there and the leader only replicates what
+    /// came after it, instead of the whole history.
+    pub async fn install_bootstrap_snapshot(
+        &mut self,
+        meta: &SnapshotMeta<NodeId, ()>,
+        data: Vec<u8>,
+    ) -> Result<(), StorageError<NodeId>> {
+        self.install_snapshot(meta, Box::new(Cursor::new(data))).await?;
+
+        if let Some(log_id) = meta.last_log_id {
+            self.log.write().await.retain(|&index, _| index > log_id.index);
+            *self.last_purged.write().await = Some(log_id);
+        }
+        Ok(())
+    }
+}
+
+/// Extract the (raft node id, raft address, hex it serves) a committed event announces, if any
+pub fn committed_node_address(event: &GameEvent) -> Option<(NodeId, String, Option<NodeCoord>)> {
+    match event {
+        // The joining process is the client for human players (off the grid), else the capital worker
+        GameEvent::Command(CommandEvent::PlayerJoin { raft_node_id, client_endpoint, capital_ip, capital_coord, .. })
+            if *raft_node_id != 0 =>
+        {
+            match client_endpoint {
+                Some(ip) => Some((*raft_node_id, format!("{}:5000", ip), None)),
+                None => Some((*raft_node_id, format!("{}:5000", capital_ip.as_ref()?), Some(*capital_coord))),
+            }
+        }
+        // A restarted client comes back as a new Raft node
+        GameEvent::Command(CommandEvent::PlayerReconnected { raft_node_id, client_endpoint: Some(ip), .. }) if *raft_node_id != 0 => {
+            Some((*raft_node_id, format!("{}:5000", ip), None))
+        }
+        GameEvent::Fact(FactEvent::NodeInitializationComplete { raft_node_id, node_ip, node_coord, .. }) if *raft_node_id != 0 => {
+            Some((*raft_node_id, format!("{}:5000", node_ip), Some(*node_coord)))
+        }
+        _ => None,
+    }
+}
+
+impl Default for MemStorage {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RaftLogReader<GameRaftTypeConfig> for MemStorage {
@@ -140,6 +370,9 @@ impl RaftSnapshotBuilder<GameRaftTypeConfig> for MemStorage {
         let sm = self.state_machine.read().await;
         let snapshot_data = GameStateSnapshot {
             events: sm.events.clone(),
+            event_times: sm.event_times.clone(),
+            event_indices: sm.event_indices.clone(),
+            audit: sm.audit.clone(),
             last_applied_log_index: sm.last_applied_log_index,
         };
 
@@ -149,25 +382,20 @@ impl RaftSnapshotBuilder<GameRaftTypeConfig> for MemStorage {
                 StorageError::from_io_error(
                     ErrorSubject::Snapshot(None),
                     ErrorVerb::Read,
-                    std::io::Error::new(std::io::ErrorKind::Other, e)
+                    std::io::Error::other(e)
                 )
             })?;
 
         let meta = SnapshotMeta {
-            last_log_id: if sm.last_applied_log_index > 0 {
-                Some(LogId::new(
-                    openraft::LeaderId::new(0, 0),
-                    sm.last_applied_log_index
-                ))
-            } else {
-                None
-            },
+            last_log_id: sm.last_applied_log_id,
             last_membership: self.committed.read().await.clone().unwrap_or_default(),
             snapshot_id: format!("snapshot-{}", sm.last_applied_log_index),
         };
 
         *self.snapshot.write().await = Some(snapshot_data);
         *self.snapshot_meta.write().await = Some(meta.clone());
+        drop(sm);
+        self.persist_snapshot(&meta, &bytes).await;
 
         Ok(Snapshot {
             meta,
@@ -181,22 +409,23 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
     type SnapshotBuilder = Self;
 
     async fn save_vote(&mut self, vote: &Vote<NodeId>) -> Result<(), StorageError<NodeId>> {
-        *self.vote.write().await = Some(vote.clone());
+        *self.vote.write().await = Some(*vote);
         Ok(())
     }
 
     async fn read_vote(&mut self) -> Result<Option<Vote<NodeId>>, StorageError<NodeId>> {
-        Ok(self.vote.read().await.clone())
+        Ok(*self.vote.read().await)
     }
 
     async fn get_log_state(&mut self) -> Result<LogState<GameRaftTypeConfig>, StorageError<NodeId>> {
         let log = self.log.read().await;
+        let last_purged_log_id = *self.last_purged.read().await;
+        // With nothing after the purge point, the last log id is the purged one
         let last_log_id = log
             .iter()
             .last()
-            .map(|(_, entry)| entry.log_id);
-
-        let last_purged_log_id = None;
+            .map(|(_, entry)| entry.log_id)
+            .or(last_purged_log_id);
 
         Ok(LogState {
             last_purged_log_id,
@@ -230,6 +459,7 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
     async fn purge_logs_upto(&mut self, log_id: LogId<NodeId>) -> Result<(), StorageError<NodeId>> {
         let mut log = self.log.write().await;
         log.retain(|&index, _| index > log_id.index);
+        *self.last_purged.write().await = Some(log_id);
         Ok(())
     }
 
@@ -237,14 +467,7 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
         &mut self,
     ) -> Result<(Option<LogId<NodeId>>, StoredMembership<NodeId, ()>), StorageError<NodeId>> {
         let sm = self.state_machine.read().await;
-        let last_log_id = if sm.last_applied_log_index > 0 {
-            Some(LogId::new(
-                openraft::LeaderId::new(0, 0),
-                sm.last_applied_log_index
-            ))
-        } else {
-            None
-        };
+        let last_log_id = sm.last_applied_log_id;
 
         let committed = self.committed.read().await.clone().unwrap_or_default();
 
@@ -257,21 +480,58 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
     ) -> Result<Vec<GameEventResponse>, StorageError<NodeId>> {
         let mut sm = self.state_machine.write().await;
         let mut responses = Vec::new();
+        let mut addresses = Vec::new();
 
         for entry in entries {
-            if let EntryPayload::Normal(request) = &entry.payload {
-                // Store event for replay/audit
-                sm.events.push(request.event.clone());
-
-                // Process event into derived game state
-                sm.game_state.process_event(request.event.clone(), entry.log_id.index);
-
-                sm.last_applied_log_index = entry.log_id.index;
-                responses.push(GameEventResponse { success: true });
-            } else {
-                responses.push(GameEventResponse { success: false });
+            sm.last_applied_log_id = Some(entry.log_id);
+            match &entry.payload {
+                EntryPayload::Normal(request) => {
+                    // Store event for replay/audit
+                    if let Some(origin) = &request.origin {
+                        let index = sm.events.len();
+                        sm.audit.insert(index, origin.clone());
+                    }
+                    let position = sm.events.len();
+                    sm.events.push(request.event.clone());
+                    sm.event_times.push(request.committed_at);
+                    sm.event_indices.push(entry.log_id.index);
+
+                    // Keep the registry in step with committed node announcements
+                    addresses.extend(committed_node_address(&request.event));
+
+                    // Process event into derived game state, on the leader's clock
+                    let changes = sm.apply_event(position, entry.log_id.index, request.committed_at);
+                    if !changes.is_empty() {
+                        sm.change_version += 1;
+                    }
+                    #[cfg(debug_assertions)]
+                    for violation in sm.game_state.check_invariants() {
+                        eprintln!("[Invariants] Broken after log index {}: {}", entry.log_id.index, violation);
+                    }
+
+                    self.digests.applied(entry.log_id.index, &request.event, &sm.game_state);
+
+                    sm.last_applied_log_index = entry.log_id.index;
+                    self.hooks.applied(entry.log_id.index, request, &changes, &sm.game_state).await;
+                    responses.push(GameEventResponse { success: true });
+                }
+                EntryPayload::Membership(membership) => {
+                    *self.committed.write().await =
+                        Some(StoredMembership::new(Some(entry.log_id), membership.clone()));
+                    responses.push(GameEventResponse { success: false });
+                }
+                EntryPayload::Blank => {
+                    responses.push(GameEventResponse { success: false });
+                }
             }
         }
+        let applied = sm.last_applied_log_index;
+        drop(sm);
+        self.applied.send_if_modified(|index| std::mem::replace(index, applied) != applied);
+
+        for (node_id, addr, coord) in addresses {
+            self.registry.register_committed(node_id, addr, coord).await;
+        }
 
         Ok(responses)
     }
@@ -296,23 +556,44 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
                 StorageError::from_io_error(
                     ErrorSubject::Snapshot(Some(meta.signature())),
                     ErrorVerb::Read,
-                    std::io::Error::new(std::io::ErrorKind::Other, e)
+                    std::io::Error::other(e)
                 )
             })?;
 
         let mut sm = self.state_machine.write().await;
         sm.events = snapshot_data.events.clone();
+        sm.event_times = snapshot_data.event_times.clone();
+        sm.audit = snapshot_data.audit.clone();
         sm.last_applied_log_index = snapshot_data.last_applied_log_index;
+        sm.last_applied_log_id = meta.last_log_id;
 
-        // Rebuild game state from events
+        // Rebuild game state (and registry view) from events
         sm.game_state = GameState::new();
+        sm.dead_letters.clear();
+        let mut addresses = Vec::new();
+        sm.event_indices.clear();
         for (idx, event) in snapshot_data.events.iter().enumerate() {
-            sm.game_state.process_event(event.clone(), idx as u64 + 1);
+            addresses.extend(committed_node_address(event));
+            let committed_at = snapshot_data.event_times.get(idx).copied().unwrap_or(0);
+            // Snapshots from before event_indices fall back to positions
+            let log_index = snapshot_data.event_indices.get(idx).copied().unwrap_or(idx as u64 + 1);
+            sm.event_indices.push(log_index);
+            sm.apply_event(idx, log_index, committed_at);
+        }
+        sm.change_version += 1;
+        self.hooks.snapshot_installed(sm.last_applied_log_index, &sm.game_state).await;
+        let applied = sm.last_applied_log_index;
+        drop(sm);
+        self.applied.send_replace(applied);
+
+        for (node_id, addr, coord) in addresses {
+            self.registry.register_committed(node_id, addr, coord).await;
         }
 
         *self.snapshot.write().await = Some(snapshot_data);
         *self.snapshot_meta.write().await = Some(meta.clone());
         *self.committed.write().await = Some(meta.last_membership.clone());
+        self.persist_snapshot(meta, snapshot.get_ref()).await;
 
         Ok(())
     }
@@ -329,7 +610,7 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
                         StorageError::from_io_error(
                             ErrorSubject::Snapshot(Some(meta.signature())),
                             ErrorVerb::Read,
-                            std::io::Error::new(std::io::ErrorKind::Other, e)
+                            std::io::Error::other(e)
                         )
                     })?;
 
@@ -342,3 +623,186 @@ impl RaftStorage<GameRaftTypeConfig> for MemStorage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raft::hooks::ForwardHook;
+
+    fn join(raft_node_id: u64, ip: &str) -> GameEvent {
+        GameEvent::Command(CommandEvent::PlayerJoin {
+            player_id: raft_node_id,
+            name: format!("player{}", raft_node_id),
+            capital_coord: crate::game::NodeCoord::new(0, 0),
+            capital_ip: Some(ip.to_string()),
+            client_endpoint: None,
+            timestamp: 0,
+            raft_node_id,
+            reconnect_key: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_apply_registers_committed_addresses() {
+        let registry = NodeRegistry::new();
+        let mut storage = MemStorage::with_registry(registry.clone());
+
+        let entries: Vec<Entry<GameRaftTypeConfig>> = [join(7, "10.0.1.7"), join(0, "10.0.1.8")]
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| Entry {
+                log_id: LogId::new(openraft::LeaderId::new(1, 1), i as u64 + 1),
+                payload: EntryPayload::Normal(GameEventRequest { event, committed_at: 0, origin: None }),
+            })
+            .collect();
+        storage.apply_to_state_machine(&entries).await.unwrap();
+
+        // Only announcements carrying a raft node id land in the registry
+        assert_eq!(registry.get_address(7).await, Some("10.0.1.7:5000".to_string()));
+        assert_eq!(registry.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_snapshot_resumes_after_snapshot() {
+        let mut leader = MemStorage::new();
+        let entries: Vec<Entry<GameRaftTypeConfig>> = [join(7, "10.0.1.7"), join(8, "10.0.1.8")]
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| Entry {
+                log_id: LogId::new(openraft::LeaderId::new(3, 1), i as u64 + 1),
+                payload: EntryPayload::Normal(GameEventRequest { event, committed_at: 0, origin: None }),
+            })
+            .collect();
+        append_and_apply(&mut leader, entries).await;
+
+        let (meta, data) = leader.export_snapshot().await.unwrap();
+        let registry = NodeRegistry::new();
+        let mut joiner = MemStorage::with_registry(registry.clone());
+        joiner.install_bootstrap_snapshot(&meta, data).await.unwrap();
+
+        // State, registry and log position all come from the snapshot
+        let expected = Some(LogId::new(openraft::LeaderId::new(3, 1), 2));
+        assert_eq!(joiner.state_machine().read().await.game_state.players.len(), 2);
+        assert_eq!(registry.get_address(8).await, Some("10.0.1.8:5000".to_string()));
+        assert_eq!(joiner.last_applied_state().await.unwrap().0, expected);
+        let log_state = joiner.get_log_state().await.unwrap();
+        assert_eq!(log_state.last_purged_log_id, expected);
+        assert_eq!(log_state.last_log_id, expected);
+    }
+
+    #[tokio::test]
+    async fn test_leader_stamps_and_origins_survive_snapshot() {
+        let mut leader = MemStorage::new();
+        let entry = Entry {
+            log_id: LogId::new(openraft::LeaderId::new(1, 1), 1),
+            payload: EntryPayload::Normal(GameEventRequest {
+                event: join(7, "10.0.1.7"),
+                committed_at: 1000,
+                origin: Some(CommandOrigin::new([203, 0, 113, 9].into(), Some(7))),
+            }),
+        };
+        append_and_apply(&mut leader, vec![entry]).await;
+        assert_eq!(leader.state_machine().read().await.game_state.players[&7].join_time, 1000);
+
+        // A joiner rebuilds on the leader's clock, not the event's own timestamp (0)
+        let (meta, data) = leader.export_snapshot().await.unwrap();
+        let mut joiner = MemStorage::new();
+        joiner.install_bootstrap_snapshot(&meta, data).await.unwrap();
+        let sm = joiner.state_machine();
+        let sm = sm.read().await;
+        assert_eq!(sm.event_times, vec![1000]);
+        assert_eq!(sm.audit[&0].ip.as_deref(), Some("203.0.113.9"));
+        assert_eq!(sm.game_state.players[&7].join_time, 1000);
+        assert_eq!(sm.game_state.clock, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_unappliable_events_are_dead_letters() {
+        let mut leader = MemStorage::new();
+        let stray = GameEvent::Command(CommandEvent::SetNodeTarget {
+            node_coord: NodeCoord::new(5, 5),
+            target: None,
+            timestamp: 0,
+        });
+        let entries: Vec<Entry<GameRaftTypeConfig>> = [join(7, "10.0.1.7"), stray]
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| Entry {
+                log_id: LogId::new(openraft::LeaderId::new(1, 1), i as u64 + 1),
+                payload: EntryPayload::Normal(GameEventRequest { event, committed_at: 1000, origin: None }),
+            })
+            .collect();
+        append_and_apply(&mut leader, entries).await;
+
+        let dead_letters = leader.state_machine().read().await.dead_letters.clone();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!((dead_letters[0].log_index, dead_letters[0].position), (2, 1));
+        assert_eq!((dead_letters[0].kind.as_str(), dead_letters[0].reason.as_str()), ("SetNodeTarget", "unknown node"));
+
+        // A joiner rebuilding from a snapshot finds the same ones
+        let (meta, data) = leader.export_snapshot().await.unwrap();
+        let mut joiner = MemStorage::new();
+        joiner.install_bootstrap_snapshot(&meta, data).await.unwrap();
+        let rebuilt = joiner.state_machine().read().await.dead_letters.clone();
+        assert_eq!(rebuilt.len(), 1);
+        assert_eq!(rebuilt[0].log_index, 2);
+    }
+
+    #[tokio::test]
+    async fn test_apply_hooks_see_committed_events() {
+        let mut leader = MemStorage::new();
+        let (hook, mut applied) = ForwardHook::new("test");
+        leader.hooks().register(hook.clone()).await;
+
+        let entries: Vec<Entry<GameRaftTypeConfig>> = [join(7, "10.0.1.7"), join(8, "10.0.1.8")]
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| Entry {
+                log_id: LogId::new(openraft::LeaderId::new(1, 1), i as u64 + 1),
+                payload: EntryPayload::Normal(GameEventRequest { event, committed_at: 0, origin: None }),
+            })
+            .collect();
+        append_and_apply(&mut leader, entries).await;
+
+        let first = applied.try_recv().unwrap();
+        assert_eq!(first.log_index, 1);
+        assert!(matches!(first.request.event, GameEvent::Command(CommandEvent::PlayerJoin { player_id: 7, .. })));
+        assert_eq!(first.changes[0], StateChange::PlayerAdded { player_id: 7 });
+        assert_eq!(applied.try_recv().unwrap().log_index, 2);
+        assert_eq!(leader.state_machine().read().await.change_version, 2);
+
+        // A snapshot install isn't replayed through on_apply
+        let (meta, data) = leader.export_snapshot().await.unwrap();
+        let mut joiner = MemStorage::new();
+        joiner.hooks().register(hook).await;
+        joiner.install_bootstrap_snapshot(&meta, data).await.unwrap();
+        assert!(applied.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_built_snapshots_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("camhack-storage-snapshots-{}", std::process::id()));
+        let store = SnapshotStore::new(&dir, "game1", 2);
+        let mut before = MemStorage::new().with_snapshot_store(Some(store.clone()));
+        let entry = Entry {
+            log_id: LogId::new(openraft::LeaderId::new(1, 1), 1),
+            payload: EntryPayload::Normal(GameEventRequest { event: join(7, "10.0.1.7"), committed_at: 0, origin: None }),
+        };
+        append_and_apply(&mut before, vec![entry]).await;
+        before.build_snapshot().await.unwrap();
+
+        // The restarted node starts from the file, not an empty state machine
+        let (meta, data) = store.load_latest().await.unwrap();
+        let mut after = MemStorage::new();
+        after.install_bootstrap_snapshot(&meta, data).await.unwrap();
+        assert_eq!(after.state_machine().read().await.game_state.players.len(), 1);
+        assert_eq!(after.get_log_state().await.unwrap().last_purged_log_id, meta.last_log_id);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    async fn append_and_apply(storage: &mut MemStorage, entries: Vec<Entry<GameRaftTypeConfig>>) {
+        storage.append_to_log(entries.clone()).await.unwrap();
+        storage.apply_to_state_machine(&entries).await.unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+// Raft write failures, typed for API callers
+//
+// `Raft::client_write` fails for reasons a caller handles differently: this node
+// isn't the leader (send the request there instead), a membership change is in
+// flight (send it again shortly), Raft is shutting down here, or storage failed
+// (this node won't commit anything again). RaftWriteError keeps them apart, with
+// the HTTP status and retry hint each one gets. The write endpoints return it as
+// `error` next to `success` and `message`, so callers don't parse the message;
+// the SubmitCommand RPC maps it onto gRPC codes the same way.
+
+use crate::raft::node_registry::NodeRegistry;
+use crate::raft::storage::NodeId;
+use axum::http::StatusCode;
+use openraft::error::{ClientWriteError, Fatal, RaftError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How long to wait before sending a write again when the hint is `Later`
+pub const RETRY_AFTER_MS: u64 = 500;
+
+/// What a caller should do with a write that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryHint {
+    /// Send it to the leader (`leader_api_addr`, or find it once an election settles)
+    Leader,
+    /// Send it to this node again after `retry_after_ms`
+    Later,
+    /// Don't send it to this node again
+    Never,
+}
+
+/// Why a Raft write failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RaftWriteError {
+    /// Only the leader commits; None while there is no leader (an election is running)
+    NotLeader {
+        leader_id: Option<NodeId>,
+        /// The leader's HTTP API (host:8080), if this node knows where it is
+        leader_api_addr: Option<String>,
+    },
+    /// The cluster's membership is changing
+    MembershipChange { reason: String },
+    /// Raft has stopped on this node (shutting down, or its core panicked)
+    Stopped,
+    /// The log or state machine failed - this node can't commit anything anymore
+    Storage { reason: String },
+}
+
+/// The `error` of a failed write in an API response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteErrorInfo {
+    #[serde(flatten)]
+    pub error: RaftWriteError,
+    pub retry: RetryHint,
+    /// Set when `retry` is `later`
+    pub retry_after_ms: Option<u64>,
+}
+
+impl RaftWriteError {
+    /// A NotLeader for the leader `leader_id`, located through `registry`
+    pub async fn not_leader(leader_id: Option<NodeId>, registry: &NodeRegistry) -> Self {
+        let leader_api_addr = match leader_id {
+            // The leader's API listens on port 8080 of the host its Raft address names
+            Some(id) => registry.get_address(id).await.map(|raft_addr| {
+                let host = raft_addr.rsplit_once(':').map_or(raft_addr.as_str(), |(host, _)| host);
+                format!("{}:8080", host)
+            }),
+            None => None,
+        };
+        RaftWriteError::NotLeader { leader_id, leader_api_addr }
+    }
+
+    /// Type a client_write failure, locating the leader if that's where it should go
+    pub async fn from_client_write(error: RaftError<NodeId, ClientWriteError<NodeId, ()>>, registry: &NodeRegistry) -> Self {
+        match error {
+            RaftError::APIError(ClientWriteError::ForwardToLeader(forward)) => {
+                Self::not_leader(forward.leader_id, registry).await
+            }
+            RaftError::APIError(ClientWriteError::ChangeMembershipError(e)) => {
+                RaftWriteError::MembershipChange { reason: e.to_string() }
+            }
+            RaftError::Fatal(Fatal::StorageError(e)) => RaftWriteError::Storage { reason: e.to_string() },
+            RaftError::Fatal(Fatal::Panicked | Fatal::Stopped) => RaftWriteError::Stopped,
+        }
+    }
+
+    /// HTTP status for a response carrying this error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            RaftWriteError::Storage { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn retry(&self) -> RetryHint {
+        match self {
+            RaftWriteError::NotLeader { .. } => RetryHint::Leader,
+            RaftWriteError::MembershipChange { .. } => RetryHint::Later,
+            RaftWriteError::Stopped | RaftWriteError::Storage { .. } => RetryHint::Never,
+        }
+    }
+
+    /// This error as an API response's `error`
+    pub fn info(&self) -> WriteErrorInfo {
+        let retry = self.retry();
+        WriteErrorInfo {
+            error: self.clone(),
+            retry,
+            retry_after_ms: (retry == RetryHint::Later).then_some(RETRY_AFTER_MS),
+        }
+    }
+}
+
+impl fmt::Display for RaftWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaftWriteError::NotLeader { leader_id: Some(id), .. } => write!(f, "Not the leader. Current leader: {}", id),
+            RaftWriteError::NotLeader { leader_id: None, .. } => write!(f, "Not the leader, and no leader is elected"),
+            RaftWriteError::MembershipChange { reason } => write!(f, "Cluster membership is changing: {}", reason),
+            RaftWriteError::Stopped => write!(f, "Raft is shutting down on this node"),
+            RaftWriteError::Storage { reason } => write!(f, "Storage failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RaftWriteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openraft::error::ForwardToLeader;
+    use openraft::{ErrorSubject, ErrorVerb, StorageError};
+
+    #[tokio::test]
+    async fn test_client_write_errors_are_typed() {
+        let registry = NodeRegistry::new();
+        registry.register(3, "10.0.0.3:5000".to_string()).await;
+
+        let forward = RaftError::APIError(ClientWriteError::ForwardToLeader(ForwardToLeader { leader_id: Some(3), leader_node: None }));
+        let error = RaftWriteError::from_client_write(forward, &registry).await;
+        assert_eq!(error, RaftWriteError::NotLeader { leader_id: Some(3), leader_api_addr: Some("10.0.0.3:8080".to_string()) });
+        assert_eq!((error.status(), error.retry()), (StatusCode::SERVICE_UNAVAILABLE, RetryHint::Leader));
+
+        let stopped = RaftError::Fatal(Fatal::Stopped);
+        assert_eq!(RaftWriteError::from_client_write(stopped, &registry).await, RaftWriteError::Stopped);
+
+        let io = std::io::Error::other("disk full");
+        let storage = RaftError::Fatal(Fatal::StorageError(StorageError::from_io_error(ErrorSubject::Logs, ErrorVerb::Write, io)));
+        let error = RaftWriteError::from_client_write(storage, &registry).await;
+        assert!(matches!(error, RaftWriteError::Storage { .. }));
+        assert_eq!((error.status(), error.retry()), (StatusCode::INTERNAL_SERVER_ERROR, RetryHint::Never));
+    }
+
+    #[test]
+    fn test_error_envelope() {
+        let info = RaftWriteError::MembershipChange { reason: "in progress".to_string() }.info();
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["kind"], "membership_change");
+        assert_eq!(json["retry"], "later");
+        assert_eq!(json["retry_after_ms"], RETRY_AFTER_MS);
+        assert_eq!(serde_json::from_value::<WriteErrorInfo>(json).unwrap(), info);
+
+        let json = serde_json::to_value(RaftWriteError::NotLeader { leader_id: None, leader_api_addr: None }.info()).unwrap();
+        assert_eq!(json["kind"], "not_leader");
+        assert_eq!(json["retry"], "leader");
+    }
+}
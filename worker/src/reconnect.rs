@@ -0,0 +1,120 @@
+// Reconnect tokens - the same player after a client restart
+//
+// A client used to join as a brand new player every time it started, so a crashed
+// laptop lost its nodes to a stranger with the same name. Now a client's PlayerJoin
+// carries the SHA-256 of a secret token (`reconnect_key`), and the token itself is
+// saved in a session file (RECONNECT_FILE, default camhack-session.json). A
+// restarted client that finds a session for the game submits PlayerReconnected with
+// the token instead of joining again. The leader checks it against the committed key
+// (see refuse_event), and the commit replaces the key with the hash of a fresh token
+// the client saved beforehand, so a token read out of the log is already spent.
+
+use crate::game::NodeCoord;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A new secret reconnect token: 32 random bytes, hex
+pub fn new_token() -> String {
+    hex(&rand::random::<[u8; 32]>())
+}
+
+/// The key a token is committed as: its SHA-256, hex
+pub fn reconnect_key(token: &str) -> String {
+    hex(ring::digest::digest(&ring::digest::SHA256, token.as_bytes()).as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// What a client needs to take its player back after a restart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub game_id: String,
+    pub player_id: u64,
+    pub player_name: String,
+    pub capital_coord: NodeCoord,
+    /// Secret whose key is committed for the player
+    pub token: String,
+    /// Token whose key a reconnect was submitting, in case it committed before the
+    /// client could save - tried after `token` on the next start
+    #[serde(default)]
+    pub next_token: Option<String>,
+}
+
+impl Session {
+    /// Where sessions are saved: RECONNECT_FILE (default: camhack-session.json)
+    pub fn default_path() -> PathBuf {
+        std::env::var("RECONNECT_FILE")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("camhack-session.json"))
+    }
+
+    /// The saved session for `player_name` in `game_id`, if any
+    pub async fn load(path: &Path, game_id: &str, player_name: &str) -> Option<Self> {
+        let bytes = match tokio::fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                eprintln!("[Reconnect] Failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let session: Self = serde_json::from_slice(&bytes)
+            .map_err(|e| eprintln!("[Reconnect] Ignoring corrupt session file {}: {}", path.display(), e))
+            .ok()?;
+        Some(session).filter(|s| s.game_id == game_id && s.player_name == player_name)
+    }
+
+    /// Write to `path` via a temp file + rename, like the worker identity
+    pub async fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+
+    /// Tokens to try, the saved one first
+    pub fn candidates(&self) -> Vec<String> {
+        std::iter::once(self.token.clone()).chain(self.next_token.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_key() {
+        assert_eq!(reconnect_key("test"), "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+        let (a, b) = (new_token(), new_token());
+        assert_eq!(a.len(), 64);
+        assert_ne!(a, b);
+    }
+
+    #[tokio::test]
+    async fn test_session_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("camhack-session-{}", std::process::id()));
+        let path = dir.join("session.json");
+        let session = Session {
+            game_id: "game1".to_string(),
+            player_id: 7,
+            player_name: "alice".to_string(),
+            capital_coord: NodeCoord::new(3, 0),
+            token: new_token(),
+            next_token: None,
+        };
+        session.save(&path).await.unwrap();
+
+        assert_eq!(Session::load(&path, "game1", "alice").await, Some(session.clone()));
+        // Another game, or another name on the same laptop, starts fresh
+        assert_eq!(Session::load(&path, "game2", "alice").await, None);
+        assert_eq!(Session::load(&path, "game1", "bob").await, None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
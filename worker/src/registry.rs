@@ -1,3 +1,5 @@
+use crate::game::GameSummary;
+use crate::request_id::{self, RequestIdExt};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::env;
@@ -14,9 +16,11 @@ struct RegisterWorkerRequest {
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct RegisterWorkerResponse {
     message: String,
+    /// Stable name the master registered for us (DNS discovery mode only)
+    #[serde(default)]
+    dns_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,27 +29,201 @@ struct GetPeerResponse {
     peer_port: Option<u16>,
 }
 
+/// One game in the master's GET /games (mirrors the master's GameInfo)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameInfo {
+    pub game_id: String,
+    pub worker_count: usize,
+    pub created_at_secs: u64,
+    /// starting (no summary yet) | lobby | live | sudden_death | finished
+    pub status: String,
+    pub player_names: Vec<String>,
+    pub alive_players: usize,
+    pub node_count: usize,
+    pub map_radius: u32,
+    pub rules_preset: Option<String>,
+    pub joinable: bool,
+    /// Leader's /game/state
+    pub spectator_url: Option<String>,
+    /// Finished game this one is a rematch of
+    #[serde(default)]
+    pub rematch_of: Option<String>,
+    /// Rematch staged from this game, once someone asked for one
+    #[serde(default)]
+    pub rematch_game_id: Option<String>,
+    /// Players invited back for a rematch
+    #[serde(default)]
+    pub invited_players: Vec<String>,
+}
+
+/// Response for the master's POST /games/{id}/rematch (mirrors the master's)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RematchResponse {
+    pub game_id: String,
+    pub rematch_of: String,
+    pub invited_players: Vec<String>,
+    pub rules_preset: String,
+    /// Founding worker of the new game (None if ECS started nothing)
+    pub task_arn: Option<String>,
+}
+
+/// Response for the master's POST /games and /spawn_workers (mirrors the master's)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnResponse {
+    pub message: String,
+    pub spawned_count: usize,
+    pub task_arns: Vec<String>,
+    /// Only in the response that created the game
+    #[serde(default)]
+    pub referee_token: Option<String>,
+}
+
+/// Response for the master's GET /games (and the client's /discover passthrough)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetGamesResponse {
+    pub games: Vec<GameInfo>,
+}
+
 /// Peer information for joining a Raft cluster
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
+    /// IP address, or DNS name in DNS discovery mode
     pub ip: String,
     pub port: u16,
 }
 
+/// Result of registering with the master
+#[derive(Debug, Clone)]
+pub struct Registration {
+    /// Peer to join, or None to bootstrap a new cluster
+    pub peer: Option<PeerInfo>,
+    /// Stable DNS name for this worker, if the master runs in DNS discovery mode
+    pub dns_name: Option<String>,
+}
+
+impl Registration {
+    /// Address to advertise to peers - the DNS name if we have one, otherwise `my_ip`
+    pub fn advertised_host(&self, my_ip: &str) -> String {
+        self.dns_name.clone().unwrap_or_else(|| my_ip.to_string())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyPlayerRequest<'a> {
+    name: &'a str,
+    token: &'a str,
+}
+
+/// A player account on the master (POST /players/verify)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerAccount {
+    pub player_id: u64,
+    pub name: String,
+    pub rating: f64,
+}
+
+/// Response for the master's POST /players - the token is shown only once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterPlayerResponse {
+    pub player_id: u64,
+    pub name: String,
+    pub token: String,
+    pub rating: f64,
+}
+
+/// One finished game in a player's history (mirrors the master's)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatedGame {
+    pub game_id: String,
+    pub won: bool,
+    pub players: usize,
+    pub rating_before: f64,
+    pub rating_after: f64,
+    pub finished_at: u64,
+}
+
+/// Response for the master's GET /players/{id}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    pub player_id: u64,
+    pub name: String,
+    pub rating: f64,
+    /// Oldest first
+    pub games: Vec<RatedGame>,
+}
+
+/// Check a returning player's token with the master, returning their account
+pub async fn verify_player(master_url: &str, name: &str, token: &str) -> Result<PlayerAccount> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/players/verify", master_url))
+        .request_id()
+        .json(&VerifyPlayerRequest { name, token })
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .context("Failed to reach master to verify player")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Master rejected player {}: {}", name, response.status());
+    }
+    response.json().await.context("Failed to parse player account")
+}
+
+#[derive(Debug, Serialize)]
+struct PoolReadyRequest {
+    worker_id: String,
+}
+
+/// Tell the master this warm pool worker is up and can adopt a coordinate
+pub async fn announce_warm(master_url: &str, game_id: &str, worker_id: &str) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/games/{}/pool", master_url, game_id))
+        .request_id()
+        .json(&PoolReadyRequest { worker_id: worker_id.to_string() })
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .context("Failed to announce warm worker to master")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Master rejected warm worker: {}", response.status());
+    }
+    Ok(())
+}
+
+/// Ship a game summary to the master (leader only)
+/// Short timeout - this runs inside the leader's tick. Carries INTERNAL_API_TOKEN,
+/// which the master requires of summaries since they decide ratings.
+pub async fn report_summary(master_url: &str, summary: &GameSummary) -> Result<()> {
+    let request = reqwest::Client::new().post(format!("{}/games/{}/summary", master_url, summary.game_id));
+    let response = crate::raft::internal::with_token(request)
+        .request_id()
+        .json(summary)
+        .timeout(std::time::Duration::from_secs(2))
+        .send()
+        .await
+        .context("Failed to send summary to master")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Master rejected summary: {}", response.status());
+    }
+    Ok(())
+}
+
 /// Register this worker with the master and get a peer to join (if any)
 pub async fn register_and_get_peer(
     worker_id: String,
     task_arn: String,
     my_ip: String,
     game_id: String,
-) -> Result<Option<PeerInfo>> {
+) -> Result<Registration> {
     let master_url = env::var("MASTER_URL")
         .context("MASTER_URL environment variable not set")?;
 
     let client = reqwest::Client::new();
 
     // Register ourselves with the master
-    println!("Registering with master at {} for game {}", master_url, game_id);
+    println!("{}Registering with master at {} for game {}", request_id::prefix(), master_url, game_id);
     let register_req = RegisterWorkerRequest {
         worker_id: worker_id.clone(),
         task_arn,
@@ -56,6 +234,7 @@ pub async fn register_and_get_peer(
 
     let response: RegisterWorkerResponse = client
         .post(format!("{}/register_worker", master_url))
+        .request_id()
         .json(&register_req)
         .send()
         .await
@@ -65,11 +244,15 @@ pub async fn register_and_get_peer(
         .context("Failed to parse registration response")?;
 
     println!("Registration response: {}", response.message);
+    if let Some(name) = &response.dns_name {
+        println!("Advertising DNS name {}", name);
+    }
 
     // Get a peer to join (if any exist) for this specific game
     println!("Requesting peer from master for game {}...", game_id);
     let peer_response: GetPeerResponse = client
         .get(format!("{}/get_peer?game_id={}&requesting_ip={}", master_url, game_id, register_req.ip))
+        .request_id()
         .send()
         .await
         .context("Failed to get peer from master")?
@@ -77,14 +260,16 @@ pub async fn register_and_get_peer(
         .await
         .context("Failed to parse peer response")?;
 
-    match (peer_response.peer_ip, peer_response.peer_port) {
+    let peer = match (peer_response.peer_ip, peer_response.peer_port) {
         (Some(ip), Some(port)) => {
             println!("Got peer from master for game {}: {}:{}", game_id, ip, port);
-            Ok(Some(PeerInfo { ip, port }))
+            Some(PeerInfo { ip, port })
         }
         _ => {
             println!("No peers available for game {} - will bootstrap new cluster", game_id);
-            Ok(None)
+            None
         }
-    }
+    };
+
+    Ok(Registration { peer, dns_name: response.dns_name })
 }
@@ -0,0 +1,115 @@
+// Request ids - one id for a call chain across client, worker and master
+//
+// A failed join touches three binaries: the client's /game/join registers with the
+// master, joins Raft through a worker and asks the master to spawn a capital, whose
+// worker then registers itself. Every router runs `propagate`: it takes the caller's
+// X-Request-Id (or makes one up), runs the handler with it as the current id, echoes
+// it on the response and logs failed responses with it. Calls to the other services
+// carry the current id (`RequestIdExt::request_id`), and the master hands it to the
+// workers it starts as REQUEST_ID, so grepping the three logs for one id follows the
+// whole chain. Work outside a request (a worker's startup) runs under `scope`.
+
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::future::Future;
+
+/// Header the id travels in
+pub const HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest id accepted from a caller; longer ones are replaced
+const MAX_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// A fresh id: 16 random hex digits
+pub fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// The id of the request or scope this task is running under, if any
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Run `f` with `id` as the current request id
+pub async fn scope<F: Future>(id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(id, f).await
+}
+
+/// "[Request <id>] " inside a request or scope, for log lines worth following across services
+pub fn prefix() -> String {
+    current().map(|id| format!("[Request {}] ", id)).unwrap_or_default()
+}
+
+/// A caller's id, if it's one we can log and echo safely
+fn accept(value: &HeaderValue) -> Option<String> {
+    let id = value.to_str().ok()?.trim();
+    let printable = id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c));
+    (!id.is_empty() && id.len() <= MAX_LEN && printable).then(|| id.to_string())
+}
+
+/// Middleware: adopt or assign the request's id, echo it, and log failures with it
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let id = request.headers().get(HEADER).and_then(accept).unwrap_or_else(new_id);
+    let (method, path) = (request.method().clone(), request.uri().path().to_string());
+
+    let mut response = scope(id.clone(), next.run(request)).await;
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        eprintln!("[Request {}] {} {} -> {}", id, method, path, status);
+    }
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HEADER, value);
+    }
+    response
+}
+
+/// Attach the current request id to a call to another service
+pub trait RequestIdExt {
+    /// Send the current id along; outside any request a fresh one
+    fn request_id(self) -> Self;
+}
+
+impl RequestIdExt for reqwest::RequestBuilder {
+    fn request_id(self) -> Self {
+        self.header(HEADER.as_str(), current().unwrap_or_else(new_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+
+    #[tokio::test]
+    async fn test_propagate() {
+        let app = Router::new()
+            .route("/id", get(|| async { current().unwrap_or_default() }))
+            .route("/fail", get(|| async { StatusCode::BAD_GATEWAY }))
+            .layer(axum::middleware::from_fn(propagate));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+
+        // The caller's current id is the handler's, and comes back on the response
+        let call = async { client.get(format!("{}/id", url)).request_id().send().await };
+        let response = scope("join-42".to_string(), call).await.unwrap();
+        assert_eq!(response.headers()[HEADER.as_str()], "join-42");
+        assert_eq!(response.text().await.unwrap(), "join-42");
+
+        // Without one (or with one unfit for a log line) a fresh id is assigned
+        let response = client.get(format!("{}/fail", url)).send().await.unwrap();
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_GATEWAY.as_u16());
+        assert_eq!(response.headers()[HEADER.as_str()].len(), 16);
+        let response = client.get(format!("{}/id", url)).header(HEADER.as_str(), "a b").send().await.unwrap();
+        assert_ne!(response.text().await.unwrap(), "a b");
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,303 @@
+// Tick scheduler - named periodic tasks for the worker's main loop
+//
+// Each task (network sync, reconciliation, game logic, ...) has its own interval instead
+// of being a counter in one 1-second loop. Tasks run one at a time in registration
+// order whenever they're due, so a task can still rely on the ones registered before
+// it having run. A task that panics is logged and counted; the others keep going.
+
+use crate::game::settings::{MAX_GAME_TICK_MS, MIN_GAME_TICK_MS};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How often each of the worker's periodic tasks runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickIntervals {
+    /// Sync attack connections with committed state
+    pub network: Duration,
+    /// Full network reconciliation (also runs on leadership change)
+    pub reconcile: Duration,
+    /// Leader: captures, lazy initialization, effect requests and execution
+    pub game_logic: Duration,
+    /// Leader: ship a game summary to the master
+    pub summary: Duration,
+    /// Leader: commit the newest state digest for followers to check against
+    pub digest: Duration,
+    /// Status log line
+    pub status: Duration,
+}
+
+impl TickIntervals {
+    /// Load intervals from the environment
+    /// NETWORK_TICK_MS (default: 1000), RECONCILE_INTERVAL_SECS (30),
+    /// GAME_TICK_MS (1000, MIN_GAME_TICK_MS to MAX_GAME_TICK_MS), SUMMARY_INTERVAL_SECS (5), STATE_DIGEST_INTERVAL_SECS (30),
+    /// STATUS_INTERVAL_SECS (30)
+    pub fn from_env() -> Self {
+        fn env_or(name: &str, default: Duration, unit: fn(u64) -> Duration) -> Duration {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(unit)
+                .filter(|d| !d.is_zero())
+                .unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+        Self {
+            network: env_or("NETWORK_TICK_MS", defaults.network, Duration::from_millis),
+            reconcile: env_or("RECONCILE_INTERVAL_SECS", defaults.reconcile, Duration::from_secs),
+            game_logic: env_or("GAME_TICK_MS", defaults.game_logic, Duration::from_millis)
+                .clamp(Duration::from_millis(MIN_GAME_TICK_MS), Duration::from_millis(MAX_GAME_TICK_MS)),
+            summary: env_or("SUMMARY_INTERVAL_SECS", defaults.summary, Duration::from_secs),
+            digest: env_or("STATE_DIGEST_INTERVAL_SECS", defaults.digest, Duration::from_secs),
+            status: env_or("STATUS_INTERVAL_SECS", defaults.status, Duration::from_secs),
+        }
+    }
+}
+
+impl TickIntervals {
+    /// These intervals for a game running `time_scale` times faster (see GameConfig::time_scale)
+    /// Everything that paces the game speeds up; reconciliation, digests and the status line don't.
+    pub fn scaled(self, time_scale: u32) -> Self {
+        let scale = time_scale.max(1);
+        Self {
+            network: self.network / scale,
+            game_logic: self.game_logic / scale,
+            summary: self.summary / scale,
+            ..self
+        }
+    }
+}
+
+impl Default for TickIntervals {
+    fn default() -> Self {
+        Self {
+            network: Duration::from_secs(1),
+            reconcile: Duration::from_secs(30),
+            game_logic: Duration::from_secs(1),
+            summary: Duration::from_secs(5),
+            digest: Duration::from_secs(30),
+            status: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Timing for one task
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TaskStats {
+    pub interval_ms: u64,
+    pub runs: u64,
+    pub panics: u64,
+    /// Runs that took longer than the interval
+    pub overruns: u64,
+    pub last_ms: f64,
+    pub max_ms: f64,
+    pub total_ms: f64,
+}
+
+impl TaskStats {
+    pub fn mean_ms(&self) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            self.total_ms / self.runs as f64
+        }
+    }
+}
+
+/// Shared view of every task's stats, by name
+pub type SchedulerStats = Arc<Mutex<BTreeMap<&'static str, TaskStats>>>;
+
+type TaskFn = Box<dyn FnMut() -> BoxFuture<'static, ()> + Send>;
+
+struct ScheduledTask {
+    name: &'static str,
+    interval: Duration,
+    next_due: Instant,
+    run: TaskFn,
+}
+
+/// Runs named periodic tasks on independent intervals
+pub struct TickScheduler {
+    tasks: Vec<ScheduledTask>,
+    stats: SchedulerStats,
+}
+
+impl TickScheduler {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            stats: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Run `task` every `interval`, first one interval from now
+    pub fn every<F>(&mut self, name: &'static str, interval: Duration, task: F) -> &mut Self
+    where
+        F: FnMut() -> BoxFuture<'static, ()> + Send + 'static,
+    {
+        self.add(name, interval, Instant::now() + interval, Box::new(task))
+    }
+
+    /// Run `task` every `interval`, first on the scheduler's first pass
+    pub fn every_from_start<F>(&mut self, name: &'static str, interval: Duration, task: F) -> &mut Self
+    where
+        F: FnMut() -> BoxFuture<'static, ()> + Send + 'static,
+    {
+        self.add(name, interval, Instant::now(), Box::new(task))
+    }
+
+    fn add(&mut self, name: &'static str, interval: Duration, next_due: Instant, run: TaskFn) -> &mut Self {
+        let interval = interval.max(Duration::from_millis(1));
+        let stats = TaskStats { interval_ms: interval.as_millis() as u64, ..TaskStats::default() };
+        self.stats.lock().unwrap().insert(name, stats);
+        self.tasks.push(ScheduledTask { name, interval, next_due, run });
+        self
+    }
+
+    /// Handle on the task stats, readable while the scheduler runs
+    pub fn stats(&self) -> SchedulerStats {
+        self.stats.clone()
+    }
+
+    /// Run every task that's due, in registration order
+    pub async fn run_due(&mut self) {
+        for task in &mut self.tasks {
+            let started = Instant::now();
+            if task.next_due > started {
+                continue;
+            }
+
+            let result = AssertUnwindSafe((task.run)()).catch_unwind().await;
+            let elapsed = started.elapsed();
+
+            // Next run is one interval after this one started; missed runs are skipped, not bunched
+            task.next_due = (started + task.interval).max(Instant::now());
+
+            let mut stats = self.stats.lock().unwrap();
+            let stats = stats.entry(task.name).or_default();
+            let ms = elapsed.as_secs_f64() * 1000.0;
+            stats.runs += 1;
+            stats.last_ms = ms;
+            stats.max_ms = stats.max_ms.max(ms);
+            stats.total_ms += ms;
+            if elapsed > task.interval {
+                stats.overruns += 1;
+            }
+            if let Err(panic) = result {
+                stats.panics += 1;
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                eprintln!("[Scheduler] Task '{}' panicked (will run again next interval): {}", task.name, message);
+            }
+        }
+    }
+
+    /// When the next task is due
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.tasks.iter().map(|t| t.next_due).min()
+    }
+
+    /// Run tasks forever
+    pub async fn run(mut self) {
+        loop {
+            self.run_due().await;
+            match self.next_wake() {
+                Some(wake) => tokio::time::sleep_until(wake).await,
+                None => std::future::pending::<()>().await,
+            }
+        }
+    }
+}
+
+impl Default for TickScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn counter(count: &Arc<AtomicU32>) -> impl FnMut() -> BoxFuture<'static, ()> + Send + 'static {
+        let count = count.clone();
+        move || {
+            let count = count.clone();
+            async move {
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_independent_intervals() {
+        let fast = Arc::new(AtomicU32::new(0));
+        let slow = Arc::new(AtomicU32::new(0));
+        let first = Arc::new(AtomicU32::new(0));
+
+        let mut scheduler = TickScheduler::new();
+        scheduler
+            .every("fast", Duration::from_millis(10), counter(&fast))
+            .every("slow", Duration::from_secs(60), counter(&slow))
+            .every_from_start("first", Duration::from_secs(60), counter(&first));
+
+        // Only the from-start task is due right away
+        scheduler.run_due().await;
+        assert_eq!((fast.load(Ordering::SeqCst), first.load(Ordering::SeqCst)), (0, 1));
+
+        for _ in 0..3 {
+            tokio::time::sleep_until(scheduler.next_wake().unwrap()).await;
+            scheduler.run_due().await;
+        }
+        assert_eq!(fast.load(Ordering::SeqCst), 3);
+        assert_eq!(slow.load(Ordering::SeqCst), 0);
+        assert_eq!(first.load(Ordering::SeqCst), 1);
+
+        let stats = scheduler.stats();
+        let stats = stats.lock().unwrap();
+        assert_eq!(stats["fast"].runs, 3);
+        assert_eq!(stats["slow"].interval_ms, 60_000);
+    }
+
+    #[tokio::test]
+    async fn test_panic_is_isolated() {
+        let after = Arc::new(AtomicU32::new(0));
+
+        let mut scheduler = TickScheduler::new();
+        scheduler
+            .every_from_start("broken", Duration::from_millis(5), || async { panic!("bad state") }.boxed())
+            .every_from_start("after", Duration::from_millis(5), counter(&after));
+
+        for _ in 0..2 {
+            scheduler.run_due().await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The broken task keeps being scheduled, and doesn't take the next one down
+        assert_eq!(after.load(Ordering::SeqCst), 2);
+        let stats = scheduler.stats();
+        let stats = stats.lock().unwrap();
+        assert_eq!((stats["broken"].runs, stats["broken"].panics), (2, 2));
+        assert_eq!(stats["after"].panics, 0);
+    }
+
+    #[test]
+    fn test_scaled_intervals() {
+        let scaled = TickIntervals::default().scaled(10);
+        assert_eq!(scaled.game_logic, Duration::from_millis(100));
+        assert_eq!(scaled.summary, Duration::from_millis(500));
+        assert_eq!(scaled.reconcile, TickIntervals::default().reconcile);
+        assert_eq!(TickIntervals::default().scaled(0), TickIntervals::default());
+    }
+}
@@ -0,0 +1,456 @@
+// Supervisor - spawned subsystems that log their panics and come back
+//
+// A panic in a bare tokio::spawn only kills that task: the gRPC server or the UDP
+// responder disappears and the process keeps running half-broken. Subsystems are
+// spawned through a Supervisor instead, which catches panics and errors, logs them
+// with the subsystem's name, restarts it according to its RestartPolicy, and keeps
+// a status per subsystem for GET /healthz.
+//
+// Every task a supervisor spawns is tracked (a TaskTracker), so the process can stop
+// them all on its way out: `shutdown` cancels each at its next await point, restarts
+// nothing, and waits for them to finish so their sockets and guards are dropped
+// cleanly. The worker and client binaries do that on SIGTERM or Ctrl-C (see app.rs).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// When a subsystem is restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restart {
+    /// Run once; a panic or error is logged and the subsystem marked failed
+    Never,
+    /// Restart after a panic or error, not after a clean exit
+    OnFailure,
+    /// Restart whenever it exits (servers that should run forever)
+    Always,
+}
+
+/// Restart behaviour and backoff for one subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    pub restart: Restart,
+    /// Delay before the first restart (doubles for each consecutive one)
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay; a run at least this long resets the backoff
+    pub max_backoff: Duration,
+    /// Give up (and report failed) after this many consecutive restarts
+    pub max_restarts: Option<u32>,
+}
+
+impl RestartPolicy {
+    pub fn always() -> Self {
+        Self { restart: Restart::Always, ..Self::default() }
+    }
+
+    pub fn on_failure() -> Self {
+        Self { restart: Restart::OnFailure, ..Self::default() }
+    }
+
+    pub fn never() -> Self {
+        Self { restart: Restart::Never, ..Self::default() }
+    }
+
+    /// Delay before the given (1-based) consecutive restart
+    pub fn backoff(&self, restart: u32) -> Duration {
+        let factor = 2u32.saturating_pow(restart.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            restart: Restart::OnFailure,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_restarts: None,
+        }
+    }
+}
+
+/// Where a subsystem stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubsystemState {
+    Running,
+    /// Waiting out the backoff before the next restart
+    Restarting,
+    /// Finished cleanly and isn't restarted
+    Exited,
+    /// Panicked or errored and won't be restarted
+    Failed,
+    /// Cancelled by the process shutting down
+    Stopped,
+}
+
+/// One subsystem, as reported on /healthz
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemStatus {
+    pub name: String,
+    pub state: SubsystemState,
+    /// Instances running now (transient subsystems can have many)
+    pub running: u32,
+    pub restarts: u32,
+    pub panics: u32,
+    pub last_error: Option<String>,
+    /// Unix seconds of the last state change
+    pub since: u64,
+}
+
+impl SubsystemStatus {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: SubsystemState::Running,
+            running: 0,
+            restarts: 0,
+            panics: 0,
+            last_error: None,
+            since: now_secs(),
+        }
+    }
+
+    /// Failed, or stuck restarting, counts as unhealthy
+    pub fn healthy(&self) -> bool {
+        matches!(self.state, SubsystemState::Running | SubsystemState::Exited | SubsystemState::Stopped)
+    }
+}
+
+/// Spawns and restarts subsystems, and tracks their status
+#[derive(Clone, Default)]
+pub struct Supervisor {
+    subsystems: Arc<RwLock<BTreeMap<String, SubsystemStatus>>>,
+    /// Every task spawned here, waited for by `shutdown`
+    tasks: TaskTracker,
+    /// Cancelled when shutdown starts
+    stopping: CancellationToken,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide supervisor (what /healthz reports)
+    pub fn global() -> &'static Supervisor {
+        static GLOBAL: OnceLock<Supervisor> = OnceLock::new();
+        GLOBAL.get_or_init(Supervisor::new)
+    }
+
+    /// Run a long-lived subsystem, restarted per `policy`
+    /// `start` builds a fresh run each time. Aborting the returned handle stops it for good.
+    pub fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, mut start: F) -> JoinHandle<()>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let name = name.to_string();
+        supervisor.update(&name, |s| s.state = SubsystemState::Running);
+
+        self.tasks.spawn(async move {
+            let mut consecutive = 0u32;
+            loop {
+                let started = Instant::now();
+                supervisor.update(&name, |s| {
+                    s.state = SubsystemState::Running;
+                    s.running = 1;
+                });
+
+                let failure = tokio::select! {
+                    failure = supervisor.run_once(&name, start()) => failure,
+                    _ = supervisor.stopping.cancelled() => return supervisor.stopped(&name),
+                };
+                supervisor.update(&name, |s| s.running = 0);
+
+                let restart = match policy.restart {
+                    Restart::Always => true,
+                    Restart::OnFailure => failure.is_some(),
+                    Restart::Never => false,
+                };
+                if started.elapsed() >= policy.max_backoff {
+                    consecutive = 0;
+                }
+                let gave_up = policy.max_restarts.is_some_and(|max| consecutive >= max);
+
+                if !restart || gave_up {
+                    if gave_up {
+                        eprintln!("[Supervisor] {} gave up after {} consecutive restarts", name, consecutive);
+                    }
+                    let state = if failure.is_some() { SubsystemState::Failed } else { SubsystemState::Exited };
+                    supervisor.update(&name, |s| s.state = state);
+                    return;
+                }
+
+                consecutive += 1;
+                let delay = policy.backoff(consecutive);
+                println!("[Supervisor] Restarting {} in {:?} (restart {})", name, delay, consecutive);
+                supervisor.update(&name, |s| {
+                    s.state = SubsystemState::Restarting;
+                    s.restarts += 1;
+                });
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = supervisor.stopping.cancelled() => return supervisor.stopped(&name),
+                }
+            }
+        })
+    }
+
+    /// Run one of many short-lived tasks grouped under `name` (e.g. UDP flooders)
+    /// Never restarted; panics and errors are logged and counted under the group.
+    pub fn spawn_transient<Fut>(&self, name: &str, task: Fut) -> JoinHandle<()>
+    where
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let name = name.to_string();
+        supervisor.update(&name, |s| {
+            s.state = SubsystemState::Running;
+            s.running += 1;
+        });
+
+        self.tasks.spawn(async move {
+            // Decremented even if this wrapper is aborted
+            let _running = RunningGuard { supervisor: supervisor.clone(), name: name.clone() };
+            tokio::select! {
+                _ = supervisor.run_once(&name, task) => {}
+                _ = supervisor.stopping.cancelled() => {}
+            }
+        })
+    }
+
+    /// Run a task whose caller awaits its result (e.g. one final kill connection),
+    /// counted under `name` like a transient task
+    /// A panic isn't caught here: it reaches whoever awaits the handle. The result is
+    /// None if shutdown cancelled the task first.
+    pub fn spawn_scoped<T, Fut>(&self, name: &str, task: Fut) -> JoinHandle<Option<T>>
+    where
+        T: Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let name = name.to_string();
+        supervisor.update(&name, |s| {
+            s.state = SubsystemState::Running;
+            s.running += 1;
+        });
+
+        self.tasks.spawn(async move {
+            let _running = RunningGuard { supervisor: supervisor.clone(), name };
+            tokio::select! {
+                result = task => Some(result),
+                _ = supervisor.stopping.cancelled() => None,
+            }
+        })
+    }
+
+    /// Cancel every task spawned here and wait up to `grace` for them to finish
+    /// Subsystems aren't restarted after this. Returns the names of any still
+    /// running when `grace` ran out.
+    pub async fn shutdown(&self, grace: Duration) -> Vec<String> {
+        self.stopping.cancel();
+        self.tasks.close();
+        if tokio::time::timeout(grace, self.tasks.wait()).await.is_ok() {
+            return Vec::new();
+        }
+        self.status().into_iter().filter(|s| s.running > 0).map(|s| s.name).collect()
+    }
+
+    /// Whether shutdown has started
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.is_cancelled()
+    }
+
+    /// Tasks spawned here that haven't finished (a subsystem counts twice: its
+    /// supervising loop and its current run)
+    pub fn tasks(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Record `name` as cancelled by shutdown
+    fn stopped(&self, name: &str) {
+        self.update(name, |s| {
+            s.state = SubsystemState::Stopped;
+            s.running = 0;
+        });
+    }
+
+    /// Run `task` in its own tokio task and return why it failed, if it did
+    async fn run_once<Fut>(&self, name: &str, task: Fut) -> Option<String>
+    where
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let handle = self.tasks.spawn(task);
+        // Aborting the supervising task must take the subsystem down with it
+        let _abort = AbortOnDrop(handle.abort_handle());
+
+        let failure = match handle.await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => {
+                eprintln!("[Supervisor] {} failed: {:#}", name, e);
+                Some(format!("{:#}", e))
+            }
+            Err(e) => {
+                let message = panic_message(e);
+                eprintln!("[Supervisor] {} panicked: {}", name, message);
+                self.update(name, |s| s.panics += 1);
+                Some(format!("panicked: {}", message))
+            }
+        };
+        if let Some(error) = &failure {
+            self.update(name, |s| s.last_error = Some(error.clone()));
+        }
+        failure
+    }
+
+    fn update(&self, name: &str, change: impl FnOnce(&mut SubsystemStatus)) {
+        let mut subsystems = self.subsystems.write().unwrap();
+        let status = subsystems.entry(name.to_string()).or_insert_with(|| SubsystemStatus::new(name));
+        let before = status.state;
+        change(status);
+        if status.state != before {
+            status.since = now_secs();
+        }
+    }
+
+    /// Every subsystem's status, by name
+    pub fn status(&self) -> Vec<SubsystemStatus> {
+        self.subsystems.read().unwrap().values().cloned().collect()
+    }
+
+    /// Whether every subsystem is running or exited cleanly
+    pub fn healthy(&self) -> bool {
+        self.subsystems.read().unwrap().values().all(SubsystemStatus::healthy)
+    }
+}
+
+/// Aborts a task when dropped, tying it to the scope that holds this
+pub struct AbortOnDrop(pub AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+struct RunningGuard {
+    supervisor: Supervisor,
+    name: String,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.supervisor.update(&self.name, |s| s.running = s.running.saturating_sub(1));
+    }
+}
+
+fn panic_message(error: JoinError) -> String {
+    if !error.is_panic() {
+        return "cancelled".to_string();
+    }
+    let panic = error.into_panic();
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast(restart: Restart) -> RestartPolicy {
+        RestartPolicy {
+            restart,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            max_restarts: None,
+        }
+    }
+
+    fn state_of(supervisor: &Supervisor, name: &str) -> SubsystemStatus {
+        supervisor.status().into_iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_panicking_subsystem_restarts() {
+        let supervisor = Supervisor::new();
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let r = runs.clone();
+        let handle = supervisor.spawn("server", fast(Restart::OnFailure), move || {
+            let r = r.clone();
+            async move {
+                // Panics twice, then serves until it's told to stop
+                if r.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("bind failed");
+                }
+                Ok(())
+            }
+        });
+        handle.await.unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let status = state_of(&supervisor, "server");
+        assert_eq!((status.state, status.restarts, status.panics), (SubsystemState::Exited, 2, 2));
+        assert_eq!(status.last_error.as_deref(), Some("panicked: bind failed"));
+        assert!(supervisor.healthy());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_and_reports_unhealthy() {
+        let supervisor = Supervisor::new();
+        let policy = RestartPolicy { max_restarts: Some(2), ..fast(Restart::Always) };
+        supervisor.spawn("grpc", policy, || async { anyhow::bail!("address in use") }).await.unwrap();
+
+        let status = state_of(&supervisor, "grpc");
+        assert_eq!((status.state, status.restarts), (SubsystemState::Failed, 2));
+        assert!(!supervisor.healthy());
+    }
+
+    #[tokio::test]
+    async fn test_transient_tasks_counted_by_group() {
+        let supervisor = Supervisor::new();
+        let long = supervisor.spawn_transient("flooder", std::future::pending());
+        supervisor.spawn_transient("flooder", async { panic!("send on closed socket") }).await.unwrap();
+
+        let status = state_of(&supervisor, "flooder");
+        assert_eq!((status.running, status.panics), (1, 1));
+        // One flooder dying doesn't make the process unhealthy
+        assert!(supervisor.healthy());
+
+        long.abort();
+        let _ = long.await;
+        assert_eq!(state_of(&supervisor, "flooder").running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_every_task() {
+        let supervisor = Supervisor::new();
+        supervisor.spawn("scheduler", fast(Restart::Always), std::future::pending);
+        supervisor.spawn_transient("flooder", std::future::pending());
+        let scoped = supervisor.spawn_scoped("submission", std::future::pending::<()>());
+        assert_eq!(supervisor.tasks(), 3);
+
+        assert!(supervisor.shutdown(Duration::from_secs(1)).await.is_empty());
+        assert_eq!(supervisor.tasks(), 0);
+        assert_eq!(scoped.await.unwrap(), None);
+        assert_eq!(state_of(&supervisor, "scheduler").state, SubsystemState::Stopped);
+        assert_eq!(state_of(&supervisor, "flooder").running, 0);
+        assert!(supervisor.healthy());
+    }
+}
@@ -0,0 +1,138 @@
+// Optional TLS for the HTTP/WebSocket APIs (worker and client)
+//
+// With TLS_CERT_PATH and TLS_KEY_PATH set (PEM files), `serve` terminates TLS in
+// the axum server itself, so the API is https:// and its WebSocket endpoints wss://
+// with no proxy in front. Raft gRPC and the UDP flood traffic are unaffected.
+//
+// The few calls one process makes to another's API (forwarding an event to the
+// leader, the final-kill WebSocket) follow PEER_TLS, which is off by default. Peers
+// are addressed by bare IP and their certificates are checked against the public web
+// roots, which don't issue for private IPs - so peer links stay plaintext unless
+// PEER_TLS=1, and the workers' internal listener (peers only) is served plaintext to
+// match. Turn it on only with certificates valid for the addresses peers connect to.
+
+use anyhow::{Context, Result};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// Certificate and key for the API listener
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Load from the environment - None (plaintext) unless both are set
+    /// TLS_CERT_PATH: PEM certificate chain, TLS_KEY_PATH: PEM private key
+    pub fn from_env() -> Option<Self> {
+        let cert = std::env::var("TLS_CERT_PATH").ok().filter(|s| !s.is_empty());
+        let key = std::env::var("TLS_KEY_PATH").ok().filter(|s| !s.is_empty());
+        match (cert, key) {
+            (Some(cert), Some(key)) => Some(Self { cert_path: cert.into(), key_path: key.into() }),
+            (None, None) => None,
+            _ => {
+                eprintln!("[TLS] TLS_CERT_PATH and TLS_KEY_PATH must both be set, serving plaintext");
+                None
+            }
+        }
+    }
+}
+
+/// Serve `app` on `addr`, over TLS if TLS_CERT_PATH/TLS_KEY_PATH are set
+/// Handlers can extract the peer's `ConnectInfo<SocketAddr>` (used by rate limiting).
+pub async fn serve(addr: &str, app: Router) -> Result<()> {
+    serve_with(addr, app, TlsConfig::from_env()).await
+}
+
+/// Serve a listener only peers call: over TLS only if they'd use it (PEER_TLS)
+pub async fn serve_peers(addr: &str, app: Router) -> Result<()> {
+    serve_with(addr, app, TlsConfig::from_env().filter(|_| peer_tls())).await
+}
+
+async fn serve_with(addr: &str, app: Router, tls: Option<TlsConfig>) -> Result<()> {
+    match tls {
+        Some(tls) => {
+            // Whichever of us gets here first picks the crypto provider
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            let socket_addr: SocketAddr = addr.parse().with_context(|| format!("Invalid address {}", addr))?;
+            let config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+                .with_context(|| format!("Failed to load TLS cert {:?} / key {:?}", tls.cert_path, tls.key_path))?;
+            println!("HTTPS API server listening on {} (TLS)", addr);
+            axum_server::bind_rustls(socket_addr, config).serve(app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            println!("HTTP API server listening on {}", addr);
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether calls to other processes' APIs use https:// and wss://
+/// PEER_TLS (1/true or 0/false), default: off
+pub fn peer_tls() -> bool {
+    parse_peer_tls(std::env::var("PEER_TLS").ok().as_deref())
+}
+
+fn parse_peer_tls(value: Option<&str>) -> bool {
+    matches!(value, Some("1") | Some("true"))
+}
+
+/// URL of `path` on a peer's API at `authority` (host:port)
+pub fn peer_http_url(authority: &str, path: &str) -> String {
+    format!("{}://{}{}", if peer_tls() { "https" } else { "http" }, authority, path)
+}
+
+/// WebSocket URL of `path` on a peer's API at `authority` (host:port)
+pub fn peer_ws_url(authority: &str, path: &str) -> String {
+    format!("{}://{}{}", if peer_tls() { "wss" } else { "ws" }, authority, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_tls_is_opt_in() {
+        assert!(!parse_peer_tls(None));
+        assert!(parse_peer_tls(Some("1")));
+        assert!(parse_peer_tls(Some("true")));
+        assert!(!parse_peer_tls(Some("0")));
+        assert!(!parse_peer_tls(Some("yes")));
+    }
+
+    #[tokio::test]
+    async fn test_serves_plaintext_without_tls() {
+        let app = Router::new().route("/healthz", axum::routing::get(|| async { "ok" }));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        tokio::spawn({
+            let addr = addr.clone();
+            async move { serve_with(&addr, app, None).await }
+        });
+
+        let url = format!("http://{}/healthz", addr);
+        for _ in 0..50 {
+            if let Ok(response) = reqwest::get(&url).await {
+                assert_eq!(response.text().await.unwrap(), "ok");
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("listener at {} never answered", addr);
+    }
+
+    #[tokio::test]
+    async fn test_missing_cert_fails_to_serve() {
+        let tls = TlsConfig { cert_path: "/nonexistent/cert.pem".into(), key_path: "/nonexistent/key.pem".into() };
+        let app = Router::new();
+        let err = serve_with("127.0.0.1:0", app, Some(tls)).await.unwrap_err();
+        assert!(err.to_string().contains("Failed to load TLS cert"));
+    }
+}
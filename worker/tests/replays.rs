@@ -0,0 +1,113 @@
+// Replay corpus - recorded games whose outcome a rules change must not move
+//
+// Every `tests/replays/<game>.json` is a GET /events body saved from a game's leader
+// (events and their stamps). Each is replayed through GameState the way a node
+// applies its log, and the result is compared with `<game>.fingerprint.json`: the
+// state digest plus the outcome in readable form (who is alive, who holds what).
+//
+// A change that moves a fingerprint changes how a past game would have ended. If
+// that's intended, rewrite the fingerprints and commit them with the change:
+//
+//   UPDATE_REPLAYS=1 cargo test --test replays
+//
+// To add a game, save its leader's GET /events (all of it, `limit` as high as it
+// goes) next to the others and run the same command.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use worker::game::replay::load_replay_log;
+use worker::game::GameState;
+
+/// How a replayed game ended up
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Fingerprint {
+    events: usize,
+    /// GameState::digest, which covers every committed field
+    digest: String,
+    phase: String,
+    game_over: bool,
+    winner: Option<u64>,
+    players: BTreeMap<u64, PlayerOutcome>,
+    neutral_nodes: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PlayerOutcome {
+    name: String,
+    alive: bool,
+    idle: bool,
+    nodes: usize,
+}
+
+impl Fingerprint {
+    fn of(events: usize, state: &GameState) -> Self {
+        let nodes_of = |owner_id: u64| state.nodes.values().filter(|n| n.owner_id == owner_id).count();
+        Self {
+            events,
+            digest: format!("{:016x}", state.digest()),
+            phase: format!("{:?}", state.phase),
+            game_over: state.game_over,
+            winner: state.winner(),
+            players: state
+                .players
+                .values()
+                .map(|p| {
+                    let outcome = PlayerOutcome { name: p.name.clone(), alive: p.alive, idle: p.idle_since.is_some(), nodes: nodes_of(p.player_id) };
+                    (p.player_id, outcome)
+                })
+                .collect(),
+            neutral_nodes: nodes_of(0),
+        }
+    }
+}
+
+fn corpus() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+    let mut games: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json") && !path.to_string_lossy().ends_with(".fingerprint.json"))
+        .collect();
+    games.sort();
+    games
+}
+
+#[test]
+fn test_replays_match_their_fingerprints() {
+    let update = std::env::var_os("UPDATE_REPLAYS").is_some();
+    let games = corpus();
+    assert!(!games.is_empty(), "No games in tests/replays");
+
+    let mut failures = Vec::new();
+    for game in &games {
+        let log = load_replay_log(game).unwrap();
+        let actual = Fingerprint::of(log.events.len(), &log.state());
+        let expected_path = game.with_extension("fingerprint.json");
+        if update {
+            std::fs::write(&expected_path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+            continue;
+        }
+
+        let name = game.file_name().unwrap().to_string_lossy();
+        let Ok(text) = std::fs::read_to_string(&expected_path) else {
+            failures.push(format!("{}: no fingerprint yet", name));
+            continue;
+        };
+        let expected: Fingerprint = serde_json::from_str(&text).unwrap();
+        if actual != expected {
+            failures.push(format!(
+                "{} ended differently\nexpected: {}\nactual: {}",
+                name,
+                serde_json::to_string_pretty(&expected).unwrap(),
+                serde_json::to_string_pretty(&actual).unwrap()
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{}\n\nIf the rules change was meant to change these outcomes: UPDATE_REPLAYS=1 cargo test --test replays",
+        failures.join("\n\n")
+    );
+}